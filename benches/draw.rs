@@ -0,0 +1,117 @@
+//! Benchmarks for the hot paths involved in drawing a presentation.
+//!
+//! Run them via `cargo bench`. Results are stored by criterion so running them again reports how
+//! much faster or slower every benchmark got. A named baseline can be used to compare branches:
+//!
+//! ```shell
+//! cargo bench --bench draw -- --save-baseline before
+//! cargo bench --bench draw -- --baseline before
+//! ```
+
+use comrak::Arena;
+use criterion::{criterion_group, criterion_main, Criterion};
+use presenterm::{
+    CellSizePolicy, ImagePrinter, ImageRegistry, MarkdownParser, PresentationBuilderOptions, PresentationThemeSet,
+    RenderDimensions, Resources, SlidePrinter, Themes, TypstRender,
+};
+use std::{fs, hint::black_box, io, path::Path, rc::Rc};
+use tempfile::TempDir;
+
+const PARAGRAPH: &str = "Presentations are usually written long before they're given, and the person giving them \
+    rarely has time to double check every slide right before going on stage. This means anything that can make the \
+    content render differently than it did when it was written, like a terminal with a different size or a font \
+    that's missing a few glyphs, should be caught as early as possible. Keeping slides short and focused helps with \
+    this, but long paragraphs like this one still show up every now and then, and they need to be wrapped so every \
+    line fits in the screen without splitting words in half.";
+
+const CODE: &str = r#"fn load_config(path: &Path) -> Result<Config, ConfigError> {
+    let contents = fs::read_to_string(path).map_err(|e| ConfigError::Read(path.into(), e))?;
+    let config: Config = serde_yaml::from_str(&contents)?;
+    if config.options.max_width.is_some_and(|width| width < 10) {
+        return Err(ConfigError::Invalid("max_width must be at least 10"));
+    }
+    // Relative paths are resolved relative to the config file's directory.
+    let base = path.parent().unwrap_or(Path::new("."));
+    Ok(Config { themes_path: base.join(&config.themes_path), ..config })
+}
+"#;
+
+fn build_slide(index: usize) -> String {
+    let mut slide = format!(
+        "Slide {index}: keeping things in sync\n===\n\n{PARAGRAPH}\n\n\
+         * The first point, with **bold** and _italics_ in it\n\
+         * A second point that references `some_function`\n    * A nested point\n\
+         * A [link](https://example.com) to the docs\n\n"
+    );
+    if index % 3 == 0 {
+        slide.push_str(&format!("```rust\n{CODE}```\n\n"));
+    }
+    if index % 5 == 0 {
+        slide.push_str("| Name | Value |\n|------|-------|\n| timeout | 30s |\n| retries | 3 |\n\n");
+    }
+    slide
+}
+
+fn build_presentation(slides: usize) -> String {
+    let slides: Vec<_> = (1..=slides).map(build_slide).collect();
+    format!("---\ntitle: A realistic presentation\nauthor: Someone\n---\n\n{}", slides.join("<!-- end_slide -->\n\n"))
+}
+
+fn build_code_slide(lines: usize) -> String {
+    let mut code = String::new();
+    for (index, line) in CODE.lines().cycle().take(lines).enumerate() {
+        // Every copy of the function gets its own name so lines aren't all the same.
+        code.push_str(&line.replace("load_config", &format!("load_config_{}", index / 10)));
+        code.push('\n');
+    }
+    format!("Loading configs\n===\n\n```rust\n{code}```\n")
+}
+
+// Print the first slide in a presentation, which goes through building and drawing it.
+fn print_slide(path: &Path, columns: u16, rows: u16) {
+    let arena = Arena::new();
+    let parser = MarkdownParser::new(&arena);
+    let theme = PresentationThemeSet::default().load_by_name("dark").expect("no dark theme");
+    let printer = Rc::new(ImagePrinter::default());
+    let registry = ImageRegistry(printer.clone());
+    let resources = Resources::new(".", registry.clone());
+    let typst = TypstRender::new(300, registry, Path::new("."));
+    let options = PresentationBuilderOptions::default();
+    let mut printer = SlidePrinter::new(
+        parser,
+        &theme,
+        resources,
+        typst,
+        Themes::default(),
+        printer,
+        CellSizePolicy::from_font_size(16),
+        options,
+    );
+    let dimensions = RenderDimensions::Fixed { columns, rows };
+    printer.print(path, 1, dimensions, None, io::sink()).expect("printing failed");
+}
+
+fn benchmarks(c: &mut Criterion) {
+    let directory = TempDir::new().expect("creating directory failed");
+    let write = |name: &str, contents: String| {
+        let path = directory.path().join(name);
+        fs::write(&path, contents).expect("writing presentation failed");
+        path
+    };
+    let presentation = build_presentation(50);
+    let slide = write("slide.md", build_slide(3));
+    let code_slide = write("code.md", build_code_slide(200));
+    let paragraph = write("paragraph.md", PARAGRAPH.to_string());
+
+    c.bench_function("parse_50_slides", |b| {
+        let arena = Arena::new();
+        let parser = MarkdownParser::new(&arena);
+        b.iter(|| parser.validate(black_box(&presentation)).expect("invalid presentation"))
+    });
+    c.bench_function("print_slide", |b| b.iter(|| print_slide(black_box(&slide), 100, 40)));
+    c.bench_function("print_highlighted_code", |b| b.iter(|| print_slide(black_box(&code_slide), 100, 220)));
+    c.bench_function("print_paragraph_80_columns", |b| b.iter(|| print_slide(black_box(&paragraph), 80, 40)));
+}
+
+criterion_group!(benches, benchmarks);
+criterion_main!(benches);
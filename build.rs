@@ -2,6 +2,7 @@ use std::{
     env,
     fs::{self, File},
     io::{self, BufWriter, Write},
+    path::Path,
 };
 
 // Take all files under `themes` and turn them into a file that contains a hashmap with their
@@ -31,5 +32,41 @@ fn main() -> io::Result<()> {
 
     // Rebuild if anything changes.
     println!("cargo:rerun-if-changed=themes");
+
+    embed_reveal_js(&out_dir)?;
+    Ok(())
+}
+
+// Take all files in the vendored reveal.js distribution and turn them into a file that contains a list of their paths
+// and contents. This is pulled in reveal.rs, which writes them next to exported presentations.
+fn embed_reveal_js(out_dir: &str) -> io::Result<()> {
+    let output_path = format!("{out_dir}/reveal_js.rs");
+    let mut output_file = BufWriter::new(File::create(output_path)?);
+    output_file.write_all(b"static REVEAL_JS_ASSETS: &[(&str, &[u8])] = &[\n")?;
+    let root = Path::new("reveal.js/dist");
+    let mut pending = vec![root.to_path_buf()];
+    let mut paths = Vec::new();
+    while let Some(directory) = pending.pop() {
+        if !directory.is_dir() {
+            println!("cargo:warning=reveal.js is missing, run reveal.js/update.sh to vendor it");
+            break;
+        }
+        for entry in fs::read_dir(&directory)? {
+            let path = entry?.path();
+            match path.is_dir() {
+                true => pending.push(path),
+                false => paths.push(path),
+            };
+        }
+    }
+    paths.sort();
+    for path in paths {
+        let name = path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+        let absolute_path = fs::canonicalize(&path)?;
+        output_file.write_all(format!("({name:?}, include_bytes!({absolute_path:?})),\n").as_bytes())?;
+    }
+    output_file.write_all(b"];\n")?;
+
+    println!("cargo:rerun-if-changed=reveal.js");
     Ok(())
 }
@@ -0,0 +1,83 @@
+//! Presents a markdown file and renders `metrics` code blocks using a custom hook.
+//!
+//! Run it via:
+//!
+//! ```shell
+//! cargo run --example render_hook -- examples/metrics.md
+//! ```
+
+use comrak::Arena;
+use presenterm::{
+    CellSizePolicy, CommandSource, Config, GraphicsMode, HookContext, HookError, HookOutput, ImagePrinter,
+    ImageRegistry, MarkdownParser, PresentMode, PresentationBuilderOptions, PresentationThemeSet, Presenter,
+    PresenterOptions, RenderHooks, Resources, StyledText, Themes, TypstRender,
+};
+use std::{env, path::PathBuf, rc::Rc};
+
+/// Renders every `name: value` line in the block as a horizontal bar.
+fn render_metrics(source: &str, context: &HookContext) -> Result<HookOutput, HookError> {
+    let mut metrics = Vec::new();
+    for line in source.lines().filter(|line| !line.trim().is_empty()) {
+        let (name, value) = line.split_once(':').ok_or_else(|| format!("invalid metric: '{line}'"))?;
+        let value: f64 = value.trim().parse()?;
+        metrics.push((name.trim(), value));
+    }
+    let name_width = metrics.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let max_value = metrics.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+    // Leave some room for the name and the value itself.
+    let bar_width = (context.columns as usize).saturating_sub(name_width + 12) as f64;
+    let mut lines = Vec::new();
+    for (name, value) in metrics {
+        let length = if max_value > 0.0 { (value / max_value * bar_width) as usize } else { 0 };
+        lines.push(vec![
+            StyledText::new(format!("{name:>name_width$} ")).bold(),
+            StyledText::new("█".repeat(length)).foreground(0x7d, 0xcf, 0xff),
+            StyledText::new(format!(" {value}")),
+        ]);
+    }
+    Ok(HookOutput::Lines(lines))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path: PathBuf = env::args().nth(1).ok_or("usage: render_hook <path>")?.into();
+    let config = Config::default();
+
+    let mut render_hooks = RenderHooks::default();
+    render_hooks.register_code_block("metrics", render_metrics);
+
+    let themes = Themes::default();
+    let theme_set = PresentationThemeSet::default();
+    let theme = theme_set.load_by_name("dark").expect("no dark theme");
+    let printer = Rc::new(ImagePrinter::new(GraphicsMode::AsciiBlocks)?);
+    let registry = ImageRegistry(printer.clone());
+    let resources_path = path.parent().unwrap_or(&path).to_path_buf();
+    let resources = Resources::new(&resources_path, registry.clone());
+    let typst = TypstRender::new(config.typst.ppi, registry, &resources_path);
+    let commands = CommandSource::new(&path, config.bindings.clone())?;
+    let arena = Arena::new();
+    let parser = MarkdownParser::new(&arena);
+    let options = PresenterOptions {
+        mode: PresentMode::Development,
+        builder_options: PresentationBuilderOptions { render_hooks, ..Default::default() },
+        cell_size: CellSizePolicy::from_font_size(config.defaults.terminal_font_size),
+        dimensions: Default::default(),
+        bindings: config.bindings,
+        validate_overflows: false,
+        event_emitter: None,
+        safe_area_margin: Default::default(),
+        cache_budget: config.defaults.cache_budget_mb as usize * 1024 * 1024,
+        cache_usage_overlay: config.defaults.cache_usage_overlay,
+        end_of_deck: config.defaults.end_of_deck,
+        preview_dimensions: config.defaults.preview_dimensions,
+        kiosk: false,
+        session: None,
+        resume: None,
+        suggested_resume: None,
+        fps: 60,
+        slow_frames: config.defaults.slow_frames,
+        pause_on_focus_loss: config.defaults.pause_on_focus_loss,
+    };
+    let presenter = Presenter::new(&theme, commands, parser, resources, typst, themes, printer, options);
+    presenter.present(&path)?;
+    Ok(())
+}
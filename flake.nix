@@ -27,6 +27,7 @@
           "src"
           "themes"
           "bat"
+          "reveal.js"
         ];
 
         buildSrc = flakeboxLib.filterSubPaths {
@@ -0,0 +1,23 @@
+#![no_main]
+
+use comrak::Arena;
+use libfuzzer_sys::fuzz_target;
+use presenterm::MarkdownParser;
+
+// The largest input we care about. Presentations are rarely bigger than this and larger inputs
+// mostly make the fuzzer slower.
+const MAX_INPUT_SIZE: usize = 65 * 1024;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() > MAX_INPUT_SIZE {
+        return;
+    }
+    // The presentation is read into a string so invalid utf8 never makes it to the parser.
+    let Ok(contents) = std::str::from_utf8(data) else {
+        return;
+    };
+    let arena = Arena::new();
+    let parser = MarkdownParser::new(&arena);
+    // Errors are fine, we only care about panics and hangs.
+    let _ = parser.validate(contents);
+});
@@ -0,0 +1,26 @@
+#!/usr/bin/env bash
+
+set -e
+
+if [ $# -ne 1 ]; then
+    echo "Usage: $0 <reveal.js-version>"
+    exit 1
+fi
+
+script_path=$(realpath "$0")
+script_dir=$(dirname "$script_path")
+version=$1
+download_path=$(mktemp -d)
+
+echo "Downloading reveal.js ${version} into '$download_path'"
+curl -sSfL "https://registry.npmjs.org/reveal.js/-/reveal.js-${version}.tgz" | tar -xz -C "$download_path"
+
+# Only what exported presentations load is kept.
+rm -rf "$script_dir/dist"
+mkdir -p "$script_dir/dist/theme"
+cp "$download_path/package/dist/reveal.css" "$download_path/package/dist/reveal.js" "$script_dir/dist"
+cp -r "$download_path/package/dist/theme/black.css" "$download_path/package/dist/theme/fonts" "$script_dir/dist/theme"
+cp "$download_path/package/LICENSE" "$script_dir/LICENSE"
+echo "$version" >"$script_dir/reveal.js.version"
+
+echo "reveal.js updated"
@@ -0,0 +1,32 @@
+#!/usr/bin/env bash
+
+set -e
+
+script_path=$(realpath "$0")
+script_dir=$(dirname "$script_path")
+download_path=$(mktemp -d)
+
+version=$(cat "$script_dir/reveal.js.version")
+echo "Downloading reveal.js ${version} into '$download_path'"
+curl -sSfL "https://registry.npmjs.org/reveal.js/-/reveal.js-${version}.tgz" | tar -xz -C "$download_path"
+
+for file in reveal.css reveal.js theme/black.css; do
+    if [ ! -f "$script_dir/dist/$file" ]; then
+        echo "Missing ${file}, run update.sh to vendor reveal.js"
+        exit 1
+    fi
+done
+if ! diff -r "$script_dir/dist/theme/fonts" "$download_path/package/dist/theme/fonts"; then
+    echo "Unexpected contents in theme/fonts"
+    exit 1
+fi
+for file in reveal.css reveal.js theme/black.css; do
+    our_hash=$(sha256sum "$script_dir/dist/$file" | cut -d " " -f1)
+    their_hash=$(sha256sum "$download_path/package/dist/$file" | cut -d " " -f 1)
+    if [ "$our_hash" != "$their_hash" ]; then
+        echo "Unexpected hash for ${file}: should be ${their_hash}, is ${our_hash}"
+        exit 1
+    fi
+done
+
+echo "All hashes match"
@@ -0,0 +1,14 @@
+#!/usr/bin/env bash
+
+# Generates a lockfile that resolves dependencies to versions that build with the toolchain CI uses, as the latest
+# releases of some of them need a newer one.
+
+set -e
+
+script_dir=$(dirname "$0")
+cd "${script_dir}/../"
+
+cargo generate-lockfile
+cargo update -p half --precise 2.4.1
+cargo update -p syntect --precise 5.2.0
+cargo update -p onig --precise 6.4.0
@@ -0,0 +1,258 @@
+//! A cache on disk for the images generated by third party tools, like typst and mermaid.
+//!
+//! Artifacts are named after a hash of everything that goes into generating them, so generating
+//! the same image twice, even across runs, only runs the tool that generates it once. The cache
+//! is kept under a maximum size by removing the artifacts that were used the longest time ago.
+
+use filetime::FileTime;
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use tempfile::NamedTempFile;
+
+/// Everything that goes into generating an artifact.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ArtifactKey<'a> {
+    /// The name of the tool that generates the artifact.
+    pub(crate) tool: &'a str,
+
+    /// The version of the tool that generates the artifact.
+    pub(crate) version: &'a str,
+
+    /// The source the artifact is generated from.
+    pub(crate) source: &'a [u8],
+
+    /// The size the artifact is generated at, in whatever terms the tool uses.
+    pub(crate) target: &'a str,
+}
+
+impl ArtifactKey<'_> {
+    /// The hash that identifies this artifact.
+    pub(crate) fn hash(&self) -> String {
+        let mut hash = Fnv1a::default();
+        for field in [self.tool.as_bytes(), self.version.as_bytes(), self.source, self.target.as_bytes()] {
+            // Prefixing every field with its length makes sure moving bytes between fields changes the hash.
+            hash.write(&(field.len() as u64).to_le_bytes());
+            hash.write(field);
+        }
+        format!("{:032x}", hash.0)
+    }
+}
+
+/// The result of a garbage collection pass over an [ArtifactStore].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArtifactCollection {
+    /// The number of artifacts removed.
+    pub removed: usize,
+
+    /// The number of bytes used by the artifacts that are left.
+    pub used: u64,
+}
+
+/// Stores generated artifacts in a directory.
+#[derive(Clone, Debug)]
+pub struct ArtifactStore {
+    directory: PathBuf,
+    max_size: u64,
+}
+
+impl ArtifactStore {
+    /// Construct a store that keeps at most `max_size` bytes worth of artifacts in a directory.
+    pub fn new(directory: PathBuf, max_size: u64) -> Self {
+        Self { directory, max_size }
+    }
+
+    /// Get an artifact, generating and storing it if it isn't stored already.
+    ///
+    /// Failing to read or write the store is never an error, the artifact is generated instead
+    /// and simply not stored.
+    pub(crate) fn get_or_create<F, E>(&self, key: &ArtifactKey, create: F) -> Result<Vec<u8>, E>
+    where
+        F: FnOnce() -> Result<Vec<u8>, E>,
+    {
+        let path = self.directory.join(key.hash());
+        if let Ok(contents) = fs::read(&path) {
+            // The modification time is what the least recently used artifacts are found by, and
+            // unlike the access time it's always updated.
+            let _ = filetime::set_file_mtime(&path, FileTime::now());
+            return Ok(contents);
+        }
+        let contents = create()?;
+        let _ = self.write(&path, &contents);
+        Ok(contents)
+    }
+
+    /// Remove the least recently used artifacts until the store is within its maximum size.
+    pub fn collect_garbage(&self) -> io::Result<ArtifactCollection> {
+        let mut artifacts = Vec::new();
+        let handle = match fs::read_dir(&self.directory) {
+            Ok(handle) => handle,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Default::default()),
+            Err(e) => return Err(e),
+        };
+        for entry in handle {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                artifacts.push((metadata.modified()?, metadata.len(), entry.path()));
+            }
+        }
+        artifacts.sort();
+
+        let mut used: u64 = artifacts.iter().map(|(_, size, _)| size).sum();
+        let mut removed = 0;
+        for (_, size, path) in artifacts {
+            if used <= self.max_size {
+                break;
+            }
+            fs::remove_file(path)?;
+            used -= size;
+            removed += 1;
+        }
+        Ok(ArtifactCollection { removed, used })
+    }
+
+    /// Remove every stored artifact.
+    pub fn clean(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.directory) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        // Write into a temporary file first so a crash, or another process reading the same
+        // artifact, never sees a half written one.
+        let mut file = NamedTempFile::new_in(&self.directory)?;
+        file.write_all(contents)?;
+        file.persist(path)?;
+        Ok(())
+    }
+}
+
+// The 128 bit version of FNV-1a, which unlike the standard library's hasher is stable across
+// releases so artifacts can be found again after upgrading.
+struct Fnv1a(u128);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0x6c62272e07bb014262b821756295c58d)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u128).wrapping_mul(0x0000000001000000000000000000013b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    const KEY: ArtifactKey = ArtifactKey { tool: "typst", version: "0.11", source: b"= hi", target: "300" };
+
+    #[test]
+    fn hit_across_restarts() {
+        let directory = tempdir().expect("creating tempdir");
+        let store = ArtifactStore::new(directory.path().into(), 1024);
+        let contents = store.get_or_create(&KEY, || Ok::<_, ()>(b"image".to_vec())).expect("create failed");
+        assert_eq!(contents, b"image");
+
+        // A store over the same directory finds it without generating it again.
+        let store = ArtifactStore::new(directory.path().into(), 1024);
+        let contents = store.get_or_create(&KEY, || Err("generated again")).expect("not found");
+        assert_eq!(contents, b"image");
+    }
+
+    #[test]
+    fn stable_hash() {
+        assert_eq!(KEY.hash(), KEY.clone().hash());
+        assert_eq!(KEY.hash().len(), 32);
+    }
+
+    #[rstest]
+    #[case::tool(ArtifactKey { tool: "mmdc", ..KEY })]
+    #[case::version(ArtifactKey { version: "0.12", ..KEY })]
+    #[case::source(ArtifactKey { source: b"= bye", ..KEY })]
+    #[case::target(ArtifactKey { target: "150", ..KEY })]
+    #[case::moved_bytes(ArtifactKey { tool: "typst0", version: ".11", ..KEY })]
+    fn different_keys(#[case] key: ArtifactKey) {
+        assert_ne!(key.hash(), KEY.hash());
+    }
+
+    #[test]
+    fn generation_errors() {
+        let directory = tempdir().expect("creating tempdir");
+        let store = ArtifactStore::new(directory.path().into(), 1024);
+        store.get_or_create(&KEY, || Err("failed")).expect_err("generation succeeded");
+
+        // Nothing is stored when generating fails.
+        let contents = store.get_or_create(&KEY, || Ok::<_, ()>(b"image".to_vec())).expect("create failed");
+        assert_eq!(contents, b"image");
+    }
+
+    #[test]
+    fn unwritable_store() {
+        let directory = tempdir().expect("creating tempdir");
+        let path = directory.path().join("file");
+        fs::write(&path, "").expect("writing file");
+
+        // The store's directory can't be created because it's a file, which is not an error.
+        let store = ArtifactStore::new(path, 1024);
+        let contents = store.get_or_create(&KEY, || Ok::<_, ()>(b"image".to_vec())).expect("create failed");
+        assert_eq!(contents, b"image");
+    }
+
+    fn store_aged(store: &ArtifactStore, source: &'static [u8], age: u64) -> PathBuf {
+        let key = ArtifactKey { source, ..KEY };
+        store.get_or_create(&key, || Ok::<_, ()>(vec![0; 10])).expect("create failed");
+        let path = store.directory.join(key.hash());
+        let modified = SystemTime::now() - Duration::from_secs(age);
+        filetime::set_file_mtime(&path, FileTime::from_system_time(modified)).expect("touch failed");
+        path
+    }
+
+    #[test]
+    fn garbage_collection() {
+        let directory = tempdir().expect("creating tempdir");
+        let store = ArtifactStore::new(directory.path().into(), 25);
+        let oldest = store_aged(&store, b"a", 300);
+        let middle = store_aged(&store, b"b", 200);
+        let newest = store_aged(&store, b"c", 100);
+
+        // Using the oldest artifact makes it the most recently used one.
+        let key = ArtifactKey { source: b"a", ..KEY };
+        store.get_or_create(&key, || Err("generated again")).expect("not found");
+
+        let collection = store.collect_garbage().expect("collection failed");
+        assert_eq!(collection, ArtifactCollection { removed: 1, used: 20 });
+        assert!(oldest.exists());
+        assert!(!middle.exists());
+        assert!(newest.exists());
+
+        // Nothing is removed once the store is within its maximum size.
+        let collection = store.collect_garbage().expect("collection failed");
+        assert_eq!(collection, ArtifactCollection { removed: 0, used: 20 });
+    }
+
+    #[test]
+    fn clean() {
+        let directory = tempdir().expect("creating tempdir");
+        let store = ArtifactStore::new(directory.path().join("artifacts"), 1024);
+        assert_eq!(store.collect_garbage().expect("collection failed"), ArtifactCollection::default());
+        store.clean().expect("cleaning empty store failed");
+
+        store.get_or_create(&KEY, || Ok::<_, ()>(b"image".to_vec())).expect("create failed");
+        store.clean().expect("cleaning failed");
+        store.get_or_create(&KEY, || Err("generated again")).expect_err("artifact still stored");
+    }
+}
@@ -0,0 +1,102 @@
+//! Presentations that ship with presenterm rather than being loaded from a file.
+//!
+//! These are made out of markdown elements directly rather than parsed from markdown so any
+//! mistake in them is caught when compiling rather than when they're displayed.
+
+use crate::{
+    markdown::elements::{
+        Code, CodeAttributes, CodeLanguage, ListItem, ListItemType, MarkdownElement, QuoteLine, Text, TextBlock,
+    },
+    presentation::Presentation,
+    processing::builder::{BuildError, PresentationBuilder, PresentationBuilderOptions, Themes},
+    style::TextStyle,
+    ImageRegistry, PresentationTheme, Resources, TypstRender,
+};
+
+const DEMO_CODE: &str = r#"fn greet(name: &str) -> String {
+    format!("hi {name}")
+}"#;
+
+/// Build the presentation used to preview a theme.
+///
+/// This shows every heading level along with code, lists, and block quotes so how they all look
+/// in the theme can be seen at a glance.
+pub(crate) fn theme_demo(
+    theme_name: &str,
+    theme: &PresentationTheme,
+    themes: &Themes,
+) -> Result<Presentation, BuildError> {
+    let image_registry = ImageRegistry::default();
+    let mut resources = Resources::new("non_existent", image_registry.clone());
+    let mut typst = TypstRender::default();
+    let builder = PresentationBuilder::new(
+        theme,
+        &mut resources,
+        &mut typst,
+        themes,
+        image_registry,
+        Default::default(),
+        PresentationBuilderOptions::default(),
+    );
+    builder.build(theme_demo_elements(theme_name))
+}
+
+fn theme_demo_elements(theme_name: &str) -> Vec<MarkdownElement> {
+    let mut elements = vec![MarkdownElement::SetexHeading {
+        text: format!("theme: {theme_name}").into(),
+        source_position: Default::default(),
+    }];
+    // Headings go on their own column so everything fits in a small terminal.
+    elements.extend([command("column_layout: [1, 2]"), command("column: 0")]);
+    for level in 1..=6 {
+        let text = format!("Header {level}").into();
+        elements.push(MarkdownElement::Heading { level, text, source_position: Default::default() });
+    }
+    elements.extend([
+        command("column: 1"),
+        MarkdownElement::Code(Code {
+            contents: DEMO_CODE.into(),
+            language: CodeLanguage::Rust,
+            language_name: "rust".into(),
+            attributes: CodeAttributes::default(),
+        }),
+        MarkdownElement::List(vec![
+            list_item(0, Text::new("bold text", TextStyle::default().bold())),
+            list_item(0, Text::new("italics", TextStyle::default().italics())),
+            list_item(1, Text::new("some inline code", TextStyle::default().code())),
+            list_item(1, Text::new("strikethrough", TextStyle::default().strikethrough())),
+        ]),
+        MarkdownElement::BlockQuote(vec![
+            QuoteLine { text: "a block quote".into(), depth: 0 },
+            QuoteLine { text: "a nested one".into(), depth: 1 },
+        ]),
+    ]);
+    elements
+}
+
+fn command(comment: &str) -> MarkdownElement {
+    MarkdownElement::Comment { comment: comment.into(), source_position: Default::default() }
+}
+
+fn list_item(depth: u8, text: Text) -> ListItem {
+    ListItem { depth, contents: TextBlock(vec![text]), item_type: ListItemType::Unordered }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{properties::WindowSize, validate::OverflowValidator};
+
+    #[test]
+    fn theme_demos_fit() {
+        let themes = Themes::default();
+        let dimensions = WindowSize { rows: 24, columns: 80, width: 640, height: 480 };
+        for theme_name in themes.presentation.theme_names() {
+            let theme = themes.presentation.load_by_name(&theme_name).expect("theme not found");
+            let presentation = theme_demo(&theme_name, &theme, &themes).expect("building demo failed");
+            assert_eq!(presentation.iter_slides().count(), 1);
+            let result = OverflowValidator::validate(&presentation, dimensions.clone(), Default::default());
+            assert!(result.is_ok(), "theme {theme_name} overflows: {result:?}");
+        }
+    }
+}
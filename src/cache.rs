@@ -0,0 +1,283 @@
+use crate::{
+    media::printer::ResourceProperties,
+    presentation::{Presentation, RenderOperation},
+};
+use std::{collections::HashMap, mem};
+
+/// The kind of content being cached.
+///
+/// These are sorted by how expensive they are to rebuild, cheapest first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum CacheKind {
+    /// Laid out text, which only needs to be laid out again.
+    Layout,
+
+    /// Images, which need to be read, decoded, and in some cases generated again.
+    Image,
+}
+
+/// The memory used by cached content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CacheUsage {
+    pub(crate) kind: CacheKind,
+    pub(crate) bytes: usize,
+}
+
+impl CacheUsage {
+    /// Estimate the memory used by a list of cached render operations.
+    pub(crate) fn of_operations(operations: &[RenderOperation]) -> Self {
+        let mut kind = CacheKind::Layout;
+        let mut bytes = 0;
+        for operation in operations {
+            bytes += mem::size_of::<RenderOperation>();
+            match operation {
+                RenderOperation::RenderText { line, .. } => {
+                    bytes += line.iter_texts().map(|text| text.text().content.len()).sum::<usize>();
+                }
+                RenderOperation::RenderPreformattedLine(line) => bytes += line.text.len(),
+                RenderOperation::RenderImage(image, _) => {
+                    kind = CacheKind::Image;
+                    bytes += image.memory_size();
+                }
+                _ => (),
+            };
+        }
+        Self { kind, bytes }
+    }
+}
+
+/// The result of enforcing a [CacheBudget].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CacheReport {
+    /// The number of bytes cached after evicting.
+    pub(crate) used: usize,
+
+    /// The number of bytes evicted.
+    pub(crate) evicted: usize,
+}
+
+/// Keeps the memory used by the content cached in a presentation within a budget.
+///
+/// Caches that are cheaper to rebuild are evicted first and, among those, the ones in the slides
+/// that were displayed the longest time ago. The current slide and the ones next to it are never
+/// evicted so moving back and forth doesn't need to rebuild anything.
+pub(crate) struct CacheBudget {
+    max_size: usize,
+    last_displayed: HashMap<usize, u64>,
+    ticks: u64,
+}
+
+impl CacheBudget {
+    pub(crate) fn new(max_size: usize) -> Self {
+        Self { max_size, last_displayed: Default::default(), ticks: 0 }
+    }
+
+    /// Forget which slides were recently displayed, e.g. because the presentation was reloaded.
+    pub(crate) fn reset(&mut self) {
+        self.last_displayed.clear();
+    }
+
+    /// Mark the current slide as displayed and evict caches until the budget is respected.
+    pub(crate) fn enforce(&mut self, presentation: &Presentation) -> CacheReport {
+        let current = presentation.current_slide_index();
+        self.ticks += 1;
+        self.last_displayed.insert(current, self.ticks);
+
+        let mut used = 0;
+        let mut candidates = Vec::new();
+        for (index, slide) in presentation.iter_slides().enumerate() {
+            for operation in slide.iter_operations() {
+                let Some(usage) = Self::cache_usage(operation) else {
+                    continue;
+                };
+                used += usage.bytes;
+                if index.abs_diff(current) > 1 {
+                    let last_displayed = self.last_displayed.get(&index).copied().unwrap_or_default();
+                    candidates.push((usage, last_displayed, operation));
+                }
+            }
+        }
+        let mut report = CacheReport { used, evicted: 0 };
+        if used <= self.max_size {
+            return report;
+        }
+        candidates.sort_by_key(|(usage, last_displayed, _)| (usage.kind, *last_displayed));
+        for (usage, _, operation) in candidates {
+            if report.used <= self.max_size {
+                break;
+            }
+            Self::evict(operation);
+            report.used -= usage.bytes;
+            report.evicted += usage.bytes;
+        }
+        report
+    }
+
+    fn cache_usage(operation: &RenderOperation) -> Option<CacheUsage> {
+        match operation {
+            RenderOperation::RenderDynamic(generator) => generator.cache_usage(),
+            RenderOperation::RenderOnDemand(generator) => generator.cache_usage(),
+            _ => None,
+        }
+    }
+
+    fn evict(operation: &RenderOperation) {
+        match operation {
+            RenderOperation::RenderDynamic(generator) => generator.evict_cache(),
+            RenderOperation::RenderOnDemand(generator) => generator.evict_cache(),
+            _ => (),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        presentation::{AsRenderOperations, SlideBuilder, SlideChunk},
+        render::properties::WindowSize,
+    };
+    use std::{cell::Cell, ops::RangeInclusive, rc::Rc};
+
+    const IMAGE_SIZE: usize = 100;
+    const LAYOUT_SIZE: usize = 10;
+
+    #[derive(Debug)]
+    struct FakeCache {
+        kind: CacheKind,
+        bytes: usize,
+        cached: Cell<bool>,
+    }
+
+    impl FakeCache {
+        fn new(kind: CacheKind, bytes: usize) -> Rc<Self> {
+            Rc::new(Self { kind, bytes, cached: Cell::new(true) })
+        }
+    }
+
+    impl AsRenderOperations for FakeCache {
+        fn as_render_operations(&self, _: &WindowSize) -> Vec<RenderOperation> {
+            self.cached.set(true);
+            Vec::new()
+        }
+
+        fn diffable_content(&self) -> Option<&str> {
+            None
+        }
+
+        fn cache_usage(&self) -> Option<CacheUsage> {
+            self.cached.get().then_some(CacheUsage { kind: self.kind, bytes: self.bytes })
+        }
+
+        fn evict_cache(&self) {
+            self.cached.set(false);
+        }
+    }
+
+    struct Deck {
+        presentation: Presentation,
+        images: Vec<Rc<FakeCache>>,
+        layouts: Vec<Rc<FakeCache>>,
+    }
+
+    impl Deck {
+        fn new(slide_count: usize) -> Self {
+            let mut slides = Vec::new();
+            let mut images = Vec::new();
+            let mut layouts = Vec::new();
+            for _ in 0..slide_count {
+                let image = FakeCache::new(CacheKind::Image, IMAGE_SIZE);
+                let layout = FakeCache::new(CacheKind::Layout, LAYOUT_SIZE);
+                let operations =
+                    vec![RenderOperation::RenderDynamic(image.clone()), RenderOperation::RenderDynamic(layout.clone())];
+                slides.push(SlideBuilder::default().chunks(vec![SlideChunk::new(operations, vec![])]).build());
+                images.push(image);
+                layouts.push(layout);
+            }
+            Self { presentation: Presentation::from(slides), images, layouts }
+        }
+
+        fn cached_bytes(&self) -> usize {
+            self.images
+                .iter()
+                .chain(&self.layouts)
+                .filter_map(|cache| cache.cache_usage())
+                .map(|usage| usage.bytes)
+                .sum()
+        }
+
+        // Simulates displaying the current slide, which also loads the adjacent ones ahead of time.
+        fn display_current(&self) {
+            let current = self.presentation.current_slide_index();
+            let dimensions = WindowSize { rows: 10, columns: 10, width: 0, height: 0 };
+            for index in self.adjacent_slides(current) {
+                self.images[index].as_render_operations(&dimensions);
+                self.layouts[index].as_render_operations(&dimensions);
+            }
+        }
+
+        fn adjacent_slides(&self, index: usize) -> RangeInclusive<usize> {
+            index.saturating_sub(1)..=(index + 1).min(self.images.len() - 1)
+        }
+    }
+
+    #[test]
+    fn within_budget() {
+        let deck = Deck::new(3);
+        let mut budget = CacheBudget::new(1000);
+        let report = budget.enforce(&deck.presentation);
+        assert_eq!(report, CacheReport { used: 330, evicted: 0 });
+    }
+
+    #[test]
+    fn budget_is_respected_while_navigating() {
+        let slide_count = 1000;
+        let max_size = 10 * (IMAGE_SIZE + LAYOUT_SIZE);
+        let mut deck = Deck::new(slide_count);
+        let mut budget = CacheBudget::new(max_size);
+        let forwards = 0..slide_count;
+        let backwards = (0..slide_count).rev();
+        for index in forwards.chain(backwards) {
+            deck.presentation.go_to_slide(index);
+            deck.display_current();
+            let report = budget.enforce(&deck.presentation);
+            assert!(report.used <= max_size, "{report:?} at slide {index}");
+            assert_eq!(report.used, deck.cached_bytes());
+
+            // The current slide and the ones next to it are always warm.
+            for adjacent in deck.adjacent_slides(index) {
+                assert!(deck.images[adjacent].cached.get(), "slide {adjacent} evicted at slide {index}");
+                assert!(deck.layouts[adjacent].cached.get(), "slide {adjacent} evicted at slide {index}");
+            }
+        }
+    }
+
+    #[test]
+    fn layouts_evicted_first() {
+        let deck = Deck::new(5);
+        let mut budget = CacheBudget::new(5 * IMAGE_SIZE + 3 * LAYOUT_SIZE);
+        let report = budget.enforce(&deck.presentation);
+        assert_eq!(report.evicted, 2 * LAYOUT_SIZE);
+        assert!(deck.images.iter().all(|image| image.cached.get()));
+        let cached_layouts: Vec<_> = deck.layouts.iter().map(|layout| layout.cached.get()).collect();
+        assert_eq!(cached_layouts, &[true, true, false, false, true]);
+    }
+
+    #[test]
+    fn least_recently_displayed_evicted_first() {
+        let mut deck = Deck::new(6);
+        let mut budget = CacheBudget::new(usize::MAX);
+        for index in [4, 0, 5] {
+            deck.presentation.go_to_slide(index);
+            budget.enforce(&deck.presentation);
+        }
+        for layout in &deck.layouts {
+            layout.evict_cache();
+        }
+        // Slides 4 and 5 are protected and out of the rest, 0 is the only one that was displayed.
+        budget.max_size = 3 * IMAGE_SIZE;
+        budget.enforce(&deck.presentation);
+        let cached_images: Vec<_> = deck.images.iter().map(|image| image.cached.get()).collect();
+        assert_eq!(cached_images, &[true, false, false, false, true, true]);
+    }
+}
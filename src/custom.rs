@@ -6,7 +6,7 @@ use crate::{
 use clap::ValueEnum;
 use schemars::JsonSchema;
 use serde::Deserialize;
-use std::{fs, io, path::Path};
+use std::{fmt, fs, io, path::Path};
 
 #[derive(Clone, Debug, Default, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
@@ -24,6 +24,9 @@ pub struct Config {
 
     #[serde(default)]
     pub bindings: KeyBindingsConfig,
+
+    #[serde(default)]
+    pub execution: ExecutionConfig,
 }
 
 impl Config {
@@ -52,7 +55,7 @@ pub enum ConfigLoadError {
 #[serde(deny_unknown_fields)]
 pub struct DefaultsConfig {
     /// The theme to use by default in every presentation unless overridden.
-    pub theme: Option<String>,
+    pub theme: Option<DefaultTheme>,
 
     /// Override the terminal font size when in windows or when using sixel.
     #[serde(default = "default_font_size")]
@@ -66,6 +69,129 @@ pub struct DefaultsConfig {
     /// Validate that the presentation does not overflow the terminal screen.
     #[serde(default)]
     pub validate_overflows: ValidateOverflows,
+
+    /// A margin to leave empty around the terminal, for screens or projectors that cut off its edges.
+    #[serde(default)]
+    pub safe_area_margin: SafeAreaMargin,
+
+    /// The maximum amount of memory, in megabytes, used to cache content such as images.
+    #[serde(default = "default_cache_budget")]
+    pub cache_budget_mb: u32,
+
+    /// Show how much memory cached content is using in the bottom right corner of the screen.
+    #[serde(default)]
+    pub cache_usage_overlay: bool,
+
+    /// The maximum size, in megabytes, of the images generated by tools like typst and mermaid that are kept on disk
+    /// so they're only generated once. Setting this to 0 disables it.
+    #[serde(default = "default_artifact_cache")]
+    pub artifact_cache_mb: u32,
+
+    /// What to do when moving forward on the last slide.
+    #[serde(default)]
+    pub end_of_deck: EndOfDeck,
+
+    /// The message displayed in the end screen.
+    #[serde(default = "default_end_screen_message")]
+    pub end_screen_message: String,
+
+    /// The terminal sizes the current slide can be previewed at.
+    #[serde(default)]
+    pub preview_dimensions: Vec<PreviewDimensions>,
+
+    /// The size of a terminal cell in pixels, as `[width, height]`.
+    ///
+    /// This overrides whatever the terminal reports, which is useful if images look stretched.
+    #[serde(default)]
+    pub cell_pixel_size: Option<[u16; 2]>,
+
+    /// Report frames that take too long to render.
+    #[serde(default)]
+    pub slow_frames: Option<SlowFramesConfig>,
+
+    /// Replace emoji shortcodes in text, like `:rocket:`, with the emoji they stand for.
+    #[serde(default)]
+    pub shortcode_emoji: bool,
+
+    /// Pause countdown timers while the terminal doesn't have focus.
+    #[serde(default = "default_pause_on_focus_loss")]
+    pub pause_on_focus_loss: bool,
+
+    /// Only allow presentations to reference files within their own directory, and never execute
+    /// code in them.
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+/// A margin applied on every side of the terminal before laying out anything.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SafeAreaMargin {
+    /// The number of rows to leave empty at the top and bottom of the terminal.
+    #[serde(default)]
+    pub rows: u16,
+
+    /// The number of columns to leave empty at the left and right of the terminal.
+    #[serde(default, alias = "cols")]
+    pub columns: u16,
+}
+
+/// A terminal size to preview slides at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PreviewDimensions {
+    /// The number of columns.
+    #[serde(alias = "cols")]
+    #[validate(range(min = 1))]
+    pub columns: u16,
+
+    /// The number of rows.
+    #[validate(range(min = 1))]
+    pub rows: u16,
+}
+
+impl fmt::Display for PreviewDimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.columns, self.rows)
+    }
+}
+
+/// How to report frames that take too long to render.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SlowFramesConfig {
+    /// The number of milliseconds after which a frame is considered slow.
+    #[serde(default = "default_slow_frame_threshold")]
+    pub threshold_ms: u32,
+
+    /// Whether to show a marker in the top right corner of the screen after a slow frame.
+    #[serde(default)]
+    pub indicator: bool,
+}
+
+/// The theme to use by default.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum DefaultTheme {
+    /// Always use the theme with this name.
+    Named(String),
+
+    /// Pick a theme depending on whether the terminal's background is light or dark.
+    Auto {
+        /// The themes to choose from.
+        auto: AutoTheme,
+    },
+}
+
+/// The themes to pick from based on the terminal's background.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AutoTheme {
+    /// The theme to use when the terminal's background is light.
+    pub light: String,
+
+    /// The theme to use when the terminal's background is dark.
+    pub dark: String,
 }
 
 impl Default for DefaultsConfig {
@@ -75,6 +201,18 @@ impl Default for DefaultsConfig {
             terminal_font_size: default_font_size(),
             image_protocol: Default::default(),
             validate_overflows: Default::default(),
+            safe_area_margin: Default::default(),
+            cache_budget_mb: default_cache_budget(),
+            cache_usage_overlay: false,
+            artifact_cache_mb: default_artifact_cache(),
+            end_of_deck: Default::default(),
+            end_screen_message: default_end_screen_message(),
+            preview_dimensions: Default::default(),
+            cell_pixel_size: None,
+            slow_frames: None,
+            shortcode_emoji: false,
+            pause_on_focus_loss: default_pause_on_focus_loss(),
+            sandbox: false,
         }
     }
 }
@@ -83,6 +221,44 @@ fn default_font_size() -> u8 {
     16
 }
 
+fn default_cache_budget() -> u32 {
+    512
+}
+
+fn default_artifact_cache() -> u32 {
+    256
+}
+
+fn default_end_screen_message() -> String {
+    "End of presentation".into()
+}
+
+fn default_slow_frame_threshold() -> u32 {
+    50
+}
+
+fn default_pause_on_focus_loss() -> bool {
+    true
+}
+
+/// What to do when moving forward on the last slide.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EndOfDeck {
+    /// Stay on the last slide.
+    #[default]
+    Stop,
+
+    /// Go back to the first slide. Moving backwards on the first slide goes to the last one.
+    Wrap,
+
+    /// Show a blank screen. Moving backwards goes back to the last slide.
+    Black,
+
+    /// Show a screen saying the presentation is over. Moving backwards goes back to the last slide.
+    EndScreen,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ValidateOverflows {
@@ -93,6 +269,55 @@ pub enum ValidateOverflows {
     WhenDeveloping,
 }
 
+/// How elements that haven't been revealed yet are displayed.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevealStyle {
+    /// Hide them until they're revealed.
+    #[default]
+    Hide,
+
+    /// Display them dimmed so the layout doesn't change as they're revealed.
+    Dim,
+}
+
+/// Which checks on the structure of a presentation's headings are made when it's loaded.
+#[derive(Clone, Copy, Debug, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StructureChecks {
+    /// Warn about skipped heading levels, multiple level 1 headings in a slide, and slide titles
+    /// that show up after other content.
+    #[default]
+    Strict,
+
+    /// Don't check the structure of the presentation's headings.
+    Relaxed,
+}
+
+/// When countdown timers start ticking.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CountdownStart {
+    /// As soon as the slide they're in is displayed.
+    #[default]
+    Shown,
+
+    /// When the key binding that renders widgets is pressed.
+    KeyPress,
+}
+
+/// What happens to a countdown timer when the slide it's in is displayed again.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CountdownReturn {
+    /// Continue from where it was paused when moving away from the slide.
+    #[default]
+    Resume,
+
+    /// Start over from its full duration.
+    Reset,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct OptionsConfig {
@@ -110,6 +335,50 @@ pub struct OptionsConfig {
 
     /// Whether to be strict about parsing the presentation's front matter.
     pub strict_front_matter_parsing: Option<bool>,
+
+    /// The maximum number of elements to render in a single slide.
+    ///
+    /// Any elements past this limit are replaced by a line indicating how many of them were left out.
+    pub max_elements_per_render: Option<usize>,
+
+    /// How table rows that haven't been revealed yet are displayed when using incremental rows.
+    pub reveal_style: Option<RevealStyle>,
+
+    /// Whether terms in the presentation's glossary are matched in a case sensitive way.
+    pub glossary_case_sensitive: Option<bool>,
+
+    /// When countdown timers start ticking.
+    pub countdown_start: Option<CountdownStart>,
+
+    /// What happens to a countdown timer when the slide it's in is displayed again.
+    pub countdown_return: Option<CountdownReturn>,
+
+    /// Whether to ring the terminal bell when a countdown timer reaches zero.
+    pub countdown_bell: Option<bool>,
+
+    /// Whether to warn about problems in the structure of the presentation's headings.
+    pub structure: Option<StructureChecks>,
+
+    /// Whether to warn about slides that have the same title.
+    pub warn_on_duplicate_titles: Option<bool>,
+
+    /// How much a single slide can hold before it's reported or split.
+    pub slide_budget: Option<SlideBudget>,
+}
+
+/// How much a single slide can hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SlideBudget {
+    /// The terminal size every slide needs to fit in vertically.
+    pub size: Option<PreviewDimensions>,
+
+    /// The maximum number of steps in a slide.
+    pub max_steps: Option<usize>,
+
+    /// Split slides that go over the budget into several ones instead of only warning about them.
+    #[serde(default)]
+    pub split: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, JsonSchema)]
@@ -118,11 +387,15 @@ pub struct TypstConfig {
     /// The pixels per inch when rendering latex/typst formulas.
     #[serde(default = "default_typst_ppi")]
     pub ppi: u32,
+
+    /// Whether to render display math blocks delimited by `$$`.
+    #[serde(default)]
+    pub math_blocks: bool,
 }
 
 impl Default for TypstConfig {
     fn default() -> Self {
-        Self { ppi: default_typst_ppi() }
+        Self { ppi: default_typst_ppi(), math_blocks: false }
     }
 }
 
@@ -130,6 +403,41 @@ fn default_typst_ppi() -> u32 {
     300
 }
 
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ExecutionConfig {
+    /// The number of seconds a piece of code can run for before it's killed.
+    #[serde(default = "default_execution_timeout")]
+    #[validate(range(min = 1))]
+    pub timeout_seconds: u64,
+
+    /// The maximum number of bytes of output a piece of code can produce before it's killed.
+    #[serde(default = "default_execution_max_output_bytes")]
+    pub max_output_bytes: usize,
+
+    /// The only environment variables passed on to executed code. By default every environment
+    /// variable is passed on. This is only supported on unix systems.
+    pub environment: Option<Vec<String>>,
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: default_execution_timeout(),
+            max_output_bytes: default_execution_max_output_bytes(),
+            environment: None,
+        }
+    }
+}
+
+fn default_execution_timeout() -> u64 {
+    300
+}
+
+fn default_execution_max_output_bytes() -> usize {
+    1024 * 1024
+}
+
 #[derive(Clone, Debug, Default, Deserialize, ValueEnum, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum ImageProtocol {
@@ -227,6 +535,36 @@ pub struct KeyBindingsConfig {
     #[serde(default = "default_close_modal_bindings")]
     pub(crate) close_modal: Vec<KeyBinding>,
 
+    /// The key binding to toggle a blank screen.
+    #[serde(default = "default_toggle_blank_bindings")]
+    pub(crate) toggle_blank: Vec<KeyBinding>,
+
+    /// The key binding to cycle through the dimensions the current slide is previewed at.
+    #[serde(default = "default_cycle_preview_dimensions_bindings")]
+    pub(crate) cycle_preview_dimensions: Vec<KeyBinding>,
+
+    /// The key binding to toggle the modal with the glossary terms used in the current slide.
+    #[serde(default = "default_toggle_glossary_bindings")]
+    pub(crate) toggle_glossary: Vec<KeyBinding>,
+
+    /// The key binding to display the current slide's code blocks on their own, one at a time.
+    #[serde(default = "default_focus_code_bindings")]
+    pub(crate) focus_code: Vec<KeyBinding>,
+
+    /// The key binding to expand or collapse the `<details>` blocks in the current slide.
+    #[serde(default = "default_toggle_details_bindings")]
+    pub(crate) toggle_details: Vec<KeyBinding>,
+
+    /// The key binding to temporarily reveal the text redacted in the presentation.
+    ///
+    /// There's no binding for this by default so redacted text can't be revealed by accident.
+    #[serde(default)]
+    pub(crate) toggle_redactions: Vec<KeyBinding>,
+
+    /// The key binding to open the command line.
+    #[serde(default = "default_command_line_bindings")]
+    pub(crate) command_line: Vec<KeyBinding>,
+
     /// The key binding to close the application.
     #[serde(default = "default_exit_bindings")]
     pub(crate) exit: Vec<KeyBinding>,
@@ -245,6 +583,13 @@ impl Default for KeyBindingsConfig {
             toggle_slide_index: default_toggle_index_bindings(),
             toggle_bindings: default_toggle_bindings_modal_bindings(),
             close_modal: default_close_modal_bindings(),
+            toggle_blank: default_toggle_blank_bindings(),
+            cycle_preview_dimensions: default_cycle_preview_dimensions_bindings(),
+            toggle_glossary: default_toggle_glossary_bindings(),
+            focus_code: default_focus_code_bindings(),
+            toggle_details: default_toggle_details_bindings(),
+            toggle_redactions: Vec::new(),
+            command_line: default_command_line_bindings(),
             exit: default_exit_bindings(),
         }
     }
@@ -298,6 +643,30 @@ fn default_close_modal_bindings() -> Vec<KeyBinding> {
     make_keybindings(["<esc>"])
 }
 
+fn default_toggle_blank_bindings() -> Vec<KeyBinding> {
+    make_keybindings(["b", "."])
+}
+
+fn default_cycle_preview_dimensions_bindings() -> Vec<KeyBinding> {
+    make_keybindings(["d"])
+}
+
+fn default_toggle_glossary_bindings() -> Vec<KeyBinding> {
+    make_keybindings(["<c-g>"])
+}
+
+fn default_focus_code_bindings() -> Vec<KeyBinding> {
+    make_keybindings(["z"])
+}
+
+fn default_toggle_details_bindings() -> Vec<KeyBinding> {
+    make_keybindings(["<tab>"])
+}
+
+fn default_command_line_bindings() -> Vec<KeyBinding> {
+    make_keybindings([":"])
+}
+
 fn default_exit_bindings() -> Vec<KeyBinding> {
     make_keybindings(["<c-c>"])
 }
@@ -307,6 +676,36 @@ mod test {
     use super::*;
     use crate::input::user::CommandKeyBindings;
 
+    #[test]
+    fn default_theme() {
+        let config: DefaultsConfig = serde_yaml::from_str("theme: light").expect("parse failed");
+        assert_eq!(config.theme, Some(DefaultTheme::Named("light".into())));
+
+        let config: DefaultsConfig =
+            serde_yaml::from_str("theme:\n  auto:\n    light: catppuccin-latte\n    dark: tokyonight-storm")
+                .expect("parse failed");
+        let expected = AutoTheme { light: "catppuccin-latte".into(), dark: "tokyonight-storm".into() };
+        assert_eq!(config.theme, Some(DefaultTheme::Auto { auto: expected }));
+    }
+
+    #[test]
+    fn preview_dimensions() {
+        let config: DefaultsConfig =
+            serde_yaml::from_str("preview_dimensions:\n  - columns: 100\n    rows: 30\n  - cols: 80\n    rows: 24")
+                .expect("parse failed");
+        let expected = &[PreviewDimensions { columns: 100, rows: 30 }, PreviewDimensions { columns: 80, rows: 24 }];
+        assert_eq!(config.preview_dimensions, expected);
+        assert_eq!(expected[0].to_string(), "100x30");
+    }
+
+    #[test]
+    fn cell_pixel_size() {
+        let config: DefaultsConfig = serde_yaml::from_str("cell_pixel_size: [10, 22]").expect("parse failed");
+        assert_eq!(config.cell_pixel_size, Some([10, 22]));
+
+        serde_yaml::from_str::<DefaultsConfig>("cell_pixel_size: [10]").expect_err("parse succeeded");
+    }
+
     #[test]
     fn default_bindings() {
         let config = KeyBindingsConfig::default();
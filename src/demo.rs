@@ -1,41 +1,14 @@
 use crate::{
+    builtin,
     input::{
         source::Command,
         user::{CommandKeyBindings, UserInput},
     },
-    markdown::elements::MarkdownElement,
-    presentation::Presentation,
-    processing::builder::{BuildError, PresentationBuilder},
-    render::{draw::TerminalDrawer, terminal::TerminalWrite},
-    ImageRegistry, MarkdownParser, PresentationBuilderOptions, PresentationTheme, Resources, Themes, TypstRender,
+    render::{capabilities::CellSizePolicy, draw::TerminalDrawer, terminal::TerminalWrite},
+    Themes,
 };
 use std::io;
 
-const PRESENTATION: &str = r#"
-# Header 1
-## Header 2
-### Header 3
-#### Header 4
-##### Header 5
-###### Header 6
-
-```rust
-fn greet(name: &str) -> String {
-    format!("hi {name}")
-}
-````
-
-* **bold text**
-* _italics_
-    * `some inline code`
-    * ~strikethrough~
-
-> a block quote
-
-<!-- end_slide -->
-<!-- end_slide -->
-"#;
-
 pub struct ThemesDemo<W: TerminalWrite> {
     themes: Themes,
     input: UserInput,
@@ -45,18 +18,17 @@ pub struct ThemesDemo<W: TerminalWrite> {
 impl<W: TerminalWrite> ThemesDemo<W> {
     pub fn new(themes: Themes, bindings: CommandKeyBindings, writer: W) -> io::Result<Self> {
         let input = UserInput::new(bindings);
-        let drawer = TerminalDrawer::new(writer, Default::default(), 1)?;
+        let cell_size = CellSizePolicy::from_font_size(1);
+        let drawer =
+            TerminalDrawer::new(writer, Default::default(), cell_size, Default::default(), Default::default())?;
         Ok(Self { themes, input, drawer })
     }
 
     pub fn run(mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let arena = Default::default();
-        let parser = MarkdownParser::new(&arena);
-        let elements = parser.parse(PRESENTATION).expect("broken demo presentation");
         let mut presentations = Vec::new();
         for theme_name in self.themes.presentation.theme_names() {
             let theme = self.themes.presentation.load_by_name(&theme_name).expect("theme not found");
-            let presentation = self.build(&elements, &theme_name, &theme)?;
+            let presentation = builtin::theme_demo(&theme_name, &theme, &self.themes)?;
             presentations.push(presentation);
         }
         let mut current = 0;
@@ -90,31 +62,6 @@ impl<W: TerminalWrite> ThemesDemo<W> {
             }
         }
     }
-
-    fn build(
-        &self,
-        base_elements: &[MarkdownElement],
-        theme_name: &str,
-        theme: &PresentationTheme,
-    ) -> Result<Presentation, BuildError> {
-        let image_registry = ImageRegistry::default();
-        let mut resources = Resources::new("non_existent", image_registry.clone());
-        let mut typst = TypstRender::default();
-        let options = PresentationBuilderOptions::default();
-        let bindings_config = Default::default();
-        let builder = PresentationBuilder::new(
-            theme,
-            &mut resources,
-            &mut typst,
-            &self.themes,
-            image_registry,
-            bindings_config,
-            options,
-        );
-        let mut elements = vec![MarkdownElement::SetexHeading { text: format!("theme: {theme_name}").into() }];
-        elements.extend(base_elements.iter().cloned());
-        builder.build(elements)
-    }
 }
 
 enum DemoCommand {
@@ -124,15 +71,3 @@ enum DemoCommand {
     Last,
     Exit,
 }
-
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn demo_presentation() {
-        let arena = Default::default();
-        let parser = MarkdownParser::new(&arena);
-        parser.parse(PRESENTATION).expect("broken demo presentation");
-    }
-}
@@ -81,6 +81,10 @@ impl ContentDiff for RenderOperation {
             (RenderDynamic(original), RenderDynamic(updated)) => {
                 original.diffable_content() != updated.diffable_content()
             }
+            (RenderOnDemand(original), RenderOnDemand(updated)) if original.type_id() != updated.type_id() => true,
+            (RenderOnDemand(original), RenderOnDemand(updated)) => {
+                original.diffable_content() != updated.diffable_content()
+            }
             _ => false,
         }
     }
@@ -131,7 +135,7 @@ mod test {
 
     #[rstest]
     #[case(RenderOperation::ClearScreen)]
-    #[case(RenderOperation::JumpToVerticalCenter)]
+    #[case(RenderOperation::JumpToVerticalCenter { operations: None })]
     #[case(RenderOperation::JumpToBottomRow{ index: 0 })]
     #[case(RenderOperation::RenderLineBreak)]
     #[case(RenderOperation::SetColors(Colors{background: None, foreground: None}))]
@@ -241,7 +245,7 @@ mod test {
         ]);
         let rhs = Presentation::from(vec![
             Slide::from(vec![RenderOperation::ClearScreen]),
-            Slide::from(vec![RenderOperation::JumpToVerticalCenter]),
+            Slide::from(vec![RenderOperation::JumpToVerticalCenter { operations: None }]),
             Slide::from(vec![RenderOperation::ClearScreen]),
         ]);
 
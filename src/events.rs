@@ -0,0 +1,687 @@
+//! Machine readable events emitted while a presentation is running.
+//!
+//! Events are written as newline delimited JSON so external tools (lighting scripts, scene
+//! switchers, etc) can follow along with the presentation.
+
+use crate::{
+    cache::CacheReport,
+    prefetch::PrefetchStats,
+    presentation::{Presentation, SlideNumbering, SlideReference},
+    render::profile::FrameProfile,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The version of the event schema.
+///
+/// This is bumped every time a change that is not backwards compatible is made to
+/// [PresentationEvent] or [EventRecord].
+pub const EVENTS_SCHEMA_VERSION: u32 = 1;
+
+// The maximum number of events we'll buffer while the consumer isn't reading them.
+const DEFAULT_CAPACITY: usize = 1024;
+
+// The speaking rate used to estimate how long a presentation takes.
+const WORDS_PER_MINUTE: u32 = 130;
+
+// The number of operations included in a slow frame's breakdown.
+const SLOW_FRAME_OPERATIONS: usize = 5;
+
+/// An event emitted during a presentation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PresentationEvent {
+    /// The presentation was loaded, either for the first time or after a reload.
+    PresentationLoaded {
+        /// The presentation's title, if it has one.
+        title: Option<String>,
+
+        /// The number of slides in the presentation.
+        slide_count: usize,
+
+        /// The number of words in the presentation, excluding code blocks.
+        word_count: usize,
+
+        /// The estimated number of seconds it takes to read the presentation out loud.
+        estimated_seconds: u64,
+
+        /// The title of every slide, if it has one.
+        titles: Vec<Option<String>>,
+    },
+
+    /// The current slide or the current step within it changed.
+    SlideChanged {
+        /// The zero based index of the slide.
+        index: usize,
+
+        /// The number the slide is displayed with, or `None` if it's unnumbered.
+        number: Option<usize>,
+
+        /// The title of the slide, if it has one.
+        title: Option<String>,
+
+        /// The zero based index of the step within the slide.
+        step: usize,
+
+        /// The number of steps in the slide.
+        total_steps: usize,
+    },
+
+    /// Moving forward on the last slide displayed the end screen.
+    EndReached,
+
+    /// The code snippets in a slide started executing.
+    ExecStarted {
+        /// The zero based index of the slide.
+        slide: usize,
+    },
+
+    /// The code snippets in a slide finished executing.
+    ExecFinished {
+        /// The zero based index of the slide.
+        slide: usize,
+    },
+
+    /// Cached content was evicted to stay within the cache budget.
+    CacheTrimmed {
+        /// The number of bytes cached after evicting.
+        used_bytes: usize,
+
+        /// The cache budget in bytes.
+        budget_bytes: usize,
+
+        /// The number of bytes evicted.
+        evicted_bytes: usize,
+    },
+
+    /// The time spent in a slide went over its `max_duration`.
+    ///
+    /// This is emitted at most once per slide.
+    SlideOverBudget {
+        /// The zero based index of the slide.
+        slide: usize,
+
+        /// The number the slide is displayed with, or `None` if it's unnumbered.
+        number: Option<usize>,
+
+        /// The slide's maximum duration in seconds.
+        budget_seconds: u64,
+    },
+
+    /// A frame took longer to render than the configured threshold.
+    SlowFrame {
+        /// The zero based index of the slide being displayed.
+        slide: usize,
+
+        /// The total time spent rendering the frame, in microseconds.
+        total_micros: u64,
+
+        /// The time spent laying out the frame, in microseconds.
+        layout_micros: u64,
+
+        /// The time spent writing the frame to the terminal, in microseconds.
+        paint_micros: u64,
+
+        /// The operations that took the longest to lay out, slowest first.
+        slowest: Vec<OperationTime>,
+    },
+
+    /// The slides that went over their `max_duration`, emitted right before the presentation ends.
+    BudgetReport {
+        /// Every slide that went over its budget.
+        slides: Vec<SlideOverage>,
+    },
+
+    /// The presentation ended.
+    PresentationEnded {
+        /// The number of times the current slide changed.
+        navigations: usize,
+
+        /// The number of navigations into a slide that had already been prefetched.
+        warm_navigations: usize,
+    },
+}
+
+/// A slide that went over its maximum duration.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlideOverage {
+    /// The zero based index of the slide.
+    pub slide: usize,
+
+    /// The number the slide is displayed with, or `None` if it's unnumbered.
+    pub number: Option<usize>,
+
+    /// The slide's maximum duration in seconds.
+    pub budget_seconds: u64,
+
+    /// The total time spent in the slide in seconds.
+    pub spent_seconds: u64,
+}
+
+/// The time an operation in a slow frame took to lay out.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationTime {
+    /// The index of the operation within the ones rendered along with it.
+    pub index: usize,
+
+    /// The kind of operation, like `text` or `image`.
+    pub operation: String,
+
+    /// The time it took, in microseconds.
+    pub micros: u64,
+}
+
+/// A single line in the event stream.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventRecord {
+    /// The schema version, see [EVENTS_SCHEMA_VERSION].
+    pub version: u32,
+
+    /// The total number of events dropped so far because the consumer wasn't keeping up.
+    pub dropped: u64,
+
+    /// The event itself.
+    #[serde(flatten)]
+    pub event: PresentationEvent,
+}
+
+/// Emits events into a file or FIFO.
+///
+/// Writes happen in a background thread so emitting an event never blocks. If the consumer stops
+/// reading and the internal buffer fills up, events are dropped and counted instead.
+pub struct EventEmitter {
+    sender: SyncSender<PresentationEvent>,
+    dropped: Arc<AtomicU64>,
+    finished: Receiver<()>,
+}
+
+impl EventEmitter {
+    /// Construct an emitter that writes events into the given path.
+    ///
+    /// The file is opened in the background as opening a FIFO blocks until someone reads from it.
+    pub fn open(path: PathBuf) -> Self {
+        Self::spawn(move || File::create(path).map(BufWriter::new), DEFAULT_CAPACITY)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn new<W: Write + Send + 'static>(writer: W, capacity: usize) -> Self {
+        Self::spawn(move || Ok(writer), capacity)
+    }
+
+    fn spawn<F, W>(open: F, capacity: usize) -> Self
+    where
+        F: FnOnce() -> io::Result<W> + Send + 'static,
+        W: Write,
+    {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let (finished_sender, finished) = mpsc::channel();
+        let dropped = Arc::new(AtomicU64::new(0));
+        let writer_dropped = dropped.clone();
+        thread::spawn(move || {
+            // Errors here can't be displayed anywhere so the stream simply stops.
+            if let Ok(writer) = open() {
+                let _ = Self::write_events(writer, receiver, &writer_dropped);
+            }
+            let _ = finished_sender.send(());
+        });
+        Self { sender, dropped, finished }
+    }
+
+    fn write_events<W: Write>(
+        mut writer: W,
+        receiver: Receiver<PresentationEvent>,
+        dropped: &AtomicU64,
+    ) -> io::Result<()> {
+        for event in receiver {
+            let record =
+                EventRecord { version: EVENTS_SCHEMA_VERSION, dropped: dropped.load(Ordering::Relaxed), event };
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n")?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Emit an event.
+    pub(crate) fn emit(&self, event: PresentationEvent) {
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(event) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The number of events that were dropped so far.
+    #[cfg(test)]
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Stop emitting events, waiting up to `timeout` for pending ones to be written.
+    pub(crate) fn finish(self, timeout: Duration) {
+        let Self { sender, finished, .. } = self;
+        drop(sender);
+        let _ = finished.recv_timeout(timeout);
+    }
+}
+
+/// Tracks a presentation and emits events when its state changes.
+pub(crate) struct PresentationEvents {
+    emitter: Option<EventEmitter>,
+    position: Option<(usize, usize)>,
+    at_end: bool,
+    budgets: SlideBudgets,
+    numbering: SlideNumbering,
+}
+
+impl PresentationEvents {
+    pub(crate) fn new(emitter: Option<EventEmitter>) -> Self {
+        Self { emitter, position: None, at_end: false, budgets: Default::default(), numbering: Default::default() }
+    }
+
+    /// Notify that a presentation was loaded.
+    pub(crate) fn loaded(&mut self, presentation: &Presentation) {
+        let titles = presentation.iter_slides().map(|slide| slide.title().map(String::from)).collect();
+        self.emit(PresentationEvent::PresentationLoaded {
+            title: presentation.title().map(String::from),
+            slide_count: presentation.slide_count(),
+            word_count: presentation.word_count(),
+            estimated_seconds: presentation.estimated_duration(WORDS_PER_MINUTE).as_secs(),
+            titles,
+        });
+        let budgets = presentation.iter_slides().map(|slide| slide.max_duration()).collect();
+        self.budgets.load(budgets, Instant::now());
+        self.numbering = presentation.numbering().clone();
+        self.position = None;
+        self.moved(presentation);
+    }
+
+    /// Notify that the presentation's position may have changed.
+    pub(crate) fn moved(&mut self, presentation: &Presentation) {
+        if presentation.iter_slides().next().is_none() {
+            return;
+        }
+        let position = (presentation.current_slide_index(), presentation.current_step());
+        let at_end = presentation.is_at_end();
+        if self.position == Some(position) && self.at_end == at_end {
+            return;
+        }
+        self.position = Some(position);
+        self.at_end = at_end;
+        if at_end {
+            // The time spent in the end screen doesn't count towards the last slide's budget.
+            self.budgets.leave(Instant::now());
+            self.emit(PresentationEvent::EndReached);
+            return;
+        }
+        let (index, step) = position;
+        self.budgets.enter(index, Instant::now());
+        let number = presentation.numbering().display_number(index);
+        let slide = presentation.current_slide();
+        let title = slide.title().map(String::from);
+        let total_steps = slide.steps().total();
+        self.emit(PresentationEvent::SlideChanged { index, number, title, step, total_steps });
+    }
+
+    /// Notify that the code in a slide started executing.
+    pub(crate) fn exec_started(&self, slide: usize) {
+        self.emit(PresentationEvent::ExecStarted { slide });
+    }
+
+    /// Notify that the code in a slide finished executing.
+    pub(crate) fn exec_finished(&self, slide: usize) {
+        self.emit(PresentationEvent::ExecFinished { slide });
+    }
+
+    /// Notify that cached content was evicted.
+    pub(crate) fn cache_trimmed(&self, report: &CacheReport, budget_bytes: usize) {
+        let CacheReport { used, evicted } = report;
+        self.emit(PresentationEvent::CacheTrimmed { used_bytes: *used, budget_bytes, evicted_bytes: *evicted });
+    }
+
+    /// Notify that a frame took too long to render.
+    pub(crate) fn slow_frame(&self, slide: usize, profile: &FrameProfile) {
+        let micros = |duration: Duration| duration.as_micros() as u64;
+        let slowest = profile
+            .slowest(SLOW_FRAME_OPERATIONS)
+            .into_iter()
+            .map(|timing| OperationTime {
+                index: timing.index,
+                operation: timing.operation.to_string(),
+                micros: micros(timing.elapsed),
+            })
+            .collect();
+        self.emit(PresentationEvent::SlowFrame {
+            slide,
+            total_micros: micros(profile.total()),
+            layout_micros: micros(profile.layout),
+            paint_micros: micros(profile.paint),
+            slowest,
+        });
+    }
+
+    /// Check whether the current slide went over its maximum duration.
+    ///
+    /// This returns the slide and its maximum duration the first time it goes over it.
+    pub(crate) fn tick(&mut self, now: Instant) -> Option<(SlideReference, Duration)> {
+        let (slide, budget) = self.budgets.check(now)?;
+        let number = self.numbering.display_number(slide);
+        self.emit(PresentationEvent::SlideOverBudget { slide, number, budget_seconds: budget.as_secs() });
+        Some((self.numbering.reference(slide), budget))
+    }
+
+    /// Notify that the presentation ended and flush any pending events.
+    pub(crate) fn ended(&mut self, stats: &PrefetchStats) {
+        let slides = self.budgets.overages(Instant::now(), &self.numbering);
+        if !slides.is_empty() {
+            self.emit(PresentationEvent::BudgetReport { slides });
+        }
+        let PrefetchStats { navigations, warm_navigations } = stats;
+        self.emit(PresentationEvent::PresentationEnded {
+            navigations: *navigations,
+            warm_navigations: *warm_navigations,
+        });
+        if let Some(emitter) = self.emitter.take() {
+            emitter.finish(Duration::from_secs(1));
+        }
+    }
+
+    fn emit(&self, event: PresentationEvent) {
+        if let Some(emitter) = &self.emitter {
+            emitter.emit(event);
+        }
+    }
+}
+
+/// Keeps track of how long is spent in every slide that has a maximum duration.
+///
+/// Time spent in a slide is accumulated across visits and it's kept when the presentation is
+/// reloaded.
+#[derive(Debug, Default)]
+struct SlideBudgets {
+    budgets: Vec<Option<Duration>>,
+    spent: Vec<Duration>,
+    notified: Vec<bool>,
+    current: Option<(usize, Instant)>,
+}
+
+impl SlideBudgets {
+    fn load(&mut self, budgets: Vec<Option<Duration>>, now: Instant) {
+        self.account(now);
+        self.spent.resize(budgets.len(), Duration::ZERO);
+        self.notified.resize(budgets.len(), false);
+        self.budgets = budgets;
+        self.current = self.current.filter(|(slide, _)| *slide < self.budgets.len());
+    }
+
+    fn enter(&mut self, slide: usize, now: Instant) {
+        if matches!(self.current, Some((current, _)) if current == slide) {
+            return;
+        }
+        self.account(now);
+        self.current = (slide < self.budgets.len()).then_some((slide, now));
+    }
+
+    fn leave(&mut self, now: Instant) {
+        self.account(now);
+        self.current = None;
+    }
+
+    // Returns the current slide and its budget the first time it goes over it.
+    fn check(&mut self, now: Instant) -> Option<(usize, Duration)> {
+        let (slide, since) = self.current?;
+        let budget = self.budgets[slide]?;
+        if self.notified[slide] || self.spent[slide] + now.saturating_duration_since(since) <= budget {
+            return None;
+        }
+        self.notified[slide] = true;
+        Some((slide, budget))
+    }
+
+    fn overages(&mut self, now: Instant, numbering: &SlideNumbering) -> Vec<SlideOverage> {
+        self.account(now);
+        self.budgets
+            .iter()
+            .zip(&self.spent)
+            .enumerate()
+            .filter_map(|(slide, (budget, spent))| {
+                let budget = (*budget)?;
+                (*spent > budget).then_some(SlideOverage {
+                    slide,
+                    number: numbering.display_number(slide),
+                    budget_seconds: budget.as_secs(),
+                    spent_seconds: spent.as_secs(),
+                })
+            })
+            .collect()
+    }
+
+    // Adds the time spent in the current slide up until now to its total.
+    fn account(&mut self, now: Instant) {
+        if let Some((slide, since)) = &mut self.current {
+            if let Some(spent) = self.spent.get_mut(*slide) {
+                *spent += now.saturating_duration_since(*since);
+            }
+            *since = now;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::presentation::{RenderOperation, SlideBuilder, SlideChunk};
+    use std::sync::{Barrier, Mutex};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn records(&self) -> Vec<EventRecord> {
+            let contents = String::from_utf8(self.0.lock().unwrap().clone()).expect("not utf8");
+            contents.lines().map(|line| serde_json::from_str(line).expect("invalid record")).collect()
+        }
+    }
+
+    fn build_presentation() -> Presentation {
+        let chunk = || SlideChunk::new(vec![RenderOperation::ClearScreen], vec![]);
+        let slides = vec![
+            SlideBuilder::default().chunks(vec![chunk(), chunk()]).title("intro".into()).build(),
+            SlideBuilder::default().chunks(vec![chunk()]).numbered(false).build(),
+        ];
+        Presentation::from(slides)
+    }
+
+    #[test]
+    fn navigation_stream() {
+        let buffer = SharedBuffer::default();
+        let mut events = PresentationEvents::new(Some(EventEmitter::new(buffer.clone(), 16)));
+        let mut presentation = build_presentation();
+        events.loaded(&presentation);
+        presentation.jump_next();
+        events.moved(&presentation);
+        presentation.jump_next();
+        events.moved(&presentation);
+        // We're at the end so this is a no-op and shouldn't be emitted.
+        presentation.jump_next();
+        events.moved(&presentation);
+        events.exec_started(1);
+        events.exec_finished(1);
+        presentation.jump_previous();
+        events.moved(&presentation);
+        events.ended(&PrefetchStats { navigations: 3, warm_navigations: 2 });
+
+        let records = buffer.records();
+        assert!(records.iter().all(|record| record.version == EVENTS_SCHEMA_VERSION && record.dropped == 0));
+        let events: Vec<_> = records.into_iter().map(|record| record.event).collect();
+        let intro = || Some("intro".to_string());
+        let expected = vec![
+            PresentationEvent::PresentationLoaded {
+                title: None,
+                slide_count: 2,
+                word_count: 0,
+                estimated_seconds: 0,
+                titles: vec![intro(), None],
+            },
+            PresentationEvent::SlideChanged { index: 0, number: Some(1), title: intro(), step: 0, total_steps: 2 },
+            PresentationEvent::SlideChanged { index: 0, number: Some(1), title: intro(), step: 1, total_steps: 2 },
+            PresentationEvent::SlideChanged { index: 1, number: None, title: None, step: 0, total_steps: 1 },
+            PresentationEvent::ExecStarted { slide: 1 },
+            PresentationEvent::ExecFinished { slide: 1 },
+            PresentationEvent::SlideChanged { index: 0, number: Some(1), title: intro(), step: 1, total_steps: 2 },
+            PresentationEvent::PresentationEnded { navigations: 3, warm_navigations: 2 },
+        ];
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn end_reached() {
+        let buffer = SharedBuffer::default();
+        let mut events = PresentationEvents::new(Some(EventEmitter::new(buffer.clone(), 16)));
+        let mut presentation = build_presentation().with_end_slide(Some(SlideBuilder::default().build()));
+        presentation.go_to_slide(1);
+        events.loaded(&presentation);
+        presentation.jump_next();
+        events.moved(&presentation);
+        presentation.jump_previous();
+        events.moved(&presentation);
+        events.ended(&PrefetchStats::default());
+
+        let mut events: Vec<_> = buffer.records().into_iter().skip(1).map(|record| record.event).collect();
+        events.pop();
+        let slide_changed =
+            || PresentationEvent::SlideChanged { index: 1, number: None, title: None, step: 0, total_steps: 1 };
+        let expected = vec![slide_changed(), PresentationEvent::EndReached, slide_changed()];
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn slow_frame() {
+        let buffer = SharedBuffer::default();
+        let mut events = PresentationEvents::new(Some(EventEmitter::new(buffer.clone(), 16)));
+        let mut profile =
+            FrameProfile { layout: Duration::from_millis(70), paint: Duration::from_millis(5), ..Default::default() };
+        for (index, millis) in [1, 60, 9].into_iter().enumerate() {
+            profile.record(index, &RenderOperation::RenderLineBreak, Duration::from_millis(millis));
+        }
+        events.slow_frame(2, &profile);
+        events.ended(&PrefetchStats::default());
+
+        let mut events: Vec<_> = buffer.records().into_iter().map(|record| record.event).collect();
+        events.pop();
+        let operation = |index, micros| OperationTime { index, operation: "line_break".into(), micros };
+        let expected = PresentationEvent::SlowFrame {
+            slide: 2,
+            total_micros: 75_000,
+            layout_micros: 70_000,
+            paint_micros: 5_000,
+            slowest: vec![operation(1, 60_000), operation(2, 9_000), operation(0, 1_000)],
+        };
+        assert_eq!(events, &[expected]);
+    }
+
+    #[test]
+    fn budgets() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let mut budgets = SlideBudgets::default();
+        budgets.load(vec![Some(Duration::from_secs(10)), None, Some(Duration::from_secs(5))], start);
+        budgets.enter(0, start);
+        assert_eq!(budgets.check(at(8)), None);
+
+        // Time spent is accumulated across visits.
+        budgets.enter(1, at(8));
+        assert_eq!(budgets.check(at(100)), None);
+        budgets.enter(0, at(100));
+        assert_eq!(budgets.check(at(102)), None);
+        assert_eq!(budgets.check(at(103)), Some((0, Duration::from_secs(10))));
+        // Only notified once.
+        assert_eq!(budgets.check(at(104)), None);
+
+        // Reloading keeps the time spent.
+        budgets.load(vec![Some(Duration::from_secs(10)), None, Some(Duration::from_secs(5))], at(105));
+        budgets.enter(2, at(105));
+        let numbering = SlideNumbering::new([false, true, true]);
+        let expected = vec![SlideOverage { slide: 0, number: None, budget_seconds: 10, spent_seconds: 13 }];
+        assert_eq!(budgets.overages(at(109), &numbering), expected);
+    }
+
+    #[test]
+    fn tick_reports_overrun() {
+        let mut events = PresentationEvents::new(None);
+        let chunk = || SlideChunk::new(vec![RenderOperation::ClearScreen], vec![]);
+        let budget = Some(Duration::from_secs(5));
+        let slides = vec![
+            SlideBuilder::default().chunks(vec![chunk()]).numbered(false).max_duration(budget).build(),
+            SlideBuilder::default().chunks(vec![chunk()]).build(),
+        ];
+        let presentation = Presentation::from(slides);
+        let start = Instant::now();
+        events.loaded(&presentation);
+        assert_eq!(events.tick(start), None);
+
+        let slide = SlideReference::Unnumbered { after: None };
+        assert_eq!(events.tick(start + Duration::from_secs(6)), Some((slide, Duration::from_secs(5))));
+        // Only reported once.
+        assert_eq!(events.tick(start + Duration::from_secs(7)), None);
+    }
+
+    #[test]
+    fn record_format() {
+        let record = EventRecord { version: 1, dropped: 0, event: PresentationEvent::ExecStarted { slide: 3 } };
+        let serialized = serde_json::to_string(&record).expect("serialize failed");
+        assert_eq!(serialized, r#"{"version":1,"dropped":0,"type":"exec_started","slide":3}"#);
+    }
+
+    struct BlockedWriter(Arc<Barrier>, SharedBuffer);
+
+    impl Write for BlockedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.1.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.wait();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn stalled_consumer_drops_events() {
+        let barrier = Arc::new(Barrier::new(2));
+        let buffer = SharedBuffer::default();
+        let emitter = EventEmitter::new(BlockedWriter(barrier.clone(), buffer.clone()), 1);
+        for slide in 0..10 {
+            emitter.emit(PresentationEvent::ExecStarted { slide });
+        }
+        // At most one event is being written and another one is buffered.
+        let dropped = emitter.dropped();
+        assert!((8..=9).contains(&dropped), "dropped {dropped}");
+
+        // Let every accepted event through.
+        for _ in 0..10 - dropped {
+            barrier.wait();
+        }
+        emitter.finish(Duration::from_secs(5));
+        assert_eq!(buffer.records().len() as u64, 10 - dropped);
+    }
+}
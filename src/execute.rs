@@ -2,46 +2,117 @@
 
 use crate::markdown::elements::{Code, CodeLanguage};
 use std::{
-    io::{self, BufRead, BufReader, Write},
+    io::{self, Read, Write},
+    mem,
     process::{self, ChildStdout, Stdio},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
     thread::{self},
+    time::{Duration, Instant},
 };
 use tempfile::NamedTempFile;
 
+/// How often a running process is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The number of bytes read from a process' output at a time.
+const OUTPUT_CHUNK_SIZE: usize = 4096;
+
+/// The ids of the processes that are still running, each of which leads its own process group.
+static RUNNING_PROCESSES: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// Tests that spawn processes hold this for reading while the ones that kill every running process hold it for
+/// writing, so they don't kill processes other tests are waiting on.
+#[cfg(test)]
+pub(crate) static PROCESS_TESTS: std::sync::RwLock<()> = std::sync::RwLock::new(());
+
+/// The limits that apply to code executions.
+#[derive(Clone, Debug)]
+pub struct ExecutionPolicy {
+    /// How long code can run for before it's killed, unless the code block overrides it.
+    pub timeout: Duration,
+
+    /// The maximum number of output bytes to capture before the process is killed.
+    pub max_output_bytes: usize,
+
+    /// The only environment variables passed on to the process, if any.
+    ///
+    /// This is only applied on unix systems.
+    pub environment: Option<Vec<String>>,
+}
+
+impl Default for ExecutionPolicy {
+    fn default() -> Self {
+        Self { timeout: Duration::from_secs(300), max_output_bytes: 1024 * 1024, environment: None }
+    }
+}
+
 /// Allows executing code.
 pub(crate) struct CodeExecuter;
 
 impl CodeExecuter {
     /// Execute a piece of code.
-    pub(crate) fn execute(code: &Code) -> Result<ExecutionHandle, CodeExecuteError> {
+    pub(crate) fn execute(code: &Code, policy: &ExecutionPolicy) -> Result<ExecutionHandle, CodeExecuteError> {
         if !code.language.supports_execution() {
             return Err(CodeExecuteError::UnsupportedExecution);
         }
         if !code.attributes.execute {
             return Err(CodeExecuteError::NotExecutableCode);
         }
+        let mut policy = policy.clone();
+        if let Some(seconds) = code.attributes.timeout_seconds {
+            policy.timeout = Duration::from_secs(seconds);
+        }
         match &code.language {
-            CodeLanguage::Shell(interpreter) => Self::execute_shell(interpreter, &code.contents),
+            CodeLanguage::Shell(interpreter) => Self::execute_shell(interpreter, &code.contents, policy),
             _ => Err(CodeExecuteError::UnsupportedExecution),
         }
     }
 
-    fn execute_shell(interpreter: &str, code: &str) -> Result<ExecutionHandle, CodeExecuteError> {
+    /// Kill every process that's still running, along with anything they spawned.
+    pub(crate) fn kill_running() {
+        for id in RUNNING_PROCESSES.lock().unwrap_or_else(PoisonError::into_inner).drain(..) {
+            kill_process_group(id);
+        }
+    }
+
+    fn execute_shell(
+        interpreter: &str,
+        code: &str,
+        policy: ExecutionPolicy,
+    ) -> Result<ExecutionHandle, CodeExecuteError> {
         let mut output_file = NamedTempFile::new().map_err(CodeExecuteError::TempFile)?;
         output_file.write_all(code.as_bytes()).map_err(CodeExecuteError::TempFile)?;
         output_file.flush().map_err(CodeExecuteError::TempFile)?;
-        let process_handle = process::Command::new("/usr/bin/env")
+        let mut command = process::Command::new("/usr/bin/env");
+        command
             .arg(interpreter)
             .arg(output_file.path())
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn()
-            .map_err(CodeExecuteError::SpawnProcess)?;
+            .stderr(Stdio::null());
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+
+            // Run in a process group of its own so anything the code spawns can be killed along with it.
+            command.process_group(0);
+            if let Some(variables) = &policy.environment {
+                command.env_clear();
+                for variable in variables {
+                    if let Some(value) = std::env::var_os(variable) {
+                        command.env(variable, value);
+                    }
+                }
+            }
+        }
+        let process_handle = command.spawn().map_err(CodeExecuteError::SpawnProcess)?;
+        RUNNING_PROCESSES.lock().unwrap_or_else(PoisonError::into_inner).push(process_handle.id());
 
         let state: Arc<Mutex<ExecutionState>> = Default::default();
-        let reader_handle = ProcessReader::spawn(process_handle, state.clone(), output_file);
+        let reader_handle = ProcessReader::spawn(process_handle, state.clone(), output_file, policy);
         let handle = ExecutionHandle { state, reader_handle };
         Ok(handle)
     }
@@ -84,6 +155,7 @@ struct ProcessReader {
     state: Arc<Mutex<ExecutionState>>,
     #[allow(dead_code)]
     file_handle: NamedTempFile,
+    policy: ExecutionPolicy,
 }
 
 impl ProcessReader {
@@ -91,36 +163,118 @@ impl ProcessReader {
         handle: process::Child,
         state: Arc<Mutex<ExecutionState>>,
         file_handle: NamedTempFile,
+        policy: ExecutionPolicy,
     ) -> thread::JoinHandle<()> {
-        let reader = Self { handle, state, file_handle };
+        let reader = Self { handle, state, file_handle, policy };
         thread::spawn(|| reader.run())
     }
 
     fn run(mut self) {
         let stdout = self.handle.stdout.take().expect("no stdout");
-        let stdout = BufReader::new(stdout);
-        let _ = Self::process_output(self.state.clone(), stdout);
-        let success = match self.handle.try_wait() {
-            Ok(Some(code)) => code.success(),
-            _ => false,
+        let truncated = Arc::new(AtomicBool::new(false));
+        let output_handle = {
+            let state = self.state.clone();
+            let truncated = truncated.clone();
+            let max_bytes = self.policy.max_output_bytes;
+            thread::spawn(move || Self::process_output(state, stdout, max_bytes, truncated))
         };
-        let status = match success {
-            true => ProcessStatus::Success,
-            false => ProcessStatus::Failure,
+
+        let start = Instant::now();
+        let status = loop {
+            match self.handle.try_wait() {
+                Ok(Some(code)) if code.success() => break ProcessStatus::Success,
+                Ok(Some(_)) | Err(_) => break ProcessStatus::Failure,
+                Ok(None) => (),
+            };
+            if truncated.load(Ordering::Relaxed) {
+                break ProcessStatus::OutputLimitExceeded { bytes: self.policy.max_output_bytes };
+            }
+            if start.elapsed() >= self.policy.timeout {
+                break ProcessStatus::TimedOut { after: self.policy.timeout };
+            }
+            thread::sleep(POLL_INTERVAL);
         };
+        // Kill whatever is left, which includes anything running in the background once the
+        // process itself finished, so no orphans are left behind and the output reaches its end.
+        // The process is unregistered before it is reaped so `CodeExecuter::kill_running` never
+        // signals a process group whose id could have been reused by then.
+        RUNNING_PROCESSES.lock().unwrap_or_else(PoisonError::into_inner).retain(|id| *id != self.handle.id());
+        self.kill();
+        let _ = self.handle.wait();
+        let _ = output_handle.join();
         self.state.lock().unwrap().status = status;
     }
 
-    fn process_output(state: Arc<Mutex<ExecutionState>>, stdout: BufReader<ChildStdout>) -> io::Result<()> {
-        for line in stdout.lines() {
-            let line = line?;
-            // TODO: consider not locking per line...
-            state.lock().unwrap().output.push(line);
+    #[cfg(unix)]
+    fn kill(&mut self) {
+        kill_process_group(self.handle.id());
+    }
+
+    #[cfg(not(unix))]
+    fn kill(&mut self) {
+        let _ = self.handle.kill();
+    }
+
+    fn process_output(
+        state: Arc<Mutex<ExecutionState>>,
+        mut stdout: ChildStdout,
+        max_bytes: usize,
+        truncated: Arc<AtomicBool>,
+    ) -> io::Result<()> {
+        let mut buffer = [0; OUTPUT_CHUNK_SIZE];
+        // The output is read in chunks so a single huge line can't grow past the limit, meaning
+        // this holds the bytes of the last line until it's complete.
+        let mut pending_line = Vec::new();
+        let mut total_bytes = 0;
+        loop {
+            let bytes_read = match stdout.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(bytes_read) => bytes_read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if truncated.load(Ordering::Relaxed) {
+                // Keep reading so the process doesn't block on a full pipe before it's killed.
+                continue;
+            }
+            total_bytes += bytes_read;
+            if total_bytes > max_bytes {
+                truncated.store(true, Ordering::Relaxed);
+            }
+            // Only keep the lines that fit within the limit in full.
+            let chunk = &buffer[..bytes_read - total_bytes.saturating_sub(max_bytes)];
+            let mut lines = Vec::new();
+            for byte in chunk {
+                match byte {
+                    b'\n' => lines.push(String::from_utf8_lossy(&mem::take(&mut pending_line)).into_owned()),
+                    byte => pending_line.push(*byte),
+                }
+            }
+            if !lines.is_empty() {
+                state.lock().unwrap().output.extend(lines);
+            }
+        }
+        // The last line may not end in a new line.
+        if !pending_line.is_empty() && !truncated.load(Ordering::Relaxed) {
+            state.lock().unwrap().output.push(String::from_utf8_lossy(&pending_line).into_owned());
         }
         Ok(())
     }
 }
 
+#[cfg(unix)]
+fn kill_process_group(id: u32) {
+    // SAFETY: this only sends a signal to a process group we created for one of our processes.
+    unsafe {
+        libc::kill(-(id as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+// Without process groups, processes can only be killed through their handle by the thread that
+// reads their output.
+#[cfg(not(unix))]
+fn kill_process_group(_id: u32) {}
+
 /// The state of the execution of a process.
 #[derive(Clone, Default, Debug)]
 pub(crate) struct ExecutionState {
@@ -129,18 +283,24 @@ pub(crate) struct ExecutionState {
 }
 
 /// The status of a process.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub(crate) enum ProcessStatus {
     #[default]
     Running,
     Success,
     Failure,
+    TimedOut {
+        after: Duration,
+    },
+    OutputLimitExceeded {
+        bytes: usize,
+    },
 }
 
 impl ProcessStatus {
     /// Check whether the underlying process is finished.
     pub(crate) fn is_finished(&self) -> bool {
-        matches!(self, ProcessStatus::Success | ProcessStatus::Failure)
+        !matches!(self, ProcessStatus::Running)
     }
 }
 
@@ -149,8 +309,28 @@ mod test {
     use super::*;
     use crate::markdown::elements::CodeAttributes;
 
+    fn make_code(contents: &str) -> Code {
+        Code {
+            contents: contents.into(),
+            language: CodeLanguage::Shell("sh".into()),
+            language_name: "sh".into(),
+            attributes: CodeAttributes { execute: true, ..Default::default() },
+        }
+    }
+
+    fn wait_for_completion(handle: ExecutionHandle) -> ExecutionState {
+        loop {
+            let state = handle.state();
+            if state.status.is_finished() {
+                return state;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
     #[test]
     fn shell_code_execution() {
+        let _guard = PROCESS_TESTS.read().unwrap_or_else(PoisonError::into_inner);
         let contents = r"
 echo 'hello world'
 echo 'bye'"
@@ -158,9 +338,10 @@ echo 'bye'"
         let code = Code {
             contents,
             language: CodeLanguage::Shell("sh".into()),
+            language_name: "sh".into(),
             attributes: CodeAttributes { execute: true, ..Default::default() },
         };
-        let handle = CodeExecuter::execute(&code).expect("execution failed");
+        let handle = CodeExecuter::execute(&code, &Default::default()).expect("execution failed");
         let state = loop {
             let state = handle.state();
             if state.status.is_finished() {
@@ -178,9 +359,91 @@ echo 'bye'"
         let code = Code {
             contents,
             language: CodeLanguage::Shell("sh".into()),
+            language_name: "sh".into(),
             attributes: CodeAttributes { execute: false, ..Default::default() },
         };
-        let result = CodeExecuter::execute(&code);
+        let result = CodeExecuter::execute(&code, &Default::default());
         assert!(result.is_err());
     }
+
+    #[test]
+    fn sleeper_times_out() {
+        let _guard = PROCESS_TESTS.read().unwrap_or_else(PoisonError::into_inner);
+        let code = make_code("echo 'zzz'\nsleep 30");
+        let policy = ExecutionPolicy { timeout: Duration::from_millis(200), ..Default::default() };
+        let start = Instant::now();
+        let handle = CodeExecuter::execute(&code, &policy).expect("execution failed");
+        let state = wait_for_completion(handle);
+        assert_eq!(state.status, ProcessStatus::TimedOut { after: policy.timeout });
+        assert_eq!(state.output, &["zzz"]);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn block_timeout_overrides_policy() {
+        let _guard = PROCESS_TESTS.read().unwrap_or_else(PoisonError::into_inner);
+        let mut code = make_code("sleep 30");
+        code.attributes.timeout_seconds = Some(1);
+        let handle = CodeExecuter::execute(&code, &Default::default()).expect("execution failed");
+        let state = wait_for_completion(handle);
+        assert_eq!(state.status, ProcessStatus::TimedOut { after: Duration::from_secs(1) });
+    }
+
+    #[test]
+    fn spammer_output_capped() {
+        let _guard = PROCESS_TESTS.read().unwrap_or_else(PoisonError::into_inner);
+        let code = make_code("while true; do echo 'spam spam spam'; done");
+        let policy = ExecutionPolicy { max_output_bytes: 100, ..Default::default() };
+        let handle = CodeExecuter::execute(&code, &policy).expect("execution failed");
+        let state = wait_for_completion(handle);
+        assert_eq!(state.status, ProcessStatus::OutputLimitExceeded { bytes: 100 });
+        let total_bytes: usize = state.output.iter().map(|line| line.len() + 1).sum();
+        assert!(total_bytes <= 100, "captured {total_bytes} bytes");
+    }
+
+    #[test]
+    fn long_line_output_capped() {
+        let _guard = PROCESS_TESTS.read().unwrap_or_else(PoisonError::into_inner);
+        let code = make_code("while true; do printf 'spam'; done");
+        let policy = ExecutionPolicy { max_output_bytes: 100, ..Default::default() };
+        let handle = CodeExecuter::execute(&code, &policy).expect("execution failed");
+        let state = wait_for_completion(handle);
+        assert_eq!(state.status, ProcessStatus::OutputLimitExceeded { bytes: 100 });
+        assert!(state.output.is_empty(), "{:?}", state.output);
+    }
+
+    #[test]
+    fn invalid_utf8_output() {
+        let _guard = PROCESS_TESTS.read().unwrap_or_else(PoisonError::into_inner);
+        let code = make_code("printf 'a\\377b\\nafter\\n'");
+        let handle = CodeExecuter::execute(&code, &Default::default()).expect("execution failed");
+        let state = wait_for_completion(handle);
+        assert_eq!(state.status, ProcessStatus::Success);
+        assert_eq!(state.output, &["a\u{FFFD}b", "after"]);
+    }
+
+    #[test]
+    fn forker_children_killed() {
+        let _guard = PROCESS_TESTS.read().unwrap_or_else(PoisonError::into_inner);
+        // The background process keeps stdout open so this would only finish once it exits if it
+        // wasn't killed along with its parent.
+        let code = make_code("sleep 30 &\necho 'forked'");
+        let start = Instant::now();
+        let handle = CodeExecuter::execute(&code, &Default::default()).expect("execution failed");
+        let state = wait_for_completion(handle);
+        assert_eq!(state.status, ProcessStatus::Success);
+        assert_eq!(state.output, &["forked"]);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restricted_environment() {
+        let _guard = PROCESS_TESTS.read().unwrap_or_else(PoisonError::into_inner);
+        let code = make_code("echo \"${HOME:-unset}\"");
+        let policy = ExecutionPolicy { environment: Some(vec!["PATH".into()]), ..Default::default() };
+        let handle = CodeExecuter::execute(&code, &policy).expect("execution failed");
+        let state = wait_for_completion(handle);
+        assert_eq!(state.output, &["unset"]);
+    }
 }
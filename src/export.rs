@@ -7,6 +7,7 @@ use crate::{
     },
     presentation::{Presentation, RenderOperation},
     processing::builder::{BuildError, PresentationBuilder, PresentationBuilderOptions, Themes},
+    render::{capabilities::CellSize, properties::RenderDimensions},
     tools::{ExecutionError, ThirdPartyTools},
     typst::TypstRender,
     MarkdownParser, PresentationTheme, Resources,
@@ -31,6 +32,8 @@ pub struct Exporter<'a> {
     typst: TypstRender,
     themes: Themes,
     options: PresentationBuilderOptions,
+    cell_size: Option<CellSize>,
+    dimensions: RenderDimensions,
 }
 
 impl<'a> Exporter<'a> {
@@ -43,7 +46,20 @@ impl<'a> Exporter<'a> {
         themes: Themes,
         options: PresentationBuilderOptions,
     ) -> Self {
-        Self { parser, default_theme, resources, typst, themes, options }
+        let dimensions = Default::default();
+        Self { parser, default_theme, resources, typst, themes, options, cell_size: None, dimensions }
+    }
+
+    /// Use a specific cell size when capturing the presentation.
+    pub fn with_cell_size(mut self, cell_size: Option<CellSize>) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Use specific dimensions when capturing the presentation rather than the terminal's.
+    pub fn with_dimensions(mut self, dimensions: RenderDimensions) -> Self {
+        self.dimensions = dimensions;
+        self
     }
 
     /// Export the given presentation into PDF.
@@ -53,7 +69,7 @@ impl<'a> Exporter<'a> {
         Self::validate_exporter_version()?;
 
         let metadata = self.generate_metadata(presentation_path)?;
-        Self::execute_exporter(metadata, extra_args)?;
+        Self::execute_exporter(metadata, &self.dimensions, extra_args)?;
         Ok(())
     }
 
@@ -93,19 +109,29 @@ impl<'a> Exporter<'a> {
         let images = Self::build_image_metadata(&mut presentation)?;
         Self::validate_theme_colors(&presentation)?;
         let commands = Self::build_capture_commands(presentation);
-        let metadata = ExportMetadata { commands, presentation_path: path, images };
+        let metadata = ExportMetadata { commands, presentation_path: path, images, cell_size: self.cell_size };
         Ok(metadata)
     }
 
-    fn execute_exporter(metadata: ExportMetadata, extra_args: &[&str]) -> Result<(), ExportError> {
+    fn execute_exporter(
+        metadata: ExportMetadata,
+        dimensions: &RenderDimensions,
+        extra_args: &[&str],
+    ) -> Result<(), ExportError> {
         let presenterm_path = env::current_exe().map_err(ExportError::Io)?;
         let presenterm_path = presenterm_path.display().to_string();
         let presentation_path = metadata.presentation_path.display().to_string();
-        let metadata = serde_json::to_vec(&metadata).expect("serialization failed");
+        let serialized_metadata = serde_json::to_vec(&metadata).expect("serialization failed");
         let mut args = vec![&presenterm_path, "--export"];
+        // The presentation must be laid out in the same dimensions it's captured in. These are only passed in as an
+        // argument since the exporter doesn't know about them.
+        let dimensions_arg = dimensions.to_string();
+        if !dimensions.is_terminal() {
+            args.extend(["--export-dimensions", &dimensions_arg]);
+        }
         args.extend(extra_args);
         args.push(&presentation_path);
-        ThirdPartyTools::presenterm_export(&args).stdin(metadata).run()?;
+        ThirdPartyTools::presenterm_export(&args).stdin(serialized_metadata).run()?;
         Ok(())
     }
 
@@ -213,6 +239,8 @@ pub struct ExportMetadata {
     presentation_path: PathBuf,
     images: Vec<ImageMetadata>,
     commands: Vec<CaptureCommand>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cell_size: Option<CellSize>,
 }
 
 /// Metadata about an image.
@@ -0,0 +1,352 @@
+use super::source::Command;
+use crate::outline::OutlineFormat;
+use clap::ValueEnum;
+use std::{fmt, path::PathBuf, str::FromStr};
+
+// The names of all the commands, used to suggest one when an unknown one is used.
+const COMMAND_NAMES: &[&str] = &[
+    "quit", "q", "first", "last", "next", "previous", "prev", "reload", "index", "bindings", "glossary", "blank",
+    "details", "focus", "exec", "preview", "theme", "set", "export",
+];
+
+/// A command typed in the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum LineCommand {
+    /// A command that can also be triggered via key bindings.
+    Command(Command),
+
+    /// Switch to another theme.
+    Theme(String),
+
+    /// Turn an option on or off.
+    Set { option: LineOption, enabled: bool },
+
+    /// Export the presentation to a file or directory.
+    Export { format: ExportFormat, path: PathBuf },
+}
+
+impl FromStr for LineCommand {
+    type Err = CommandLineError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let (name, arguments) = split_word(line);
+        if name.is_empty() {
+            return Err(CommandLineError::Empty);
+        }
+        if name.chars().all(|c| c.is_ascii_digit()) {
+            expect_no_arguments(name, arguments)?;
+            return match name.parse::<u32>() {
+                Ok(number) if number > 0 => Ok(Self::Command(Command::GoToSlide(number))),
+                _ => Err(CommandLineError::InvalidSlide(name.into())),
+            };
+        }
+        let command = match name {
+            "q" | "quit" => Command::Exit,
+            "first" => Command::FirstSlide,
+            "last" => Command::LastSlide,
+            "next" => Command::Next,
+            "prev" | "previous" => Command::Previous,
+            "reload" => Command::HardReload,
+            "index" => Command::ToggleSlideIndex,
+            "bindings" => Command::ToggleKeyBindingsConfig,
+            "glossary" => Command::ToggleGlossary,
+            "blank" => Command::ToggleBlank,
+            "details" => Command::ToggleDetails,
+            "focus" => Command::FocusCode,
+            "exec" => Command::RenderWidgets,
+            "preview" => Command::CyclePreviewDimensions,
+            "theme" => return Self::parse_theme(arguments),
+            "set" => return Self::parse_set(arguments),
+            "export" => return Self::parse_export(arguments),
+            _ => {
+                let suggestion = closest_match(name, COMMAND_NAMES.iter().copied()).map(String::from);
+                return Err(CommandLineError::UnknownCommand { command: name.into(), suggestion });
+            }
+        };
+        expect_no_arguments(name, arguments)?;
+        Ok(Self::Command(command))
+    }
+}
+
+impl LineCommand {
+    fn parse_theme(arguments: &str) -> Result<Self, CommandLineError> {
+        if arguments.is_empty() {
+            return Err(CommandLineError::MissingArgument { command: "theme", expected: "a theme name" });
+        }
+        Ok(Self::Theme(arguments.into()))
+    }
+
+    fn parse_set(arguments: &str) -> Result<Self, CommandLineError> {
+        let (option, value) = split_word(arguments);
+        if option.is_empty() {
+            return Err(CommandLineError::MissingArgument { command: "set", expected: "an option name" });
+        }
+        let option = option.parse()?;
+        let enabled = match value {
+            "" | "on" | "true" => true,
+            "off" | "false" => false,
+            other => return Err(CommandLineError::InvalidValue(other.into())),
+        };
+        Ok(Self::Set { option, enabled })
+    }
+
+    fn parse_export(arguments: &str) -> Result<Self, CommandLineError> {
+        let (format, path) = split_word(arguments);
+        if format.is_empty() {
+            return Err(CommandLineError::MissingArgument { command: "export", expected: "a format and a path" });
+        }
+        let format = match format {
+            "html" => ExportFormat::Html,
+            format => match OutlineFormat::from_str(format, true) {
+                Ok(format) => ExportFormat::Outline(format),
+                Err(_) => {
+                    let names = ExportFormat::names();
+                    let suggestion = closest_match(format, names.iter().map(String::as_str)).map(String::from);
+                    return Err(CommandLineError::UnknownFormat { format: format.into(), suggestion });
+                }
+            },
+        };
+        if path.is_empty() {
+            return Err(CommandLineError::MissingArgument { command: "export", expected: "a path" });
+        }
+        Ok(Self::Export { format, path: path.into() })
+    }
+}
+
+/// A format the presentation can be exported to via the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ExportFormat {
+    /// The presentation's outline.
+    Outline(OutlineFormat),
+
+    /// A reveal.js presentation, like `--export-reveal-js` generates.
+    Html,
+}
+
+impl ExportFormat {
+    fn names() -> Vec<String> {
+        let outlines = OutlineFormat::value_variants().iter().filter_map(|format| format.to_possible_value());
+        let mut names: Vec<_> = outlines.map(|value| value.get_name().to_string()).collect();
+        names.push("html".into());
+        names
+    }
+}
+
+/// An option that can be changed via the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LineOption {
+    Footer,
+    IncrementalLists,
+    ImplicitSlideEnds,
+    EndSlideShorthand,
+}
+
+impl LineOption {
+    const NAMES: &'static [&'static str] =
+        &["footer", "incremental_lists", "implicit_slide_ends", "end_slide_shorthand"];
+}
+
+impl FromStr for LineOption {
+    type Err = CommandLineError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "footer" => Ok(Self::Footer),
+            "incremental_lists" => Ok(Self::IncrementalLists),
+            "implicit_slide_ends" => Ok(Self::ImplicitSlideEnds),
+            "end_slide_shorthand" => Ok(Self::EndSlideShorthand),
+            _ => {
+                let suggestion = closest_match(name, Self::NAMES.iter().copied()).map(String::from);
+                Err(CommandLineError::UnknownOption { option: name.into(), suggestion })
+            }
+        }
+    }
+}
+
+/// An error when parsing a command typed in the command line.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub(crate) enum CommandLineError {
+    #[error("no command given")]
+    Empty,
+
+    #[error("unknown command '{command}'{}", Hint(.suggestion))]
+    UnknownCommand { command: String, suggestion: Option<String> },
+
+    #[error("unknown option '{option}'{}", Hint(.suggestion))]
+    UnknownOption { option: String, suggestion: Option<String> },
+
+    #[error("unknown export format '{format}'{}", Hint(.suggestion))]
+    UnknownFormat { format: String, suggestion: Option<String> },
+
+    #[error("unknown theme '{theme}'{}", Hint(.suggestion))]
+    UnknownTheme { theme: String, suggestion: Option<String> },
+
+    #[error("'{command}' expects {expected}")]
+    MissingArgument { command: &'static str, expected: &'static str },
+
+    #[error("'{0}' doesn't take any arguments")]
+    UnexpectedArguments(String),
+
+    #[error("invalid slide number '{0}'")]
+    InvalidSlide(String),
+
+    #[error("invalid value '{0}', expected 'on' or 'off'")]
+    InvalidValue(String),
+}
+
+// Displays a suggestion, if there's any, at the end of an error message.
+struct Hint<'a>(&'a Option<String>);
+
+impl fmt::Display for Hint<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(suggestion) => write!(f, ", did you mean '{suggestion}'?"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Find the candidate that's closest to the given input, if any of them is close enough.
+///
+/// This is meant to catch typos so candidates are only considered if they're at most 2 edits
+/// away from the input.
+pub(crate) fn closest_match<'a>(input: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let input_length = input.chars().count();
+    candidates
+        .map(|candidate| (edit_distance(input, candidate), candidate))
+        .filter(|(distance, _)| *distance <= 2 && *distance < input_length)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// The levenshtein distance between two strings.
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<_> = right.chars().collect();
+    let mut previous: Vec<_> = (0..=right.len()).collect();
+    for (row, left_char) in left.chars().enumerate() {
+        let mut current = vec![row + 1];
+        for (column, right_char) in right.iter().enumerate() {
+            let substitution = previous[column] + usize::from(left_char != *right_char);
+            let value = substitution.min(previous[column + 1] + 1).min(current[column] + 1);
+            current.push(value);
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+// Split the first word in a line from the rest of it.
+fn split_word(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    match line.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (line, ""),
+    }
+}
+
+fn expect_no_arguments(command: &str, arguments: &str) -> Result<(), CommandLineError> {
+    if arguments.is_empty() { Ok(()) } else { Err(CommandLineError::UnexpectedArguments(command.into())) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::quit("q", Command::Exit)]
+    #[case::quit_long("quit", Command::Exit)]
+    #[case::slide("42", Command::GoToSlide(42))]
+    #[case::slide_padded("  7 ", Command::GoToSlide(7))]
+    #[case::first("first", Command::FirstSlide)]
+    #[case::last("last", Command::LastSlide)]
+    #[case::next("next", Command::Next)]
+    #[case::previous("prev", Command::Previous)]
+    #[case::reload("reload", Command::HardReload)]
+    #[case::index("index", Command::ToggleSlideIndex)]
+    #[case::details("details", Command::ToggleDetails)]
+    fn commands(#[case] line: &str, #[case] expected: Command) {
+        assert_eq!(line.parse(), Ok(LineCommand::Command(expected)));
+    }
+
+    #[test]
+    fn all_commands_known() {
+        for name in COMMAND_NAMES {
+            let result = name.parse::<LineCommand>();
+            assert!(
+                !matches!(result, Err(CommandLineError::UnknownCommand { .. })),
+                "{name} is listed but not handled"
+            );
+        }
+    }
+
+    #[test]
+    fn theme() {
+        assert_eq!("theme catppuccin-mocha".parse(), Ok(LineCommand::Theme("catppuccin-mocha".into())));
+    }
+
+    #[rstest]
+    #[case::off("set footer off", LineOption::Footer, false)]
+    #[case::on("set footer on", LineOption::Footer, true)]
+    #[case::implicit_on("set incremental_lists", LineOption::IncrementalLists, true)]
+    #[case::boolean("set implicit_slide_ends false", LineOption::ImplicitSlideEnds, false)]
+    #[case::spaces("  set   end_slide_shorthand   true ", LineOption::EndSlideShorthand, true)]
+    fn set(#[case] line: &str, #[case] option: LineOption, #[case] enabled: bool) {
+        assert_eq!(line.parse(), Ok(LineCommand::Set { option, enabled }));
+    }
+
+    #[rstest]
+    #[case::markdown("export markdown outline.md", ExportFormat::Outline(OutlineFormat::Markdown), "outline.md")]
+    #[case::plain("export plain my outline.txt", ExportFormat::Outline(OutlineFormat::Plain), "my outline.txt")]
+    #[case::html("export html slides", ExportFormat::Html, "slides")]
+    fn export(#[case] line: &str, #[case] format: ExportFormat, #[case] path: &str) {
+        assert_eq!(line.parse(), Ok(LineCommand::Export { format, path: path.into() }));
+    }
+
+    #[rstest]
+    #[case::empty("   ", CommandLineError::Empty)]
+    #[case::zero("0", CommandLineError::InvalidSlide("0".into()))]
+    #[case::overflow("99999999999", CommandLineError::InvalidSlide("99999999999".into()))]
+    #[case::slide_arguments("3 4", CommandLineError::UnexpectedArguments("3".into()))]
+    #[case::quit_arguments("q now", CommandLineError::UnexpectedArguments("q".into()))]
+    #[case::theme("theme", CommandLineError::MissingArgument { command: "theme", expected: "a theme name" })]
+    #[case::set("set", CommandLineError::MissingArgument { command: "set", expected: "an option name" })]
+    #[case::set_value("set footer maybe", CommandLineError::InvalidValue("maybe".into()))]
+    #[case::export_path("export plain", CommandLineError::MissingArgument { command: "export", expected: "a path" })]
+    fn invalid(#[case] line: &str, #[case] expected: CommandLineError) {
+        assert_eq!(line.parse::<LineCommand>(), Err(expected));
+    }
+
+    #[rstest]
+    #[case::typo("thme dark", "unknown command 'thme', did you mean 'theme'?")]
+    #[case::swapped("qiut", "unknown command 'qiut', did you mean 'quit'?")]
+    #[case::nothing_close("potato", "unknown command 'potato'")]
+    #[case::option("set foter off", "unknown option 'foter', did you mean 'footer'?")]
+    #[case::format("export pdf out.pdf", "unknown export format 'pdf'")]
+    #[case::html_typo("export htlm out", "unknown export format 'htlm', did you mean 'html'?")]
+    #[case::format_typo("export plian out.txt", "unknown export format 'plian', did you mean 'plain'?")]
+    fn error_messages(#[case] line: &str, #[case] expected: &str) {
+        let error = line.parse::<LineCommand>().expect_err("parse succeeded");
+        assert_eq!(error.to_string(), expected);
+    }
+
+    #[rstest]
+    #[case::same("first", "first", 0)]
+    #[case::insertion("frst", "first", 1)]
+    #[case::substitution("lest", "last", 1)]
+    #[case::empty("", "last", 4)]
+    #[case::unicode("日本", "日", 1)]
+    fn distance(#[case] left: &str, #[case] right: &str, #[case] expected: usize) {
+        assert_eq!(edit_distance(left, right), expected);
+        assert_eq!(edit_distance(right, left), expected);
+    }
+
+    #[test]
+    fn closest() {
+        let candidates = ["dark", "light", "terminal-dark"];
+        assert_eq!(closest_match("drak", candidates.into_iter()), Some("dark"));
+        assert_eq!(closest_match("terminal-drak", candidates.into_iter()), Some("terminal-dark"));
+        // A single character is too short to be a typo of anything.
+        assert_eq!(closest_match("x", ["q"].into_iter()), None);
+    }
+}
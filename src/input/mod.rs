@@ -1,3 +1,5 @@
+pub(crate) mod command_line;
 pub(crate) mod fs;
+pub(crate) mod prompt;
 pub(crate) mod source;
 pub(crate) mod user;
@@ -0,0 +1,265 @@
+use crate::{markdown::elements::Text, style::TextStyle};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// What happened after a key was pressed in a [Prompt].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PromptAction {
+    /// The line is still being edited.
+    Continue,
+
+    /// The prompt was dismissed without submitting anything.
+    Cancel,
+
+    /// The line was submitted.
+    Submit(String),
+}
+
+/// A single line prompt.
+///
+/// This supports moving the cursor around the line, deleting characters, and going through the
+/// lines submitted before it.
+#[derive(Debug, Default)]
+pub(crate) struct Prompt {
+    input: Vec<char>,
+    cursor: usize,
+    history: Vec<String>,
+    history_index: Option<usize>,
+}
+
+impl Prompt {
+    /// Handle a key press.
+    pub(crate) fn handle_key(&mut self, event: KeyEvent) -> PromptAction {
+        let control = event.modifiers.contains(KeyModifiers::CONTROL);
+        match event.code {
+            KeyCode::Esc => return self.cancel(),
+            KeyCode::Char('c') if control => return self.cancel(),
+            // Like in vim, deleting past the start of an empty line dismisses the prompt.
+            KeyCode::Backspace if self.input.is_empty() => return self.cancel(),
+            KeyCode::Enter => return self.submit(),
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.input.remove(self.cursor);
+            }
+            KeyCode::Delete if self.cursor < self.input.len() => {
+                self.input.remove(self.cursor);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.input.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::Char('a') if control => self.cursor = 0,
+            KeyCode::End => self.cursor = self.input.len(),
+            KeyCode::Char('e') if control => self.cursor = self.input.len(),
+            KeyCode::Char('u') if control => {
+                self.input.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            KeyCode::Up => self.history_previous(),
+            KeyCode::Down => self.history_next(),
+            KeyCode::Char(c) if !control => {
+                self.input.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            _ => (),
+        };
+        PromptAction::Continue
+    }
+
+    /// The text in the line.
+    pub(crate) fn text(&self) -> String {
+        self.input.iter().collect()
+    }
+
+    /// The text to display for this prompt, starting with the given prefix.
+    ///
+    /// The character under the cursor is underlined.
+    pub(crate) fn display(&self, prefix: &str) -> Vec<Text> {
+        let before: String = self.input[..self.cursor].iter().collect();
+        let under = self.input.get(self.cursor).copied().unwrap_or(' ');
+        let after: String = self.input.iter().skip(self.cursor + 1).collect();
+        vec![
+            Text::from(format!("{prefix}{before}")),
+            Text::new(under.to_string(), TextStyle::default().underlined()),
+            Text::from(after),
+        ]
+    }
+
+    fn cancel(&mut self) -> PromptAction {
+        self.reset();
+        PromptAction::Cancel
+    }
+
+    fn submit(&mut self) -> PromptAction {
+        let line = self.text();
+        let line = line.trim();
+        if !line.is_empty() && self.history.last().map(String::as_str) != Some(line) {
+            self.history.push(line.to_string());
+        }
+        self.reset();
+        PromptAction::Submit(line.to_string())
+    }
+
+    fn reset(&mut self) {
+        self.input.clear();
+        self.cursor = 0;
+        self.history_index = None;
+    }
+
+    fn history_previous(&mut self) {
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.show_history_entry(Some(index));
+    }
+
+    fn history_next(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => self.show_history_entry(Some(index + 1)),
+            // Going past the last entry goes back to an empty line.
+            Some(_) => self.show_history_entry(None),
+            None => (),
+        };
+    }
+
+    fn show_history_entry(&mut self, index: Option<usize>) {
+        self.input = index.map(|index| self.history[index].chars().collect()).unwrap_or_default();
+        self.cursor = self.input.len();
+        self.history_index = index;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::event::{KeyEventKind, KeyEventState};
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent { code, modifiers: KeyModifiers::empty(), kind: KeyEventKind::Press, state: KeyEventState::NONE }
+    }
+
+    fn control(c: char) -> KeyEvent {
+        KeyEvent { modifiers: KeyModifiers::CONTROL, ..key(KeyCode::Char(c)) }
+    }
+
+    fn type_text(prompt: &mut Prompt, text: &str) {
+        for c in text.chars() {
+            assert_eq!(prompt.handle_key(key(KeyCode::Char(c))), PromptAction::Continue);
+        }
+    }
+
+    #[test]
+    fn editing() {
+        let mut prompt = Prompt::default();
+        type_text(&mut prompt, "thme");
+        prompt.handle_key(key(KeyCode::Left));
+        prompt.handle_key(key(KeyCode::Left));
+        type_text(&mut prompt, "e");
+        assert_eq!(prompt.text(), "theme");
+        assert_eq!(prompt.cursor, 3);
+
+        prompt.handle_key(key(KeyCode::End));
+        type_text(&mut prompt, " darkk");
+        prompt.handle_key(key(KeyCode::Backspace));
+        prompt.handle_key(key(KeyCode::Home));
+        prompt.handle_key(key(KeyCode::Delete));
+        assert_eq!(prompt.text(), "heme dark");
+        assert_eq!(prompt.cursor, 0);
+
+        // The cursor can't go past either end of the line.
+        prompt.handle_key(key(KeyCode::Left));
+        assert_eq!(prompt.cursor, 0);
+        prompt.handle_key(control('e'));
+        prompt.handle_key(key(KeyCode::Right));
+        assert_eq!(prompt.cursor, 9);
+    }
+
+    #[test]
+    fn clear_before_cursor() {
+        let mut prompt = Prompt::default();
+        type_text(&mut prompt, "set footer");
+        for _ in 0..6 {
+            prompt.handle_key(key(KeyCode::Left));
+        }
+        prompt.handle_key(control('u'));
+        assert_eq!(prompt.text(), "footer");
+        assert_eq!(prompt.cursor, 0);
+    }
+
+    #[test]
+    fn wide_characters() {
+        let mut prompt = Prompt::default();
+        type_text(&mut prompt, "日本");
+        prompt.handle_key(key(KeyCode::Backspace));
+        assert_eq!(prompt.text(), "日");
+        assert_eq!(prompt.cursor, 1);
+    }
+
+    #[test]
+    fn submit() {
+        let mut prompt = Prompt::default();
+        type_text(&mut prompt, " 42 ");
+        assert_eq!(prompt.handle_key(key(KeyCode::Enter)), PromptAction::Submit("42".into()));
+        assert_eq!(prompt.text(), "");
+        assert_eq!(prompt.cursor, 0);
+    }
+
+    #[test]
+    fn cancel() {
+        let mut prompt = Prompt::default();
+        type_text(&mut prompt, "q");
+        assert_eq!(prompt.handle_key(key(KeyCode::Esc)), PromptAction::Cancel);
+        assert_eq!(prompt.text(), "");
+
+        // Deleting a character is fine but deleting on an empty line dismisses it.
+        type_text(&mut prompt, "q");
+        assert_eq!(prompt.handle_key(key(KeyCode::Backspace)), PromptAction::Continue);
+        assert_eq!(prompt.handle_key(key(KeyCode::Backspace)), PromptAction::Cancel);
+        assert_eq!(prompt.handle_key(control('c')), PromptAction::Cancel);
+    }
+
+    #[test]
+    fn display() {
+        let mut prompt = Prompt::default();
+        type_text(&mut prompt, "last");
+        let underlined = TextStyle::default().underlined();
+        let expected = vec![Text::from(":last"), Text::new(" ", underlined.clone()), Text::from("")];
+        assert_eq!(prompt.display(":"), expected);
+
+        prompt.handle_key(key(KeyCode::Home));
+        let expected = vec![Text::from(":"), Text::new("l", underlined), Text::from("ast")];
+        assert_eq!(prompt.display(":"), expected);
+    }
+
+    #[test]
+    fn history() {
+        let mut prompt = Prompt::default();
+        for line in ["first", "last", "last", "", "42"] {
+            type_text(&mut prompt, line);
+            prompt.handle_key(key(KeyCode::Enter));
+        }
+        // Nothing newer than the line being edited.
+        prompt.handle_key(key(KeyCode::Down));
+        assert_eq!(prompt.text(), "");
+
+        // Repeated and empty lines aren't stored.
+        let mut lines = Vec::new();
+        for _ in 0..4 {
+            prompt.handle_key(key(KeyCode::Up));
+            lines.push(prompt.text());
+        }
+        assert_eq!(lines, &["42", "last", "first", "first"]);
+        assert_eq!(prompt.cursor, 5);
+
+        prompt.handle_key(key(KeyCode::Down));
+        assert_eq!(prompt.text(), "last");
+        prompt.handle_key(key(KeyCode::Down));
+        prompt.handle_key(key(KeyCode::Down));
+        assert_eq!(prompt.text(), "");
+
+        // Entries can be edited before submitting them.
+        prompt.handle_key(key(KeyCode::Up));
+        type_text(&mut prompt, "0");
+        assert_eq!(prompt.handle_key(key(KeyCode::Enter)), PromptAction::Submit("420".into()));
+    }
+}
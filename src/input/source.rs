@@ -3,6 +3,7 @@ use super::{
     user::{CommandKeyBindings, KeyBindingsValidationError, UserInput},
 };
 use crate::custom::KeyBindingsConfig;
+use crossterm::event::KeyEvent;
 use serde::Deserialize;
 use std::{io, path::PathBuf, time::Duration};
 use strum::EnumDiscriminants;
@@ -29,13 +30,19 @@ impl CommandSource {
 
     /// Try to get the next command.
     ///
-    /// This attempts to get a command and returns `Ok(None)` on timeout.
-    pub(crate) fn try_next_command(&mut self) -> io::Result<Option<Command>> {
-        if let Some(command) = self.user_input.poll_next_command(Duration::from_millis(250))? {
+    /// This waits at most `timeout` for a command and returns `Ok(None)` if none shows up.
+    pub(crate) fn try_next_command(&mut self, timeout: Duration) -> io::Result<Option<Command>> {
+        if let Some(command) = self.user_input.poll_next_command(timeout)? {
             return Ok(Some(command));
         };
         if self.watcher.has_modifications()? { Ok(Some(Command::Reload)) } else { Ok(None) }
     }
+
+    /// Set whether every key press should be captured as [Command::PromptInput] rather than
+    /// being matched against the key bindings.
+    pub(crate) fn capture_keys(&mut self, capture: bool) {
+        self.user_input.capture_keys(capture);
+    }
 }
 
 /// A command.
@@ -84,4 +91,50 @@ pub(crate) enum Command {
 
     /// Hide the currently open modal, if any.
     CloseModal,
+
+    /// Toggle a blank screen.
+    ToggleBlank,
+
+    /// Preview the current slide at the next configured dimensions.
+    CyclePreviewDimensions,
+
+    /// Toggle the glossary modal.
+    ToggleGlossary,
+
+    /// Display the next code block in the current slide on its own.
+    FocusCode,
+
+    /// Expand or collapse the `<details>` blocks in the current slide.
+    ToggleDetails,
+
+    /// Temporarily reveal or hide the text redacted in the presentation.
+    ToggleRedactions,
+
+    /// Open the command line.
+    OpenCommandLine,
+
+    /// A key was pressed while the command line is open.
+    PromptInput(KeyEvent),
+
+    /// The terminal gained or lost focus.
+    FocusChanged(bool),
+}
+
+impl Command {
+    /// Whether this command can be used in kiosk mode.
+    ///
+    /// Only commands that move through the presentation or that are triggered internally are allowed.
+    pub(crate) fn allowed_in_kiosk(&self) -> bool {
+        matches!(
+            self,
+            Self::Redraw
+                | Self::Next
+                | Self::Previous
+                | Self::FirstSlide
+                | Self::LastSlide
+                | Self::Exit
+                | Self::Reload
+                | Self::FocusChanged(_)
+        )
+    }
 }
@@ -9,11 +9,18 @@ use std::{fmt, io, iter, mem, str::FromStr, time::Duration};
 pub struct UserInput {
     bindings: CommandKeyBindings,
     events: Vec<KeyEvent>,
+    capture: bool,
 }
 
 impl UserInput {
     pub fn new(bindings: CommandKeyBindings) -> Self {
-        Self { bindings, events: Vec::new() }
+        Self { bindings, events: Vec::new(), capture: false }
+    }
+
+    /// Set whether key presses are emitted as they are rather than being matched against bindings.
+    pub(crate) fn capture_keys(&mut self, capture: bool) {
+        self.capture = capture;
+        self.events.clear();
     }
 
     /// Polls for the next input command coming from the keyboard.
@@ -27,11 +34,14 @@ impl UserInput {
         let (command, events) = match read()? {
             // Ignore release events
             Event::Key(event) if event.kind == KeyEventKind::Release => (None, events),
+            Event::Key(event) if self.capture => (Some(Command::PromptInput(event)), events),
             Event::Key(event) => {
                 events.push(event);
                 self.match_events(events)
             }
             Event::Resize(..) => (Some(Command::Redraw), events),
+            Event::FocusGained => (Some(Command::FocusChanged(true)), events),
+            Event::FocusLost => (Some(Command::FocusChanged(false)), events),
             _ => (None, vec![]),
         };
         self.events = events;
@@ -93,6 +103,16 @@ impl CommandKeyBindings {
             ToggleSlideIndex => Command::ToggleSlideIndex,
             ToggleKeyBindingsConfig => Command::ToggleKeyBindingsConfig,
             CloseModal => Command::CloseModal,
+            ToggleBlank => Command::ToggleBlank,
+            CyclePreviewDimensions => Command::CyclePreviewDimensions,
+            ToggleGlossary => Command::ToggleGlossary,
+            FocusCode => Command::FocusCode,
+            ToggleDetails => Command::ToggleDetails,
+            ToggleRedactions => Command::ToggleRedactions,
+            OpenCommandLine => Command::OpenCommandLine,
+            // Key presses are only captured while the command line is open and focus changes come
+            // from the terminal, so these are never bound.
+            PromptInput | FocusChanged => return InputAction::Reset,
         };
         InputAction::Emit(command)
     }
@@ -134,6 +154,13 @@ impl TryFrom<KeyBindingsConfig> for CommandKeyBindings {
             .chain(zip(CommandDiscriminants::ToggleKeyBindingsConfig, config.toggle_bindings))
             .chain(zip(CommandDiscriminants::RenderWidgets, config.execute_code))
             .chain(zip(CommandDiscriminants::CloseModal, config.close_modal))
+            .chain(zip(CommandDiscriminants::ToggleBlank, config.toggle_blank))
+            .chain(zip(CommandDiscriminants::CyclePreviewDimensions, config.cycle_preview_dimensions))
+            .chain(zip(CommandDiscriminants::ToggleGlossary, config.toggle_glossary))
+            .chain(zip(CommandDiscriminants::FocusCode, config.focus_code))
+            .chain(zip(CommandDiscriminants::ToggleDetails, config.toggle_details))
+            .chain(zip(CommandDiscriminants::ToggleRedactions, config.toggle_redactions))
+            .chain(zip(CommandDiscriminants::OpenCommandLine, config.command_line))
             .collect();
         Self::validate_conflicts(bindings.iter().map(|binding| &binding.0))?;
         Ok(Self { bindings })
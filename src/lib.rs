@@ -2,35 +2,82 @@
 //!
 //! This is not meant to be used as a crate!
 
+pub(crate) mod artifacts;
+pub(crate) mod builtin;
+pub(crate) mod cache;
 pub(crate) mod custom;
 pub(crate) mod demo;
 pub(crate) mod diff;
+pub(crate) mod events;
 pub(crate) mod execute;
 pub(crate) mod export;
 pub(crate) mod input;
 pub(crate) mod markdown;
 pub(crate) mod media;
+pub(crate) mod mermaid;
+pub(crate) mod outline;
+pub(crate) mod pattern;
+pub(crate) mod prefetch;
 pub(crate) mod presentation;
 pub(crate) mod presenter;
+pub(crate) mod print;
 pub(crate) mod processing;
 pub(crate) mod render;
 pub(crate) mod resource;
+pub(crate) mod reveal;
+pub(crate) mod schema;
+pub(crate) mod separators;
+#[cfg(feature = "serve")]
+pub(crate) mod serve;
+pub(crate) mod session;
+pub(crate) mod stats;
 pub(crate) mod style;
 pub(crate) mod theme;
 pub(crate) mod tools;
 pub(crate) mod typst;
 
 pub use crate::{
-    custom::{Config, ImageProtocol, ValidateOverflows},
+    artifacts::{ArtifactCollection, ArtifactStore},
+    custom::{
+        AutoTheme, Config, DefaultTheme, EndOfDeck, ImageProtocol, PreviewDimensions, SafeAreaMargin, SlowFramesConfig,
+        ValidateOverflows,
+    },
     demo::ThemesDemo,
+    events::{EventEmitter, EventRecord, OperationTime, PresentationEvent, SlideOverage, EVENTS_SCHEMA_VERSION},
+    execute::ExecutionPolicy,
     export::{ExportError, Exporter},
     input::source::CommandSource,
     markdown::parse::MarkdownParser,
     media::{graphics::GraphicsMode, printer::ImagePrinter, register::ImageRegistry},
-    presenter::{PresentMode, Presenter, PresenterOptions},
-    processing::builder::{PresentationBuilderOptions, Themes},
-    render::highlighting::{CodeHighlighter, HighlightThemeSet},
+    outline::{OutlineError, OutlineExporter, OutlineFormat},
+    pattern::TestPattern,
+    presentation::{SlideReference, TodoNote},
+    presenter::{PresentMode, PresentationError, Presenter, PresenterOptions},
+    print::SlidePrinter,
+    processing::{
+        builder::{PresentationBuilderOptions, Themes},
+        directives::DirectiveWarning,
+        end_screen::EndScreen,
+        hooks::{CodeBlockHook, HookContext, HookError, HookOutput, RenderHooks, StyledText},
+        split::{BudgetIssue, BudgetWarning, Overrun},
+        structure::StructureWarning,
+    },
+    render::{
+        background::TerminalBackground,
+        capabilities::{CellSize, CellSizePolicy},
+        highlighting::{CodeHighlighter, HighlightThemeSet},
+        properties::{InvalidRenderDimensions, RenderDimensions},
+        terminal::install_panic_hook,
+    },
     resource::Resources,
+    reveal::RevealExporter,
+    schema::{generate_schema, SchemaKind},
+    separators::SeparatorAnalyzer,
+    session::{SessionState, SessionStore},
+    stats::StatsCollector,
     theme::{LoadThemeError, PresentationTheme, PresentationThemeSet},
     typst::TypstRender,
 };
+
+#[cfg(feature = "serve")]
+pub use crate::serve::PreviewServer;
@@ -1,18 +1,31 @@
 use clap::{error::ErrorKind, CommandFactory, Parser};
 use comrak::Arena;
+use crossterm::tty::IsTty;
 use directories::ProjectDirs;
+#[cfg(feature = "serve")]
+use presenterm::PreviewServer;
 use presenterm::{
-    CommandSource, Config, Exporter, GraphicsMode, HighlightThemeSet, ImagePrinter, ImageProtocol, ImageRegistry,
-    LoadThemeError, MarkdownParser, PresentMode, PresentationBuilderOptions, PresentationTheme, PresentationThemeSet,
-    Presenter, PresenterOptions, Resources, Themes, ThemesDemo, TypstRender, ValidateOverflows,
+    generate_schema, install_panic_hook, ArtifactStore, AutoTheme, CellSize, CellSizePolicy, CommandSource, Config,
+    DefaultTheme, EndOfDeck, EndScreen, EventEmitter, ExecutionPolicy, Exporter, GraphicsMode, HighlightThemeSet,
+    ImagePrinter, ImageProtocol, ImageRegistry, LoadThemeError, MarkdownParser, OutlineExporter, OutlineFormat,
+    PresentMode, PresentationBuilderOptions, PresentationError, PresentationTheme, PresentationThemeSet, Presenter,
+    PresenterOptions, RenderDimensions, Resources, RevealExporter, SafeAreaMargin, SchemaKind, SeparatorAnalyzer,
+    SessionState, SessionStore, SlidePrinter, StatsCollector, TerminalBackground, TestPattern, Themes, ThemesDemo,
+    TypstRender, ValidateOverflows,
 };
 use std::{
-    env, io,
+    env, fs, io,
     path::{Path, PathBuf},
     rc::Rc,
+    time::{Duration, Instant, SystemTime},
 };
 
 const DEFAULT_THEME: &str = "dark";
+const BACKGROUND_DETECTION_TIMEOUT: Duration = Duration::from_millis(100);
+const CELL_SIZE_DETECTION_TIMEOUT: Duration = Duration::from_millis(100);
+
+// The exit code used when the terminal goes away while presenting, which is sysexits' `EX_IOERR`.
+const DISCONNECTED_EXIT_CODE: i32 = 74;
 
 /// Run slideshows from your terminal.
 #[derive(Parser)]
@@ -27,6 +40,34 @@ struct Cli {
     #[clap(short, long)]
     export_pdf: bool,
 
+    /// Export the presentation as a reveal.js presentation rather than displaying it.
+    #[clap(long, requires = "output")]
+    export_reveal_js: bool,
+
+    /// Serve the presentation as reveal.js over HTTP, reloading it in the browser every time it changes.
+    #[clap(long)]
+    serve: bool,
+
+    /// The address to listen on when using `--serve`.
+    #[clap(long, requires = "serve", default_value = "127.0.0.1")]
+    serve_host: String,
+
+    /// The port to listen on when using `--serve`.
+    #[clap(long, requires = "serve", default_value_t = 8000)]
+    serve_port: u16,
+
+    /// Print an outline of the presentation's slides rather than displaying it.
+    #[clap(long)]
+    export_outline: bool,
+
+    /// The format to use when exporting an outline.
+    #[clap(long, requires = "export_outline", default_value = "markdown")]
+    outline_format: OutlineFormat,
+
+    /// The directory to write the exported presentation to.
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+
     /// Generate the PDF metadata without generating the PDF itself.
     #[clap(long, hide = true)]
     generate_pdf_metadata: bool,
@@ -35,6 +76,10 @@ struct Cli {
     #[clap(long)]
     generate_config_file_schema: bool,
 
+    /// Generate a JSON schema for a theme, the configuration file, or a presentation's front matter.
+    #[clap(long, value_name = "KIND")]
+    generate_schema: Option<SchemaKind>,
+
     /// Run in export mode.
     #[clap(long, hide = true)]
     export: bool,
@@ -43,6 +88,18 @@ struct Cli {
     #[clap(short, long, default_value_t = false)]
     present: bool,
 
+    /// Run in kiosk mode: only display the slides' contents and only allow moving between slides.
+    #[clap(long)]
+    kiosk: bool,
+
+    /// Only allow the presentation to reference files within its own directory, and never execute code in it.
+    #[clap(long)]
+    sandbox: bool,
+
+    /// Resume the presentation at the slide it was at the last time it was displayed.
+    #[clap(long)]
+    resume: bool,
+
     /// The theme to use.
     #[clap(short, long)]
     theme: Option<String>,
@@ -51,10 +108,18 @@ struct Cli {
     #[clap(long)]
     list_themes: bool,
 
+    /// Display a test pattern to check that the whole screen is visible and adjust the safe area margin.
+    #[clap(long)]
+    test_pattern: bool,
+
     /// Display acknowledgements.
     #[clap(long, group = "target")]
     acknowledgements: bool,
 
+    /// Remove the cached images generated by tools like typst and mermaid.
+    #[clap(long, group = "target")]
+    clean_cache: bool,
+
     /// The image protocol to use.
     #[clap(long)]
     image_protocol: Option<ImageProtocol>,
@@ -63,6 +128,42 @@ struct Cli {
     #[clap(long)]
     validate_overflows: bool,
 
+    /// Display statistics about the presentation, like the estimated time it takes to go through each slide.
+    #[clap(long)]
+    stats: bool,
+
+    /// Fail if there are any TODO or FIXME comments left in the presentation, listing them.
+    #[clap(long)]
+    fail_on_todo: bool,
+
+    /// Rewrite every thematic break in the presentation as `---`, leaving everything else untouched.
+    #[clap(long)]
+    normalize_separators: bool,
+
+    /// Print this slide, with all of its pauses revealed, into the terminal and exit.
+    #[clap(long)]
+    print_slide: Option<usize>,
+
+    /// Include the footer when using `--print-slide`.
+    #[clap(long, requires = "print_slide")]
+    with_footer: bool,
+
+    /// The number of columns to lay out the slide in when using `--print-slide` instead of the terminal's width.
+    #[clap(long, requires = "print_slide", conflicts_with = "export_dimensions")]
+    cols: Option<u16>,
+
+    /// The size to lay out slides in when exporting or printing them, as `<columns>x<rows>`, e.g. `100x30`.
+    #[clap(long)]
+    export_dimensions: Option<RenderDimensions>,
+
+    /// The maximum number of times per second the screen is redrawn.
+    #[clap(long, default_value_t = 60, value_parser = clap::value_parser!(u16).range(1..))]
+    fps: u16,
+
+    /// Write newline delimited JSON events about the presentation's progress into this file or FIFO.
+    #[clap(long)]
+    emit_events: Option<PathBuf>,
+
     /// The path to the configuration file.
     #[clap(short, long)]
     config_file: Option<String>,
@@ -98,11 +199,39 @@ fn load_customizations(config_file_path: Option<PathBuf>) -> Result<(Config, The
     Ok((config, themes))
 }
 
+fn session_store() -> Option<SessionStore> {
+    let directory = match env::var("XDG_STATE_HOME") {
+        Ok(path) => Path::new(&path).join("presenterm"),
+        Err(_) => {
+            let project_dirs = ProjectDirs::from("", "", "presenterm")?;
+            // Only some platforms have a state directory.
+            project_dirs.state_dir().unwrap_or(project_dirs.data_local_dir()).into()
+        }
+    };
+    Some(SessionStore::new(directory))
+}
+
+fn artifacts_directory() -> Option<PathBuf> {
+    let directory = match env::var("XDG_CACHE_HOME") {
+        Ok(path) => Path::new(&path).join("presenterm"),
+        Err(_) => ProjectDirs::from("", "", "presenterm")?.cache_dir().into(),
+    };
+    Some(directory.join("artifacts"))
+}
+
+fn artifact_store(config: &Config) -> Option<ArtifactStore> {
+    let max_size = config.defaults.artifact_cache_mb as u64 * 1024 * 1024;
+    if max_size == 0 {
+        return None;
+    }
+    artifacts_directory().map(|directory| ArtifactStore::new(directory, max_size))
+}
+
 fn load_themes(config_path: &Path) -> Result<Themes, Box<dyn std::error::Error>> {
     let themes_path = config_path.join("themes");
 
     let mut highlight_themes = HighlightThemeSet::default();
-    highlight_themes.register_from_directory(&themes_path.join("highlighting"))?;
+    highlight_themes.register_from_directory(themes_path.join("highlighting"))?;
 
     let mut presentation_themes = PresentationThemeSet::default();
     let register_result = presentation_themes.register_from_directory(&themes_path);
@@ -129,12 +258,38 @@ fn make_builder_options(config: &Config, mode: &PresentMode, force_default_theme
         end_slide_shorthand: config.options.end_slide_shorthand.unwrap_or_default(),
         print_modal_background: false,
         strict_front_matter_parsing: config.options.strict_front_matter_parsing.unwrap_or(true),
+        max_elements_per_render: config.options.max_elements_per_render,
+        reveal_style: config.options.reveal_style.clone().unwrap_or_default(),
+        glossary_case_sensitive: config.options.glossary_case_sensitive.unwrap_or(true),
+        render_hooks: Default::default(),
+        defer_media_loading: !matches!(mode, PresentMode::Export),
+        execution: ExecutionPolicy {
+            timeout: Duration::from_secs(config.execution.timeout_seconds),
+            max_output_bytes: config.execution.max_output_bytes,
+            environment: config.execution.environment.clone(),
+        },
+        hide_footer: false,
+        countdown_start: config.options.countdown_start.clone().unwrap_or_default(),
+        countdown_return: config.options.countdown_return.clone().unwrap_or_default(),
+        countdown_bell: config.options.countdown_bell.unwrap_or_default(),
+        structure: config.options.structure.unwrap_or_default(),
+        warn_on_duplicate_titles: config.options.warn_on_duplicate_titles.unwrap_or(false),
+        slide_budget: config.options.slide_budget,
+        native_images: true,
+        end_screen: (config.defaults.end_of_deck == EndOfDeck::EndScreen)
+            .then(|| EndScreen { message: config.defaults.end_screen_message.clone(), started_at: Instant::now() }),
+        shortcode_emoji: config.defaults.shortcode_emoji,
+        artifacts: artifact_store(config),
+        reveal_redactions: false,
     }
 }
 
 fn load_default_theme(config: &Config, themes: &Themes, cli: &Cli) -> PresentationTheme {
-    let default_theme_name =
-        cli.theme.as_ref().or(config.defaults.theme.as_ref()).map(|s| s.as_str()).unwrap_or(DEFAULT_THEME);
+    let default_theme_name = match (&cli.theme, &config.defaults.theme) {
+        (Some(name), _) | (None, Some(DefaultTheme::Named(name))) => name.as_str(),
+        (None, Some(DefaultTheme::Auto { auto })) => select_auto_theme(auto, cli),
+        (None, None) => DEFAULT_THEME,
+    };
     let Some(default_theme) = themes.presentation.load_by_name(default_theme_name) else {
         let valid_themes = themes.presentation.theme_names().join(", ");
         let error_message = format!("invalid theme name, valid themes are: {valid_themes}");
@@ -143,6 +298,32 @@ fn load_default_theme(config: &Config, themes: &Themes, cli: &Cli) -> Presentati
     default_theme
 }
 
+fn select_auto_theme<'a>(auto: &'a AutoTheme, cli: &Cli) -> &'a str {
+    // There's no terminal to look at when exporting. In that case or if detection fails, we use the
+    // dark theme as presenterm's default theme is dark.
+    let exporting = cli.export || cli.export_pdf || cli.generate_pdf_metadata || cli.export_reveal_js || cli.serve;
+    if exporting || cli.export_outline || cli.stats || cli.fail_on_todo || cli.normalize_separators {
+        return &auto.dark;
+    }
+    match TerminalBackground::detect(BACKGROUND_DETECTION_TIMEOUT) {
+        Ok(TerminalBackground::Light) => &auto.light,
+        Ok(TerminalBackground::Dark) | Err(_) => &auto.dark,
+    }
+}
+
+fn configured_cell_size(config: &Config) -> Option<CellSize> {
+    let [width, height] = config.defaults.cell_pixel_size?;
+    let Some(cell_size) = CellSize::new(width, height) else {
+        Cli::command().error(ErrorKind::InvalidValue, "cell_pixel_size can't contain zeros").exit();
+    };
+    Some(cell_size)
+}
+
+fn detect_cell_size(config: &Config) -> CellSizePolicy {
+    let configured = configured_cell_size(config);
+    CellSizePolicy::detect(configured, config.defaults.terminal_font_size, CELL_SIZE_DETECTION_TIMEOUT)
+}
+
 fn select_graphics_mode(cli: &Cli, config: &Config) -> GraphicsMode {
     if cli.export || cli.export_pdf || cli.generate_pdf_metadata {
         GraphicsMode::AsciiBlocks
@@ -168,8 +349,9 @@ fn overflow_validation(mode: &PresentMode, config: &ValidateOverflows) -> bool {
 }
 
 fn run(mut cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
-    if cli.generate_config_file_schema {
-        let schema = schemars::schema_for!(Config);
+    let schema_kind = cli.generate_schema.or(cli.generate_config_file_schema.then_some(SchemaKind::Config));
+    if let Some(kind) = schema_kind {
+        let schema = generate_schema(kind);
         serde_json::to_writer_pretty(io::stdout(), &schema).map_err(|e| format!("failed to write schema: {e}"))?;
         return Ok(());
     }
@@ -178,21 +360,41 @@ fn run(mut cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
 
     let default_theme = load_default_theme(&config, &themes, &cli);
     let force_default_theme = cli.theme.is_some();
-    let mode = match (cli.present, cli.export) {
+    let mode = match (cli.present || cli.kiosk, cli.export) {
         (true, _) => PresentMode::Presentation,
         (false, true) => PresentMode::Export,
         (false, false) => PresentMode::Development,
     };
+    // There's no one to interact with the presentation when the output isn't a terminal, so every slide is printed.
+    let prints_all_slides = !cli.export && !io::stdout().is_tty();
+    let renders_offscreen =
+        cli.export || cli.export_pdf || cli.generate_pdf_metadata || cli.print_slide.is_some() || prints_all_slides;
+    if cli.export_dimensions.is_some() && !renders_offscreen {
+        let error_message = "--export-dimensions can only be used when exporting or printing slides";
+        Cli::command().error(ErrorKind::ArgumentConflict, error_message).exit();
+    }
+    let dimensions = cli.export_dimensions.unwrap_or_default();
     let arena = Arena::new();
-    let parser = MarkdownParser::new(&arena);
+    let parser = MarkdownParser::new(&arena).math_blocks(config.typst.math_blocks);
     if cli.acknowledgements {
         display_acknowledgements();
         return Ok(());
+    } else if cli.clean_cache {
+        if let Some(directory) = artifacts_directory() {
+            ArtifactStore::new(directory, 0).clean()?;
+        }
+        return Ok(());
     } else if cli.list_themes {
         let bindings = config.bindings.try_into()?;
         let demo = ThemesDemo::new(themes, bindings, io::stdout())?;
         demo.run()?;
         return Ok(());
+    } else if cli.test_pattern {
+        let cell_size = detect_cell_size(&config);
+        let pattern = TestPattern::new(config.defaults.safe_area_margin, cell_size, io::stdout())?;
+        let SafeAreaMargin { rows, columns } = pattern.run()?;
+        println!("defaults:\n  safe_area_margin:\n    rows: {rows}\n    columns: {columns}");
+        return Ok(());
     }
 
     let path = cli.path.take().unwrap_or_else(|| {
@@ -202,12 +404,61 @@ fn run(mut cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     let resources_path = path.parent().unwrap_or(Path::new("/"));
     let mut options = make_builder_options(&config, &mode, force_default_theme);
     let graphics_mode = select_graphics_mode(&cli, &config);
+    // Diagrams are unreadable when drawn using ascii blocks so they're drawn as ascii art instead.
+    options.native_images = !matches!(graphics_mode, GraphicsMode::AsciiBlocks);
     let printer = Rc::new(ImagePrinter::new(graphics_mode.clone())?);
     let registry = ImageRegistry(printer.clone());
-    let resources = Resources::new(resources_path, registry.clone());
-    let typst = TypstRender::new(config.typst.ppi, registry, resources_path);
-    if cli.export_pdf || cli.generate_pdf_metadata {
-        let mut exporter = Exporter::new(parser, &default_theme, resources, typst, themes, options);
+    let sandbox = cli.sandbox || config.defaults.sandbox;
+    let make_resources = |registry| {
+        let resources = Resources::new(resources_path, registry);
+        if sandbox { resources.sandboxed() } else { Ok(resources) }
+    };
+    let resources = make_resources(registry.clone())?;
+    let mut typst = TypstRender::new(config.typst.ppi, registry, resources_path);
+    if let Some(artifacts) = &options.artifacts {
+        // Keeping the cache within its size is best effort, there's no reason to stop over it.
+        let _ = artifacts.collect_garbage();
+        typst = typst.artifacts(artifacts.clone());
+    }
+    if cli.export_reveal_js {
+        let output = cli.output.as_ref().expect("output is required");
+        RevealExporter::new(parser, resources, options).export(&path, output)?;
+    } else if cli.serve {
+        serve(RevealExporter::new(parser, resources, options), &path, &cli)?;
+    } else if cli.export_outline {
+        let outline = OutlineExporter::new(parser, options).export(&path, cli.outline_format)?;
+        print!("{outline}");
+    } else if cli.stats || cli.fail_on_todo {
+        let stats = StatsCollector::new(parser, &default_theme, resources, typst, themes, options).collect(&path)?;
+        if cli.stats {
+            print!("{stats}");
+        }
+        if cli.fail_on_todo && !stats.todos().is_empty() {
+            if !cli.stats {
+                for todo in stats.todos() {
+                    eprintln!("{todo}");
+                }
+            }
+            return Err(format!("found {} TODO/FIXME notes", stats.todos().len()).into());
+        }
+    } else if cli.normalize_separators {
+        let contents = fs::read_to_string(&path)?;
+        let normalized = SeparatorAnalyzer::new(parser, options).normalize(&contents)?;
+        if normalized != contents {
+            fs::write(&path, normalized)?;
+        }
+    } else if let Some(slide) = cli.print_slide {
+        // Media is loaded right away as there's no presentation loop to wait for it in.
+        options.defer_media_loading = false;
+        options.hide_footer = !cli.with_footer;
+        let cell_size = detect_cell_size(&config);
+        let mut printer =
+            SlidePrinter::new(parser, &default_theme, resources, typst, themes, printer, cell_size, options);
+        printer.print(&path, slide, dimensions, cli.cols, io::stdout())?;
+    } else if cli.export_pdf || cli.generate_pdf_metadata {
+        let mut exporter = Exporter::new(parser, &default_theme, resources, typst, themes, options)
+            .with_cell_size(configured_cell_size(&config))
+            .with_dimensions(dimensions);
         let mut args = Vec::new();
         if let Some(theme) = cli.theme.as_ref() {
             args.extend(["--theme", theme]);
@@ -221,16 +472,46 @@ fn run(mut cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
             let meta = exporter.generate_metadata(&path)?;
             println!("{}", serde_json::to_string_pretty(&meta)?);
         }
+    } else if prints_all_slides {
+        // Media is loaded right away as there's no presentation loop to wait for it in.
+        options.defer_media_loading = false;
+        let cell_size = detect_cell_size(&config);
+        let mut printer =
+            SlidePrinter::new(parser, &default_theme, resources, typst, themes, printer, cell_size, options);
+        printer.print_all(&path, dimensions, None, io::stdout())?;
     } else {
         let commands = CommandSource::new(&path, config.bindings.clone())?;
         options.print_modal_background = matches!(graphics_mode, GraphicsMode::Kitty { .. });
+        options.hide_footer = cli.kiosk;
+        // Overflows can only be checked once every image's size is known.
+        if validate_overflows {
+            options.defer_media_loading = false;
+        }
+        let session = session_store();
+        let previous_session = session.as_ref().and_then(|store| store.load(&path));
+        let suggest_resume = |state: &&SessionState| !cli.resume && state.is_recent(SystemTime::now());
+        let suggested_resume = previous_session.as_ref().filter(suggest_resume).cloned();
 
         let options = PresenterOptions {
             builder_options: options,
             mode,
-            font_size_fallback: config.defaults.terminal_font_size,
+            cell_size: detect_cell_size(&config),
+            dimensions,
             bindings: config.bindings,
             validate_overflows,
+            event_emitter: cli.emit_events.map(EventEmitter::open),
+            safe_area_margin: config.defaults.safe_area_margin,
+            cache_budget: config.defaults.cache_budget_mb as usize * 1024 * 1024,
+            cache_usage_overlay: config.defaults.cache_usage_overlay,
+            end_of_deck: config.defaults.end_of_deck,
+            preview_dimensions: config.defaults.preview_dimensions,
+            kiosk: cli.kiosk,
+            session,
+            resume: previous_session.filter(|_| cli.resume),
+            suggested_resume,
+            fps: cli.fps,
+            slow_frames: config.defaults.slow_frames,
+            pause_on_focus_loss: config.defaults.pause_on_focus_loss,
         };
         let presenter = Presenter::new(&default_theme, commands, parser, resources, typst, themes, printer, options);
         presenter.present(&path)?;
@@ -238,9 +519,29 @@ fn run(mut cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[cfg(feature = "serve")]
+fn serve(exporter: RevealExporter, path: &Path, cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let address = (cli.serve_host.as_str(), cli.serve_port);
+    let server = PreviewServer::bind(exporter, path, address)
+        .map_err(|e| format!("failed to listen on {}:{}: {e}", cli.serve_host, cli.serve_port))?;
+    eprintln!("serving presentation at http://{}", server.local_addr()?);
+    server.run()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "serve"))]
+fn serve(_exporter: RevealExporter, _path: &Path, _cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+    Cli::command().error(ErrorKind::InvalidValue, "serve support was not enabled during compilation").exit()
+}
+
 fn main() {
+    install_panic_hook();
     let cli = Cli::parse();
     if let Err(e) = run(cli) {
+        // There's nowhere to print anything to if the terminal is gone.
+        if let Some(PresentationError::Disconnected) = e.downcast_ref() {
+            std::process::exit(DISCONNECTED_EXIT_CODE);
+        }
         eprintln!("{e}");
         std::process::exit(1);
     }
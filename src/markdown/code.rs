@@ -9,7 +9,8 @@ pub(crate) struct CodeBlockParser;
 impl CodeBlockParser {
     pub(crate) fn parse(code_block: &NodeCodeBlock) -> ParseResult<Code> {
         let (language, attributes) = Self::parse_block_info(&code_block.info)?;
-        let code = Code { contents: code_block.literal.clone(), language, attributes };
+        let language_name = Self::language_identifier(&code_block.info).to_string();
+        let code = Code { contents: code_block.literal.clone(), language, language_name, attributes };
         Ok(code)
     }
 
@@ -26,7 +27,7 @@ impl CodeBlockParser {
     }
 
     fn parse_language(input: &str) -> (CodeLanguage, &str) {
-        let token = Self::next_identifier(input);
+        let token = Self::language_identifier(input);
         use CodeLanguage::*;
         let language = match token {
             "ada" => Ada,
@@ -57,6 +58,7 @@ impl CodeBlockParser {
             "lua" => Lua,
             "make" => Makefile,
             "markdown" => Markdown,
+            "mermaid" => Mermaid,
             "nix" => Nix,
             "ocaml" => OCaml,
             "perl" => Perl,
@@ -98,6 +100,7 @@ impl CodeBlockParser {
                 Attribute::LineNumbers => attributes.line_numbers = true,
                 Attribute::Exec => attributes.execute = true,
                 Attribute::AutoRender => attributes.auto_render = true,
+                Attribute::Timeout(seconds) => attributes.timeout_seconds = Some(seconds),
                 Attribute::HighlightedLines(lines) => attributes.highlight_groups = lines,
             };
             processed_attributes.push(discriminant);
@@ -118,7 +121,10 @@ impl CodeBlockParser {
                     "line_numbers" => Attribute::LineNumbers,
                     "exec" => Attribute::Exec,
                     "render" => Attribute::AutoRender,
-                    _ => return Err(CodeBlockParseError::InvalidToken(Self::next_identifier(input).into())),
+                    other => match other.strip_prefix("timeout:").map(str::parse) {
+                        Some(Ok(seconds)) if seconds > 0 => Attribute::Timeout(seconds),
+                        _ => return Err(CodeBlockParseError::InvalidToken(Self::next_identifier(input).into())),
+                    },
                 };
                 (Some(attribute), &input[token.len() + 1..])
             }
@@ -187,6 +193,14 @@ impl CodeBlockParser {
         input.trim_start_matches(' ')
     }
 
+    // Highlighted lines can come right after the language, like in `rust{1,3}`.
+    fn language_identifier(input: &str) -> &str {
+        match input.find([' ', '{']) {
+            Some(index) => &input[..index],
+            None => input,
+        }
+    }
+
     fn next_identifier(input: &str) -> &str {
         match input.split_once(' ') {
             Some((token, _)) => token,
@@ -215,6 +229,7 @@ enum Attribute {
     LineNumbers,
     Exec,
     AutoRender,
+    Timeout(u64),
     HighlightedLines(Vec<HighlightGroup>),
 }
 
@@ -264,6 +279,21 @@ mod test {
         CodeBlockParser::parse_block_info("bash potato").unwrap_err();
     }
 
+    #[test]
+    fn timeout() {
+        let attributes = parse_attributes("bash +exec +timeout:5");
+        assert_eq!(attributes.timeout_seconds, Some(5));
+    }
+
+    #[rstest]
+    #[case::no_value("bash +exec +timeout:")]
+    #[case::not_a_number("bash +exec +timeout:potato")]
+    #[case::zero("bash +exec +timeout:0")]
+    #[case::duplicate("bash +exec +timeout:1 +timeout:2")]
+    fn invalid_timeout(#[case] input: &str) {
+        CodeBlockParser::parse_block_info(input).unwrap_err();
+    }
+
     #[rstest]
     #[case::no_end("{")]
     #[case::number_no_end("{42")]
@@ -301,6 +331,16 @@ mod test {
         );
     }
 
+    #[rstest]
+    #[case::spaced("rust {3,5-7}")]
+    #[case::attached("rust{3,5-7}")]
+    #[case::attached_with_attributes("rust{3,5-7} +line_numbers")]
+    fn highlight_after_language(#[case] input: &str) {
+        let (language, attributes) = CodeBlockParser::parse_block_info(input).expect("parse failed");
+        assert_eq!(language, CodeLanguage::Rust);
+        assert_eq!(attributes.highlight_groups, &[HighlightGroup::new(vec![Single(3), Range(5..8)])]);
+    }
+
     #[test]
     fn multiple_groups() {
         let attributes = parse_attributes("bash {1-3,5  |6-9}");
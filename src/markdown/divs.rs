@@ -0,0 +1,391 @@
+//! Support for pandoc style fenced divs.
+//!
+//! comrak doesn't know about fenced divs so they're extracted before the markdown is parsed: every
+//! fence is replaced with a placeholder comment which the parser then swaps for the [FencedDiv]
+//! extracted in that position. Only the divs used to define column layouts have any meaning:
+//!
+//! ```markdown
+//! :::: columns
+//! ::: {.column width="40%"}
+//! left
+//! :::
+//! ::: column
+//! right
+//! :::
+//! ::::
+//! ```
+//!
+//! Any other div is ignored and its contents are processed as if it wasn't there.
+
+use std::{collections::VecDeque, fmt::Write};
+
+/// The comment every fence that has a meaning is replaced with.
+pub(crate) const FENCED_DIV_PLACEHOLDER: &str = "presenterm:fenced-div";
+
+/// A fenced div that has a meaning in a presentation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum FencedDiv {
+    /// The start of a column layout, along with the width of each of its columns.
+    Columns(Vec<u8>),
+
+    /// The start of a column in the current layout.
+    Column(usize),
+
+    /// The end of a column layout.
+    EndColumns,
+}
+
+/// The result of extracting the fenced divs in a markdown file.
+#[derive(Debug)]
+pub(crate) struct FencedDivs {
+    /// The markdown contents, with every fence replaced.
+    pub(crate) contents: String,
+
+    /// The divs found, in the order they show up in.
+    pub(crate) divs: VecDeque<FencedDiv>,
+}
+
+impl FencedDivs {
+    /// Extract all fenced divs in the given markdown.
+    ///
+    /// Fences are replaced line by line so the line numbers in the output match the input's.
+    pub(crate) fn extract(contents: &str) -> Result<Self, FencedDivError> {
+        if !contents.contains(":::") {
+            return Ok(Self { contents: contents.into(), divs: Default::default() });
+        }
+        let mut extractor = Extractor::default();
+        for (index, line) in contents.split_inclusive('\n').enumerate() {
+            extractor.process_line(line, index + 1)?;
+        }
+        if let Some(div) = extractor.open_divs.pop() {
+            return Err(FencedDivError { line: div.line, kind: FencedDivErrorKind::Unclosed });
+        }
+        let Extractor { output, divs, .. } = extractor;
+        Ok(Self { contents: output, divs: divs.into() })
+    }
+}
+
+#[derive(Default)]
+struct Extractor {
+    output: String,
+    divs: Vec<FencedDiv>,
+    open_divs: Vec<OpenDiv>,
+    raw_blocks: RawBlocks,
+}
+
+impl Extractor {
+    fn process_line(&mut self, line: &str, line_number: usize) -> Result<(), FencedDivError> {
+        if self.raw_blocks.skip_line(line, line_number) {
+            self.output.push_str(line);
+            return Ok(());
+        }
+        let Some(fence) = Fence::parse(line) else {
+            self.output.push_str(line);
+            return Ok(());
+        };
+        let error = |kind| FencedDivError { line: line_number, kind };
+        let replace_with_placeholder = match fence {
+            Fence::Open(attributes) => {
+                let kind = match attributes.class() {
+                    Some("columns") => {
+                        if self.open_divs.iter().any(|div| matches!(div.kind, OpenDivKind::Columns { .. })) {
+                            return Err(error(FencedDivErrorKind::NestedLayout));
+                        }
+                        self.divs.push(FencedDiv::Columns(Vec::new()));
+                        OpenDivKind::Columns { index: self.divs.len() - 1, widths: Vec::new() }
+                    }
+                    Some("column") => {
+                        let width = attributes.width().map_err(error)?;
+                        let Some(OpenDiv { kind: OpenDivKind::Columns { widths, .. }, .. }) = self.open_divs.last_mut()
+                        else {
+                            return Err(error(FencedDivErrorKind::ColumnOutsideLayout));
+                        };
+                        widths.push(width);
+                        self.divs.push(FencedDiv::Column(widths.len() - 1));
+                        OpenDivKind::Column
+                    }
+                    _ => OpenDivKind::Other,
+                };
+                let replace = !matches!(kind, OpenDivKind::Other);
+                self.open_divs.push(OpenDiv { kind, line: line_number });
+                replace
+            }
+            Fence::Close => {
+                let div = self.open_divs.pop().ok_or_else(|| error(FencedDivErrorKind::Unopened))?;
+                match div.kind {
+                    OpenDivKind::Columns { index, widths } => {
+                        self.divs[index] = FencedDiv::Columns(Self::column_widths(&widths));
+                        self.divs.push(FencedDiv::EndColumns);
+                        true
+                    }
+                    OpenDivKind::Column | OpenDivKind::Other => false,
+                }
+            }
+        };
+        // Fences that don't have a meaning are replaced with an empty line so they still
+        // separate the blocks around them.
+        if replace_with_placeholder {
+            let _ = write!(self.output, "<!--{FENCED_DIV_PLACEHOLDER}-->");
+        }
+        self.output.push_str(&line[line.trim_end_matches(['\r', '\n']).len()..]);
+        Ok(())
+    }
+
+    // Columns without an explicit width split whatever is left evenly. If no widths are given at
+    // all, all columns get the same one.
+    fn column_widths(widths: &[Option<u8>]) -> Vec<u8> {
+        let specified: u32 = widths.iter().flatten().map(|width| *width as u32).sum();
+        let unspecified = widths.iter().filter(|width| width.is_none()).count() as u32;
+        let default_width = match specified {
+            0 => 1,
+            _ => (100u32.saturating_sub(specified) / unspecified.max(1)).max(1) as u8,
+        };
+        widths.iter().map(|width| width.unwrap_or(default_width)).collect()
+    }
+}
+
+/// Tracks the blocks in a markdown file whose lines must be left alone when preprocessing it.
+///
+/// These are code blocks, multiline comments, and the front matter.
+#[derive(Default)]
+pub(crate) struct RawBlocks {
+    skipping: Option<Skipped>,
+}
+
+impl RawBlocks {
+    /// Process the next line, returning whether it's part of a block that must be left alone.
+    pub(crate) fn skip_line(&mut self, line: &str, line_number: usize) -> bool {
+        let trimmed = line.trim_end();
+        match &self.skipping {
+            Some(Skipped::FrontMatter) => {
+                if trimmed == "---" {
+                    self.skipping = None;
+                }
+                true
+            }
+            Some(Skipped::Code { delimiter, length }) => {
+                let code = trimmed.trim_start();
+                let fence_length = code.chars().take_while(|c| c == delimiter).count();
+                if fence_length >= *length && fence_length == code.len() {
+                    self.skipping = None;
+                }
+                true
+            }
+            Some(Skipped::Comment) => {
+                if trimmed.contains("-->") {
+                    self.skipping = None;
+                }
+                true
+            }
+            None => {
+                let code = trimmed.trim_start();
+                if line_number == 1 && trimmed == "---" {
+                    self.skipping = Some(Skipped::FrontMatter);
+                } else if trimmed.len() - code.len() <= 3 && (code.starts_with("```") || code.starts_with("~~~")) {
+                    let delimiter = code.chars().next().expect("not empty");
+                    let length = code.chars().take_while(|c| c == &delimiter).count();
+                    self.skipping = Some(Skipped::Code { delimiter, length });
+                } else if code.starts_with("<!--") && !code.contains("-->") {
+                    self.skipping = Some(Skipped::Comment);
+                }
+                self.skipping.is_some()
+            }
+        }
+    }
+}
+
+enum Skipped {
+    FrontMatter,
+    Code { delimiter: char, length: usize },
+    Comment,
+}
+
+struct OpenDiv {
+    kind: OpenDivKind,
+    line: usize,
+}
+
+enum OpenDivKind {
+    Columns { index: usize, widths: Vec<Option<u8>> },
+    Column,
+    Other,
+}
+
+enum Fence<'a> {
+    Open(Attributes<'a>),
+    Close,
+}
+
+impl<'a> Fence<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let line = line.trim_end();
+        let attributes = line.trim_start_matches(':');
+        if line.len() - attributes.len() < 3 {
+            return None;
+        }
+        // Opening fences can optionally be closed by any number of colons, like `::: column :::`.
+        let attributes = attributes.trim_end_matches(':').trim();
+        match attributes {
+            "" => Some(Self::Close),
+            _ => Some(Self::Open(Attributes(attributes))),
+        }
+    }
+}
+
+/// The attributes in an opening fence, either in the form `{#id .class key="value"}` or just a
+/// class name.
+struct Attributes<'a>(&'a str);
+
+impl Attributes<'_> {
+    fn class(&self) -> Option<&str> {
+        match self.0.strip_prefix('{').and_then(|attributes| attributes.strip_suffix('}')) {
+            Some(attributes) => attributes.split_whitespace().find_map(|attribute| attribute.strip_prefix('.')),
+            None => self.0.split_whitespace().next(),
+        }
+    }
+
+    fn width(&self) -> Result<Option<u8>, FencedDivErrorKind> {
+        let Some(attributes) = self.0.strip_prefix('{').and_then(|attributes| attributes.strip_suffix('}')) else {
+            return Ok(None);
+        };
+        let Some(width) = attributes.split_whitespace().find_map(|attribute| attribute.strip_prefix("width=")) else {
+            return Ok(None);
+        };
+        let width = width.trim_matches('"');
+        match width.trim_end_matches('%').parse::<u8>() {
+            Ok(value @ 1..=100) => Ok(Some(value)),
+            _ => Err(FencedDivErrorKind::InvalidWidth(width.into())),
+        }
+    }
+}
+
+/// An error when extracting fenced divs.
+#[derive(Debug)]
+pub(crate) struct FencedDivError {
+    /// The line the error was found in.
+    pub(crate) line: usize,
+
+    /// The kind of error.
+    pub(crate) kind: FencedDivErrorKind,
+}
+
+/// The kind of fenced div error.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub(crate) enum FencedDivErrorKind {
+    #[error("fenced div is never closed")]
+    Unclosed,
+
+    #[error("closing fence without a matching opening fence")]
+    Unopened,
+
+    #[error("'column' div must be directly inside a 'columns' div")]
+    ColumnOutsideLayout,
+
+    #[error("'columns' divs can't be nested")]
+    NestedLayout,
+
+    #[error("invalid column width '{0}': must be a percentage between 1 and 100")]
+    InvalidWidth(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    fn extract(input: &str) -> FencedDivs {
+        FencedDivs::extract(input).expect("extraction failed")
+    }
+
+    #[test]
+    fn column_layout() {
+        let input = "
+:::: columns
+::: column
+left
+:::
+::: column
+right
+:::
+::::
+after";
+        let output = extract(input);
+        let expected = "
+<!--presenterm:fenced-div-->
+<!--presenterm:fenced-div-->
+left
+
+<!--presenterm:fenced-div-->
+right
+
+<!--presenterm:fenced-div-->
+after";
+        assert_eq!(output.contents, expected);
+        assert_eq!(
+            output.divs,
+            &[FencedDiv::Columns(vec![1, 1]), FencedDiv::Column(0), FencedDiv::Column(1), FencedDiv::EndColumns]
+        );
+    }
+
+    #[rstest]
+    #[case::percentages(&["{.column width=\"40%\"}", "{.column width=\"60%\"}"], &[40, 60])]
+    #[case::unquoted(&["{.column width=30}", "column"], &[30, 70])]
+    #[case::remainder_split(&["{.column width=\"50%\"}", "column", "column"], &[50, 25, 25])]
+    #[case::nothing_left(&["{.column width=\"100%\"}", "column"], &[100, 1])]
+    #[case::all_implicit(&["column", "{.column}", "column :::"], &[1, 1, 1])]
+    fn column_widths(#[case] columns: &[&str], #[case] expected: &[u8]) {
+        let mut input = String::from(":::: {.columns}\n");
+        for column in columns {
+            input.push_str(&format!("::: {column}\ntext\n:::\n"));
+        }
+        input.push_str("::::\n");
+        let output = extract(&input);
+        assert_eq!(output.divs[0], FencedDiv::Columns(expected.to_vec()));
+    }
+
+    #[test]
+    fn other_divs_ignored() {
+        let input = "::: notes\nhi\n:::\n";
+        let output = extract(input);
+        assert_eq!(output.contents, "\nhi\n\n");
+        assert!(output.divs.is_empty());
+    }
+
+    #[rstest]
+    #[case::code_block("```\n::: columns\n```\n")]
+    #[case::tilde_code_block("~~~~\n::: columns\n~~~\n:::\n~~~~\n")]
+    #[case::comment("<!--\n::: columns\n-->\n")]
+    #[case::front_matter("---\ntitle: \"::: columns\"\n---\n")]
+    #[case::not_a_fence(":: columns\n")]
+    fn fences_skipped(#[case] input: &str) {
+        let output = extract(input);
+        assert_eq!(output.contents, input);
+        assert!(output.divs.is_empty());
+    }
+
+    #[rstest]
+    #[case::unclosed(":::: columns\n::: column\n:::\n", 1, FencedDivErrorKind::Unclosed)]
+    #[case::unclosed_column(":::: columns\n::: column\n", 2, FencedDivErrorKind::Unclosed)]
+    #[case::unopened("hi\n:::\n", 2, FencedDivErrorKind::Unopened)]
+    #[case::column_outside_layout("::: column\n:::\n", 1, FencedDivErrorKind::ColumnOutsideLayout)]
+    #[case::nested_column(
+        ":::: columns\n::: column\n::: column\n:::\n:::\n::::\n",
+        3,
+        FencedDivErrorKind::ColumnOutsideLayout
+    )]
+    #[case::nested_layout(
+        ":::: columns\n::: column\n::: columns\n:::\n:::\n::::\n",
+        3,
+        FencedDivErrorKind::NestedLayout
+    )]
+    #[case::invalid_width(
+        ":::: columns\n::: {.column width=\"0%\"}\n:::\n::::\n",
+        2,
+        FencedDivErrorKind::InvalidWidth("0%".into())
+    )]
+    fn invalid(#[case] input: &str, #[case] line: usize, #[case] kind: FencedDivErrorKind) {
+        let error = FencedDivs::extract(input).expect_err("extraction succeeded");
+        assert_eq!(error.line, line);
+        assert_eq!(error.kind, kind);
+    }
+}
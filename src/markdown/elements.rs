@@ -1,4 +1,4 @@
-use crate::style::TextStyle;
+use crate::{markdown::divs::FencedDiv, style::TextStyle};
 use std::{iter, ops::Range, path::PathBuf};
 use strum::EnumIter;
 use unicode_width::UnicodeWidthStr;
@@ -13,22 +13,25 @@ pub(crate) enum MarkdownElement {
     FrontMatter(String),
 
     /// A setex heading.
-    SetexHeading { text: TextBlock },
+    SetexHeading { text: TextBlock, source_position: SourcePosition },
 
     /// A normal heading.
-    Heading { level: u8, text: TextBlock },
+    Heading { level: u8, text: TextBlock, source_position: SourcePosition },
 
     /// A paragraph, composed of text and line breaks.
     Paragraph(Vec<ParagraphElement>),
 
     /// An image.
-    Image { path: PathBuf },
+    Image { path: PathBuf, alt_text: String, source_position: SourcePosition },
 
     /// A list.
     ///
     /// All contiguous list items are merged into a single one, regardless of levels of nesting.
     List(Vec<ListItem>),
 
+    /// A description list, made up of terms along with their definitions.
+    DescriptionList(Vec<DescriptionItem>),
+
     /// A block of code.
     Code(Code),
 
@@ -36,13 +39,74 @@ pub(crate) enum MarkdownElement {
     Table(Table),
 
     /// A thematic break.
-    ThematicBreak,
+    ThematicBreak { source_position: SourcePosition },
 
     /// An HTML comment.
     Comment { comment: String, source_position: SourcePosition },
 
     /// A quote.
-    BlockQuote(Vec<String>),
+    BlockQuote(Vec<QuoteLine>),
+
+    /// A pandoc style fenced div.
+    FencedDiv(FencedDiv),
+
+    /// The start or end of a collapsible `<details>` block.
+    Details(DetailsTag),
+}
+
+impl MarkdownElement {
+    /// Get the number of characters in the text in this element.
+    ///
+    /// This doesn't include markup nor the contents of code blocks.
+    pub(crate) fn char_count(&self) -> usize {
+        self.texts().iter().map(|text| text.chars().count()).sum()
+    }
+
+    /// Get the number of words in the text in this element.
+    ///
+    /// This doesn't include the contents of code blocks.
+    pub(crate) fn word_count(&self) -> usize {
+        self.texts().iter().map(|text| text.split_whitespace().count()).sum()
+    }
+
+    // Get the text in this element, one entry per heading, paragraph line, list item, etc.
+    fn texts(&self) -> Vec<String> {
+        match self {
+            Self::SetexHeading { text, .. } | Self::Heading { text, .. } => vec![text.to_plain()],
+            Self::Paragraph(elements) => elements
+                .iter()
+                .filter_map(|element| match element {
+                    ParagraphElement::Text(text) => Some(text.to_plain()),
+                    ParagraphElement::LineBreak => None,
+                })
+                .collect(),
+            Self::List(items) => items.iter().map(|item| item.contents.to_plain()).collect(),
+            Self::DescriptionList(items) => items
+                .iter()
+                .flat_map(|item| [&item.term].into_iter().chain(&item.definitions))
+                .map(TextBlock::to_plain)
+                .collect(),
+            Self::Table(table) => table
+                .header
+                .0
+                .iter()
+                .chain(table.rows.iter().flat_map(|row| row.0.iter()))
+                .map(TextBlock::to_plain)
+                .collect(),
+            Self::BlockQuote(lines) => lines.iter().map(|line| line.text.clone()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A tag that delimits a collapsible `<details>` block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum DetailsTag {
+    /// The start of the block, along with the summary that's displayed while it's collapsed.
+    Start { summary: String, open: bool },
+
+    /// The end of the block.
+    End,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -102,6 +166,11 @@ impl TextBlock {
         self.0.iter().map(|text| text.content.width()).sum()
     }
 
+    /// Get the contents of this text without any formatting.
+    pub(crate) fn to_plain(&self) -> String {
+        self.0.iter().map(|text| text.content.as_str()).collect()
+    }
+
     /// Applies the given style to this text.
     pub(crate) fn apply_style(&mut self, style: &TextStyle) {
         for text in &mut self.0 {
@@ -123,27 +192,48 @@ impl<T: Into<Text>> From<T> for TextBlock {
 pub(crate) struct Text {
     pub(crate) content: String,
     pub(crate) style: TextStyle,
+
+    /// What this text stands for, if it's a term in the presentation's glossary.
+    pub(crate) expansion: Option<String>,
 }
 
 impl Text {
     /// Construct a new styled text.
     pub(crate) fn new<S: Into<String>>(content: S, style: TextStyle) -> Self {
-        Self { content: content.into(), style }
+        Self { content: content.into(), style, expansion: None }
+    }
+
+    /// Iterate over the characters in this text, along with the style they're rendered with.
+    ///
+    /// Every character is a unicode scalar value so multi byte ones, like emojis that are encoded as
+    /// surrogate pairs in UTF-16, are yielded as a single character.
+    pub(crate) fn iter_chars(&self) -> impl Iterator<Item = (char, &TextStyle)> {
+        self.content.chars().map(|c| (c, &self.style))
     }
 }
 
 impl From<String> for Text {
     fn from(text: String) -> Self {
-        Self { content: text, style: TextStyle::default() }
+        Self::new(text, TextStyle::default())
     }
 }
 
 impl From<&str> for Text {
     fn from(text: &str) -> Self {
-        Self { content: text.into(), style: TextStyle::default() }
+        Self::new(text, TextStyle::default())
     }
 }
 
+/// An item in a description list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DescriptionItem {
+    /// The term being described.
+    pub(crate) term: TextBlock,
+
+    /// The definitions of the term.
+    pub(crate) definitions: Vec<TextBlock>,
+}
+
 /// A list item.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct ListItem {
@@ -159,6 +249,18 @@ pub(crate) struct ListItem {
     pub(crate) item_type: ListItemType,
 }
 
+/// A line in a block quote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct QuoteLine {
+    /// The contents of this line.
+    pub(crate) text: String,
+
+    /// The depth of this line.
+    ///
+    /// This increases by one for every block quote nested within the outermost one.
+    pub(crate) depth: u8,
+}
+
 /// The type of a list item.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum ListItemType {
@@ -166,10 +268,20 @@ pub(crate) enum ListItemType {
     Unordered,
 
     /// A list item for an ordered list that uses parenthesis after the list item number.
-    OrderedParens,
+    OrderedParens { start: usize },
 
     /// A list item for an ordered list that uses a period after the list item number.
-    OrderedPeriod,
+    OrderedPeriod { start: usize },
+}
+
+impl ListItemType {
+    /// The number the list this item belongs to starts at.
+    pub(crate) fn start(&self) -> usize {
+        match self {
+            Self::Unordered => 1,
+            Self::OrderedParens { start } | Self::OrderedPeriod { start } => *start,
+        }
+    }
 }
 
 /// A piece of code.
@@ -181,6 +293,9 @@ pub(crate) struct Code {
     /// The programming language this code is written in.
     pub(crate) language: CodeLanguage,
 
+    /// The language as written in the code block, which is useful for languages we don't know about.
+    pub(crate) language_name: String,
+
     /// The attributes used for this code.
     pub(crate) attributes: CodeAttributes,
 }
@@ -218,6 +333,7 @@ pub(crate) enum CodeLanguage {
     Lua,
     Makefile,
     Markdown,
+    Mermaid,
     Nix,
     OCaml,
     Perl,
@@ -249,7 +365,7 @@ impl CodeLanguage {
     }
 
     pub(crate) fn supports_auto_render(&self) -> bool {
-        matches!(self, Self::Latex | Self::Typst)
+        matches!(self, Self::Latex | Self::Mermaid | Self::Typst)
     }
 }
 
@@ -268,6 +384,9 @@ pub(crate) struct CodeAttributes {
     /// Whether the code block should show line numbers.
     pub(crate) line_numbers: bool,
 
+    /// The number of seconds this code can run for when executed, overriding the configured one.
+    pub(crate) timeout_seconds: Option<u64>,
+
     /// The groups of lines to highlight.
     pub(crate) highlight_groups: Vec<HighlightGroup>,
 }
@@ -309,6 +428,9 @@ pub(crate) struct Table {
 
     /// All of the rows in this table, excluding the header.
     pub(crate) rows: Vec<TableRow>,
+
+    /// The alignment of every column.
+    pub(crate) alignments: Vec<TableAlignment>,
 }
 
 impl Table {
@@ -317,6 +439,11 @@ impl Table {
         self.header.0.len()
     }
 
+    /// Gets the alignment of a column.
+    pub(crate) fn alignment(&self, column: usize) -> TableAlignment {
+        self.alignments.get(column).copied().unwrap_or_default()
+    }
+
     /// Iterates all the text entries in a column.
     ///
     /// This includes the header.
@@ -330,3 +457,23 @@ impl Table {
 /// A table row.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct TableRow(pub(crate) Vec<TextBlock>);
+
+/// The alignment of the text in a table column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum TableAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl From<comrak::nodes::TableAlignment> for TableAlignment {
+    fn from(alignment: comrak::nodes::TableAlignment) -> Self {
+        use comrak::nodes::TableAlignment::*;
+        match alignment {
+            None | Left => Self::Left,
+            Center => Self::Center,
+            Right => Self::Right,
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Support for display math blocks delimited by `$$`.
+//!
+//! comrak doesn't know about math so, like fenced divs, these blocks are extracted before the
+//! markdown is parsed: every block is replaced with a placeholder comment which the parser then
+//! swaps for the formula extracted in that position. Both of these are supported, as long as
+//! they're enabled via [MarkdownParser::math_blocks](super::parse::MarkdownParser::math_blocks):
+//!
+//! ```markdown
+//! $$ \sum_{n=1}^{\infty} 2^{-n} = 1 $$
+//!
+//! $$
+//! e^{i \pi} + 1 = 0
+//! $$
+//! ```
+//!
+//! Formulas are rendered the same way as `latex +render` code blocks.
+
+use super::divs::RawBlocks;
+use std::collections::VecDeque;
+
+/// The comment every math block is replaced with.
+pub(crate) const MATH_PLACEHOLDER: &str = "presenterm:math";
+
+// The delimiter at the start and end of a math block.
+const DELIMITER: &str = "$$";
+
+/// The result of extracting the math blocks in a markdown file.
+#[derive(Debug)]
+pub(crate) struct MathBlocks {
+    /// The markdown contents, with every math block replaced.
+    pub(crate) contents: String,
+
+    /// The formulas found, in the order they show up in.
+    pub(crate) formulas: VecDeque<String>,
+}
+
+impl MathBlocks {
+    /// Extract all math blocks in the given markdown.
+    ///
+    /// Blocks are replaced line by line so the line numbers in the output match the input's. A
+    /// block that spans multiple lines must start with a line that only contains the delimiter and
+    /// can't contain empty lines, otherwise it's left as is. This keeps text that happens to start
+    /// with `$$`, like a price, from swallowing everything that follows it.
+    pub(crate) fn extract(contents: &str) -> Self {
+        if !contents.contains(DELIMITER) {
+            return Self { contents: contents.into(), formulas: Default::default() };
+        }
+        let mut output = String::new();
+        let mut formulas = VecDeque::new();
+        let mut raw_blocks = RawBlocks::default();
+        let mut open_block: Option<OpenBlock> = None;
+        for (index, line) in contents.split_inclusive('\n').enumerate() {
+            let (text, line_ending) = line.split_at(line.trim_end_matches(['\r', '\n']).len());
+            if let Some(block) = &mut open_block {
+                if text.trim().is_empty() {
+                    output.extend(block.lines.drain(..));
+                    output.push_str(line);
+                    open_block = None;
+                    continue;
+                }
+                block.lines.push(line);
+                match text.trim_end().strip_suffix(DELIMITER) {
+                    Some(formula) => {
+                        block.formula.push_str(formula);
+                        formulas.push_back(block.formula.trim().to_string());
+                        // The rest of the lines are left empty so the block takes up as many lines as before.
+                        output.push_str(&format!("<!--{MATH_PLACEHOLDER}-->"));
+                        output.extend(block.lines.iter().map(|line| Self::line_ending(line)));
+                        open_block = None;
+                    }
+                    None => block.formula.push_str(line),
+                };
+                continue;
+            }
+            if raw_blocks.skip_line(line, index + 1) {
+                output.push_str(line);
+                continue;
+            }
+            let Some(formula) = Self::block_start(text) else {
+                output.push_str(line);
+                continue;
+            };
+            match formula.trim_end().strip_suffix(DELIMITER) {
+                Some(formula) if !formula.trim().is_empty() => {
+                    formulas.push_back(formula.trim().to_string());
+                    output.push_str(&format!("<!--{MATH_PLACEHOLDER}-->{line_ending}"));
+                }
+                _ if formula.trim().is_empty() => {
+                    open_block = Some(OpenBlock { formula: String::new(), lines: vec![line] })
+                }
+                _ => output.push_str(line),
+            };
+        }
+        if let Some(block) = open_block {
+            output.extend(block.lines);
+        }
+        Self { contents: output, formulas }
+    }
+
+    // Blocks can be indented by up to 3 spaces, just like code blocks.
+    fn block_start(line: &str) -> Option<&str> {
+        let trimmed = line.trim_start();
+        if line.len() - trimmed.len() > 3 {
+            return None;
+        }
+        trimmed.strip_prefix(DELIMITER)
+    }
+
+    fn line_ending(line: &str) -> &str {
+        &line[line.trim_end_matches(['\r', '\n']).len()..]
+    }
+}
+
+struct OpenBlock<'a> {
+    formula: String,
+    lines: Vec<&'a str>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::single_line("$$ x^2 $$\n", "<!--presenterm:math-->\n", "x^2")]
+    #[case::indented("   $$ x^2 $$\n", "<!--presenterm:math-->\n", "x^2")]
+    #[case::multi_line("$$\nx^2\n$$\nafter", "<!--presenterm:math-->\n\n\nafter", "x^2")]
+    #[case::crlf("$$\r\nx^2\r\n$$\r\n", "<!--presenterm:math-->\r\n\r\n\r\n", "x^2")]
+    fn blocks(#[case] input: &str, #[case] expected: &str, #[case] formula: &str) {
+        let output = MathBlocks::extract(input);
+        assert_eq!(output.contents, expected);
+        assert_eq!(output.formulas, &[formula]);
+        assert_eq!(output.contents.lines().count(), input.lines().count());
+    }
+
+    #[rstest]
+    #[case::unclosed("$$\nx^2\n")]
+    #[case::text_after_start("$$ a\nb $$\n")]
+    #[case::price("$$100 is a lot\nfor this\n\n$$\nx^2\n")]
+    #[case::empty_line("$$\nx^2\n\nmore text $$\n")]
+    #[case::inline("costs $$5 or $$6\n")]
+    #[case::too_indented("    $$ x^2 $$\n")]
+    #[case::code_block("```\n$$ x^2 $$\n```\n")]
+    fn ignored(#[case] input: &str) {
+        let output = MathBlocks::extract(input);
+        assert_eq!(output.contents, input);
+        assert!(output.formulas.is_empty());
+    }
+
+    #[test]
+    fn multiple_blocks() {
+        let output = MathBlocks::extract("$$ a $$\n\n$$\nb\n$$\n");
+        assert_eq!(output.formulas, &["a", "b"]);
+    }
+}
@@ -1,4 +1,6 @@
 pub(crate) mod code;
+pub(crate) mod divs;
 pub(crate) mod elements;
+pub(crate) mod math;
 pub(crate) mod parse;
 pub(crate) mod text;
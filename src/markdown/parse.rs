@@ -1,15 +1,25 @@
-use super::{code::CodeBlockParseError, elements::SourcePosition};
+use super::{
+    code::CodeBlockParseError,
+    divs::{FencedDivErrorKind, FencedDivs, FENCED_DIV_PLACEHOLDER},
+    elements::{Code, CodeAttributes, CodeLanguage, LineColumn, SourcePosition},
+    math::{MathBlocks, MATH_PLACEHOLDER},
+};
 use crate::{
     markdown::{
         code::CodeBlockParser,
-        elements::{ListItem, ListItemType, MarkdownElement, ParagraphElement, Table, TableRow, Text, TextBlock},
+        elements::{
+            DescriptionItem, DetailsTag, ListItem, ListItemType, MarkdownElement, ParagraphElement, QuoteLine, Table,
+            TableAlignment, TableRow, Text, TextBlock,
+        },
     },
     style::TextStyle,
+    tools::ThirdPartyTools,
 };
 use comrak::{
     format_commonmark,
     nodes::{
-        AstNode, ListDelimType, ListType, NodeCodeBlock, NodeHeading, NodeHtmlBlock, NodeList, NodeValue, Sourcepos,
+        AstNode, ListDelimType, ListType, NodeCodeBlock, NodeHeading, NodeHtmlBlock, NodeList, NodeTable, NodeValue,
+        Sourcepos,
     },
     parse_document, Arena, ComrakOptions, ListStyleType,
 };
@@ -31,6 +41,7 @@ impl Default for ParserOptions {
         options.extension.table = true;
         options.extension.strikethrough = true;
         options.extension.multiline_block_quotes = true;
+        options.extension.description_lists = true;
         Self(options)
     }
 }
@@ -38,49 +49,167 @@ impl Default for ParserOptions {
 /// A markdown parser.
 ///
 /// This takes the contents of a markdown file and parses it into a list of [MarkdownElement].
+#[derive(Clone)]
 pub struct MarkdownParser<'a> {
     arena: &'a Arena<AstNode<'a>>,
     options: ComrakOptions,
+    math_blocks: bool,
 }
 
 impl<'a> MarkdownParser<'a> {
     /// Construct a new markdown parser.
     pub fn new(arena: &'a Arena<AstNode<'a>>) -> Self {
-        Self { arena, options: ParserOptions::default().0 }
+        Self { arena, options: ParserOptions::default().0, math_blocks: false }
+    }
+
+    /// Enable `$$` delimited display math blocks.
+    pub fn math_blocks(mut self, enabled: bool) -> Self {
+        self.math_blocks = enabled;
+        self
     }
 
     /// Parse the contents of a markdown file.
     pub(crate) fn parse(&self, contents: &str) -> ParseResult<Vec<MarkdownElement>> {
-        let node = parse_document(self.arena, contents, &self.options);
+        let FencedDivs { contents, mut divs } = FencedDivs::extract(contents).map_err(|e| {
+            let sourcepos = SourcePosition { start: LineColumn { line: e.line, column: 1 } };
+            ParseErrorKind::InvalidFencedDiv(e.kind).with_sourcepos(sourcepos)
+        })?;
+        let MathBlocks { contents, mut formulas } = match self.math_blocks {
+            true => MathBlocks::extract(&contents),
+            false => MathBlocks { contents, formulas: Default::default() },
+        };
+        // Formulas are displayed as is if the tools needed to render them aren't installed.
+        let render_math =
+            !formulas.is_empty() && ThirdPartyTools::is_installed("pandoc") && ThirdPartyTools::is_installed("typst");
+        let node = parse_document(self.arena, &contents, &self.options);
         let mut elements = Vec::new();
         let mut lines_offset = 0;
         for node in node.children() {
             let mut parsed_elements =
-                Self::parse_node(node).map_err(|e| ParseError::new(e.kind, e.sourcepos.offset_lines(lines_offset)))?;
+                self.parse_block(node).map_err(|e| ParseError::new(e.kind, e.sourcepos.offset_lines(lines_offset)))?;
+            // Fences were replaced with placeholders before parsing so swap them back.
+            for element in &mut parsed_elements {
+                if matches!(element, MarkdownElement::Comment { comment, .. } if comment == FENCED_DIV_PLACEHOLDER) {
+                    let div = divs.pop_front().ok_or_else(|| {
+                        ParseErrorKind::Internal("fenced div not found".into())
+                            .with_sourcepos(node.data.borrow().sourcepos)
+                    })?;
+                    *element = MarkdownElement::FencedDiv(div);
+                }
+                if matches!(element, MarkdownElement::Comment { comment, .. } if comment == MATH_PLACEHOLDER) {
+                    let formula = formulas.pop_front().ok_or_else(|| {
+                        ParseErrorKind::Internal("math block not found".into())
+                            .with_sourcepos(node.data.borrow().sourcepos)
+                    })?;
+                    *element = Self::math_block(formula, render_math);
+                }
+            }
             if let Some(MarkdownElement::FrontMatter(contents)) = parsed_elements.first() {
                 lines_offset += contents.lines().count() + 2;
             }
             // comrak ignores the lines in the front matter so we need to offset this ourselves.
             Self::adjust_source_positions(parsed_elements.iter_mut(), lines_offset);
+            // Compact description list items are parsed one at a time so contiguous ones are merged.
+            for element in parsed_elements {
+                match (elements.last_mut(), element) {
+                    (Some(MarkdownElement::DescriptionList(items)), MarkdownElement::DescriptionList(next)) => {
+                        items.extend(next)
+                    }
+                    (_, element) => elements.push(element),
+                };
+            }
+        }
+        Ok(elements)
+    }
+
+    // Fences and math blocks are replaced with comments before parsing, which end up inside the html block right before
+    // them, like a `<details>` tag, unless there's an empty line in between. Those blocks are split around every
+    // placeholder and whatever's in between is parsed again on its own.
+    fn parse_block(&self, node: &'a AstNode<'a>) -> ParseResult<Vec<MarkdownElement>> {
+        let data = node.data.borrow();
+        let literal = match &data.value {
+            // A block that's only a placeholder is swapped as is.
+            NodeValue::HtmlBlock(block) if block.literal.trim().lines().count() > 1 => block.literal.clone(),
+            _ => return Self::parse_node(node),
+        };
+        let start_line = data.sourcepos.start.line;
+        drop(data);
+        if !literal.lines().any(is_placeholder) {
+            return Self::parse_node(node);
+        }
+        let mut elements = Vec::new();
+        let mut segment = String::new();
+        let mut segment_start = start_line;
+        for (index, line) in literal.lines().enumerate() {
+            if !is_placeholder(line) {
+                segment.push_str(line);
+                segment.push('\n');
+                continue;
+            }
+            elements.extend(self.parse_segment(&mem::take(&mut segment), segment_start)?);
+            let line = line.trim();
+            let comment = line["<!--".len()..line.len() - "-->".len()].to_string();
+            let source_position = SourcePosition { start: LineColumn { line: start_line + index, column: 1 } };
+            elements.push(MarkdownElement::Comment { comment, source_position });
+            segment_start = start_line + index + 1;
+        }
+        elements.extend(self.parse_segment(&segment, segment_start)?);
+        Ok(elements)
+    }
+
+    // Parse a piece of a document that starts at the given line.
+    fn parse_segment(&self, contents: &str, first_line: usize) -> ParseResult<Vec<MarkdownElement>> {
+        let lines_offset = first_line.saturating_sub(1);
+        let node = parse_document(self.arena, contents, &self.options);
+        let mut elements = Vec::new();
+        for node in node.children() {
+            let mut parsed_elements =
+                self.parse_block(node).map_err(|e| ParseError::new(e.kind, e.sourcepos.offset_lines(lines_offset)))?;
+            Self::adjust_source_positions(parsed_elements.iter_mut(), lines_offset);
             elements.extend(parsed_elements);
         }
         Ok(elements)
     }
 
+    // Math blocks are rendered just like a `latex +render` code block containing the formula.
+    fn math_block(formula: String, render: bool) -> MarkdownElement {
+        let (contents, auto_render) = match render {
+            true => (format!("\\[ {formula} \\]"), true),
+            false => (formula, false),
+        };
+        MarkdownElement::Code(Code {
+            contents,
+            language: CodeLanguage::Latex,
+            language_name: "latex".into(),
+            attributes: CodeAttributes { auto_render, ..Default::default() },
+        })
+    }
+
+    /// Parse the contents of a markdown file, discarding the parsed elements.
+    ///
+    /// This only exists so the parser can be fuzzed from outside this crate.
+    #[doc(hidden)]
+    pub fn validate(&self, contents: &str) -> Result<(), ParseError> {
+        self.parse(contents).map(|_| ())
+    }
+
     fn adjust_source_positions<'b>(elements: impl Iterator<Item = &'b mut MarkdownElement>, lines_offset: usize) {
         for element in elements {
             let position = match element {
                 MarkdownElement::FrontMatter(_)
-                | MarkdownElement::SetexHeading { .. }
-                | MarkdownElement::Heading { .. }
                 | MarkdownElement::Paragraph(_)
-                | MarkdownElement::Image { .. }
                 | MarkdownElement::List(_)
+                | MarkdownElement::DescriptionList(_)
                 | MarkdownElement::Code(_)
                 | MarkdownElement::Table(_)
-                | MarkdownElement::ThematicBreak
-                | MarkdownElement::BlockQuote(_) => continue,
-                MarkdownElement::Comment { source_position, .. } => source_position,
+                | MarkdownElement::BlockQuote(_)
+                | MarkdownElement::FencedDiv(_)
+                | MarkdownElement::Details(_) => continue,
+                MarkdownElement::Comment { source_position, .. }
+                | MarkdownElement::SetexHeading { source_position, .. }
+                | MarkdownElement::Heading { source_position, .. }
+                | MarkdownElement::Image { source_position, .. }
+                | MarkdownElement::ThematicBreak { source_position } => source_position,
             };
             *position = position.offset_lines(lines_offset);
         }
@@ -89,18 +218,22 @@ impl<'a> MarkdownParser<'a> {
     fn parse_node(node: &'a AstNode<'a>) -> ParseResult<Vec<MarkdownElement>> {
         let data = node.data.borrow();
         let element = match &data.value {
-            // Paragraphs are the only ones that can actually yield more than one.
-            NodeValue::Paragraph => return Self::parse_paragraph(node),
+            NodeValue::Paragraph => match Self::parse_compact_description_item(node)? {
+                Some(item) => MarkdownElement::DescriptionList(vec![item]),
+                // Paragraphs are the only ones that can actually yield more than one.
+                None => return Self::parse_paragraph(node),
+            },
             NodeValue::FrontMatter(contents) => Self::parse_front_matter(contents)?,
             NodeValue::Heading(heading) => Self::parse_heading(heading, node)?,
             NodeValue::List(list) => {
-                let items = Self::parse_list(node, list.marker_offset as u8 / 2)?;
+                let items = Self::parse_list(node, list, list.marker_offset as u8 / 2)?;
                 MarkdownElement::List(items)
             }
-            NodeValue::Table(_) => Self::parse_table(node)?,
+            NodeValue::DescriptionList => return Self::parse_description_list(node),
+            NodeValue::Table(table) => Self::parse_table(node, table)?,
             NodeValue::CodeBlock(block) => Self::parse_code_block(block, data.sourcepos)?,
-            NodeValue::ThematicBreak => MarkdownElement::ThematicBreak,
-            NodeValue::HtmlBlock(block) => Self::parse_html_block(block, data.sourcepos)?,
+            NodeValue::ThematicBreak => MarkdownElement::ThematicBreak { source_position: data.sourcepos.into() },
+            NodeValue::HtmlBlock(block) => return Self::parse_html_block(block, data.sourcepos),
             NodeValue::BlockQuote | NodeValue::MultilineBlockQuote(_) => Self::parse_block_quote(node)?,
             other => return Err(ParseErrorKind::UnsupportedElement(other.identifier()).with_sourcepos(data.sourcepos)),
         };
@@ -119,22 +252,34 @@ impl<'a> MarkdownParser<'a> {
         Ok(MarkdownElement::FrontMatter(contents.into()))
     }
 
-    fn parse_html_block(block: &NodeHtmlBlock, sourcepos: Sourcepos) -> ParseResult<MarkdownElement> {
+    fn parse_html_block(block: &NodeHtmlBlock, sourcepos: Sourcepos) -> ParseResult<Vec<MarkdownElement>> {
         let block = block.literal.trim();
         let start_tag = "<!--";
         let end_tag = "-->";
         if !block.starts_with(start_tag) || !block.ends_with(end_tag) {
-            return Err(ParseErrorKind::UnsupportedElement("html block").with_sourcepos(sourcepos));
+            return match parse_details_tags(block) {
+                Some(tags) => Ok(tags.into_iter().map(MarkdownElement::Details).collect()),
+                None => Err(ParseErrorKind::UnsupportedElement("html block").with_sourcepos(sourcepos)),
+            };
         }
         let block = &block[start_tag.len()..];
         let block = &block[0..block.len() - end_tag.len()];
-        Ok(MarkdownElement::Comment { comment: block.into(), source_position: sourcepos.into() })
+        Ok(vec![MarkdownElement::Comment { comment: block.into(), source_position: sourcepos.into() }])
     }
 
     fn parse_block_quote(node: &'a AstNode<'a>) -> ParseResult<MarkdownElement> {
         // This renders the contents of this block quote AST as commonmark, given we otherwise
         // would need to either do this outselves or pull the raw block contents off of the
         // original raw string and that also isn't great.
+        // `format_commonmark` would escape control characters so they're removed beforehand. The
+        // block quote node itself is skipped as it's already borrowed.
+        for descendant in node.descendants().skip(1) {
+            match &mut descendant.data.borrow_mut().value {
+                NodeValue::Text(text) => *text = sanitize_text(text),
+                NodeValue::Code(code) => code.literal = sanitize_text(&code.literal),
+                _ => (),
+            };
+        }
         let mut buffer = BufWriter::new(Vec::new());
         let mut options = ParserOptions::default().0;
         options.render.list_style = ListStyleType::Star;
@@ -144,18 +289,22 @@ impl<'a> MarkdownParser<'a> {
         let buffer = buffer.into_inner().expect("unwrapping writer failed");
         let mut lines = Vec::new();
         for line in String::from_utf8_lossy(&buffer).lines() {
-            let line = match line.find('>') {
-                Some(index) => line[index + 1..].trim(),
-                None => line,
-            };
-            let mut line = line.to_string();
+            // Every `>` marker at the start of the line is a quote this line is in, the first
+            // one being this block quote itself.
+            let mut line = line.trim_start();
+            let mut depth: u8 = 0;
+            while let Some(rest) = line.strip_prefix('>') {
+                line = rest.trim_start();
+                depth += 1;
+            }
+            let mut line = line.trim_end().to_string();
             // `format_commonmark` escapes these symbols so we un-escape them.
             for escape in &["\\*", "\\!", "\\[", "\\]", "\\#", "\\`", "\\<", "\\>"] {
                 if line.contains(escape) {
                     line = line.replace(escape, &escape[1..]);
                 }
             }
-            lines.push(line);
+            lines.push(QuoteLine { text: line, depth: depth.saturating_sub(1) });
         }
         Ok(MarkdownElement::BlockQuote(lines))
     }
@@ -171,10 +320,11 @@ impl<'a> MarkdownParser<'a> {
 
     fn parse_heading(heading: &NodeHeading, node: &'a AstNode<'a>) -> ParseResult<MarkdownElement> {
         let text = Self::parse_text(node)?;
+        let source_position = node.data.borrow().sourcepos.into();
         if heading.setext {
-            Ok(MarkdownElement::SetexHeading { text })
+            Ok(MarkdownElement::SetexHeading { text, source_position })
         } else {
-            Ok(MarkdownElement::Heading { text, level: heading.level })
+            Ok(MarkdownElement::Heading { text, level: heading.level, source_position })
         }
     }
 
@@ -186,11 +336,11 @@ impl<'a> MarkdownParser<'a> {
             match inline {
                 Inline::Text(text) => paragraph_elements.push(ParagraphElement::Text(text)),
                 Inline::LineBreak => paragraph_elements.push(ParagraphElement::LineBreak),
-                Inline::Image(path) => {
+                Inline::Image { path, alt_text, source_position } => {
                     if !paragraph_elements.is_empty() {
                         elements.push(MarkdownElement::Paragraph(mem::take(&mut paragraph_elements)));
                     }
-                    elements.push(MarkdownElement::Image { path: path.into() });
+                    elements.push(MarkdownElement::Image { path: path.into(), alt_text, source_position });
                 }
             }
         }
@@ -202,6 +352,10 @@ impl<'a> MarkdownParser<'a> {
 
     fn parse_text(node: &'a AstNode<'a>) -> ParseResult<TextBlock> {
         let inlines = InlinesParser::default().parse(node)?;
+        Self::inlines_to_text(inlines, node)
+    }
+
+    fn inlines_to_text(inlines: Vec<Inline>, node: &'a AstNode<'a>) -> ParseResult<TextBlock> {
         let mut chunks = Vec::new();
         for inline in inlines {
             match inline {
@@ -215,13 +369,77 @@ impl<'a> MarkdownParser<'a> {
         Ok(TextBlock(chunks))
     }
 
-    fn parse_list(root: &'a AstNode<'a>, depth: u8) -> ParseResult<Vec<ListItem>> {
+    fn parse_description_list(root: &'a AstNode<'a>) -> ParseResult<Vec<MarkdownElement>> {
+        // Definitions can only hold text so anything else, like `: - item`, is parsed as if this wasn't a
+        // description list, which is what it was before they were supported.
+        let contents: Vec<_> =
+            root.children().flat_map(|item| item.children()).flat_map(|node| node.children()).collect();
+        if contents.iter().any(|node| !matches!(node.data.borrow().value, NodeValue::Paragraph)) {
+            let mut elements = Vec::new();
+            for node in contents {
+                elements.extend(Self::parse_node(node)?);
+            }
+            return Ok(elements);
+        }
+        let mut items = Vec::new();
+        for item in root.children() {
+            let mut term = TextBlock(Vec::new());
+            let mut definitions = Vec::new();
+            for node in item.children() {
+                let is_term = matches!(node.data.borrow().value, NodeValue::DescriptionTerm);
+                for node in node.children() {
+                    let text = Self::parse_text(node)?;
+                    match is_term {
+                        true => term.0.extend(text.0),
+                        false => definitions.push(text),
+                    };
+                }
+            }
+            items.push(DescriptionItem { term, definitions });
+        }
+        Ok(vec![MarkdownElement::DescriptionList(items)])
+    }
+
+    // comrak only parses description lists that have an empty line between the term and its
+    // definition so the compact form, where every line after the first one in a paragraph starts
+    // with `: `, is handled here.
+    fn parse_compact_description_item(node: &'a AstNode<'a>) -> ParseResult<Option<DescriptionItem>> {
+        let mut lines = vec![Vec::new()];
+        for child in node.children() {
+            match &child.data.borrow().value {
+                NodeValue::SoftBreak => lines.push(Vec::new()),
+                NodeValue::LineBreak | NodeValue::Image(_) => return Ok(None),
+                _ => lines.last_mut().expect("no lines").push(child),
+            };
+        }
+        let is_definition = |line: &Vec<&AstNode>| {
+            let Some(node) = line.first() else { return false };
+            matches!(&node.data.borrow().value, NodeValue::Text(text) if text.starts_with(": "))
+        };
+        if lines.len() < 2 || is_definition(&lines[0]) || !lines[1..].iter().all(is_definition) {
+            return Ok(None);
+        }
+        let mut texts = Vec::new();
+        for line in lines {
+            let inlines = InlinesParser::default().parse_nodes(&line)?;
+            texts.push(Self::inlines_to_text(inlines, node)?);
+        }
+        let term = texts.remove(0);
+        for definition in &mut texts {
+            if let Some(first) = definition.0.first_mut() {
+                first.content = first.content[1..].trim_start().to_string();
+            }
+        }
+        Ok(Some(DescriptionItem { term, definitions: texts }))
+    }
+
+    fn parse_list(root: &'a AstNode<'a>, list: &NodeList, depth: u8) -> ParseResult<Vec<ListItem>> {
         let mut elements = Vec::new();
         for node in root.children() {
             let data = node.data.borrow();
             match &data.value {
-                NodeValue::Item(item) => {
-                    elements.extend(Self::parse_list_item(item, node, depth)?);
+                NodeValue::Item(_) => {
+                    elements.extend(Self::parse_list_item(list, node, depth)?);
                 }
                 other => {
                     return Err(ParseErrorKind::UnsupportedStructure {
@@ -235,11 +453,12 @@ impl<'a> MarkdownParser<'a> {
         Ok(elements)
     }
 
-    fn parse_list_item(item: &NodeList, root: &'a AstNode<'a>, depth: u8) -> ParseResult<Vec<ListItem>> {
-        let item_type = match (item.list_type, item.delimiter) {
+    fn parse_list_item(list: &NodeList, root: &'a AstNode<'a>, depth: u8) -> ParseResult<Vec<ListItem>> {
+        let start = list.start;
+        let item_type = match (list.list_type, list.delimiter) {
             (ListType::Bullet, _) => ListItemType::Unordered,
-            (ListType::Ordered, ListDelimType::Paren) => ListItemType::OrderedParens,
-            (ListType::Ordered, ListDelimType::Period) => ListItemType::OrderedPeriod,
+            (ListType::Ordered, ListDelimType::Paren) => ListItemType::OrderedParens { start },
+            (ListType::Ordered, ListDelimType::Period) => ListItemType::OrderedPeriod { start },
         };
         let mut elements = Vec::new();
         for node in root.children() {
@@ -249,8 +468,8 @@ impl<'a> MarkdownParser<'a> {
                     let contents = Self::parse_text(node)?;
                     elements.push(ListItem { contents, depth, item_type: item_type.clone() });
                 }
-                NodeValue::List(_) => {
-                    elements.extend(Self::parse_list(node, depth + 1)?);
+                NodeValue::List(list) => {
+                    elements.extend(Self::parse_list(node, list, depth + 1)?);
                 }
                 other => {
                     return Err(ParseErrorKind::UnsupportedStructure {
@@ -264,7 +483,7 @@ impl<'a> MarkdownParser<'a> {
         Ok(elements)
     }
 
-    fn parse_table(node: &'a AstNode<'a>) -> ParseResult<MarkdownElement> {
+    fn parse_table(node: &'a AstNode<'a>, table: &NodeTable) -> ParseResult<MarkdownElement> {
         let mut header = TableRow(Vec::new());
         let mut rows = Vec::new();
         for node in node.children() {
@@ -283,7 +502,8 @@ impl<'a> MarkdownParser<'a> {
                 rows.push(row)
             }
         }
-        Ok(MarkdownElement::Table(Table { header, rows }))
+        let alignments = table.alignments.iter().copied().map(TableAlignment::from).collect();
+        Ok(MarkdownElement::Table(Table { header, rows, alignments }))
     }
 
     fn parse_table_row(node: &'a AstNode<'a>) -> ParseResult<TableRow> {
@@ -317,21 +537,41 @@ impl InlinesParser {
         Ok(self.inlines)
     }
 
+    fn parse_nodes<'a>(mut self, nodes: &[&'a AstNode<'a>]) -> ParseResult<Vec<Inline>> {
+        for node in nodes {
+            self.process_node(node, TextStyle::default())?;
+        }
+        self.store_pending_text();
+        Ok(self.inlines)
+    }
+
     fn store_pending_text(&mut self) {
-        let chunks = mem::take(&mut self.pending_text);
+        let chunks = Self::merge_chunks(mem::take(&mut self.pending_text));
         if !chunks.is_empty() {
             self.inlines.push(Inline::Text(TextBlock(chunks)));
         }
     }
 
+    // Nested emphasis, like `**a __b__**`, and soft breaks split text into chunks that are styled
+    // the same. Merging them makes the same text yield the same chunks no matter how it's spelled.
+    fn merge_chunks(chunks: Vec<Text>) -> Vec<Text> {
+        let mut merged: Vec<Text> = Vec::new();
+        for chunk in chunks {
+            match merged.last_mut() {
+                _ if chunk.content.is_empty() => (),
+                Some(last) if last.style == chunk.style => last.content.push_str(&chunk.content),
+                _ => merged.push(chunk),
+            };
+        }
+        merged
+    }
+
     fn process_node<'a>(&mut self, node: &'a AstNode<'a>, style: TextStyle) -> ParseResult<()> {
         let data = node.data.borrow();
         match &data.value {
-            NodeValue::Text(text) => {
-                self.pending_text.push(Text::new(text.clone(), style.clone()));
-            }
+            NodeValue::Text(text) => self.process_text(text, &style),
             NodeValue::Code(code) => {
-                self.pending_text.push(Text::new(code.literal.clone(), TextStyle::default().code()));
+                self.pending_text.push(Text::new(sanitize_text(&code.literal), TextStyle::default().code()));
             }
             NodeValue::Strong => self.process_children(node, style.clone().bold())?,
             NodeValue::Emph => self.process_children(node, style.clone().italics())?,
@@ -344,9 +584,38 @@ impl InlinesParser {
             }
             NodeValue::Image(link) => {
                 self.store_pending_text();
-                self.inlines.push(Inline::Image(link.url.clone()));
+                let alt_text = Self::collect_alt_text(node);
+                let source_position = data.sourcepos.into();
+                self.inlines.push(Inline::Image { path: link.url.clone(), alt_text, source_position });
             }
-            other => {
+            // We have no way of styling these so we simply display their contents.
+            NodeValue::Superscript => self.process_children(node, style)?,
+            NodeValue::FootnoteReference(reference) => {
+                self.pending_text.push(Text::new(format!("[^{}]", reference.name), style.clone()));
+            }
+            // Inline html can't be rendered in a terminal so it's dropped, keeping any text around it.
+            NodeValue::HtmlInline(_) => (),
+            // Block elements can't show up inside text.
+            other @ (NodeValue::Document
+            | NodeValue::FrontMatter(_)
+            | NodeValue::BlockQuote
+            | NodeValue::MultilineBlockQuote(_)
+            | NodeValue::List(_)
+            | NodeValue::Item(_)
+            | NodeValue::TaskItem(_)
+            | NodeValue::DescriptionList
+            | NodeValue::DescriptionItem(_)
+            | NodeValue::DescriptionTerm
+            | NodeValue::DescriptionDetails
+            | NodeValue::CodeBlock(_)
+            | NodeValue::HtmlBlock(_)
+            | NodeValue::Paragraph
+            | NodeValue::Heading(_)
+            | NodeValue::ThematicBreak
+            | NodeValue::FootnoteDefinition(_)
+            | NodeValue::Table(_)
+            | NodeValue::TableRow(_)
+            | NodeValue::TableCell) => {
                 return Err(ParseErrorKind::UnsupportedStructure { container: "text", element: other.identifier() }
                     .with_sourcepos(data.sourcepos));
             }
@@ -360,11 +629,119 @@ impl InlinesParser {
         }
         Ok(())
     }
+
+    // The alt text of an image is made up of its children, which can be styled like any other text.
+    fn collect_alt_text<'a>(node: &'a AstNode<'a>) -> String {
+        node.descendants()
+            .filter_map(|node| match &node.data.borrow().value {
+                NodeValue::Text(text) => Some(text.clone()),
+                NodeValue::Code(code) => Some(code.literal.clone()),
+                NodeValue::SoftBreak | NodeValue::LineBreak => Some(" ".into()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Wikilinks (`[[Page Name]]`) aren't standard markdown so they're left as text by comrak. These
+    // are displayed as links to the page they point to.
+    fn process_text(&mut self, mut text: &str, style: &TextStyle) {
+        while let Some((before, page, after)) = split_wikilink(text) {
+            if !before.is_empty() {
+                self.pending_text.push(Text::new(sanitize_text(before), style.clone()));
+            }
+            self.pending_text.push(Text::new(sanitize_text(page), TextStyle::default().link()));
+            text = after;
+        }
+        if !text.is_empty() {
+            self.pending_text.push(Text::new(sanitize_text(text), style.clone()));
+        }
+    }
+}
+
+/// Check whether a line is one of the comments fenced divs and math blocks are replaced with before parsing.
+fn is_placeholder(line: &str) -> bool {
+    let Some(comment) = line.trim().strip_prefix("<!--").and_then(|line| line.strip_suffix("-->")) else {
+        return false;
+    };
+    comment == FENCED_DIV_PLACEHOLDER || comment == MATH_PLACEHOLDER
+}
+
+/// Parse the `<details>` related tags in an HTML block.
+///
+/// Blocks can contain the start of a `<details>` block, optionally followed by its `<summary>`,
+/// and/or its end. Anything else in them means this isn't something we can display.
+fn parse_details_tags(block: &str) -> Option<Vec<DetailsTag>> {
+    let mut tags = Vec::new();
+    let mut rest = block.trim();
+    if let Some(start) = strip_tag_prefix(rest, "<details") {
+        let (attributes, after) = start.split_once('>')?;
+        let open = attributes.split_whitespace().any(|attribute| attribute.eq_ignore_ascii_case("open"));
+        if !attributes.is_empty() && !attributes.starts_with(char::is_whitespace) {
+            return None;
+        }
+        rest = after.trim_start();
+        let mut summary = "Details".to_string();
+        if let Some(start) = strip_tag_prefix(rest, "<summary>") {
+            let end = start.to_ascii_lowercase().find("</summary>")?;
+            summary = start[..end].split_whitespace().collect::<Vec<_>>().join(" ");
+            rest = start[end + "</summary>".len()..].trim_start();
+        }
+        tags.push(DetailsTag::Start { summary, open });
+    }
+    if let Some(after) = strip_tag_prefix(rest, "</details>") {
+        tags.push(DetailsTag::End);
+        rest = after.trim_start();
+    }
+    if tags.is_empty() || !rest.is_empty() {
+        return None;
+    }
+    Some(tags)
+}
+
+// Strip an HTML tag from the start of a string, ignoring its case.
+fn strip_tag_prefix<'a>(text: &'a str, tag: &str) -> Option<&'a str> {
+    let prefix = text.get(..tag.len())?;
+    prefix.eq_ignore_ascii_case(tag).then(|| &text[tag.len()..])
+}
+
+/// Find the first wikilink in a piece of text.
+///
+/// Returns the text before it, the name of the page it points to, and the text after it.
+fn split_wikilink(text: &str) -> Option<(&str, &str, &str)> {
+    let mut offset = 0;
+    loop {
+        let start = offset + text[offset..].find("[[")?;
+        let name_start = start + 2;
+        let name_end = name_start + text[name_start..].find("]]")?;
+        let page = &text[name_start..name_end];
+        // A page name can't be empty nor contain brackets, in which case we keep looking after
+        // the opening brackets.
+        if page.trim().is_empty() || page.contains(['[', ']']) {
+            offset = start + 1;
+            continue;
+        }
+        return Some((&text[..start], page.trim(), &text[name_end + 2..]));
+    }
+}
+
+/// Sanitize a piece of text so it can be printed as is.
+///
+/// Control characters would otherwise get to the terminal and make the cursor end up somewhere
+/// other than where we think it is. Tabs are turned into a single space and any other control
+/// character is removed.
+fn sanitize_text(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c {
+            '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect()
 }
 
 enum Inline {
     Text(TextBlock),
-    Image(String),
+    Image { path: String, alt_text: String, source_position: SourcePosition },
     LineBreak,
 }
 
@@ -372,7 +749,7 @@ impl Inline {
     fn kind(&self) -> &'static str {
         match self {
             Self::Text(_) => "text",
-            Self::Image(_) => "image",
+            Self::Image { .. } => "image",
             Self::LineBreak => "line break",
         }
     }
@@ -415,6 +792,9 @@ pub(crate) enum ParseErrorKind {
     /// A code block contains invalid attributes.
     InvalidCodeBlock(CodeBlockParseError),
 
+    /// A fenced div is invalid.
+    InvalidFencedDiv(FencedDivErrorKind),
+
     /// An internal parsing error.
     Internal(String),
 }
@@ -428,6 +808,7 @@ impl Display for ParseErrorKind {
             }
             Self::UnfencedCodeBlock => write!(f, "only fenced code blocks are supported"),
             Self::InvalidCodeBlock(error) => write!(f, "invalid code block: {error}"),
+            Self::InvalidFencedDiv(error) => write!(f, "invalid fenced div: {error}"),
             Self::Internal(message) => write!(f, "internal error: {message}"),
         }
     }
@@ -487,7 +868,7 @@ mod test {
     use rstest::rstest;
 
     use super::*;
-    use crate::markdown::elements::CodeLanguage;
+    use crate::markdown::{divs::FencedDiv, elements::CodeLanguage};
     use std::path::Path;
 
     fn parse_single(input: &str) -> MarkdownElement {
@@ -538,6 +919,130 @@ boop
         assert_eq!(elements, expected_elements);
     }
 
+    #[rstest]
+    #[case::named("A &amp; B", "A & B")]
+    #[case::less_than("&lt;br&gt;", "<br>")]
+    #[case::hex("&#x1F600;", "😀")]
+    #[case::decimal("&#169; 2024", "© 2024")]
+    #[case::unknown("&potato;", "&potato;")]
+    fn html_entities(#[case] input: &str, #[case] expected: &str) {
+        let parsed = parse_single(input);
+        let MarkdownElement::Paragraph(elements) = parsed else { panic!("not a paragraph: {parsed:?}") };
+        let expected = &[ParagraphElement::Text(TextBlock(vec![Text::from(expected)]))];
+        assert_eq!(elements, expected);
+    }
+
+    #[test]
+    fn soft_break() {
+        let parsed = parse_single("hello\nthere");
+        let MarkdownElement::Paragraph(elements) = parsed else { panic!("not a paragraph: {parsed:?}") };
+        let expected_chunks = vec![Text::from("hello there")];
+        assert_eq!(elements, &[ParagraphElement::Text(TextBlock(expected_chunks))]);
+    }
+
+    #[rstest]
+    #[case::bold(&["**a b c**", "__a b c__", "**a __b__ c**", "__a **b** c__"], TextStyle::default().bold())]
+    #[case::italics(&["*a b c*", "_a b c_", "*a _b_ c*", "_a *b* c_"], TextStyle::default().italics())]
+    #[case::bold_italics(
+        &["***a b c***", "___a b c___", "**_a b c_**", "_**a b c**_", "*__a b c__*", "__*a b c*__", "***a _b_ c***"],
+        TextStyle::default().bold().italics()
+    )]
+    #[case::strikethrough(&["~~a b c~~", "~a b c~", "~~a ~~b~~ c~~"], TextStyle::default().strikethrough())]
+    fn equivalent_emphasis(#[case] inputs: &[&str], #[case] style: TextStyle) {
+        let expected = [ParagraphElement::Text(Text::new("a b c", style).into())];
+        for input in inputs {
+            let parsed = parse_single(input);
+            let MarkdownElement::Paragraph(elements) = parsed else { panic!("not a paragraph: {parsed:?}") };
+            assert_eq!(elements, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn emphasis_chunks() {
+        let parsed = parse_single("a **b _c_** d\ne");
+        let MarkdownElement::Paragraph(elements) = parsed else { panic!("not a paragraph: {parsed:?}") };
+        let expected_chunks = vec![
+            Text::from("a "),
+            Text::new("b ", TextStyle::default().bold()),
+            Text::new("c", TextStyle::default().bold().italics()),
+            Text::from(" d e"),
+        ];
+        assert_eq!(elements, &[ParagraphElement::Text(TextBlock(expected_chunks))]);
+    }
+
+    #[rstest]
+    #[case::single("~a~ b", &[Text::new("a", TextStyle::default().strikethrough()), Text::from(" b")])]
+    #[case::double("~~a~~ b", &[Text::new("a", TextStyle::default().strikethrough()), Text::from(" b")])]
+    // Runs of tildes only close a run of the same length.
+    #[case::mismatched("~a~~ b", &[Text::from("~a~~ b")])]
+    fn tilde_strikethrough(#[case] input: &str, #[case] expected: &[Text]) {
+        let parsed = parse_single(input);
+        let MarkdownElement::Paragraph(elements) = parsed else { panic!("not a paragraph: {parsed:?}") };
+        assert_eq!(elements, &[ParagraphElement::Text(TextBlock(expected.to_vec()))]);
+    }
+
+    #[rstest]
+    #[case::tab("hello\tthere", "hello there")]
+    #[case::carriage_return("hello\rthere", "hello there")]
+    #[case::bell("hello\x07 there", "hello there")]
+    #[case::escape_sequence("hello \x1b[31mthere", "hello [31mthere")]
+    #[case::delete("hello\x7f there", "hello there")]
+    fn control_characters(#[case] input: &str, #[case] expected: &str) {
+        let parsed = parse_single(input);
+        let MarkdownElement::Paragraph(elements) = parsed else { panic!("not a paragraph: {parsed:?}") };
+        let [ParagraphElement::Text(text)] = elements.as_slice() else { panic!("unexpected elements: {elements:?}") };
+        let contents: String = text.0.iter().map(|text| text.content.as_str()).collect();
+        assert_eq!(contents, expected);
+        assert_eq!(text.width(), expected.len());
+    }
+
+    #[test]
+    fn control_characters_in_heading() {
+        let parsed = parse_single("# hi\tthere\x1b");
+        let MarkdownElement::Heading { text, .. } = parsed else { panic!("not a heading: {parsed:?}") };
+        assert_eq!(text, TextBlock(vec![Text::from("hi there")]));
+    }
+
+    #[test]
+    fn control_characters_in_block_quote() {
+        let parsed = parse_single("> hi\tthere\x07");
+        let MarkdownElement::BlockQuote(lines) = parsed else { panic!("not a block quote: {parsed:?}") };
+        assert_eq!(lines, &[QuoteLine { text: "hi there".into(), depth: 0 }]);
+    }
+
+    #[test]
+    fn inline_html_ignored() {
+        let parsed = parse_single("some <span>html</span> here");
+        let MarkdownElement::Paragraph(elements) = parsed else { panic!("not a paragraph: {parsed:?}") };
+        let expected_chunks = vec![Text::from("some html here")];
+        assert_eq!(elements, &[ParagraphElement::Text(TextBlock(expected_chunks))]);
+    }
+
+    #[test]
+    fn footnote_reference() {
+        let arena = Arena::new();
+        let mut options = ParserOptions::default().0;
+        options.extension.footnotes = true;
+        // Footnote definitions aren't supported so only parse the paragraph that references it.
+        let root = parse_document(&arena, "hello[^1]\n\n[^1]: a footnote", &options);
+        let paragraph = root.first_child().expect("no children");
+        let parsed = MarkdownParser::parse_node(paragraph).expect("parse failed");
+        let MarkdownElement::Paragraph(elements) = &parsed[0] else { panic!("not a paragraph: {parsed:?}") };
+        let expected_chunks = vec![Text::from("hello[^1]")];
+        assert_eq!(elements, &[ParagraphElement::Text(TextBlock(expected_chunks))]);
+    }
+
+    #[test]
+    fn superscript() {
+        let arena = Arena::new();
+        let mut parser = MarkdownParser::new(&arena);
+        parser.options.extension.superscript = true;
+        let parsed = parser.parse("e = mc^2^").expect("parse failed");
+        let MarkdownElement::Paragraph(elements) = &parsed[0] else { panic!("not a paragraph: {parsed:?}") };
+        let expected_chunks = vec![Text::from("e = mc2")];
+        assert_eq!(elements, &[ParagraphElement::Text(TextBlock(expected_chunks))]);
+    }
+
     #[test]
     fn link() {
         let parsed = parse_single("my [website](https://example.com)");
@@ -548,6 +1053,34 @@ boop
         assert_eq!(elements, expected_elements);
     }
 
+    #[test]
+    fn wikilinks() {
+        let parsed = parse_single("see [[Page Name]] and [[ Other ]].");
+        let MarkdownElement::Paragraph(elements) = parsed else { panic!("not a paragraph: {parsed:?}") };
+        let expected_chunks = vec![
+            Text::from("see "),
+            Text::new("Page Name", TextStyle::default().link()),
+            Text::from(" and "),
+            Text::new("Other", TextStyle::default().link()),
+            Text::from("."),
+        ];
+        let expected_elements = &[ParagraphElement::Text(TextBlock(expected_chunks))];
+        assert_eq!(elements, expected_elements);
+    }
+
+    #[rstest]
+    #[case::unclosed("[[potato")]
+    #[case::empty("[[]]")]
+    #[case::nested_brackets("[[a]b]]")]
+    fn not_wikilinks(#[case] input: &str) {
+        assert_eq!(split_wikilink(input), None);
+    }
+
+    #[test]
+    fn wikilink_after_brackets() {
+        assert_eq!(split_wikilink("[[x [[Page]]"), Some(("[[x ", "Page", "")));
+    }
+
     #[test]
     fn image() {
         let parsed = parse_single("![](potato.png)");
@@ -555,6 +1088,24 @@ boop
         assert_eq!(path, Path::new("potato.png"));
     }
 
+    #[rstest]
+    #[case::empty("![](potato.png)", "")]
+    #[case::plain("![a potato](potato.png)", "a potato")]
+    #[case::styled("![a **big** `potato`](potato.png)", "a big potato")]
+    fn image_alt_text(#[case] input: &str, #[case] expected: &str) {
+        let parsed = parse_single(input);
+        let MarkdownElement::Image { alt_text, .. } = parsed else { panic!("not an image: {parsed:?}") };
+        assert_eq!(alt_text, expected);
+    }
+
+    #[test]
+    fn image_source_position() {
+        let parsed = parse_all("text\n\nmore text ![](potato.png)");
+        let MarkdownElement::Image { source_position, .. } = &parsed[2] else { panic!("not an image: {parsed:?}") };
+        assert_eq!(source_position.start.line, 3);
+        assert_eq!(source_position.start.column, 11);
+    }
+
     #[test]
     fn image_within_text() {
         let parsed = parse_all(
@@ -573,7 +1124,7 @@ Title
 ===
 ",
         );
-        let MarkdownElement::SetexHeading { text } = parsed else { panic!("not a slide title: {parsed:?}") };
+        let MarkdownElement::SetexHeading { text, .. } = parsed else { panic!("not a slide title: {parsed:?}") };
         let expected_chunks = [Text::from("Title")];
         assert_eq!(text.0, expected_chunks);
     }
@@ -581,13 +1132,23 @@ Title
     #[test]
     fn heading() {
         let parsed = parse_single("# Title **with bold**");
-        let MarkdownElement::Heading { text, level } = parsed else { panic!("not a heading: {parsed:?}") };
+        let MarkdownElement::Heading { text, level, .. } = parsed else { panic!("not a heading: {parsed:?}") };
         let expected_chunks = vec![Text::from("Title "), Text::new("with bold", TextStyle::default().bold())];
 
         assert_eq!(level, 1);
         assert_eq!(text.0, expected_chunks);
     }
 
+    #[test]
+    fn heading_with_only_code() {
+        let parsed = parse_single("## `SomeType`");
+        let MarkdownElement::Heading { text, level, .. } = parsed else { panic!("not a heading: {parsed:?}") };
+        let expected_chunks = vec![Text::new("SomeType", TextStyle::default().code())];
+
+        assert_eq!(level, 2);
+        assert_eq!(text.0, expected_chunks);
+    }
+
     #[test]
     fn unordered_list() {
         let parsed = parse_single(
@@ -608,6 +1169,72 @@ Title
         assert_eq!(next().depth, 0);
     }
 
+    #[test]
+    fn ordered_list_start() {
+        let parsed = parse_single(
+            r"
+3. First
+4. Second
+    1) Sub1
+10. Third",
+        );
+        let MarkdownElement::List(items) = parsed else { panic!("not a list: {parsed:?}") };
+        let item_types: Vec<_> = items.into_iter().map(|item| item.item_type).collect();
+        let expected = &[
+            ListItemType::OrderedPeriod { start: 3 },
+            ListItemType::OrderedPeriod { start: 3 },
+            ListItemType::OrderedParens { start: 1 },
+            ListItemType::OrderedPeriod { start: 3 },
+        ];
+        assert_eq!(item_types, expected);
+    }
+
+    #[rstest]
+    #[case::compact("Term\n: Definition text")]
+    #[case::spaced("Term\n\n: Definition text")]
+    fn description_list(#[case] input: &str) {
+        let parsed = parse_single(input);
+        let MarkdownElement::DescriptionList(items) = parsed else { panic!("not a description list: {parsed:?}") };
+        let item = DescriptionItem { term: "Term".into(), definitions: vec!["Definition text".into()] };
+        assert_eq!(items, &[item]);
+    }
+
+    #[test]
+    fn compact_description_list() {
+        let parsed = parse_single("First\n: one **bold**\n: two\n\nSecond\n: three");
+        let MarkdownElement::DescriptionList(items) = parsed else { panic!("not a description list: {parsed:?}") };
+        let expected = vec![
+            DescriptionItem {
+                term: "First".into(),
+                definitions: vec![
+                    TextBlock(vec!["one ".into(), Text::new("bold", TextStyle::default().bold())]),
+                    "two".into(),
+                ],
+            },
+            DescriptionItem { term: "Second".into(), definitions: vec!["three".into()] },
+        ];
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn description_list_with_nested_blocks() {
+        let parsed = parse_all("Term\n\n: - item");
+        let [MarkdownElement::Paragraph(term), MarkdownElement::List(items)] = parsed.as_slice() else {
+            panic!("unexpected elements: {parsed:?}");
+        };
+        assert_eq!(term, &[ParagraphElement::Text("Term".into())]);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].contents, "item".into());
+    }
+
+    #[rstest]
+    #[case::single_line(": not a definition")]
+    #[case::not_all_definitions("Term\n: definition\nsomething else")]
+    fn not_description_list(#[case] input: &str) {
+        let parsed = parse_single(input);
+        assert!(matches!(parsed, MarkdownElement::Paragraph(_)), "not a paragraph: {parsed:?}");
+    }
+
     #[test]
     fn line_breaks() {
         let parsed = parse_all(
@@ -624,7 +1251,7 @@ another",
         let MarkdownElement::Paragraph(elements) = &parsed[0] else { panic!("not a line break: {parsed:?}") };
         assert_eq!(elements.len(), 3);
 
-        let expected_chunks = &[Text::from("some text"), Text::from(" "), Text::from("with line breaks")];
+        let expected_chunks = &[Text::from("some text with line breaks")];
         let ParagraphElement::Text(text) = &elements[0] else { panic!("non-text in paragraph") };
         assert_eq!(text.0, expected_chunks);
         assert!(matches!(&elements[1], ParagraphElement::LineBreak));
@@ -671,21 +1298,52 @@ echo hi mom
         assert_eq!(text.0, expected_chunks);
     }
 
-    #[test]
-    fn table() {
-        let parsed = parse_single(
-            r"
+    #[rstest]
+    #[case::bordered(
+        r"
 | Name | Taste |
 | ------ | ------ |
 | Potato | Great |
 | Carrot | Yuck |
+"
+    )]
+    #[case::borderless(
+        r"
+Name | Taste
+------ | ------
+Potato | Great
+Carrot | Yuck
+"
+    )]
+    #[case::mixed_borders(
+        r"
+| Name | Taste
+------ | ------ |
+Potato | Great |
+| Carrot | Yuck
+"
+    )]
+    fn table(#[case] input: &str) {
+        let parsed = parse_single(input);
+        let MarkdownElement::Table(Table { header, rows, .. }) = parsed else { panic!("not a table: {parsed:?}") };
+        assert_eq!(header.0, &[TextBlock(vec![Text::from("Name")]), TextBlock(vec![Text::from("Taste")])]);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0, &[TextBlock(vec![Text::from("Potato")]), TextBlock(vec![Text::from("Great")])]);
+        assert_eq!(rows[1].0, &[TextBlock(vec![Text::from("Carrot")]), TextBlock(vec![Text::from("Yuck")])]);
+    }
+
+    #[test]
+    fn table_alignments() {
+        let parsed = parse_single(
+            r"
+| a | b | c | d |
+|---|:--|:-:|--:|
+| 1 | 2 | 3 | 4 |
 ",
         );
-        let MarkdownElement::Table(Table { header, rows }) = parsed else { panic!("not a table: {parsed:?}") };
-        assert_eq!(header.0.len(), 2);
-        assert_eq!(rows.len(), 2);
-        assert_eq!(rows[0].0.len(), 2);
-        assert_eq!(rows[1].0.len(), 2);
+        let MarkdownElement::Table(table) = parsed else { panic!("not a table: {parsed:?}") };
+        let expected = &[TableAlignment::Left, TableAlignment::Left, TableAlignment::Center, TableAlignment::Right];
+        assert_eq!(table.alignments, expected);
     }
 
     #[test]
@@ -699,6 +1357,41 @@ echo hi mom
         assert_eq!(comment, " foo ");
     }
 
+    #[rstest]
+    #[case::plain("<details>", vec![DetailsTag::Start { summary: "Details".into(), open: false }])]
+    #[case::open("<DETAILS open>", vec![DetailsTag::Start { summary: "Details".into(), open: true }])]
+    #[case::summary(
+        "<details>\n<summary>Click  me</summary>",
+        vec![DetailsTag::Start { summary: "Click me".into(), open: false }]
+    )]
+    #[case::end("</details>", vec![DetailsTag::End])]
+    #[case::both(
+        "<details><summary>hi</summary></details>",
+        vec![DetailsTag::Start { summary: "hi".into(), open: false }, DetailsTag::End]
+    )]
+    fn details(#[case] input: &str, #[case] expected: Vec<DetailsTag>) {
+        let parsed = parse_all(input);
+        let tags: Vec<_> = parsed
+            .into_iter()
+            .map(|element| match element {
+                MarkdownElement::Details(tag) => tag,
+                other => panic!("not a details tag: {other:?}"),
+            })
+            .collect();
+        assert_eq!(tags, expected);
+    }
+
+    #[rstest]
+    #[case::other_tag("<div>")]
+    #[case::trailing_content("<details>hi")]
+    #[case::unclosed_summary("<details><summary>hi")]
+    #[case::similar_tag("<detailsx>")]
+    fn unsupported_html(#[case] input: &str) {
+        let arena = Arena::new();
+        let result = MarkdownParser::new(&arena).parse(input);
+        assert!(result.is_err(), "parsed: {result:?}");
+    }
+
     #[test]
     fn list_comment_in_between() {
         let parsed = parse_all(
@@ -726,11 +1419,11 @@ echo hi mom
         );
         let MarkdownElement::BlockQuote(lines) = parsed else { panic!("not a block quote: {parsed:?}") };
         assert_eq!(lines.len(), 5);
-        assert_eq!(lines[0], "bar!@#$%^&*()[]'\"{}-=`~,.<>/?");
-        assert_eq!(lines[1], "foo");
-        assert_eq!(lines[2], "");
-        assert_eq!(lines[3], "* a");
-        assert_eq!(lines[4], "* b");
+        assert_eq!(lines[0].text, "bar!@#$%^&*()[]'\"{}-=`~,.<>/?");
+        assert_eq!(lines[1].text, "foo");
+        assert_eq!(lines[2].text, "");
+        assert_eq!(lines[3].text, "* a");
+        assert_eq!(lines[4].text, "* b");
     }
 
     #[test]
@@ -747,11 +1440,30 @@ foo
         );
         let MarkdownElement::BlockQuote(lines) = parsed else { panic!("not a block quote: {parsed:?}") };
         assert_eq!(lines.len(), 5);
-        assert_eq!(lines[0], "bar");
-        assert_eq!(lines[1], "foo");
-        assert_eq!(lines[2], "");
-        assert_eq!(lines[3], "* a");
-        assert_eq!(lines[4], "* b");
+        assert_eq!(lines[0].text, "bar");
+        assert_eq!(lines[1].text, "foo");
+        assert_eq!(lines[2].text, "");
+        assert_eq!(lines[3].text, "* a");
+        assert_eq!(lines[4].text, "* b");
+    }
+
+    #[test]
+    fn nested_block_quote() {
+        let parsed = parse_single(
+            r"
+> outer
+>
+> > inner
+> > > innermost \> not a quote
+>
+> outer again
+",
+        );
+        let MarkdownElement::BlockQuote(lines) = parsed else { panic!("not a block quote: {parsed:?}") };
+        let lines: Vec<_> = lines.into_iter().map(|line| (line.text, line.depth)).collect();
+        let expected =
+            [("outer", 0), ("", 0), ("inner", 1), ("", 1), ("innermost > not a quote", 2), ("", 0), ("outer again", 0)];
+        assert_eq!(lines, expected.map(|(text, depth)| (text.to_string(), depth)));
     }
 
     #[test]
@@ -766,7 +1478,7 @@ bye
 ",
         );
         assert_eq!(parsed.len(), 3);
-        assert!(matches!(parsed[1], MarkdownElement::ThematicBreak));
+        assert!(matches!(parsed[1], MarkdownElement::ThematicBreak { .. }));
     }
 
     #[test]
@@ -814,4 +1526,116 @@ mom
         let expected = format!("hi{nl}mom{nl}");
         assert_eq!(contents, &expected);
     }
+
+    #[test]
+    fn fenced_divs() {
+        let parsed = parse_all(
+            r#"
+:::: columns
+::: {.column width="30%"}
+left
+:::
+::: column
+- right
+:::
+::::
+after
+"#,
+        );
+        let divs: Vec<_> = parsed
+            .iter()
+            .map(|element| match element {
+                MarkdownElement::FencedDiv(div) => Some(div.clone()),
+                _ => None,
+            })
+            .collect();
+        let expected = vec![
+            Some(FencedDiv::Columns(vec![30, 70])),
+            Some(FencedDiv::Column(0)),
+            None,
+            Some(FencedDiv::Column(1)),
+            None,
+            Some(FencedDiv::EndColumns),
+            None,
+        ];
+        assert_eq!(divs, expected);
+    }
+
+    #[test]
+    fn fenced_divs_inside_html_block() {
+        let parsed = parse_all("<details>\n:::: columns\n::: column\n**hi**\n:::\n::::\n</details>\n\n# after\n");
+        let expected = vec![
+            "Details(Start { summary: \"Details\", open: false })",
+            "FencedDiv(Columns([1]))",
+            "FencedDiv(Column(0))",
+            "Paragraph",
+            "FencedDiv(EndColumns)",
+            "Details(End)",
+            "Heading 9",
+        ];
+        let elements: Vec<_> = parsed
+            .iter()
+            .map(|element| match element {
+                MarkdownElement::Paragraph(_) => "Paragraph".to_string(),
+                MarkdownElement::Heading { source_position, .. } => format!("Heading {}", source_position.start.line),
+                other => format!("{other:?}"),
+            })
+            .collect();
+        assert_eq!(elements, expected);
+    }
+
+    #[test]
+    fn unclosed_fenced_div() {
+        let input = "---\ntitle: hi\n---\n\n:::: columns\n::: column\nhi\n:::\n";
+        let arena = Arena::new();
+        let error = MarkdownParser::new(&arena).parse(input).expect_err("parse succeeded");
+        assert_eq!(error.sourcepos.start.line, 5);
+        assert!(matches!(error.kind, ParseErrorKind::InvalidFencedDiv(FencedDivErrorKind::Unclosed)));
+    }
+
+    #[test]
+    fn math_blocks() {
+        let input = "hi\n\n$$\ne^{i \\pi}\n$$\n\n# title\n";
+        let arena = Arena::new();
+        let parsed = MarkdownParser::new(&arena).math_blocks(true).parse(input).expect("parsing failed");
+        let [_, MarkdownElement::Code(code), MarkdownElement::Heading { source_position, .. }] = parsed.as_slice()
+        else {
+            panic!("unexpected elements: {parsed:?}");
+        };
+        assert_eq!(code.language, CodeLanguage::Latex);
+        assert_eq!(source_position.start.line, 7);
+    }
+
+    #[test]
+    fn math_blocks_inside_html_block() {
+        let input = "<details>\n$$\nx^2\n$$\n</details>\n\n# title\n";
+        let arena = Arena::new();
+        let parsed = MarkdownParser::new(&arena).math_blocks(true).parse(input).expect("parsing failed");
+        let [
+            MarkdownElement::Details(_),
+            MarkdownElement::Code(code),
+            MarkdownElement::Details(_),
+            MarkdownElement::Heading { source_position, .. },
+        ] = parsed.as_slice()
+        else {
+            panic!("unexpected elements: {parsed:?}");
+        };
+        assert_eq!(code.language, CodeLanguage::Latex);
+        assert_eq!(source_position.start.line, 7);
+    }
+
+    #[test]
+    fn math_blocks_disabled() {
+        let parsed = parse_all("$$ x^2 $$\n");
+        assert!(matches!(parsed.as_slice(), [MarkdownElement::Paragraph(_)]), "{parsed:?}");
+    }
+
+    #[rstest]
+    #[case::rendered(true, "\\[ x^2 \\]")]
+    #[case::unrendered(false, "x^2")]
+    fn math_block(#[case] render: bool, #[case] contents: &str) {
+        let MarkdownElement::Code(code) = MarkdownParser::math_block("x^2".into(), render) else { panic!("not code") };
+        assert_eq!(code.contents, contents);
+        assert_eq!(code.attributes.auto_render, render);
+    }
 }
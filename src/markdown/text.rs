@@ -3,6 +3,13 @@ use crate::style::TextStyle;
 use std::mem;
 use unicode_width::UnicodeWidthChar;
 
+// The character appended to truncated text.
+const ELLIPSIS: &str = "…";
+const ELLIPSIS_WIDTH: usize = 1;
+
+// The zero width joiner used to glue emoji sequences together.
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+
 /// A weighted block of text.
 ///
 /// The weight of a character is its given by its width in unicode.
@@ -11,7 +18,7 @@ pub(crate) struct WeightedTextBlock(Vec<WeightedText>);
 
 impl WeightedTextBlock {
     /// Split this line into chunks of at most `max_length` width.
-    pub(crate) fn split(&self, max_length: usize) -> SplitTextIter {
+    pub(crate) fn split(&self, max_length: usize) -> SplitTextIter<'_> {
         SplitTextIter::new(&self.0, max_length)
     }
 
@@ -21,7 +28,6 @@ impl WeightedTextBlock {
     }
 
     /// Get an iterator to the underlying text chunks.
-    #[cfg(test)]
     pub(crate) fn iter_texts(&self) -> impl Iterator<Item = &WeightedText> {
         self.0.iter()
     }
@@ -37,11 +43,14 @@ impl From<Vec<Text>> for WeightedTextBlock {
     fn from(mut texts: Vec<Text>) -> Self {
         let mut output = Vec::new();
         let mut index = 0;
-        // Compact chunks so any consecutive chunk with the same style is merged into the same block.
+        // Compact chunks so any consecutive chunk with the same style and expansion is merged into the same block.
         while index < texts.len() {
             let mut target = mem::replace(&mut texts[index], Text::from(""));
             let mut current = index + 1;
-            while current < texts.len() && texts[current].style == target.style {
+            while current < texts.len()
+                && texts[current].style == target.style
+                && texts[current].expansion == target.expansion
+            {
                 let current_content = mem::take(&mut texts[current].content);
                 target.content.push_str(&current_content);
                 current += 1;
@@ -74,7 +83,7 @@ pub(crate) struct WeightedText {
 }
 
 impl WeightedText {
-    fn to_ref(&self) -> WeightedTextRef {
+    fn to_ref(&self) -> WeightedTextRef<'_> {
         WeightedTextRef { text: &self.text.content, accumulators: &self.accumulators, style: self.text.style.clone() }
     }
 
@@ -82,7 +91,6 @@ impl WeightedText {
         self.accumulators.last().map(|a| a.width).unwrap_or(0)
     }
 
-    #[cfg(test)]
     pub(crate) fn text(&self) -> &Text {
         &self.text
     }
@@ -99,7 +107,7 @@ impl From<Text> for WeightedText {
         let mut accumulators = Vec::new();
         let mut width = 0;
         let mut bytes = 0;
-        for c in text.content.chars() {
+        for (c, _) in text.iter_chars() {
             accumulators.push(CharAccumulator { width, bytes });
             width += c.width().unwrap_or(0);
             bytes += c.len_utf8();
@@ -221,6 +229,88 @@ impl<'a> WeightedTextRef<'a> {
     }
 }
 
+/// Truncate a string so it takes up at most `max_width` columns, ending it with an ellipsis if it's cut.
+pub(crate) fn truncate(text: &str, max_width: usize) -> String {
+    truncate_styled(&TextBlock::from(text), max_width).to_plain()
+}
+
+/// Truncate a styled text so it takes up at most `max_width` columns, ending it with an ellipsis if
+/// it's cut.
+///
+/// Every chunk keeps its style up to the cut and the ellipsis takes the style of the chunk it
+/// replaces. The text is never cut in the middle of a grapheme cluster, so combining marks and
+/// emoji sequences are kept along with the character they belong to.
+pub(crate) fn truncate_styled(text: &TextBlock, max_width: usize) -> TextBlock {
+    let total_width: usize = text.0.iter().flat_map(|chunk| clusters(&chunk.content)).map(|(_, width)| width).sum();
+    if total_width <= max_width {
+        return text.clone();
+    }
+    if max_width == 0 {
+        return TextBlock(Vec::new());
+    }
+    // Leave room for the ellipsis.
+    let available = max_width - ELLIPSIS_WIDTH;
+    let mut output = Vec::new();
+    let mut output_width = 0;
+    for chunk in &text.0 {
+        let mut bytes = 0;
+        let mut cut = false;
+        for (cluster, cluster_width) in clusters(&chunk.content) {
+            if output_width + cluster_width > available {
+                cut = true;
+                break;
+            }
+            bytes += cluster.len();
+            output_width += cluster_width;
+        }
+        let mut content = chunk.content[..bytes].to_string();
+        if cut {
+            content.push_str(ELLIPSIS);
+        }
+        if !content.is_empty() {
+            output.push(Text { content, ..chunk.clone() });
+        }
+        if cut {
+            break;
+        }
+    }
+    TextBlock(output)
+}
+
+// Split a string into approximate grapheme clusters along with their widths.
+//
+// A cluster is a character followed by any zero width characters, like combining marks and
+// variation selectors, and anything glued to it via a zero width joiner. Pairs of regional
+// indicators, which make up flags, are kept together as well.
+fn clusters(text: &str) -> Vec<(&str, usize)> {
+    let mut clusters: Vec<(&str, usize)> = Vec::new();
+    let mut start = 0;
+    let mut width = 0;
+    let mut previous: Option<char> = None;
+    let mut regional_indicators = 0;
+    for (index, c) in text.char_indices() {
+        let c_width = c.width().unwrap_or(0);
+        let is_regional_indicator = ('\u{1f1e6}'..='\u{1f1ff}').contains(&c);
+        let joined = c_width == 0
+            || previous == Some(ZERO_WIDTH_JOINER)
+            || (is_regional_indicator && regional_indicators % 2 == 1);
+        if !joined && index > 0 {
+            clusters.push((&text[start..index], width));
+            start = index;
+            width = 0;
+        }
+        if !(joined && previous == Some(ZERO_WIDTH_JOINER)) {
+            width += c_width;
+        }
+        regional_indicators = if is_regional_indicator { regional_indicators + 1 } else { 0 };
+        previous = Some(c);
+    }
+    if start < text.len() {
+        clusters.push((&text[start..], width));
+    }
+    clusters
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -238,6 +328,14 @@ mod test {
         assert_eq!(text_ref.width(), 11);
     }
 
+    #[test]
+    fn iter_chars() {
+        let style = TextStyle::default().bold();
+        let text = Text::new("a█😀é", style.clone());
+        let chars: Vec<_> = text.iter_chars().collect();
+        assert_eq!(chars, &[('a', &style), ('█', &style), ('😀', &style), ('é', &style)]);
+    }
+
     #[test]
     fn text_creation_utf8() {
         let text = WeightedText::from("█████");
@@ -413,4 +511,72 @@ mod test {
         let block = WeightedTextBlock::from(texts.to_vec());
         assert_eq!(block.0.len(), expected);
     }
+
+    #[rstest]
+    #[case::fits("hello", 5, "hello")]
+    #[case::cut("hello world", 6, "hello…")]
+    #[case::wide("日本語です", 5, "日本…")]
+    #[case::combining("cafe\u{301} noir", 5, "cafe\u{301}…")]
+    #[case::combining_cut("abce\u{301}f", 4, "abc…")]
+    #[case::emoji_sequence("ab👩\u{200d}💻cd", 5, "ab👩\u{200d}💻…")]
+    #[case::flags("🇦🇷🇧🇷🇨🇱", 5, "🇦🇷🇧🇷…")]
+    #[case::no_room("hello", 0, "")]
+    #[case::only_ellipsis("hello", 1, "…")]
+    fn truncation(#[case] text: &str, #[case] width: usize, #[case] expected: &str) {
+        assert_eq!(truncate(text, width), expected);
+    }
+
+    #[test]
+    fn truncation_styles() {
+        let bold = TextStyle::default().bold();
+        let italics = TextStyle::default().italics();
+        let text = TextBlock(vec![Text::new("hello ", bold.clone()), Text::new("world", italics.clone())]);
+        let expected = TextBlock(vec![Text::new("hello ", bold.clone()), Text::new("w…", italics.clone())]);
+        assert_eq!(truncate_styled(&text, 8), expected);
+
+        // Cutting right at the end of a chunk uses the next one's style for the ellipsis.
+        let expected = TextBlock(vec![Text::new("hello ", bold), Text::new("…", italics)]);
+        assert_eq!(truncate_styled(&text, 7), expected);
+    }
+
+    #[test]
+    fn truncation_properties() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        const SAMPLES: &[&str] = &["a", "b", " ", "日", "é", "e\u{301}", "👩\u{200d}💻", "🇦🇷", "─"];
+        let styles = [TextStyle::default(), TextStyle::default().bold(), TextStyle::default().italics()];
+        let width = |text: &TextBlock| -> usize {
+            text.0.iter().flat_map(|chunk| clusters(&chunk.content)).map(|(_, width)| width).sum()
+        };
+        let mut rng = StdRng::seed_from_u64(1337);
+        for _ in 0..1000 {
+            let chunks = (0..rng.gen_range(1..4))
+                .map(|_| {
+                    let content: String =
+                        (0..rng.gen_range(0..8)).map(|_| SAMPLES[rng.gen_range(0..SAMPLES.len())]).collect();
+                    Text::new(content, styles[rng.gen_range(0..styles.len())].clone())
+                })
+                .collect();
+            let text = TextBlock(chunks);
+            let max_width = rng.gen_range(0..20);
+            let truncated = truncate_styled(&text, max_width);
+            assert!(width(&truncated) <= max_width, "{text:?} truncated to {max_width} is {truncated:?}");
+            if width(&text) <= max_width {
+                assert_eq!(truncated, text);
+                continue;
+            }
+            if max_width == 0 {
+                assert_eq!(truncated.0, &[]);
+                continue;
+            }
+            // Whatever is kept is a prefix of the original text with the same styles.
+            let last = truncated.0.last().expect("empty output");
+            assert!(last.content.ends_with(ELLIPSIS));
+            let originals = text.0.iter().filter(|chunk| !chunk.content.is_empty());
+            for (kept, original) in truncated.0.iter().zip(originals) {
+                assert!(original.content.starts_with(kept.content.trim_end_matches(ELLIPSIS)));
+                assert_eq!(kept.style, original.style);
+            }
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use super::printer::{PrintImage, PrintImageError, PrintOptions, RegisterImageError, ResourceProperties};
+use super::printer::{PrintImage, PrintImageError, PrintOptions, RegisterImage, RegisterImageError, ResourceProperties};
 use crossterm::{
     cursor::{MoveRight, MoveToColumn},
     style::{Color, Stylize},
@@ -17,6 +17,10 @@ impl ResourceProperties for AsciiResource {
     fn dimensions(&self) -> (u32, u32) {
         self.0.dimensions()
     }
+
+    fn memory_size(&self) -> usize {
+        self.0.as_bytes().len()
+    }
 }
 
 impl From<DynamicImage> for AsciiResource {
@@ -34,7 +38,7 @@ impl Deref for AsciiResource {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct AsciiPrinter;
 
 impl AsciiPrinter {
@@ -61,7 +65,7 @@ impl AsciiPrinter {
     }
 }
 
-impl PrintImage for AsciiPrinter {
+impl RegisterImage for AsciiPrinter {
     type Resource = AsciiResource;
 
     fn register_image(&self, image: image::DynamicImage) -> Result<Self::Resource, RegisterImageError> {
@@ -73,6 +77,10 @@ impl PrintImage for AsciiPrinter {
         let image = image::load_from_memory(&contents)?;
         Ok(AsciiResource(image))
     }
+}
+
+impl PrintImage for AsciiPrinter {
+    type Resource = AsciiResource;
 
     fn print<W>(&self, image: &Self::Resource, options: &PrintOptions, writer: &mut W) -> Result<(), PrintImageError>
     where
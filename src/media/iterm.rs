@@ -1,4 +1,4 @@
-use super::printer::{PrintImage, PrintImageError, PrintOptions, RegisterImageError, ResourceProperties};
+use super::printer::{PrintImage, PrintImageError, PrintOptions, RegisterImage, RegisterImageError, ResourceProperties};
 use base64::{engine::general_purpose::STANDARD, Engine};
 use image::{codecs::png::PngEncoder, GenericImageView, ImageEncoder};
 use std::{env, fs, path::Path};
@@ -21,8 +21,13 @@ impl ResourceProperties for ItermResource {
     fn dimensions(&self) -> (u32, u32) {
         self.dimensions
     }
+
+    fn memory_size(&self) -> usize {
+        self.base64_contents.len()
+    }
 }
 
+#[derive(Clone)]
 pub struct ItermPrinter {
     // Whether this is iterm2. Otherwise it can be a terminal that _supports_ the iterm2 protocol.
     is_iterm: bool,
@@ -41,7 +46,7 @@ impl Default for ItermPrinter {
     }
 }
 
-impl PrintImage for ItermPrinter {
+impl RegisterImage for ItermPrinter {
     type Resource = ItermResource;
 
     fn register_image(&self, image: image::DynamicImage) -> Result<Self::Resource, RegisterImageError> {
@@ -57,6 +62,10 @@ impl PrintImage for ItermPrinter {
         let image = image::load_from_memory(&contents)?;
         Ok(ItermResource::new(contents, image.dimensions()))
     }
+}
+
+impl PrintImage for ItermPrinter {
+    type Resource = ItermResource;
 
     fn print<W>(&self, image: &Self::Resource, options: &PrintOptions, writer: &mut W) -> Result<(), PrintImageError>
     where
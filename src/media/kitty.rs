@@ -1,4 +1,4 @@
-use super::printer::{PrintImage, PrintImageError, PrintOptions, RegisterImageError, ResourceProperties};
+use super::printer::{PrintImage, PrintImageError, PrintOptions, RegisterImage, RegisterImageError, ResourceProperties};
 use crate::style::Color;
 use base64::{engine::general_purpose::STANDARD, Engine};
 use console::{Key, Term};
@@ -10,7 +10,10 @@ use std::{
     fs::{self, File},
     io::{self, BufReader, Write},
     path::{Path, PathBuf},
-    sync::atomic::{AtomicU32, Ordering},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
 };
 use tempfile::{tempdir, NamedTempFile, TempDir};
 
@@ -75,6 +78,13 @@ impl ResourceProperties for KittyResource {
     fn dimensions(&self) -> (u32, u32) {
         self.dimensions
     }
+
+    fn memory_size(&self) -> usize {
+        match &self.resource {
+            GenericResource::Image(buffer) => buffer.memory_size(),
+            GenericResource::Gif(frames) => frames.iter().map(|frame| frame.buffer.memory_size()).sum(),
+        }
+    }
 }
 
 enum KittyBuffer {
@@ -82,6 +92,16 @@ enum KittyBuffer {
     Memory(Vec<u8>),
 }
 
+impl KittyBuffer {
+    fn memory_size(&self) -> usize {
+        match self {
+            // These live in a file so they don't take up any memory.
+            Self::Filesystem(_) => 0,
+            Self::Memory(buffer) => buffer.len(),
+        }
+    }
+}
+
 impl Drop for KittyBuffer {
     fn drop(&mut self) {
         if let Self::Filesystem(path) = self {
@@ -95,16 +115,18 @@ struct GifFrame<T> {
     buffer: T,
 }
 
+// This is cloned so images can be registered from other threads, hence the shared directory.
+#[derive(Clone)]
 pub struct KittyPrinter {
     mode: KittyMode,
     tmux: bool,
-    base_directory: TempDir,
-    next: AtomicU32,
+    base_directory: Arc<TempDir>,
+    next: Arc<AtomicU32>,
 }
 
 impl KittyPrinter {
     pub(crate) fn new(mode: KittyMode, tmux: bool) -> io::Result<Self> {
-        let base_directory = tempdir()?;
+        let base_directory = Arc::new(tempdir()?);
         Ok(Self { mode, tmux, base_directory, next: Default::default() })
     }
 
@@ -367,7 +389,7 @@ impl KittyPrinter {
     }
 }
 
-impl PrintImage for KittyPrinter {
+impl RegisterImage for KittyPrinter {
     type Resource = KittyResource;
 
     fn register_image(&self, image: DynamicImage) -> Result<Self::Resource, RegisterImageError> {
@@ -387,6 +409,10 @@ impl PrintImage for KittyPrinter {
         };
         Ok(resource)
     }
+}
+
+impl PrintImage for KittyPrinter {
+    type Resource = KittyResource;
 
     fn print<W: std::io::Write>(
         &self,
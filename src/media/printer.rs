@@ -11,19 +11,26 @@ use std::{borrow::Cow, io, path::Path};
 pub(crate) trait PrintImage {
     type Resource: ResourceProperties;
 
+    fn print<W>(&self, image: &Self::Resource, options: &PrintOptions, writer: &mut W) -> Result<(), PrintImageError>
+    where
+        W: io::Write;
+}
+
+pub(crate) trait RegisterImage {
+    type Resource: ResourceProperties;
+
     /// Register an image.
     fn register_image(&self, image: DynamicImage) -> Result<Self::Resource, RegisterImageError>;
 
     /// Load and register a resource from the given path.
     fn register_resource<P: AsRef<Path>>(&self, path: P) -> Result<Self::Resource, RegisterImageError>;
-
-    fn print<W>(&self, image: &Self::Resource, options: &PrintOptions, writer: &mut W) -> Result<(), PrintImageError>
-    where
-        W: io::Write;
 }
 
 pub(crate) trait ResourceProperties {
     fn dimensions(&self) -> (u32, u32);
+
+    /// The number of bytes this resource keeps in memory.
+    fn memory_size(&self) -> usize;
 }
 
 #[derive(Debug)]
@@ -58,6 +65,16 @@ impl ResourceProperties for ImageResource {
             Self::Sixel(resource) => resource.dimensions(),
         }
     }
+
+    fn memory_size(&self) -> usize {
+        match self {
+            Self::Kitty(resource) => resource.memory_size(),
+            Self::Iterm(resource) => resource.memory_size(),
+            Self::Ascii(resource) => resource.memory_size(),
+            #[cfg(feature = "sixel")]
+            Self::Sixel(resource) => resource.memory_size(),
+        }
+    }
 }
 
 pub enum ImagePrinter {
@@ -103,11 +120,55 @@ impl ImagePrinter {
     fn new_sixel() -> Result<Self, CreatePrinterError> {
         Ok(Self::Sixel(super::sixel::SixelPrinter::new()?))
     }
+
+    /// Get a registrar for the images this printer can print.
+    pub(crate) fn registrar(&self) -> ImageRegistrar {
+        match self {
+            Self::Kitty(printer) => ImageRegistrar::Kitty(printer.clone()),
+            Self::Iterm(printer) => ImageRegistrar::Iterm(printer.clone()),
+            Self::Ascii(printer) => ImageRegistrar::Ascii(printer.clone()),
+            Self::Null => ImageRegistrar::Null,
+            #[cfg(feature = "sixel")]
+            Self::Sixel(_) => ImageRegistrar::Sixel(super::sixel::SixelRegistrar),
+        }
+    }
 }
 
 impl PrintImage for ImagePrinter {
     type Resource = ImageResource;
 
+    fn print<W>(&self, image: &Self::Resource, options: &PrintOptions, writer: &mut W) -> Result<(), PrintImageError>
+    where
+        W: io::Write,
+    {
+        match (self, image) {
+            (Self::Kitty(printer), ImageResource::Kitty(image)) => printer.print(image, options, writer),
+            (Self::Iterm(printer), ImageResource::Iterm(image)) => printer.print(image, options, writer),
+            (Self::Ascii(printer), ImageResource::Ascii(image)) => printer.print(image, options, writer),
+            (Self::Null, _) => Ok(()),
+            #[cfg(feature = "sixel")]
+            (Self::Sixel(printer), ImageResource::Sixel(image)) => printer.print(image, options, writer),
+            _ => Err(PrintImageError::Unsupported),
+        }
+    }
+}
+
+/// Registers images so they can be printed by an [ImagePrinter].
+///
+/// Unlike the printer, this can be sent to other threads so images can be loaded in the background.
+#[derive(Clone)]
+pub(crate) enum ImageRegistrar {
+    Kitty(KittyPrinter),
+    Iterm(ItermPrinter),
+    Ascii(AsciiPrinter),
+    Null,
+    #[cfg(feature = "sixel")]
+    Sixel(super::sixel::SixelRegistrar),
+}
+
+impl RegisterImage for ImageRegistrar {
+    type Resource = ImageResource;
+
     fn register_image(&self, image: DynamicImage) -> Result<Self::Resource, RegisterImageError> {
         let resource = match self {
             Self::Kitty(printer) => ImageResource::Kitty(printer.register_image(image)?),
@@ -115,7 +176,7 @@ impl PrintImage for ImagePrinter {
             Self::Ascii(printer) => ImageResource::Ascii(printer.register_image(image)?),
             Self::Null => return Err(RegisterImageError::Unsupported),
             #[cfg(feature = "sixel")]
-            Self::Sixel(printer) => ImageResource::Sixel(printer.register_image(image)?),
+            Self::Sixel(registrar) => ImageResource::Sixel(registrar.register_image(image)?),
         };
         Ok(resource)
     }
@@ -127,25 +188,10 @@ impl PrintImage for ImagePrinter {
             Self::Ascii(printer) => ImageResource::Ascii(printer.register_resource(path)?),
             Self::Null => return Err(RegisterImageError::Unsupported),
             #[cfg(feature = "sixel")]
-            Self::Sixel(printer) => ImageResource::Sixel(printer.register_resource(path)?),
+            Self::Sixel(registrar) => ImageResource::Sixel(registrar.register_resource(path)?),
         };
         Ok(resource)
     }
-
-    fn print<W>(&self, image: &Self::Resource, options: &PrintOptions, writer: &mut W) -> Result<(), PrintImageError>
-    where
-        W: io::Write,
-    {
-        match (self, image) {
-            (Self::Kitty(printer), ImageResource::Kitty(image)) => printer.print(image, options, writer),
-            (Self::Iterm(printer), ImageResource::Iterm(image)) => printer.print(image, options, writer),
-            (Self::Ascii(printer), ImageResource::Ascii(image)) => printer.print(image, options, writer),
-            (Self::Null, _) => Ok(()),
-            #[cfg(feature = "sixel")]
-            (Self::Sixel(printer), ImageResource::Sixel(image)) => printer.print(image, options, writer),
-            _ => Err(PrintImageError::Unsupported),
-        }
-    }
 }
 
 #[derive(Debug, thiserror::Error)]
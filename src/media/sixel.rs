@@ -1,5 +1,6 @@
 use super::printer::{
-    CreatePrinterError, PrintImage, PrintImageError, PrintOptions, RegisterImageError, ResourceProperties,
+    CreatePrinterError, PrintImage, PrintImageError, PrintOptions, RegisterImage, RegisterImageError,
+    ResourceProperties,
 };
 use image::{imageops::FilterType, DynamicImage, GenericImageView};
 use sixel_rs::{
@@ -15,6 +16,10 @@ impl ResourceProperties for SixelResource {
     fn dimensions(&self) -> (u32, u32) {
         self.0.dimensions()
     }
+
+    fn memory_size(&self) -> usize {
+        self.0.as_bytes().len()
+    }
 }
 
 pub struct SixelPrinter {
@@ -32,7 +37,11 @@ impl SixelPrinter {
     }
 }
 
-impl PrintImage for SixelPrinter {
+// The encoder can't be shared between threads so images are registered separately.
+#[derive(Clone)]
+pub(crate) struct SixelRegistrar;
+
+impl RegisterImage for SixelRegistrar {
     type Resource = SixelResource;
 
     fn register_image(&self, image: image::DynamicImage) -> Result<Self::Resource, RegisterImageError> {
@@ -44,6 +53,10 @@ impl PrintImage for SixelPrinter {
         let image = image::load_from_memory(&contents)?;
         Ok(SixelResource(image))
     }
+}
+
+impl PrintImage for SixelPrinter {
+    type Resource = SixelResource;
 
     fn print<W>(&self, image: &Self::Resource, options: &PrintOptions, writer: &mut W) -> Result<(), PrintImageError>
     where
@@ -0,0 +1,408 @@
+//! Rendering of mermaid diagrams.
+//!
+//! Diagrams are rendered using the first of these that applies:
+//!
+//! * If the terminal can display images natively and `mmdc` is installed, as an image.
+//! * If the diagram is a simple flowchart and `graph-easy` is installed, as ASCII art.
+//! * Otherwise the diagram's source is displayed like any other code block.
+
+use crate::{
+    artifacts::{ArtifactKey, ArtifactStore},
+    media::{
+        image::{Image, ImageSource},
+        printer::{ImageRegistrar, RegisterImage, RegisterImageError},
+    },
+    tools::{ExecutionError, ThirdPartyTools},
+    ImageRegistry,
+};
+use std::{fmt::Write, fs, io};
+use tempfile::tempdir;
+
+// The scale diagrams are rendered at, which makes them look sharp in high resolution terminals.
+const IMAGE_SCALE: &str = "2";
+
+/// The way a mermaid diagram is rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MermaidBackend {
+    /// Rendered into an image using `mmdc`.
+    Image,
+
+    /// Rendered into ASCII art using `graph-easy`.
+    Ascii,
+
+    /// Not rendered at all, the diagram's source is displayed instead.
+    Source,
+}
+
+impl MermaidBackend {
+    /// Pick the backend to render a diagram with based on the tools that are installed.
+    ///
+    /// `native_images` is whether the terminal can display images natively. Images rendered using
+    /// ASCII blocks make diagrams unreadable so ASCII art is preferred in that case.
+    pub(crate) fn select(input: &str, native_images: bool) -> Self {
+        Self::select_with(input, native_images, ThirdPartyTools::is_installed)
+    }
+
+    fn select_with<F>(input: &str, native_images: bool, is_installed: F) -> Self
+    where
+        F: Fn(&str) -> bool,
+    {
+        if native_images && is_installed("mmdc") {
+            Self::Image
+        } else if is_installed("graph-easy") && convert_flowchart(input).is_some() {
+            Self::Ascii
+        } else {
+            Self::Source
+        }
+    }
+}
+
+/// Renders mermaid diagrams.
+#[derive(Clone)]
+pub(crate) struct MermaidRender {
+    registrar: ImageRegistrar,
+    artifacts: Option<ArtifactStore>,
+}
+
+impl MermaidRender {
+    pub(crate) fn new(image_registry: ImageRegistry, artifacts: Option<ArtifactStore>) -> Self {
+        Self { registrar: image_registry.registrar(), artifacts }
+    }
+
+    /// Render a diagram into an image using `mmdc`.
+    pub(crate) fn render_image(&self, input: &str) -> Result<Image, MermaidRenderError> {
+        let png_contents = match &self.artifacts {
+            Some(artifacts) => {
+                let version = ThirdPartyTools::version("mmdc");
+                let source = input.as_bytes();
+                let key = ArtifactKey { tool: "mmdc", version: &version, source, target: IMAGE_SCALE };
+                artifacts.get_or_create(&key, || Self::generate_image(input))?
+            }
+            None => Self::generate_image(input)?,
+        };
+        let image = image::load_from_memory(&png_contents)?;
+        let resource = self.registrar.register_image(image)?;
+        Ok(Image::new(resource, ImageSource::Generated))
+    }
+
+    fn generate_image(input: &str) -> Result<Vec<u8>, MermaidRenderError> {
+        let workdir = tempdir()?;
+        let input_path = workdir.path().join("input.mmd");
+        let output_path = workdir.path().join("output.png");
+        fs::write(&input_path, input)?;
+        // mermaid's default theme uses dark lines so the background is always white to keep them
+        // visible in dark terminals.
+        ThirdPartyTools::mmdc(&[
+            "--input",
+            &input_path.to_string_lossy(),
+            "--output",
+            &output_path.to_string_lossy(),
+            "--backgroundColor",
+            "white",
+            "--scale",
+            IMAGE_SCALE,
+        ])
+        .run()?;
+
+        Ok(fs::read(&output_path)?)
+    }
+
+    /// Render a flowchart into ASCII art using `graph-easy`.
+    pub(crate) fn render_ascii(input: &str) -> Result<String, MermaidRenderError> {
+        let graph = convert_flowchart(input).ok_or(MermaidRenderError::UnsupportedDiagram)?;
+        let output = ThirdPartyTools::graph_easy(&["--as=ascii"]).stdin(graph.into_bytes()).run_and_capture_stdout()?;
+        Ok(String::from_utf8_lossy(&output).trim_end().to_string())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Node {
+    id: String,
+    label: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Edge {
+    from: String,
+    to: String,
+    arrow: &'static str,
+    label: Option<String>,
+}
+
+/// Convert a mermaid flowchart into `graph-easy`'s own syntax.
+///
+/// Only nodes, their labels, and the links between them are supported. Anything else, like
+/// subgraphs, makes this return `None`, while styling statements are ignored.
+fn convert_flowchart(input: &str) -> Option<String> {
+    let mut lines = input.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with("%%"));
+    let mut header = lines.next()?.split_whitespace();
+    if !matches!(header.next()?, "flowchart" | "graph") {
+        return None;
+    }
+    let flow = match header.next() {
+        None | Some("TB" | "TD") => "south",
+        Some("BT") => "north",
+        Some("LR") => "east",
+        Some("RL") => "west",
+        Some(_) => return None,
+    };
+    if header.next().is_some() {
+        return None;
+    }
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for statement in lines.flat_map(|line| line.split(';')).map(str::trim).filter(|line| !line.is_empty()) {
+        let keyword = statement.split_whitespace().next().unwrap_or_default();
+        if matches!(keyword, "classDef" | "class" | "style" | "linkStyle" | "click") {
+            continue;
+        }
+        parse_chain(statement, &mut nodes, &mut edges)?;
+    }
+
+    let mut output = format!("graph {{ flow: {flow}; }}\n");
+    for Node { id, label } in &nodes {
+        let _ = writeln!(output, "[ {id} ] {{ label: {label}; }}");
+    }
+    for Edge { from, to, arrow, label } in &edges {
+        let _ = match label {
+            Some(label) => writeln!(output, "[ {from} ] {arrow} {{ label: {label}; }} [ {to} ]"),
+            None => writeln!(output, "[ {from} ] {arrow} [ {to} ]"),
+        };
+    }
+    Some(output)
+}
+
+// Parse a statement like `A[Start] --> B --> C`.
+fn parse_chain(input: &str, nodes: &mut Vec<Node>, edges: &mut Vec<Edge>) -> Option<()> {
+    let (mut from, mut input) = parse_node(input, nodes)?;
+    while !input.trim_start().is_empty() {
+        let (arrow, label, rest) = parse_link(input.trim_start())?;
+        let (to, rest) = parse_node(rest, nodes)?;
+        edges.push(Edge { from, to: to.clone(), arrow, label });
+        from = to;
+        input = rest;
+    }
+    Some(())
+}
+
+fn parse_node<'a>(input: &'a str, nodes: &mut Vec<Node>) -> Option<(String, &'a str)> {
+    let input = input.trim_start();
+    let id_length = input.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(input.len());
+    if id_length == 0 {
+        return None;
+    }
+    let (id, rest) = input.split_at(id_length);
+    let (label, rest) = match rest.starts_with(['[', '(', '{', '>']) {
+        true => {
+            let (label, rest) = parse_shape(rest)?;
+            (Some(label), rest)
+        }
+        false => (None, rest),
+    };
+    match nodes.iter_mut().find(|node| node.id == id) {
+        Some(node) => node.label = label.unwrap_or(node.label.clone()),
+        None => nodes.push(Node { id: id.into(), label: label.unwrap_or_else(|| id.into()) }),
+    };
+    Some((id.into(), rest))
+}
+
+// Parse a node's shape, like `[text]` or `((text))`, and return the text in it.
+fn parse_shape(input: &str) -> Option<(String, &str)> {
+    let opening = match input.strip_prefix('>') {
+        Some(_) => ">",
+        None => &input[..input.find(|c| !matches!(c, '[' | '(' | '{')).unwrap_or(input.len())],
+    };
+    let closing: String = opening
+        .chars()
+        .rev()
+        .map(|c| match c {
+            '(' => ')',
+            '{' => '}',
+            _ => ']',
+        })
+        .collect();
+    let rest = &input[opening.len()..];
+    let end = rest.find(&closing)?;
+    let label = rest[..end].trim().trim_matches('"').trim_matches(&['/', '\\'][..]);
+    let label = label.replace("<br>", "\\n").replace("<br/>", "\\n").replace("<br />", "\\n");
+    // These would break graph-easy's attribute syntax.
+    if label.is_empty() || label.contains(['{', '}', '[', ']', '|']) {
+        return None;
+    }
+    Some((label, &rest[end + closing.len()..]))
+}
+
+// Parse a link like `-->`, `-->|text|`, or `-- text -->`.
+fn parse_link(input: &str) -> Option<(&'static str, Option<String>, &str)> {
+    let length = input.find(|c| !matches!(c, '-' | '=' | '.' | '<' | '>')).unwrap_or(input.len());
+    let (arrow, rest) = input.split_at(length);
+    if let Some(rest) = rest.strip_prefix('|') {
+        let end = rest.find('|')?;
+        let label = rest[..end].trim().trim_matches('"').to_string();
+        return Some((convert_arrow(arrow)?, Some(label), &rest[end + 1..]));
+    }
+    let closings: &[&str] = match arrow.trim_start_matches('<') {
+        "--" => &["-->", "---"],
+        "==" => &["==>", "==="],
+        "-." => &[".->", ".-"],
+        _ => return Some((convert_arrow(arrow)?, None, rest)),
+    };
+    // The text in between is the label, e.g. `-- text -->`.
+    let (end, closing) = closings.iter().filter_map(|closing| Some((rest.find(closing)?, *closing))).min()?;
+    let label = rest[..end].trim().trim_matches('"').to_string();
+    let arrow = convert_arrow(&format!("{arrow}{closing}"))?;
+    Some((arrow, Some(label), &rest[end + closing.len()..]))
+}
+
+// Convert a mermaid arrow into the equivalent graph-easy one.
+fn convert_arrow(arrow: &str) -> Option<&'static str> {
+    let (starts, body) = match arrow.strip_prefix('<') {
+        Some(body) => (true, body),
+        None => (false, arrow),
+    };
+    let (ends, body) = match body.strip_suffix('>') {
+        Some(body) => (true, body),
+        None => (false, body),
+    };
+    let solid = body.len() >= 2 && body.chars().all(|c| c == '-');
+    let thick = body.len() >= 2 && body.chars().all(|c| c == '=');
+    let dotted = body.len() >= 3
+        && body.starts_with('-')
+        && body.ends_with('-')
+        && body.contains('.')
+        && body.chars().all(|c| matches!(c, '-' | '.'));
+    let arrow = match (starts, ends) {
+        (false, true) if solid => "->",
+        (false, true) if thick => "==>",
+        (false, true) if dotted => "..>",
+        (true, true) if solid => "<->",
+        (true, true) if thick => "<==>",
+        (true, true) if dotted => "<..>",
+        (false, false) if solid => "--",
+        (false, false) if thick => "==",
+        (false, false) if dotted => "..",
+        _ => return None,
+    };
+    Some(arrow)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MermaidRenderError {
+    #[error(transparent)]
+    Execution(#[from] ExecutionError),
+
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid image: {0}")]
+    InvalidImage(#[from] image::ImageError),
+
+    #[error("invalid image: {0}")]
+    RegisterImage(#[from] RegisterImageError),
+
+    #[error("only flowcharts can be rendered as ASCII art")]
+    UnsupportedDiagram,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn flowchart() {
+        let input = r#"
+flowchart LR
+    %% a comment
+    A[Start] --> B{"Is it?"}
+    B -->|Yes| C(Done)
+    B -- No --> A
+    C --- D; D -.-> E
+    E ==> F
+    style A fill:#f9f
+"#;
+        let expected = "graph { flow: east; }
+[ A ] { label: Start; }
+[ B ] { label: Is it?; }
+[ C ] { label: Done; }
+[ D ] { label: D; }
+[ E ] { label: E; }
+[ F ] { label: F; }
+[ A ] -> [ B ]
+[ B ] -> { label: Yes; } [ C ]
+[ B ] -> { label: No; } [ A ]
+[ C ] -- [ D ]
+[ D ] ..> [ E ]
+[ E ] ==> [ F ]
+";
+        assert_eq!(convert_flowchart(input).as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn chains() {
+        let input = "graph\nA --> B --> C\nC <--> A\n";
+        let expected = "graph { flow: south; }
+[ A ] { label: A; }
+[ B ] { label: B; }
+[ C ] { label: C; }
+[ A ] -> [ B ]
+[ B ] -> [ C ]
+[ C ] <-> [ A ]
+";
+        assert_eq!(convert_flowchart(input).as_deref(), Some(expected));
+    }
+
+    #[rstest]
+    #[case::square("A[text]", "text")]
+    #[case::round("A(text)", "text")]
+    #[case::stadium("A([text])", "text")]
+    #[case::subroutine("A[[text]]", "text")]
+    #[case::circle("A((text))", "text")]
+    #[case::rhombus("A{text}", "text")]
+    #[case::hexagon("A{{text}}", "text")]
+    #[case::asymmetric("A>text]", "text")]
+    #[case::parallelogram("A[/text/]", "text")]
+    #[case::quoted(r#"A["some text"]"#, "some text")]
+    #[case::line_break("A[one<br>two]", r"one\ntwo")]
+    fn shapes(#[case] node: &str, #[case] label: &str) {
+        let output = convert_flowchart(&format!("flowchart TD\n{node}\n")).expect("conversion failed");
+        assert_eq!(output.lines().nth(1), Some(format!("[ A ] {{ label: {label}; }}").as_str()));
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::sequence("sequenceDiagram\nAlice->>Bob: Hi")]
+    #[case::gantt("gantt\ntitle A Gantt Diagram")]
+    #[case::unknown_direction("flowchart XY\nA --> B")]
+    #[case::subgraph("flowchart TD\nsubgraph one\nA --> B\nend")]
+    #[case::multiple_nodes("flowchart TD\nA & B --> C")]
+    #[case::invalid_arrow("flowchart TD\nA -> B")]
+    #[case::unclosed_shape("flowchart TD\nA[text --> B")]
+    #[case::unclosed_label("flowchart TD\nA -->|text B")]
+    fn unsupported(#[case] input: &str) {
+        assert_eq!(convert_flowchart(input), None);
+    }
+
+    #[rstest]
+    #[case::mmdc(true, &["mmdc", "graph-easy"], MermaidBackend::Image)]
+    #[case::mmdc_without_images(false, &["mmdc", "graph-easy"], MermaidBackend::Ascii)]
+    #[case::graph_easy(true, &["graph-easy"], MermaidBackend::Ascii)]
+    #[case::mmdc_only_without_images(false, &["mmdc"], MermaidBackend::Source)]
+    #[case::nothing(true, &[], MermaidBackend::Source)]
+    fn backend_selection(#[case] native_images: bool, #[case] installed: &[&str], #[case] expected: MermaidBackend) {
+        let input = "flowchart LR\nA --> B";
+        let backend = MermaidBackend::select_with(input, native_images, |tool| installed.contains(&tool));
+        assert_eq!(backend, expected);
+    }
+
+    #[test]
+    fn non_flowchart_backend() {
+        // graph-easy can only draw flowcharts.
+        let input = "sequenceDiagram\nAlice->>Bob: Hi";
+        let backend = MermaidBackend::select_with(input, false, |_| true);
+        assert_eq!(backend, MermaidBackend::Source);
+        let backend = MermaidBackend::select_with(input, true, |_| true);
+        assert_eq!(backend, MermaidBackend::Image);
+    }
+}
@@ -0,0 +1,342 @@
+//! Export a presentation as a plain outline of its slides.
+//!
+//! The outline is meant to be shared with people reviewing a presentation who don't want to read
+//! its markdown: every slide becomes an entry that contains its headings, list items and a
+//! summary of the rest of its contents, while commands are left out entirely.
+
+use crate::{
+    markdown::{
+        elements::{Code, DetailsTag, MarkdownElement, ParagraphElement, TextBlock},
+        parse::ParseError,
+        text::truncate,
+    },
+    presentation::PresentationMetadata,
+    processing::{
+        builder::{CommentCommand, PresentationBuilderOptions},
+        redaction::Redactions,
+    },
+    MarkdownParser,
+};
+use clap::ValueEnum;
+use std::{fmt::Write, fs, io, path::Path};
+
+// The maximum width of an entry's text, longer ones are truncated.
+const MAX_ENTRY_WIDTH: usize = 80;
+
+/// The format an outline is written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutlineFormat {
+    /// A markdown list.
+    #[default]
+    Markdown,
+
+    /// Indented plain text.
+    Plain,
+}
+
+/// Exports presentations as an outline.
+pub struct OutlineExporter<'a> {
+    parser: MarkdownParser<'a>,
+    options: PresentationBuilderOptions,
+}
+
+impl<'a> OutlineExporter<'a> {
+    /// Construct a new outline exporter.
+    pub fn new(parser: MarkdownParser<'a>, options: PresentationBuilderOptions) -> Self {
+        Self { parser, options }
+    }
+
+    /// Generate the outline for the given presentation.
+    pub fn export(&self, presentation_path: &Path, format: OutlineFormat) -> Result<String, OutlineError> {
+        let contents = fs::read_to_string(presentation_path).map_err(OutlineError::ReadPresentation)?;
+        self.generate(&contents, format)
+    }
+
+    fn generate(&self, contents: &str, format: OutlineFormat) -> Result<String, OutlineError> {
+        let elements = self.parser.parse(contents)?;
+        let slides = OutlineBuilder::new(self.options.clone()).build(&elements)?;
+        let mut output = String::new();
+        let mut number = 0;
+        for slide in &slides {
+            let title = slide.title.as_deref().unwrap_or("(untitled)");
+            let _ = match slide.unnumbered {
+                true => writeln!(output, "-  {title}"),
+                false => {
+                    number += 1;
+                    writeln!(output, "{number}. {title}")
+                }
+            };
+            for entry in &slide.entries {
+                // Entries are indented so they line up with the slide's title.
+                let indentation = " ".repeat(3 + entry.depth * 2);
+                let _ = match format {
+                    OutlineFormat::Markdown => writeln!(output, "{indentation}- {}", entry.text),
+                    OutlineFormat::Plain => writeln!(output, "{indentation}{}", entry.text),
+                };
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[derive(Debug, Default)]
+struct OutlineSlide {
+    title: Option<String>,
+    entries: Vec<OutlineEntry>,
+    unnumbered: bool,
+}
+
+impl OutlineSlide {
+    fn is_empty(&self) -> bool {
+        self.title.is_none() && self.entries.is_empty()
+    }
+}
+
+#[derive(Debug)]
+struct OutlineEntry {
+    text: String,
+    depth: usize,
+}
+
+/// Walks through a presentation's elements and builds the outline for every slide in it.
+struct OutlineBuilder {
+    options: PresentationBuilderOptions,
+    redactions: Redactions,
+    slides: Vec<OutlineSlide>,
+    current: OutlineSlide,
+    // Whether the current slide has a heading, other than its title, that contents nest under.
+    nested: bool,
+}
+
+impl OutlineBuilder {
+    fn new(options: PresentationBuilderOptions) -> Self {
+        Self {
+            options,
+            redactions: Redactions::default(),
+            slides: Vec::new(),
+            current: OutlineSlide::default(),
+            nested: false,
+        }
+    }
+
+    fn build(mut self, elements: &[MarkdownElement]) -> Result<Vec<OutlineSlide>, OutlineError> {
+        for element in elements {
+            self.process_element(element)?;
+        }
+        if !self.current.is_empty() {
+            self.terminate_slide();
+        }
+        Ok(self.slides)
+    }
+
+    fn process_element(&mut self, element: &MarkdownElement) -> Result<(), OutlineError> {
+        match element {
+            MarkdownElement::FrontMatter(contents) => self.process_front_matter(contents)?,
+            MarkdownElement::SetexHeading { text, .. } => {
+                if self.options.implicit_slide_ends && !self.current.is_empty() {
+                    self.terminate_slide();
+                }
+                self.push_heading(text, true);
+            }
+            MarkdownElement::Heading { text, .. } => self.push_heading(text, false),
+            MarkdownElement::Paragraph(elements) => {
+                let text = elements
+                    .iter()
+                    .filter_map(|element| match element {
+                        ParagraphElement::Text(text) => Some(text.to_plain()),
+                        ParagraphElement::LineBreak => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                self.push_entry(text, 0);
+            }
+            MarkdownElement::Image { path, .. } => {
+                let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+                self.push_entry(format!("[image: {name}]"), 0);
+            }
+            MarkdownElement::List(items) => {
+                for item in items {
+                    self.push_entry(item.contents.to_plain(), item.depth as usize);
+                }
+            }
+            MarkdownElement::DescriptionList(items) => {
+                for item in items {
+                    self.push_entry(item.term.to_plain(), 0);
+                    for definition in &item.definitions {
+                        self.push_entry(definition.to_plain(), 1);
+                    }
+                }
+            }
+            MarkdownElement::Code(code) => self.push_entry(summarize_code(code), 0),
+            MarkdownElement::Table(table) => {
+                let columns = table.columns();
+                let rows = table.rows.len();
+                self.push_entry(format!("[table: {columns} columns, {rows} rows]"), 0);
+            }
+            MarkdownElement::ThematicBreak { .. } => {
+                if self.options.end_slide_shorthand {
+                    self.terminate_slide();
+                }
+            }
+            MarkdownElement::Comment { comment, .. } => self.process_comment(comment),
+            MarkdownElement::BlockQuote(lines) => {
+                let text = lines.iter().map(|line| line.text.as_str()).collect::<Vec<_>>().join(" ");
+                self.push_entry(text, 0);
+            }
+            MarkdownElement::Details(DetailsTag::Start { summary, .. }) => self.push_entry(summary.clone(), 0),
+            MarkdownElement::FencedDiv(_) | MarkdownElement::Details(DetailsTag::End) => (),
+        };
+        Ok(())
+    }
+
+    fn process_front_matter(&mut self, contents: &str) -> Result<(), OutlineError> {
+        let metadata: PresentationMetadata =
+            serde_yaml::from_str(contents).map_err(|e| OutlineError::InvalidMetadata(e.to_string()))?;
+        if let Some(options) = metadata.options {
+            self.options.merge(options);
+        }
+        // Outlines are shared with others so redactions are never revealed in them.
+        self.redactions =
+            Redactions::new(&metadata.redact, false).map_err(|e| OutlineError::InvalidMetadata(e.to_string()))?;
+        self.current.title = metadata.title.map(|title| self.redactions.conceal(&title));
+        if let Some(sub_title) = metadata.sub_title {
+            self.push_entry(sub_title, 0);
+        }
+        for author in metadata.author.into_iter().chain(metadata.authors) {
+            self.push_entry(author, 0);
+        }
+        if !self.current.is_empty() {
+            self.terminate_slide();
+        }
+        Ok(())
+    }
+
+    fn process_comment(&mut self, comment: &str) {
+        let comment = comment.trim();
+        let prefix = &self.options.command_prefix;
+        if comment.contains('\n') || !comment.starts_with(prefix.as_str()) {
+            return;
+        }
+        // Every other command is omitted from the outline.
+        match comment.trim_start_matches(prefix.as_str()).parse::<CommentCommand>() {
+            Ok(CommentCommand::EndSlide) => self.terminate_slide(),
+            Ok(CommentCommand::Unnumbered) => self.current.unnumbered = true,
+            _ => (),
+        };
+    }
+
+    fn push_heading(&mut self, text: &TextBlock, slide_title: bool) {
+        let text = text.to_plain();
+        // The first heading in a slide that has no title is used as its title.
+        if self.current.title.is_none() && (slide_title || self.current.entries.is_empty()) {
+            self.current.title = Some(truncate(&flatten(&self.redactions.conceal(&text)), MAX_ENTRY_WIDTH));
+            return;
+        }
+        self.nested = false;
+        self.push_entry(text, 0);
+        self.nested = true;
+    }
+
+    fn push_entry(&mut self, text: String, depth: usize) {
+        let text = truncate(&flatten(&self.redactions.conceal(&text)), MAX_ENTRY_WIDTH);
+        if text.is_empty() {
+            return;
+        }
+        let depth = depth + usize::from(self.nested);
+        self.current.entries.push(OutlineEntry { text, depth });
+    }
+
+    fn terminate_slide(&mut self) {
+        self.slides.push(std::mem::take(&mut self.current));
+        self.nested = false;
+    }
+}
+
+fn summarize_code(code: &Code) -> String {
+    let lines = code.contents.lines().count();
+    let lines = if lines == 1 { "1 line".to_string() } else { format!("{lines} lines") };
+    match code.language_name.as_str() {
+        "" => format!("[code: {lines}]"),
+        language => format!("[code: {language}, {lines}]"),
+    }
+}
+
+// Turn a piece of text into a single line.
+fn flatten(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// An error when exporting an outline.
+#[derive(thiserror::Error, Debug)]
+pub enum OutlineError {
+    #[error("failed to read presentation: {0}")]
+    ReadPresentation(io::Error),
+
+    #[error("failed to parse presentation: {0}")]
+    Parse(#[from] ParseError),
+
+    #[error("invalid presentation metadata: {0}")]
+    InvalidMetadata(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use comrak::Arena;
+    use rstest::rstest;
+
+    const DECK: &str = include_str!("../tests/fixtures/outline/deck.md");
+
+    fn generate(input: &str, format: OutlineFormat) -> String {
+        let arena = Arena::new();
+        let exporter = OutlineExporter::new(MarkdownParser::new(&arena), Default::default());
+        exporter.generate(input, format).expect("generation failed")
+    }
+
+    #[rstest]
+    #[case::markdown(OutlineFormat::Markdown, include_str!("../tests/fixtures/outline/deck.outline.md"))]
+    #[case::plain(OutlineFormat::Plain, include_str!("../tests/fixtures/outline/deck.outline.txt"))]
+    fn golden(#[case] format: OutlineFormat, #[case] expected: &str) {
+        assert_eq!(generate(DECK, format), expected);
+    }
+
+    #[test]
+    fn implicit_slide_ends() {
+        let input = "---
+options:
+  implicit_slide_ends: true
+---
+
+First
+===
+
+hi
+
+Second
+===
+";
+        assert_eq!(generate(input, OutlineFormat::Plain), "1. First\n   hi\n2. Second\n");
+    }
+
+    #[test]
+    fn unnumbered_slides() {
+        let input = "# First\n\n<!-- end_slide -->\n\n<!-- unnumbered -->\n# Backup\n\n<!-- end_slide -->\n\n# Last\n";
+        assert_eq!(generate(input, OutlineFormat::Plain), "1. First\n-  Backup\n2. Last\n");
+    }
+
+    #[test]
+    fn redactions() {
+        let input = "---
+title: access to ||acme||
+redact:
+  spoilers: true
+  patterns: ['sk-[a-z]+']
+---
+
+# Key sk-abc
+
+> for ||acme||
+";
+        assert_eq!(generate(input, OutlineFormat::Plain), "1. access to ████\n2. Key ██████\n   for ████\n");
+    }
+}
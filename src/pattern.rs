@@ -0,0 +1,213 @@
+use crate::{
+    custom::SafeAreaMargin,
+    presentation::{AsRenderOperations, PreformattedLine, RenderOperation},
+    render::{capabilities::CellSizePolicy, draw::TerminalDrawer, properties::WindowSize, terminal::TerminalWrite},
+    theme::{Alignment, Margin},
+};
+use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::{io, rc::Rc};
+use unicode_width::UnicodeWidthStr;
+
+// The rows and columns a cross extends to on each side of its center.
+const CROSS_ARM_ROWS: usize = 1;
+const CROSS_ARM_COLUMNS: usize = 2;
+
+/// A test pattern used to check that the entire layout region is visible.
+///
+/// This draws a border around the edge of the layout region, which is the terminal minus the safe
+/// area margin, and a few crosses to check alignment. The margin can be adjusted live so the right
+/// value for a given screen or projector can be found.
+pub struct TestPattern<W: TerminalWrite> {
+    drawer: TerminalDrawer<W>,
+    margin: SafeAreaMargin,
+}
+
+impl<W: TerminalWrite> TestPattern<W> {
+    pub fn new(margin: SafeAreaMargin, cell_size: CellSizePolicy, writer: W) -> io::Result<Self> {
+        let drawer = TerminalDrawer::new(writer, Default::default(), cell_size, Default::default(), margin)?;
+        Ok(Self { drawer, margin })
+    }
+
+    /// Display the test pattern until the user exits, returning the last margin used.
+    pub fn run(mut self) -> Result<SafeAreaMargin, Box<dyn std::error::Error>> {
+        loop {
+            self.drawer.set_safe_area(self.margin);
+            let pattern = PatternGrid { margin: self.margin };
+            self.drawer.render_operations(&[RenderOperation::RenderDynamic(Rc::new(pattern))])?;
+            let command = match read()? {
+                Event::Key(event) if event.kind != KeyEventKind::Release => Self::parse_key(event),
+                _ => continue,
+            };
+            let SafeAreaMargin { rows, columns } = &mut self.margin;
+            match command {
+                Some(PatternCommand::GrowRows) => *rows = rows.saturating_add(1),
+                Some(PatternCommand::ShrinkRows) => *rows = rows.saturating_sub(1),
+                Some(PatternCommand::GrowColumns) => *columns = columns.saturating_add(1),
+                Some(PatternCommand::ShrinkColumns) => *columns = columns.saturating_sub(1),
+                Some(PatternCommand::Reset) => self.margin = SafeAreaMargin::default(),
+                Some(PatternCommand::Exit) => return Ok(self.margin),
+                None => (),
+            };
+        }
+    }
+
+    fn parse_key(event: KeyEvent) -> Option<PatternCommand> {
+        let command = match event.code {
+            KeyCode::Up | KeyCode::Char('k') => PatternCommand::GrowRows,
+            KeyCode::Down | KeyCode::Char('j') => PatternCommand::ShrinkRows,
+            KeyCode::Right | KeyCode::Char('l') => PatternCommand::GrowColumns,
+            KeyCode::Left | KeyCode::Char('h') => PatternCommand::ShrinkColumns,
+            KeyCode::Char('r') => PatternCommand::Reset,
+            KeyCode::Char('c') if event.modifiers == KeyModifiers::CONTROL => PatternCommand::Exit,
+            KeyCode::Char('q') | KeyCode::Esc => PatternCommand::Exit,
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+enum PatternCommand {
+    GrowRows,
+    ShrinkRows,
+    GrowColumns,
+    ShrinkColumns,
+    Reset,
+    Exit,
+}
+
+/// The test pattern itself, which is drawn to fill whatever space is available.
+#[derive(Debug)]
+struct PatternGrid {
+    margin: SafeAreaMargin,
+}
+
+impl PatternGrid {
+    fn build(&self, rows: usize, columns: usize) -> Vec<String> {
+        if rows < 2 || columns < 2 {
+            return vec!["#".repeat(columns); rows];
+        }
+        let mut grid = vec![vec![' '; columns]; rows];
+        for row in &mut grid {
+            row[0] = '│';
+            row[columns - 1] = '│';
+        }
+        grid[0].fill('─');
+        grid[rows - 1].fill('─');
+        grid[0][0] = '┌';
+        grid[0][columns - 1] = '┐';
+        grid[rows - 1][0] = '└';
+        grid[rows - 1][columns - 1] = '┘';
+
+        let info = [
+            format!("layout: {columns}x{rows}, margin: {} rows, {} columns", self.margin.rows, self.margin.columns),
+            "arrows/hjkl: adjust margin, r: reset, q: exit".to_string(),
+        ];
+        for (index, line) in info.iter().enumerate() {
+            let row = rows / 2 + CROSS_ARM_ROWS + 1 + index;
+            // Leave the border and one column on each side of the text untouched.
+            if row >= rows - 1 || line.width() + 4 > columns {
+                continue;
+            }
+            let start = (columns - line.width()) / 2;
+            for (offset, character) in line.chars().enumerate() {
+                grid[row][start + offset] = character;
+            }
+        }
+
+        // Crosses are drawn last so text never hides them on small screens.
+        for row in [rows / 4, rows / 2, rows * 3 / 4] {
+            for column in [columns / 4, columns / 2, columns * 3 / 4] {
+                // Only draw the center cross and the ones in the middle of each quadrant.
+                if (row == rows / 2) == (column == columns / 2) {
+                    Self::draw_cross(&mut grid, row, column);
+                }
+            }
+        }
+        grid.into_iter().map(|row| row.into_iter().collect()).collect()
+    }
+
+    fn draw_cross(grid: &mut [Vec<char>], row: usize, column: usize) {
+        let rows = grid.len();
+        let columns = grid[0].len();
+        // Crosses are only drawn if they don't touch the border.
+        if row <= CROSS_ARM_ROWS || row + CROSS_ARM_ROWS >= rows - 1 {
+            return;
+        }
+        if column <= CROSS_ARM_COLUMNS || column + CROSS_ARM_COLUMNS >= columns - 1 {
+            return;
+        }
+        for line in &mut grid[row - CROSS_ARM_ROWS..=row + CROSS_ARM_ROWS] {
+            line[column] = '│';
+        }
+        grid[row][column - CROSS_ARM_COLUMNS..=column + CROSS_ARM_COLUMNS].fill('─');
+        grid[row][column] = '┼';
+    }
+}
+
+impl AsRenderOperations for PatternGrid {
+    fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
+        let alignment = Alignment::Left { margin: Margin::Fixed(0) };
+        let mut operations = vec![RenderOperation::ClearScreen];
+        for (index, line) in self.build(dimensions.rows as usize, dimensions.columns as usize).into_iter().enumerate() {
+            operations.push(RenderOperation::JumpToRow { index: index as u16 });
+            operations.push(RenderOperation::RenderPreformattedLine(PreformattedLine {
+                text: line,
+                unformatted_length: dimensions.columns,
+                block_length: dimensions.columns,
+                alignment: alignment.clone(),
+            }));
+        }
+        operations
+    }
+
+    fn diffable_content(&self) -> Option<&str> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build(rows: usize, columns: usize) -> Vec<String> {
+        PatternGrid { margin: Default::default() }.build(rows, columns)
+    }
+
+    #[test]
+    fn border() {
+        let grid = build(10, 60);
+        assert_eq!(grid.len(), 10);
+        for row in &grid {
+            assert_eq!(row.width(), 60);
+        }
+        assert_eq!(grid[0], format!("┌{}┐", "─".repeat(58)));
+        assert_eq!(grid[9], format!("└{}┘", "─".repeat(58)));
+        for row in &grid[1..9] {
+            assert!(row.starts_with('│') && row.ends_with('│'), "{row}");
+        }
+    }
+
+    #[test]
+    fn crosses() {
+        let grid = build(20, 80);
+        let at = |row: usize, column: usize| grid[row].chars().nth(column).unwrap();
+        for (row, column) in [(10, 40), (5, 20), (5, 60), (15, 20), (15, 60)] {
+            assert_eq!(at(row, column), '┼');
+            assert_eq!(at(row - 1, column), '│');
+            assert_eq!(at(row + 1, column), '│');
+            assert_eq!(at(row, column - 2), '─');
+            assert_eq!(at(row, column + 2), '─');
+        }
+        // These are the middle of each edge, which don't get a cross.
+        assert_eq!(at(5, 40), ' ');
+        assert_eq!(at(10, 20), ' ');
+        assert!(grid[12].contains("layout: 80x20, margin: 0 rows, 0 columns"));
+    }
+
+    #[test]
+    fn tiny_screen() {
+        assert_eq!(build(1, 3), vec!["###"]);
+        let grid = build(3, 3);
+        assert_eq!(grid, vec!["┌─┐", "│ │", "└─┘"]);
+    }
+}
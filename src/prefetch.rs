@@ -0,0 +1,238 @@
+use crate::{
+    custom::SafeAreaMargin,
+    media::printer::ImagePrinter,
+    presentation::Presentation,
+    render::{
+        engine::{RenderEngine, RenderEngineOptions},
+        properties::WindowSize,
+        terminal::Terminal,
+    },
+};
+use std::{
+    collections::{HashSet, VecDeque},
+    io,
+    rc::Rc,
+};
+
+/// The number of navigations that were served from prefetched slides.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PrefetchStats {
+    /// The number of times the current slide changed.
+    pub(crate) navigations: usize,
+
+    /// The number of times the new slide had already been prefetched.
+    pub(crate) warm_navigations: usize,
+}
+
+/// Prefetches the slides next to the current one so moving to them only needs to paint them.
+///
+/// Slides are prefetched by rendering them into a terminal that discards everything, which lays
+/// out any dynamic content in them at the same size it will be displayed at. Render operations
+/// can't be sent across threads so this happens in the presenter's thread, one slide at a time
+/// while it's idle. Images are loaded in background threads as soon as their slide is adjacent to
+/// the current one.
+///
+/// Only the slides next to the current one are prefetched, as those are the ones the cache budget
+/// never evicts. Moving anywhere else discards whatever was left to prefetch.
+pub(crate) struct Prefetcher {
+    printer: Rc<ImagePrinter>,
+    safe_area: SafeAreaMargin,
+    dimensions: Option<WindowSize>,
+    current: Option<usize>,
+    pending: VecDeque<usize>,
+    warm: HashSet<usize>,
+    stats: PrefetchStats,
+}
+
+impl Prefetcher {
+    pub(crate) fn new(safe_area: SafeAreaMargin) -> Self {
+        Self {
+            printer: Rc::new(ImagePrinter::Null),
+            safe_area,
+            dimensions: None,
+            current: None,
+            pending: Default::default(),
+            warm: Default::default(),
+            stats: Default::default(),
+        }
+    }
+
+    /// Forget everything that was prefetched, e.g. because the presentation was reloaded.
+    pub(crate) fn reset(&mut self) {
+        self.current = None;
+        self.pending.clear();
+        self.warm.clear();
+    }
+
+    pub(crate) fn stats(&self) -> &PrefetchStats {
+        &self.stats
+    }
+
+    /// Mark the current slide as displayed using the given dimensions.
+    ///
+    /// This queues the slides next to it to be prefetched.
+    pub(crate) fn displayed(&mut self, presentation: &Presentation, dimensions: WindowSize) {
+        let resized = self.dimensions.as_ref() != Some(&dimensions);
+        if resized {
+            // Anything laid out for a different size needs to be laid out again.
+            self.warm.clear();
+            self.dimensions = Some(dimensions);
+        }
+        let current = presentation.current_slide_index();
+        match self.current {
+            Some(previous) if previous == current && !resized => return,
+            Some(previous) if previous != current => {
+                self.stats.navigations += 1;
+                if self.warm.contains(&current) && presentation.current_slide().automatic_widgets_rendered() {
+                    self.stats.warm_navigations += 1;
+                }
+            }
+            _ => (),
+        };
+        self.current = Some(current);
+
+        // Slides that aren't next to the current one can be evicted at any time.
+        self.warm.retain(|index| index.abs_diff(current) <= 1);
+        self.warm.insert(current);
+        let slide_count = presentation.iter_slides().count();
+        let adjacent = [Some(current + 1), current.checked_sub(1)];
+        self.pending =
+            adjacent.into_iter().flatten().filter(|index| *index < slide_count && !self.warm.contains(index)).collect();
+    }
+
+    /// Prefetch the next slide in the queue, returning whether there's anything left to prefetch.
+    pub(crate) fn prefetch_next(&mut self, presentation: &Presentation) -> bool {
+        let (Some(index), Some(dimensions)) = (self.pending.pop_front(), &self.dimensions) else {
+            return false;
+        };
+        if let Some(slide) = presentation.iter_slides().nth(index) {
+            // Errors, like the slide overflowing, will show up when it's actually displayed.
+            if let Ok(mut terminal) = Terminal::new(io::Empty::default(), self.printer.clone()) {
+                let options = RenderEngineOptions { safe_area: self.safe_area, ..Default::default() };
+                let engine = RenderEngine::new(&mut terminal, dimensions.clone(), options);
+                let _ = engine.render(slide.iter_all_operations());
+            }
+            self.warm.insert(index);
+        }
+        !self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::presentation::{AsRenderOperations, RenderOperation, SlideBuilder, SlideChunk};
+    use std::{cell::RefCell, rc::Rc};
+
+    const DIMENSIONS: WindowSize = WindowSize { rows: 20, columns: 40, width: 0, height: 0 };
+
+    #[derive(Debug, Default)]
+    struct FakeLayout {
+        laid_out: RefCell<Vec<u16>>,
+    }
+
+    impl AsRenderOperations for FakeLayout {
+        fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
+            self.laid_out.borrow_mut().push(dimensions.columns);
+            Vec::new()
+        }
+
+        fn diffable_content(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    struct Deck {
+        presentation: Presentation,
+        layouts: Vec<Rc<FakeLayout>>,
+        prefetcher: Prefetcher,
+    }
+
+    impl Deck {
+        fn new(slide_count: usize) -> Self {
+            let mut slides = Vec::new();
+            let mut layouts = Vec::new();
+            for _ in 0..slide_count {
+                let layout = Rc::new(FakeLayout::default());
+                let operations = vec![RenderOperation::RenderDynamic(layout.clone())];
+                slides.push(SlideBuilder::default().chunks(vec![SlideChunk::new(operations, vec![])]).build());
+                layouts.push(layout);
+            }
+            Self { presentation: Presentation::from(slides), layouts, prefetcher: Prefetcher::new(Default::default()) }
+        }
+
+        // Simulates the presenter displaying the current slide and then idling.
+        fn display(&mut self, dimensions: WindowSize) {
+            self.prefetcher.displayed(&self.presentation, dimensions);
+            while self.prefetcher.prefetch_next(&self.presentation) {}
+        }
+
+        fn laid_out(&self) -> Vec<usize> {
+            self.layouts
+                .iter()
+                .enumerate()
+                .filter(|(_, layout)| !layout.laid_out.borrow().is_empty())
+                .map(|(index, _)| index)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn sequential_navigation_is_warm() {
+        let slide_count = 50;
+        let mut deck = Deck::new(slide_count);
+        deck.display(DIMENSIONS);
+        while deck.presentation.jump_next() {
+            deck.display(DIMENSIONS);
+        }
+        while deck.presentation.jump_previous() {
+            deck.display(DIMENSIONS);
+        }
+        let stats = deck.prefetcher.stats();
+        assert_eq!(stats.navigations, 2 * (slide_count - 1));
+        assert_eq!(stats.warm_navigations, stats.navigations);
+    }
+
+    #[test]
+    fn adjacent_slides_laid_out() {
+        let mut deck = Deck::new(10);
+        deck.presentation.go_to_slide(4);
+        deck.display(DIMENSIONS);
+        assert_eq!(deck.laid_out(), &[3, 5]);
+        assert_eq!(*deck.layouts[5].laid_out.borrow(), &[DIMENSIONS.columns]);
+
+        // Nothing is laid out again while moving around.
+        deck.presentation.jump_next();
+        deck.display(DIMENSIONS);
+        assert_eq!(deck.laid_out(), &[3, 5, 6]);
+        assert_eq!(deck.layouts[5].laid_out.borrow().len(), 1);
+    }
+
+    #[test]
+    fn jumping_redirects_prefetching() {
+        let mut deck = Deck::new(10);
+        deck.display(DIMENSIONS);
+        deck.presentation.jump_next();
+        // Move away before the adjacent slides are prefetched.
+        deck.prefetcher.displayed(&deck.presentation, DIMENSIONS);
+        deck.presentation.go_to_slide(7);
+        deck.display(DIMENSIONS);
+        assert_eq!(deck.laid_out(), &[1, 6, 8]);
+
+        let stats = deck.prefetcher.stats();
+        assert_eq!(stats.navigations, 2);
+        assert_eq!(stats.warm_navigations, 1);
+    }
+
+    #[test]
+    fn resizing_invalidates() {
+        let mut deck = Deck::new(3);
+        deck.display(DIMENSIONS);
+        let dimensions = WindowSize { columns: 80, ..DIMENSIONS };
+        deck.display(dimensions.clone());
+        deck.presentation.jump_next();
+        deck.display(dimensions);
+        assert_eq!(*deck.layouts[1].laid_out.borrow(), &[40, 80]);
+        assert_eq!(deck.prefetcher.stats().warm_navigations, 1);
+    }
+}
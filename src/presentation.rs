@@ -1,32 +1,182 @@
 use crate::{
+    cache::CacheUsage,
     custom::OptionsConfig,
     markdown::text::WeightedTextBlock,
     media::image::Image,
-    render::properties::WindowSize,
+    processing::{
+        details::{self, CollapsibleDetails},
+        directives::DirectiveWarning,
+        focus::FocusableCode,
+        split::BudgetWarning,
+        structure::StructureWarning,
+    },
+    render::{gradient::BackgroundGradient, properties::WindowSize, region::PaintRegion},
     style::{Color, Colors},
     theme::{Alignment, Margin, PresentationTheme},
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
-use std::{cell::RefCell, fmt::Debug, ops::Deref, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fmt::{self, Debug, Display},
+    mem,
+    ops::Deref,
+    rc::Rc,
+    slice,
+    time::Duration,
+};
+
+/// A `TODO` or `FIXME` note left in a comment, which is never rendered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TodoNote {
+    /// The slide this note is in.
+    pub slide: SlideReference,
+
+    /// The line this note is in.
+    pub line: usize,
+
+    /// The note itself, including its `TODO`/`FIXME` marker.
+    pub text: String,
+}
+
+impl Display for TodoNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, line {}: {}", self.slide, self.line, self.text)
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct Modals {
     pub(crate) slide_index: Vec<RenderOperation>,
     pub(crate) bindings: Vec<RenderOperation>,
+    pub(crate) glossary: Vec<RenderOperation>,
+    pub(crate) prompt_colors: Colors,
+    pub(crate) error_colors: Colors,
 }
 
 /// A presentation.
 #[derive(Debug)]
 pub(crate) struct Presentation {
     slides: Vec<Slide>,
+    numbering: SlideNumbering,
     modals: Modals,
     state: PresentationState,
+    title: Option<String>,
+    todos: Vec<TodoNote>,
+    budget_warnings: Vec<BudgetWarning>,
+    structure_warnings: Vec<StructureWarning>,
+    directive_warnings: Vec<DirectiveWarning>,
+    end_slide: Option<Slide>,
+    at_end: bool,
 }
 
 impl Presentation {
     /// Construct a new presentation.
     pub(crate) fn new(slides: Vec<Slide>, modals: Modals, state: PresentationState) -> Self {
-        Self { slides, modals, state }
+        let numbering = SlideNumbering::new(slides.iter().map(Slide::is_numbered));
+        Self {
+            slides,
+            numbering,
+            modals,
+            state,
+            title: None,
+            todos: Vec::new(),
+            budget_warnings: Vec::new(),
+            structure_warnings: Vec::new(),
+            directive_warnings: Vec::new(),
+            end_slide: None,
+            at_end: false,
+        }
+    }
+
+    /// Set the slide shown after moving forward on the last slide.
+    ///
+    /// This slide isn't part of the presentation's slides so it isn't counted anywhere.
+    pub(crate) fn with_end_slide(mut self, slide: Option<Slide>) -> Self {
+        self.end_slide = slide;
+        self
+    }
+
+    /// Check whether the end slide is being displayed.
+    pub(crate) fn is_at_end(&self) -> bool {
+        self.at_end
+    }
+
+    /// Set the title of this presentation.
+    pub(crate) fn with_title(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Set the TODO/FIXME notes found in this presentation.
+    pub(crate) fn with_todos(mut self, todos: Vec<TodoNote>) -> Self {
+        self.todos = todos;
+        self
+    }
+
+    /// Get the TODO/FIXME notes found in this presentation.
+    pub(crate) fn todos(&self) -> &[TodoNote] {
+        &self.todos
+    }
+
+    /// Set the problems found when fitting this presentation's slides in its slide budget.
+    pub(crate) fn with_budget_warnings(mut self, warnings: Vec<BudgetWarning>) -> Self {
+        self.budget_warnings = warnings;
+        self
+    }
+
+    /// Get the problems found when fitting this presentation's slides in its slide budget.
+    pub(crate) fn budget_warnings(&self) -> &[BudgetWarning] {
+        &self.budget_warnings
+    }
+
+    /// Set the problems found in the structure of this presentation's headings and images.
+    pub(crate) fn with_structure_warnings(mut self, warnings: Vec<StructureWarning>) -> Self {
+        self.structure_warnings = warnings;
+        self
+    }
+
+    /// Get the problems found in the structure of this presentation's headings and images.
+    pub(crate) fn structure_warnings(&self) -> &[StructureWarning] {
+        &self.structure_warnings
+    }
+
+    /// Set the commands written using a deprecated syntax in this presentation.
+    pub(crate) fn with_directive_warnings(mut self, warnings: Vec<DirectiveWarning>) -> Self {
+        self.directive_warnings = warnings;
+        self
+    }
+
+    /// Get the commands written using a deprecated syntax in this presentation.
+    pub(crate) fn directive_warnings(&self) -> &[DirectiveWarning] {
+        &self.directive_warnings
+    }
+
+    /// Get the title of this presentation, if it has one.
+    pub(crate) fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Get the number of slides in this presentation.
+    pub(crate) fn slide_count(&self) -> usize {
+        self.slides.len()
+    }
+
+    /// Get the numbers this presentation's slides are displayed with.
+    pub(crate) fn numbering(&self) -> &SlideNumbering {
+        &self.numbering
+    }
+
+    /// Get the number of words in this presentation, excluding code blocks.
+    pub(crate) fn word_count(&self) -> usize {
+        self.slides.iter().map(Slide::word_count).sum()
+    }
+
+    /// Estimate how long it takes to read this presentation out loud at the given number of words per minute.
+    pub(crate) fn estimated_duration(&self, words_per_minute: u32) -> Duration {
+        let minutes = self.word_count() as f64 / words_per_minute.max(1) as f64;
+        Duration::from_secs_f64(minutes * 60.0)
     }
 
     /// Iterate the slides in this presentation.
@@ -44,6 +194,21 @@ impl Presentation {
         self.modals.bindings.iter()
     }
 
+    /// The colors to use for the command line prompt.
+    pub(crate) fn prompt_colors(&self) -> &Colors {
+        &self.modals.prompt_colors
+    }
+
+    /// The colors to use for errors displayed in the status line.
+    pub(crate) fn error_colors(&self) -> &Colors {
+        &self.modals.error_colors
+    }
+
+    /// Iterate the operations that render the modal with the current slide's glossary terms.
+    pub(crate) fn iter_glossary_operations(&self) -> impl Iterator<Item = &RenderOperation> {
+        self.modals.glossary.iter()
+    }
+
     /// Consume this presentation and return its slides.
     #[cfg(test)]
     pub(crate) fn into_slides(self) -> Vec<Slide> {
@@ -51,8 +216,13 @@ impl Presentation {
     }
 
     /// Get the current slide.
+    ///
+    /// This is the end slide if it's being displayed.
     pub(crate) fn current_slide(&self) -> &Slide {
-        &self.slides[self.current_slide_index()]
+        match &self.end_slide {
+            Some(slide) if self.at_end => slide,
+            _ => &self.slides[self.current_slide_index()],
+        }
     }
 
     /// Get the current slide index.
@@ -61,7 +231,12 @@ impl Presentation {
     }
 
     /// Jump forwards.
+    ///
+    /// Moving forward on the last slide goes to the end slide, if there's one.
     pub(crate) fn jump_next(&mut self) -> bool {
+        if self.at_end {
+            return false;
+        }
         let current_slide = self.current_slide_mut();
         if current_slide.move_next() {
             return true;
@@ -72,6 +247,9 @@ impl Presentation {
             // Going forward we show only the first chunk.
             self.current_slide_mut().show_first_chunk();
             true
+        } else if self.end_slide.is_some() {
+            self.at_end = true;
+            true
         } else {
             false
         }
@@ -79,6 +257,10 @@ impl Presentation {
 
     /// Jump backwards.
     pub(crate) fn jump_previous(&mut self) -> bool {
+        // The last slide is still showing all of its chunks so simply go back to it.
+        if mem::take(&mut self.at_end) {
+            return true;
+        }
         let current_slide = self.current_slide_mut();
         if current_slide.move_previous() {
             return true;
@@ -105,9 +287,21 @@ impl Presentation {
         self.go_to_slide(last_slide_index)
     }
 
+    /// Jump to the end of the last slide, showing all of its chunks.
+    ///
+    /// This is the same as moving backwards into the last slide.
+    pub(crate) fn jump_last_slide_end(&mut self) -> bool {
+        let moved = self.jump_last_slide();
+        if moved {
+            self.current_slide_mut().show_all_chunks();
+        }
+        moved
+    }
+
     /// Jump to a specific slide.
     pub(crate) fn go_to_slide(&mut self, slide_index: usize) -> bool {
         if slide_index < self.slides.len() {
+            self.at_end = false;
             self.state.set_current_slide_index(slide_index);
             // Always show only the first slide when jumping to a particular one.
             self.current_slide_mut().show_first_chunk();
@@ -117,6 +311,15 @@ impl Presentation {
         }
     }
 
+    /// Jump to the end of a specific slide, showing all of its chunks.
+    pub(crate) fn go_to_slide_end(&mut self, slide_index: usize) -> bool {
+        let moved = self.go_to_slide(slide_index);
+        if moved {
+            self.current_slide_mut().show_all_chunks();
+        }
+        moved
+    }
+
     /// Jump to a specific chunk within the current slide.
     pub(crate) fn jump_chunk(&mut self, chunk_index: usize) {
         self.current_slide_mut().jump_chunk(chunk_index);
@@ -127,30 +330,89 @@ impl Presentation {
         self.current_slide().current_chunk_index()
     }
 
+    /// Jump to a specific step within the current slide.
+    pub(crate) fn jump_step(&mut self, step_index: usize) {
+        let slide = self.current_slide_mut();
+        let last_step = slide.steps.total() - 1;
+        slide.jump_step(step_index.min(last_step));
+    }
+
+    /// Get the index of the current slide's step.
+    pub(crate) fn current_step(&self) -> usize {
+        self.current_slide().steps.current()
+    }
+
     /// Render all widgets in this slide.
     pub(crate) fn render_slide_widgets(&mut self) -> bool {
-        let slide = self.current_slide_mut();
-        let mut any_rendered = false;
-        for operation in slide.iter_operations_mut() {
-            if let RenderOperation::RenderOnDemand(operation) = operation {
-                any_rendered = any_rendered || operation.start_render();
+        Self::start_widgets(self.current_slide_mut(), |_| true)
+    }
+
+    /// Check whether any of the widgets in the current slide runs code.
+    pub(crate) fn current_slide_runs_code(&self) -> bool {
+        self.current_slide().iter_on_demand().any(|operation| operation.runs_code())
+    }
+
+    /// Render the widgets that start rendering automatically in this slide and the ones next to it.
+    ///
+    /// Adjacent slides are included so they're ready by the time they're displayed.
+    pub(crate) fn render_automatic_widgets(&mut self) {
+        if self.slides.is_empty() {
+            return;
+        }
+        let current = self.current_slide_index();
+        let first = current.saturating_sub(1);
+        let last = (current + 1).min(self.slides.len() - 1);
+        for (index, slide) in self.slides[first..=last].iter_mut().enumerate() {
+            let visible = first + index == current;
+            Self::start_widgets(slide, |operation| match operation.start_policy() {
+                RenderOnDemandStartPolicy::Automatic => true,
+                RenderOnDemandStartPolicy::Visible => visible,
+                RenderOnDemandStartPolicy::OnDemand => false,
+            });
+        }
+    }
+
+    /// Pause the widgets in a slide that's no longer being displayed.
+    pub(crate) fn pause_widgets(&mut self, slide_index: usize) {
+        let Some(slide) = self.slides.get(slide_index) else {
+            return;
+        };
+        for operation in slide.iter_all_on_demand() {
+            operation.pause_render();
+        }
+    }
+
+    /// Notify every widget in the presentation that the terminal gained or lost focus.
+    pub(crate) fn focus_changed(&self, focused: bool) {
+        for slide in &self.slides {
+            for operation in slide.iter_all_on_demand() {
+                operation.focus_changed(focused);
             }
         }
-        any_rendered
     }
 
-    /// Poll every widget in the current slide and check whether they're rendered.
+    /// Poll every widget in the current slide and check whether they're done rendering.
+    ///
+    /// Widgets that were never started are not considered to be pending.
     pub(crate) fn widgets_rendered(&mut self) -> bool {
         let slide = self.current_slide_mut();
         let mut all_rendered = true;
-        for operation in slide.iter_operations_mut() {
-            if let RenderOperation::RenderOnDemand(operation) = operation {
-                all_rendered = all_rendered && matches!(operation.poll_state(), RenderOnDemandState::Rendered);
-            }
+        for operation in slide.iter_on_demand() {
+            all_rendered = all_rendered && !matches!(operation.poll_state(), RenderOnDemandState::Rendering);
         }
         all_rendered
     }
 
+    /// Check whether any of the widgets in the current slide needs the terminal's bell to be rung.
+    pub(crate) fn take_bell(&self) -> bool {
+        // Every widget is checked so none of them rings the bell again later on.
+        let mut ring = false;
+        for operation in self.current_slide().iter_on_demand() {
+            ring |= operation.take_bell();
+        }
+        ring
+    }
+
     /// Run a callback through every operation and let it mutate it in place.
     ///
     /// This should be used with care!
@@ -167,19 +429,115 @@ impl Presentation {
         }
     }
 
+    fn start_widgets<F>(slide: &mut Slide, filter: F) -> bool
+    where
+        F: Fn(&dyn RenderOnDemand) -> bool,
+    {
+        let mut any_rendered = false;
+        for operation in slide.iter_on_demand() {
+            if filter(operation.as_ref()) {
+                any_rendered = operation.start_render() || any_rendered;
+            }
+        }
+        any_rendered
+    }
+
     fn current_slide_mut(&mut self) -> &mut Slide {
         let index = self.current_slide_index();
-        &mut self.slides[index]
+        match &mut self.end_slide {
+            Some(slide) if self.at_end => slide,
+            _ => &mut self.slides[index],
+        }
     }
 }
 
 impl From<Vec<Slide>> for Presentation {
     fn from(slides: Vec<Slide>) -> Self {
-        let modals = Modals { slide_index: vec![], bindings: vec![] };
+        let modals = Modals {
+            slide_index: vec![],
+            bindings: vec![],
+            glossary: vec![],
+            prompt_colors: Default::default(),
+            error_colors: Default::default(),
+        };
         Self::new(slides, modals, Default::default())
     }
 }
 
+/// The numbers slides are displayed with.
+///
+/// Slides are always referred to by their index within the presentation internally. Slides can
+/// opt out of being numbered though, so anything that shows a slide number to the user, or takes
+/// one from them, needs to go through this to convert between the two.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SlideNumbering {
+    numbers: Vec<Option<usize>>,
+    indexes: Vec<usize>,
+}
+
+impl SlideNumbering {
+    /// Construct the numbering for slides given whether each of them is numbered.
+    pub(crate) fn new<I: IntoIterator<Item = bool>>(numbered: I) -> Self {
+        let mut numbers = Vec::new();
+        let mut indexes = Vec::new();
+        for (index, numbered) in numbered.into_iter().enumerate() {
+            if numbered {
+                indexes.push(index);
+                numbers.push(Some(indexes.len()));
+            } else {
+                numbers.push(None);
+            }
+        }
+        Self { numbers, indexes }
+    }
+
+    /// Get the number the slide at the given index is displayed with, starting at 1.
+    pub(crate) fn display_number(&self, slide_index: usize) -> Option<usize> {
+        self.numbers.get(slide_index).copied().flatten()
+    }
+
+    /// Get the index of the slide displayed with the given number.
+    pub(crate) fn slide_index(&self, number: usize) -> Option<usize> {
+        self.indexes.get(number.checked_sub(1)?).copied()
+    }
+
+    /// Get the number of slides that are numbered.
+    pub(crate) fn total(&self) -> usize {
+        self.indexes.len()
+    }
+
+    /// Get the way the slide at the given index is referred to when telling the user about it.
+    pub(crate) fn reference(&self, slide_index: usize) -> SlideReference {
+        match self.display_number(slide_index) {
+            Some(number) => SlideReference::Numbered(number),
+            None => {
+                let previous = self.numbers.iter().take(slide_index).rev().flatten().next().copied();
+                SlideReference::Unnumbered { after: previous }
+            }
+        }
+    }
+}
+
+/// The way a slide is referred to when telling the user about it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlideReference {
+    /// A slide displayed with this number.
+    Numbered(usize),
+
+    /// A slide that isn't numbered, along with the number of the last numbered slide before it.
+    Unnumbered { after: Option<usize> },
+}
+
+impl Display for SlideReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Numbered(number) => write!(f, "slide {number}"),
+            Self::Unnumbered { after: Some(number) } => write!(f, "unnumbered slide after slide {number}"),
+            Self::Unnumbered { after: None } => write!(f, "unnumbered slide at the start"),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct PresentationStateInner {
     current_slide_index: usize,
@@ -195,7 +553,7 @@ impl PresentationState {
         self.inner.deref().borrow().current_slide_index
     }
 
-    fn set_current_slide_index(&self, value: usize) {
+    pub(crate) fn set_current_slide_index(&self, value: usize) {
         self.inner.deref().borrow_mut().current_slide_index = value;
     }
 }
@@ -205,6 +563,15 @@ impl PresentationState {
 pub(crate) struct SlideBuilder {
     chunks: Vec<SlideChunk>,
     footer: Vec<RenderOperation>,
+    title: Option<String>,
+    char_count: usize,
+    code_char_count: usize,
+    word_count: usize,
+    max_duration: Option<Duration>,
+    focusable_code: Vec<FocusableCode>,
+    details: Vec<Rc<CollapsibleDetails>>,
+    unnumbered: bool,
+    steps: Option<Rc<SlideSteps>>,
 }
 
 impl SlideBuilder {
@@ -218,8 +585,61 @@ impl SlideBuilder {
         self
     }
 
+    pub(crate) fn title(mut self, title: String) -> Self {
+        self.title = Some(title);
+        self
+    }
+
+    pub(crate) fn char_counts(mut self, char_count: usize, code_char_count: usize) -> Self {
+        self.char_count = char_count;
+        self.code_char_count = code_char_count;
+        self
+    }
+
+    pub(crate) fn word_count(mut self, word_count: usize) -> Self {
+        self.word_count = word_count;
+        self
+    }
+
+    pub(crate) fn max_duration(mut self, max_duration: Option<Duration>) -> Self {
+        self.max_duration = max_duration;
+        self
+    }
+
+    pub(crate) fn focusable_code(mut self, focusable_code: Vec<FocusableCode>) -> Self {
+        self.focusable_code = focusable_code;
+        self
+    }
+
+    pub(crate) fn details(mut self, details: Vec<Rc<CollapsibleDetails>>) -> Self {
+        self.details = details;
+        self
+    }
+
+    pub(crate) fn numbered(mut self, numbered: bool) -> Self {
+        self.unnumbered = !numbered;
+        self
+    }
+
+    pub(crate) fn steps(mut self, steps: Rc<SlideSteps>) -> Self {
+        self.steps = Some(steps);
+        self
+    }
+
     pub(crate) fn build(self) -> Slide {
-        Slide::new(self.chunks, self.footer)
+        let mut slide = Slide::new(self.chunks, self.footer);
+        if let Some(steps) = self.steps {
+            slide.steps = steps;
+        }
+        slide.title = self.title;
+        slide.char_count = self.char_count;
+        slide.code_char_count = self.code_char_count;
+        slide.word_count = self.word_count;
+        slide.max_duration = self.max_duration;
+        slide.focusable_code = self.focusable_code;
+        slide.details = self.details;
+        slide.numbered = !self.unnumbered;
+        slide
     }
 }
 
@@ -232,23 +652,129 @@ pub(crate) struct Slide {
     chunks: Vec<SlideChunk>,
     footer: Vec<RenderOperation>,
     visible_chunks: usize,
+    title: Option<String>,
+    char_count: usize,
+    code_char_count: usize,
+    word_count: usize,
+    max_duration: Option<Duration>,
+    focusable_code: Vec<FocusableCode>,
+    details: Vec<Rc<CollapsibleDetails>>,
+    numbered: bool,
+    steps: Rc<SlideSteps>,
 }
 
 impl Slide {
     pub(crate) fn new(chunks: Vec<SlideChunk>, footer: Vec<RenderOperation>) -> Self {
-        Self { chunks, footer, visible_chunks: 1 }
+        let steps = Rc::new(SlideSteps::new(&chunks, &[]));
+        Self {
+            chunks,
+            footer,
+            visible_chunks: 1,
+            title: None,
+            char_count: 0,
+            code_char_count: 0,
+            word_count: 0,
+            max_duration: None,
+            focusable_code: Vec::new(),
+            details: Vec::new(),
+            numbered: true,
+            steps,
+        }
+    }
+
+    /// Get this slide's title, if it has one.
+    pub(crate) fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Get the steps this slide is revealed in.
+    pub(crate) fn steps(&self) -> &SlideSteps {
+        &self.steps
+    }
+
+    /// Check whether this slide gets a number.
+    pub(crate) fn is_numbered(&self) -> bool {
+        self.numbered
+    }
+
+    /// Get the number of characters in this slide's text, excluding markup and code blocks.
+    pub(crate) fn char_count(&self) -> usize {
+        self.char_count
+    }
+
+    /// Get how long this slide is meant to be displayed for, at most.
+    pub(crate) fn max_duration(&self) -> Option<Duration> {
+        self.max_duration
+    }
+
+    /// Get the code blocks in the visible part of this slide that can be focused.
+    pub(crate) fn focusable_code(&self) -> Vec<&FocusableCode> {
+        self.focusable_code.iter().filter(|code| code.chunk < self.visible_chunks).collect()
+    }
+
+    /// Expand the next collapsed `<details>` block in the visible part of this slide, or collapse
+    /// all of them if they're all expanded.
+    ///
+    /// Returns whether the slide changed.
+    pub(crate) fn toggle_details(&self) -> bool {
+        let visible: Vec<_> =
+            self.details.iter().filter(|details| details.chunk < self.visible_chunks).map(Rc::as_ref).collect();
+        details::toggle_details(&visible)
+    }
+
+    /// Get the number of characters in this slide's code blocks.
+    pub(crate) fn code_char_count(&self) -> usize {
+        self.code_char_count
+    }
+
+    /// Get the number of words in this slide's text, excluding code blocks.
+    pub(crate) fn word_count(&self) -> usize {
+        self.word_count
     }
 
     pub(crate) fn iter_operations(&self) -> impl Iterator<Item = &RenderOperation> + Clone {
         self.chunks.iter().take(self.visible_chunks).flat_map(|chunk| chunk.operations.iter()).chain(self.footer.iter())
     }
 
-    pub(crate) fn iter_operations_mut(&mut self) -> impl Iterator<Item = &mut RenderOperation> {
-        self.chunks
-            .iter_mut()
-            .take(self.visible_chunks)
-            .flat_map(|chunk| chunk.operations.iter_mut())
-            .chain(self.footer.iter_mut())
+    /// Get the visible operations in this slide that match a predicate.
+    #[cfg(test)]
+    pub(crate) fn filter_operations<P>(&self, predicate: P) -> Vec<&RenderOperation>
+    where
+        P: Fn(&RenderOperation) -> bool,
+    {
+        self.iter_operations().filter(|operation| predicate(operation)).collect()
+    }
+
+    /// Iterate the visible on demand operations in this slide, including the ones nested in other operations.
+    pub(crate) fn iter_on_demand(&self) -> impl Iterator<Item = &Rc<dyn RenderOnDemand>> {
+        self.iter_operations().flat_map(RenderOperation::on_demand_operations)
+    }
+
+    /// Iterate the operations in every chunk, including the ones that aren't visible yet.
+    pub(crate) fn iter_all_operations(&self) -> impl Iterator<Item = &RenderOperation> {
+        self.chunks.iter().flat_map(|chunk| chunk.operations.iter()).chain(self.footer.iter())
+    }
+
+    /// Iterate the on demand operations in every chunk, including the ones that aren't visible yet.
+    pub(crate) fn iter_all_on_demand(&self) -> impl Iterator<Item = &Rc<dyn RenderOnDemand>> {
+        self.iter_all_operations().flat_map(RenderOperation::on_demand_operations)
+    }
+
+    /// Check whether every visible widget that starts rendering automatically is done rendering.
+    pub(crate) fn automatic_widgets_rendered(&self) -> bool {
+        self.iter_on_demand().all(|operation| {
+            !matches!(operation.start_policy(), RenderOnDemandStartPolicy::Automatic)
+                || matches!(operation.poll_state(), RenderOnDemandState::Rendered)
+        })
+    }
+
+    /// Check whether every visible widget that's still rendering is painted in a region of its own.
+    ///
+    /// If so, painting those regions again is enough to show how they changed.
+    pub(crate) fn rendering_widgets_in_regions(&self) -> bool {
+        self.iter_on_demand()
+            .filter(|operation| matches!(operation.poll_state(), RenderOnDemandState::Rendering))
+            .all(|operation| operation.clone().region_painter().is_some())
     }
 
     pub(crate) fn iter_chunks(&self) -> impl Iterator<Item = &SlideChunk> {
@@ -261,10 +787,27 @@ impl Slide {
     }
 
     fn jump_chunk(&mut self, chunk_index: usize) {
-        self.visible_chunks = (chunk_index + 1).min(self.chunks.len());
-        for chunk in self.chunks.iter().take(self.visible_chunks - 1) {
+        let chunk_index = chunk_index.min(self.chunks.len().saturating_sub(1));
+        let step_index = self.steps.steps.iter().position(|step| step.chunk == chunk_index).unwrap_or(0);
+        self.jump_step(step_index);
+    }
+
+    // Show everything up to and including a step.
+    fn jump_step(&mut self, step_index: usize) -> bool {
+        let Some(step) = self.steps.get(step_index).copied() else {
+            return false;
+        };
+        self.visible_chunks = step.chunk + 1;
+        for chunk in self.chunks.iter().take(step.chunk) {
             chunk.apply_all_mutations();
         }
+        let chunk = self.current_chunk();
+        chunk.reset_mutations();
+        for _ in 0..step.mutation {
+            chunk.mutate_next();
+        }
+        self.steps.current.set(step_index);
+        true
     }
 
     fn current_chunk_index(&self) -> usize {
@@ -276,41 +819,21 @@ impl Slide {
     }
 
     fn show_first_chunk(&mut self) {
-        self.visible_chunks = 1;
-        self.current_chunk().reset_mutations();
+        self.jump_step(0);
     }
 
     fn show_all_chunks(&mut self) {
-        self.visible_chunks = self.chunks.len();
-        for chunk in &self.chunks {
-            chunk.apply_all_mutations();
-        }
+        self.jump_step(self.steps.total() - 1);
     }
 
     fn move_next(&mut self) -> bool {
-        if self.chunks[self.current_chunk_index()].mutate_next() {
-            return true;
-        }
-
-        if self.visible_chunks == self.chunks.len() {
-            false
-        } else {
-            self.visible_chunks += 1;
-            self.current_chunk().reset_mutations();
-            true
-        }
+        self.jump_step(self.steps.current() + 1)
     }
 
     fn move_previous(&mut self) -> bool {
-        if self.chunks[self.current_chunk_index()].mutate_previous() {
-            return true;
-        }
-        if self.visible_chunks == 1 {
-            false
-        } else {
-            self.visible_chunks -= 1;
-            self.current_chunk().apply_all_mutations();
-            true
+        match self.steps.current().checked_sub(1) {
+            Some(step_index) => self.jump_step(step_index),
+            None => false,
         }
     }
 }
@@ -336,6 +859,10 @@ impl SlideChunk {
         self.operations.iter()
     }
 
+    pub(crate) fn mutators(&self) -> &[Box<dyn ChunkMutator>] {
+        &self.mutators
+    }
+
     pub(crate) fn pop_last(&mut self) -> Option<RenderOperation> {
         self.operations.pop()
     }
@@ -349,15 +876,6 @@ impl SlideChunk {
         false
     }
 
-    fn mutate_previous(&self) -> bool {
-        for mutator in self.mutators.iter().rev() {
-            if mutator.mutate_previous() {
-                return true;
-            }
-        }
-        false
-    }
-
     fn reset_mutations(&self) {
         for mutator in &self.mutators {
             mutator.reset_mutations();
@@ -373,15 +891,100 @@ impl SlideChunk {
 
 pub(crate) trait ChunkMutator: Debug {
     fn mutate_next(&self) -> bool;
-    fn mutate_previous(&self) -> bool;
     fn reset_mutations(&self);
     fn apply_all_mutations(&self);
-    #[allow(dead_code)]
+
+    /// Get the current mutation and the total number of states this mutator can be in.
     fn mutations(&self) -> (usize, usize);
+
+    /// Get the kind of step every mutation is.
+    fn step_kind(&self) -> StepKind;
+}
+
+/// What a step in a slide reveals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StepKind {
+    /// The start of the slide.
+    Start,
+
+    /// Whatever comes after a pause.
+    Pause,
+
+    /// The next item in an incremental list.
+    ListItem,
+
+    /// The next row in an incremental table.
+    TableRow,
+
+    /// The next group of highlighted lines in a code block.
+    CodeHighlight,
+}
+
+/// A step in which part of a slide is revealed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SlideStep {
+    /// The last visible chunk.
+    pub(crate) chunk: usize,
+
+    /// The number of mutations applied to the last visible chunk.
+    pub(crate) mutation: usize,
+
+    /// What this step reveals.
+    pub(crate) kind: StepKind,
+}
+
+/// The steps a slide is revealed in.
+///
+/// Every chunk in a slide starts a step and so does every mutation within it, like highlighting
+/// the next group of lines in a code block. A step's index within its slide identifies it.
+#[derive(Debug, Default)]
+pub(crate) struct SlideSteps {
+    steps: Vec<SlideStep>,
+    current: Cell<usize>,
+}
+
+impl SlideSteps {
+    /// Compute the steps for a slide's chunks.
+    ///
+    /// `kinds` contains the kind of step that starts every chunk after the first one. Chunks
+    /// without one are assumed to start after a pause.
+    pub(crate) fn new(chunks: &[SlideChunk], kinds: &[StepKind]) -> Self {
+        let mut steps = Vec::new();
+        for (chunk_index, chunk) in chunks.iter().enumerate() {
+            let kind = match chunk_index {
+                0 => StepKind::Start,
+                _ => kinds.get(chunk_index - 1).copied().unwrap_or(StepKind::Pause),
+            };
+            steps.push(SlideStep { chunk: chunk_index, mutation: 0, kind });
+            for mutator in &chunk.mutators {
+                let (_, total) = mutator.mutations();
+                for _ in 1..total {
+                    let mutation = steps.last().map(|step| step.mutation).unwrap_or_default() + 1;
+                    steps.push(SlideStep { chunk: chunk_index, mutation, kind: mutator.step_kind() });
+                }
+            }
+        }
+        Self { steps, current: Default::default() }
+    }
+
+    /// Get the total number of steps.
+    pub(crate) fn total(&self) -> usize {
+        self.steps.len().max(1)
+    }
+
+    /// Get the index of the current step.
+    pub(crate) fn current(&self) -> usize {
+        self.current.get()
+    }
+
+    /// Get a step by its index.
+    pub(crate) fn get(&self, index: usize) -> Option<&SlideStep> {
+        self.steps.get(index)
+    }
 }
 
 /// The metadata for a presentation.
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
 pub(crate) struct PresentationMetadata {
     /// The presentation title.
     pub(crate) title: Option<String>,
@@ -405,16 +1008,37 @@ pub(crate) struct PresentationMetadata {
     /// The presentation's options.
     #[serde(default)]
     pub(crate) options: Option<OptionsConfig>,
+
+    /// The terms used in the presentation, along with what they stand for.
+    #[serde(default)]
+    pub(crate) glossary: BTreeMap<String, String>,
+
+    /// The text to be hidden in the presentation.
+    #[serde(default)]
+    pub(crate) redact: RedactionConfig,
+}
+
+/// The text to be hidden in a presentation.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RedactionConfig {
+    /// Whether text surrounded by `||`, like `||this||`, is hidden.
+    #[serde(default)]
+    pub(crate) spoilers: bool,
+
+    /// Regular expressions that match the text to be hidden.
+    #[serde(default)]
+    pub(crate) patterns: Vec<String>,
 }
 
 /// A presentation's theme metadata.
-#[derive(Clone, Debug, Default, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema)]
 pub(crate) struct PresentationThemeMetadata {
     /// The theme name.
     #[serde(default)]
     pub(crate) name: Option<String>,
 
-    /// the theme path.
+    /// The theme path.
     #[serde(default)]
     pub(crate) path: Option<String>,
 
@@ -444,8 +1068,17 @@ pub(crate) enum RenderOperation {
     /// Set the colors to be used for any subsequent operations.
     SetColors(Colors),
 
-    /// Jump the draw cursor into the vertical center, that is, at `screen_height / 2`.
-    JumpToVerticalCenter,
+    /// Fill the screen's background with a vertical gradient.
+    ///
+    /// Text drawn afterwards using the current background color is drawn on top of the gradient.
+    RenderBackgroundGradient(BackgroundGradient),
+
+    /// Jump the draw cursor so the operations that follow are centered vertically.
+    ///
+    /// Operations are measured up until the next one that jumps to a specific row. If a number of
+    /// operations is set, at most that many of them are measured, which keeps the contents from
+    /// moving as more of them become visible.
+    JumpToVerticalCenter { operations: Option<usize> },
 
     /// Jumps to the N-th row in the current layout.
     ///
@@ -503,6 +1136,17 @@ pub(crate) enum RenderOperation {
     PopMargin,
 }
 
+impl RenderOperation {
+    /// Get the on demand operations in this operation, including the ones nested in it.
+    pub(crate) fn on_demand_operations(&self) -> &[Rc<dyn RenderOnDemand>] {
+        match self {
+            Self::RenderOnDemand(operation) => slice::from_ref(operation),
+            Self::RenderDynamic(operation) => operation.on_demand_operations(),
+            _ => &[],
+        }
+    }
+}
+
 /// The properties of an image being rendered.
 #[derive(Clone, Debug)]
 pub(crate) struct ImageProperties {
@@ -537,6 +1181,26 @@ pub(crate) trait AsRenderOperations: Debug + 'static {
 
     /// Get the content in this type to diff it against another `AsRenderOperations`.
     fn diffable_content(&self) -> Option<&str>;
+
+    /// Get the on demand operations nested in the ones this type generates.
+    ///
+    /// These are started, polled, and paused along with the ones in the slide this type is in.
+    fn on_demand_operations(&self) -> &[Rc<dyn RenderOnDemand>] {
+        &[]
+    }
+
+    /// Get the memory used by anything this type caches.
+    fn cache_usage(&self) -> Option<CacheUsage> {
+        None
+    }
+
+    /// Drop anything this type caches, which will be built again the next time it's needed.
+    fn evict_cache(&self) {}
+
+    /// Get the component that paints this type in a region of its own, if it's painted that way.
+    fn region_painter(self: Rc<Self>) -> Option<Rc<dyn PaintRegion>> {
+        None
+    }
 }
 
 /// A type that can be rendered on demand.
@@ -546,6 +1210,44 @@ pub(crate) trait RenderOnDemand: AsRenderOperations {
 
     /// Poll and update the internal on demand state and return the latest.
     fn poll_state(&self) -> RenderOnDemandState;
+
+    /// Get the policy that determines when this operation starts rendering.
+    fn start_policy(&self) -> RenderOnDemandStartPolicy {
+        RenderOnDemandStartPolicy::OnDemand
+    }
+
+    /// Pause the on demand render because the slide that contains it is no longer displayed.
+    fn pause_render(&self) {}
+
+    /// Check whether the terminal's bell should be rung because of this operation.
+    ///
+    /// This only returns true once every time the bell needs to be rung.
+    fn take_bell(&self) -> bool {
+        false
+    }
+
+    /// Notify that the terminal gained or lost focus.
+    fn focus_changed(&self, _focused: bool) {}
+
+    /// Whether rendering this operation means running code.
+    fn runs_code(&self) -> bool {
+        false
+    }
+}
+
+/// When a [RenderOnDemand] starts rendering.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum RenderOnDemandStartPolicy {
+    /// Only start when the user asks for it.
+    #[default]
+    OnDemand,
+
+    /// Start as soon as the slide that contains it is displayed.
+    Automatic,
+
+    /// Like [RenderOnDemandStartPolicy::Automatic] but never ahead of time, as this only makes
+    /// sense while the slide is being displayed.
+    Visible,
 }
 
 /// The state of a [RenderOnDemand].
@@ -568,6 +1270,7 @@ mod test {
     enum Jump {
         First,
         Last,
+        LastEnd,
         Next,
         Previous,
         Specific(usize),
@@ -579,6 +1282,7 @@ mod test {
             match self {
                 First => presentation.jump_first_slide(),
                 Last => presentation.jump_last_slide(),
+                LastEnd => presentation.jump_last_slide_end(),
                 Next => presentation.jump_next(),
                 Previous => presentation.jump_previous(),
                 Specific(index) => presentation.go_to_slide(*index),
@@ -613,16 +1317,6 @@ mod test {
             }
         }
 
-        fn mutate_previous(&self) -> bool {
-            let mut current = self.current.borrow_mut();
-            if *current > 0 {
-                *current -= 1;
-                true
-            } else {
-                false
-            }
-        }
-
         fn reset_mutations(&self) {
             *self.current.borrow_mut() = 0;
         }
@@ -632,7 +1326,11 @@ mod test {
         }
 
         fn mutations(&self) -> (usize, usize) {
-            (*self.current.borrow(), self.limit)
+            (*self.current.borrow(), self.limit + 1)
+        }
+
+        fn step_kind(&self) -> StepKind {
+            StepKind::CodeHighlight
         }
     }
 
@@ -646,6 +1344,7 @@ mod test {
     #[case::specific_first_from_second(1, &[Jump::Specific(0)], 0, 0)]
     #[case::specific_last_from_second(1, &[Jump::Specific(2)], 2, 0)]
     #[case::first_from_last(2, &[Jump::First], 0, 0)]
+    #[case::last_end_from_first(0, &[Jump::LastEnd], 2, 1)]
     fn jumping(
         #[case] from: usize,
         #[case] jumps: &[Jump],
@@ -717,4 +1416,109 @@ mod test {
         assert_eq!(presentation.current_slide_index(), expected_slide, "slide differs");
         assert_eq!(presentation.current_slide().visible_chunks - 1, expected_chunk, "chunk differs");
     }
+
+    #[rstest]
+    #[case::past_last(1, &[Jump::Next.repeat(2)].concat(), true, 1, 1)]
+    #[case::before_last_step(1, &[Jump::Next], false, 1, 1)]
+    #[case::stays_at_end(1, &Jump::Next.repeat(4), true, 1, 1)]
+    #[case::previous_from_end(1, &[Jump::Next.repeat(2), vec![Jump::Previous]].concat(), false, 1, 1)]
+    #[case::previous_twice_from_end(1, &[Jump::Next.repeat(2), Jump::Previous.repeat(2)].concat(), false, 1, 0)]
+    #[case::first_from_end(1, &[Jump::Next.repeat(2), vec![Jump::First]].concat(), false, 0, 0)]
+    #[case::last_from_end(1, &[Jump::Next.repeat(2), vec![Jump::Last]].concat(), false, 1, 0)]
+    #[case::last_end_never_goes_to_end(0, &[Jump::LastEnd], false, 1, 1)]
+    fn jumping_to_end(
+        #[case] from: usize,
+        #[case] jumps: &[Jump],
+        #[case] expected_at_end: bool,
+        #[case] expected_slide: usize,
+        #[case] expected_chunk: usize,
+    ) {
+        let slide = || Slide::new(vec![SlideChunk::default(), SlideChunk::default()], vec![]);
+        let end_slide = SlideBuilder::default().title("end".into()).numbered(false).build();
+        let mut presentation = Presentation::from(vec![slide(), slide()]).with_end_slide(Some(end_slide));
+        presentation.go_to_slide(from);
+
+        for jump in jumps {
+            jump.apply(&mut presentation);
+        }
+        assert_eq!(presentation.is_at_end(), expected_at_end, "end differs");
+        assert_eq!(presentation.current_slide_index(), expected_slide, "slide differs");
+        let current_title = presentation.current_slide().title();
+        assert_eq!(current_title == Some("end"), expected_at_end, "current slide differs");
+        let last_slide = presentation.iter_slides().nth(expected_slide).unwrap();
+        assert_eq!(last_slide.visible_chunks - 1, expected_chunk, "chunk differs");
+        // The end slide is never counted.
+        assert_eq!(presentation.slide_count(), 2);
+        assert_eq!(presentation.numbering().total(), 2);
+    }
+
+    #[test]
+    fn no_end_slide() {
+        let mut presentation = Presentation::from(vec![Slide::new(vec![SlideChunk::default()], vec![])]);
+        assert!(!presentation.jump_next());
+        assert!(!presentation.is_at_end());
+    }
+
+    #[rstest]
+    #[case::all_numbered(&[true, true, true], &[Some(1), Some(2), Some(3)])]
+    #[case::unnumbered_intro(&[false, true, true], &[None, Some(1), Some(2)])]
+    #[case::unnumbered_middle(&[true, false, true], &[Some(1), None, Some(2)])]
+    #[case::appendix(&[true, true, false, false], &[Some(1), Some(2), None, None])]
+    #[case::combined(&[false, true, false, true, false], &[None, Some(1), None, Some(2), None])]
+    #[case::none_numbered(&[false, false], &[None, None])]
+    #[case::empty(&[], &[])]
+    fn numbering(#[case] numbered: &[bool], #[case] expected: &[Option<usize>]) {
+        let numbering = SlideNumbering::new(numbered.iter().copied());
+        let numbers: Vec<_> = (0..numbered.len()).map(|index| numbering.display_number(index)).collect();
+        assert_eq!(numbers, expected);
+        assert_eq!(numbering.total(), expected.iter().flatten().count());
+
+        // Every display number maps back to the slide that's displayed with it.
+        for (index, number) in expected.iter().enumerate() {
+            if let Some(number) = number {
+                assert_eq!(numbering.slide_index(*number), Some(index));
+            }
+        }
+        assert_eq!(numbering.slide_index(0), None);
+        assert_eq!(numbering.slide_index(numbering.total() + 1), None);
+        assert_eq!(numbering.display_number(numbered.len()), None);
+    }
+
+    #[rstest]
+    #[case::numbered(2, "slide 2")]
+    #[case::unnumbered_start(0, "unnumbered slide at the start")]
+    #[case::unnumbered_middle(3, "unnumbered slide after slide 2")]
+    #[case::unnumbered_end(5, "unnumbered slide after slide 3")]
+    fn reference(#[case] index: usize, #[case] expected: &str) {
+        let numbering = SlideNumbering::new([false, true, true, false, true, false]);
+        assert_eq!(numbering.reference(index).to_string(), expected);
+    }
+
+    #[test]
+    fn numbering_from_slides() {
+        let slide = |numbered| SlideBuilder::default().numbered(numbered).build();
+        let presentation = Presentation::from(vec![slide(true), slide(false), slide(true)]);
+        assert_eq!(presentation.numbering(), &SlideNumbering::new([true, false, true]));
+        assert_eq!(presentation.numbering().slide_index(2), Some(2));
+    }
+
+    #[test]
+    fn filter_operations() {
+        let slide = SlideBuilder::default()
+            .chunks(vec![
+                SlideChunk::new(vec![RenderOperation::ClearScreen, RenderOperation::RenderLineBreak], vec![]),
+                SlideChunk::new(vec![RenderOperation::ClearScreen], vec![]),
+            ])
+            .build();
+        let line_breaks = true;
+        let operations = slide.filter_operations(|operation| match operation {
+            RenderOperation::RenderLineBreak => line_breaks,
+            _ => false,
+        });
+        assert_eq!(operations.len(), 1);
+
+        // Only the visible chunks are considered.
+        let operations = slide.filter_operations(|operation| matches!(operation, RenderOperation::ClearScreen));
+        assert_eq!(operations.len(), 1);
+    }
 }
@@ -1,21 +1,48 @@
 use crate::{
-    custom::KeyBindingsConfig,
+    cache::CacheBudget,
+    custom::{EndOfDeck, KeyBindingsConfig, PreviewDimensions, SafeAreaMargin, SlowFramesConfig},
     diff::PresentationDiffer,
+    events::{EventEmitter, PresentationEvents},
+    execute::CodeExecuter,
     export::ImageReplacer,
-    input::source::{Command, CommandSource},
-    markdown::parse::{MarkdownParser, ParseError},
+    input::{
+        command_line::{closest_match, CommandLineError, ExportFormat, LineCommand, LineOption},
+        prompt::{Prompt, PromptAction},
+        source::{Command, CommandSource},
+    },
+    markdown::{
+        elements::Text,
+        parse::{MarkdownParser, ParseError},
+    },
     media::{printer::ImagePrinter, register::ImageRegistry},
+    outline::OutlineExporter,
+    prefetch::Prefetcher,
     presentation::Presentation,
-    processing::builder::{BuildError, PresentationBuilder, PresentationBuilderOptions, Themes},
+    processing::{
+        builder::{BuildError, PresentationBuilder, PresentationBuilderOptions, Themes},
+        deferred::SPINNER_INTERVAL,
+        focus::{CodeFocus, FocusableCode},
+    },
     render::{
+        capabilities::CellSizePolicy,
         draw::{RenderError, RenderResult, TerminalDrawer},
-        properties::WindowSize,
+        frames::FrameLimiter,
+        properties::RenderDimensions,
+        terminal::is_disconnection,
         validate::OverflowValidator,
     },
     resource::Resources,
+    reveal::RevealExporter,
+    separators::{SeparatorAnalyzer, SeparatorWarning},
+    session::{SessionRecorder, SessionState, SessionStore},
+    style::{Colors, TextStyle},
     theme::PresentationTheme,
     typst::TypstRender,
 };
+use crossterm::{
+    event::{EnableFocusChange, KeyEvent},
+    ExecutableCommand,
+};
 use std::{
     collections::HashSet,
     fmt::Display,
@@ -24,14 +51,32 @@ use std::{
     mem,
     path::Path,
     rc::Rc,
+    time::{Duration, Instant},
 };
 
+// How long to wait for a command before checking on the presentation's state again.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 pub struct PresenterOptions {
     pub mode: PresentMode,
     pub builder_options: PresentationBuilderOptions,
-    pub font_size_fallback: u8,
+    pub cell_size: CellSizePolicy,
+    pub dimensions: RenderDimensions,
     pub bindings: KeyBindingsConfig,
     pub validate_overflows: bool,
+    pub event_emitter: Option<EventEmitter>,
+    pub safe_area_margin: SafeAreaMargin,
+    pub cache_budget: usize,
+    pub cache_usage_overlay: bool,
+    pub end_of_deck: EndOfDeck,
+    pub preview_dimensions: Vec<PreviewDimensions>,
+    pub kiosk: bool,
+    pub session: Option<SessionStore>,
+    pub resume: Option<SessionState>,
+    pub suggested_resume: Option<SessionState>,
+    pub fps: u16,
+    pub slow_frames: Option<SlowFramesConfig>,
+    pub pause_on_focus_loss: bool,
 }
 
 /// A slideshow presenter.
@@ -45,8 +90,20 @@ pub struct Presenter<'a> {
     typst: TypstRender,
     state: PresenterState,
     slides_with_pending_widgets: HashSet<usize>,
+    slides_with_pending_executions: HashSet<usize>,
+    cache_budget: CacheBudget,
+    cache_used: usize,
+    prefetcher: Prefetcher,
     image_printer: Rc<ImagePrinter>,
     themes: Themes,
+    theme_override: Option<PresentationTheme>,
+    events: PresentationEvents,
+    command_line: Prompt,
+    command_line_open: bool,
+    status: Option<Text>,
+    warnings: Vec<String>,
+    frames: FrameLimiter,
+    focused: bool,
     options: PresenterOptions,
 }
 
@@ -61,8 +118,9 @@ impl<'a> Presenter<'a> {
         typst: TypstRender,
         themes: Themes,
         image_printer: Rc<ImagePrinter>,
-        options: PresenterOptions,
+        mut options: PresenterOptions,
     ) -> Self {
+        let events = PresentationEvents::new(options.event_emitter.take());
         Self {
             default_theme,
             commands,
@@ -71,62 +129,281 @@ impl<'a> Presenter<'a> {
             typst,
             state: PresenterState::Empty,
             slides_with_pending_widgets: HashSet::new(),
+            slides_with_pending_executions: HashSet::new(),
+            cache_budget: CacheBudget::new(options.cache_budget),
+            cache_used: 0,
+            prefetcher: Prefetcher::new(options.safe_area_margin),
             image_printer,
             themes,
+            theme_override: None,
+            events,
+            command_line: Prompt::default(),
+            command_line_open: false,
+            status: None,
+            warnings: Vec::new(),
+            frames: FrameLimiter::new(options.fps),
+            focused: true,
             options,
         }
     }
 
+    /// Get the number of slides in the presentation being displayed.
+    ///
+    /// This is 0 until a presentation is loaded.
+    pub fn slide_count(&self) -> usize {
+        match &self.state {
+            PresenterState::Empty => 0,
+            state => state.presentation().slide_count(),
+        }
+    }
+
+    /// Get the index of the slide being displayed.
+    pub fn current_index(&self) -> usize {
+        match &self.state {
+            PresenterState::Empty => 0,
+            state => state.presentation().current_slide_index(),
+        }
+    }
+
     /// Run a presentation.
     pub fn present(mut self, path: &Path) -> Result<(), PresentationError> {
         self.state = PresenterState::Presenting(Presentation::from(vec![]));
         self.try_reload(path, true);
+        self.resume_session();
+        self.show_startup_notice();
+        let mut session = self.options.session.take().map(|store| SessionRecorder::new(store, path.into()));
+
+        let result = self.run(path, &mut session);
+        self.finish(result, session.as_mut())
+    }
+
+    // None of this touches the terminal so it's done no matter how the presentation ended.
+    fn finish(
+        &mut self,
+        result: Result<(), PresentationError>,
+        session: Option<&mut SessionRecorder>,
+    ) -> Result<(), PresentationError> {
+        // Flushing the session and the events can take a while so processes are killed first.
+        CodeExecuter::kill_running();
+        if let Some(session) = session {
+            session.flush();
+        }
+        self.events.ended(self.prefetcher.stats());
+        match result {
+            Err(e) if e.is_disconnection() => Err(PresentationError::Disconnected),
+            result => {
+                // The terminal is restored by now so these don't get lost along with the alternate screen.
+                for warning in &self.warnings {
+                    eprintln!("warning: {warning}");
+                }
+                result
+            }
+        }
+    }
 
-        let mut drawer =
-            TerminalDrawer::new(io::stdout(), self.image_printer.clone(), self.options.font_size_fallback)?;
+    fn run(&mut self, path: &Path, session: &mut Option<SessionRecorder>) -> Result<(), PresentationError> {
+        let mut drawer = TerminalDrawer::new(
+            io::stdout(),
+            self.image_printer.clone(),
+            self.options.cell_size,
+            self.options.dimensions,
+            self.options.safe_area_margin,
+        )?;
+        // Terminals that don't support this simply never report focus changes.
+        if self.options.pause_on_focus_loss {
+            io::stdout().execute(EnableFocusChange)?;
+        }
         loop {
+            self.trim_caches();
+            self.start_automatic_widgets();
             self.render(&mut drawer)?;
             self.update_widgets(&mut drawer)?;
 
             loop {
                 self.update_widgets(&mut drawer)?;
-                let Some(command) = self.commands.try_next_command()? else {
+                if self.frames.pending_delay(Instant::now()) == Some(Duration::ZERO) {
+                    self.render(&mut drawer)?;
+                }
+                self.prefetch();
+                if let Some((slide, budget)) = self.events.tick(Instant::now()) {
+                    self.status =
+                        Some(Text::from(format!("{slide} went over its maximum duration of {}s", budget.as_secs())));
+                    self.render(&mut drawer)?;
+                }
+                if let Some(session) = session {
+                    session.tick(Instant::now());
+                }
+                let Some(command) = self.commands.try_next_command(self.poll_interval())? else {
                     continue;
                 };
-                match self.apply_command(command) {
-                    CommandSideEffect::Exit => return Ok(()),
+                // Messages are only displayed until something else happens.
+                let cleared_status = self.status.take().is_some();
+                let side_effect = match command {
+                    Command::PromptInput(event) => self.apply_command_line_input(event, path),
+                    command => self.apply_command(command),
+                };
+                self.events.moved(self.state.presentation());
+                if let Some(session) = session {
+                    session.moved(self.state.presentation());
+                }
+                match side_effect {
+                    CommandSideEffect::Exit => {
+                        return Ok(());
+                    }
                     CommandSideEffect::Reload => {
                         self.try_reload(path, false);
                         break;
                     }
+                    CommandSideEffect::ForceReload => {
+                        self.try_reload(path, true);
+                        break;
+                    }
                     CommandSideEffect::Redraw => {
                         break;
                     }
+                    CommandSideEffect::RepaintStatus => {
+                        self.render_status_line(&mut drawer)?;
+                    }
                     CommandSideEffect::PollWidgets => {
                         self.slides_with_pending_widgets.insert(self.state.presentation().current_slide_index());
                     }
+                    CommandSideEffect::None if cleared_status => {
+                        break;
+                    }
                     CommandSideEffect::None => (),
                 };
             }
         }
     }
 
+    fn poll_interval(&self) -> Duration {
+        // Slides with widgets that are still rendering are redrawn more often so things like
+        // loading spinners are animated.
+        let current_index = self.state.presentation().current_slide_index();
+        let interval =
+            if self.slides_with_pending_widgets.contains(&current_index) { SPINNER_INTERVAL } else { POLL_INTERVAL };
+        // Wake up in time to draw any frame that was skipped for coming too soon after the last one.
+        match self.frames.pending_delay(Instant::now()) {
+            Some(delay) => interval.min(delay),
+            None => interval,
+        }
+    }
+
+    fn resume_session(&mut self) {
+        let Some(state) = self.options.resume.take() else {
+            return;
+        };
+        // If the presentation changed too much to find where we were, simply start from the beginning.
+        let presentation = self.state.presentation_mut();
+        if let Some((slide, step)) = state.locate(presentation) {
+            presentation.go_to_slide(slide);
+            presentation.jump_step(step);
+            self.events.moved(self.state.presentation());
+        }
+    }
+
+    fn show_startup_notice(&mut self) {
+        let presentation = self.state.presentation();
+        // There's nothing to go back to if the slide can't be found anymore.
+        let previous_slide = self.options.suggested_resume.as_ref().and_then(|state| state.locate(presentation));
+        if let Some((index, _)) = previous_slide {
+            let slide = presentation.numbering().reference(index);
+            let notice = format!("this presentation was last left at {slide}, run with --resume to go back to it");
+            self.status = Some(Text::from(notice));
+        } else if !self.warnings.is_empty() {
+            let notice = format!("found {} warnings, they'll be listed on exit", self.warnings.len());
+            self.status = Some(Text::from(notice));
+        }
+    }
+
     fn update_widgets(&mut self, drawer: &mut TerminalDrawer<Stdout>) -> RenderResult {
         let current_index = self.state.presentation().current_slide_index();
         if self.slides_with_pending_widgets.contains(&current_index) {
-            self.render(drawer)?;
-            if self.state.presentation_mut().widgets_rendered() {
-                // Render one last time just in case it _just_ rendered
-                self.render(drawer)?;
+            self.render_widgets(drawer)?;
+            let rendered = self.state.presentation_mut().widgets_rendered();
+            if self.state.presentation().take_bell() {
+                drawer.ring_bell()?;
+            }
+            if rendered {
                 self.slides_with_pending_widgets.remove(&current_index);
+                let executed = self.slides_with_pending_executions.remove(&current_index);
+                // Render one last time just in case it _just_ rendered, which also stops dimming
+                // the slide if code was running in it.
+                self.render(drawer)?;
+                if executed {
+                    self.events.exec_finished(current_index);
+                }
             }
         }
         Ok(())
     }
 
+    fn render_widgets(&mut self, drawer: &mut TerminalDrawer<Stdout>) -> RenderResult {
+        // Widgets like countdowns are painted in a region of their own so, as long as the screen
+        // still shows the slide they were laid out in, repainting that region is enough.
+        if let PresenterState::Presenting(presentation) = &self.state {
+            let current_index = presentation.current_slide_index();
+            let repaint = self.frames.pending_delay(Instant::now()).is_none()
+                && !self.slides_with_pending_executions.contains(&current_index)
+                && presentation.current_slide().rendering_widgets_in_regions();
+            if repaint && drawer.repaint_regions()? {
+                return Ok(());
+            }
+        }
+        self.render(drawer)
+    }
+
+    fn prefetch(&mut self) {
+        // Don't get in the way of widgets that are still rendering, like running code.
+        let presentation = self.state.presentation();
+        if !self.slides_with_pending_widgets.contains(&presentation.current_slide_index()) {
+            self.prefetcher.prefetch_next(presentation);
+        }
+    }
+
+    fn trim_caches(&mut self) {
+        let report = self.cache_budget.enforce(self.state.presentation());
+        self.cache_used = report.used;
+        if report.evicted > 0 {
+            self.events.cache_trimmed(&report, self.options.cache_budget);
+        }
+    }
+
+    fn start_automatic_widgets(&mut self) {
+        // Things like images are loaded in the background so the rest of the slide shows up right away.
+        let presentation = self.state.presentation_mut();
+        presentation.render_automatic_widgets();
+        if !presentation.widgets_rendered() {
+            self.slides_with_pending_widgets.insert(presentation.current_slide_index());
+        }
+    }
+
     fn render(&mut self, drawer: &mut TerminalDrawer<Stdout>) -> RenderResult {
+        if !self.frames.try_frame(Instant::now()) {
+            return Ok(());
+        }
+        if self.options.slow_frames.is_some() {
+            drawer.start_profile();
+        }
+        self.render_frame(drawer)?;
+        self.report_slow_frame(drawer)
+    }
+
+    fn render_frame(&mut self, drawer: &mut TerminalDrawer<Stdout>) -> RenderResult {
         let result = match &self.state {
-            PresenterState::Presenting(presentation) => drawer.render_slide(presentation),
+            PresenterState::Presenting(presentation)
+                if self.slides_with_pending_executions.contains(&presentation.current_slide_index()) =>
+            {
+                drawer.render_executing_slide(presentation)
+            }
+            PresenterState::Presenting(presentation) => {
+                let result = drawer.render_slide(presentation);
+                let dimensions = self.options.dimensions.window_size(self.options.cell_size);
+                if let (Ok(_), Ok(dimensions)) = (&result, dimensions) {
+                    self.prefetcher.displayed(presentation, dimensions);
+                }
+                result
+            }
             PresenterState::SlideIndex(presentation) => {
                 drawer.render_slide(presentation)?;
                 drawer.render_slide_index(presentation)
@@ -135,15 +412,86 @@ impl<'a> Presenter<'a> {
                 drawer.render_slide(presentation)?;
                 drawer.render_key_bindings(presentation)
             }
+            PresenterState::Glossary(presentation) => {
+                drawer.render_slide(presentation)?;
+                drawer.render_glossary(presentation)
+            }
+            PresenterState::CodeFocus { presentation, focus } => drawer.render_code_focus(presentation, focus),
+            PresenterState::Preview { presentation, index } => {
+                drawer.render_preview(presentation, self.options.preview_dimensions[*index])
+            }
+            PresenterState::Blank { .. } => drawer.render_blank(),
             PresenterState::Failure { error, .. } => drawer.render_error(error),
             PresenterState::Empty => panic!("cannot render without state"),
         };
+        let result = result.and_then(|_| self.render_status_line(drawer));
+        let result = result.and_then(|_| self.render_cache_usage(drawer));
         // If the screen is too small, simply ignore this. Eventually the user will resize the
         // screen.
         if matches!(result, Err(RenderError::TerminalTooSmall)) { Ok(()) } else { result }
     }
 
+    fn report_slow_frame(&self, drawer: &mut TerminalDrawer<Stdout>) -> RenderResult {
+        let (Some(config), Some(profile)) = (&self.options.slow_frames, drawer.take_profile()) else {
+            return Ok(());
+        };
+        if profile.total() < Duration::from_millis(config.threshold_ms.into()) {
+            return Ok(());
+        }
+        self.events.slow_frame(self.state.presentation().current_slide_index(), &profile);
+        // Kiosks are left unattended so there's no one to look at the indicator.
+        if config.indicator && !self.options.kiosk {
+            drawer.render_slow_frame_indicator()?;
+        }
+        Ok(())
+    }
+
+    fn render_status_line(&self, drawer: &mut TerminalDrawer<Stdout>) -> RenderResult {
+        match self.status_line() {
+            Some((line, colors)) => drawer.render_status_line(line, &colors),
+            None => Ok(()),
+        }
+    }
+
+    fn status_line(&self) -> Option<(Vec<Text>, Colors)> {
+        match &self.status {
+            // Kiosks are left unattended so nothing but the presentation is ever shown.
+            _ if self.options.kiosk => None,
+            _ if self.command_line_open => {
+                let colors = self.state.presentation().prompt_colors().clone();
+                Some((self.command_line.display(":"), colors))
+            }
+            Some(status) => Some((vec![status.clone()], Colors::default())),
+            None => None,
+        }
+    }
+
+    fn render_cache_usage(&self, drawer: &mut TerminalDrawer<Stdout>) -> RenderResult {
+        match self.cache_usage() {
+            Some(usage) => drawer.render_cache_usage(&usage),
+            None => Ok(()),
+        }
+    }
+
+    fn cache_usage(&self) -> Option<String> {
+        if !self.options.cache_usage_overlay || self.options.kiosk {
+            return None;
+        }
+        const MEGABYTE: f64 = 1024.0 * 1024.0;
+        let used = self.cache_used as f64 / MEGABYTE;
+        let budget = self.options.cache_budget as f64 / MEGABYTE;
+        Some(format!("cache {used:.1}/{budget:.0}MB"))
+    }
+
     fn apply_command(&mut self, command: Command) -> CommandSideEffect {
+        if self.options.kiosk && !command.allowed_in_kiosk() {
+            return CommandSideEffect::None;
+        }
+        if matches!(command, Command::OpenCommandLine) {
+            self.command_line_open = true;
+            self.commands.capture_keys(true);
+            return CommandSideEffect::Redraw;
+        }
         // These ones always happens no matter our state.
         match command {
             Command::Reload => {
@@ -156,32 +504,113 @@ impl<'a> Presenter<'a> {
                 return CommandSideEffect::Reload;
             }
             Command::Exit => return CommandSideEffect::Exit,
+            Command::ToggleRedactions => {
+                let options = &mut self.options.builder_options;
+                options.reveal_redactions = !options.reveal_redactions;
+                return CommandSideEffect::ForceReload;
+            }
+            Command::FocusChanged(focused) => {
+                self.focused = focused;
+                self.state.presentation().focus_changed(focused);
+                return CommandSideEffect::None;
+            }
             _ => (),
         };
         if matches!(command, Command::Redraw) {
-            let presentation = mem::take(&mut self.state).into_presentation();
-            self.state = self.validate_overflows(presentation);
+            // Stay blank, previewing or focused until explicitly told otherwise.
+            if !matches!(
+                self.state,
+                PresenterState::Blank { .. } | PresenterState::Preview { .. } | PresenterState::CodeFocus { .. }
+            ) {
+                let presentation = mem::take(&mut self.state).into_presentation();
+                self.state = self.validate_overflows(presentation);
+            }
             return CommandSideEffect::Redraw;
         }
 
+        if let PresenterState::Blank { after_last_slide, .. } = &self.state {
+            let unblank = match command {
+                Command::ToggleBlank | Command::CloseModal => true,
+                Command::Previous => *after_last_slide,
+                _ => false,
+            };
+            if unblank {
+                self.unblank();
+                return CommandSideEffect::Redraw;
+            }
+            return CommandSideEffect::None;
+        }
+
+        if let PresenterState::CodeFocus { presentation, focus } = &mut self.state {
+            let code = presentation.current_slide().focusable_code();
+            let line_count = code.get(focus.block).map(|code| code.line_count()).unwrap_or(0);
+            match command {
+                Command::Next => {
+                    let dimensions = self.options.dimensions.window_size(self.options.cell_size);
+                    let rows = dimensions.map(|size| size.rows).unwrap_or(0);
+                    focus.scroll_down(line_count, FocusableCode::visible_lines(rows));
+                    return CommandSideEffect::Redraw;
+                }
+                Command::Previous => {
+                    focus.scroll_up();
+                    return CommandSideEffect::Redraw;
+                }
+                Command::FocusCode => {
+                    focus.next_block(code.len());
+                    return CommandSideEffect::Redraw;
+                }
+                Command::CloseModal => {
+                    let presentation = mem::take(&mut self.state).into_presentation();
+                    self.state = PresenterState::Presenting(presentation);
+                    return CommandSideEffect::Redraw;
+                }
+                // Anything else goes back to the slide first.
+                _ => {
+                    let presentation = mem::take(&mut self.state).into_presentation();
+                    self.state = PresenterState::Presenting(presentation);
+                }
+            };
+        }
+
         // Now apply the commands that require a presentation.
         let presentation = match &mut self.state {
             PresenterState::Presenting(presentation)
             | PresenterState::SlideIndex(presentation)
-            | PresenterState::KeyBindings(presentation) => presentation,
+            | PresenterState::KeyBindings(presentation)
+            | PresenterState::Glossary(presentation)
+            | PresenterState::Preview { presentation, .. } => presentation,
             _ => {
                 return CommandSideEffect::None;
             }
         };
+        let previous_slide = presentation.current_slide_index();
         let needs_redraw = match command {
-            Command::Next => presentation.jump_next(),
-            Command::Previous => presentation.jump_previous(),
+            Command::Next => match (presentation.jump_next(), self.options.end_of_deck) {
+                (false, EndOfDeck::Wrap) => presentation.jump_first_slide(),
+                (false, EndOfDeck::Black) => {
+                    self.blank(true);
+                    true
+                }
+                (moved, _) => moved,
+            },
+            Command::Previous => match (presentation.jump_previous(), self.options.end_of_deck) {
+                (false, EndOfDeck::Wrap) => presentation.jump_last_slide_end(),
+                (moved, _) => moved,
+            },
             Command::FirstSlide => presentation.jump_first_slide(),
             Command::LastSlide => presentation.jump_last_slide(),
-            Command::GoToSlide(number) => presentation.go_to_slide(number.saturating_sub(1) as usize),
+            Command::GoToSlide(number) => match presentation.numbering().slide_index(number as usize) {
+                Some(index) => presentation.go_to_slide(index),
+                None => false,
+            },
             Command::RenderWidgets => {
                 if presentation.render_slide_widgets() {
-                    self.slides_with_pending_widgets.insert(self.state.presentation().current_slide_index());
+                    let current_index = presentation.current_slide_index();
+                    self.slides_with_pending_widgets.insert(current_index);
+                    if presentation.current_slide_runs_code() {
+                        self.slides_with_pending_executions.insert(current_index);
+                        self.events.exec_started(current_index);
+                    }
                     return CommandSideEffect::PollWidgets;
                 } else {
                     return CommandSideEffect::None;
@@ -200,19 +629,130 @@ impl<'a> Presenter<'a> {
                 self.state = PresenterState::Presenting(presentation);
                 true
             }
+            Command::ToggleBlank => {
+                self.blank(false);
+                true
+            }
+            Command::CyclePreviewDimensions => self.cycle_preview_dimensions(),
+            Command::ToggleGlossary => {
+                self.toggle_glossary();
+                true
+            }
+            Command::FocusCode => self.focus_code(),
+            Command::ToggleDetails => presentation.current_slide().toggle_details(),
             // These are handled above as they don't require the presentation
-            Command::Reload | Command::HardReload | Command::Exit | Command::Redraw => {
+            Command::Reload
+            | Command::HardReload
+            | Command::Exit
+            | Command::ToggleRedactions
+            | Command::Redraw
+            | Command::OpenCommandLine
+            | Command::PromptInput(_)
+            | Command::FocusChanged(_) => {
                 panic!("unreachable commands")
             }
         };
+        let presentation = self.state.presentation_mut();
+        if presentation.current_slide_index() != previous_slide {
+            presentation.pause_widgets(previous_slide);
+        }
         if needs_redraw { CommandSideEffect::Redraw } else { CommandSideEffect::None }
     }
 
+    fn apply_command_line_input(&mut self, event: KeyEvent, path: &Path) -> CommandSideEffect {
+        let line = match self.command_line.handle_key(event) {
+            PromptAction::Continue => return CommandSideEffect::RepaintStatus,
+            PromptAction::Cancel => {
+                self.close_command_line();
+                return CommandSideEffect::Redraw;
+            }
+            PromptAction::Submit(line) => line,
+        };
+        self.close_command_line();
+        if line.is_empty() {
+            return CommandSideEffect::Redraw;
+        }
+        match line.parse() {
+            Ok(command) => self.apply_line_command(command, path),
+            Err(e) => {
+                self.show_error(e);
+                CommandSideEffect::Redraw
+            }
+        }
+    }
+
+    fn apply_line_command(&mut self, command: LineCommand, path: &Path) -> CommandSideEffect {
+        match command {
+            LineCommand::Command(command) => match self.apply_command(command) {
+                // The command line needs to be cleared even if nothing else changed.
+                CommandSideEffect::None => CommandSideEffect::Redraw,
+                other => other,
+            },
+            LineCommand::Theme(name) => {
+                let Some(theme) = self.themes.presentation.load_by_name(&name) else {
+                    let names = self.themes.presentation.theme_names();
+                    let suggestion = closest_match(&name, names.iter().map(String::as_str)).map(String::from);
+                    self.show_error(CommandLineError::UnknownTheme { theme: name, suggestion });
+                    return CommandSideEffect::Redraw;
+                };
+                self.theme_override = Some(theme);
+                self.try_reload(path, true);
+                CommandSideEffect::Redraw
+            }
+            LineCommand::Set { option, enabled } => {
+                let options = &mut self.options.builder_options;
+                match option {
+                    LineOption::Footer => options.hide_footer = !enabled,
+                    LineOption::IncrementalLists => options.incremental_lists = enabled,
+                    LineOption::ImplicitSlideEnds => options.implicit_slide_ends = enabled,
+                    LineOption::EndSlideShorthand => options.end_slide_shorthand = enabled,
+                };
+                self.try_reload(path, true);
+                CommandSideEffect::Redraw
+            }
+            LineCommand::Export { format, path: output_path } => {
+                match self.export(format, path, &output_path) {
+                    Ok(()) => self.status = Some(Text::from(format!("exported to {}", output_path.display()))),
+                    Err(e) => self.show_error(format!("failed to export: {e}")),
+                };
+                CommandSideEffect::Redraw
+            }
+        }
+    }
+
+    fn export(&self, format: ExportFormat, path: &Path, output_path: &Path) -> Result<(), String> {
+        let options = self.options.builder_options.clone();
+        match format {
+            ExportFormat::Outline(format) => {
+                let outline = OutlineExporter::new(self.parser.clone(), options)
+                    .export(path, format)
+                    .map_err(|e| e.to_string())?;
+                fs::write(output_path, outline).map_err(|e| e.to_string())
+            }
+            ExportFormat::Html => RevealExporter::new(self.parser.clone(), self.resources.detached(), options)
+                .export(path, output_path)
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    fn close_command_line(&mut self) {
+        self.command_line_open = false;
+        self.commands.capture_keys(false);
+    }
+
+    fn show_error<E: Display>(&mut self, error: E) {
+        let colors = self.state.presentation().error_colors().clone();
+        self.status = Some(Text::new(error.to_string(), TextStyle::default().colors(colors)));
+    }
+
     fn try_reload(&mut self, path: &Path, force: bool) {
         if matches!(self.options.mode, PresentMode::Presentation) && !force {
             return;
         }
         self.slides_with_pending_widgets.clear();
+        self.slides_with_pending_executions.clear();
+        self.cache_budget.reset();
+        self.prefetcher.reset();
         match self.load_presentation(path) {
             Ok(mut presentation) => {
                 let current = self.state.presentation();
@@ -223,6 +763,10 @@ impl<'a> Presenter<'a> {
                     presentation.go_to_slide(current.current_slide_index());
                     presentation.jump_chunk(current.current_chunk());
                 }
+                if !self.focused {
+                    presentation.focus_changed(false);
+                }
+                self.events.loaded(&presentation);
                 self.state = self.validate_overflows(presentation);
             }
             Err(e) => {
@@ -234,11 +778,11 @@ impl<'a> Presenter<'a> {
 
     fn validate_overflows(&self, presentation: Presentation) -> PresenterState {
         if self.options.validate_overflows {
-            let dimensions = match WindowSize::current(self.options.font_size_fallback) {
+            let dimensions = match self.options.dimensions.window_size(self.options.cell_size) {
                 Ok(dimensions) => dimensions,
                 Err(e) => return PresenterState::failure(e, presentation),
             };
-            match OverflowValidator::validate(&presentation, dimensions) {
+            match OverflowValidator::validate(&presentation, dimensions, self.options.safe_area_margin) {
                 Ok(()) => PresenterState::Presenting(presentation),
                 Err(e) => PresenterState::failure(e, presentation),
             }
@@ -250,52 +794,136 @@ impl<'a> Presenter<'a> {
     fn load_presentation(&mut self, path: &Path) -> Result<Presentation, LoadPresentationError> {
         let content = fs::read_to_string(path).map_err(LoadPresentationError::Reading)?;
         let elements = self.parser.parse(&content)?;
+        let separator_warnings = SeparatorAnalyzer::new(self.parser.clone(), self.options.builder_options.clone())
+            .find_warnings(&content, &elements);
         let export_mode = matches!(self.options.mode, PresentMode::Export);
+        let mut options = self.options.builder_options.clone();
+        // A theme picked via the command line takes precedence over the one in the presentation.
+        let theme = match &self.theme_override {
+            Some(theme) => {
+                options.force_default_theme = true;
+                theme
+            }
+            None => self.default_theme,
+        };
         let mut presentation = PresentationBuilder::new(
-            self.default_theme,
+            theme,
             &mut self.resources,
             &mut self.typst,
             &self.themes,
             ImageRegistry(self.image_printer.clone()),
             self.options.bindings.clone(),
-            self.options.builder_options.clone(),
+            options,
         )
         .build(elements)?;
         if export_mode {
             ImageReplacer::default().replace_presentation_images(&mut presentation);
         }
+        self.warnings = Self::collect_warnings(&presentation, &separator_warnings);
 
         Ok(presentation)
     }
 
+    // Problems that don't prevent the presentation from being displayed but that its author should know about.
+    fn collect_warnings(presentation: &Presentation, separator_warnings: &[SeparatorWarning]) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let todos = presentation.todos().len();
+        if todos > 0 {
+            warnings.push(format!("{todos} TODO/FIXME notes left in the presentation, run with --stats to list them"));
+        }
+        warnings.extend(presentation.directive_warnings().iter().map(ToString::to_string));
+        warnings.extend(presentation.structure_warnings().iter().map(ToString::to_string));
+        warnings.extend(presentation.budget_warnings().iter().map(ToString::to_string));
+        warnings.extend(separator_warnings.iter().map(ToString::to_string));
+        warnings
+    }
+
     fn toggle_slide_index(&mut self) {
         let state = mem::take(&mut self.state);
         match state {
-            PresenterState::Presenting(presentation) | PresenterState::KeyBindings(presentation) => {
-                self.state = PresenterState::SlideIndex(presentation)
-            }
+            PresenterState::Presenting(presentation)
+            | PresenterState::KeyBindings(presentation)
+            | PresenterState::Glossary(presentation) => self.state = PresenterState::SlideIndex(presentation),
             PresenterState::SlideIndex(presentation) => self.state = PresenterState::Presenting(presentation),
             other => self.state = other,
         }
     }
 
+    // The current state is kept as is so the exact same thing is displayed when unblanking.
+    fn blank(&mut self, after_last_slide: bool) {
+        let previous = mem::take(&mut self.state);
+        self.state = PresenterState::Blank { previous: Box::new(previous), after_last_slide };
+    }
+
+    fn unblank(&mut self) {
+        if let PresenterState::Blank { previous, .. } = mem::take(&mut self.state) {
+            self.state = *previous;
+        }
+    }
+
+    fn cycle_preview_dimensions(&mut self) -> bool {
+        let total = self.options.preview_dimensions.len();
+        if total == 0 {
+            return false;
+        }
+        let state = mem::take(&mut self.state);
+        self.state = match state {
+            PresenterState::Presenting(presentation)
+            | PresenterState::SlideIndex(presentation)
+            | PresenterState::KeyBindings(presentation)
+            | PresenterState::Glossary(presentation) => PresenterState::Preview { presentation, index: 0 },
+            PresenterState::Preview { presentation, index } if index + 1 < total => {
+                PresenterState::Preview { presentation, index: index + 1 }
+            }
+            // Go back to normal after going through all of them.
+            PresenterState::Preview { presentation, .. } => PresenterState::Presenting(presentation),
+            other => other,
+        };
+        true
+    }
+
     fn toggle_key_bindings(&mut self) {
         let state = mem::take(&mut self.state);
         match state {
-            PresenterState::Presenting(presentation) | PresenterState::SlideIndex(presentation) => {
-                self.state = PresenterState::KeyBindings(presentation)
-            }
+            PresenterState::Presenting(presentation)
+            | PresenterState::SlideIndex(presentation)
+            | PresenterState::Glossary(presentation) => self.state = PresenterState::KeyBindings(presentation),
             PresenterState::KeyBindings(presentation) => self.state = PresenterState::Presenting(presentation),
             other => self.state = other,
         }
     }
+
+    fn focus_code(&mut self) -> bool {
+        let state = mem::take(&mut self.state);
+        let (state, focused) = match state {
+            PresenterState::Presenting(presentation) if !presentation.current_slide().focusable_code().is_empty() => {
+                (PresenterState::CodeFocus { presentation, focus: CodeFocus::default() }, true)
+            }
+            other => (other, false),
+        };
+        self.state = state;
+        focused
+    }
+
+    fn toggle_glossary(&mut self) {
+        let state = mem::take(&mut self.state);
+        match state {
+            PresenterState::Presenting(presentation)
+            | PresenterState::SlideIndex(presentation)
+            | PresenterState::KeyBindings(presentation) => self.state = PresenterState::Glossary(presentation),
+            PresenterState::Glossary(presentation) => self.state = PresenterState::Presenting(presentation),
+            other => self.state = other,
+        }
+    }
 }
 
 enum CommandSideEffect {
     Exit,
     Redraw,
+    RepaintStatus,
     PollWidgets,
     Reload,
+    ForceReload,
     None,
 }
 
@@ -306,6 +934,19 @@ enum PresenterState {
     Presenting(Presentation),
     SlideIndex(Presentation),
     KeyBindings(Presentation),
+    Glossary(Presentation),
+    CodeFocus {
+        presentation: Presentation,
+        focus: CodeFocus,
+    },
+    Preview {
+        presentation: Presentation,
+        index: usize,
+    },
+    Blank {
+        previous: Box<PresenterState>,
+        after_last_slide: bool,
+    },
     Failure {
         error: String,
         presentation: Presentation,
@@ -322,7 +963,11 @@ impl PresenterState {
             Self::Presenting(presentation)
             | Self::SlideIndex(presentation)
             | Self::KeyBindings(presentation)
+            | Self::Glossary(presentation)
+            | Self::CodeFocus { presentation, .. }
+            | Self::Preview { presentation, .. }
             | Self::Failure { presentation, .. } => presentation,
+            Self::Blank { previous, .. } => previous.presentation(),
             Self::Empty => panic!("state is empty"),
         }
     }
@@ -332,7 +977,11 @@ impl PresenterState {
             Self::Presenting(presentation)
             | Self::SlideIndex(presentation)
             | Self::KeyBindings(presentation)
+            | Self::Glossary(presentation)
+            | Self::CodeFocus { presentation, .. }
+            | Self::Preview { presentation, .. }
             | Self::Failure { presentation, .. } => presentation,
+            Self::Blank { previous, .. } => previous.presentation_mut(),
             Self::Empty => panic!("state is empty"),
         }
     }
@@ -342,7 +991,11 @@ impl PresenterState {
             Self::Presenting(presentation)
             | Self::SlideIndex(presentation)
             | Self::KeyBindings(presentation)
+            | Self::Glossary(presentation)
+            | Self::CodeFocus { presentation, .. }
+            | Self::Preview { presentation, .. }
             | Self::Failure { presentation, .. } => presentation,
+            Self::Blank { previous, .. } => previous.into_presentation(),
             Self::Empty => panic!("state is empty"),
         }
     }
@@ -387,4 +1040,305 @@ pub enum PresentationError {
 
     #[error("fatal error: {0}")]
     Fatal(String),
+
+    #[error("terminal disconnected")]
+    Disconnected,
+}
+
+impl PresentationError {
+    /// Checks whether this error was caused by the terminal being gone.
+    pub fn is_disconnection(&self) -> bool {
+        match self {
+            Self::Render(e) => e.is_disconnection(),
+            Self::Io(e) => is_disconnection(e),
+            Self::Disconnected => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        execute::{ExecutionPolicy, PROCESS_TESTS},
+        markdown::elements::{Code, CodeAttributes, CodeLanguage},
+        outline::OutlineFormat,
+        render::capabilities::CellSize,
+    };
+    use comrak::{nodes::AstNode, Arena};
+    use rstest::rstest;
+    use std::{io::Write, sync::PoisonError, thread};
+    use tempfile::NamedTempFile;
+
+    fn build_options() -> PresenterOptions {
+        PresenterOptions {
+            mode: PresentMode::Development,
+            builder_options: Default::default(),
+            cell_size: CellSizePolicy::Fixed(CellSize::new(10, 20).expect("invalid cell size")),
+            dimensions: Default::default(),
+            bindings: Default::default(),
+            validate_overflows: false,
+            event_emitter: None,
+            safe_area_margin: Default::default(),
+            cache_budget: 1024 * 1024,
+            cache_usage_overlay: false,
+            end_of_deck: Default::default(),
+            preview_dimensions: Vec::new(),
+            kiosk: false,
+            session: None,
+            resume: None,
+            suggested_resume: None,
+            fps: 30,
+            slow_frames: None,
+            pause_on_focus_loss: false,
+        }
+    }
+
+    fn write_presentation(contents: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().expect("failed to create file");
+        file.write_all(contents.as_bytes()).expect("failed to write file");
+        file
+    }
+
+    // Builds a presenter and loads the presentation in the given file, like `Presenter::present` does.
+    fn build_presenter<'a>(
+        theme: &'a PresentationTheme,
+        arena: &'a Arena<AstNode<'a>>,
+        file: &NamedTempFile,
+        options: PresenterOptions,
+    ) -> Presenter<'a> {
+        let commands = CommandSource::new(file.path(), Default::default()).expect("invalid bindings");
+        let printer = Rc::new(ImagePrinter::default());
+        let resources = Resources::new("examples", ImageRegistry(printer.clone()));
+        let mut presenter = Presenter::new(
+            theme,
+            commands,
+            MarkdownParser::new(arena),
+            resources,
+            TypstRender::default(),
+            Themes::default(),
+            printer,
+            options,
+        );
+        presenter.state = PresenterState::Presenting(Presentation::from(vec![]));
+        presenter.try_reload(file.path(), true);
+        presenter
+    }
+
+    #[test]
+    fn disconnection_kills_running_processes() {
+        let _guard = PROCESS_TESTS.write().unwrap_or_else(PoisonError::into_inner);
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("hi\n");
+        let mut presenter = build_presenter(&theme, &arena, &file, build_options());
+
+        let code = Code {
+            contents: "sleep 30".into(),
+            language: CodeLanguage::Shell("sh".into()),
+            language_name: "sh".into(),
+            attributes: CodeAttributes { execute: true, ..Default::default() },
+        };
+        let handle = CodeExecuter::execute(&code, &ExecutionPolicy::default()).expect("execution failed");
+        let start = Instant::now();
+        let result = presenter.finish(Err(io::Error::from(io::ErrorKind::BrokenPipe).into()), None);
+        assert!(matches!(result, Err(PresentationError::Disconnected)), "unexpected result: {result:?}");
+
+        while !handle.state().status.is_finished() {
+            assert!(start.elapsed() < Duration::from_secs(10), "process wasn't killed");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[rstest]
+    #[case::regular(false, true)]
+    #[case::kiosk(true, false)]
+    fn status_line_visibility(#[case] kiosk: bool, #[case] visible: bool) {
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("hi\n");
+        let options = PresenterOptions { kiosk, ..build_options() };
+        let mut presenter = build_presenter(&theme, &arena, &file, options);
+
+        presenter.status = Some(Text::from("hello"));
+        assert_eq!(presenter.status_line().is_some(), visible);
+        presenter.status = None;
+        presenter.command_line_open = true;
+        assert_eq!(presenter.status_line().is_some(), visible);
+    }
+
+    #[rstest]
+    #[case::disabled(false, false, None)]
+    #[case::enabled(true, false, Some("cache 1.5/2MB"))]
+    #[case::kiosk(true, true, None)]
+    fn cache_usage_overlay(#[case] enabled: bool, #[case] kiosk: bool, #[case] expected: Option<&str>) {
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("hi\n");
+        let options =
+            PresenterOptions { cache_budget: 2 * 1024 * 1024, cache_usage_overlay: enabled, kiosk, ..build_options() };
+        let mut presenter = build_presenter(&theme, &arena, &file, options);
+
+        presenter.cache_used = 3 * 512 * 1024;
+        assert_eq!(presenter.cache_usage().as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case::stop(EndOfDeck::Stop, 1, false, 0)]
+    #[case::wrap(EndOfDeck::Wrap, 0, false, 1)]
+    #[case::black(EndOfDeck::Black, 1, true, 1)]
+    fn end_of_deck_navigation(
+        #[case] end_of_deck: EndOfDeck,
+        #[case] after_next: usize,
+        #[case] blank: bool,
+        #[case] after_previous: usize,
+    ) {
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("one\n\n<!-- end_slide -->\n\ntwo\n");
+        let options = PresenterOptions { end_of_deck, ..build_options() };
+        let mut presenter = build_presenter(&theme, &arena, &file, options);
+
+        presenter.apply_command(Command::Next);
+        presenter.apply_command(Command::Next);
+        assert_eq!(presenter.state.presentation().current_slide_index(), after_next);
+        assert_eq!(matches!(presenter.state, PresenterState::Blank { after_last_slide: true, .. }), blank);
+
+        presenter.apply_command(Command::Previous);
+        assert!(matches!(presenter.state, PresenterState::Presenting(_)));
+        assert_eq!(presenter.state.presentation().current_slide_index(), after_previous);
+    }
+
+    #[test]
+    fn navigation_sequence() {
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("one\n<!-- end_slide -->\ntwo\n<!-- end_slide -->\nthree\n");
+        let mut presenter = build_presenter(&theme, &arena, &file, build_options());
+        assert_eq!(presenter.slide_count(), 3);
+        assert_eq!(presenter.current_index(), 0);
+
+        for index in 1..3 {
+            presenter.apply_command(Command::Next);
+            assert_eq!(presenter.current_index(), index);
+        }
+        presenter.apply_command(Command::Next);
+        assert_eq!(presenter.current_index(), 2);
+
+        presenter.apply_command(Command::FirstSlide);
+        assert_eq!(presenter.current_index(), 0);
+        presenter.apply_command(Command::LastSlide);
+        assert_eq!(presenter.current_index(), 2);
+        presenter.apply_command(Command::Previous);
+        assert_eq!(presenter.current_index(), 1);
+
+        presenter.state = PresenterState::Empty;
+        assert_eq!(presenter.slide_count(), 0);
+        assert_eq!(presenter.current_index(), 0);
+    }
+
+    #[test]
+    fn blank_keeps_state() {
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("one\n\n<!-- end_slide -->\n\ntwo\n");
+        let mut presenter = build_presenter(&theme, &arena, &file, build_options());
+
+        presenter.apply_command(Command::Next);
+        presenter.apply_command(Command::ToggleSlideIndex);
+        presenter.apply_command(Command::ToggleBlank);
+        assert!(matches!(presenter.state, PresenterState::Blank { after_last_slide: false, .. }));
+
+        // Navigating while blank does nothing and moving backwards doesn't unblank it.
+        presenter.apply_command(Command::Previous);
+        presenter.apply_command(Command::FirstSlide);
+        assert!(matches!(presenter.state, PresenterState::Blank { .. }));
+
+        presenter.apply_command(Command::ToggleBlank);
+        assert!(matches!(presenter.state, PresenterState::SlideIndex(_)));
+        assert_eq!(presenter.state.presentation().current_slide_index(), 1);
+    }
+
+    #[test]
+    fn leave_code_focus() {
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("```rust\nfn main() {}\n```\n");
+        let mut presenter = build_presenter(&theme, &arena, &file, build_options());
+
+        presenter.apply_command(Command::FocusCode);
+        assert!(matches!(presenter.state, PresenterState::CodeFocus { .. }));
+        presenter.apply_command(Command::CloseModal);
+        assert!(matches!(presenter.state, PresenterState::Presenting(_)));
+    }
+
+    #[test]
+    fn theme_command() {
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("hi\n");
+        let mut presenter = build_presenter(&theme, &arena, &file, build_options());
+
+        presenter.apply_line_command(LineCommand::Theme("dark".into()), file.path());
+        assert!(presenter.theme_override.is_some());
+        assert!(presenter.status.is_none());
+
+        presenter.apply_line_command(LineCommand::Theme("drak".into()), file.path());
+        let status = presenter.status.take().expect("no status");
+        assert_eq!(status.content, "unknown theme 'drak', did you mean 'dark'?");
+        assert_eq!(status.style.colors, presenter.state.presentation().error_colors().clone());
+    }
+
+    #[test]
+    fn set_command() {
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("hi\n");
+        let mut presenter = build_presenter(&theme, &arena, &file, build_options());
+
+        let command = LineCommand::Set { option: LineOption::Footer, enabled: false };
+        presenter.apply_line_command(command, file.path());
+        assert!(presenter.options.builder_options.hide_footer);
+
+        let command = LineCommand::Set { option: LineOption::IncrementalLists, enabled: true };
+        presenter.apply_line_command(command, file.path());
+        assert!(presenter.options.builder_options.incremental_lists);
+    }
+
+    #[test]
+    fn export_commands() {
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("Hello\n===\n\ntext\n");
+        let mut presenter = build_presenter(&theme, &arena, &file, build_options());
+        let directory = tempfile::tempdir().expect("failed to create directory");
+
+        let outline_path = directory.path().join("outline.md");
+        let format = ExportFormat::Outline(OutlineFormat::Markdown);
+        presenter.apply_line_command(LineCommand::Export { format, path: outline_path.clone() }, file.path());
+        let outline = fs::read_to_string(&outline_path).expect("outline not written");
+        assert!(outline.contains("Hello"), "{outline}");
+
+        let html_path = directory.path().join("html");
+        let command = LineCommand::Export { format: ExportFormat::Html, path: html_path.clone() };
+        presenter.apply_line_command(command, file.path());
+        let html = fs::read_to_string(html_path.join("index.html")).expect("html not written");
+        assert!(html.contains("Hello"), "{html}");
+        let status = presenter.status.take().expect("no status");
+        assert_eq!(status.content, format!("exported to {}", html_path.display()));
+    }
+
+    #[test]
+    fn other_errors_are_kept() {
+        let _guard = PROCESS_TESTS.write().unwrap_or_else(PoisonError::into_inner);
+        let theme = PresentationTheme::default();
+        let arena = Arena::new();
+        let file = write_presentation("hi\n");
+        let mut presenter = build_presenter(&theme, &arena, &file, build_options());
+
+        let result = presenter.finish(Err(PresentationError::Fatal("oops".into())), None);
+        assert!(matches!(result, Err(PresentationError::Fatal(_))), "unexpected result: {result:?}");
+        assert!(matches!(presenter.finish(Ok(()), None), Ok(())));
+    }
 }
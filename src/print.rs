@@ -0,0 +1,374 @@
+//! Printing of slides into the terminal's regular screen.
+//!
+//! Unlike when presenting, nothing is cleared and neither the alternate screen nor raw mode are
+//! used, so slides stay in the terminal's scrollback after presenterm exits. This is meant to be
+//! used to take screenshots of slides, and to dump presentations when the output isn't a terminal.
+
+use crate::{
+    custom::KeyBindingsConfig,
+    markdown::parse::ParseError,
+    media::printer::ImagePrinter,
+    presentation::{Presentation, RenderOperation, Slide},
+    processing::builder::{BuildError, PresentationBuilder, PresentationBuilderOptions, Themes},
+    render::{
+        capabilities::{supports_truecolor, CellSizePolicy},
+        draw::RenderError,
+        engine::{RenderEngine, RenderEngineOptions},
+        properties::{RenderDimensions, WindowSize},
+        terminal::{Terminal, TerminalWrite},
+    },
+    style::Colors,
+    typst::TypstRender,
+    MarkdownParser, PresentationTheme, Resources,
+};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    rc::Rc,
+};
+
+/// Prints the slides of a presentation into the terminal.
+pub struct SlidePrinter<'a> {
+    parser: MarkdownParser<'a>,
+    default_theme: &'a PresentationTheme,
+    resources: Resources,
+    typst: TypstRender,
+    themes: Themes,
+    image_printer: Rc<ImagePrinter>,
+    cell_size: CellSizePolicy,
+    options: PresentationBuilderOptions,
+}
+
+impl<'a> SlidePrinter<'a> {
+    /// Construct a new slide printer.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        parser: MarkdownParser<'a>,
+        default_theme: &'a PresentationTheme,
+        resources: Resources,
+        typst: TypstRender,
+        themes: Themes,
+        image_printer: Rc<ImagePrinter>,
+        cell_size: CellSizePolicy,
+        options: PresentationBuilderOptions,
+    ) -> Self {
+        Self { parser, default_theme, resources, typst, themes, image_printer, cell_size, options }
+    }
+
+    /// Print the slide with the given 1 based index, with all of its pauses revealed.
+    ///
+    /// The slide is laid out in the given dimensions, optionally overriding their number of
+    /// columns. If these come from the terminal but the output isn't one, a default size is used.
+    pub fn print<W: Write>(
+        &mut self,
+        presentation_path: &Path,
+        slide: usize,
+        dimensions: RenderDimensions,
+        columns: Option<u16>,
+        writer: W,
+    ) -> Result<(), PrintSlideError> {
+        let dimensions = self.window_size(dimensions, columns)?;
+        let mut presentation = self.build(presentation_path)?;
+        // Slides are picked by the number they're displayed with so unnumbered ones can't be printed.
+        let slide_count = presentation.numbering().total();
+        let index = presentation.numbering().slide_index(slide);
+        if !index.is_some_and(|index| presentation.go_to_slide_end(index)) {
+            return Err(PrintSlideError::NoSuchSlide { slide, slide_count });
+        }
+
+        let mut drawer = SlideDrawer::new(writer, self.image_printer.clone(), dimensions)?;
+        drawer.draw_next(presentation.current_slide())?;
+        Ok(())
+    }
+
+    /// Print every slide, one after the other, with all of their pauses revealed.
+    ///
+    /// Unlike [SlidePrinter::print], this includes unnumbered slides. This is meant to be used when
+    /// the output isn't a terminal, so there's no way to move between slides.
+    pub fn print_all<W: Write>(
+        &mut self,
+        presentation_path: &Path,
+        dimensions: RenderDimensions,
+        columns: Option<u16>,
+        writer: W,
+    ) -> Result<(), PrintSlideError> {
+        let dimensions = self.window_size(dimensions, columns)?;
+        let mut presentation = self.build(presentation_path)?;
+        let mut drawer = SlideDrawer::new(writer, self.image_printer.clone(), dimensions)?;
+        for index in 0..presentation.slide_count() {
+            presentation.go_to_slide_end(index);
+            drawer.draw_next(presentation.current_slide())?;
+        }
+        Ok(())
+    }
+
+    fn window_size(&self, dimensions: RenderDimensions, columns: Option<u16>) -> Result<WindowSize, PrintSlideError> {
+        let mut dimensions = match dimensions.window_size(self.cell_size) {
+            Ok(dimensions) => dimensions,
+            Err(_) => RenderDimensions::FALLBACK.window_size(self.cell_size)?,
+        };
+        if let Some(columns) = columns {
+            dimensions.width = (dimensions.width / dimensions.columns.max(1)).saturating_mul(columns);
+            dimensions.columns = columns;
+        }
+        Ok(dimensions)
+    }
+
+    fn build(&mut self, presentation_path: &Path) -> Result<Presentation, PrintSlideError> {
+        let content = fs::read_to_string(presentation_path).map_err(PrintSlideError::Read)?;
+        let elements = self.parser.parse(&content)?;
+        let presentation = PresentationBuilder::new(
+            self.default_theme,
+            &mut self.resources,
+            &mut self.typst,
+            &self.themes,
+            Default::default(),
+            KeyBindingsConfig::default(),
+            self.options.clone(),
+        )
+        .build(elements)?;
+        Ok(presentation)
+    }
+}
+
+/// Draws slides one after the other into the terminal's regular screen.
+///
+/// Every slide is written out as soon as it's drawn so a reader sees the first ones without
+/// waiting for the rest.
+pub(crate) struct SlideDrawer<W: Write> {
+    terminal: Terminal<Scrollback<W>>,
+    dimensions: WindowSize,
+}
+
+impl<W: Write> SlideDrawer<W> {
+    /// Construct a drawer that lays out slides in the given dimensions.
+    pub(crate) fn new(writer: W, image_printer: Rc<ImagePrinter>, dimensions: WindowSize) -> io::Result<Self> {
+        let terminal = Terminal::new(Scrollback(writer), image_printer)?;
+        Ok(Self { terminal, dimensions })
+    }
+
+    /// Draw a slide below the last one drawn, showing only the parts of it that are visible.
+    pub(crate) fn draw_next(&mut self, slide: &Slide) -> Result<(), PrintSlideError> {
+        let terminal = &mut self.terminal;
+        // Make room for the slide by scrolling whatever is in the screen up rather than clearing it.
+        terminal.print_line(&"\n".repeat(self.dimensions.rows as usize))?;
+        terminal.move_to(0, 0)?;
+        let operations = slide.iter_operations().filter(|operation| !matches!(operation, RenderOperation::ClearScreen));
+        let options = RenderEngineOptions { truecolor: supports_truecolor(), ..Default::default() };
+        RenderEngine::new(terminal, self.dimensions.clone(), options).render(operations)?;
+
+        // Leave the cursor right below the slide so the shell's prompt shows up after it.
+        let last_row = terminal.cursor_row;
+        terminal.set_colors(Colors::default())?;
+        terminal.move_to(0, last_row)?;
+        terminal.print_line("\n")?;
+        terminal.flush()?;
+        Ok(())
+    }
+}
+
+// Writes into the terminal's regular screen without changing any of its modes.
+struct Scrollback<W>(W);
+
+impl<W: Write> Write for Scrollback<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl<W: Write> TerminalWrite for Scrollback<W> {
+    fn init(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn deinit(&mut self) {}
+}
+
+/// An error when printing a slide.
+#[derive(thiserror::Error, Debug)]
+pub enum PrintSlideError {
+    #[error("failed to read presentation: {0}")]
+    Read(io::Error),
+
+    #[error("failed to parse presentation: {0}")]
+    Parse(#[from] ParseError),
+
+    #[error("failed to build presentation: {0}")]
+    Build(#[from] BuildError),
+
+    #[error("slide {slide} doesn't exist, the presentation has {slide_count} slides")]
+    NoSuchSlide { slide: usize, slide_count: usize },
+
+    #[error("failed to render slide: {0}")]
+    Render(#[from] RenderError),
+
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{render::terminal::TestWriter, PresentationThemeSet};
+    use tempfile::tempdir;
+
+    const ALTERNATE_SCREEN: &str = "\x1b[?1049h";
+    const CLEAR_SCREEN: &str = "\x1b[2J";
+
+    fn print(contents: &str, slide: usize, options: PresentationBuilderOptions) -> Result<String, PrintSlideError> {
+        print_in(contents, slide, options, RenderDimensions::Fixed { columns: 40, rows: 10 })
+    }
+
+    fn print_in(
+        contents: &str,
+        slide: usize,
+        options: PresentationBuilderOptions,
+        dimensions: RenderDimensions,
+    ) -> Result<String, PrintSlideError> {
+        let directory = tempdir().expect("creating tempdir");
+        let path = directory.path().join("slides.md");
+        fs::write(&path, contents).expect("writing presentation");
+
+        let arena = comrak::Arena::new();
+        let theme = PresentationThemeSet::default().load_by_name("dark").expect("no theme");
+        let resources = Resources::new(directory.path(), Default::default());
+        let mut printer = SlidePrinter::new(
+            MarkdownParser::new(&arena),
+            &theme,
+            resources,
+            TypstRender::default(),
+            Default::default(),
+            Default::default(),
+            CellSizePolicy::from_font_size(16),
+            options,
+        );
+        let mut output = Vec::new();
+        printer.print(&path, slide, dimensions, None, &mut output)?;
+        Ok(String::from_utf8(output).expect("invalid utf8"))
+    }
+
+    #[test]
+    fn final_reveal_state() {
+        let contents = "first\n\n<!-- end_slide -->\n\nhello\n\n<!-- pause -->\n\npotato\n";
+        let output = print(contents, 2, Default::default()).expect("print failed");
+        assert!(output.contains("hello"));
+        assert!(output.contains("potato"));
+        assert!(!output.contains("first"));
+        assert!(!output.contains(ALTERNATE_SCREEN), "{output:?}");
+        assert!(!output.contains(CLEAR_SCREEN), "{output:?}");
+    }
+
+    #[test]
+    fn footer() {
+        let contents = r#"---
+theme:
+  override:
+    footer:
+      style: template
+      right: "{current_slide} / {total_slides}"
+---
+
+hello
+"#;
+        let options = PresentationBuilderOptions { hide_footer: true, ..Default::default() };
+        let output = print(contents, 1, options).expect("print failed");
+        assert!(!output.contains("1 / 1"), "{output:?}");
+
+        let output = print(contents, 1, Default::default()).expect("print failed");
+        assert!(output.contains("1 / 1"), "{output:?}");
+    }
+
+    #[test]
+    fn dimensions() {
+        let words: Vec<_> = (0..30).map(|index| format!("w{index:02}")).collect();
+        let contents = words.join(" ");
+        // Every line is printed on its own so the words in it are only separated by spaces.
+        let first_line = |columns| -> usize {
+            let dimensions = RenderDimensions::Fixed { columns, rows: 20 };
+            let output = print_in(&contents, 1, Default::default(), dimensions).expect("print failed");
+            let start = output.find("w00").expect("no text");
+            let line = &output[start..];
+            let line = &line[..line.find('\x1b').unwrap_or(line.len())];
+            line.split_whitespace().count()
+        };
+        let narrow = first_line(40);
+        let wide = first_line(100);
+        assert!(narrow < wide, "{narrow} >= {wide}");
+        assert!(wide < words.len());
+    }
+
+    #[test]
+    fn unnumbered_slides() {
+        let contents = "first\n<!-- end_slide -->\n<!-- unnumbered -->\nbackup\n<!-- end_slide -->\nlast\n";
+        let output = print(contents, 2, Default::default()).expect("print failed");
+        assert!(output.contains("last"), "{output:?}");
+
+        let result = print(contents, 3, Default::default());
+        assert!(matches!(result, Err(PrintSlideError::NoSuchSlide { slide_count: 2, .. })), "{result:?}");
+    }
+
+    #[test]
+    fn all_slides() {
+        let directory = tempdir().expect("creating tempdir");
+        let path = directory.path().join("slides.md");
+        let contents = "first\n<!-- end_slide -->\n<!-- unnumbered -->\nbackup\n<!-- end_slide -->\nlast\n\
+                        <!-- pause -->\nhidden\n";
+        fs::write(&path, contents).expect("writing presentation");
+
+        let arena = comrak::Arena::new();
+        let theme = PresentationThemeSet::default().load_by_name("dark").expect("no theme");
+        let mut printer = SlidePrinter::new(
+            MarkdownParser::new(&arena),
+            &theme,
+            Resources::new(directory.path(), Default::default()),
+            TypstRender::default(),
+            Default::default(),
+            Default::default(),
+            CellSizePolicy::from_font_size(16),
+            Default::default(),
+        );
+        let mut output = Vec::new();
+        let dimensions = RenderDimensions::Fixed { columns: 40, rows: 10 };
+        printer.print_all(&path, dimensions, None, &mut output).expect("print failed");
+        let output = String::from_utf8(output).expect("invalid utf8");
+        let positions: Vec<_> = ["first", "backup", "last", "hidden"]
+            .into_iter()
+            .map(|text| output.find(text).unwrap_or_else(|| panic!("no {text} in {output:?}")))
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] <= pair[1]), "{positions:?}");
+        assert!(!output.contains(ALTERNATE_SCREEN), "{output:?}");
+    }
+
+    #[test]
+    fn missing_slide() {
+        for slide in [0, 2] {
+            let result = print("hello\n", slide, Default::default());
+            assert!(matches!(result, Err(PrintSlideError::NoSuchSlide { slide_count: 1, .. })), "{result:?}");
+        }
+    }
+
+    fn text_slide(text: &str) -> Slide {
+        Slide::from(vec![RenderOperation::RenderText { line: text.to_string().into(), alignment: Default::default() }])
+    }
+
+    #[test]
+    fn draw_slides_incrementally() {
+        let writer = TestWriter::default();
+        let dimensions = WindowSize { rows: 5, columns: 20, height: 100, width: 200 };
+        let mut drawer = SlideDrawer::new(writer.clone(), Default::default(), dimensions).expect("creating drawer");
+        drawer.draw_next(&text_slide("first")).expect("draw failed");
+        drawer.draw_next(&text_slide("second")).expect("draw failed");
+        drop(drawer);
+
+        // The first slide, down to the line break after it, is written out before the second one is drawn.
+        let flushed = writer.flushed();
+        let drawn = |output: &String| output.contains("first") && !output.contains("second") && output.ends_with('\n');
+        assert!(flushed.iter().any(drawn), "{flushed:?}");
+        assert!(flushed.last().is_some_and(|output| output.contains("second")), "{flushed:?}");
+    }
+}
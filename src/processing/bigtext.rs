@@ -0,0 +1,252 @@
+use crate::{
+    markdown::{
+        elements::{Text, TextBlock},
+        text::WeightedTextBlock,
+    },
+    presentation::{AsRenderOperations, RenderOperation},
+    render::{layout::Layout, properties::WindowSize},
+    style::{Colors, TextStyle},
+    theme::Alignment,
+};
+use std::{iter, rc::Rc};
+
+// The number of rows each glyph in the font is made of.
+const GLYPH_HEIGHT: usize = 5;
+
+// The maximum factor glyphs will be scaled by.
+const MAX_SCALE: usize = 2;
+
+const FILLED_CHARACTER: char = '█';
+
+/// Text rendered in a large font made out of block characters.
+///
+/// This picks the largest scale that fits in the available width and falls back to rendering the
+/// text normally if it doesn't fit at all.
+#[derive(Debug)]
+pub(crate) struct BigText {
+    text: String,
+    colors: Colors,
+    alignment: Alignment,
+    fallback: WeightedTextBlock,
+}
+
+impl BigText {
+    pub(crate) fn new(text: String, colors: Colors, alignment: Alignment, fallback: WeightedTextBlock) -> Self {
+        Self { text, colors, alignment, fallback }
+    }
+
+    /// Get the rows this text is made of when aligned within the given number of columns.
+    pub(crate) fn lines(&self, columns: u16) -> Vec<TextBlock> {
+        let rows: Vec<_> = match BlockFont::render(&self.text, self.available_width(columns)) {
+            Some(rows) => rows
+                .into_iter()
+                .map(|row| TextBlock(vec![Text::new(row, TextStyle::default().colors(self.colors.clone()))]))
+                .collect(),
+            None => vec![TextBlock(self.fallback.iter_texts().map(|text| text.text().clone()).collect())],
+        };
+        let dimensions = WindowSize { rows: 0, columns, width: 0, height: 0 };
+        let layout = Layout::new(self.alignment.clone());
+        rows.into_iter()
+            .map(|mut row| {
+                let start_column = layout.compute(&dimensions, row.width() as u16).start_column;
+                row.0.insert(0, Text::from(" ".repeat(start_column as usize)));
+                row
+            })
+            .collect()
+    }
+
+    fn available_width(&self, columns: u16) -> usize {
+        let margins = match &self.alignment {
+            Alignment::Left { margin } | Alignment::Right { margin } => margin.as_characters(columns),
+            Alignment::Center { minimum_margin, .. } => minimum_margin.as_characters(columns).saturating_mul(2),
+        };
+        columns.saturating_sub(margins) as usize
+    }
+}
+
+impl From<BigText> for RenderOperation {
+    fn from(text: BigText) -> Self {
+        Self::RenderDynamic(Rc::new(text))
+    }
+}
+
+impl AsRenderOperations for BigText {
+    fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
+        let alignment = &self.alignment;
+        let Some(rows) = BlockFont::render(&self.text, self.available_width(dimensions.columns)) else {
+            return vec![RenderOperation::RenderText { line: self.fallback.clone(), alignment: alignment.clone() }];
+        };
+        let mut operations = Vec::new();
+        for row in rows {
+            if !operations.is_empty() {
+                operations.push(RenderOperation::RenderLineBreak);
+            }
+            let text = Text::new(row, TextStyle::default().colors(self.colors.clone()));
+            operations.push(RenderOperation::RenderText { line: vec![text].into(), alignment: alignment.clone() });
+        }
+        operations
+    }
+
+    fn diffable_content(&self) -> Option<&str> {
+        Some(&self.text)
+    }
+}
+
+/// A font where every glyph is made of block characters.
+pub(crate) struct BlockFont;
+
+impl BlockFont {
+    /// Render a piece of text using the largest scale that fits in `max_width` columns.
+    ///
+    /// Returns `None` if the text contains characters the font doesn't support or if the text
+    /// doesn't fit even when not scaled.
+    pub(crate) fn render(text: &str, max_width: usize) -> Option<Vec<String>> {
+        let glyphs: Vec<_> = text.chars().map(Self::glyph).collect::<Option<_>>()?;
+        if glyphs.is_empty() {
+            return None;
+        }
+        // One column of separation between glyphs.
+        let width = glyphs.iter().map(|glyph| glyph[0].len()).sum::<usize>() + glyphs.len() - 1;
+        let scale = (1..=MAX_SCALE).rev().find(|scale| width * scale <= max_width)?;
+        let mut rows = Vec::new();
+        for row_index in 0..GLYPH_HEIGHT {
+            let mut row = String::new();
+            for (glyph_index, glyph) in glyphs.iter().enumerate() {
+                if glyph_index > 0 {
+                    row.extend(iter::repeat(' ').take(scale));
+                }
+                for pixel in glyph[row_index].chars() {
+                    let character = if pixel == '#' { FILLED_CHARACTER } else { ' ' };
+                    row.extend(iter::repeat(character).take(scale));
+                }
+            }
+            rows.extend(iter::repeat(row).take(scale));
+        }
+        Some(rows)
+    }
+
+    fn glyph(character: char) -> Option<[&'static str; GLYPH_HEIGHT]> {
+        let glyph = match character.to_ascii_uppercase() {
+            'A' => [" ### ", "#   #", "#####", "#   #", "#   #"],
+            'B' => ["#### ", "#   #", "#### ", "#   #", "#### "],
+            'C' => [" ####", "#    ", "#    ", "#    ", " ####"],
+            'D' => ["#### ", "#   #", "#   #", "#   #", "#### "],
+            'E' => ["#####", "#    ", "#### ", "#    ", "#####"],
+            'F' => ["#####", "#    ", "#### ", "#    ", "#    "],
+            'G' => [" ####", "#    ", "#  ##", "#   #", " ### "],
+            'H' => ["#   #", "#   #", "#####", "#   #", "#   #"],
+            'I' => ["###", " # ", " # ", " # ", "###"],
+            'J' => ["  ###", "    #", "    #", "#   #", " ### "],
+            'K' => ["#   #", "#  # ", "###  ", "#  # ", "#   #"],
+            'L' => ["#    ", "#    ", "#    ", "#    ", "#####"],
+            'M' => ["#   #", "## ##", "# # #", "#   #", "#   #"],
+            'N' => ["#   #", "##  #", "# # #", "#  ##", "#   #"],
+            'O' => [" ### ", "#   #", "#   #", "#   #", " ### "],
+            'P' => ["#### ", "#   #", "#### ", "#    ", "#    "],
+            'Q' => [" ### ", "#   #", "# # #", "#  # ", " ## #"],
+            'R' => ["#### ", "#   #", "#### ", "#  # ", "#   #"],
+            'S' => [" ####", "#    ", " ### ", "    #", "#### "],
+            'T' => ["#####", "  #  ", "  #  ", "  #  ", "  #  "],
+            'U' => ["#   #", "#   #", "#   #", "#   #", " ### "],
+            'V' => ["#   #", "#   #", "#   #", " # # ", "  #  "],
+            'W' => ["#   #", "#   #", "# # #", "## ##", "#   #"],
+            'X' => ["#   #", " # # ", "  #  ", " # # ", "#   #"],
+            'Y' => ["#   #", " # # ", "  #  ", "  #  ", "  #  "],
+            'Z' => ["#####", "   # ", "  #  ", " #   ", "#####"],
+            '0' => [" ### ", "#  ##", "# # #", "##  #", " ### "],
+            '1' => [" # ", "## ", " # ", " # ", "###"],
+            '2' => [" ### ", "#   #", "  ## ", " #   ", "#####"],
+            '3' => ["#### ", "    #", " ### ", "    #", "#### "],
+            '4' => ["#   #", "#   #", "#####", "    #", "    #"],
+            '5' => ["#####", "#    ", "#### ", "    #", "#### "],
+            '6' => [" ### ", "#    ", "#### ", "#   #", " ### "],
+            '7' => ["#####", "    #", "   # ", "  #  ", "  #  "],
+            '8' => [" ### ", "#   #", " ### ", "#   #", " ### "],
+            '9' => [" ### ", "#   #", " ####", "    #", " ### "],
+            ' ' => ["  ", "  ", "  ", "  ", "  "],
+            '.' => [" ", " ", " ", " ", "#"],
+            ',' => ["  ", "  ", "  ", " #", "# "],
+            '!' => ["#", "#", "#", " ", "#"],
+            '?' => ["### ", "   #", " ## ", "    ", " #  "],
+            ':' => [" ", "#", " ", "#", " "],
+            ';' => ["  ", " #", "  ", " #", "# "],
+            '\'' => ["#", "#", " ", " ", " "],
+            '"' => ["# #", "# #", "   ", "   ", "   "],
+            '-' => ["   ", "   ", "###", "   ", "   "],
+            '_' => ["    ", "    ", "    ", "    ", "####"],
+            '+' => ["   ", " # ", "###", " # ", "   "],
+            '=' => ["   ", "###", "   ", "###", "   "],
+            '*' => ["# #", " # ", "# #", "   ", "   "],
+            '/' => ["    #", "   # ", "  #  ", " #   ", "#    "],
+            '\\' => ["#    ", " #   ", "  #  ", "   # ", "    #"],
+            '(' => [" #", "# ", "# ", "# ", " #"],
+            ')' => ["# ", " #", " #", " #", "# "],
+            '[' => ["##", "# ", "# ", "# ", "##"],
+            ']' => ["##", " #", " #", " #", "##"],
+            '<' => ["  #", " # ", "#  ", " # ", "  #"],
+            '>' => ["#  ", " # ", "  #", " # ", "#  "],
+            '#' => [" # # ", "#####", " # # ", "#####", " # # "],
+            '%' => ["#   #", "   # ", "  #  ", " #   ", "#   #"],
+            '&' => [" ##  ", "#  # ", " ## #", "#  # ", " ## #"],
+            _ => return None,
+        };
+        Some(glyph)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use unicode_width::UnicodeWidthStr;
+
+    #[test]
+    fn glyphs_are_rectangular() {
+        for character in (' '..='~').filter_map(BlockFont::glyph) {
+            assert!(character.iter().all(|row| row.len() == character[0].len()), "{character:?}");
+        }
+    }
+
+    #[rstest]
+    #[case::single("A", 20, 10, 10)]
+    #[case::single_scaled_down("A", 9, 5, 5)]
+    #[case::word("hi!", 100, 22, 10)]
+    #[case::word_not_scaled("hi!", 12, 11, 5)]
+    #[case::narrow_glyphs("1.", 100, 10, 10)]
+    fn dimensions(#[case] text: &str, #[case] max_width: usize, #[case] width: usize, #[case] height: usize) {
+        let rows = BlockFont::render(text, max_width).expect("didn't fit");
+        assert_eq!(rows.len(), height);
+        for row in rows {
+            assert_eq!(row.width(), width);
+        }
+    }
+
+    #[test]
+    fn render_text() {
+        let rows = BlockFont::render("hi", 9).expect("didn't fit");
+        let expected = &["█   █ ███", "█   █  █ ", "█████  █ ", "█   █  █ ", "█   █ ███"];
+        assert_eq!(rows, expected);
+    }
+
+    #[rstest]
+    #[case::too_wide("hello", 20)]
+    #[case::unsupported_characters("ñ", 100)]
+    #[case::empty("", 100)]
+    fn does_not_render(#[case] text: &str, #[case] max_width: usize) {
+        assert_eq!(BlockFont::render(text, max_width), None);
+    }
+
+    #[test]
+    fn fallback() {
+        let fallback = WeightedTextBlock::from("hello".to_string());
+        let alignment = Alignment::Center { minimum_margin: Default::default(), minimum_size: 0 };
+        let text = BigText::new("hello".into(), Colors::default(), alignment, fallback);
+        let operations = text.as_render_operations(&WindowSize { rows: 10, columns: 10, height: 0, width: 0 });
+        assert_eq!(operations.len(), 1);
+        assert!(matches!(&operations[0], RenderOperation::RenderText { line, .. } if line.width() == 5));
+
+        let operations = text.as_render_operations(&WindowSize { rows: 10, columns: 100, height: 0, width: 0 });
+        let texts = operations.iter().filter(|op| matches!(op, RenderOperation::RenderText { .. })).count();
+        assert_eq!(texts, 10);
+    }
+}
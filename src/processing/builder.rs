@@ -1,35 +1,75 @@
 use crate::{
-    custom::{KeyBindingsConfig, OptionsConfig},
+    artifacts::ArtifactStore,
+    custom::{
+        CountdownReturn, CountdownStart, KeyBindingsConfig, OptionsConfig, RevealStyle, SlideBudget, StructureChecks,
+    },
+    execute::ExecutionPolicy,
     markdown::{
+        divs::FencedDiv,
         elements::{
-            Code, CodeLanguage, Highlight, HighlightGroup, ListItem, ListItemType, MarkdownElement, ParagraphElement,
-            SourcePosition, Table, TableRow, Text, TextBlock,
+            Code, CodeAttributes, CodeLanguage, DescriptionItem, DetailsTag, Highlight, HighlightGroup, ListItem,
+            ListItemType, MarkdownElement, ParagraphElement, QuoteLine, SourcePosition, Table, TableAlignment,
+            TableRow, Text, TextBlock,
         },
         text::WeightedTextBlock,
     },
     media::{image::Image, printer::RegisterImageError, register::ImageRegistry},
+    mermaid::{MermaidBackend, MermaidRender, MermaidRenderError},
     presentation::{
         ChunkMutator, ImageProperties, MarginProperties, Modals, PreformattedLine, Presentation, PresentationMetadata,
-        PresentationState, PresentationThemeMetadata, RenderOperation, Slide, SlideBuilder, SlideChunk,
+        PresentationState, PresentationThemeMetadata, RedactionConfig, RenderOperation, Slide, SlideBuilder,
+        SlideChunk, SlideNumbering, SlideSteps, StepKind, TodoNote,
     },
     processing::{
+        bigtext::BigText,
         code::{CodePreparer, HighlightContext, HighlightMutator, HighlightedLine},
+        countdown::{self, CountdownOperation},
+        deferred::DeferredImage,
+        details::CollapsibleDetails,
+        directives::{find_deprecated, replace_deprecated, DirectiveWarning},
+        end_screen::{ElapsedTime, EndScreen},
         execution::RunCodeOperation,
-        footer::{FooterContext, FooterGenerator},
-        modals::IndexBuilder,
+        focus::{self, FocusableCode},
+        footer::{FooterContext, FooterGenerator, FooterOverride},
+        footnotes::{superscript, Footnote, Footnotes},
+        glossary::{Glossary, GlossaryEntry},
+        hooks::{CodeBlockHook, HookedCodeBlock, RenderHooks},
+        modals::{GlossaryModalBuilder, IndexBuilder},
+        redaction::Redactions,
         separator::RenderSeparator,
+        shortcodes::ShortcodeEmoji,
+        split::{self, BudgetIssue, BudgetWarning, SlideMeasurement, SlideSplitter, SplitPoint, SplitPosition},
+        structure::{StructureElement, StructureValidator},
+        table::{DimmedTableRow, RevealRowsMutator, RevealedRowsContext},
+        transform::{ApplyStyle, TextPipeline},
+    },
+    render::{
+        draw::RenderError,
+        gradient::BackgroundGradient,
+        highlighting::{CodeHighlighter, HighlightThemeSet},
     },
-    render::highlighting::{CodeHighlighter, HighlightThemeSet},
-    resource::{LoadImageError, Resources},
+    resource::{LoadImageError, ResolveError, Resources},
     style::{Color, Colors, TextStyle},
     theme::{
-        Alignment, AuthorPositioning, ElementType, LoadThemeError, Margin, PresentationTheme, PresentationThemeSet,
+        Alignment, AuthorPositioning, CodeBlockStyle, ElementType, LayoutRegion, LoadThemeError, Margin,
+        OrderedListMarker, PresentationTheme, PresentationThemeSet,
     },
     typst::{TypstRender, TypstRenderError},
 };
 use image::DynamicImage;
 use serde::Deserialize;
-use std::{borrow::Cow, cell::RefCell, fmt::Display, iter, mem, path::PathBuf, rc::Rc, str::FromStr};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt::Display,
+    io, iter, mem,
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 use unicode_width::UnicodeWidthStr;
 
 use super::modals::KeyBindingsModalBuilder;
@@ -38,6 +78,9 @@ use super::modals::KeyBindingsModalBuilder;
 static DEFAULT_BOTTOM_SLIDE_MARGIN: u16 = 3;
 static DEFAULT_Z_INDEX: i32 = -2;
 
+// How far the definitions in a description list are indented.
+const DEFINITION_INDENT: u16 = 4;
+
 #[derive(Default)]
 pub struct Themes {
     pub presentation: PresentationThemeSet,
@@ -54,10 +97,28 @@ pub struct PresentationBuilderOptions {
     pub end_slide_shorthand: bool,
     pub print_modal_background: bool,
     pub strict_front_matter_parsing: bool,
+    pub max_elements_per_render: Option<usize>,
+    pub reveal_style: RevealStyle,
+    pub glossary_case_sensitive: bool,
+    pub render_hooks: RenderHooks,
+    pub defer_media_loading: bool,
+    pub execution: ExecutionPolicy,
+    pub hide_footer: bool,
+    pub countdown_start: CountdownStart,
+    pub countdown_return: CountdownReturn,
+    pub countdown_bell: bool,
+    pub structure: StructureChecks,
+    pub warn_on_duplicate_titles: bool,
+    pub slide_budget: Option<SlideBudget>,
+    pub native_images: bool,
+    pub end_screen: Option<EndScreen>,
+    pub shortcode_emoji: bool,
+    pub artifacts: Option<ArtifactStore>,
+    pub reveal_redactions: bool,
 }
 
 impl PresentationBuilderOptions {
-    fn merge(&mut self, options: OptionsConfig) {
+    pub(crate) fn merge(&mut self, options: OptionsConfig) {
         self.implicit_slide_ends = options.implicit_slide_ends.unwrap_or(self.implicit_slide_ends);
         self.incremental_lists = options.incremental_lists.unwrap_or(self.incremental_lists);
         self.end_slide_shorthand = options.end_slide_shorthand.unwrap_or(self.end_slide_shorthand);
@@ -66,6 +127,25 @@ impl PresentationBuilderOptions {
         if let Some(prefix) = options.command_prefix {
             self.command_prefix = prefix;
         }
+        if let Some(max_elements) = options.max_elements_per_render {
+            self.max_elements_per_render = Some(max_elements);
+        }
+        if let Some(style) = options.reveal_style {
+            self.reveal_style = style;
+        }
+        self.glossary_case_sensitive = options.glossary_case_sensitive.unwrap_or(self.glossary_case_sensitive);
+        if let Some(start) = options.countdown_start {
+            self.countdown_start = start;
+        }
+        if let Some(on_return) = options.countdown_return {
+            self.countdown_return = on_return;
+        }
+        self.countdown_bell = options.countdown_bell.unwrap_or(self.countdown_bell);
+        self.structure = options.structure.unwrap_or(self.structure);
+        self.warn_on_duplicate_titles = options.warn_on_duplicate_titles.unwrap_or(self.warn_on_duplicate_titles);
+        if let Some(budget) = options.slide_budget {
+            self.slide_budget = Some(budget);
+        }
     }
 }
 
@@ -80,6 +160,24 @@ impl Default for PresentationBuilderOptions {
             end_slide_shorthand: false,
             print_modal_background: false,
             strict_front_matter_parsing: true,
+            max_elements_per_render: None,
+            reveal_style: RevealStyle::default(),
+            glossary_case_sensitive: true,
+            render_hooks: RenderHooks::default(),
+            defer_media_loading: false,
+            execution: ExecutionPolicy::default(),
+            hide_footer: false,
+            countdown_start: CountdownStart::default(),
+            countdown_return: CountdownReturn::default(),
+            countdown_bell: false,
+            structure: StructureChecks::default(),
+            warn_on_duplicate_titles: false,
+            slide_budget: None,
+            shortcode_emoji: false,
+            native_images: true,
+            end_screen: None,
+            artifacts: None,
+            reveal_redactions: false,
         }
     }
 }
@@ -94,6 +192,7 @@ pub(crate) struct PresentationBuilder<'a> {
     chunk_mutators: Vec<Box<dyn ChunkMutator>>,
     slides: Vec<Slide>,
     highlighter: CodeHighlighter,
+    default_theme: &'a PresentationTheme,
     theme: Cow<'a, PresentationTheme>,
     resources: &'a mut Resources,
     typst: &'a mut TypstRender,
@@ -101,9 +200,30 @@ pub(crate) struct PresentationBuilder<'a> {
     footer_context: Rc<RefCell<FooterContext>>,
     themes: &'a Themes,
     index_builder: IndexBuilder,
+    footnotes: Footnotes,
+    glossary: Rc<Glossary>,
+    redactions: Rc<Redactions>,
+    text_pipeline: TextPipeline,
+    title_pipeline: Option<TextPipeline>,
+    glossary_modal_builder: GlossaryModalBuilder,
     image_registry: ImageRegistry,
     bindings_config: KeyBindingsConfig,
     options: PresentationBuilderOptions,
+    title: Option<String>,
+    // The index of the slide every TODO note is in, along with the note's line and text.
+    todos: Vec<(usize, usize, String)>,
+    theme_overrides: Option<PresentationTheme>,
+    prelude_length: usize,
+    // Only set when measuring slides to fit them in the slide budget.
+    measurements: Option<Vec<SlideMeasurement>>,
+    slide_measurement: SlideMeasurement,
+    current_element: usize,
+    // The index of the slide every problem found when fitting slides in the budget refers to.
+    budget_warnings: Vec<(usize, BudgetIssue)>,
+    structure: StructureValidator,
+    directive_warnings: Vec<DirectiveWarning>,
+    // The number the next ordered list picks up from when it's continued using `list_continue`.
+    next_list_number: Option<usize>,
 }
 
 impl<'a> PresentationBuilder<'a> {
@@ -124,6 +244,7 @@ impl<'a> PresentationBuilder<'a> {
             chunk_mutators: Vec::new(),
             slides: Vec::new(),
             highlighter: CodeHighlighter::default(),
+            default_theme,
             theme: Cow::Borrowed(default_theme),
             resources,
             typst,
@@ -131,55 +252,226 @@ impl<'a> PresentationBuilder<'a> {
             footer_context: Default::default(),
             themes,
             index_builder: Default::default(),
+            footnotes: Default::default(),
+            glossary: Default::default(),
+            redactions: Default::default(),
+            text_pipeline: Default::default(),
+            title_pipeline: None,
+            glossary_modal_builder: Default::default(),
             image_registry,
             bindings_config,
             options,
+            title: None,
+            todos: Vec::new(),
+            theme_overrides: None,
+            prelude_length: 0,
+            measurements: None,
+            slide_measurement: Default::default(),
+            current_element: 0,
+            budget_warnings: Vec::new(),
+            structure: Default::default(),
+            directive_warnings: Vec::new(),
+            next_list_number: None,
         }
     }
 
     /// Build a presentation.
     pub(crate) fn build(mut self, elements: Vec<MarkdownElement>) -> Result<Presentation, BuildError> {
+        self.build_slides(elements)?;
+        let end_slide = self.options.end_screen.clone().map(|end_screen| self.build_end_slide(end_screen));
+        let numbering = SlideNumbering::new(self.slides.iter().map(Slide::is_numbered));
+        self.footer_context.borrow_mut().total_slides = numbering.total();
+
+        let mut bindings_modal_builder = KeyBindingsModalBuilder::default();
+        if self.options.print_modal_background {
+            let background = self.build_modal_background()?;
+            self.index_builder.set_background(background.clone());
+            self.glossary_modal_builder.set_background(background.clone());
+            bindings_modal_builder.set_background(background);
+        };
+
+        let presentation_state = PresentationState::default();
+        let slide_index = self.index_builder.build(&self.theme, presentation_state.clone(), &numbering);
+        let bindings = bindings_modal_builder.build(&self.theme, &self.bindings_config);
+        let glossary = self.glossary_modal_builder.build(&self.theme, presentation_state.clone());
+        let prompt_colors = self.theme.modals.prompt_colors.merge(&self.theme.default_style.colors);
+        let error_colors = self.theme.modals.error_colors();
+        let modals = Modals { slide_index, bindings, glossary, prompt_colors, error_colors };
+        let todos = self
+            .todos
+            .into_iter()
+            .map(|(index, line, text)| TodoNote { slide: numbering.reference(index), line, text })
+            .collect();
+        let budget_warnings = self
+            .budget_warnings
+            .into_iter()
+            .map(|(index, issue)| BudgetWarning { slide: numbering.reference(index), issue })
+            .collect();
+        let presentation = Presentation::new(self.slides, modals, presentation_state)
+            .with_title(self.title)
+            .with_todos(todos)
+            .with_budget_warnings(budget_warnings)
+            .with_structure_warnings(self.structure.finish(&numbering))
+            .with_directive_warnings(self.directive_warnings)
+            .with_end_slide(end_slide);
+        Ok(presentation)
+    }
+
+    fn build_slides(&mut self, mut elements: Vec<MarkdownElement>) -> Result<(), BuildError> {
         let mut skip_first = false;
         if let Some(MarkdownElement::FrontMatter(contents)) = elements.first() {
             self.process_front_matter(contents)?;
             skip_first = true;
         }
-        let mut elements = elements.into_iter();
-        if skip_first {
-            elements.next();
+        if let (Some(budget), None) = (self.options.slide_budget, &self.measurements) {
+            elements = self.fit_slide_budget(budget, elements)?;
         }
 
         self.set_code_theme()?;
+        self.structure = StructureValidator::new(&self.options);
+        self.build_text_pipelines();
 
         if self.chunk_operations.is_empty() {
             self.push_slide_prelude();
         }
-        for element in elements {
+        for (index, element) in elements.into_iter().enumerate().skip(skip_first as usize) {
+            self.current_element = index;
+            if self.should_truncate(&element) {
+                self.slide_state.truncated_elements += 1;
+                continue;
+            }
+            if !matches!(element, MarkdownElement::Comment { .. }) {
+                self.slide_state.rendered_elements += 1;
+            }
+            if Self::is_split_boundary(&element) {
+                self.record_split_point(SplitPosition::Element(index));
+            }
             self.slide_state.ignore_element_line_break = false;
+            let structure = StructureElement::new(&element, &self.options);
             self.process_element(element)?;
+            // Elements can end the slide they're in, so they're checked against the slide they ended up in.
+            self.structure.push(self.slides.len(), structure);
             self.validate_last_operation()?;
             if !self.slide_state.ignore_element_line_break {
                 self.push_line_break();
             }
+            if self.slide_measurement.heading == Some(index) {
+                self.slide_measurement.header_operations = self.slide_operations();
+            }
         }
         if !self.chunk_operations.is_empty() || !self.slide_chunks.is_empty() {
-            self.terminate_slide();
+            self.terminate_slide()?;
         }
-        self.footer_context.borrow_mut().total_slides = self.slides.len();
+        Ok(())
+    }
 
-        let mut bindings_modal_builder = KeyBindingsModalBuilder::default();
-        if self.options.print_modal_background {
-            let background = self.build_modal_background()?;
-            self.index_builder.set_background(background.clone());
-            bindings_modal_builder.set_background(background);
+    // Builds the presentation once to find the slides that go over the slide budget, and returns
+    // the elements with those slides split up if the budget allows it.
+    fn fit_slide_budget(
+        &mut self,
+        budget: SlideBudget,
+        elements: Vec<MarkdownElement>,
+    ) -> Result<Vec<MarkdownElement>, BuildError> {
+        // Images are loaded right away so they take up as much space as they will when displayed.
+        let options = PresentationBuilderOptions { defer_media_loading: false, ..self.options.clone() };
+        let mut builder = PresentationBuilder::new(
+            self.default_theme,
+            self.resources,
+            self.typst,
+            self.themes,
+            self.image_registry.clone(),
+            self.bindings_config.clone(),
+            options,
+        );
+        builder.measurements = Some(Vec::new());
+        builder.build_slides(elements.clone())?;
+        let measurements = builder.measurements.take().unwrap_or_default();
+        let plan = SlideSplitter::new(budget).plan(&builder.slides, &measurements)?;
+        self.budget_warnings = plan.warnings;
+        Ok(split::apply_splits(elements, &plan.splits, &self.options.command_prefix))
+    }
+
+    fn is_split_boundary(element: &MarkdownElement) -> bool {
+        // Lists are also split in between their items so they record their own split points.
+        matches!(
+            element,
+            MarkdownElement::Heading { .. }
+                | MarkdownElement::Paragraph(_)
+                | MarkdownElement::DescriptionList(_)
+                | MarkdownElement::Code(_)
+                | MarkdownElement::Table(_)
+                | MarkdownElement::BlockQuote(_)
+                | MarkdownElement::Image { .. }
+        )
+    }
+
+    // Records that the current slide can be split right here.
+    fn record_split_point(&mut self, position: SplitPosition) {
+        if self.measurements.is_none()
+            || !matches!(self.slide_state.layout, LayoutState::Default)
+            || self.slide_state.layout_template.is_some()
+            || self.slide_state.open_details.is_some()
+            || self.slide_state.truncated_elements > 0
+        {
+            return;
+        }
+        let point = SplitPoint {
+            position,
+            operations: self.slide_operations(),
+            step: self.current_step(),
+            incremental_lists: self.slide_state.incremental_lists,
         };
+        self.slide_measurement.points.push(point);
+    }
 
-        let presentation_state = PresentationState::default();
-        let slide_index = self.index_builder.build(&self.theme, presentation_state.clone());
-        let bindings = bindings_modal_builder.build(&self.theme, &self.bindings_config);
-        let modals = Modals { slide_index, bindings };
-        let presentation = Presentation::new(self.slides, modals, presentation_state);
-        Ok(presentation)
+    // Records that the current element is the heading the slide starts with, if it is.
+    fn record_slide_heading(&mut self) {
+        if self.measurements.is_some()
+            && self.slide_measurement.heading.is_none()
+            && self.slide_operations() == self.slide_measurement.header_operations
+        {
+            self.slide_measurement.heading = Some(self.current_element);
+        }
+    }
+
+    // The number of operations in the slide so far.
+    fn slide_operations(&self) -> usize {
+        let chunks: usize = self.slide_chunks.iter().map(|chunk| chunk.iter_operations().count()).sum();
+        chunks + self.chunk_operations.len()
+    }
+
+    // The index of the step the slide's last operation is shown in.
+    fn current_step(&self) -> usize {
+        let mutations = |mutators: &[Box<dyn ChunkMutator>]| -> usize {
+            mutators.iter().map(|mutator| mutator.mutations().1.saturating_sub(1)).sum()
+        };
+        let previous: usize = self.slide_chunks.iter().map(|chunk| 1 + mutations(chunk.mutators())).sum();
+        previous + mutations(&self.chunk_mutators)
+    }
+
+    fn build_end_slide(&mut self, end_screen: EndScreen) -> Slide {
+        // The last slide was just terminated so only the prelude for the next one is in here.
+        let styles = self.theme.intro_slide.clone();
+        let message = Text::new(end_screen.message, TextStyle::default().bold().colors(styles.title.colors));
+        self.push_vertical_center();
+        self.push_text(message.into(), ElementType::PresentationTitle);
+        self.push_line_break();
+        if let Some(title) = self.title.clone() {
+            let title = Text::new(title, TextStyle::default().colors(styles.subtitle.colors));
+            self.push_text(title.into(), ElementType::PresentationSubTitle);
+            self.push_line_break();
+        }
+        self.push_line_break();
+        let elapsed = ElapsedTime {
+            started_at: end_screen.started_at,
+            style: TextStyle::default().colors(styles.author.colors),
+            alignment: self.theme.alignment(&ElementType::PresentationAuthor),
+        };
+        self.chunk_operations.push(RenderOperation::RenderDynamic(Rc::new(elapsed)));
+        self.end_vertical_center();
+        let chunks = vec![SlideChunk::new(mem::take(&mut self.chunk_operations), Vec::new())];
+        // This isn't a real slide so it doesn't get a number, a footer, nor an entry in the slide index.
+        SlideBuilder::default().chunks(chunks).numbered(false).build()
     }
 
     fn build_modal_background(&self) -> Result<Image, RegisterImageError> {
@@ -203,6 +495,20 @@ impl<'a> PresentationBuilder<'a> {
         Ok(image)
     }
 
+    fn should_truncate(&self, element: &MarkdownElement) -> bool {
+        let Some(max_elements) = self.options.max_elements_per_render else {
+            return false;
+        };
+        // Anything that can end a slide must always be processed.
+        let ends_slide = match element {
+            MarkdownElement::Comment { .. } | MarkdownElement::FencedDiv(_) => true,
+            MarkdownElement::SetexHeading { .. } => self.options.implicit_slide_ends,
+            MarkdownElement::ThematicBreak { .. } => self.options.end_slide_shorthand,
+            _ => false,
+        };
+        !ends_slide && self.slide_state.rendered_elements >= max_elements
+    }
+
     fn validate_last_operation(&mut self) -> Result<(), BuildError> {
         if !self.slide_state.needs_enter_column {
             return Ok(());
@@ -219,38 +525,180 @@ impl<'a> PresentationBuilder<'a> {
     }
 
     fn push_slide_prelude(&mut self) {
-        let colors = self.theme.default_style.colors.clone();
-        self.chunk_operations.extend([
-            RenderOperation::SetColors(colors),
-            RenderOperation::ClearScreen,
-            RenderOperation::ApplyMargin(MarginProperties {
-                horizontal_margin: self.theme.default_style.margin.clone().unwrap_or_default(),
-                bottom_slide_margin: DEFAULT_BOTTOM_SLIDE_MARGIN,
-            }),
-        ]);
+        let mut colors = self.theme.default_style.colors.clone();
+        let mut gradient = None;
+        if let Some((top, bottom)) = self.theme.background_gradient {
+            // Only RGB colors can be interpolated so use a solid background otherwise.
+            gradient = BackgroundGradient::new(top, bottom);
+            if gradient.is_none() {
+                colors.background = Some(top);
+            }
+        }
+        self.chunk_operations.extend([RenderOperation::SetColors(colors), RenderOperation::ClearScreen]);
+        if let Some(gradient) = gradient {
+            self.chunk_operations.push(RenderOperation::RenderBackgroundGradient(gradient));
+        }
+        self.chunk_operations.extend([RenderOperation::ApplyMargin(MarginProperties {
+            horizontal_margin: self.theme.default_style.margin.clone().unwrap_or_default(),
+            bottom_slide_margin: DEFAULT_BOTTOM_SLIDE_MARGIN,
+        })]);
         self.push_line_break();
+        self.prelude_length = self.chunk_operations.len();
+        self.slide_measurement = SlideMeasurement { header_operations: self.prelude_length, ..Default::default() };
+    }
+
+    fn build_text_pipelines(&mut self) {
+        self.text_pipeline = self.build_text_pipeline(self.redactions.clone());
+        // Titles are used outside of the presentation, like in the event stream, so redactions are never revealed in
+        // them.
+        if self.options.reveal_redactions && !self.redactions.is_empty() {
+            self.title_pipeline = Some(self.build_text_pipeline(Rc::new(self.redactions.concealed())));
+        }
+    }
+
+    fn build_text_pipeline(&self, redactions: Rc<Redactions>) -> TextPipeline {
+        let mut pipeline = TextPipeline::default();
+        if self.options.shortcode_emoji {
+            pipeline.push(Rc::new(ShortcodeEmoji));
+        }
+        // This runs after emoji are replaced so redacted text takes up as many columns as it would otherwise.
+        if !redactions.is_empty() {
+            pipeline.push(redactions);
+        }
+        pipeline.push(self.glossary.clone());
+        pipeline
     }
 
-    fn process_element(&mut self, element: MarkdownElement) -> Result<(), BuildError> {
-        let should_clear_last = !matches!(element, MarkdownElement::List(_) | MarkdownElement::Comment { .. });
+    // Get the text in a heading after running it through the given pipeline.
+    fn plain_heading(pipeline: &TextPipeline, element: &MarkdownElement) -> Option<String> {
+        if !matches!(element, MarkdownElement::SetexHeading { .. } | MarkdownElement::Heading { .. }) {
+            return None;
+        }
+        let mut element = element.clone();
+        pipeline.apply(&mut element);
+        match element {
+            MarkdownElement::SetexHeading { text, .. } | MarkdownElement::Heading { text, .. } => Some(text.to_plain()),
+            _ => None,
+        }
+    }
+
+    fn process_element(&mut self, mut element: MarkdownElement) -> Result<(), BuildError> {
+        let footnotes = self.footnotes.extract_element(&mut element);
+        self.slide_state.footnotes.extend(footnotes);
+        if let Some(pipeline) = &self.title_pipeline {
+            self.slide_state.plain_heading = Self::plain_heading(pipeline, &element);
+        }
+        let mut pipeline = self.text_pipeline.clone();
+        if !matches!(element, MarkdownElement::Comment { .. }) && mem::take(&mut self.slide_state.inverse) {
+            pipeline.push(Rc::new(ApplyStyle(TextStyle::default().inverse())));
+        }
+        pipeline.apply(&mut element);
+        match &element {
+            MarkdownElement::Code(code) => self.slide_state.code_char_count += code.contents.chars().count(),
+            other => {
+                self.slide_state.char_count += other.char_count();
+                self.slide_state.word_count += other.word_count();
+            }
+        };
+        let should_clear_last = !matches!(
+            element,
+            MarkdownElement::List(_)
+                | MarkdownElement::Comment { .. }
+                | MarkdownElement::FencedDiv(_)
+                | MarkdownElement::Details(_)
+        );
+        let is_region_content = self.is_region_content(&element);
+        if is_region_content {
+            self.enter_next_layout_region();
+        }
         match element {
             // This one is processed before everything else as it affects how the rest of the
             // elements is rendered.
             MarkdownElement::FrontMatter(_) => self.slide_state.ignore_element_line_break = true,
-            MarkdownElement::SetexHeading { text } => self.push_slide_title(text),
-            MarkdownElement::Heading { level, text } => self.push_heading(level, text),
+            MarkdownElement::SetexHeading { text, .. } => self.push_slide_title(text)?,
+            MarkdownElement::Heading { level, text, .. } => self.push_heading(level, text),
             MarkdownElement::Paragraph(elements) => self.push_paragraph(elements)?,
             MarkdownElement::List(elements) => self.push_list(elements),
+            MarkdownElement::DescriptionList(items) => self.push_description_list(items),
             MarkdownElement::Code(code) => self.push_code(code)?,
             MarkdownElement::Table(table) => self.push_table(table),
-            MarkdownElement::ThematicBreak => self.process_thematic_break(),
+            MarkdownElement::ThematicBreak { .. } => self.process_thematic_break()?,
             MarkdownElement::Comment { comment, source_position } => self.process_comment(comment, source_position)?,
-            MarkdownElement::BlockQuote(lines) => self.push_block_quote(lines),
+            MarkdownElement::BlockQuote(lines) => self.push_block_quote(lines, &pipeline),
             MarkdownElement::Image { path, .. } => self.push_image_from_path(path)?,
+            MarkdownElement::FencedDiv(div) => self.process_fenced_div(div)?,
+            MarkdownElement::Details(tag) => self.process_details_tag(tag, &pipeline)?,
         };
+        self.collect_glossary_entries();
         if should_clear_last {
             self.slide_state.last_element = LastElement::Other;
         }
+        if is_region_content {
+            if let (Some(template), LayoutState::InColumn { column, .. }) =
+                (&mut self.slide_state.layout_template, &self.slide_state.layout)
+            {
+                template.filled[*column] = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn collect_glossary_entries(&mut self) {
+        for entry in self.glossary.take_found() {
+            if !self.slide_state.glossary_entries.contains(&entry) {
+                self.slide_state.glossary_entries.push(entry);
+            }
+        }
+    }
+
+    fn is_region_content(&self, element: &MarkdownElement) -> bool {
+        match element {
+            MarkdownElement::FrontMatter(_)
+            | MarkdownElement::SetexHeading { .. }
+            | MarkdownElement::Comment { .. }
+            | MarkdownElement::FencedDiv(_)
+            | MarkdownElement::Details(DetailsTag::End) => false,
+            MarkdownElement::ThematicBreak { .. } => !self.options.end_slide_shorthand,
+            _ => true,
+        }
+    }
+
+    fn enter_next_layout_region(&mut self) {
+        // Elements are only assigned to regions in document order if no region was selected explicitly.
+        let Some(template) = &self.slide_state.layout_template else {
+            return;
+        };
+        if template.explicit_regions {
+            return;
+        }
+        // Every element goes into the next required region and whatever's left over goes into the last one. Optional
+        // regions are only ever filled when they're selected explicitly.
+        let (current, columns_count) = match self.slide_state.layout {
+            LayoutState::InLayout { columns_count } => (None, columns_count),
+            LayoutState::InColumn { column, columns_count } => (Some(column), columns_count),
+            LayoutState::Default => return,
+        };
+        let start = current.map(|column| column + 1).unwrap_or(0);
+        let Some(column) = (start..columns_count).find(|index| template.regions[*index].required) else {
+            return;
+        };
+        self.slide_state.layout = LayoutState::InColumn { column, columns_count };
+        self.chunk_operations.push(RenderOperation::EnterColumn { column });
+    }
+
+    fn validate_layout_regions(&self) -> Result<(), BuildError> {
+        let Some(template) = &self.slide_state.layout_template else {
+            return Ok(());
+        };
+        for (region, filled) in template.regions.iter().zip(&template.filled) {
+            if region.required && !filled {
+                return Err(BuildError::UnfilledRegion {
+                    layout: template.name.clone(),
+                    region: region.name.clone(),
+                    slide: self.slides.len() + 1,
+                });
+            }
+        }
         Ok(())
     }
 
@@ -267,15 +715,24 @@ impl<'a> PresentationBuilder<'a> {
         if let Some(options) = metadata.options.take() {
             self.options.merge(options);
         }
-        self.footer_context.borrow_mut().author = metadata.author.clone().unwrap_or_default();
+        let glossary = Glossary::new(mem::take(&mut metadata.glossary), self.options.glossary_case_sensitive);
+        self.glossary = Rc::new(glossary);
+        let redactions = Redactions::new(&metadata.redact, self.options.reveal_redactions)
+            .map_err(|e| BuildError::InvalidMetadata(e.to_string()))?;
+        self.redactions = Rc::new(redactions);
+        self.footer_context.borrow_mut().author =
+            self.redactions.redact_text(metadata.author.as_deref().unwrap_or_default());
+        self.title = metadata.title.as_deref().map(|title| self.redactions.conceal(title));
         self.set_theme(&metadata.theme)?;
+        self.theme_overrides = metadata.theme.overrides.clone();
         if metadata.title.is_some()
             || metadata.sub_title.is_some()
             || metadata.author.is_some()
             || !metadata.authors.is_empty()
         {
+            self.build_text_pipelines();
             self.push_slide_prelude();
-            self.push_intro_slide(metadata);
+            self.push_intro_slide(metadata)?;
         }
         Ok(())
     }
@@ -296,6 +753,7 @@ impl<'a> PresentationBuilder<'a> {
                 self.theme = Cow::Owned(theme);
             }
             if let Some(theme_path) = &metadata.path {
+                let theme_path = self.resolve_path(theme_path)?;
                 let theme = self.resources.theme(theme_path)?;
                 self.theme = Cow::Owned(theme);
             }
@@ -309,6 +767,30 @@ impl<'a> PresentationBuilder<'a> {
         Ok(())
     }
 
+    // Switches to another theme starting with the current slide, which can't have any content yet.
+    fn switch_theme(&mut self, name: String) -> Result<(), BuildError> {
+        let slide = self.slides.len() + 1;
+        if !self.slide_chunks.is_empty() || self.chunk_operations.len() > self.prelude_length {
+            return Err(BuildError::ThemeAfterContent { slide });
+        }
+        if self.themes.presentation.load_by_name(&name).is_none() {
+            return Err(BuildError::UnknownTheme { name, slide });
+        }
+        if self.options.force_default_theme {
+            return Ok(());
+        }
+        // Overrides in the front matter apply on top of any theme that's switched to.
+        let overrides = self.theme_overrides.clone();
+        let metadata = PresentationThemeMetadata { name: Some(name), path: None, overrides };
+        self.set_theme(&metadata)?;
+        self.highlighter = CodeHighlighter::default();
+        self.set_code_theme()?;
+        // The slide's background was already set up using the previous theme.
+        self.chunk_operations.clear();
+        self.push_slide_prelude();
+        Ok(())
+    }
+
     fn set_code_theme(&mut self) -> Result<(), BuildError> {
         if let Some(theme) = &self.theme.code.theme_name {
             let highlighter =
@@ -318,30 +800,40 @@ impl<'a> PresentationBuilder<'a> {
         Ok(())
     }
 
-    fn push_intro_slide(&mut self, metadata: PresentationMetadata) {
+    fn push_intro_slide(&mut self, metadata: PresentationMetadata) -> Result<(), BuildError> {
         let styles = &self.theme.intro_slide;
-        let title = Text::new(
-            metadata.title.unwrap_or_default().clone(),
+        let pipeline = &self.text_pipeline;
+        let build_text = |text: String, style: TextStyle| {
+            let mut text = TextBlock::from(Text::new(text, style));
+            pipeline.apply_text(&mut text);
+            text
+        };
+        let title = build_text(
+            metadata.title.unwrap_or_default(),
             TextStyle::default().bold().colors(styles.title.colors.clone()),
         );
         let sub_title = metadata
             .sub_title
-            .as_ref()
-            .map(|text| Text::new(text.clone(), TextStyle::default().colors(styles.subtitle.colors.clone())));
+            .map(|text| build_text(text, TextStyle::default().colors(styles.subtitle.colors.clone())));
         let authors: Vec<_> = metadata
             .author
             .into_iter()
             .chain(metadata.authors)
-            .map(|author| Text::new(author, TextStyle::default().colors(styles.author.colors.clone())))
+            .map(|author| build_text(author, TextStyle::default().colors(styles.author.colors.clone())))
             .collect();
         if styles.footer == Some(false) {
             self.slide_state.ignore_footer = true;
         }
-        self.chunk_operations.push(RenderOperation::JumpToVerticalCenter);
-        self.push_text(TextBlock::from(title), ElementType::PresentationTitle);
+        let big_title = styles.title.big_text;
+        self.push_vertical_center();
+        if big_title {
+            self.push_big_text(title, ElementType::PresentationTitle);
+        } else {
+            self.push_text(title, ElementType::PresentationTitle);
+        }
         self.push_line_break();
         if let Some(text) = sub_title {
-            self.push_text(TextBlock::from(text), ElementType::PresentationSubTitle);
+            self.push_text(text, ElementType::PresentationSubTitle);
             self.push_line_break();
         }
         if !authors.is_empty() {
@@ -356,34 +848,104 @@ impl<'a> PresentationBuilder<'a> {
                 }
             };
             for author in authors {
-                self.push_text(TextBlock::from(author), ElementType::PresentationAuthor);
+                self.push_text(author, ElementType::PresentationAuthor);
                 self.push_line_break();
             }
         }
-        self.slide_state.title = Some(TextBlock::from("[Introduction]"));
-        self.terminate_slide();
+        self.collect_glossary_entries();
+        self.slide_state.title = Some((TextBlock::from("[Introduction]"), "[Introduction]".into()));
+        self.terminate_slide()
     }
 
     fn process_comment(&mut self, comment: String, source_position: SourcePosition) -> Result<(), BuildError> {
-        let comment = comment.trim();
-        if self.should_ignore_comment(comment) {
-            return Ok(());
+        if let Some((old, new)) = Self::command_text(&comment, &self.options).and_then(find_deprecated) {
+            let line = source_position.start.line;
+            self.directive_warnings.push(DirectiveWarning::DeprecatedDirective {
+                old_syntax: old.into(),
+                new_syntax: new.into(),
+                line,
+            });
+        }
+        let comment = match Self::parse_command(&comment, &self.options) {
+            Some(Ok(comment)) => comment,
+            Some(Err(error)) => return Err(BuildError::CommandParse { line: source_position.start.line + 1, error }),
+            None => return Ok(()),
+        };
+        let line = source_position.start.line + 1;
+        match &comment {
+            CommentCommand::Todo(text) => {
+                self.todos.push((self.slides.len(), line, text.clone()));
+            }
+            CommentCommand::ColumnBreak => self.process_column_break(line)?,
+            _ => (),
+        };
+        self.process_command(comment)
+    }
+
+    fn process_column_break(&mut self, line: usize) -> Result<(), BuildError> {
+        let slide = self.slides.len() + 1;
+        let LayoutState::InColumn { column, columns_count } = self.slide_state.layout else {
+            return Err(BuildError::ColumnBreakOutsideColumn { slide, line });
+        };
+        let column = column + 1;
+        if column >= columns_count {
+            return Err(BuildError::ColumnBreakAfterLastColumn { slide, line });
         }
-        let comment = comment.trim_start_matches(&self.options.command_prefix);
-        let comment = match comment.parse::<CommentCommand>() {
-            Ok(comment) => comment,
-            Err(error) => return Err(BuildError::CommandParse { line: source_position.start.line + 1, error }),
+        self.slide_state.layout = LayoutState::InColumn { column, columns_count };
+        self.chunk_operations.push(RenderOperation::EnterColumn { column });
+        Ok(())
+    }
+
+    fn process_fenced_div(&mut self, div: FencedDiv) -> Result<(), BuildError> {
+        let command = match div {
+            FencedDiv::Columns(columns) => CommentCommand::InitColumnLayout(columns),
+            FencedDiv::Column(column) => CommentCommand::Column(column),
+            FencedDiv::EndColumns => CommentCommand::ResetLayout,
+        };
+        self.process_command(command)
+    }
+
+    fn process_details_tag(&mut self, tag: DetailsTag, pipeline: &TextPipeline) -> Result<(), BuildError> {
+        match tag {
+            DetailsTag::Start { summary, open } => {
+                if self.slide_state.open_details.is_some() {
+                    return Err(BuildError::NestedDetails);
+                }
+                let mut summary = TextBlock::from(summary);
+                pipeline.apply_text(&mut summary);
+                // Everything up to the end tag is wrapped into the block once it's found.
+                let start = self.chunk_operations.len();
+                let chunk = self.slide_chunks.len();
+                self.slide_state.open_details = Some(OpenDetails { summary, open, start, chunk });
+                self.slide_state.ignore_element_line_break = true;
+            }
+            DetailsTag::End => {
+                let OpenDetails { summary, open, start, chunk } =
+                    self.slide_state.open_details.take().ok_or(BuildError::UnmatchedDetailsEnd)?;
+                if chunk != self.slide_chunks.len() {
+                    return Err(BuildError::PauseInDetails);
+                }
+                let body = self.chunk_operations.split_off(start);
+                let alignment = self.theme.alignment(&ElementType::Paragraph);
+                let details = Rc::new(CollapsibleDetails::new(chunk, summary, alignment, body, open));
+                self.chunk_operations.push(RenderOperation::RenderDynamic(details.clone()));
+                self.slide_state.details.push(details);
+            }
         };
-        match comment {
-            CommentCommand::Pause => self.process_pause(),
-            CommentCommand::EndSlide => self.terminate_slide(),
+        Ok(())
+    }
+
+    fn process_command(&mut self, command: CommentCommand) -> Result<(), BuildError> {
+        match command {
+            CommentCommand::Pause => self.process_pause(StepKind::Pause),
+            CommentCommand::EndSlide => self.terminate_slide()?,
             CommentCommand::NewLine => self.push_line_break(),
             CommentCommand::NewLines(count) => {
                 for _ in 0..count {
                     self.push_line_break();
                 }
             }
-            CommentCommand::JumpToMiddle => self.chunk_operations.push(RenderOperation::JumpToVerticalCenter),
+            CommentCommand::JumpToMiddle => self.push_vertical_center(),
             CommentCommand::InitColumnLayout(columns) => {
                 Self::validate_column_layout(&columns)?;
                 self.slide_state.layout = LayoutState::InLayout { columns_count: columns.len() };
@@ -391,7 +953,9 @@ impl<'a> PresentationBuilder<'a> {
                 self.slide_state.needs_enter_column = true;
             }
             CommentCommand::ResetLayout => {
+                self.validate_layout_regions()?;
                 self.slide_state.layout = LayoutState::Default;
+                self.slide_state.layout_template = None;
                 self.chunk_operations.extend([RenderOperation::ExitLayout, RenderOperation::RenderLineBreak]);
             }
             CommentCommand::Column(column) => {
@@ -408,20 +972,110 @@ impl<'a> PresentationBuilder<'a> {
                 self.slide_state.layout = LayoutState::InColumn { column, columns_count };
                 self.chunk_operations.push(RenderOperation::EnterColumn { column });
             }
+            CommentCommand::Layout(name) => {
+                let slide = self.slides.len() + 1;
+                let Some(layout) = self.theme.layout(&name) else {
+                    return Err(BuildError::UnknownLayout { name, slide });
+                };
+                let columns: Vec<_> = layout.regions.iter().map(|region| region.width).collect();
+                Self::validate_column_layout(&columns)?;
+                self.validate_layout_regions()?;
+                self.slide_state.layout = LayoutState::InLayout { columns_count: columns.len() };
+                self.slide_state.layout_template = Some(SlideLayoutState::new(name, layout.regions));
+                self.chunk_operations.push(RenderOperation::InitColumnLayout { columns });
+            }
+            CommentCommand::Region(region) => {
+                let slide = self.slides.len() + 1;
+                let Some(template) = &mut self.slide_state.layout_template else {
+                    return Err(BuildError::NoLayout);
+                };
+                let Some(column) = template.regions.iter().position(|r| r.name == region) else {
+                    return Err(BuildError::UnknownRegion { layout: template.name.clone(), region, slide });
+                };
+                template.explicit_regions = true;
+                let columns_count = template.regions.len();
+                if let LayoutState::InColumn { column: current, .. } = self.slide_state.layout {
+                    if current == column {
+                        return Err(BuildError::AlreadyInColumn);
+                    }
+                }
+                self.slide_state.layout = LayoutState::InColumn { column, columns_count };
+                self.chunk_operations.push(RenderOperation::EnterColumn { column });
+            }
             CommentCommand::IncrementalLists(value) => {
                 self.slide_state.incremental_lists = Some(value);
             }
             CommentCommand::NoFooter => {
                 self.slide_state.ignore_footer = true;
             }
+            CommentCommand::Unnumbered => {
+                self.slide_state.unnumbered = true;
+            }
+            CommentCommand::ListContinue => {
+                self.slide_state.continue_list = true;
+            }
+            CommentCommand::Footer(overrides) => {
+                self.slide_state.footer_override.merge(overrides);
+            }
+            CommentCommand::BigText => {
+                self.slide_state.big_text = true;
+            }
+            CommentCommand::Inverse => {
+                self.slide_state.inverse = true;
+            }
+            CommentCommand::IncrementalRows => {
+                self.slide_state.incremental_rows = true;
+            }
+            CommentCommand::Countdown(label) => {
+                let duration =
+                    countdown::parse_duration(&label).ok_or_else(|| BuildError::InvalidCountdown(label.clone()))?;
+                let operation = CountdownOperation::new(
+                    label,
+                    duration,
+                    self.theme.countdown.colors.clone(),
+                    self.theme.countdown.final_colors(),
+                    self.theme.alignment(&ElementType::Countdown),
+                    self.options.countdown_start.clone(),
+                    self.options.countdown_return.clone(),
+                    self.options.countdown_bell,
+                );
+                self.chunk_operations.push(RenderOperation::RenderOnDemand(Rc::new(operation)));
+            }
+            CommentCommand::MaxDuration(label) => {
+                let duration =
+                    countdown::parse_duration(&label).ok_or_else(|| BuildError::InvalidMaxDuration(label.clone()))?;
+                self.slide_state.max_duration = Some(duration);
+            }
+            CommentCommand::Theme(name) => self.switch_theme(name)?,
+            // These are only recorded, they're never rendered.
+            CommentCommand::Todo(_) => (),
+            // This needs to know the line it's in so it's handled when processing the comment.
+            CommentCommand::ColumnBreak => (),
         };
         // Don't push line breaks for any comments.
         self.slide_state.ignore_element_line_break = true;
         Ok(())
     }
 
-    fn should_ignore_comment(&self, comment: &str) -> bool {
-        if comment.contains('\n') || !comment.starts_with(&self.options.command_prefix) {
+    /// Parse a comment into a command, returning `None` if it's a regular comment.
+    pub(crate) fn parse_command(
+        comment: &str,
+        options: &PresentationBuilderOptions,
+    ) -> Option<Result<CommentCommand, CommandParseError>> {
+        Self::command_text(comment, options).map(str::parse::<CommentCommand>)
+    }
+
+    /// Get the text of the command in a comment, without its prefix, or `None` if it's a regular comment.
+    pub(crate) fn command_text<'c>(comment: &'c str, options: &PresentationBuilderOptions) -> Option<&'c str> {
+        let comment = comment.trim();
+        if Self::should_ignore_comment(comment, options) {
+            return None;
+        }
+        Some(comment.trim_start_matches(&options.command_prefix))
+    }
+
+    fn should_ignore_comment(comment: &str, options: &PresentationBuilderOptions) -> bool {
+        if comment.contains('\n') || !comment.starts_with(&options.command_prefix) {
             // Ignore any multi line comment; those are assumed to be user comments
             // Ignore any line that doesn't start with the selected prefix.
             true
@@ -442,22 +1096,48 @@ impl<'a> PresentationBuilder<'a> {
         }
     }
 
-    fn process_pause(&mut self) {
+    fn process_pause(&mut self, kind: StepKind) {
+        // There's nothing left to show after the slide has been truncated.
+        if self.slide_state.truncated_elements > 0 {
+            return;
+        }
         self.slide_state.last_chunk_ended_in_list = matches!(self.slide_state.last_element, LastElement::List { .. });
 
+        self.end_vertical_center();
         let chunk_operations = mem::take(&mut self.chunk_operations);
         let mutators = mem::take(&mut self.chunk_mutators);
         self.slide_chunks.push(SlideChunk::new(chunk_operations, mutators));
+        self.slide_state.step_kinds.push(kind);
     }
 
-    fn push_slide_title(&mut self, mut text: TextBlock) {
+    fn push_vertical_center(&mut self) {
+        self.end_vertical_center();
+        self.slide_state.vertical_center = Some(self.chunk_operations.len());
+        self.chunk_operations.push(RenderOperation::JumpToVerticalCenter { operations: None });
+    }
+
+    // Only what's in the same chunk is centered, otherwise the contents would move up every time a
+    // pause reveals more of them, and the footer would be centered along with them.
+    fn end_vertical_center(&mut self) {
+        let Some(index) = self.slide_state.vertical_center.take() else {
+            return;
+        };
+        let following = self.chunk_operations.len().saturating_sub(index + 1);
+        if let Some(RenderOperation::JumpToVerticalCenter { operations }) = self.chunk_operations.get_mut(index) {
+            *operations = Some(following);
+        }
+    }
+
+    fn push_slide_title(&mut self, mut text: TextBlock) -> Result<(), BuildError> {
         if self.options.implicit_slide_ends && !matches!(self.slide_state.last_element, LastElement::None) {
-            self.terminate_slide();
+            self.terminate_slide()?;
         }
 
         if self.slide_state.title.is_none() {
-            self.slide_state.title = Some(text.clone());
+            let plain_title = self.slide_state.plain_heading.take().unwrap_or_else(|| text.to_plain());
+            self.slide_state.title = Some((text.clone(), plain_title));
         }
+        self.record_slide_heading();
 
         let style = self.theme.slide_title.clone();
         let mut text_style = TextStyle::default().colors(style.colors.clone());
@@ -475,7 +1155,11 @@ impl<'a> PresentationBuilder<'a> {
         for _ in 0..style.padding_top.unwrap_or(0) {
             self.push_line_break();
         }
-        self.push_text(text, ElementType::SlideTitle);
+        if mem::take(&mut self.slide_state.big_text) {
+            self.push_big_text(text, ElementType::SlideTitle);
+        } else {
+            self.push_text(text, ElementType::SlideTitle);
+        }
         self.push_line_break();
 
         for _ in 0..style.padding_bottom.unwrap_or(0) {
@@ -486,9 +1170,15 @@ impl<'a> PresentationBuilder<'a> {
         }
         self.push_line_break();
         self.slide_state.ignore_element_line_break = true;
+        Ok(())
     }
 
     fn push_heading(&mut self, level: u8, mut text: TextBlock) {
+        self.record_slide_heading();
+        if self.slide_state.first_heading.is_none() {
+            self.slide_state.first_heading =
+                Some(self.slide_state.plain_heading.take().unwrap_or_else(|| text.to_plain()));
+        }
         let (element_type, style) = match level {
             1 => (ElementType::Heading1, &self.theme.headings.h1),
             2 => (ElementType::Heading2, &self.theme.headings.h2),
@@ -506,7 +1196,11 @@ impl<'a> PresentationBuilder<'a> {
         let text_style = TextStyle::default().bold().colors(style.colors.clone());
         text.apply_style(&text_style);
 
-        self.push_text(text, element_type);
+        if mem::take(&mut self.slide_state.big_text) {
+            self.push_big_text(text, element_type);
+        } else {
+            self.push_text(text, element_type);
+        }
         self.push_line_break();
     }
 
@@ -525,34 +1219,69 @@ impl<'a> PresentationBuilder<'a> {
         Ok(())
     }
 
-    fn process_thematic_break(&mut self) {
+    fn process_thematic_break(&mut self) -> Result<(), BuildError> {
         if self.options.end_slide_shorthand {
-            self.terminate_slide();
+            self.terminate_slide()?;
             self.slide_state.ignore_element_line_break = true;
         } else {
             self.chunk_operations.extend([RenderSeparator::default().into(), RenderOperation::RenderLineBreak]);
         }
+        Ok(())
+    }
+
+    // Resolves a path referenced in the presentation, making sure it's allowed when sandboxed.
+    fn resolve_path<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf, BuildError> {
+        let path = path.as_ref();
+        let slide = self.slides.len() + 1;
+        self.resources.resolve(path).map_err(|e| match e {
+            ResolveError::OutsideSandbox => BuildError::OutsideSandbox { path: path.into(), slide },
+            ResolveError::Io(error) => BuildError::ResolvePath { path: path.into(), slide, error },
+        })
     }
 
     fn push_image_from_path(&mut self, path: PathBuf) -> Result<(), BuildError> {
+        let path = self.resolve_path(path)?;
+        if self.options.defer_media_loading {
+            let image = self.resources.cached_image(&path);
+            let loader = self.resources.image_loader(&path)?;
+            self.push_deferred_image(path.display().to_string(), image, move || loader().map_err(|e| e.to_string()));
+            return Ok(());
+        }
         let image = self.resources.image(&path)?;
         self.push_image(image);
         Ok(())
     }
 
     fn push_image(&mut self, image: Image) {
-        let properties = ImageProperties {
-            z_index: DEFAULT_Z_INDEX,
-            size: Default::default(),
-            restore_cursor: false,
-            background_color: self.theme.default_style.colors.background,
-        };
+        let properties = self.image_properties();
         self.chunk_operations.extend([
             RenderOperation::RenderImage(image, properties),
             RenderOperation::SetColors(self.theme.default_style.colors.clone()),
         ]);
     }
 
+    fn push_deferred_image<F>(&mut self, source: String, image: Option<Image>, loader: F)
+    where
+        F: Fn() -> Result<Image, String> + Send + Sync + 'static,
+    {
+        let colors = self.theme.default_style.colors.clone();
+        let mut deferred = DeferredImage::new(source, self.image_properties(), colors, loader);
+        // Images that are already loaded, e.g. after a reload, are displayed right away.
+        if let Some(image) = image {
+            deferred = deferred.loaded(image);
+        }
+        self.chunk_operations.push(RenderOperation::RenderOnDemand(Rc::new(deferred)));
+    }
+
+    fn image_properties(&self) -> ImageProperties {
+        ImageProperties {
+            z_index: DEFAULT_Z_INDEX,
+            size: Default::default(),
+            restore_cursor: false,
+            background_color: self.theme.default_style.colors.background,
+        }
+    }
+
     fn push_list(&mut self, list: Vec<ListItem>) {
         let last_chunk_operation = self.slide_chunks.last().and_then(|chunk| chunk.iter_operations().last());
         // If the last chunk ended in a list, pop the newline so we get them all next to each
@@ -563,27 +1292,60 @@ impl<'a> PresentationBuilder<'a> {
         {
             self.slide_chunks.last_mut().unwrap().pop_last();
         }
-        // If this chunk just starts (because there was a pause), pick up from the last index.
-        let start_index = match self.slide_state.last_element {
-            LastElement::List { last_index } if self.chunk_operations.is_empty() => last_index + 1,
-            _ => 0,
+        // If this chunk just starts (because there was a pause), pick up from the last index and
+        // keep numbering items the same way as the list we're continuing.
+        let (start_index, continued_start) = match self.slide_state.last_element {
+            LastElement::List { last_index, start } if self.chunk_operations.is_empty() => {
+                (last_index + 1, Some(start))
+            }
+            // Otherwise this could be continuing the last ordered list in an earlier slide.
+            _ if mem::take(&mut self.slide_state.continue_list) => (0, self.next_list_number),
+            _ => (0, None),
         };
 
         let incremental_lists = self.slide_state.incremental_lists.unwrap_or(self.options.incremental_lists);
-        let iter = ListIterator::new(list, start_index);
-        for (index, item) in iter.enumerate() {
+        let items: Vec<_> = ListIterator::new(list, start_index)
+            .map(|item| {
+                let start = match continued_start {
+                    Some(start) if item.item.depth == 0 => start,
+                    _ => item.item.item_type.start(),
+                };
+                (start, item)
+            })
+            .collect();
+        // Markers are right aligned so the text in every item at the same depth starts at the
+        // same column, even once numbers take up more characters.
+        let mut marker_widths = BTreeMap::new();
+        for (start, item) in &items {
+            if let Some(marker) = self.ordered_list_marker(&item.item, start + item.index) {
+                let width = marker_widths.entry(item.item.depth).or_default();
+                *width = marker.width().max(*width);
+            }
+        }
+        for (index, (start, item)) in items.into_iter().enumerate() {
+            if item.item.depth == 0 {
+                let element = self.current_element;
+                self.record_split_point(SplitPosition::ListItem { element, item: index, number: start + item.index });
+            }
             if index > 0 && incremental_lists {
-                self.process_pause();
+                self.process_pause(StepKind::ListItem);
             }
-            self.push_list_item(item.index, item.item);
+            let marker_width = marker_widths.get(&item.item.depth).copied().unwrap_or_default();
+            self.push_list_item(item.index, start, item.item, marker_width);
         }
     }
 
-    fn push_list_item(&mut self, index: usize, item: ListItem) {
+    fn push_list_item(&mut self, index: usize, start: usize, item: ListItem, marker_width: usize) {
         let padding_length = (item.depth as usize + 1) * 3;
         let mut prefix: String = " ".repeat(padding_length);
-        match item.item_type {
-            ListItemType::Unordered => {
+        match self.ordered_list_marker(&item, start + index) {
+            Some(marker) => {
+                prefix.push_str(&format!("{marker:>marker_width$} "));
+                if item.depth == 0 {
+                    self.next_list_number = Some(start + index + 1);
+                }
+            }
+            None => {
                 let delimiter = match item.depth {
                     0 => '•',
                     1 => '◦',
@@ -591,30 +1353,58 @@ impl<'a> PresentationBuilder<'a> {
                 };
                 prefix.push(delimiter);
             }
-            ListItemType::OrderedParens => {
-                prefix.push_str(&(index + 1).to_string());
-                prefix.push_str(") ");
-            }
-            ListItemType::OrderedPeriod => {
-                prefix.push_str(&(index + 1).to_string());
-                prefix.push_str(". ");
-            }
         };
 
-        let prefix_length = prefix.len() as u16;
+        let prefix_length = prefix.width() as u16;
         self.push_text(prefix.into(), ElementType::List);
 
         let text = item.contents;
         self.push_aligned_text(text, Alignment::Left { margin: Margin::Fixed(prefix_length) });
         self.push_line_break();
         if item.depth == 0 {
-            self.slide_state.last_element = LastElement::List { last_index: index };
+            self.slide_state.last_element = LastElement::List { last_index: index, start };
+        }
+    }
+
+    // The marker for an ordered list item, which the theme can override for every depth.
+    fn ordered_list_marker(&self, item: &ListItem, number: usize) -> Option<String> {
+        let marker = match (&item.item_type, self.theme.list.ordered_marker(item.depth)) {
+            (ListItemType::Unordered, _) => return None,
+            (_, Some(marker)) => marker,
+            (ListItemType::OrderedParens { .. }, None) => OrderedListMarker::NumberParens,
+            (ListItemType::OrderedPeriod { .. }, None) => OrderedListMarker::NumberPeriod,
+        };
+        Some(marker.format(number))
+    }
+
+    fn push_description_list(&mut self, items: Vec<DescriptionItem>) {
+        for item in items {
+            let mut term = item.term;
+            term.apply_style(&TextStyle::default().bold());
+            self.push_text(term, ElementType::Paragraph);
+            self.push_line_break();
+            for definition in item.definitions {
+                self.push_aligned_text(definition, Alignment::Left { margin: Margin::Fixed(DEFINITION_INDENT) });
+                self.push_line_break();
+            }
         }
     }
 
-    fn push_block_quote(&mut self, lines: Vec<String>) {
+    fn push_block_quote(&mut self, lines: Vec<QuoteLine>, pipeline: &TextPipeline) {
+        let style = TextStyle::default().colors(self.theme.block_quote.colors.base.clone());
+        let lines: Vec<_> = lines
+            .into_iter()
+            .map(|line| {
+                let mut text = TextBlock::from(Text::new(line.text, style.clone()));
+                pipeline.apply_text(&mut text);
+                (text, line.depth)
+            })
+            .collect();
         let prefix = self.theme.block_quote.prefix.clone().unwrap_or_default();
-        let block_length = lines.iter().map(|line| line.width() + prefix.width()).max().unwrap_or(0) as u16;
+        // Nested quotes get one extra prefix for every level they're nested in.
+        let block_length =
+            lines.iter().map(|(text, depth)| text.width() + prefix.width() * (*depth as usize + 1)).max().unwrap_or(0)
+                as u16;
         let prefix_color = self.theme.block_quote.colors.prefix.or(self.theme.block_quote.colors.base.foreground);
         let prefix = Text::new(
             prefix,
@@ -622,10 +1412,11 @@ impl<'a> PresentationBuilder<'a> {
                 .colors(Colors { foreground: prefix_color, background: self.theme.block_quote.colors.base.background }),
         );
         let alignment = self.theme.alignment(&ElementType::BlockQuote).clone();
-        let style = TextStyle::default().colors(self.theme.block_quote.colors.base.clone());
 
-        for line in lines {
-            let line = TextBlock(vec![prefix.clone(), Text::new(line, style.clone())]);
+        for (text, depth) in lines {
+            let mut chunks = vec![prefix.clone(); depth as usize + 1];
+            chunks.extend(text.0);
+            let line = TextBlock(chunks);
             self.chunk_operations.extend([
                 // Print a preformatted empty block so we fill in the line with properly colored
                 // spaces.
@@ -650,11 +1441,7 @@ impl<'a> PresentationBuilder<'a> {
     }
 
     fn push_aligned_text(&mut self, mut block: TextBlock, alignment: Alignment) {
-        for chunk in &mut block.0 {
-            if chunk.style.is_code() {
-                chunk.style.colors = self.theme.inline_code.colors.clone();
-            }
-        }
+        self.style_inline_code(&mut block);
         if !block.0.is_empty() {
             self.chunk_operations.push(RenderOperation::RenderText {
                 line: WeightedTextBlock::from(block),
@@ -663,11 +1450,31 @@ impl<'a> PresentationBuilder<'a> {
         }
     }
 
+    fn push_big_text(&mut self, text: TextBlock, element_type: ElementType) {
+        let alignment = self.theme.alignment(&element_type);
+        let colors = text.0.first().map(|text| text.style.colors.clone()).unwrap_or_default();
+        let contents = text.0.iter().map(|text| text.content.as_str()).collect();
+        let fallback = WeightedTextBlock::from(text);
+        self.chunk_operations.push(BigText::new(contents, colors, alignment, fallback).into());
+    }
+
+    fn style_inline_code(&self, block: &mut TextBlock) {
+        for chunk in &mut block.0 {
+            if chunk.style.is_code() {
+                chunk.style.colors = self.theme.inline_code.colors.clone();
+            }
+        }
+    }
+
     fn push_line_break(&mut self) {
         self.chunk_operations.push(RenderOperation::RenderLineBreak);
     }
 
     fn push_code(&mut self, code: Code) -> Result<(), BuildError> {
+        if let Some(hook) = self.options.render_hooks.code_block(&code.language_name) {
+            self.push_hooked_code(hook, code);
+            return Ok(());
+        }
         if code.attributes.auto_render {
             return self.push_rendered_code(code);
         }
@@ -679,13 +1486,46 @@ impl<'a> PresentationBuilder<'a> {
         if self.options.allow_mutations && context.borrow().groups.len() > 1 {
             self.chunk_mutators.push(Box::new(HighlightMutator::new(context)));
         }
-        if code.attributes.execute {
+        self.push_focusable_code(&code);
+        // Running code could touch anything so it's never allowed when sandboxed.
+        if code.attributes.execute && !self.resources.is_sandboxed() {
             self.push_code_execution(code);
         }
         Ok(())
     }
 
+    fn push_hooked_code(&mut self, hook: Arc<dyn CodeBlockHook>, code: Code) {
+        let block = HookedCodeBlock::new(
+            hook,
+            code.language_name,
+            code.contents,
+            self.theme.default_style.colors.clone(),
+            self.theme.alignment(&ElementType::Code),
+            self.image_registry.clone(),
+            self.image_properties(),
+        );
+        self.chunk_operations.push(RenderOperation::RenderDynamic(Rc::new(block)));
+    }
+
     fn push_rendered_code(&mut self, code: Code) -> Result<(), BuildError> {
+        if code.language == CodeLanguage::Mermaid {
+            return self.push_mermaid(code);
+        }
+        if self.options.defer_media_loading {
+            let typst = self.typst.clone();
+            let style = self.theme.typst.clone();
+            let Code { contents, language, .. } = code;
+            let source = contents.clone();
+            self.push_deferred_image(source, None, move || {
+                let image = match language {
+                    CodeLanguage::Typst => typst.render_typst(&contents, &style),
+                    CodeLanguage::Latex => typst.render_latex(&contents, &style),
+                    _ => return Err(format!("language {language:?} can't be rendered")),
+                };
+                image.map_err(|e| e.to_string())
+            });
+            return Ok(());
+        }
         let image = match code.language {
             CodeLanguage::Typst => self.typst.render_typst(&code.contents, &self.theme.typst)?,
             CodeLanguage::Latex => self.typst.render_latex(&code.contents, &self.theme.typst)?,
@@ -695,42 +1535,68 @@ impl<'a> PresentationBuilder<'a> {
         Ok(())
     }
 
-    fn highlight_lines(&self, code: &Code) -> (Vec<HighlightedLine>, Rc<RefCell<HighlightContext>>) {
-        let lines = CodePreparer::new(&self.theme).prepare(code);
-        let block_length = lines.iter().map(|line| line.width()).max().unwrap_or(0);
-        let mut empty_highlighter = self.highlighter.language_highlighter(&CodeLanguage::Unknown);
-        let mut code_highlighter = self.highlighter.language_highlighter(&code.language);
-        let padding_style = {
-            let mut highlighter = self.highlighter.language_highlighter(&CodeLanguage::Rust);
-            highlighter.style_line("//").next().expect("no styles").style
+    fn push_mermaid(&mut self, code: Code) -> Result<(), BuildError> {
+        match MermaidBackend::select(&code.contents, self.options.native_images) {
+            MermaidBackend::Image if self.options.defer_media_loading => {
+                let renderer = MermaidRender::new(self.image_registry.clone(), self.options.artifacts.clone());
+                let contents = code.contents.clone();
+                self.push_deferred_image(code.contents, None, move || {
+                    renderer.render_image(&contents).map_err(|e| e.to_string())
+                });
+            }
+            MermaidBackend::Image => {
+                let renderer = MermaidRender::new(self.image_registry.clone(), self.options.artifacts.clone());
+                let image = renderer.render_image(&code.contents)?;
+                self.push_image(image);
+            }
+            MermaidBackend::Ascii => {
+                let contents = MermaidRender::render_ascii(&code.contents)?;
+                let attributes = CodeAttributes { auto_render: false, line_numbers: false, ..code.attributes };
+                let code = Code { contents, language: CodeLanguage::Unknown, attributes, ..code };
+                self.push_code(code)?;
+            }
+            MermaidBackend::Source => {
+                let attributes = CodeAttributes { auto_render: false, ..code.attributes };
+                self.push_code(Code { attributes, ..code })?;
+            }
         };
-        let groups = match self.options.allow_mutations {
-            true => code.attributes.highlight_groups.clone(),
-            false => vec![HighlightGroup::new(vec![Highlight::All])],
+        Ok(())
+    }
+
+    fn push_focusable_code(&mut self, code: &Code) {
+        // Most code blocks are never focused so their focused version is only highlighted when it's first needed.
+        let mut code = code.clone();
+        code.attributes.line_numbers = true;
+        let style = self.theme.code.clone();
+        let highlighter = self.highlighter.clone();
+        let redactions = self.redactions.clone();
+        let build_lines = move || {
+            // When focused every line is highlighted and the code gets more room than within the slide.
+            let alignment = Alignment::Center { minimum_margin: focus::HORIZONTAL_MARGIN, minimum_size: 0 };
+            let (lines, _) = highlight_lines(&code, &style, &highlighter, &redactions, false, alignment);
+            lines.into_iter().map(|line| RenderOperation::RenderDynamic(Rc::new(line))).collect()
         };
-        let context = Rc::new(RefCell::new(HighlightContext {
-            groups,
-            current: 0,
-            block_length,
-            alignment: self.theme.alignment(&ElementType::Code),
-        }));
+        let chunk = self.slide_chunks.len();
+        let colors = self.theme.default_style.colors.clone();
+        self.slide_state.focusable_code.push(FocusableCode::new(chunk, build_lines, colors));
+    }
 
-        let mut output = Vec::new();
-        let block_style = &self.theme.code;
-        for line in lines.into_iter() {
-            let highlighted = line.highlight(&padding_style, &mut code_highlighter, block_style);
-            let not_highlighted = line.highlight(&padding_style, &mut empty_highlighter, block_style);
-            let width = line.width();
-            let line_number = line.line_number;
-            let context = context.clone();
-            output.push(HighlightedLine { highlighted, not_highlighted, line_number, width, context });
-        }
-        (output, context)
+    fn highlight_lines(&self, code: &Code) -> (Vec<HighlightedLine>, Rc<RefCell<HighlightContext>>) {
+        let alignment = self.theme.alignment(&ElementType::Code);
+        highlight_lines(
+            code,
+            &self.theme.code,
+            &self.highlighter,
+            &self.redactions,
+            self.options.allow_mutations,
+            alignment,
+        )
     }
 
     fn push_code_execution(&mut self, code: Code) {
         let operation = RunCodeOperation::new(
             code,
+            self.options.execution.clone(),
             self.theme.default_style.colors.clone(),
             self.theme.execution_output.colors.clone(),
         );
@@ -738,37 +1604,103 @@ impl<'a> PresentationBuilder<'a> {
         self.chunk_operations.push(operation);
     }
 
-    fn terminate_slide(&mut self) {
-        let footer = self.generate_footer();
+    fn terminate_slide(&mut self) -> Result<(), BuildError> {
+        self.validate_layout_regions()?;
+        if self.slide_state.open_details.is_some() {
+            return Err(BuildError::UnclosedDetails);
+        }
+        if self.slide_state.truncated_elements > 0 {
+            let count = self.slide_state.truncated_elements;
+            let suffix = if count == 1 { "" } else { "s" };
+            let text = Text::new(format!("... ({count} more element{suffix})"), TextStyle::default().italics());
+            self.push_text(text.into(), ElementType::Paragraph);
+            self.push_line_break();
+        }
+        self.push_footnotes();
+        self.end_vertical_center();
 
         let operations = mem::take(&mut self.chunk_operations);
         let mutators = mem::take(&mut self.chunk_mutators);
         self.slide_chunks.push(SlideChunk::new(operations, mutators));
 
         let chunks = mem::take(&mut self.slide_chunks);
-        let slide = SlideBuilder::default().chunks(chunks).footer(footer).build();
-        self.index_builder.add_title(self.slide_state.title.take().unwrap_or_else(|| Text::from("<no title>").into()));
-        self.slides.push(slide);
+        let steps = Rc::new(SlideSteps::new(&chunks, &self.slide_state.step_kinds));
+        let footer = self.generate_footer(steps.clone());
+        let mut builder = SlideBuilder::default()
+            .chunks(chunks)
+            .footer(footer)
+            .steps(steps)
+            .char_counts(self.slide_state.char_count, self.slide_state.code_char_count)
+            .word_count(self.slide_state.word_count)
+            .max_duration(self.slide_state.max_duration)
+            .numbered(!self.slide_state.unnumbered)
+            .focusable_code(mem::take(&mut self.slide_state.focusable_code))
+            .details(mem::take(&mut self.slide_state.details));
+        match self.slide_state.title.take() {
+            Some((title, plain_title)) => {
+                builder = builder.title(plain_title);
+                self.index_builder.add_title(title);
+            }
+            None => {
+                // Slides without a title are named after their first heading, if any.
+                if let Some(heading) = self.slide_state.first_heading.take() {
+                    builder = builder.title(heading);
+                }
+                self.index_builder.add_title(Text::from("<no title>").into());
+            }
+        };
+        self.slides.push(builder.build());
+        if let Some(measurements) = &mut self.measurements {
+            measurements.push(mem::take(&mut self.slide_measurement));
+        }
+        self.glossary_modal_builder.add_slide(mem::take(&mut self.slide_state.glossary_entries));
 
         self.push_slide_prelude();
         self.slide_state = Default::default();
         self.slide_state.last_element = LastElement::None;
+        Ok(())
+    }
+
+    fn push_footnotes(&mut self) {
+        let footnotes = mem::take(&mut self.slide_state.footnotes);
+        if footnotes.is_empty() {
+            return;
+        }
+        self.push_line_break();
+        self.chunk_operations.extend([RenderSeparator::default().into(), RenderOperation::RenderLineBreak]);
+        for Footnote { number, text } in footnotes {
+            let mut line = vec![Text::from(format!("{} ", superscript(number)))];
+            line.extend(text.0);
+            self.push_text(TextBlock(line), ElementType::Paragraph);
+            self.push_line_break();
+        }
     }
 
-    fn generate_footer(&mut self) -> Vec<RenderOperation> {
-        if self.slide_state.ignore_footer {
+    fn generate_footer(&mut self, steps: Rc<SlideSteps>) -> Vec<RenderOperation> {
+        if self.slide_state.ignore_footer || self.options.hide_footer {
             return Vec::new();
         }
+        // Unnumbered slides keep the number of the last numbered slide before them for the sake of
+        // the progress bar.
+        let previous_slides = self.slides.iter().filter(|slide| slide.is_numbered()).count();
+        let numbered = !self.slide_state.unnumbered;
         let generator = FooterGenerator {
             style: self.theme.footer.clone().unwrap_or_default(),
-            current_slide: self.slides.len(),
+            current_slide: previous_slides + numbered as usize,
+            numbered,
             context: self.footer_context.clone(),
+            overrides: mem::take(&mut self.slide_state.footer_override),
+            steps,
+        };
+        let Some(row) = generator.row() else {
+            return Vec::new();
         };
         vec![
             // Exit any layout we're in so this gets rendered on a default screen size.
             RenderOperation::ExitLayout,
             // Pop the slide margin so we're at the terminal rect.
             RenderOperation::PopMargin,
+            RenderOperation::JumpToBottomRow { index: row },
             RenderOperation::RenderDynamic(Rc::new(generator)),
         ]
     }
@@ -777,7 +1709,8 @@ impl<'a> PresentationBuilder<'a> {
         let widths: Vec<_> = (0..table.columns())
             .map(|column| table.iter_column(column).map(|text| text.width()).max().unwrap_or(0))
             .collect();
-        let flattened_header = Self::prepare_table_row(table.header, &widths);
+        let alignments: Vec<_> = (0..table.columns()).map(|column| table.alignment(column)).collect();
+        let flattened_header = Self::prepare_table_row(table.header, &widths, &alignments);
         self.push_text(flattened_header, ElementType::Table);
         self.push_line_break();
 
@@ -799,42 +1732,148 @@ impl<'a> PresentationBuilder<'a> {
         self.push_text(separator, ElementType::Table);
         self.push_line_break();
 
-        for row in table.rows {
-            let flattened_row = Self::prepare_table_row(row, &widths);
-            self.push_text(flattened_row, ElementType::Table);
+        let rows: Vec<_> = table
+            .rows
+            .into_iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let mut row = Self::prepare_table_row(row, &widths, &alignments);
+                if index % 2 == 1 {
+                    row.apply_style(&TextStyle::default().colors(self.theme.table.alternate_row_colors.clone()));
+                }
+                row
+            })
+            .collect();
+        let incremental_rows = mem::take(&mut self.slide_state.incremental_rows);
+        match (incremental_rows, &self.options.reveal_style) {
+            (true, RevealStyle::Dim) if !rows.is_empty() => self.push_dimmed_table_rows(rows),
+            _ => {
+                for (index, row) in rows.into_iter().enumerate() {
+                    // Like lists, the first row is shown along with the header.
+                    if index > 0 && incremental_rows {
+                        self.process_pause(StepKind::TableRow);
+                    }
+                    self.push_text(row, ElementType::Table);
+                    self.push_line_break();
+                }
+            }
+        };
+    }
+
+    fn push_dimmed_table_rows(&mut self, rows: Vec<TextBlock>) {
+        let alignment = self.theme.alignment(&ElementType::Table);
+        let context = Rc::new(RefCell::new(RevealedRowsContext::new(rows.len())));
+        for (index, mut row) in rows.into_iter().enumerate() {
+            self.style_inline_code(&mut row);
+            let row = DimmedTableRow::new(index, row, alignment.clone(), context.clone());
+            self.chunk_operations.push(RenderOperation::RenderDynamic(Rc::new(row)));
             self.push_line_break();
         }
+        self.chunk_mutators.push(Box::new(RevealRowsMutator::new(context)));
     }
 
-    fn prepare_table_row(row: TableRow, widths: &[usize]) -> TextBlock {
+    fn prepare_table_row(row: TableRow, widths: &[usize], alignments: &[TableAlignment]) -> TextBlock {
         let mut flattened_row = TextBlock(Vec::new());
         for (column, text) in row.0.into_iter().enumerate() {
             if column > 0 {
                 flattened_row.0.push(Text::from(" │ "));
             }
-            let text_length = text.width();
-            flattened_row.0.extend(text.0.into_iter());
-
-            let cell_width = widths[column];
-            if text_length < cell_width {
-                let padding = " ".repeat(cell_width - text_length);
-                flattened_row.0.push(Text::from(padding));
+            let padding = widths[column].saturating_sub(text.width());
+            let (before, after) = match alignments[column] {
+                TableAlignment::Left => (0, padding),
+                TableAlignment::Center => (padding / 2, padding - padding / 2),
+                TableAlignment::Right => (padding, 0),
+            };
+            if before > 0 {
+                flattened_row.0.push(Text::from(" ".repeat(before)));
+            }
+            flattened_row.0.extend(text.0);
+            if after > 0 {
+                flattened_row.0.push(Text::from(" ".repeat(after)));
             }
         }
         flattened_row
     }
 }
 
+fn highlight_lines(
+    code: &Code,
+    style: &CodeBlockStyle,
+    highlighter: &CodeHighlighter,
+    redactions: &Redactions,
+    allow_mutations: bool,
+    alignment: Alignment,
+) -> (Vec<HighlightedLine>, Rc<RefCell<HighlightContext>>) {
+    let lines = CodePreparer::new(style).prepare(code);
+    let block_length = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+    let mut empty_highlighter = highlighter.language_highlighter(&CodeLanguage::Unknown);
+    let mut code_highlighter = highlighter.language_highlighter(&code.language);
+    let padding_style = {
+        let mut highlighter = highlighter.language_highlighter(&CodeLanguage::Rust);
+        highlighter.style_line("//").next().expect("no styles").style
+    };
+    let groups = match allow_mutations {
+        true => code.attributes.highlight_groups.clone(),
+        false => vec![HighlightGroup::new(vec![Highlight::All])],
+    };
+    let context = Rc::new(RefCell::new(HighlightContext { groups, current: 0, block_length, alignment }));
+
+    let mut output = Vec::new();
+    for line in lines.into_iter() {
+        let highlighted = line.highlight(&padding_style, &mut code_highlighter, style, redactions);
+        let not_highlighted = line.highlight(&padding_style, &mut empty_highlighter, style, redactions);
+        let width = line.width();
+        let line_number = line.line_number;
+        let context = context.clone();
+        output.push(HighlightedLine { highlighted, not_highlighted, line_number, width, context });
+    }
+    (output, context)
+}
+
 #[derive(Debug, Default)]
 struct SlideState {
     ignore_element_line_break: bool,
     ignore_footer: bool,
+    footer_override: FooterOverride,
     needs_enter_column: bool,
     last_chunk_ended_in_list: bool,
     last_element: LastElement,
     incremental_lists: Option<bool>,
     layout: LayoutState,
-    title: Option<TextBlock>,
+    layout_template: Option<SlideLayoutState>,
+    title: Option<(TextBlock, String)>,
+    first_heading: Option<String>,
+    // The text in the heading being processed with redactions concealed, if they're revealed.
+    plain_heading: Option<String>,
+    big_text: bool,
+    inverse: bool,
+    incremental_rows: bool,
+    rendered_elements: usize,
+    truncated_elements: usize,
+    glossary_entries: Vec<GlossaryEntry>,
+    footnotes: Vec<Footnote>,
+    step_kinds: Vec<StepKind>,
+    char_count: usize,
+    code_char_count: usize,
+    word_count: usize,
+    max_duration: Option<Duration>,
+    unnumbered: bool,
+    continue_list: bool,
+    focusable_code: Vec<FocusableCode>,
+    open_details: Option<OpenDetails>,
+    details: Vec<Rc<CollapsibleDetails>>,
+    // The index of the operation that centers what follows it in the current chunk, if any.
+    vertical_center: Option<usize>,
+}
+
+// A `<details>` block whose end hasn't been found yet.
+#[derive(Debug)]
+struct OpenDetails {
+    summary: TextBlock,
+    open: bool,
+    // The index of the first operation that's part of it.
+    start: usize,
+    chunk: usize,
 }
 
 #[derive(Debug, Default)]
@@ -850,12 +1889,28 @@ enum LayoutState {
     },
 }
 
+#[derive(Debug)]
+struct SlideLayoutState {
+    name: String,
+    regions: Vec<LayoutRegion>,
+    filled: Vec<bool>,
+    explicit_regions: bool,
+}
+
+impl SlideLayoutState {
+    fn new(name: String, regions: Vec<LayoutRegion>) -> Self {
+        let filled = vec![false; regions.len()];
+        Self { name, regions, filled, explicit_regions: false }
+    }
+}
+
 #[derive(Debug, Default)]
 enum LastElement {
     #[default]
     None,
     List {
         last_index: usize,
+        start: usize,
     },
     Other,
 }
@@ -893,16 +1948,67 @@ pub enum BuildError {
     #[error("need to enter layout column explicitly using `column` command")]
     NotInsideColumn,
 
+    #[error("column break outside of a layout column in slide {slide}, line {line}")]
+    ColumnBreakOutsideColumn { slide: usize, line: usize },
+
+    #[error("column break after the last column in slide {slide}, line {line}")]
+    ColumnBreakAfterLastColumn { slide: usize, line: usize },
+
+    #[error("'{}' in slide {slide} is outside of the presentation's directory", path.display())]
+    OutsideSandbox { path: PathBuf, slide: usize },
+
+    #[error("resolving '{}' in slide {slide}: {error}", path.display())]
+    ResolvePath { path: PathBuf, slide: usize, error: io::Error },
+
+    #[error("unknown theme '{name}' in slide {slide}")]
+    UnknownTheme { name: String, slide: usize },
+
+    #[error("theme can only be changed before any content in slide {slide}")]
+    ThemeAfterContent { slide: usize },
+
+    #[error("unknown layout '{name}' in slide {slide}")]
+    UnknownLayout { name: String, slide: usize },
+
+    #[error("layout '{layout}' has no region '{region}' in slide {slide}")]
+    UnknownRegion { layout: String, region: String, slide: usize },
+
+    #[error("region '{region}' of layout '{layout}' is empty in slide {slide}")]
+    UnfilledRegion { layout: String, region: String, slide: usize },
+
+    #[error("invalid countdown duration: '{0}'")]
+    InvalidCountdown(String),
+
+    #[error("invalid max duration: '{0}'")]
+    InvalidMaxDuration(String),
+
+    #[error("<details> blocks can't be nested")]
+    NestedDetails,
+
+    #[error("</details> without a matching <details>")]
+    UnmatchedDetailsEnd,
+
+    #[error("<details> block is never closed")]
+    UnclosedDetails,
+
+    #[error("<details> blocks can't contain pauses")]
+    PauseInDetails,
+
     #[error("error parsing command at line {line}: {error}")]
     CommandParse { line: usize, error: CommandParseError },
 
     #[error("typst render failed: {0}")]
     TypstRender(#[from] TypstRenderError),
+
+    #[error("mermaid render failed: {0}")]
+    MermaidRender(#[from] MermaidRenderError),
+
+    #[error("measuring slides: {0}")]
+    MeasureSlides(#[from] RenderError),
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(rename_all = "snake_case")]
-enum CommentCommand {
+pub(crate) enum CommentCommand {
     Pause,
     EndSlide,
     #[serde(alias = "newline")]
@@ -912,24 +2018,57 @@ enum CommentCommand {
     #[serde(rename = "column_layout")]
     InitColumnLayout(Vec<u8>),
     Column(usize),
+    ColumnBreak,
     ResetLayout,
+    Layout(String),
+    Region(String),
     JumpToMiddle,
     IncrementalLists(bool),
     NoFooter,
+    Unnumbered,
+    ListContinue,
+    Footer(FooterOverride),
+    BigText,
+    Inverse,
+    IncrementalRows,
+    Countdown(String),
+    MaxDuration(String),
+    Theme(String),
+    // A note the author left for themselves, like `TODO: add benchmarks`.
+    #[serde(skip)]
+    Todo(String),
 }
 
 impl FromStr for CommentCommand {
     type Err = CommandParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        if Self::is_todo(text) {
+            return Ok(Self::Todo(text.to_string()));
+        }
+
         #[derive(Deserialize)]
         struct CommandWrapper(#[serde(with = "serde_yaml::with::singleton_map")] CommentCommand);
 
-        let wrapper = serde_yaml::from_str::<CommandWrapper>(s)?;
+        // Deprecated commands keep working, they're only reported when the presentation is loaded.
+        let wrapper = match replace_deprecated(text) {
+            Some(command) => serde_yaml::from_str::<CommandWrapper>(&command)?,
+            None => serde_yaml::from_str::<CommandWrapper>(s)?,
+        };
         Ok(wrapper.0)
     }
 }
 
+impl CommentCommand {
+    fn is_todo(text: &str) -> bool {
+        ["TODO", "FIXME"].iter().any(|marker| match text.strip_prefix(marker) {
+            Some(rest) => rest.is_empty() || rest.starts_with(':') || rest.starts_with(char::is_whitespace),
+            None => false,
+        })
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub struct CommandParseError(#[from] serde_yaml::Error);
 
@@ -1014,19 +2153,48 @@ struct StrictPresentationMetadata {
 
     #[serde(default)]
     options: Option<OptionsConfig>,
+
+    #[serde(default)]
+    glossary: BTreeMap<String, String>,
+
+    #[serde(default)]
+    redact: RedactionConfig,
 }
 
 impl From<StrictPresentationMetadata> for PresentationMetadata {
     fn from(strict: StrictPresentationMetadata) -> Self {
-        let StrictPresentationMetadata { title, sub_title, author, authors, theme, options } = strict;
-        Self { title, sub_title, author, authors, theme, options }
+        let StrictPresentationMetadata { title, sub_title, author, authors, theme, options, glossary, redact } = strict;
+        Self { title, sub_title, author, authors, theme, options, glossary, redact }
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{
+        custom::PreviewDimensions,
+        markdown::elements::{CodeAttributes, LineColumn},
+        presentation::SlideReference,
+        processing::{
+            hooks::{HookContext, HookError, HookOutput, StyledText},
+            split::Overrun,
+        },
+        render::properties::WindowSize,
+    };
     use rstest::rstest;
+    use std::time::Instant;
+
+    fn build_heading(level: u8, text: impl Into<TextBlock>) -> MarkdownElement {
+        MarkdownElement::Heading { level, text: text.into(), source_position: Default::default() }
+    }
+
+    fn build_slide_title(text: impl Into<TextBlock>) -> MarkdownElement {
+        MarkdownElement::SetexHeading { text: text.into(), source_position: Default::default() }
+    }
+
+    fn build_details_start() -> MarkdownElement {
+        MarkdownElement::Details(DetailsTag::Start { summary: "summary".into(), open: false })
+    }
 
     fn build_presentation(elements: Vec<MarkdownElement>) -> Presentation {
         try_build_presentation(elements).expect("build failed")
@@ -1085,12 +2253,13 @@ mod test {
         match operation {
             ClearScreen
             | SetColors(_)
-            | JumpToVerticalCenter
+            | RenderBackgroundGradient(_)
+            | JumpToVerticalCenter { .. }
             | JumpToRow { .. }
             | JumpToBottomRow { .. }
             | InitColumnLayout { .. }
             | EnterColumn { .. }
-            | ExitLayout { .. }
+            | ExitLayout
             | ApplyMargin(_)
             | PopMargin => false,
             RenderText { .. }
@@ -1132,16 +2301,14 @@ mod test {
     fn prelude_appears_once() {
         let elements = vec![
             MarkdownElement::FrontMatter("author: bob".to_string()),
-            MarkdownElement::Heading { text: TextBlock::from("hello"), level: 1 },
+            build_heading(1, "hello"),
             build_end_slide(),
-            MarkdownElement::Heading { text: TextBlock::from("bye"), level: 1 },
+            build_heading(1, "bye"),
         ];
         let presentation = build_presentation(elements);
         for (index, slide) in presentation.iter_slides().enumerate() {
-            let clear_screen_count =
-                slide.iter_operations().filter(|op| matches!(op, RenderOperation::ClearScreen)).count();
-            let set_colors_count =
-                slide.iter_operations().filter(|op| matches!(op, RenderOperation::SetColors(_))).count();
+            let clear_screen_count = slide.filter_operations(|op| matches!(op, RenderOperation::ClearScreen)).len();
+            let set_colors_count = slide.filter_operations(|op| matches!(op, RenderOperation::SetColors(_))).len();
             assert_eq!(clear_screen_count, 1, "{clear_screen_count} clear screens in slide {index}");
             assert_eq!(set_colors_count, 1, "{set_colors_count} clear screens in slide {index}");
         }
@@ -1151,9 +2318,9 @@ mod test {
     fn slides_start_with_one_newline() {
         let elements = vec![
             MarkdownElement::FrontMatter("author: bob".to_string()),
-            MarkdownElement::Heading { text: TextBlock::from("hello"), level: 1 },
+            build_heading(1, "hello"),
             build_end_slide(),
-            MarkdownElement::Heading { text: TextBlock::from("bye"), level: 1 },
+            build_heading(1, "bye"),
         ];
         let presentation = build_presentation(elements);
         assert_eq!(presentation.iter_slides().count(), 3);
@@ -1174,6 +2341,7 @@ mod test {
         let elements = vec![MarkdownElement::Table(Table {
             header: TableRow(vec![TextBlock::from("key"), TextBlock::from("value"), TextBlock::from("other")]),
             rows: vec![TableRow(vec![TextBlock::from("potato"), TextBlock::from("bar"), TextBlock::from("yes")])],
+            alignments: Vec::new(),
         })];
         let slides = build_presentation(elements).into_slides();
         let lines = extract_slide_text_lines(slides.into_iter().next().unwrap());
@@ -1182,181 +2350,1379 @@ mod test {
     }
 
     #[test]
-    fn layout_without_init() {
-        let elements = vec![build_column(0)];
-        let result = try_build_presentation(elements);
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn already_in_column() {
+    fn nested_block_quote() {
         let elements = vec![
-            MarkdownElement::Comment { comment: "column_layout: [1]".into(), source_position: Default::default() },
-            MarkdownElement::Comment { comment: "column: 0".into(), source_position: Default::default() },
-            MarkdownElement::Comment { comment: "column: 0".into(), source_position: Default::default() },
+            MarkdownElement::FrontMatter("theme:\n  override:\n    block_quote:\n      prefix: '| '".into()),
+            MarkdownElement::BlockQuote(vec![
+                QuoteLine { text: "outer".into(), depth: 0 },
+                QuoteLine { text: "inner".into(), depth: 1 },
+            ]),
         ];
-        let result = try_build_presentation(elements);
-        assert!(result.is_err());
+        let slides = build_presentation(elements).into_slides();
+        let lines = extract_slide_text_lines(slides.into_iter().next().unwrap());
+        let lines: Vec<_> = lines.iter().map(|line| line.trim()).filter(|line| !line.is_empty()).collect();
+        assert_eq!(lines, &["| outer", "| | inner"]);
     }
 
     #[test]
-    fn column_index_overflow() {
-        let elements = vec![
-            MarkdownElement::Comment { comment: "column_layout: [1]".into(), source_position: Default::default() },
-            MarkdownElement::Comment { comment: "column: 1".into(), source_position: Default::default() },
-        ];
-        let result = try_build_presentation(elements);
-        assert!(result.is_err());
+    fn table_with_wide_characters() {
+        let elements = vec![MarkdownElement::Table(Table {
+            header: TableRow(vec![TextBlock::from("日本語"), TextBlock::from("x")]),
+            rows: vec![TableRow(vec![TextBlock::from("🚀"), TextBlock::from("e\u{301}")])],
+            alignments: Vec::new(),
+        })];
+        let slides = build_presentation(elements).into_slides();
+        let lines = extract_slide_text_lines(slides.into_iter().next().unwrap());
+        let expected_lines = &["日本語 │ x", "───────┼──", "🚀     │ e\u{301}"];
+        assert_eq!(lines, expected_lines);
     }
 
-    #[rstest]
-    #[case::empty("column_layout: []")]
-    #[case::zero("column_layout: [0]")]
-    #[case::one_is_zero("column_layout: [1, 0]")]
-    fn invalid_layouts(#[case] definition: &str) {
-        let elements =
-            vec![MarkdownElement::Comment { comment: definition.into(), source_position: Default::default() }];
-        let result = try_build_presentation(elements);
-        assert!(result.is_err());
+    #[test]
+    fn table_alignments() {
+        let elements = vec![MarkdownElement::Table(Table {
+            header: TableRow(vec![TextBlock::from("left"), TextBlock::from("center"), TextBlock::from("right")]),
+            rows: vec![TableRow(vec![TextBlock::from("a"), TextBlock::from("b"), TextBlock::from("42")])],
+            alignments: vec![TableAlignment::Left, TableAlignment::Center, TableAlignment::Right],
+        })];
+        let slides = build_presentation(elements).into_slides();
+        let lines = extract_slide_text_lines(slides.into_iter().next().unwrap());
+        let expected_lines = &["left │ center │ right", "─────┼────────┼──────", "a    │   b    │    42"];
+        assert_eq!(lines, expected_lines);
     }
 
     #[test]
-    fn operation_without_enter_column() {
+    fn table_alternate_row_colors() {
+        let front_matter = "theme:\n  override:\n    table:\n      alternate_row_colors:\n        background: ff0000";
         let elements = vec![
-            MarkdownElement::Comment { comment: "column_layout: [1]".into(), source_position: Default::default() },
-            MarkdownElement::ThematicBreak,
+            MarkdownElement::FrontMatter(front_matter.into()),
+            MarkdownElement::Table(Table {
+                header: TableRow(vec![TextBlock::from("h")]),
+                rows: ["a", "b", "c", "d"].iter().map(|row| TableRow(vec![TextBlock::from(*row)])).collect(),
+                alignments: Vec::new(),
+            }),
         ];
-        let result = try_build_presentation(elements);
-        assert!(result.is_err());
+        let slide = build_presentation(elements).into_slides().into_iter().next().unwrap();
+        let shaded: Vec<_> = slide
+            .into_operations()
+            .into_iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderText { line, .. } => {
+                    let text = line.iter_texts().next()?.text().clone();
+                    text.style.colors.background.map(|_| text.content)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(shaded, &["b", "d"]);
     }
 
-    #[rstest]
-    #[case::pause("pause", CommentCommand::Pause)]
-    #[case::pause(" pause ", CommentCommand::Pause)]
-    #[case::end_slide("end_slide", CommentCommand::EndSlide)]
-    #[case::column_layout("column_layout: [1, 2]", CommentCommand::InitColumnLayout(vec![1, 2]))]
-    #[case::column("column: 1", CommentCommand::Column(1))]
-    #[case::reset_layout("reset_layout", CommentCommand::ResetLayout)]
-    #[case::incremental_lists("incremental_lists: true", CommentCommand::IncrementalLists(true))]
-    #[case::incremental_lists("new_lines: 2", CommentCommand::NewLines(2))]
-    #[case::incremental_lists("newlines: 2", CommentCommand::NewLines(2))]
-    #[case::incremental_lists("new_line", CommentCommand::NewLine)]
-    #[case::incremental_lists("newline", CommentCommand::NewLine)]
-    fn command_formatting(#[case] input: &str, #[case] expected: CommentCommand) {
-        let parsed: CommentCommand = input.parse().expect("deserialization failed");
-        assert_eq!(parsed, expected);
+    #[test]
+    fn list_item_margin() {
+        let elements = vec![MarkdownElement::List(vec![ListItem {
+            depth: 0,
+            contents: "potato".into(),
+            item_type: ListItemType::Unordered,
+        }])];
+        let slide = build_presentation(elements).into_slides().into_iter().next().unwrap();
+        // The bullet takes a single column even though it's more than one byte long.
+        let margins: Vec<_> = slide
+            .into_operations()
+            .into_iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderText {
+                    alignment: Alignment::Left { margin: Margin::Fixed(margin) }, ..
+                } => Some(margin),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(margins, &[0, 4]);
     }
 
-    #[test]
-    fn end_slide_inside_layout() {
-        let elements = vec![build_column_layout(1), build_end_slide()];
-        let presentation = build_presentation(elements);
-        assert_eq!(presentation.iter_slides().count(), 2);
+    fn build_incremental_table(rows: &[&str]) -> Vec<MarkdownElement> {
+        vec![
+            MarkdownElement::Comment { comment: "no_footer".into(), source_position: Default::default() },
+            MarkdownElement::Comment { comment: "incremental_rows".into(), source_position: Default::default() },
+            MarkdownElement::Table(Table {
+                header: TableRow(vec![TextBlock::from("h")]),
+                rows: rows.iter().map(|row| TableRow(vec![TextBlock::from(*row)])).collect(),
+                alignments: Vec::new(),
+            }),
+            build_pause(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("after".into())]),
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("next".into())]),
+        ]
     }
 
-    #[test]
-    fn end_slide_inside_column() {
-        let elements = vec![build_column_layout(1), build_column(0), build_end_slide()];
-        let presentation = build_presentation(elements);
-        assert_eq!(presentation.iter_slides().count(), 2);
+    // Gets the visible lines in the current slide, wrapping dimmed ones in brackets.
+    fn visible_lines(presentation: &Presentation) -> Vec<String> {
+        let dimensions = WindowSize { rows: 50, columns: 100, height: 0, width: 0 };
+        let mut lines = Vec::new();
+        for operation in presentation.current_slide().iter_operations() {
+            let operations = match operation {
+                RenderOperation::RenderDynamic(generator) => generator.as_render_operations(&dimensions),
+                other => vec![other.clone()],
+            };
+            for operation in operations {
+                if let RenderOperation::RenderText { line, .. } = operation {
+                    let text = line.iter_texts().next().unwrap().text();
+                    let content = text.content.trim_end().to_string();
+                    lines.push(if text.style.is_dim() { format!("[{content}]") } else { content });
+                }
+            }
+        }
+        lines
     }
 
-    #[test]
-    fn pause_inside_layout() {
-        let elements = vec![build_column_layout(1), build_pause(), build_column(0)];
-        let presentation = build_presentation(elements);
-        assert_eq!(presentation.iter_slides().count(), 1);
+    fn build_code(language: CodeLanguage, language_name: &str, contents: &str) -> MarkdownElement {
+        let attributes =
+            CodeAttributes { highlight_groups: vec![HighlightGroup::new(vec![Highlight::All])], ..Default::default() };
+        MarkdownElement::Code(Code {
+            contents: contents.into(),
+            language,
+            language_name: language_name.into(),
+            attributes,
+        })
     }
 
     #[test]
-    fn iterate_list() {
-        let iter = ListIterator::new(
-            vec![
-                ListItem { depth: 0, contents: "0".into(), item_type: ListItemType::Unordered },
-                ListItem { depth: 0, contents: "1".into(), item_type: ListItemType::Unordered },
-                ListItem { depth: 1, contents: "00".into(), item_type: ListItemType::Unordered },
-                ListItem { depth: 1, contents: "01".into(), item_type: ListItemType::Unordered },
-                ListItem { depth: 1, contents: "02".into(), item_type: ListItemType::Unordered },
-                ListItem { depth: 2, contents: "001".into(), item_type: ListItemType::Unordered },
-                ListItem { depth: 0, contents: "2".into(), item_type: ListItemType::Unordered },
-            ],
-            0,
-        );
-        let expected_indexes = [0, 1, 0, 1, 2, 0, 2];
-        let indexes: Vec<_> = iter.map(|item| item.index).collect();
-        assert_eq!(indexes, expected_indexes);
+    fn code_block_hooks() {
+        let mut render_hooks = RenderHooks::default();
+        render_hooks.register_code_block("shout", |source: &str, context: &HookContext| -> Result<_, HookError> {
+            let line = format!("{} ({} columns)", source.trim().to_uppercase(), context.columns);
+            Ok(HookOutput::Lines(vec![vec![StyledText::new(line)]]))
+        });
+        let options = PresentationBuilderOptions { render_hooks, ..Default::default() };
+        let elements = vec![
+            MarkdownElement::Comment { comment: "no_footer".into(), source_position: Default::default() },
+            build_code(CodeLanguage::Unknown, "shout", "hello\n"),
+            build_code(CodeLanguage::Unknown, "whisper", "bye\n"),
+        ];
+        let presentation = build_presentation_with_options(elements, options);
+        assert_eq!(visible_lines(&presentation), &["HELLO (100 columns)"]);
+
+        // Languages without a hook are rendered as regular code.
+        let dimensions = WindowSize { rows: 50, columns: 100, height: 0, width: 0 };
+        let code_lines = presentation
+            .current_slide()
+            .iter_operations()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderDynamic(generator) => Some(generator.as_render_operations(&dimensions)),
+                _ => None,
+            })
+            .flatten()
+            .filter(|operation| {
+                matches!(operation, RenderOperation::RenderPreformattedLine(line) if line.text.contains("bye"))
+            });
+        assert_eq!(code_lines.count(), 1);
     }
 
-    #[test]
-    fn iterate_list_starting_from_other() {
-        let list = ListIterator::new(
-            vec![
-                ListItem { depth: 0, contents: "0".into(), item_type: ListItemType::Unordered },
-                ListItem { depth: 0, contents: "1".into(), item_type: ListItemType::Unordered },
-            ],
-            3,
-        );
-        let expected_indexes = [3, 4];
-        let indexes: Vec<_> = list.into_iter().map(|item| item.index).collect();
-        assert_eq!(indexes, expected_indexes);
+    #[rstest]
+    #[case::footer(false, 1)]
+    #[case::hidden_footer(true, 0)]
+    fn footer_visibility(#[case] hide_footer: bool, #[case] expected: usize) {
+        let elements = vec![MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())])];
+        let options = PresentationBuilderOptions { hide_footer, ..Default::default() };
+        let slide = build_presentation_with_options(elements, options).into_slides().into_iter().next().unwrap();
+        let footers = slide.filter_operations(|op| matches!(op, RenderOperation::RenderDynamic(_))).len();
+        assert_eq!(footers, expected);
     }
 
     #[test]
-    fn ordered_list_with_pauses() {
+    fn vertical_center_ends_at_pause() {
         let elements = vec![
-            MarkdownElement::List(vec![
-                ListItem { depth: 0, contents: "one".into(), item_type: ListItemType::OrderedPeriod },
-                ListItem { depth: 1, contents: "one_one".into(), item_type: ListItemType::OrderedPeriod },
-                ListItem { depth: 1, contents: "one_two".into(), item_type: ListItemType::OrderedPeriod },
-            ]),
+            build_comment("jump_to_middle"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
             build_pause(),
-            MarkdownElement::List(vec![ListItem {
-                depth: 0,
-                contents: "two".into(),
-                item_type: ListItemType::OrderedPeriod,
-            }]),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("bye".into())]),
         ];
-        let slides = build_presentation(elements).into_slides();
-        let lines = extract_slide_text_lines(slides.into_iter().next().unwrap());
-        let expected_lines = &["   1. one", "      1. one_one", "      2. one_two", "   2. two"];
-        assert_eq!(lines, expected_lines);
+        let slide = build_presentation(elements).into_slides().into_iter().next().unwrap();
+        let operations: Vec<_> = slide.iter_chunks().next().expect("no chunks").iter_operations().collect();
+        let (index, measured) = operations
+            .iter()
+            .enumerate()
+            .find_map(|(index, operation)| match operation {
+                RenderOperation::JumpToVerticalCenter { operations } => Some((index, *operations)),
+                _ => None,
+            })
+            .expect("not centered");
+        // Only the rest of the first chunk is centered, which leaves out the footer and what's after the pause.
+        assert_eq!(measured, Some(operations.len() - index - 1));
     }
 
     #[test]
-    fn automatic_pauses() {
+    fn theme_command() {
+        let command =
+            |comment: &str| MarkdownElement::Comment { comment: comment.into(), source_position: Default::default() };
+        let paragraph = || MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]);
         let elements = vec![
-            MarkdownElement::Comment { comment: "incremental_lists: true".into(), source_position: Default::default() },
-            MarkdownElement::List(vec![
-                ListItem { depth: 0, contents: "one".into(), item_type: ListItemType::Unordered },
-                ListItem { depth: 1, contents: "two".into(), item_type: ListItemType::Unordered },
-                ListItem { depth: 0, contents: "three".into(), item_type: ListItemType::Unordered },
-            ]),
+            paragraph(),
+            build_end_slide(),
+            command("theme: dark"),
+            paragraph(),
+            build_end_slide(),
+            paragraph(),
+            build_end_slide(),
+            command("theme: light"),
+            paragraph(),
         ];
-        let slides = build_presentation(elements).into_slides();
-        assert_eq!(slides[0].iter_chunks().count(), 3);
+        let themes = Themes::default();
+        let colors = |name: &str| themes.presentation.load_by_name(name).unwrap().default_style.colors;
+        let default_colors = PresentationTheme::default().default_style.colors;
+        let expected = [default_colors, colors("dark"), colors("dark"), colors("light")];
+        let presentation = build_presentation(elements);
+        for (slide, expected) in presentation.iter_slides().zip(expected) {
+            let first = slide.iter_operations().next().unwrap();
+            assert!(matches!(first, RenderOperation::SetColors(colors) if colors == &expected), "{first:?}");
+        }
     }
 
-    #[test]
-    fn pause_after_list() {
+    #[rstest]
+    #[case::after_content(
+        vec![MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())])],
+        "dark",
+        "theme can only be changed before any content in slide 1"
+    )]
+    #[case::unknown(vec![], "potato", "unknown theme 'potato' in slide 1")]
+    fn invalid_theme_command(#[case] mut elements: Vec<MarkdownElement>, #[case] theme: &str, #[case] expected: &str) {
+        let comment = format!("theme: {theme}");
+        elements.push(MarkdownElement::Comment { comment, source_position: Default::default() });
+        let error = try_build_presentation(elements).expect_err("build succeeded");
+        assert_eq!(error.to_string(), expected);
+    }
+
+    #[rstest]
+    #[case::enabled(true, "launch 🚀")]
+    #[case::disabled(false, "launch :rocket:")]
+    fn emoji_shortcodes(#[case] shortcode_emoji: bool, #[case] expected: &str) {
+        let elements = vec![MarkdownElement::Paragraph(vec![ParagraphElement::Text("launch :rocket:".into())])];
+        let options = PresentationBuilderOptions { shortcode_emoji, ..Default::default() };
+        let slide = build_presentation_with_options(elements, options).into_slides().into_iter().next().unwrap();
+        assert_eq!(extract_slide_text_lines(slide), &[expected]);
+    }
+
+    #[rstest]
+    #[case::hidden(false, "hi ████")]
+    #[case::revealed(true, "hi acme")]
+    fn redactions(#[case] reveal_redactions: bool, #[case] expected: &str) {
+        let front_matter = "redact:\n  spoilers: true\n  patterns: ['sk-[a-z]+']";
+        let elements = vec![
+            MarkdownElement::FrontMatter(front_matter.into()),
+            build_slide_title("about ||acme||"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi ||acme||".into())]),
+            build_code(CodeLanguage::Rust, "rust", "let key = \"sk-abc\";"),
+        ];
+        let options = PresentationBuilderOptions { reveal_redactions, ..Default::default() };
+        let slide = build_presentation_with_options(elements, options).into_slides().into_iter().next().unwrap();
+        // Titles are never revealed as they're used outside of the presentation.
+        assert_eq!(slide.title(), Some("about ████"));
+        let dimensions = WindowSize { rows: 50, columns: 100, height: 0, width: 0 };
+        let code: String = slide
+            .iter_operations()
+            .flat_map(|operation| match operation {
+                RenderOperation::RenderDynamic(generator) => generator.as_render_operations(&dimensions),
+                other => vec![other.clone()],
+            })
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderPreformattedLine(line) => Some(line.text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(code.contains("sk-abc"), reveal_redactions, "{code}");
+        assert_eq!(code.contains("██████"), !reveal_redactions, "{code}");
+        assert_eq!(extract_slide_text_lines(slide)[1], expected);
+    }
+
+    #[rstest]
+    #[case::hidden(false, &["about ████", "by ████", "bob ███"])]
+    #[case::revealed(true, &["about acme", "by acme", "bob doe"])]
+    fn intro_slide_redactions(#[case] reveal_redactions: bool, #[case] expected: &[&str]) {
+        let front_matter = "title: about ||acme||\nsub_title: by ||acme||\nauthor: bob ||doe||\n\
+            redact:\n  spoilers: true";
+        let elements = vec![MarkdownElement::FrontMatter(front_matter.into())];
+        let options = PresentationBuilderOptions { reveal_redactions, ..Default::default() };
+        let slide = build_presentation_with_options(elements, options).into_slides().into_iter().next().unwrap();
+        assert_eq!(extract_slide_text_lines(slide), expected);
+    }
+
+    #[test]
+    fn details_summary_redactions() {
+        let elements = vec![
+            MarkdownElement::FrontMatter("redact:\n  spoilers: true".into()),
+            MarkdownElement::Details(DetailsTag::Start { summary: "about ||acme||".into(), open: false }),
+            MarkdownElement::Details(DetailsTag::End),
+        ];
+        let slide = build_presentation(elements).into_slides().into_iter().next().unwrap();
+        let dimensions = WindowSize { rows: 50, columns: 100, height: 0, width: 0 };
+        let operations: Vec<_> = slide
+            .iter_operations()
+            .flat_map(|operation| match operation {
+                RenderOperation::RenderDynamic(generator) => generator.as_render_operations(&dimensions),
+                _ => vec![],
+            })
+            .collect();
+        let lines = extract_text_lines(&operations);
+        assert!(lines.iter().any(|line| line.ends_with("about ████")), "{lines:?}");
+        assert!(!lines.iter().any(|line| line.contains("acme")), "{lines:?}");
+    }
+
+    #[test]
+    fn invalid_redaction_pattern() {
+        let elements = vec![MarkdownElement::FrontMatter("redact:\n  patterns: ['(']".into())];
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::InvalidMetadata(_))));
+    }
+
+    fn build_long_list(items: usize) -> MarkdownElement {
+        let items = (1..=items)
+            .map(|index| ListItem {
+                depth: 0,
+                contents: format!("item {index}").into(),
+                item_type: ListItemType::OrderedPeriod { start: 1 },
+            })
+            .collect();
+        MarkdownElement::List(items)
+    }
+
+    #[rstest]
+    #[case::height(Some(PreviewDimensions { columns: 40, rows: 16 }), None)]
+    #[case::steps(None, Some(12))]
+    fn slide_budget_split(#[case] size: Option<PreviewDimensions>, #[case] max_steps: Option<usize>) {
+        let elements = vec![build_slide_title("Title"), build_long_list(30)];
+        let budget = SlideBudget { size, max_steps, split: true };
+        // Steps are only counted once they're renumbered if every list item is its own step.
+        let incremental_lists = max_steps.is_some();
+        let options =
+            PresentationBuilderOptions { slide_budget: Some(budget), incremental_lists, ..Default::default() };
+        let presentation = build_presentation_with_options(elements, options);
+        let expected = BudgetWarning { slide: SlideReference::Numbered(1), issue: BudgetIssue::Split { slides: 3 } };
+        assert_eq!(presentation.budget_warnings(), &[expected]);
+
+        let slides = presentation.into_slides();
+        let titles: Vec<_> = slides.iter().map(|slide| slide.title().unwrap_or_default().to_string()).collect();
+        assert_eq!(titles, &["Title", "Title (cont.)", "Title (cont.)"]);
+
+        // Every item is shown exactly once and numbers keep going across slides.
+        let mut items = Vec::new();
+        for slide in slides {
+            let steps = slide.steps().total();
+            let lines = extract_slide_text_lines(slide);
+            if incremental_lists {
+                assert_eq!(steps, lines.len() - 1);
+            }
+            items.extend(lines.into_iter().skip(1).map(|line| line.trim().to_string()));
+        }
+        let expected: Vec<_> = (1..=30).map(|index| format!("{index}. item {index}")).collect();
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn slide_budget_warnings_use_display_numbers() {
+        let size = PreviewDimensions { columns: 40, rows: 16 };
+        let code = "let x = 1;\n".repeat(50);
+        let elements = vec![
+            build_slide_title("Title"),
+            build_long_list(30),
+            build_end_slide(),
+            MarkdownElement::Comment { comment: "unnumbered".into(), source_position: Default::default() },
+            build_code(CodeLanguage::Rust, "rust", &code),
+        ];
+        let budget = SlideBudget { size: Some(size), max_steps: None, split: false };
+        let options = PresentationBuilderOptions { slide_budget: Some(budget), ..Default::default() };
+        let presentation = build_presentation_with_options(elements, options);
+        let slides: Vec<_> = presentation.budget_warnings().iter().map(|warning| warning.slide).collect();
+        assert_eq!(slides, &[SlideReference::Numbered(1), SlideReference::Unnumbered { after: Some(1) }]);
+    }
+
+    #[rstest]
+    #[case::split(true)]
+    #[case::warn(false)]
+    fn slide_budget_unsplittable(#[case] split: bool) {
+        let size = PreviewDimensions { columns: 80, rows: 24 };
+        let contents = "let x = 1;\n".repeat(50);
+        let elements = vec![build_slide_title("Title"), build_code(CodeLanguage::Rust, "rust", &contents)];
+        let budget = SlideBudget { size: Some(size), max_steps: None, split };
+        let options = PresentationBuilderOptions { slide_budget: Some(budget), ..Default::default() };
+        let presentation = build_presentation_with_options(elements, options);
+
+        let overrun = Overrun::TooTall(size);
+        let issue = match split {
+            true => BudgetIssue::Unsplittable(overrun),
+            false => BudgetIssue::Overrun(overrun),
+        };
+        let expected = BudgetWarning { slide: SlideReference::Numbered(1), issue };
+        assert_eq!(presentation.budget_warnings(), &[expected]);
+        assert_eq!(presentation.slide_count(), 1);
+    }
+
+    #[rstest]
+    #[case::enabled(true)]
+    #[case::disabled(false)]
+    fn end_screen(#[case] enabled: bool) {
+        let elements = vec![MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())])];
+        let end_screen = EndScreen { message: "the end".into(), started_at: Instant::now() };
+        let options = PresentationBuilderOptions { end_screen: enabled.then_some(end_screen), ..Default::default() };
+        let mut presentation = build_presentation_with_options(elements, options);
+        assert_eq!(presentation.slide_count(), 1);
+        assert_eq!(presentation.jump_next(), enabled);
+        if !enabled {
+            return;
+        }
+        let slide = presentation.current_slide();
+        assert!(!slide.is_numbered());
+        // The only dynamic operation is the elapsed time, as there's no footer.
+        let dynamic = slide.filter_operations(|op| matches!(op, RenderOperation::RenderDynamic(_))).len();
+        assert_eq!(dynamic, 1);
+        let lines = extract_text_lines(&slide.iter_operations().cloned().collect::<Vec<_>>());
+        assert_eq!(lines.first().map(String::as_str), Some("the end"));
+    }
+
+    #[test]
+    fn footnotes() {
+        let paragraph = |text: &str| MarkdownElement::Paragraph(vec![ParagraphElement::Text(text.into())]);
+        let elements =
+            vec![paragraph("a^[first]"), build_end_slide(), paragraph("b^[second] c^[third]"), paragraph("d")];
+        let slides = build_presentation(elements).into_slides();
+        let lines: Vec<_> = slides.into_iter().map(extract_slide_text_lines).collect();
+        let expected = vec![vec!["a¹", "¹ first"], vec!["b² c³", "d", "² second", "³ third"]];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn unnumbered_slides() {
+        let front_matter = r#"title: hi
+theme:
+  override:
+    footer:
+      style: template
+      right: "{current_slide}/{total_slides}""#;
+        let unnumbered =
+            || MarkdownElement::Comment { comment: "unnumbered".into(), source_position: Default::default() };
+        let elements = vec![
+            MarkdownElement::FrontMatter(front_matter.into()),
+            build_heading(1, "first"),
+            build_end_slide(),
+            unnumbered(),
+            build_heading(1, "backup"),
+            build_end_slide(),
+            build_heading(1, "last"),
+            build_end_slide(),
+            unnumbered(),
+            build_heading(1, "appendix"),
+        ];
+        let presentation = build_presentation(elements);
+        let numbers: Vec<_> = (0..5).map(|index| presentation.numbering().display_number(index)).collect();
+        assert_eq!(numbers, &[Some(1), Some(2), None, Some(3), None]);
+
+        let dimensions = WindowSize { rows: 10, columns: 20, width: 0, height: 0 };
+        let footers: Vec<_> = presentation
+            .into_slides()
+            .into_iter()
+            .map(|slide| {
+                let operations: Vec<_> = slide
+                    .into_operations()
+                    .into_iter()
+                    .filter_map(|operation| match operation {
+                        RenderOperation::RenderDynamic(generator) => Some(generator.as_render_operations(&dimensions)),
+                        _ => None,
+                    })
+                    .flatten()
+                    .collect();
+                extract_text_lines(&operations).join("").trim().to_string()
+            })
+            .collect();
+        assert_eq!(footers, &["1/3", "2/3", "-/3", "3/3", "-/3"]);
+    }
+
+    #[test]
+    fn deferred_image_loading() {
+        // The file isn't a valid image but deferred images are only loaded when the slide is displayed.
+        let file = tempfile::NamedTempFile::new().expect("failed to create file");
+        let elements = vec![MarkdownElement::Image {
+            path: file.path().into(),
+            alt_text: String::new(),
+            source_position: Default::default(),
+        }];
+        let result = try_build_presentation(elements.clone());
+        assert!(matches!(result, Err(BuildError::LoadImage(_))));
+
+        let options = PresentationBuilderOptions { defer_media_loading: true, ..Default::default() };
+        let presentation = build_presentation_with_options(elements, options);
+        let deferred =
+            presentation.current_slide().filter_operations(|op| matches!(op, RenderOperation::RenderOnDemand(_))).len();
+        assert_eq!(deferred, 1);
+    }
+
+    #[test]
+    fn deferred_missing_image() {
+        let elements = vec![MarkdownElement::Image {
+            path: "missing-image.png".into(),
+            alt_text: String::new(),
+            source_position: Default::default(),
+        }];
+        let options = PresentationBuilderOptions { defer_media_loading: true, ..Default::default() };
+        let result = try_build_presentation_with_options(elements, options);
+        assert!(matches!(result, Err(BuildError::LoadImage(LoadImageError::Io(..)))));
+    }
+
+    fn try_build_sandboxed(elements: Vec<MarkdownElement>, sandboxed: bool) -> Result<Presentation, BuildError> {
+        let root = tempfile::tempdir().expect("failed to create directory");
+        let base = root.path().join("deck");
+        std::fs::create_dir(&base).expect("failed to create directory");
+        std::fs::write(root.path().join("image.png"), "").expect("failed to write");
+        let mut resources = Resources::new(&base, Default::default());
+        if sandboxed {
+            resources = resources.sandboxed().expect("failed to sandbox");
+        }
+        let theme = PresentationTheme::default();
+        let mut typst = TypstRender::default();
+        let themes = Themes::default();
+        let bindings = KeyBindingsConfig::default();
+        let options = PresentationBuilderOptions { defer_media_loading: true, ..Default::default() };
+        let builder = PresentationBuilder::new(
+            &theme,
+            &mut resources,
+            &mut typst,
+            &themes,
+            Default::default(),
+            bindings,
+            options,
+        );
+        builder.build(elements)
+    }
+
+    #[test]
+    fn sandboxed_image_outside() {
+        let image = MarkdownElement::Image {
+            path: "../image.png".into(),
+            alt_text: String::new(),
+            source_position: Default::default(),
+        };
+        let elements = vec![build_heading(1, "hi"), build_end_slide(), image];
+        assert!(try_build_sandboxed(elements.clone(), false).is_ok());
+
+        let result = try_build_sandboxed(elements, true);
+        assert!(matches!(result, Err(BuildError::OutsideSandbox { slide: 2, .. })), "{result:?}");
+    }
+
+    #[rstest]
+    #[case::sandboxed(true, 0)]
+    #[case::unsandboxed(false, 1)]
+    fn sandboxed_code_execution(#[case] sandboxed: bool, #[case] expected: usize) {
+        let MarkdownElement::Code(mut code) = build_code(CodeLanguage::Bash, "bash", "echo hi") else {
+            panic!("not code");
+        };
+        code.attributes.execute = true;
+        let presentation = try_build_sandboxed(vec![MarkdownElement::Code(code)], sandboxed).expect("build failed");
+        let executions =
+            presentation.current_slide().filter_operations(|op| matches!(op, RenderOperation::RenderOnDemand(_))).len();
+        assert_eq!(executions, expected);
+    }
+
+    #[test]
+    fn slide_titles() {
+        let elements = vec![
+            build_slide_title("intro"),
+            build_heading(1, "ignored"),
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+            build_heading(2, TextBlock(vec![Text::from("first "), Text::from("heading")])),
+            build_heading(1, "second heading"),
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("no headings".into())]),
+        ];
+        let presentation = build_presentation(elements);
+        let titles: Vec<_> = presentation.iter_slides().map(|slide| slide.title()).collect();
+        assert_eq!(titles, &[Some("intro"), Some("first heading"), None]);
+    }
+
+    #[test]
+    fn char_counts() {
+        let bold = Text::new("hi", TextStyle::default().bold());
+        let elements = vec![
+            build_end_slide(),
+            build_heading(1, TextBlock(vec![Text::from("hello "), Text::from("there")])),
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text(bold.into())]),
+            MarkdownElement::Code(Code {
+                contents: "let q = 42;".into(),
+                language: CodeLanguage::Rust,
+                language_name: "rust".into(),
+                attributes: Default::default(),
+            }),
+        ];
+        let presentation = build_presentation(elements);
+        let counts: Vec<_> =
+            presentation.iter_slides().map(|slide| (slide.char_count(), slide.code_char_count())).collect();
+        assert_eq!(counts, &[(0, 0), (11, 0), (2, 11)]);
+    }
+
+    #[test]
+    fn presentation_summary() {
+        let elements = vec![
+            MarkdownElement::FrontMatter("title: my talk".into()),
+            build_heading(1, "hello there"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text(TextBlock(vec![
+                Text::from("one tw"),
+                Text::new("o", TextStyle::default().bold()),
+                Text::from(" three"),
+            ]))]),
+            build_end_slide(),
+            MarkdownElement::Code(Code {
+                contents: "echo not counted".into(),
+                language: CodeLanguage::Shell("sh".into()),
+                language_name: "sh".into(),
+                attributes: Default::default(),
+            }),
+        ];
+        let presentation = build_presentation(elements);
+        assert_eq!(presentation.title(), Some("my talk"));
+        assert_eq!(presentation.slide_count(), 3);
+        assert_eq!(presentation.word_count(), 5);
+        assert_eq!(presentation.estimated_duration(60), Duration::from_secs(5));
+        assert_eq!(presentation.estimated_duration(120), Duration::from_millis(2500));
+    }
+
+    #[test]
+    fn incremental_rows_hidden() {
+        let mut presentation = build_presentation(build_incremental_table(&["a", "b", "c"]));
+        assert_eq!(visible_lines(&presentation), &["h", "──", "a"]);
+
+        let expected_steps: &[&[&str]] =
+            &[&["h", "──", "a", "b"], &["h", "──", "a", "b", "c"], &["h", "──", "a", "b", "c", "after"]];
+        for expected in expected_steps {
+            assert!(presentation.jump_next());
+            assert_eq!(visible_lines(&presentation), *expected);
+        }
+        for expected in expected_steps.iter().rev().skip(1) {
+            assert!(presentation.jump_previous());
+            assert_eq!(visible_lines(&presentation), *expected);
+        }
+    }
+
+    #[test]
+    fn incremental_rows_dimmed() {
+        let options = PresentationBuilderOptions { reveal_style: RevealStyle::Dim, ..Default::default() };
+        let mut presentation = build_presentation_with_options(build_incremental_table(&["a", "b", "c"]), options);
+        assert_eq!(visible_lines(&presentation), &["h", "──", "a", "[b]", "[c]"]);
+
+        let expected_steps: &[&[&str]] =
+            &[&["h", "──", "a", "b", "[c]"], &["h", "──", "a", "b", "c"], &["h", "──", "a", "b", "c", "after"]];
+        for expected in expected_steps {
+            assert!(presentation.jump_next());
+            assert_eq!(visible_lines(&presentation), *expected);
+        }
+        for expected in expected_steps.iter().rev().skip(1) {
+            assert!(presentation.jump_previous());
+            assert_eq!(visible_lines(&presentation), *expected);
+        }
+        assert!(presentation.jump_previous());
+        assert_eq!(visible_lines(&presentation), &["h", "──", "a", "[b]", "[c]"]);
+        assert!(!presentation.jump_previous());
+
+        // Coming back from the next slide reveals everything.
+        presentation.jump_last_slide();
+        assert!(presentation.jump_previous());
+        assert_eq!(visible_lines(&presentation), &["h", "──", "a", "b", "c", "after"]);
+    }
+
+    #[rstest]
+    #[case::hidden(RevealStyle::Hide)]
+    #[case::dimmed(RevealStyle::Dim)]
+    fn incremental_rows_applies_to_one_table(#[case] reveal_style: RevealStyle) {
+        let table = || {
+            MarkdownElement::Table(Table {
+                header: TableRow(vec![TextBlock::from("h")]),
+                rows: vec![TableRow(vec![TextBlock::from("a")]), TableRow(vec![TextBlock::from("b")])],
+                alignments: Vec::new(),
+            })
+        };
+        let elements = vec![
+            MarkdownElement::Comment { comment: "incremental_rows".into(), source_position: Default::default() },
+            table(),
+            table(),
+        ];
+        let options = PresentationBuilderOptions { reveal_style, ..Default::default() };
+        let mut presentation = build_presentation_with_options(elements, options);
+        let mut steps = 0;
+        while presentation.jump_next() {
+            steps += 1;
+        }
+        assert_eq!(steps, 1);
+    }
+
+    #[test]
+    fn slide_steps() {
+        let highlight_groups =
+            vec![HighlightGroup::new(vec![Highlight::Single(1)]), HighlightGroup::new(vec![Highlight::Single(2)])];
+        let elements = vec![
+            MarkdownElement::Comment { comment: "no_footer".into(), source_position: Default::default() },
+            MarkdownElement::Comment { comment: "incremental_lists: true".into(), source_position: Default::default() },
+            MarkdownElement::List(vec![
+                ListItem { depth: 0, contents: "one".into(), item_type: ListItemType::Unordered },
+                ListItem { depth: 0, contents: "two".into(), item_type: ListItemType::Unordered },
+            ]),
+            build_pause(),
+            MarkdownElement::Code(Code {
+                contents: "a\nb".into(),
+                language: CodeLanguage::Unknown,
+                language_name: "".into(),
+                attributes: CodeAttributes { highlight_groups, ..Default::default() },
+            }),
+            MarkdownElement::Comment { comment: "incremental_rows".into(), source_position: Default::default() },
+            MarkdownElement::Table(Table {
+                header: TableRow(vec![TextBlock::from("h")]),
+                rows: ["a", "b", "c"].iter().map(|row| TableRow(vec![TextBlock::from(*row)])).collect(),
+                alignments: Vec::new(),
+            }),
+        ];
+        let options = PresentationBuilderOptions { reveal_style: RevealStyle::Dim, ..Default::default() };
+        let mut presentation = build_presentation_with_options(elements, options);
+        let steps = presentation.current_slide().steps();
+        let kinds: Vec<_> = (0..steps.total()).map(|index| steps.get(index).unwrap().kind).collect();
+        let expected_kinds = &[
+            StepKind::Start,
+            StepKind::ListItem,
+            StepKind::Pause,
+            StepKind::CodeHighlight,
+            StepKind::TableRow,
+            StepKind::TableRow,
+        ];
+        assert_eq!(kinds, expected_kinds);
+
+        for step in 1..expected_kinds.len() {
+            assert!(presentation.jump_next());
+            assert_eq!(presentation.current_step(), step);
+        }
+        assert_eq!(visible_lines(&presentation).last().map(String::as_str), Some("c"));
+        for step in (0..expected_kinds.len() - 1).rev() {
+            assert!(presentation.jump_previous());
+            assert_eq!(presentation.current_step(), step);
+        }
+        assert!(!presentation.jump_previous());
+
+        // Jumping straight to a step shows the same thing as walking up to it.
+        presentation.jump_step(4);
+        let lines = visible_lines(&presentation);
+        assert_eq!(&lines[lines.len() - 3..], &["a", "b", "[c]"]);
+    }
+
+    #[test]
+    fn layout_without_init() {
+        let elements = vec![build_column(0)];
+        let result = try_build_presentation(elements);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn already_in_column() {
+        let elements = vec![
+            MarkdownElement::Comment { comment: "column_layout: [1]".into(), source_position: Default::default() },
+            MarkdownElement::Comment { comment: "column: 0".into(), source_position: Default::default() },
+            MarkdownElement::Comment { comment: "column: 0".into(), source_position: Default::default() },
+        ];
+        let result = try_build_presentation(elements);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn column_index_overflow() {
+        let elements = vec![
+            MarkdownElement::Comment { comment: "column_layout: [1]".into(), source_position: Default::default() },
+            MarkdownElement::Comment { comment: "column: 1".into(), source_position: Default::default() },
+        ];
+        let result = try_build_presentation(elements);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn column_break() {
+        let elements = vec![
+            MarkdownElement::Comment { comment: "column_layout: [1, 1]".into(), source_position: Default::default() },
+            MarkdownElement::Comment { comment: "column: 0".into(), source_position: Default::default() },
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("one".into())]),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("two".into())]),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("three".into())]),
+            MarkdownElement::Comment { comment: "column_break".into(), source_position: Default::default() },
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("four".into())]),
+        ];
+        let presentation = build_presentation(elements);
+        let slide = presentation.iter_slides().next().unwrap();
+        let columns = slide.filter_operations(|operation| matches!(operation, RenderOperation::EnterColumn { .. }));
+        let columns: Vec<_> = columns
+            .into_iter()
+            .map(|operation| match operation {
+                RenderOperation::EnterColumn { column } => *column,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(columns, &[0, 1]);
+    }
+
+    #[rstest]
+    #[case::outside_layout(&[])]
+    #[case::before_entering_column(&["column_layout: [1, 1]"])]
+    #[case::after_last_column(&["column_layout: [1, 1]", "column: 1"])]
+    fn invalid_column_break(#[case] commands: &[&str]) {
+        let mut elements: Vec<_> = commands
+            .iter()
+            .map(|command| MarkdownElement::Comment {
+                comment: command.to_string(),
+                source_position: Default::default(),
+            })
+            .collect();
+        let source_position = SourcePosition { start: LineColumn { line: 4, column: 0 } };
+        elements.push(MarkdownElement::Comment { comment: "column_break".into(), source_position });
+        let error = try_build_presentation(elements).expect_err("build succeeded");
+        assert!(
+            matches!(
+                error,
+                BuildError::ColumnBreakOutsideColumn { slide: 1, line: 5 }
+                    | BuildError::ColumnBreakAfterLastColumn { slide: 1, line: 5 }
+            ),
+            "{error:?}"
+        );
+    }
+
+    #[rstest]
+    #[case::empty("column_layout: []")]
+    #[case::zero("column_layout: [0]")]
+    #[case::one_is_zero("column_layout: [1, 0]")]
+    fn invalid_layouts(#[case] definition: &str) {
+        let elements =
+            vec![MarkdownElement::Comment { comment: definition.into(), source_position: Default::default() }];
+        let result = try_build_presentation(elements);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn operation_without_enter_column() {
+        let elements = vec![
+            MarkdownElement::Comment { comment: "column_layout: [1]".into(), source_position: Default::default() },
+            MarkdownElement::ThematicBreak { source_position: Default::default() },
+        ];
+        let result = try_build_presentation(elements);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::pause("pause", CommentCommand::Pause)]
+    #[case::pause(" pause ", CommentCommand::Pause)]
+    #[case::end_slide("end_slide", CommentCommand::EndSlide)]
+    #[case::column_layout("column_layout: [1, 2]", CommentCommand::InitColumnLayout(vec![1, 2]))]
+    #[case::column("column: 1", CommentCommand::Column(1))]
+    #[case::column_break("column_break", CommentCommand::ColumnBreak)]
+    #[case::reset_layout("reset_layout", CommentCommand::ResetLayout)]
+    #[case::layout("layout: image_right", CommentCommand::Layout("image_right".into()))]
+    #[case::region("region: left", CommentCommand::Region("left".into()))]
+    #[case::countdown("countdown: 5m", CommentCommand::Countdown("5m".into()))]
+    #[case::max_duration("max_duration: 90s", CommentCommand::MaxDuration("90s".into()))]
+    #[case::unnumbered("unnumbered", CommentCommand::Unnumbered)]
+    #[case::theme("theme: dark", CommentCommand::Theme("dark".into()))]
+    #[case::inverse("inverse", CommentCommand::Inverse)]
+    #[case::footer(
+        "footer: {center: CONFIDENTIAL, left: ''}",
+        CommentCommand::Footer(FooterOverride {
+            left: Some("".into()),
+            center: Some("CONFIDENTIAL".into()),
+            right: None
+        })
+    )]
+    #[case::todo("TODO: add benchmarks", CommentCommand::Todo("TODO: add benchmarks".into()))]
+    #[case::fixme(" FIXME wrong numbers", CommentCommand::Todo("FIXME wrong numbers".into()))]
+    #[case::bare_todo("TODO", CommentCommand::Todo("TODO".into()))]
+    #[case::deprecated("jump_to_vertical_center", CommentCommand::JumpToMiddle)]
+    #[case::countdown_seconds("countdown: 45", CommentCommand::Countdown("45".into()))]
+    #[case::incremental_lists("incremental_lists: true", CommentCommand::IncrementalLists(true))]
+    #[case::incremental_lists("new_lines: 2", CommentCommand::NewLines(2))]
+    #[case::incremental_lists("newlines: 2", CommentCommand::NewLines(2))]
+    #[case::incremental_lists("new_line", CommentCommand::NewLine)]
+    #[case::incremental_lists("newline", CommentCommand::NewLine)]
+    fn command_formatting(#[case] input: &str, #[case] expected: CommentCommand) {
+        let parsed: CommentCommand = input.parse().expect("deserialization failed");
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn end_slide_inside_layout() {
+        let elements = vec![build_column_layout(1), build_end_slide()];
+        let presentation = build_presentation(elements);
+        assert_eq!(presentation.iter_slides().count(), 2);
+    }
+
+    #[test]
+    fn end_slide_inside_column() {
+        let elements = vec![build_column_layout(1), build_column(0), build_end_slide()];
+        let presentation = build_presentation(elements);
+        assert_eq!(presentation.iter_slides().count(), 2);
+    }
+
+    #[test]
+    fn fenced_div_layout() {
+        let elements = vec![
+            MarkdownElement::FencedDiv(FencedDiv::Columns(vec![40, 60])),
+            MarkdownElement::FencedDiv(FencedDiv::Column(0)),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("left".into())]),
+            MarkdownElement::FencedDiv(FencedDiv::Column(1)),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("right".into())]),
+            MarkdownElement::FencedDiv(FencedDiv::EndColumns),
+        ];
+        // The command prefix only applies to comments.
+        let options = PresentationBuilderOptions { command_prefix: "cmd:".into(), ..Default::default() };
+        let presentation = build_presentation_with_options(elements, options);
+        let operations: Vec<_> = presentation.into_slides().into_iter().next().unwrap().into_operations();
+        let layout: Vec<_> = operations
+            .iter()
+            .filter(|operation| {
+                matches!(
+                    operation,
+                    RenderOperation::InitColumnLayout { .. }
+                        | RenderOperation::EnterColumn { .. }
+                        | RenderOperation::ExitLayout
+                )
+            })
+            .collect();
+        assert!(matches!(
+            layout.as_slice(),
+            [
+                RenderOperation::InitColumnLayout { columns },
+                RenderOperation::EnterColumn { column: 0 },
+                RenderOperation::EnterColumn { column: 1 },
+                RenderOperation::ExitLayout,
+                ..
+            ] if columns == &[40, 60]
+        ));
+    }
+
+    fn build_comment(comment: &str) -> MarkdownElement {
+        MarkdownElement::Comment { comment: comment.into(), source_position: Default::default() }
+    }
+
+    fn layout_operations(presentation: Presentation) -> Vec<RenderOperation> {
+        let operations = presentation.into_slides().into_iter().next().unwrap().into_operations();
+        operations
+            .into_iter()
+            .filter(|operation| {
+                matches!(operation, RenderOperation::InitColumnLayout { .. } | RenderOperation::EnterColumn { .. })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn named_layout_document_order() {
+        let elements = vec![
+            build_comment("layout: two_column_code"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("left".into())]),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("right".into())]),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("also right".into())]),
+        ];
+        let operations = layout_operations(build_presentation(elements));
+        assert!(matches!(
+            operations.as_slice(),
+            [
+                RenderOperation::InitColumnLayout { columns },
+                RenderOperation::EnterColumn { column: 0 },
+                RenderOperation::EnterColumn { column: 1 },
+            ] if columns == &[3, 2]
+        ));
+    }
+
+    #[test]
+    fn named_layout_explicit_regions() {
+        let elements = vec![
+            build_comment("layout: two_column_code"),
+            build_comment("region: right"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("right".into())]),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("also right".into())]),
+            build_comment("region: left"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("left".into())]),
+        ];
+        let operations = layout_operations(build_presentation(elements));
+        assert!(matches!(
+            operations.as_slice(),
+            [
+                RenderOperation::InitColumnLayout { .. },
+                RenderOperation::EnterColumn { column: 1 },
+                RenderOperation::EnterColumn { column: 0 },
+            ]
+        ));
+    }
+
+    #[test]
+    fn named_layout_document_order_skips_optional_regions() {
+        let elements = vec![
+            build_comment("layout: quote_center"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("quote".into())]),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("author".into())]),
+        ];
+        let operations = layout_operations(build_presentation(elements));
+        assert!(matches!(
+            operations.as_slice(),
+            [RenderOperation::InitColumnLayout { .. }, RenderOperation::EnterColumn { column: 1 }]
+        ));
+    }
+
+    #[test]
+    fn named_layout_optional_regions() {
+        let elements = vec![
+            build_comment("layout: quote_center"),
+            build_comment("region: center"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("quote".into())]),
+        ];
+        build_presentation(elements);
+    }
+
+    #[test]
+    fn todo_notes() {
+        let elements = vec![
+            build_comment("TODO: add benchmarks"),
+            build_end_slide(),
+            MarkdownElement::Comment {
+                comment: "FIXME wrong numbers".into(),
+                source_position: SourcePosition { start: LineColumn { line: 4, column: 0 } },
+            },
+        ];
+        let presentation = build_presentation(elements);
+        let expected = &[
+            TodoNote { slide: SlideReference::Numbered(1), line: 1, text: "TODO: add benchmarks".into() },
+            TodoNote { slide: SlideReference::Numbered(2), line: 5, text: "FIXME wrong numbers".into() },
+        ];
+        assert_eq!(presentation.todos(), expected);
+        // Notes are never rendered.
+        let text = presentation
+            .into_slides()
+            .into_iter()
+            .flat_map(Slide::into_operations)
+            .any(|operation| matches!(operation, RenderOperation::RenderText { .. }));
+        assert!(!text);
+    }
+
+    #[test]
+    fn countdown() {
+        let presentation = build_presentation(vec![build_comment("countdown: 5m")]);
+        let countdowns =
+            presentation.current_slide().filter_operations(|op| matches!(op, RenderOperation::RenderOnDemand(_))).len();
+        assert_eq!(countdowns, 1);
+    }
+
+    #[rstest]
+    #[case::zero("0s")]
+    #[case::unknown_unit("5 minutes")]
+    fn invalid_countdown(#[case] duration: &str) {
+        let result = try_build_presentation(vec![build_comment(&format!("countdown: {duration}"))]);
+        assert!(matches!(result, Err(BuildError::InvalidCountdown(label)) if label == duration));
+    }
+
+    #[test]
+    fn max_duration() {
+        let elements = vec![build_comment("max_duration: 1m30s"), build_end_slide(), build_comment("new_line")];
+        let presentation = build_presentation(elements);
+        let durations: Vec<_> = presentation.iter_slides().map(Slide::max_duration).collect();
+        assert_eq!(durations, &[Some(Duration::from_secs(90)), None]);
+
+        let result = try_build_presentation(vec![build_comment("max_duration: soon")]);
+        assert!(matches!(result, Err(BuildError::InvalidMaxDuration(label)) if label == "soon"));
+    }
+
+    #[test]
+    fn focusable_code() {
+        let elements = vec![
+            build_code(CodeLanguage::Rust, "rust", "let a = 1;\nlet b = 2;"),
+            build_pause(),
+            build_code(CodeLanguage::Rust, "rust", "let c = 3;"),
+        ];
+        let mut presentation = build_presentation(elements);
+        let line_counts = |presentation: &Presentation| -> Vec<usize> {
+            presentation.current_slide().focusable_code().iter().map(|code| code.line_count()).collect()
+        };
+        // Code that hasn't been revealed yet can't be focused.
+        assert_eq!(line_counts(&presentation), &[2]);
+        presentation.jump_next();
+        assert_eq!(line_counts(&presentation), &[2, 1]);
+    }
+
+    #[test]
+    fn details() {
+        let elements = vec![
+            MarkdownElement::Details(DetailsTag::Start { summary: "more".into(), open: false }),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hidden".into())]),
+            MarkdownElement::Details(DetailsTag::End),
+            build_pause(),
+            MarkdownElement::Details(DetailsTag::Start { summary: "even more".into(), open: true }),
+            MarkdownElement::Details(DetailsTag::End),
+        ];
+        let mut presentation = build_presentation(elements);
+        let summaries = |presentation: &Presentation| -> Vec<String> {
+            presentation
+                .current_slide()
+                .iter_operations()
+                .filter_map(|op| match op {
+                    RenderOperation::RenderDynamic(generator) => generator.diffable_content().map(String::from),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(summaries(&presentation), &["more\nhidden"]);
+        assert!(presentation.current_slide().toggle_details());
+        // Everything is expanded so the second time it's collapsed again.
+        assert!(presentation.current_slide().toggle_details());
+
+        presentation.jump_next();
+        assert_eq!(summaries(&presentation), &["more\nhidden", "even more"]);
+    }
+
+    #[test]
+    fn on_demand_in_details() {
+        let elements =
+            vec![build_details_start(), build_comment("countdown: 5m"), MarkdownElement::Details(DetailsTag::End)];
+        let presentation = build_presentation(elements);
+        // The countdown is nested in the block but it still needs to be started along with the slide.
+        assert_eq!(presentation.current_slide().iter_on_demand().count(), 1);
+    }
+
+    #[rstest]
+    #[case::nested(
+        vec![build_details_start(), build_details_start()],
+        |e: &BuildError| matches!(e, BuildError::NestedDetails)
+    )]
+    #[case::unmatched_end(
+        vec![MarkdownElement::Details(DetailsTag::End)],
+        |e: &BuildError| matches!(e, BuildError::UnmatchedDetailsEnd)
+    )]
+    #[case::unclosed(vec![build_details_start()], |e: &BuildError| matches!(e, BuildError::UnclosedDetails))]
+    #[case::unclosed_slide(
+        vec![build_details_start(), build_end_slide()],
+        |e: &BuildError| matches!(e, BuildError::UnclosedDetails)
+    )]
+    #[case::pause(
+        vec![build_details_start(), build_pause(), MarkdownElement::Details(DetailsTag::End)],
+        |e: &BuildError| matches!(e, BuildError::PauseInDetails)
+    )]
+    fn invalid_details(#[case] elements: Vec<MarkdownElement>, #[case] check: fn(&BuildError) -> bool) {
+        let Err(error) = try_build_presentation(elements) else { panic!("build succeeded") };
+        assert!(check(&error), "unexpected error: {error:?}");
+    }
+
+    #[test]
+    fn unknown_named_layout() {
+        let elements = vec![build_end_slide(), build_comment("layout: potato")];
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::UnknownLayout { name, slide: 2 }) if name == "potato"));
+    }
+
+    #[test]
+    fn unknown_layout_region() {
+        let elements = vec![build_comment("layout: two_column_code"), build_comment("region: potato")];
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::UnknownRegion { region, slide: 1, .. }) if region == "potato"));
+    }
+
+    #[test]
+    fn region_without_layout() {
+        let result = try_build_presentation(vec![build_comment("region: left")]);
+        assert!(matches!(result, Err(BuildError::NoLayout)));
+    }
+
+    #[rstest]
+    #[case::end_of_presentation(vec![])]
+    #[case::end_slide(vec![build_end_slide()])]
+    #[case::reset_layout(vec![build_comment("reset_layout")])]
+    fn unfilled_layout_region(#[case] trailer: Vec<MarkdownElement>) {
+        let mut elements = vec![
+            build_comment("layout: two_column_code"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("left".into())]),
+        ];
+        elements.extend(trailer);
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::UnfilledRegion { region, slide: 1, .. }) if region == "right"));
+    }
+
+    #[test]
+    fn pause_inside_layout() {
+        let elements = vec![build_column_layout(1), build_pause(), build_column(0)];
+        let presentation = build_presentation(elements);
+        assert_eq!(presentation.iter_slides().count(), 1);
+    }
+
+    #[test]
+    fn iterate_list() {
+        let iter = ListIterator::new(
+            vec![
+                ListItem { depth: 0, contents: "0".into(), item_type: ListItemType::Unordered },
+                ListItem { depth: 0, contents: "1".into(), item_type: ListItemType::Unordered },
+                ListItem { depth: 1, contents: "00".into(), item_type: ListItemType::Unordered },
+                ListItem { depth: 1, contents: "01".into(), item_type: ListItemType::Unordered },
+                ListItem { depth: 1, contents: "02".into(), item_type: ListItemType::Unordered },
+                ListItem { depth: 2, contents: "001".into(), item_type: ListItemType::Unordered },
+                ListItem { depth: 0, contents: "2".into(), item_type: ListItemType::Unordered },
+            ],
+            0,
+        );
+        let expected_indexes = [0, 1, 0, 1, 2, 0, 2];
+        let indexes: Vec<_> = iter.map(|item| item.index).collect();
+        assert_eq!(indexes, expected_indexes);
+    }
+
+    #[test]
+    fn iterate_list_starting_from_other() {
+        let list = ListIterator::new(
+            vec![
+                ListItem { depth: 0, contents: "0".into(), item_type: ListItemType::Unordered },
+                ListItem { depth: 0, contents: "1".into(), item_type: ListItemType::Unordered },
+            ],
+            3,
+        );
+        let expected_indexes = [3, 4];
+        let indexes: Vec<_> = list.into_iter().map(|item| item.index).collect();
+        assert_eq!(indexes, expected_indexes);
+    }
+
+    #[test]
+    fn ordered_list_with_pauses() {
+        let elements = vec![
+            MarkdownElement::List(vec![
+                ListItem { depth: 0, contents: "one".into(), item_type: ListItemType::OrderedPeriod { start: 1 } },
+                ListItem { depth: 1, contents: "one_one".into(), item_type: ListItemType::OrderedPeriod { start: 1 } },
+                ListItem { depth: 1, contents: "one_two".into(), item_type: ListItemType::OrderedPeriod { start: 1 } },
+            ]),
+            build_pause(),
+            MarkdownElement::List(vec![ListItem {
+                depth: 0,
+                contents: "two".into(),
+                item_type: ListItemType::OrderedPeriod { start: 1 },
+            }]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let lines = extract_slide_text_lines(slides.into_iter().next().unwrap());
+        let expected_lines = &["   1. one", "      1. one_one", "      2. one_two", "   2. two"];
+        assert_eq!(lines, expected_lines);
+    }
+
+    #[test]
+    fn description_list() {
+        let elements = vec![MarkdownElement::DescriptionList(vec![DescriptionItem {
+            term: "term".into(),
+            definitions: vec!["first".into(), "second".into()],
+        }])];
+        let slides = build_presentation(elements).into_slides();
+        let lines = extract_slide_text_lines(slides.into_iter().next().unwrap());
+        assert_eq!(lines, &["term", "first", "second"]);
+    }
+
+    #[test]
+    fn automatic_pauses() {
+        let elements = vec![
+            MarkdownElement::Comment { comment: "incremental_lists: true".into(), source_position: Default::default() },
+            MarkdownElement::List(vec![
+                ListItem { depth: 0, contents: "one".into(), item_type: ListItemType::Unordered },
+                ListItem { depth: 1, contents: "two".into(), item_type: ListItemType::Unordered },
+                ListItem { depth: 0, contents: "three".into(), item_type: ListItemType::Unordered },
+            ]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides[0].iter_chunks().count(), 3);
+    }
+
+    #[test]
+    fn ordered_list_start() {
+        let elements = vec![
+            MarkdownElement::List(vec![
+                ListItem { depth: 0, contents: "First".into(), item_type: ListItemType::OrderedPeriod { start: 3 } },
+                ListItem { depth: 1, contents: "Sub".into(), item_type: ListItemType::OrderedParens { start: 0 } },
+                ListItem { depth: 0, contents: "Second".into(), item_type: ListItemType::OrderedPeriod { start: 3 } },
+            ]),
+            build_pause(),
+            MarkdownElement::List(vec![ListItem {
+                depth: 0,
+                contents: "Third".into(),
+                item_type: ListItemType::OrderedPeriod { start: 5 },
+            }]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let lines = extract_slide_text_lines(slides.into_iter().next().unwrap());
+        let expected_lines = &["   3. First", "      0) Sub", "   4. Second", "   5. Third"];
+        assert_eq!(lines, expected_lines);
+    }
+
+    fn build_ordered_list(items: &[(u8, &str)]) -> MarkdownElement {
+        let items = items
+            .iter()
+            .map(|(depth, contents)| ListItem {
+                depth: *depth,
+                contents: (*contents).into(),
+                item_type: ListItemType::OrderedPeriod { start: 1 },
+            })
+            .collect();
+        MarkdownElement::List(items)
+    }
+
+    #[test]
+    fn list_continue_across_slides() {
+        let list_continue =
+            || MarkdownElement::Comment { comment: "list_continue".into(), source_position: Default::default() };
+        let elements = vec![
+            build_ordered_list(&[(0, "one"), (0, "two"), (1, "nested")]),
+            build_end_slide(),
+            list_continue(),
+            build_ordered_list(&[(0, "three"), (0, "four")]),
+            build_end_slide(),
+            list_continue(),
+            build_ordered_list(&[(0, "five")]),
+            build_ordered_list(&[(0, "restart")]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let lines: Vec<_> = slides.into_iter().map(extract_slide_text_lines).collect();
+        let expected_lines = [
+            vec!["   1. one", "   2. two", "      1. nested"],
+            vec!["   3. three", "   4. four"],
+            vec!["   5. five", "   1. restart"],
+        ];
+        assert_eq!(lines, expected_lines);
+    }
+
+    #[test]
+    fn ordered_list_marker_alignment() {
+        let items: Vec<_> = (1..=10).map(|index| (0, if index == 1 { "first" } else { "item" })).collect();
+        let slides = build_presentation(vec![build_ordered_list(&items)]).into_slides();
+        let lines = extract_slide_text_lines(slides.into_iter().next().unwrap());
+        assert_eq!(lines[0], "    1. first");
+        assert_eq!(lines[9], "   10. item");
+    }
+
+    #[test]
+    fn themed_ordered_list_markers() {
+        let elements = vec![
+            MarkdownElement::FrontMatter("theme:\n  override:\n    list:\n      ordered_markers: ['I.', 'a.']".into()),
+            build_ordered_list(&[(0, "one"), (1, "nested"), (1, "other"), (2, "deep"), (0, "two"), (0, "three")]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let lines = extract_slide_text_lines(slides.into_iter().next().unwrap());
+        let expected_lines =
+            &["     I. one", "      a. nested", "      b. other", "         I. deep", "    II. two", "   III. three"];
+        assert_eq!(lines, expected_lines);
+    }
+
+    #[test]
+    fn pause_after_list() {
         let elements = vec![
             MarkdownElement::List(vec![ListItem {
                 depth: 0,
                 contents: "one".into(),
-                item_type: ListItemType::OrderedPeriod,
+                item_type: ListItemType::OrderedPeriod { start: 1 },
             }]),
             build_pause(),
-            MarkdownElement::Heading { level: 1, text: "hi".into() },
+            build_heading(1, "hi"),
             MarkdownElement::List(vec![ListItem {
                 depth: 0,
                 contents: "two".into(),
-                item_type: ListItemType::OrderedPeriod,
+                item_type: ListItemType::OrderedPeriod { start: 1 },
             }]),
         ];
         let slides = build_presentation(elements).into_slides();
         let first_chunk = &slides[0];
         let operations = first_chunk.iter_operations().collect::<Vec<_>>();
         // This is pretty easy to break, refactor soon
-        let last_operation = &operations[operations.len() - 4];
+        let last_operation = &operations[operations.len() - 5];
         assert!(matches!(last_operation, RenderOperation::RenderLineBreak), "last operation is {last_operation:?}");
     }
 
@@ -1364,14 +3730,14 @@ mod test {
     fn implicit_slide_ends() {
         let elements = vec![
             // first slide
-            MarkdownElement::SetexHeading { text: "hi".into() },
+            build_slide_title("hi"),
             // second
-            MarkdownElement::SetexHeading { text: "hi".into() },
-            MarkdownElement::Heading { level: 1, text: "hi".into() },
+            build_slide_title("hi"),
+            build_heading(1, "hi"),
             // explicitly ends
             MarkdownElement::Comment { comment: "end_slide".into(), source_position: Default::default() },
             // third starts
-            MarkdownElement::SetexHeading { text: "hi".into() },
+            build_slide_title("hi"),
         ];
         let options = PresentationBuilderOptions { implicit_slide_ends: true, ..Default::default() };
         let slides = build_presentation_with_options(elements, options).into_slides();
@@ -1380,15 +3746,29 @@ mod test {
 
     #[test]
     fn implicit_slide_ends_with_front_matter() {
-        let elements = vec![
-            MarkdownElement::FrontMatter("theme:\n name: light".into()),
-            MarkdownElement::SetexHeading { text: "hi".into() },
-        ];
+        let elements = vec![MarkdownElement::FrontMatter("theme:\n name: light".into()), build_slide_title("hi")];
         let options = PresentationBuilderOptions { implicit_slide_ends: true, ..Default::default() };
         let slides = build_presentation_with_options(elements, options).into_slides();
         assert_eq!(slides.len(), 1);
     }
 
+    #[rstest]
+    #[case::rgb("[\"000000\", \"ffffff\"]", true, None)]
+    #[case::named("[red, \"ffffff\"]", false, Some("red"))]
+    fn background_gradient(#[case] colors: &str, #[case] gradient: bool, #[case] background: Option<&str>) {
+        let front_matter = format!("theme:\n  override:\n    background_gradient: {colors}");
+        let elements = vec![MarkdownElement::FrontMatter(front_matter), MarkdownElement::Paragraph(vec![])];
+        let slides = build_presentation(elements).into_slides();
+        let operations: Vec<_> = slides[0].iter_operations().collect();
+        let has_gradient =
+            operations.iter().any(|operation| matches!(operation, RenderOperation::RenderBackgroundGradient(_)));
+        assert_eq!(has_gradient, gradient);
+
+        let Some(RenderOperation::SetColors(colors)) = operations.first() else { panic!("no colors set") };
+        let background = background.map(|color| color.parse().expect("invalid color"));
+        assert_eq!(colors.background, background);
+    }
+
     #[rstest]
     #[case::multiline("hello\nworld")]
     #[case::many_open_braces("{{{")]
@@ -1422,7 +3802,7 @@ mod test {
         let options = PresentationBuilderOptions { end_slide_shorthand: true, ..Default::default() };
         let elements = vec![
             MarkdownElement::Paragraph(vec![]),
-            MarkdownElement::ThematicBreak,
+            MarkdownElement::ThematicBreak { source_position: Default::default() },
             MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
         ];
         let presentation = build_presentation_with_options(elements, options);
@@ -1441,4 +3821,134 @@ mod test {
         let result = try_build_presentation_with_options(elements, options);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn big_text_only_applies_to_next_heading() {
+        let elements = vec![
+            MarkdownElement::Comment { comment: "big_text".into(), source_position: Default::default() },
+            build_slide_title("hi"),
+            build_heading(1, "bye"),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let operations = slides.into_iter().next().unwrap().into_operations();
+        let dimensions = WindowSize { rows: 50, columns: 100, height: 0, width: 0 };
+        let big_rows: Vec<_> = operations
+            .iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderDynamic(generator) => Some(generator.as_render_operations(&dimensions)),
+                _ => None,
+            })
+            .filter(|operations| {
+                operations.iter().filter(|op| matches!(op, RenderOperation::RenderText { .. })).count() > 1
+            })
+            .collect();
+        assert_eq!(big_rows.len(), 1);
+        assert_eq!(extract_text_lines(&operations), &["bye"]);
+    }
+
+    #[test]
+    fn inverse_only_applies_to_next_element() {
+        let elements = vec![
+            MarkdownElement::Comment { comment: "inverse".into(), source_position: Default::default() },
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("bye".into())]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let operations = slides.into_iter().next().unwrap().into_operations();
+        let inverse: Vec<_> = operations
+            .iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderText { line, .. } => Some(line.iter_texts()),
+                _ => None,
+            })
+            .flatten()
+            .map(|text| (text.text().content.as_str(), text.text().style.is_inverse()))
+            .collect();
+        assert_eq!(inverse, &[("hi", true), ("bye", false)]);
+    }
+
+    #[test]
+    fn glossary_block_quote() {
+        let elements = vec![
+            MarkdownElement::FrontMatter("glossary:\n  API: application programming interface".into()),
+            MarkdownElement::BlockQuote(vec![QuoteLine { text: "an API".into(), depth: 0 }]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let operations = slides.into_iter().next().unwrap().into_operations();
+        let expansion = operations
+            .iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderText { line, .. } => Some(line.iter_texts()),
+                _ => None,
+            })
+            .flatten()
+            .find(|text| text.text().content == "API")
+            .and_then(|text| text.text().expansion.clone());
+        assert_eq!(expansion.as_deref(), Some("application programming interface"));
+    }
+
+    #[test]
+    fn inverse_block_quote() {
+        let elements = vec![
+            MarkdownElement::Comment { comment: "inverse".into(), source_position: Default::default() },
+            MarkdownElement::BlockQuote(vec![QuoteLine { text: "hi".into(), depth: 0 }]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let operations = slides.into_iter().next().unwrap().into_operations();
+        let inverse = operations
+            .iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderText { line, .. } => Some(line.iter_texts()),
+                _ => None,
+            })
+            .flatten()
+            .find(|text| text.text().content == "hi")
+            .map(|text| text.text().style.is_inverse());
+        assert_eq!(inverse, Some(true));
+    }
+
+    #[test]
+    fn shortcodes_outside_elements() {
+        let elements = vec![
+            MarkdownElement::FrontMatter("title: 'launch :rocket:'\nauthor: 'bob :wave:'".into()),
+            MarkdownElement::BlockQuote(vec![QuoteLine { text: "go :rocket:".into(), depth: 0 }]),
+            MarkdownElement::Details(DetailsTag::Start { summary: "more :rocket:".into(), open: false }),
+            MarkdownElement::Details(DetailsTag::End),
+        ];
+        let options = PresentationBuilderOptions { shortcode_emoji: true, ..Default::default() };
+        let mut slides = build_presentation_with_options(elements, options).into_slides().into_iter();
+        assert_eq!(extract_slide_text_lines(slides.next().unwrap()), &["launch 🚀", "bob 👋"]);
+
+        let slide = slides.next().unwrap();
+        let summary = slide.iter_operations().find_map(|operation| match operation {
+            RenderOperation::RenderDynamic(generator) => generator.diffable_content().map(String::from),
+            _ => None,
+        });
+        assert_eq!(summary.as_deref(), Some("more 🚀"));
+        assert_eq!(extract_slide_text_lines(slide), &["go 🚀"]);
+    }
+
+    #[rstest]
+    #[case::under_limit(2, &["a", "b"])]
+    #[case::one_over(1, &["a", "... (1 more element)"])]
+    #[case::many_over(0, &["... (2 more elements)"])]
+    fn max_elements_per_render(#[case] max_elements: usize, #[case] expected: &[&str]) {
+        let elements = vec![
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("a".into())]),
+            build_pause(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("b".into())]),
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("c".into())]),
+        ];
+        let options = PresentationBuilderOptions { max_elements_per_render: Some(max_elements), ..Default::default() };
+        let mut slides = build_presentation_with_options(elements, options).into_slides();
+        assert_eq!(slides.len(), 2);
+
+        let second = slides.pop().unwrap();
+        let first = slides.pop().unwrap();
+        assert_eq!(extract_text_lines(&first.into_operations()), expected);
+        // The limit is applied per slide.
+        let expected_second: &[&str] = if max_elements == 0 { &["... (1 more element)"] } else { &["c"] };
+        assert_eq!(extract_text_lines(&second.into_operations()), expected_second);
+    }
 }
@@ -1,31 +1,30 @@
-use super::padding::NumberPadder;
+use super::{padding::NumberPadder, redaction::Redactions};
 use crate::{
     markdown::elements::{Code, HighlightGroup},
-    presentation::{AsRenderOperations, ChunkMutator, PreformattedLine, RenderOperation},
+    presentation::{AsRenderOperations, ChunkMutator, PreformattedLine, RenderOperation, StepKind},
     render::{
         highlighting::{LanguageHighlighter, StyledTokens},
         properties::WindowSize,
     },
-    theme::{Alignment, CodeBlockStyle},
-    PresentationTheme,
+    theme::{Alignment, CodeBlockStyle, CornerStyle},
 };
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, env, iter, rc::Rc};
 use syntect::highlighting::Style;
 use unicode_width::UnicodeWidthStr;
 
 pub(crate) struct CodePreparer<'a> {
-    theme: &'a PresentationTheme,
+    style: &'a CodeBlockStyle,
 }
 
 impl<'a> CodePreparer<'a> {
-    pub(crate) fn new(theme: &'a PresentationTheme) -> Self {
-        Self { theme }
+    pub(crate) fn new(style: &'a CodeBlockStyle) -> Self {
+        Self { style }
     }
 
     pub(crate) fn prepare(&self, code: &Code) -> Vec<CodeLine> {
         let mut lines = Vec::new();
-        let horizontal_padding = self.theme.code.padding.horizontal.unwrap_or(0);
-        let vertical_padding = self.theme.code.padding.vertical.unwrap_or(0);
+        let horizontal_padding = self.style.padding.horizontal.unwrap_or(0);
+        let vertical_padding = self.style.padding.vertical.unwrap_or(0);
         if vertical_padding > 0 {
             lines.push(CodeLine::empty());
         }
@@ -33,6 +32,9 @@ impl<'a> CodePreparer<'a> {
         if vertical_padding > 0 {
             lines.push(CodeLine::empty());
         }
+        if let Some(style) = self.style.border {
+            draw_border(&mut lines, BorderGlyphs::supported(style, locale().as_deref()));
+        }
         lines
     }
 
@@ -58,6 +60,57 @@ impl<'a> CodePreparer<'a> {
     }
 }
 
+/// Surround code lines with a border.
+///
+/// Every line is padded to the width of the longest one so the right border is a straight line.
+fn draw_border(lines: &mut Vec<CodeLine>, glyphs: BorderGlyphs) {
+    let width = lines.iter().map(CodeLine::width).max().unwrap_or(0);
+    for line in lines.iter_mut() {
+        let padding = width - line.width();
+        line.prefix.insert(0, glyphs.vertical);
+        line.suffix.extend(iter::repeat(' ').take(padding));
+        line.suffix.push(glyphs.vertical);
+    }
+    let horizontal: String = iter::repeat(glyphs.horizontal).take(width).collect();
+    let [top_left, top_right, bottom_left, bottom_right] = glyphs.corners;
+    lines.insert(0, CodeLine::border(format!("{top_left}{horizontal}{top_right}")));
+    lines.push(CodeLine::border(format!("{bottom_left}{horizontal}{bottom_right}")));
+}
+
+fn locale() -> Option<String> {
+    ["LC_ALL", "LC_CTYPE", "LANG"].into_iter().filter_map(|name| env::var(name).ok()).find(|value| !value.is_empty())
+}
+
+#[derive(Debug, PartialEq)]
+struct BorderGlyphs {
+    corners: [char; 4],
+    horizontal: char,
+    vertical: char,
+}
+
+impl BorderGlyphs {
+    const ASCII: Self = Self { corners: ['+', '+', '+', '+'], horizontal: '-', vertical: '|' };
+
+    // Box drawing characters are only used when the terminal is known to use UTF-8 as they're
+    // missing from most legacy encodings. Terminals that don't set a locale at all are assumed to
+    // support them.
+    fn supported(style: CornerStyle, locale: Option<&str>) -> Self {
+        let is_utf8 = |locale: &str| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        };
+        if locale.map_or(true, is_utf8) { Self::new(style) } else { Self::ASCII }
+    }
+
+    fn new(style: CornerStyle) -> Self {
+        match style {
+            CornerStyle::Sharp => Self { corners: ['┌', '┐', '└', '┘'], horizontal: '─', vertical: '│' },
+            CornerStyle::Rounded => Self { corners: ['╭', '╮', '╰', '╯'], horizontal: '─', vertical: '│' },
+            CornerStyle::Heavy => Self { corners: ['┏', '┓', '┗', '┛'], horizontal: '━', vertical: '┃' },
+        }
+    }
+}
+
 pub(crate) struct CodeLine {
     pub(crate) prefix: String,
     pub(crate) code: String,
@@ -70,6 +123,10 @@ impl CodeLine {
         Self { prefix: String::new(), code: "\n".into(), suffix: String::new(), line_number: None }
     }
 
+    fn border(border: String) -> Self {
+        Self { prefix: border, code: "\n".into(), suffix: String::new(), line_number: None }
+    }
+
     pub(crate) fn width(&self) -> usize {
         self.prefix.width() + self.code.width() + self.suffix.width()
     }
@@ -79,9 +136,17 @@ impl CodeLine {
         padding_style: &Style,
         code_highlighter: &mut LanguageHighlighter,
         block_style: &CodeBlockStyle,
+        redactions: &Redactions,
     ) -> String {
         let mut output = StyledTokens { style: *padding_style, tokens: &self.prefix }.apply_style(block_style);
-        output.push_str(&code_highlighter.highlight_line(&self.code, block_style));
+        if redactions.is_empty() {
+            output.push_str(&code_highlighter.highlight_line(&self.code, block_style));
+        } else {
+            let tokens = code_highlighter.style_line(&self.code).map(|tokens| (tokens.style, tokens.tokens)).collect();
+            for (style, tokens) in redactions.redact_tokens(tokens) {
+                output.push_str(&StyledTokens { style, tokens: &tokens }.apply_style(block_style));
+            }
+        }
         output.push_str(&StyledTokens { style: *padding_style, tokens: &self.suffix }.apply_style(block_style));
         output
     }
@@ -107,8 +172,12 @@ pub(crate) struct HighlightedLine {
 impl AsRenderOperations for HighlightedLine {
     fn as_render_operations(&self, _: &WindowSize) -> Vec<RenderOperation> {
         let context = self.context.borrow();
-        let group = &context.groups[context.current];
-        let needs_highlight = self.line_number.map(|number| group.contains(number)).unwrap_or_default();
+        // Code without any highlight groups, like the one built outside of the parser, is
+        // highlighted as a whole.
+        let needs_highlight = match context.groups.get(context.current) {
+            Some(group) => self.line_number.map(|number| group.contains(number)).unwrap_or_default(),
+            None => true,
+        };
         // TODO: Cow<str>?
         let text = match needs_highlight {
             true => self.highlighted.clone(),
@@ -152,16 +221,6 @@ impl ChunkMutator for HighlightMutator {
         }
     }
 
-    fn mutate_previous(&self) -> bool {
-        let mut context = self.context.borrow_mut();
-        if context.current == 0 {
-            false
-        } else {
-            context.current -= 1;
-            true
-        }
-    }
-
     fn reset_mutations(&self) {
         self.context.borrow_mut().current = 0;
     }
@@ -175,13 +234,21 @@ impl ChunkMutator for HighlightMutator {
         let context = self.context.borrow();
         (context.current, context.groups.len())
     }
+
+    fn step_kind(&self) -> StepKind {
+        StepKind::CodeHighlight
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::markdown::elements::{CodeAttributes, CodeLanguage};
+    use crate::{
+        markdown::elements::{CodeAttributes, CodeLanguage},
+        theme::PaddingRect,
+    };
 
     use super::*;
+    use rstest::rstest;
 
     #[test]
     fn code_with_line_numbers() {
@@ -190,9 +257,10 @@ mod test {
         let code = Code {
             contents: input_lines,
             language: CodeLanguage::Unknown,
+            language_name: String::new(),
             attributes: CodeAttributes { line_numbers: true, ..Default::default() },
         };
-        let lines = CodePreparer { theme: &Default::default() }.prepare(&code);
+        let lines = CodePreparer { style: &Default::default() }.prepare(&code);
         assert_eq!(lines.len(), total_lines);
 
         let mut lines = lines.into_iter().enumerate();
@@ -207,4 +275,57 @@ mod test {
             assert_eq!(&line.prefix, &format!("{line_number} "));
         }
     }
+
+    fn render_lines(lines: &[CodeLine]) -> Vec<String> {
+        lines.iter().map(|line| format!("{}{}{}", line.prefix, line.code.trim_end(), line.suffix)).collect()
+    }
+
+    #[rstest]
+    #[case::sharp(CornerStyle::Sharp, &["┌─────┐", "│ a   │", "│ bcd │", "└─────┘"])]
+    #[case::rounded(CornerStyle::Rounded, &["╭─────╮", "│ a   │", "│ bcd │", "╰─────╯"])]
+    #[case::heavy(CornerStyle::Heavy, &["┏━━━━━┓", "┃ a   ┃", "┃ bcd ┃", "┗━━━━━┛"])]
+    fn border(#[case] style: CornerStyle, #[case] expected: &[&str]) {
+        let code = Code {
+            contents: "a\nbcd".into(),
+            language: CodeLanguage::Unknown,
+            language_name: String::new(),
+            attributes: Default::default(),
+        };
+        let style = CodeBlockStyle {
+            border: Some(style),
+            padding: PaddingRect { horizontal: Some(1), vertical: None },
+            ..Default::default()
+        };
+        let lines = CodePreparer { style: &style }.prepare(&code);
+        assert_eq!(render_lines(&lines), expected);
+    }
+
+    #[test]
+    fn border_with_vertical_padding() {
+        let mut lines = vec![CodeLine::empty(), CodeLine::border("ab".into())];
+        draw_border(&mut lines, BorderGlyphs::new(CornerStyle::Sharp));
+        assert_eq!(render_lines(&lines), &["┌──┐", "│  │", "│ab│", "└──┘"]);
+    }
+
+    #[rstest]
+    #[case::no_locale(CornerStyle::Rounded, None, BorderGlyphs::new(CornerStyle::Rounded))]
+    #[case::utf8(CornerStyle::Rounded, Some("en_US.UTF-8"), BorderGlyphs::new(CornerStyle::Rounded))]
+    #[case::utf8_lowercase(CornerStyle::Heavy, Some("C.utf8"), BorderGlyphs::new(CornerStyle::Heavy))]
+    #[case::latin1(CornerStyle::Rounded, Some("en_US.ISO-8859-1"), BorderGlyphs::ASCII)]
+    #[case::sharp_posix(CornerStyle::Sharp, Some("C"), BorderGlyphs::ASCII)]
+    #[case::heavy_posix(CornerStyle::Heavy, Some("C"), BorderGlyphs::ASCII)]
+    fn border_glyphs_fallback(
+        #[case] style: CornerStyle,
+        #[case] locale: Option<&str>,
+        #[case] expected: BorderGlyphs,
+    ) {
+        assert_eq!(BorderGlyphs::supported(style, locale), expected);
+    }
+
+    #[test]
+    fn ascii_border() {
+        let mut lines = vec![CodeLine::border("ab".into())];
+        draw_border(&mut lines, BorderGlyphs::ASCII);
+        assert_eq!(render_lines(&lines), &["+--+", "|ab|", "+--+"]);
+    }
 }
@@ -0,0 +1,414 @@
+use super::bigtext::BigText;
+use crate::{
+    custom::{CountdownReturn, CountdownStart},
+    markdown::{
+        elements::{Text, TextBlock},
+        text::WeightedTextBlock,
+    },
+    presentation::{
+        AsRenderOperations, RenderOnDemand, RenderOnDemandStartPolicy, RenderOnDemandState, RenderOperation,
+    },
+    render::{properties::WindowSize, region::PaintRegion},
+    style::{Colors, TextStyle},
+    theme::Alignment,
+};
+use std::{
+    cell::RefCell,
+    mem,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// The time left in a countdown when it starts using its final colors.
+const FINAL_STRETCH: Duration = Duration::from_secs(30);
+
+/// Parse a countdown duration like `5m`, `90s`, `1h30m` or `45`, which is a number of seconds.
+pub(crate) fn parse_duration(input: &str) -> Option<Duration> {
+    let input = input.trim();
+    if let Ok(seconds) = input.parse::<u64>() {
+        return (seconds > 0).then(|| Duration::from_secs(seconds));
+    }
+    let mut total = 0;
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let multiplier = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let value: u64 = number.parse().ok()?;
+        total += value.checked_mul(multiplier)?;
+        number.clear();
+    }
+    if !number.is_empty() || total == 0 {
+        return None;
+    }
+    Some(Duration::from_secs(total))
+}
+
+/// A clock that counts down from a duration and that can be paused and resumed.
+///
+/// Time only goes by while the clock is running and the terminal is focused.
+#[derive(Clone, Debug)]
+pub(crate) struct CountdownClock {
+    duration: Duration,
+    elapsed: Duration,
+    running: bool,
+    focused: bool,
+    counting_since: Option<Instant>,
+}
+
+impl CountdownClock {
+    pub(crate) fn new(duration: Duration) -> Self {
+        Self { duration, elapsed: Duration::ZERO, running: false, focused: true, counting_since: None }
+    }
+
+    /// Start or resume the countdown.
+    ///
+    /// Returns `false` if the clock was already running or there's no time left.
+    pub(crate) fn start(&mut self, now: Instant) -> bool {
+        if self.running || self.remaining(now).is_zero() {
+            return false;
+        }
+        self.running = true;
+        self.checkpoint(now);
+        true
+    }
+
+    /// Pause the countdown, keeping track of how much time had elapsed so far.
+    pub(crate) fn pause(&mut self, now: Instant) {
+        self.running = false;
+        self.checkpoint(now);
+    }
+
+    /// Go back to the beginning of the countdown.
+    pub(crate) fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.running = false;
+        self.counting_since = None;
+    }
+
+    /// Set whether the terminal is focused.
+    ///
+    /// Losing focus stops time from going by without pausing the clock, so it picks up where it
+    /// left off once focus comes back.
+    pub(crate) fn set_focused(&mut self, focused: bool, now: Instant) {
+        self.focused = focused;
+        self.checkpoint(now);
+    }
+
+    /// Whether this clock was started and hasn't run out yet.
+    pub(crate) fn is_started(&self) -> bool {
+        !self.elapsed.is_zero() || self.running
+    }
+
+    pub(crate) fn remaining(&self, now: Instant) -> Duration {
+        self.duration.saturating_sub(self.elapsed(now))
+    }
+
+    fn elapsed(&self, now: Instant) -> Duration {
+        let counting = self.counting_since.map(|since| now.saturating_duration_since(since)).unwrap_or_default();
+        (self.elapsed + counting).min(self.duration)
+    }
+
+    // Accumulate the time that went by so far and start counting again if time should be going by.
+    fn checkpoint(&mut self, now: Instant) {
+        self.elapsed = self.elapsed(now);
+        self.counting_since = (self.running && self.focused).then_some(now);
+    }
+}
+
+#[derive(Debug)]
+struct CountdownInner {
+    clock: CountdownClock,
+    state: RenderOnDemandState,
+    bell_rung: bool,
+    bell_pending: bool,
+}
+
+/// A countdown timer rendered using big block characters.
+#[derive(Debug)]
+pub(crate) struct CountdownOperation {
+    label: String,
+    colors: Colors,
+    final_colors: Colors,
+    alignment: Alignment,
+    start: CountdownStart,
+    on_return: CountdownReturn,
+    bell: bool,
+    inner: RefCell<CountdownInner>,
+}
+
+impl CountdownOperation {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        label: String,
+        duration: Duration,
+        colors: Colors,
+        final_colors: Colors,
+        alignment: Alignment,
+        start: CountdownStart,
+        on_return: CountdownReturn,
+        bell: bool,
+    ) -> Self {
+        let inner = CountdownInner {
+            clock: CountdownClock::new(duration),
+            state: Default::default(),
+            bell_rung: false,
+            bell_pending: false,
+        };
+        Self { label, colors, final_colors, alignment, start, on_return, bell, inner: RefCell::new(inner) }
+    }
+
+    /// Make time go by in this countdown without waiting for it.
+    #[cfg(test)]
+    pub(crate) fn advance(&self, duration: Duration) {
+        let clock = &mut self.inner.borrow_mut().clock;
+        clock.elapsed = (clock.elapsed + duration).min(clock.duration);
+    }
+
+    fn big_text(&self) -> BigText {
+        let remaining = self.inner.borrow().clock.remaining(Instant::now());
+        let text = format_remaining(remaining);
+        let colors = if remaining <= FINAL_STRETCH { &self.final_colors } else { &self.colors };
+        let style = TextStyle::default().colors(colors.clone());
+        let fallback = WeightedTextBlock::from(vec![Text::new(text.clone(), style)]);
+        BigText::new(text, colors.clone(), self.alignment.clone(), fallback)
+    }
+}
+
+impl AsRenderOperations for CountdownOperation {
+    fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
+        self.big_text().as_render_operations(dimensions)
+    }
+
+    fn diffable_content(&self) -> Option<&str> {
+        Some(&self.label)
+    }
+
+    fn region_painter(self: Rc<Self>) -> Option<Rc<dyn PaintRegion>> {
+        Some(self)
+    }
+}
+
+impl PaintRegion for CountdownOperation {
+    fn region_name(&self) -> &'static str {
+        "countdown"
+    }
+
+    fn region_lines(&self, width: u16) -> Vec<TextBlock> {
+        self.big_text().lines(width)
+    }
+}
+
+impl RenderOnDemand for CountdownOperation {
+    fn start_render(&self) -> bool {
+        let mut inner = self.inner.borrow_mut();
+        if !inner.clock.start(Instant::now()) {
+            return false;
+        }
+        inner.state = RenderOnDemandState::Rendering;
+        true
+    }
+
+    fn poll_state(&self) -> RenderOnDemandState {
+        let mut inner = self.inner.borrow_mut();
+        if matches!(inner.state, RenderOnDemandState::Rendering) && inner.clock.remaining(Instant::now()).is_zero() {
+            inner.state = RenderOnDemandState::Rendered;
+            if self.bell && !inner.bell_rung {
+                inner.bell_rung = true;
+                inner.bell_pending = true;
+            }
+        }
+        inner.state.clone()
+    }
+
+    fn pause_render(&self) {
+        let mut inner = self.inner.borrow_mut();
+        match self.on_return {
+            CountdownReturn::Resume => inner.clock.pause(Instant::now()),
+            CountdownReturn::Reset => {
+                inner.clock.reset();
+                inner.bell_rung = false;
+            }
+        };
+        if matches!(inner.state, RenderOnDemandState::Rendering) || !inner.clock.is_started() {
+            inner.state = RenderOnDemandState::NotStarted;
+        }
+    }
+
+    fn focus_changed(&self, focused: bool) {
+        self.inner.borrow_mut().clock.set_focused(focused, Instant::now());
+    }
+
+    fn take_bell(&self) -> bool {
+        mem::take(&mut self.inner.borrow_mut().bell_pending)
+    }
+
+    fn start_policy(&self) -> RenderOnDemandStartPolicy {
+        // Once started, a countdown always resumes when its slide is displayed again.
+        if self.start == CountdownStart::Shown || self.inner.borrow().clock.is_started() {
+            RenderOnDemandStartPolicy::Visible
+        } else {
+            RenderOnDemandStartPolicy::OnDemand
+        }
+    }
+}
+
+fn format_remaining(remaining: Duration) -> String {
+    // Round up so the countdown only shows zero once it's actually done.
+    let seconds = remaining.as_millis().div_ceil(1000) as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 { format!("{hours}:{minutes:02}:{seconds:02}") } else { format!("{minutes:02}:{seconds:02}") }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::seconds("45", Some(45))]
+    #[case::seconds_suffix("90s", Some(90))]
+    #[case::minutes("5m", Some(300))]
+    #[case::combined("1h30m15s", Some(5415))]
+    #[case::zero("0", None)]
+    #[case::zero_minutes("0m", None)]
+    #[case::empty("", None)]
+    #[case::no_unit_at_end("5m30", None)]
+    #[case::unknown_unit("5d", None)]
+    #[case::no_number("m", None)]
+    fn durations(#[case] input: &str, #[case] expected: Option<u64>) {
+        assert_eq!(parse_duration(input), expected.map(Duration::from_secs));
+    }
+
+    #[rstest]
+    #[case::zero(Duration::ZERO, "00:00")]
+    #[case::partial_second(Duration::from_millis(100), "00:01")]
+    #[case::minutes(Duration::from_secs(299), "04:59")]
+    #[case::hours(Duration::from_secs(3661), "1:01:01")]
+    fn formatting(#[case] remaining: Duration, #[case] expected: &str) {
+        assert_eq!(format_remaining(remaining), expected);
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let start = Instant::now();
+        let mut clock = CountdownClock::new(Duration::from_secs(60));
+        assert_eq!(clock.remaining(start + Duration::from_secs(10)), Duration::from_secs(60));
+
+        assert!(clock.start(start));
+        assert!(!clock.start(start), "started twice");
+        assert_eq!(clock.remaining(start + Duration::from_secs(10)), Duration::from_secs(50));
+
+        // Time doesn't go by while paused.
+        clock.pause(start + Duration::from_secs(10));
+        assert_eq!(clock.remaining(start + Duration::from_secs(40)), Duration::from_secs(50));
+
+        assert!(clock.start(start + Duration::from_secs(40)));
+        assert_eq!(clock.remaining(start + Duration::from_secs(45)), Duration::from_secs(45));
+
+        // Pausing twice doesn't count the time in between.
+        clock.pause(start + Duration::from_secs(45));
+        clock.pause(start + Duration::from_secs(50));
+        assert_eq!(clock.remaining(start + Duration::from_secs(50)), Duration::from_secs(45));
+    }
+
+    #[test]
+    fn runs_out() {
+        let start = Instant::now();
+        let mut clock = CountdownClock::new(Duration::from_secs(5));
+        clock.start(start);
+        assert_eq!(clock.remaining(start + Duration::from_secs(10)), Duration::ZERO);
+
+        clock.pause(start + Duration::from_secs(10));
+        assert!(!clock.start(start + Duration::from_secs(20)), "finished clock started");
+
+        clock.reset();
+        assert!(!clock.is_started());
+        assert!(clock.start(start + Duration::from_secs(20)));
+        assert_eq!(clock.remaining(start + Duration::from_secs(21)), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn focus_loss() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let mut clock = CountdownClock::new(Duration::from_secs(60));
+        clock.start(start);
+
+        // Time doesn't go by while the terminal isn't focused but the clock is still running.
+        clock.set_focused(false, at(10));
+        assert_eq!(clock.remaining(at(30)), Duration::from_secs(50));
+        assert!(!clock.start(at(30)), "started twice");
+        clock.set_focused(true, at(30));
+        assert_eq!(clock.remaining(at(35)), Duration::from_secs(45));
+
+        // Pausing and resuming while unfocused doesn't start counting.
+        clock.set_focused(false, at(35));
+        clock.pause(at(40));
+        assert!(clock.start(at(45)));
+        assert_eq!(clock.remaining(at(50)), Duration::from_secs(45));
+
+        // Regaining focus while paused doesn't either.
+        clock.pause(at(50));
+        clock.set_focused(true, at(55));
+        assert_eq!(clock.remaining(at(60)), Duration::from_secs(45));
+        clock.start(at(60));
+        assert_eq!(clock.remaining(at(61)), Duration::from_secs(44));
+    }
+
+    #[test]
+    fn operation_pauses_when_leaving() {
+        let operation = CountdownOperation::new(
+            "1m".into(),
+            Duration::from_secs(60),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            CountdownStart::KeyPress,
+            CountdownReturn::Resume,
+            false,
+        );
+        assert_eq!(operation.start_policy(), RenderOnDemandStartPolicy::OnDemand);
+        assert!(operation.start_render());
+        assert!(matches!(operation.poll_state(), RenderOnDemandState::Rendering));
+
+        operation.pause_render();
+        assert!(matches!(operation.poll_state(), RenderOnDemandState::NotStarted));
+        // It was already started so it resumes on its own once it's displayed again.
+        assert_eq!(operation.start_policy(), RenderOnDemandStartPolicy::Visible);
+        assert!(operation.start_render());
+    }
+
+    #[rstest]
+    #[case::enabled(true)]
+    #[case::disabled(false)]
+    fn bell_once_finished(#[case] bell: bool) {
+        let operation = CountdownOperation::new(
+            "1m".into(),
+            Duration::from_secs(60),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            CountdownStart::KeyPress,
+            CountdownReturn::Resume,
+            bell,
+        );
+        assert!(operation.start_render());
+        operation.poll_state();
+        assert!(!operation.take_bell());
+
+        operation.advance(Duration::from_secs(60));
+        assert!(matches!(operation.poll_state(), RenderOnDemandState::Rendered));
+        assert_eq!(operation.take_bell(), bell);
+        // It's only rung once.
+        operation.poll_state();
+        assert!(!operation.take_bell());
+    }
+}
@@ -0,0 +1,318 @@
+use crate::{
+    cache::{CacheKind, CacheUsage},
+    markdown::elements::Text,
+    media::{image::Image, printer::ResourceProperties},
+    presentation::{
+        AsRenderOperations, ImageProperties, RenderOnDemand, RenderOnDemandStartPolicy, RenderOnDemandState,
+        RenderOperation,
+    },
+    render::properties::WindowSize,
+    style::{Color, Colors, TextStyle},
+    theme::Alignment,
+};
+use std::{
+    cell::RefCell,
+    fmt, mem,
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+const LOADING_TEXT: &str = "[loading...]";
+
+// The frames of the spinner shown while an image takes a while to load.
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+// How long an image has to take to load before the spinner is shown.
+const SPINNER_DELAY: Duration = Duration::from_millis(100);
+
+/// How often the spinner moves to its next frame.
+pub(crate) const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+type LoadImage = Arc<dyn Fn() -> Result<Image, String> + Send + Sync>;
+
+enum DeferredImageState {
+    NotStarted,
+    Loading { handle: JoinHandle<Result<Image, String>>, started: Instant },
+    Loaded(Image),
+    Failed(String),
+}
+
+/// An image that is loaded in a background thread.
+///
+/// Loading images may involve reading files and running external tools, so this allows the rest of
+/// the slide to be displayed right away. A placeholder is displayed in the image's position until
+/// it's loaded.
+///
+/// The loaded image can be evicted to save memory, in which case it will be loaded again the next
+/// time it starts rendering.
+pub(crate) struct DeferredImage {
+    source: String,
+    properties: ImageProperties,
+    colors: Colors,
+    loader: LoadImage,
+    state: RefCell<DeferredImageState>,
+}
+
+impl DeferredImage {
+    /// Construct a new deferred image.
+    ///
+    /// The source identifies the image, e.g. its path, and is used when diffing presentations.
+    pub(crate) fn new<F>(source: String, properties: ImageProperties, colors: Colors, loader: F) -> Self
+    where
+        F: Fn() -> Result<Image, String> + Send + Sync + 'static,
+    {
+        let state = RefCell::new(DeferredImageState::NotStarted);
+        Self { source, properties, colors, loader: Arc::new(loader), state }
+    }
+
+    /// Use an image that's already loaded.
+    pub(crate) fn loaded(self, image: Image) -> Self {
+        *self.state.borrow_mut() = DeferredImageState::Loaded(image);
+        self
+    }
+
+    fn render_text(&self, text: String, style: TextStyle) -> Vec<RenderOperation> {
+        let alignment = Alignment::Center { minimum_margin: Default::default(), minimum_size: 0 };
+        let line = vec![Text::new(text, style.colors(self.colors.clone()))];
+        vec![RenderOperation::RenderText { line: line.into(), alignment }, RenderOperation::RenderLineBreak]
+    }
+}
+
+impl fmt::Debug for DeferredImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeferredImage").field("source", &self.source).finish()
+    }
+}
+
+impl AsRenderOperations for DeferredImage {
+    fn as_render_operations(&self, _: &WindowSize) -> Vec<RenderOperation> {
+        match &*self.state.borrow() {
+            DeferredImageState::Loaded(image) => vec![
+                RenderOperation::RenderImage(image.clone(), self.properties.clone()),
+                RenderOperation::SetColors(self.colors.clone()),
+            ],
+            DeferredImageState::Failed(error) => {
+                let mut style = TextStyle::default();
+                style.colors.foreground = Some(Color::new(255, 0, 0));
+                self.render_text(format!("failed to load image: {error}"), style)
+            }
+            DeferredImageState::Loading { started, .. } => match spinner_frame(started.elapsed()) {
+                Some(frame) => self.render_text(format!("{frame} {LOADING_TEXT}"), TextStyle::default().dim()),
+                None => self.render_text(LOADING_TEXT.into(), TextStyle::default().dim()),
+            },
+            DeferredImageState::NotStarted => self.render_text(LOADING_TEXT.into(), TextStyle::default().dim()),
+        }
+    }
+
+    fn diffable_content(&self) -> Option<&str> {
+        Some(&self.source)
+    }
+
+    fn cache_usage(&self) -> Option<CacheUsage> {
+        match &*self.state.borrow() {
+            DeferredImageState::Loaded(image) => {
+                Some(CacheUsage { kind: CacheKind::Image, bytes: image.memory_size() })
+            }
+            _ => None,
+        }
+    }
+
+    fn evict_cache(&self) {
+        let mut state = self.state.borrow_mut();
+        if matches!(*state, DeferredImageState::Loaded(_)) {
+            *state = DeferredImageState::NotStarted;
+        }
+    }
+}
+
+impl RenderOnDemand for DeferredImage {
+    fn start_render(&self) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !matches!(*state, DeferredImageState::NotStarted) {
+            return false;
+        }
+        let loader = self.loader.clone();
+        *state = DeferredImageState::Loading { handle: thread::spawn(move || loader()), started: Instant::now() };
+        true
+    }
+
+    fn poll_state(&self) -> RenderOnDemandState {
+        let mut state = self.state.borrow_mut();
+        match &*state {
+            DeferredImageState::NotStarted => return RenderOnDemandState::NotStarted,
+            DeferredImageState::Loading { handle, .. } if !handle.is_finished() => {
+                return RenderOnDemandState::Rendering;
+            }
+            DeferredImageState::Loading { .. } => (),
+            DeferredImageState::Loaded(_) | DeferredImageState::Failed(_) => return RenderOnDemandState::Rendered,
+        };
+        // The failed state is only a placeholder until the thread is joined below.
+        let placeholder = DeferredImageState::Failed(String::new());
+        let DeferredImageState::Loading { handle, .. } = mem::replace(&mut *state, placeholder) else {
+            unreachable!("state checked above");
+        };
+        *state = match handle.join() {
+            Ok(Ok(image)) => DeferredImageState::Loaded(image),
+            Ok(Err(e)) => DeferredImageState::Failed(e),
+            // The panic hook leaves the terminal alone for panics outside the main thread so this can be displayed.
+            Err(_) => DeferredImageState::Failed("image loader panicked".into()),
+        };
+        RenderOnDemandState::Rendered
+    }
+
+    fn start_policy(&self) -> RenderOnDemandStartPolicy {
+        RenderOnDemandStartPolicy::Automatic
+    }
+}
+
+// Get the spinner frame to display after loading for some time, if it's been loading for long enough.
+fn spinner_frame(elapsed: Duration) -> Option<char> {
+    if elapsed < SPINNER_DELAY {
+        return None;
+    }
+    let index = (elapsed.as_millis() / SPINNER_INTERVAL.as_millis()) as usize % SPINNER_FRAMES.len();
+    Some(SPINNER_FRAMES[index])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::media::{image::ImageSource, printer::ImageResource};
+    use rstest::rstest;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Barrier,
+    };
+
+    const DIMENSIONS: WindowSize = WindowSize { rows: 10, columns: 20, width: 0, height: 0 };
+
+    fn make_image(loader: impl Fn() -> Result<Image, String> + Send + Sync + 'static) -> DeferredImage {
+        let properties =
+            ImageProperties { z_index: 0, size: Default::default(), restore_cursor: false, background_color: None };
+        DeferredImage::new("image.png".into(), properties, Default::default(), loader)
+    }
+
+    fn generate_image() -> Result<Image, String> {
+        let resource = ImageResource::Ascii(image::DynamicImage::new_rgb8(2, 2).into());
+        Ok(Image::new(resource, ImageSource::Generated))
+    }
+
+    fn wait_until_rendered(image: &DeferredImage) {
+        for _ in 0..100 {
+            if matches!(image.poll_state(), RenderOnDemandState::Rendered) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("image never loaded");
+    }
+
+    fn rendered_text(operations: &[RenderOperation]) -> Vec<String> {
+        operations
+            .iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderText { line, .. } => {
+                    Some(line.iter_texts().map(|text| text.text().content.clone()).collect())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn placeholder_until_loaded() {
+        let barrier = Arc::new(Barrier::new(2));
+        let image = make_image({
+            let barrier = barrier.clone();
+            move || {
+                barrier.wait();
+                generate_image()
+            }
+        });
+        assert!(matches!(image.poll_state(), RenderOnDemandState::NotStarted));
+        assert_eq!(rendered_text(&image.as_render_operations(&DIMENSIONS)), &[LOADING_TEXT]);
+
+        assert!(image.start_render());
+        assert!(!image.start_render());
+        assert!(matches!(image.poll_state(), RenderOnDemandState::Rendering));
+        assert_eq!(rendered_text(&image.as_render_operations(&DIMENSIONS)), &[LOADING_TEXT]);
+
+        barrier.wait();
+        wait_until_rendered(&image);
+        let operations = image.as_render_operations(&DIMENSIONS);
+        assert!(matches!(operations[0], RenderOperation::RenderImage(..)));
+    }
+
+    #[rstest]
+    #[case::quick(Duration::from_millis(50), None)]
+    #[case::first_frame(Duration::from_millis(100), Some('⠙'))]
+    #[case::later_frame(Duration::from_millis(450), Some('⠼'))]
+    #[case::wraps_around(Duration::from_millis(1050), Some('⠋'))]
+    fn spinner(#[case] elapsed: Duration, #[case] expected: Option<char>) {
+        assert_eq!(spinner_frame(elapsed), expected);
+    }
+
+    #[test]
+    fn spinner_while_loading() {
+        let barrier = Arc::new(Barrier::new(2));
+        let image = make_image({
+            let barrier = barrier.clone();
+            move || {
+                barrier.wait();
+                generate_image()
+            }
+        });
+        image.start_render();
+        thread::sleep(SPINNER_DELAY);
+        let text = rendered_text(&image.as_render_operations(&DIMENSIONS));
+        assert!(text[0].ends_with(LOADING_TEXT) && text[0] != LOADING_TEXT, "{text:?}");
+
+        barrier.wait();
+        wait_until_rendered(&image);
+    }
+
+    #[test]
+    fn load_error() {
+        let image = make_image(|| Err("file not found".into()));
+        image.start_render();
+        wait_until_rendered(&image);
+        let operations = image.as_render_operations(&DIMENSIONS);
+        assert_eq!(rendered_text(&operations), &["failed to load image: file not found"]);
+        assert_eq!(image.cache_usage(), None);
+    }
+
+    #[test]
+    fn loader_panic() {
+        let image = make_image(|| panic!("loader failed"));
+        image.start_render();
+        wait_until_rendered(&image);
+        let operations = image.as_render_operations(&DIMENSIONS);
+        assert_eq!(rendered_text(&operations), &["failed to load image: image loader panicked"]);
+    }
+
+    #[test]
+    fn evict() {
+        let loads = Arc::new(AtomicUsize::new(0));
+        let image = make_image({
+            let loads = loads.clone();
+            move || {
+                loads.fetch_add(1, Ordering::Relaxed);
+                generate_image()
+            }
+        });
+        image.start_render();
+        wait_until_rendered(&image);
+        assert_eq!(image.cache_usage(), Some(CacheUsage { kind: CacheKind::Image, bytes: 16 }));
+
+        image.evict_cache();
+        assert_eq!(image.cache_usage(), None);
+        assert!(matches!(image.poll_state(), RenderOnDemandState::NotStarted));
+
+        // It gets loaded again the next time it's rendered.
+        assert!(image.start_render());
+        wait_until_rendered(&image);
+        assert!(image.cache_usage().is_some());
+        assert_eq!(loads.load(Ordering::Relaxed), 2);
+    }
+}
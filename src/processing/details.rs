@@ -0,0 +1,173 @@
+use crate::{
+    markdown::elements::TextBlock,
+    presentation::{AsRenderOperations, RenderOnDemand, RenderOperation},
+    render::properties::WindowSize,
+    style::TextStyle,
+    theme::Alignment,
+};
+use std::{cell::Cell, rc::Rc};
+
+/// A collapsible `<details>` block.
+///
+/// Only its summary is displayed while it's collapsed, and its contents are displayed right after
+/// it once it's expanded.
+#[derive(Debug)]
+pub(crate) struct CollapsibleDetails {
+    /// The index of the chunk in the slide this block is in.
+    pub(crate) chunk: usize,
+    summary: TextBlock,
+    diffable_content: String,
+    alignment: Alignment,
+    body: Vec<RenderOperation>,
+    on_demand: Vec<Rc<dyn RenderOnDemand>>,
+    expanded: Cell<bool>,
+}
+
+impl CollapsibleDetails {
+    /// Construct a new block out of the operations that render its contents.
+    pub(crate) fn new(
+        chunk: usize,
+        summary: TextBlock,
+        alignment: Alignment,
+        mut body: Vec<RenderOperation>,
+        expanded: bool,
+    ) -> Self {
+        // The line break that separates the contents from whatever comes next is added after the
+        // block itself so it's there regardless of whether it's expanded.
+        if matches!(body.last(), Some(RenderOperation::RenderLineBreak)) {
+            body.pop();
+        }
+        // The contents are diffed too, so changing them is treated as a change in this slide.
+        let mut diffable_content = summary.to_plain();
+        for operation in &body {
+            match operation {
+                RenderOperation::RenderText { line, .. } => {
+                    diffable_content.push('\n');
+                    diffable_content.extend(line.iter_texts().map(|text| text.text().content.as_str()));
+                }
+                RenderOperation::RenderPreformattedLine(line) => {
+                    diffable_content.push('\n');
+                    diffable_content.push_str(&line.text);
+                }
+                RenderOperation::RenderDynamic(operation) => {
+                    diffable_content.push('\n');
+                    diffable_content.push_str(operation.diffable_content().unwrap_or_default());
+                }
+                RenderOperation::RenderOnDemand(operation) => {
+                    diffable_content.push('\n');
+                    diffable_content.push_str(operation.diffable_content().unwrap_or_default());
+                }
+                _ => (),
+            };
+        }
+        let on_demand = body.iter().flat_map(|operation| operation.on_demand_operations()).cloned().collect();
+        Self { chunk, summary, diffable_content, alignment, body, on_demand, expanded: Cell::new(expanded) }
+    }
+
+    /// Check whether this block is expanded.
+    pub(crate) fn is_expanded(&self) -> bool {
+        self.expanded.get()
+    }
+
+    /// Expand or collapse this block.
+    pub(crate) fn set_expanded(&self, expanded: bool) {
+        self.expanded.set(expanded);
+    }
+}
+
+impl AsRenderOperations for CollapsibleDetails {
+    fn as_render_operations(&self, _: &WindowSize) -> Vec<RenderOperation> {
+        let indicator = if self.is_expanded() { '▼' } else { '▶' };
+        let mut summary = TextBlock::from(format!("{indicator} "));
+        summary.0.extend(self.summary.0.iter().cloned());
+        summary.apply_style(&TextStyle::default().bold());
+        let mut operations = vec![
+            RenderOperation::RenderText { line: summary.into(), alignment: self.alignment.clone() },
+            RenderOperation::RenderLineBreak,
+        ];
+        if self.is_expanded() {
+            operations.push(RenderOperation::RenderLineBreak);
+            operations.extend(self.body.iter().cloned());
+        }
+        operations
+    }
+
+    fn diffable_content(&self) -> Option<&str> {
+        Some(&self.diffable_content)
+    }
+
+    fn on_demand_operations(&self) -> &[Rc<dyn RenderOnDemand>] {
+        &self.on_demand
+    }
+}
+
+/// Toggle the first collapsed block among the given ones or, if they're all expanded, collapse them all.
+///
+/// Returns whether any block changed.
+pub(crate) fn toggle_details(details: &[&CollapsibleDetails]) -> bool {
+    match details.iter().find(|details| !details.is_expanded()) {
+        Some(details) => details.set_expanded(true),
+        None => details.iter().for_each(|details| details.set_expanded(false)),
+    };
+    !details.is_empty()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn details(expanded: bool) -> CollapsibleDetails {
+        let body = vec![RenderOperation::JumpToRow { index: 0 }, RenderOperation::RenderLineBreak];
+        CollapsibleDetails::new(0, "summary".into(), Default::default(), body, expanded)
+    }
+
+    fn summary(details: &CollapsibleDetails) -> String {
+        let dimensions = WindowSize { rows: 10, columns: 40, width: 0, height: 0 };
+        match details.as_render_operations(&dimensions).first() {
+            Some(RenderOperation::RenderText { line, .. }) => {
+                line.iter_texts().map(|text| text.text().content.as_str()).collect()
+            }
+            _ => panic!("no summary"),
+        }
+    }
+
+    #[test]
+    fn toggle() {
+        let (first, second) = (details(false), details(true));
+        let blocks = [&first, &second];
+        assert_eq!(summary(&first), "▶ summary");
+
+        assert!(toggle_details(&blocks));
+        assert!(first.is_expanded() && second.is_expanded());
+        assert_eq!(summary(&first), "▼ summary");
+
+        // Once everything is expanded it's all collapsed again.
+        assert!(toggle_details(&blocks));
+        assert!(!first.is_expanded() && !second.is_expanded());
+        assert!(!toggle_details(&[]));
+    }
+
+    #[test]
+    fn contents_are_diffed() {
+        let build = |text: &str| {
+            let line = TextBlock::from(text).into();
+            let body = vec![RenderOperation::RenderText { line, alignment: Default::default() }];
+            CollapsibleDetails::new(0, "summary".into(), Default::default(), body, false)
+        };
+        assert_eq!(build("foo").diffable_content(), build("foo").diffable_content());
+        assert_ne!(build("foo").diffable_content(), build("bar").diffable_content());
+    }
+
+    #[test]
+    fn body() {
+        let dimensions = WindowSize { rows: 10, columns: 40, width: 0, height: 0 };
+        let details = details(false);
+        assert_eq!(details.as_render_operations(&dimensions).len(), 2);
+
+        // The trailing line break in the contents is dropped.
+        details.set_expanded(true);
+        let operations = details.as_render_operations(&dimensions);
+        assert_eq!(operations.len(), 4);
+        assert!(matches!(operations.last(), Some(RenderOperation::JumpToRow { .. })));
+    }
+}
@@ -0,0 +1,100 @@
+//! Comment commands whose syntax changed over time.
+//!
+//! Commands written using a deprecated syntax keep working, but they're reported so authors know
+//! how to migrate their presentations.
+
+use std::fmt::{self, Display};
+
+/// The commands that were renamed, along with the command that replaces each of them.
+pub(crate) const DEPRECATED_DIRECTIVES: &[(&str, &str)] = &[("jump_to_vertical_center", "jump_to_middle")];
+
+/// Find the replacement for a command if it's written using a deprecated syntax.
+///
+/// The command is expected to have its prefix, if any, already stripped.
+pub(crate) fn find_deprecated(command: &str) -> Option<(&'static str, &'static str)> {
+    let name = command.trim().split(':').next()?.trim_end();
+    DEPRECATED_DIRECTIVES.iter().copied().find(|(old, _)| *old == name)
+}
+
+/// Rewrite a command that's written using a deprecated syntax into its replacement.
+pub(crate) fn replace_deprecated(command: &str) -> Option<String> {
+    let (old, new) = find_deprecated(command)?;
+    let arguments = &command.trim()[old.len()..];
+    Some(format!("{new}{arguments}"))
+}
+
+/// A problem with a command that doesn't prevent the presentation from being built.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DirectiveWarning {
+    /// A command was written using a syntax that's been replaced.
+    DeprecatedDirective { old_syntax: String, new_syntax: String, line: usize },
+}
+
+impl Display for DirectiveWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DeprecatedDirective { old_syntax, new_syntax, line } => {
+                write!(f, "line {line}: command '{old_syntax}' is deprecated, use '{new_syntax}' instead")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        custom::KeyBindingsConfig,
+        processing::builder::{PresentationBuilder, Themes},
+        theme::PresentationTheme,
+        typst::TypstRender,
+        MarkdownParser, Resources,
+    };
+    use comrak::Arena;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::deprecated("jump_to_vertical_center", Some("jump_to_middle"))]
+    #[case::padded(" jump_to_vertical_center ", Some("jump_to_middle"))]
+    #[case::current("jump_to_middle", None)]
+    #[case::prefix_of_deprecated("jump_to_vertical", None)]
+    fn replacement(#[case] command: &str, #[case] expected: Option<&str>) {
+        assert_eq!(replace_deprecated(command).as_deref(), expected);
+    }
+
+    #[test]
+    fn warnings() {
+        let contents = "
+hi
+
+<!-- jump_to_vertical_center -->
+
+<!-- jump_to_middle -->
+";
+        let arena = Arena::new();
+        let elements = MarkdownParser::new(&arena).parse(contents).expect("parsing failed");
+        let theme = PresentationTheme::default();
+        let mut resources = Resources::new("/tmp", Default::default());
+        let mut typst = TypstRender::default();
+        let themes = Themes::default();
+        let bindings = KeyBindingsConfig::default();
+        let options = Default::default();
+        let builder = PresentationBuilder::new(
+            &theme,
+            &mut resources,
+            &mut typst,
+            &themes,
+            Default::default(),
+            bindings,
+            options,
+        );
+        let presentation = builder.build(elements).expect("build failed");
+        let warnings = presentation.directive_warnings();
+        let expected = DirectiveWarning::DeprecatedDirective {
+            old_syntax: "jump_to_vertical_center".into(),
+            new_syntax: "jump_to_middle".into(),
+            line: 4,
+        };
+        assert_eq!(warnings, &[expected]);
+    }
+}
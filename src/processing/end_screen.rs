@@ -0,0 +1,59 @@
+use crate::{
+    markdown::{elements::Text, text::WeightedTextBlock},
+    presentation::{AsRenderOperations, RenderOperation},
+    render::properties::WindowSize,
+    style::TextStyle,
+    theme::Alignment,
+};
+use std::time::{Duration, Instant};
+
+/// The screen shown after moving forward on the last slide.
+#[derive(Clone, Debug)]
+pub struct EndScreen {
+    /// The message displayed in the middle of the screen.
+    pub message: String,
+
+    /// When the presentation started, which is used to display how long it took.
+    pub started_at: Instant,
+}
+
+/// The time that went by since the presentation started.
+#[derive(Debug)]
+pub(crate) struct ElapsedTime {
+    pub(crate) started_at: Instant,
+    pub(crate) style: TextStyle,
+    pub(crate) alignment: Alignment,
+}
+
+impl AsRenderOperations for ElapsedTime {
+    fn as_render_operations(&self, _dimensions: &WindowSize) -> Vec<RenderOperation> {
+        let text = format_elapsed(self.started_at.elapsed());
+        let line = WeightedTextBlock::from(vec![Text::new(text, self.style.clone())]);
+        vec![RenderOperation::RenderText { line, alignment: self.alignment.clone() }]
+    }
+
+    fn diffable_content(&self) -> Option<&str> {
+        None
+    }
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 { format!("{hours}:{minutes:02}:{seconds:02}") } else { format!("{minutes:02}:{seconds:02}") }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::zero(Duration::ZERO, "00:00")]
+    #[case::partial_second(Duration::from_millis(1900), "00:01")]
+    #[case::minutes(Duration::from_secs(754), "12:34")]
+    #[case::hours(Duration::from_secs(3725), "1:02:05")]
+    fn formatting(#[case] elapsed: Duration, #[case] expected: &str) {
+        assert_eq!(format_elapsed(elapsed), expected);
+    }
+}
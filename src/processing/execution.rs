@@ -1,5 +1,5 @@
 use crate::{
-    execute::{CodeExecuter, ExecutionHandle, ExecutionState, ProcessStatus},
+    execute::{CodeExecuter, ExecutionHandle, ExecutionPolicy, ExecutionState, ProcessStatus},
     markdown::elements::Code,
     presentation::{AsRenderOperations, PreformattedLine, RenderOnDemand, RenderOnDemandState, RenderOperation},
     render::properties::WindowSize,
@@ -7,6 +7,7 @@ use crate::{
 };
 use itertools::Itertools;
 use std::{cell::RefCell, rc::Rc};
+use unicode_width::UnicodeWidthStr;
 
 use super::separator::RenderSeparator;
 
@@ -20,20 +21,21 @@ struct RunCodeOperationInner {
 #[derive(Debug)]
 pub(crate) struct RunCodeOperation {
     code: Code,
+    policy: ExecutionPolicy,
     default_colors: Colors,
     block_colors: Colors,
     inner: Rc<RefCell<RunCodeOperationInner>>,
 }
 
 impl RunCodeOperation {
-    pub(crate) fn new(code: Code, default_colors: Colors, block_colors: Colors) -> Self {
+    pub(crate) fn new(code: Code, policy: ExecutionPolicy, default_colors: Colors, block_colors: Colors) -> Self {
         let inner =
             RunCodeOperationInner { handle: None, output_lines: Vec::new(), state: RenderOnDemandState::default() };
-        Self { code, default_colors, block_colors, inner: Rc::new(RefCell::new(inner)) }
+        Self { code, policy, default_colors, block_colors, inner: Rc::new(RefCell::new(inner)) }
     }
 
     fn render_line(&self, line: String) -> RenderOperation {
-        let line_len = line.len() as u16;
+        let line_len = line.width() as u16;
         RenderOperation::RenderPreformattedLine(PreformattedLine {
             text: line,
             unformatted_length: line_len,
@@ -80,6 +82,10 @@ impl AsRenderOperations for RunCodeOperation {
 }
 
 impl RenderOnDemand for RunCodeOperation {
+    fn runs_code(&self) -> bool {
+        true
+    }
+
     fn poll_state(&self) -> RenderOnDemandState {
         let mut inner = self.inner.borrow_mut();
         if let Some(handle) = inner.handle.as_mut() {
@@ -90,9 +96,16 @@ impl RenderOnDemand for RunCodeOperation {
                 inner.state = RenderOnDemandState::Rendered;
             }
             inner.output_lines = output;
-            if matches!(status, ProcessStatus::Failure) {
-                inner.output_lines.push("[finished with error]".to_string());
-            }
+            match status {
+                ProcessStatus::Failure => inner.output_lines.push("[finished with error]".to_string()),
+                ProcessStatus::TimedOut { after } => {
+                    inner.output_lines.push(format!("[timed out after {}s]", after.as_secs_f64()))
+                }
+                ProcessStatus::OutputLimitExceeded { bytes } => {
+                    inner.output_lines.push(format!("[killed after producing more than {bytes} bytes of output]"))
+                }
+                ProcessStatus::Running | ProcessStatus::Success => (),
+            };
         }
         inner.state.clone()
     }
@@ -102,7 +115,7 @@ impl RenderOnDemand for RunCodeOperation {
         if !matches!(inner.state, RenderOnDemandState::NotStarted) {
             return false;
         }
-        match CodeExecuter::execute(&self.code) {
+        match CodeExecuter::execute(&self.code, &self.policy) {
             Ok(handle) => {
                 inner.handle = Some(handle);
                 inner.state = RenderOnDemandState::Rendering;
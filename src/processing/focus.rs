@@ -0,0 +1,202 @@
+use crate::{
+    markdown::elements::Text,
+    presentation::RenderOperation,
+    render::properties::WindowSize,
+    style::{Colors, TextStyle},
+    theme::{Alignment, Margin},
+};
+use std::{cell::OnceCell, fmt};
+
+// The number of rows left empty above and below the code.
+const VERTICAL_MARGIN: u16 = 2;
+
+/// The margin on each side of a focused code block.
+pub(crate) const HORIZONTAL_MARGIN: Margin = Margin::Percent(8);
+
+/// A code block that can be focused, which displays it on its own using the whole screen.
+pub(crate) struct FocusableCode {
+    /// The index of the chunk in the slide this code block is in.
+    pub(crate) chunk: usize,
+    lines: OnceCell<Vec<RenderOperation>>,
+    build_lines: Box<dyn Fn() -> Vec<RenderOperation>>,
+    colors: Colors,
+}
+
+impl FocusableCode {
+    /// Construct a focusable code block.
+    ///
+    /// The operations that render each of its lines are only built the first time they're needed.
+    pub(crate) fn new<F>(chunk: usize, build_lines: F, colors: Colors) -> Self
+    where
+        F: Fn() -> Vec<RenderOperation> + 'static,
+    {
+        Self { chunk, lines: OnceCell::new(), build_lines: Box::new(build_lines), colors }
+    }
+
+    fn lines(&self) -> &[RenderOperation] {
+        self.lines.get_or_init(&self.build_lines)
+    }
+
+    /// Get the number of lines in this code block.
+    pub(crate) fn line_count(&self) -> usize {
+        self.lines().len()
+    }
+
+    /// Get the number of lines that can be displayed at once in a window with the given rows.
+    pub(crate) fn visible_lines(rows: u16) -> usize {
+        // The last row is used to display which lines are visible when scrolling.
+        rows.saturating_sub(VERTICAL_MARGIN * 2 + 1) as usize
+    }
+
+    /// Generate the operations that render this code block starting at the given line.
+    pub(crate) fn render_operations(&self, scroll: usize, dimensions: &WindowSize) -> Vec<RenderOperation> {
+        let visible_lines = Self::visible_lines(dimensions.rows);
+        let scroll = scroll.min(self.line_count().saturating_sub(visible_lines));
+        let end = (scroll + visible_lines).min(self.line_count());
+        let mut operations = vec![
+            RenderOperation::SetColors(self.colors.clone()),
+            RenderOperation::ClearScreen,
+            RenderOperation::JumpToRow { index: VERTICAL_MARGIN },
+        ];
+        operations.extend(self.lines()[scroll..end].iter().cloned());
+        if self.line_count() > visible_lines {
+            let text = format!("lines {}-{} of {}", scroll + 1, end, self.line_count());
+            operations.extend([
+                RenderOperation::SetColors(self.colors.clone()),
+                RenderOperation::JumpToBottomRow { index: 0 },
+                RenderOperation::RenderText {
+                    line: vec![Text::new(text, TextStyle::default().italics())].into(),
+                    alignment: Alignment::Right { margin: HORIZONTAL_MARGIN },
+                },
+            ]);
+        }
+        operations
+    }
+}
+
+impl fmt::Debug for FocusableCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FocusableCode")
+            .field("chunk", &self.chunk)
+            .field("lines", &self.lines)
+            .field("colors", &self.colors)
+            .finish()
+    }
+}
+
+/// Which code block in a slide is focused and how far it's scrolled.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CodeFocus {
+    /// The index of the focused code block among the slide's visible code blocks.
+    pub(crate) block: usize,
+
+    /// The first line being displayed.
+    pub(crate) scroll: usize,
+}
+
+impl CodeFocus {
+    /// Focus the next code block, going back to the first one after the last one.
+    pub(crate) fn next_block(&mut self, block_count: usize) {
+        self.block = (self.block + 1) % block_count.max(1);
+        self.scroll = 0;
+    }
+
+    /// Scroll down, stopping once the last line is visible.
+    pub(crate) fn scroll_down(&mut self, line_count: usize, visible_lines: usize) {
+        let max_scroll = line_count.saturating_sub(visible_lines);
+        self.scroll = (self.scroll + 1).min(max_scroll);
+    }
+
+    /// Scroll up, stopping at the first line.
+    pub(crate) fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use std::{cell::Cell, rc::Rc};
+
+    fn code(line_count: usize) -> FocusableCode {
+        let lines = move || (0..line_count).map(|index| RenderOperation::JumpToRow { index: index as u16 }).collect();
+        FocusableCode::new(0, lines, Default::default())
+    }
+
+    fn rendered_lines(operations: &[RenderOperation]) -> Vec<u16> {
+        operations
+            .iter()
+            .skip(3)
+            .filter_map(|operation| match operation {
+                RenderOperation::JumpToRow { index } => Some(*index),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cycle_blocks() {
+        let mut focus = CodeFocus { block: 0, scroll: 3 };
+        let mut blocks = Vec::new();
+        for _ in 0..4 {
+            focus.next_block(3);
+            assert_eq!(focus.scroll, 0);
+            blocks.push(focus.block);
+        }
+        assert_eq!(blocks, &[1, 2, 0, 1]);
+    }
+
+    #[rstest]
+    #[case::fits(5, 10, 0)]
+    #[case::taller(15, 10, 5)]
+    fn scroll_clamping(#[case] line_count: usize, #[case] visible_lines: usize, #[case] max_scroll: usize) {
+        let mut focus = CodeFocus::default();
+        for _ in 0..20 {
+            focus.scroll_down(line_count, visible_lines);
+        }
+        assert_eq!(focus.scroll, max_scroll);
+        for _ in 0..20 {
+            focus.scroll_up();
+        }
+        assert_eq!(focus.scroll, 0);
+    }
+
+    #[test]
+    fn lines_built_lazily() {
+        let built = Rc::new(Cell::new(0));
+        let code = FocusableCode::new(
+            0,
+            {
+                let built = built.clone();
+                move || {
+                    built.set(built.get() + 1);
+                    vec![RenderOperation::ClearScreen]
+                }
+            },
+            Default::default(),
+        );
+        assert_eq!(built.get(), 0);
+
+        assert_eq!(code.line_count(), 1);
+        assert_eq!(code.line_count(), 1);
+        assert_eq!(built.get(), 1);
+    }
+
+    #[test]
+    fn visible_window() {
+        // 10 rows leave room for 5 lines.
+        let dimensions = WindowSize { rows: 10, columns: 80, width: 0, height: 0 };
+        let operations = code(8).render_operations(2, &dimensions);
+        assert_eq!(rendered_lines(&operations), &[2, 3, 4, 5, 6]);
+
+        // Scrolling past the end is clamped.
+        let operations = code(8).render_operations(100, &dimensions);
+        assert_eq!(rendered_lines(&operations), &[3, 4, 5, 6, 7]);
+
+        // Everything fits so there's no scroll indicator.
+        let operations = code(3).render_operations(0, &dimensions);
+        assert_eq!(rendered_lines(&operations), &[0, 1, 2]);
+        assert!(!operations.iter().any(|operation| matches!(operation, RenderOperation::RenderText { .. })));
+    }
+}
@@ -1,90 +1,405 @@
 use crate::{
-    markdown::elements::Text,
-    presentation::{AsRenderOperations, RenderOperation},
-    render::properties::WindowSize,
+    markdown::{
+        elements::{Text, TextBlock},
+        text::truncate_styled,
+    },
+    presentation::{AsRenderOperations, RenderOperation, SlideSteps},
+    render::{properties::WindowSize, region::PaintRegion},
     style::{Colors, TextStyle},
-    theme::{Alignment, FooterStyle, Margin},
+    theme::{Alignment, FooterRegion, FooterStyle, Margin},
 };
+use serde::Deserialize;
 use std::{cell::RefCell, rc::Rc};
 use unicode_width::UnicodeWidthStr;
 
+// The number of columns left empty between two regions.
+const REGION_GAP: usize = 1;
+
+// Regions that would need to be truncated below this width are dropped instead.
+const MINIMUM_REGION_WIDTH: usize = 4;
+
 #[derive(Debug, Default)]
 pub(crate) struct FooterContext {
     pub(crate) total_slides: usize,
     pub(crate) author: String,
 }
 
+/// Overrides for the regions of a template footer in a single slide.
+///
+/// An empty template clears that region.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FooterOverride {
+    pub(crate) left: Option<String>,
+    pub(crate) center: Option<String>,
+    pub(crate) right: Option<String>,
+}
+
+impl FooterOverride {
+    /// Apply the regions set in another override on top of this one.
+    pub(crate) fn merge(&mut self, other: FooterOverride) {
+        self.left = other.left.or(self.left.take());
+        self.center = other.center.or(self.center.take());
+        self.right = other.right.or(self.right.take());
+    }
+
+    /// Whether any region is set to a non empty template.
+    pub(crate) fn has_content(&self) -> bool {
+        [&self.left, &self.center, &self.right].into_iter().flatten().any(|template| !template.is_empty())
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct FooterGenerator {
+    /// The number of the current slide or, if it's not numbered, of the last numbered one before it.
     pub(crate) current_slide: usize,
+    pub(crate) numbered: bool,
     pub(crate) context: Rc<RefCell<FooterContext>>,
     pub(crate) style: FooterStyle,
+    pub(crate) overrides: FooterOverride,
+    pub(crate) steps: Rc<SlideSteps>,
 }
 
 impl FooterGenerator {
-    fn render_template(
-        template: &str,
-        current_slide: &str,
-        context: &FooterContext,
-        colors: Colors,
-        alignment: Alignment,
-    ) -> RenderOperation {
-        let contents = template
+    fn render_template(template: &str, current_slide: &str, context: &FooterContext, steps: &SlideSteps) -> String {
+        template
             .replace("{current_slide}", current_slide)
             .replace("{total_slides}", &context.total_slides.to_string())
-            .replace("{author}", &context.author);
-        let text = Text::new(contents, TextStyle::default().colors(colors));
-        RenderOperation::RenderText { line: vec![text].into(), alignment }
+            .replace("{current_step}", &(steps.current() + 1).to_string())
+            .replace("{total_steps}", &steps.total().to_string())
+            .replace("{author}", &context.author)
     }
-}
 
-impl AsRenderOperations for FooterGenerator {
-    fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
+    fn resolve_region(
+        region: &Option<FooterRegion>,
+        overridden: &Option<String>,
+        footer_colors: &Colors,
+    ) -> Option<(String, Colors)> {
+        let colors = match region {
+            Some(region) => region.colors(footer_colors),
+            None => footer_colors.clone(),
+        };
+        let template = match (overridden, region) {
+            (Some(template), _) => template.as_str(),
+            (None, Some(region)) => region.template(),
+            (None, None) => return None,
+        };
+        (!template.is_empty()).then(|| (template.to_string(), colors))
+    }
+
+    fn render_regions(
+        regions: [Option<(String, Colors)>; 3],
+        current_slide: &str,
+        context: &FooterContext,
+        steps: &SlideSteps,
+        width: usize,
+    ) -> Vec<Text> {
+        let contents = regions.map(|region| {
+            region.map(|(template, colors)| (Self::render_template(&template, current_slide, context, steps), colors))
+        });
+        let mut widths = [0; 3];
+        for (width, region) in widths.iter_mut().zip(&contents) {
+            *width = region.as_ref().map(|(text, _)| text.width()).unwrap_or(0);
+        }
+        let bounds = RegionBounds::compute(widths, width);
+        let mut texts = Vec::new();
+        let mut column = 0;
+        for (region, bounds) in contents.into_iter().zip(bounds) {
+            let (Some((text, colors)), Some(RegionBounds { start, width })) = (region, bounds) else {
+                continue;
+            };
+            texts.push(Text::from(" ".repeat(start - column)));
+            let text = TextBlock::from(Text::new(text, TextStyle::default().colors(colors)));
+            texts.extend(truncate_styled(&text, width).0);
+            column = start + width;
+        }
+        texts
+    }
+
+    /// The row the footer is drawn at, counting from the bottom of the screen, if there's a footer at all.
+    pub(crate) fn row(&self) -> Option<u16> {
+        match &self.style {
+            // This is printed one row above the bottom so there's one row of padding.
+            FooterStyle::Template { .. } => Some(1),
+            // Overridden regions replace the progress bar and are laid out like a template footer.
+            FooterStyle::ProgressBar { .. } if self.overrides.has_content() => Some(1),
+            FooterStyle::ProgressBar { .. } => Some(0),
+            FooterStyle::Empty => None,
+        }
+    }
+
+    fn template_line(
+        &self,
+        [left, center, right]: [&Option<FooterRegion>; 3],
+        colors: &Colors,
+        context: &FooterContext,
+        columns: u16,
+    ) -> Option<(Vec<Text>, u16)> {
+        let current_slide = match self.numbered {
+            true => self.current_slide.to_string(),
+            false => "-".to_string(),
+        };
+        let regions = [
+            Self::resolve_region(left, &self.overrides.left, colors),
+            Self::resolve_region(center, &self.overrides.center, colors),
+            Self::resolve_region(right, &self.overrides.right, colors),
+        ];
+        // All regions are laid out in a single line so they never wrap into more than one row.
+        let margin = 1;
+        let width = (columns as usize).saturating_sub(margin * 2);
+        let texts = Self::render_regions(regions, &current_slide, context, &self.steps, width);
+        (!texts.is_empty()).then_some((texts, margin as u16))
+    }
+
+    // Get the footer's text and the margin to its left in a row with the given number of columns.
+    fn line(&self, columns: u16) -> Option<(Vec<Text>, u16)> {
         let context = self.context.borrow();
         match &self.style {
             FooterStyle::Template { left, center, right, colors } => {
-                let current_slide = (self.current_slide + 1).to_string();
-                // We print this one row below the bottom so there's one row of padding.
-                let mut operations = vec![RenderOperation::JumpToBottomRow { index: 1 }];
-                let margin = Margin::Fixed(1);
-                let alignments = [
-                    Alignment::Left { margin: margin.clone() },
-                    Alignment::Center { minimum_size: 0, minimum_margin: margin.clone() },
-                    Alignment::Right { margin: margin.clone() },
-                ];
-                for (text, alignment) in [left, center, right].iter().zip(alignments) {
-                    if let Some(text) = text {
-                        operations.push(Self::render_template(
-                            text,
-                            &current_slide,
-                            &context,
-                            colors.clone(),
-                            alignment,
-                        ));
-                    }
-                }
-                operations
+                self.template_line([left, center, right], colors, &context, columns)
+            }
+            FooterStyle::ProgressBar { colors, .. } if self.overrides.has_content() => {
+                self.template_line([&None, &None, &None], colors, &context, columns)
             }
             FooterStyle::ProgressBar { character, colors } => {
                 let character = character.unwrap_or('█').to_string();
-                let total_columns = dimensions.columns as usize / character.width();
-                let progress_ratio = (self.current_slide + 1) as f64 / context.total_slides as f64;
+                let total_columns = columns as usize / character.width().max(1);
+                let progress_ratio = self.current_slide as f64 / context.total_slides.max(1) as f64;
                 let columns_ratio = (total_columns as f64 * progress_ratio).ceil();
                 let bar = character.repeat(columns_ratio as usize);
-                let bar = Text::new(bar, TextStyle::default().colors(colors.clone()));
-                vec![
-                    RenderOperation::JumpToBottomRow { index: 0 },
-                    RenderOperation::RenderText {
-                        line: vec![bar].into(),
-                        alignment: Alignment::Left { margin: Margin::Fixed(0) },
-                    },
-                ]
+                Some((vec![Text::new(bar, TextStyle::default().colors(colors.clone()))], 0))
             }
-            FooterStyle::Empty => vec![],
+            FooterStyle::Empty => None,
         }
     }
+}
+
+impl AsRenderOperations for FooterGenerator {
+    fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
+        let Some((texts, margin)) = self.line(dimensions.columns) else {
+            return Vec::new();
+        };
+        vec![RenderOperation::RenderText {
+            line: texts.into(),
+            alignment: Alignment::Left { margin: Margin::Fixed(margin) },
+        }]
+    }
 
     fn diffable_content(&self) -> Option<&str> {
         None
     }
+
+    fn region_painter(self: Rc<Self>) -> Option<Rc<dyn PaintRegion>> {
+        Some(self)
+    }
+}
+
+impl PaintRegion for FooterGenerator {
+    fn region_name(&self) -> &'static str {
+        "footer"
+    }
+
+    fn region_lines(&self, width: u16) -> Vec<TextBlock> {
+        let Some((texts, margin)) = self.line(width) else {
+            return Vec::new();
+        };
+        let mut line = vec![Text::from(" ".repeat(margin as usize))];
+        line.extend(texts);
+        vec![TextBlock(line)]
+    }
+}
+
+/// Where a footer region is placed within the footer's row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RegionBounds {
+    start: usize,
+    width: usize,
+}
+
+impl RegionBounds {
+    /// Lay out the left, center and right regions given their widths.
+    ///
+    /// The center region has priority and is only ever truncated. The left and right regions get
+    /// whatever space is left on each side of it and, if there's no center region, the left region
+    /// takes precedence over the right one. A region that would be truncated below a minimum width
+    /// is dropped instead, which means the right region is dropped first, then the left one.
+    fn compute(widths: [usize; 3], total_width: usize) -> [Option<Self>; 3] {
+        let [left, center, right] = widths;
+        let center = (center > 0).then(|| {
+            let width = center.min(total_width);
+            Self { start: (total_width - width) / 2, width }
+        });
+        let left_space = match center {
+            Some(center) => center.start.saturating_sub(REGION_GAP),
+            None => total_width,
+        };
+        let left = Self::fit(left, left_space).map(|width| Self { start: 0, width });
+        let right_start = match (center, left) {
+            (Some(previous), _) | (None, Some(previous)) => previous.start + previous.width + REGION_GAP,
+            (None, None) => 0,
+        };
+        let right = Self::fit(right, total_width.saturating_sub(right_start))
+            .map(|width| Self { start: total_width - width, width });
+        [left, center, right]
+    }
+
+    fn fit(width: usize, available: usize) -> Option<usize> {
+        match width {
+            0 => None,
+            width if width <= available => Some(width),
+            _ if available >= MINIMUM_REGION_WIDTH => Some(available),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::presentation::SlideChunk;
+    use rstest::rstest;
+
+    fn bounds(start: usize, width: usize) -> Option<RegionBounds> {
+        Some(RegionBounds { start, width })
+    }
+
+    #[rstest]
+    #[case::all_fit([5, 6, 5], 40, [bounds(0, 5), bounds(17, 6), bounds(35, 5)])]
+    #[case::only_sides([5, 0, 5], 40, [bounds(0, 5), None, bounds(35, 5)])]
+    #[case::truncated_left([30, 6, 5], 40, [bounds(0, 16), bounds(17, 6), bounds(35, 5)])]
+    #[case::left_takes_precedence([30, 0, 30], 40, [bounds(0, 30), None, bounds(31, 9)])]
+    #[case::right_dropped([38, 0, 10], 40, [bounds(0, 38), None, None])]
+    #[case::sides_dropped([5, 10, 5], 14, [None, bounds(2, 10), None])]
+    #[case::center_truncated([5, 30, 5], 20, [None, bounds(0, 20), None])]
+    #[case::nothing([0, 0, 0], 20, [None, None, None])]
+    fn layout(#[case] widths: [usize; 3], #[case] total_width: usize, #[case] expected: [Option<RegionBounds>; 3]) {
+        let bounds = RegionBounds::compute(widths, total_width);
+        assert_eq!(bounds, expected);
+        // Regions must never overlap.
+        let bounds: Vec<_> = bounds.into_iter().flatten().collect();
+        for pair in bounds.windows(2) {
+            assert!(pair[0].start + pair[0].width < pair[1].start, "{pair:?} overlap");
+        }
+    }
+
+    fn render(style: FooterStyle, overrides: FooterOverride, columns: u16) -> Vec<RenderOperation> {
+        render_slide(3, true, style, overrides, columns)
+    }
+
+    fn render_slide(
+        current_slide: usize,
+        numbered: bool,
+        style: FooterStyle,
+        overrides: FooterOverride,
+        columns: u16,
+    ) -> Vec<RenderOperation> {
+        let context = Rc::new(RefCell::new(FooterContext { total_slides: 10, author: "bob".into() }));
+        let steps = Default::default();
+        let generator = FooterGenerator { current_slide, numbered, context, style, overrides, steps };
+        generator.as_render_operations(&WindowSize { rows: 10, columns, width: 0, height: 0 })
+    }
+
+    fn rendered_line(operations: &[RenderOperation]) -> String {
+        let lines: Vec<_> = operations
+            .iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderText { line, .. } => {
+                    Some(line.iter_texts().map(|text| text.text().content.clone()).collect::<String>())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lines.len(), 1, "footer must take up exactly one row");
+        lines.into_iter().next().unwrap()
+    }
+
+    fn template_style() -> FooterStyle {
+        FooterStyle::Template {
+            left: Some(FooterRegion::Template("{author}".into())),
+            center: Some(FooterRegion::Styled { template: "talk".into(), colors: Default::default() }),
+            right: Some(FooterRegion::Template("{current_slide} / {total_slides}".into())),
+            colors: Default::default(),
+        }
+    }
+
+    #[test]
+    fn template_regions() {
+        let line = rendered_line(&render(template_style(), Default::default(), 22));
+        assert_eq!(line, "bob     talk  3 / 10");
+    }
+
+    #[test]
+    fn unnumbered() {
+        let line = rendered_line(&render_slide(3, false, template_style(), Default::default(), 22));
+        assert_eq!(line, "bob     talk  - / 10");
+    }
+
+    #[rstest]
+    #[case::numbered(true)]
+    #[case::unnumbered(false)]
+    fn progress_bar(#[case] numbered: bool) {
+        // Unnumbered slides stay at the same progress as the last numbered slide before them.
+        let style = FooterStyle::ProgressBar { character: Some('#'), colors: Default::default() };
+        let line = rendered_line(&render_slide(3, numbered, style, Default::default(), 20));
+        assert_eq!(line, "######");
+    }
+
+    #[rstest]
+    #[case::replaced(FooterOverride { center: Some("{current_slide}".into()), ..Default::default() }, "  3", Some(1))]
+    #[case::cleared(FooterOverride { center: Some(String::new()), ..Default::default() }, "###", Some(0))]
+    fn progress_bar_overrides(#[case] overrides: FooterOverride, #[case] expected: &str, #[case] row: Option<u16>) {
+        let style = FooterStyle::ProgressBar { character: Some('#'), colors: Default::default() };
+        let line = rendered_line(&render(style.clone(), overrides.clone(), 8));
+        assert_eq!(line, expected);
+
+        let context = Default::default();
+        let generator =
+            FooterGenerator { current_slide: 3, numbered: true, context, style, overrides, steps: Default::default() };
+        assert_eq!(generator.row(), row);
+    }
+
+    #[test]
+    fn steps() {
+        let style = FooterStyle::Template {
+            left: None,
+            center: None,
+            right: Some(FooterRegion::Template("{current_step}/{total_steps}".into())),
+            colors: Default::default(),
+        };
+        let chunks = [SlideChunk::default(), SlideChunk::default(), SlideChunk::default()];
+        let steps = Rc::new(SlideSteps::new(&chunks, &[]));
+        let generator = FooterGenerator {
+            current_slide: 1,
+            numbered: true,
+            context: Default::default(),
+            style,
+            overrides: Default::default(),
+            steps,
+        };
+        let operations = generator.as_render_operations(&WindowSize { rows: 10, columns: 10, width: 0, height: 0 });
+        assert_eq!(rendered_line(&operations), "     1/3");
+    }
+
+    #[test]
+    fn narrow_terminal() {
+        let line = rendered_line(&render(template_style(), Default::default(), 12));
+        assert_eq!(line, "   talk");
+    }
+
+    #[test]
+    fn overrides() {
+        let overrides = FooterOverride { left: Some(String::new()), center: Some("CONFIDENTIAL".into()), right: None };
+        let line = rendered_line(&render(template_style(), overrides, 32));
+        assert_eq!(line, "         CONFIDENTIAL   3 / 10");
+    }
+
+    #[test]
+    fn merge_overrides() {
+        let mut overrides = FooterOverride { left: Some("a".into()), center: Some("b".into()), right: None };
+        overrides.merge(FooterOverride { left: None, center: Some(String::new()), right: Some("c".into()) });
+        assert_eq!(
+            overrides,
+            FooterOverride { left: Some("a".into()), center: Some(String::new()), right: Some("c".into()) }
+        );
+    }
 }
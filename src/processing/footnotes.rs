@@ -0,0 +1,211 @@
+use crate::markdown::elements::{MarkdownElement, ParagraphElement, Text, TextBlock};
+use std::mem;
+
+/// A footnote that was defined inline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Footnote {
+    /// The footnote's number, counting from the start of the presentation.
+    pub(crate) number: usize,
+
+    /// The footnote's text.
+    pub(crate) text: TextBlock,
+}
+
+/// Extracts pandoc style inline footnotes, like `^[some note]`, from a presentation's text.
+///
+/// Every footnote is replaced with a reference to it, which is its number in superscript.
+/// Footnotes are numbered in the order they show up in and brackets within them must be balanced.
+/// Footnotes never start or end inside code, but they can contain it.
+#[derive(Debug, Default)]
+pub(crate) struct Footnotes {
+    count: usize,
+}
+
+impl Footnotes {
+    /// Extract the footnotes in an element, replacing them with references to them.
+    pub(crate) fn extract_element(&mut self, element: &mut MarkdownElement) -> Vec<Footnote> {
+        let mut found = Vec::new();
+        match element {
+            MarkdownElement::Paragraph(elements) => {
+                for element in elements {
+                    if let ParagraphElement::Text(text) = element {
+                        self.extract_text(text, &mut found);
+                    }
+                }
+            }
+            MarkdownElement::List(items) => {
+                for item in items {
+                    self.extract_text(&mut item.contents, &mut found);
+                }
+            }
+            _ => (),
+        };
+        found
+    }
+
+    fn extract_text(&mut self, text: &mut TextBlock, found: &mut Vec<Footnote>) {
+        // Footnotes can span chunks with different styles so this works on every character along
+        // with the chunk it belongs to.
+        let chunks = mem::take(&mut text.0);
+        let characters: Vec<_> =
+            chunks.iter().flat_map(|chunk| chunk.content.chars().map(move |c| (c, chunk))).collect();
+        let mut output = Vec::new();
+        let mut index = 0;
+        while index < characters.len() {
+            match find_footnote_end(&characters, index) {
+                Some(end) => {
+                    self.count += 1;
+                    let (_, chunk) = characters[index];
+                    output.push(Text { content: superscript(self.count), ..chunk.clone() });
+                    let text = TextBlock(collect_chunks(&characters[index + 2..end]));
+                    found.push(Footnote { number: self.count, text });
+                    index = end + 1;
+                }
+                None => {
+                    let (c, chunk) = characters[index];
+                    push_char(&mut output, c, chunk);
+                    index += 1;
+                }
+            };
+        }
+        text.0 = output;
+    }
+}
+
+/// Format a number using superscript digits.
+pub(crate) fn superscript(number: usize) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    number.to_string().chars().filter_map(|c| c.to_digit(10)).map(|digit| DIGITS[digit as usize]).collect()
+}
+
+// Find the index of the `]` that closes the footnote starting at `start`, if one starts there.
+fn find_footnote_end(characters: &[(char, &Text)], start: usize) -> Option<usize> {
+    let is_text = |index: usize, expected: char| {
+        characters.get(index).is_some_and(|(c, chunk)| *c == expected && !chunk.style.is_code())
+    };
+    if !is_text(start, '^') || !is_text(start + 1, '[') {
+        return None;
+    }
+    let mut depth = 0;
+    for (index, (c, chunk)) in characters.iter().enumerate().skip(start + 1) {
+        if chunk.style.is_code() {
+            continue;
+        }
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    // Empty footnotes are left as they are.
+                    return (index > start + 2).then_some(index);
+                }
+            }
+            _ => (),
+        };
+    }
+    None
+}
+
+fn collect_chunks(characters: &[(char, &Text)]) -> Vec<Text> {
+    let mut output = Vec::new();
+    for (c, chunk) in characters {
+        push_char(&mut output, *c, chunk);
+    }
+    output
+}
+
+fn push_char(output: &mut Vec<Text>, c: char, chunk: &Text) {
+    match output.last_mut() {
+        Some(last) if last.style == chunk.style && last.expansion == chunk.expansion => last.content.push(c),
+        _ => output.push(Text { content: c.to_string(), ..chunk.clone() }),
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::style::TextStyle;
+    use rstest::rstest;
+
+    fn extract(chunks: Vec<Text>) -> (Vec<Text>, Vec<Footnote>) {
+        let mut element = MarkdownElement::Paragraph(vec![ParagraphElement::Text(TextBlock(chunks))]);
+        let footnotes = Footnotes::default().extract_element(&mut element);
+        let MarkdownElement::Paragraph(mut elements) = element else { unreachable!() };
+        let Some(ParagraphElement::Text(text)) = elements.pop() else { unreachable!() };
+        (text.0, footnotes)
+    }
+
+    #[test]
+    fn footnote() {
+        let (text, footnotes) = extract(vec!["hello^[a note] world".into()]);
+        assert_eq!(text, &[Text::from("hello"), Text::from("¹ world")]);
+        assert_eq!(footnotes, &[Footnote { number: 1, text: TextBlock::from("a note") }]);
+    }
+
+    #[test]
+    fn styled() {
+        let bold = TextStyle::default().bold();
+        let chunks = vec!["a^[".into(), Text::new("bold", bold.clone()), " note]".into()];
+        let (text, footnotes) = extract(chunks);
+        assert_eq!(text, &[Text::from("a"), Text::from("¹")]);
+        let expected = TextBlock(vec![Text::new("bold", bold), " note".into()]);
+        assert_eq!(footnotes, &[Footnote { number: 1, text: expected }]);
+    }
+
+    #[test]
+    fn numbering() {
+        let mut footnotes = Footnotes::default();
+        let mut first = MarkdownElement::Paragraph(vec![ParagraphElement::Text("a^[one] b^[two]".into())]);
+        let mut second = MarkdownElement::Paragraph(vec![ParagraphElement::Text("c^[three]".into())]);
+        let numbers: Vec<_> = footnotes
+            .extract_element(&mut first)
+            .into_iter()
+            .chain(footnotes.extract_element(&mut second))
+            .map(|footnote| footnote.number)
+            .collect();
+        assert_eq!(numbers, &[1, 2, 3]);
+    }
+
+    #[rstest]
+    #[case::nested("^[see [this]]", "see [this]")]
+    #[case::brackets_in_code("^[`]` is a bracket]", "] is a bracket")]
+    fn contents(#[case] input: &str, #[case] expected: &str) {
+        let chunks = match input.split_once('`') {
+            Some((before, rest)) => {
+                let (code, after) = rest.split_once('`').unwrap();
+                vec![before.into(), Text::new(code, TextStyle::default().code()), after.into()]
+            }
+            None => vec![input.into()],
+        };
+        let (_, footnotes) = extract(chunks);
+        let text: String = footnotes[0].text.0.iter().map(|chunk| chunk.content.as_str()).collect();
+        assert_eq!(text, expected);
+    }
+
+    #[rstest]
+    #[case::unterminated("a ^[note")]
+    #[case::empty("a ^[] b")]
+    #[case::no_caret("a [note]")]
+    #[case::separated("a ^ [note]")]
+    fn not_footnotes(#[case] input: &str) {
+        let (text, footnotes) = extract(vec![input.into()]);
+        assert_eq!(text, &[Text::from(input)]);
+        assert!(footnotes.is_empty());
+    }
+
+    #[test]
+    fn code_not_footnote() {
+        let code = Text::new("x^[0]", TextStyle::default().code());
+        let (text, footnotes) = extract(vec![code.clone()]);
+        assert_eq!(text, &[code]);
+        assert!(footnotes.is_empty());
+    }
+
+    #[rstest]
+    #[case(1, "¹")]
+    #[case(10, "¹⁰")]
+    #[case(42, "⁴²")]
+    fn superscript_numbers(#[case] number: usize, #[case] expected: &str) {
+        assert_eq!(superscript(number), expected);
+    }
+}
@@ -0,0 +1,289 @@
+use super::transform::{TextTransform, TransformStage};
+use crate::markdown::elements::Text;
+use std::{cell::RefCell, cmp::Reverse, collections::BTreeMap};
+
+/// A term in a presentation's glossary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct GlossaryEntry {
+    /// The term, e.g. an acronym.
+    pub(crate) term: String,
+
+    /// What the term stands for.
+    pub(crate) expansion: String,
+}
+
+/// Tags the terms in a presentation's glossary wherever they show up in its text.
+///
+/// Terms are only matched as whole words and never inside code. When terms overlap, the longest
+/// one wins.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Glossary {
+    entries: Vec<GlossaryEntry>,
+    case_sensitive: bool,
+    found: RefCell<Vec<GlossaryEntry>>,
+}
+
+impl Glossary {
+    pub(crate) fn new(terms: BTreeMap<String, String>, case_sensitive: bool) -> Self {
+        let mut entries: Vec<_> = terms
+            .into_iter()
+            .filter(|(term, _)| !term.is_empty())
+            .map(|(term, expansion)| GlossaryEntry { term, expansion })
+            .collect();
+        // Try the longest terms first so they take precedence over the ones contained in them.
+        entries.sort_by_key(|entry| Reverse(entry.term.chars().count()));
+        Self { entries, case_sensitive, found: Default::default() }
+    }
+
+    /// Take the entries for every term found since the last time this was called.
+    pub(crate) fn take_found(&self) -> Vec<GlossaryEntry> {
+        self.found.take()
+    }
+
+    // Tags a run of contiguous chunks that may each have a different style, so a term can span
+    // more than one of them.
+    fn tag_run(&self, run: Vec<Text>) -> Vec<Text> {
+        let joined: String = run.iter().map(|chunk| chunk.content.as_str()).collect();
+        let matches = self.find_matches(&joined);
+        if matches.is_empty() {
+            return run;
+        }
+        let mut found = self.found.borrow_mut();
+        for (_, _, entry) in &matches {
+            if !found.contains(entry) {
+                found.push((*entry).clone());
+            }
+        }
+
+        let mut output = Vec::new();
+        let mut chunk_start = 0;
+        for chunk in run {
+            let chunk_end = chunk_start + chunk.content.len();
+            // Split the chunk wherever a match starts or ends within it.
+            let mut boundaries = vec![chunk_start, chunk_end];
+            for (start, end, _) in &matches {
+                boundaries
+                    .extend([*start, *end].into_iter().filter(|offset| (chunk_start..chunk_end).contains(offset)));
+            }
+            boundaries.sort();
+            boundaries.dedup();
+            for window in boundaries.windows(2) {
+                let (start, end) = (window[0], window[1]);
+                let mut piece = Text::new(&joined[start..end], chunk.style.clone());
+                piece.expansion = chunk.expansion.clone();
+                if let Some((_, _, entry)) =
+                    matches.iter().find(|(match_start, match_end, _)| *match_start <= start && end <= *match_end)
+                {
+                    piece.expansion = Some(entry.expansion.clone());
+                }
+                output.push(piece);
+            }
+            chunk_start = chunk_end;
+        }
+        output
+    }
+
+    fn find_matches<'a>(&'a self, text: &str) -> Vec<(usize, usize, &'a GlossaryEntry)> {
+        let mut matches = Vec::new();
+        let mut previous: Option<char> = None;
+        let mut offset = 0;
+        while let Some(next) = text[offset..].chars().next() {
+            if !previous.is_some_and(is_word_char) {
+                let found = self.entries.iter().find_map(|entry| {
+                    let length = self.match_length(&text[offset..], &entry.term)?;
+                    let following = text[offset + length..].chars().next();
+                    (!following.is_some_and(is_word_char)).then_some((entry, length))
+                });
+                if let Some((entry, length)) = found {
+                    matches.push((offset, offset + length, entry));
+                    previous = text[..offset + length].chars().next_back();
+                    offset += length;
+                    continue;
+                }
+            }
+            previous = Some(next);
+            offset += next.len_utf8();
+        }
+        matches
+    }
+
+    // Returns the length in bytes of the term at the beginning of the text, if it's there.
+    fn match_length(&self, text: &str, term: &str) -> Option<usize> {
+        if self.case_sensitive {
+            return text.starts_with(term).then_some(term.len());
+        }
+        let mut length = 0;
+        let mut chars = text.chars();
+        for expected in term.chars() {
+            let actual = chars.next()?;
+            if !actual.to_lowercase().eq(expected.to_lowercase()) {
+                return None;
+            }
+            length += actual.len_utf8();
+        }
+        Some(length)
+    }
+}
+
+impl TextTransform for Glossary {
+    fn stage(&self) -> TransformStage {
+        TransformStage::Tag
+    }
+
+    fn transform(&self, chunks: Vec<Text>) -> Vec<Text> {
+        if self.entries.is_empty() {
+            return chunks;
+        }
+        self.tag_run(chunks)
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        markdown::elements::{Code, CodeLanguage, MarkdownElement, ParagraphElement, TextBlock},
+        processing::transform::TextPipeline,
+        style::TextStyle,
+    };
+    use rstest::rstest;
+    use std::rc::Rc;
+
+    fn make_glossary(terms: &[(&str, &str)], case_sensitive: bool) -> Rc<Glossary> {
+        let terms = terms.iter().map(|(term, expansion)| (term.to_string(), expansion.to_string())).collect();
+        Rc::new(Glossary::new(terms, case_sensitive))
+    }
+
+    fn tag_element(glossary: &Rc<Glossary>, element: &mut MarkdownElement) -> Vec<GlossaryEntry> {
+        let mut pipeline = TextPipeline::default();
+        pipeline.push(glossary.clone());
+        pipeline.apply(element);
+        glossary.take_found()
+    }
+
+    fn tag(glossary: &Rc<Glossary>, text: TextBlock) -> Vec<(String, Option<String>)> {
+        let mut element = MarkdownElement::Paragraph(vec![ParagraphElement::Text(text)]);
+        tag_element(glossary, &mut element);
+        let MarkdownElement::Paragraph(mut elements) = element else { panic!("not a paragraph") };
+        let ParagraphElement::Text(text) = elements.remove(0) else { panic!("not text") };
+        text.0.into_iter().map(|chunk| (chunk.content, chunk.expansion)).collect()
+    }
+
+    fn tagged(content: &str, expansion: &str) -> (String, Option<String>) {
+        (content.into(), Some(expansion.into()))
+    }
+
+    fn untagged(content: &str) -> (String, Option<String>) {
+        (content.into(), None)
+    }
+
+    #[test]
+    fn whole_words() {
+        let glossary = make_glossary(&[("API", "application programming interface")], true);
+        let chunks = tag(&glossary, TextBlock::from("the API, APIs and RAPI"));
+        let expected =
+            &[untagged("the "), tagged("API", "application programming interface"), untagged(", APIs and RAPI")];
+        assert_eq!(chunks, expected);
+    }
+
+    #[rstest]
+    #[case::sensitive(true, vec![untagged("tls and "), tagged("TLS", "transport layer security")])]
+    #[case::insensitive(
+        false,
+        vec![tagged("tls", "transport layer security"), untagged(" and "), tagged("TLS", "transport layer security")]
+    )]
+    fn case_sensitivity(#[case] case_sensitive: bool, #[case] expected: Vec<(String, Option<String>)>) {
+        let glossary = make_glossary(&[("TLS", "transport layer security")], case_sensitive);
+        let chunks = tag(&glossary, TextBlock::from("tls and TLS"));
+        assert_eq!(chunks, expected);
+    }
+
+    #[test]
+    fn overlapping_terms() {
+        let glossary = make_glossary(&[("API", "application programming interface"), ("REST API", "a web API")], true);
+        let chunks = tag(&glossary, TextBlock::from("a REST API is an API"));
+        let expected = &[
+            untagged("a "),
+            tagged("REST API", "a web API"),
+            untagged(" is an "),
+            tagged("API", "application programming interface"),
+        ];
+        assert_eq!(chunks, expected);
+    }
+
+    #[test]
+    fn adjacent_terms() {
+        let glossary = make_glossary(&[("CI", "continuous integration"), ("CD", "continuous delivery")], true);
+        let chunks = tag(&glossary, TextBlock::from("CI/CD"));
+        let expected = &[tagged("CI", "continuous integration"), untagged("/"), tagged("CD", "continuous delivery")];
+        assert_eq!(chunks, expected);
+    }
+
+    #[test]
+    fn split_across_formatting() {
+        let glossary = make_glossary(&[("HTML", "hypertext markup language")], true);
+        let text =
+            TextBlock(vec![Text::from("use "), Text::new("HT", TextStyle::default().bold()), Text::from("ML here")]);
+        let chunks = tag(&glossary, text);
+        let expected = &[
+            untagged("use "),
+            tagged("HT", "hypertext markup language"),
+            tagged("ML", "hypertext markup language"),
+            untagged(" here"),
+        ];
+        assert_eq!(chunks, expected);
+    }
+
+    #[test]
+    fn formatting_preserved() {
+        let glossary = make_glossary(&[("GPU", "graphics processing unit")], true);
+        let text = TextBlock(vec![Text::new("fast GPU!", TextStyle::default().italics())]);
+        let mut element = MarkdownElement::Heading { level: 1, text, source_position: Default::default() };
+        let found = tag_element(&glossary, &mut element);
+        assert_eq!(found, &[GlossaryEntry { term: "GPU".into(), expansion: "graphics processing unit".into() }]);
+
+        let MarkdownElement::Heading { text, .. } = element else { panic!("not a heading") };
+        assert_eq!(text.0.len(), 3);
+        assert!(text.0.iter().all(|chunk| chunk.style.is_italics()));
+        assert_eq!(text.0[1].content, "GPU");
+    }
+
+    #[test]
+    fn code_skipped() {
+        let glossary = make_glossary(&[("CPU", "central processing unit")], true);
+        let text =
+            TextBlock(vec![Text::from("a CPU"), Text::new("CPU", TextStyle::default().code()), Text::from("CPU")]);
+        let chunks = tag(&glossary, text);
+        let expected = &[
+            untagged("a "),
+            tagged("CPU", "central processing unit"),
+            untagged("CPU"),
+            tagged("CPU", "central processing unit"),
+        ];
+        assert_eq!(chunks, expected);
+    }
+
+    #[test]
+    fn code_blocks_skipped() {
+        let glossary = make_glossary(&[("CPU", "central processing unit")], true);
+        let mut element = MarkdownElement::Code(Code {
+            contents: "CPU".into(),
+            language: CodeLanguage::Unknown,
+            language_name: "".into(),
+            attributes: Default::default(),
+        });
+        assert!(tag_element(&glossary, &mut element).is_empty());
+    }
+
+    #[test]
+    fn found_terms_deduplicated() {
+        let glossary = make_glossary(&[("CPU", "central processing unit"), ("GPU", "graphics processing unit")], true);
+        let mut element = MarkdownElement::Paragraph(vec![ParagraphElement::Text("GPU CPU GPU".into())]);
+        let found: Vec<_> = tag_element(&glossary, &mut element).into_iter().map(|entry| entry.term).collect();
+        assert_eq!(found, &["GPU", "CPU"]);
+    }
+}
@@ -0,0 +1,337 @@
+//! Hooks that allow rendering custom code block types.
+//!
+//! A hook is registered for a specific code block language and is given the contents of every
+//! code block that uses it, along with the space available to render it. Code blocks using a
+//! language no hook is registered for are rendered as regular code.
+
+use crate::{
+    cache::CacheUsage,
+    markdown::elements::Text,
+    media::register::ImageRegistry,
+    presentation::{AsRenderOperations, ImageProperties, RenderOperation},
+    render::properties::WindowSize,
+    style::{Color, Colors, TextStyle},
+    theme::Alignment,
+};
+use image::DynamicImage;
+use std::{cell::RefCell, collections::HashMap, fmt, sync::Arc};
+
+/// The error returned by a hook.
+pub type HookError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A hook that renders code blocks that use a specific language.
+///
+/// Hooks can be invoked more than once for the same code block, e.g. when the terminal is resized,
+/// and they take `&self` so any state they carry needs to use interior mutability. They need to be
+/// `Send` and `Sync` so they can carry state shared with other threads.
+pub trait CodeBlockHook: Send + Sync + 'static {
+    /// Render a code block.
+    fn render(&self, source: &str, context: &HookContext) -> Result<HookOutput, HookError>;
+}
+
+impl<F> CodeBlockHook for F
+where
+    F: Fn(&str, &HookContext) -> Result<HookOutput, HookError> + Send + Sync + 'static,
+{
+    fn render(&self, source: &str, context: &HookContext) -> Result<HookOutput, HookError> {
+        self(source, context)
+    }
+}
+
+/// The context in which a code block is being rendered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HookContext {
+    /// The number of columns available.
+    pub columns: u16,
+
+    /// The number of rows available.
+    pub rows: u16,
+
+    /// The theme's default foreground color, if it's an RGB one.
+    pub foreground: Option<(u8, u8, u8)>,
+
+    /// The theme's default background color, if it's an RGB one.
+    pub background: Option<(u8, u8, u8)>,
+}
+
+/// The output of a hook.
+pub enum HookOutput {
+    /// A list of lines of styled text.
+    Lines(Vec<Vec<StyledText>>),
+
+    /// An image, which will be scaled to fit the available space.
+    Image(DynamicImage),
+}
+
+/// A piece of styled text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StyledText {
+    content: String,
+    style: TextStyle,
+}
+
+impl StyledText {
+    /// Construct a new piece of text without any style.
+    pub fn new<S: Into<String>>(content: S) -> Self {
+        Self { content: content.into(), style: TextStyle::default() }
+    }
+
+    /// Make this text bold.
+    pub fn bold(mut self) -> Self {
+        self.style = self.style.bold();
+        self
+    }
+
+    /// Make this text use italics.
+    pub fn italics(mut self) -> Self {
+        self.style = self.style.italics();
+        self
+    }
+
+    /// Make this text underlined.
+    pub fn underlined(mut self) -> Self {
+        self.style = self.style.underlined();
+        self
+    }
+
+    /// Set this text's foreground color.
+    pub fn foreground(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.style.colors.foreground = Some(Color::new(r, g, b));
+        self
+    }
+
+    /// Set this text's background color.
+    pub fn background(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.style.colors.background = Some(Color::new(r, g, b));
+        self
+    }
+}
+
+impl From<StyledText> for Text {
+    fn from(text: StyledText) -> Self {
+        Text::new(text.content, text.style)
+    }
+}
+
+/// The set of hooks registered.
+#[derive(Clone, Default)]
+pub struct RenderHooks {
+    code_blocks: HashMap<String, Arc<dyn CodeBlockHook>>,
+}
+
+impl RenderHooks {
+    /// Register a hook for code blocks that use the given language.
+    ///
+    /// This replaces any hook previously registered for the same language.
+    pub fn register_code_block<H: CodeBlockHook>(&mut self, language: impl Into<String>, hook: H) {
+        self.code_blocks.insert(language.into(), Arc::new(hook));
+    }
+
+    pub(crate) fn code_block(&self, language: &str) -> Option<Arc<dyn CodeBlockHook>> {
+        self.code_blocks.get(language).cloned()
+    }
+}
+
+impl fmt::Debug for RenderHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut languages: Vec<_> = self.code_blocks.keys().collect();
+        languages.sort();
+        f.debug_struct("RenderHooks").field("code_blocks", &languages).finish()
+    }
+}
+
+/// A code block rendered via a hook.
+pub(crate) struct HookedCodeBlock {
+    hook: Arc<dyn CodeBlockHook>,
+    language: String,
+    source: String,
+    colors: Colors,
+    alignment: Alignment,
+    image_registry: ImageRegistry,
+    image_properties: ImageProperties,
+    last_render: RefCell<Option<(HookContext, Vec<RenderOperation>)>>,
+}
+
+impl HookedCodeBlock {
+    pub(crate) fn new(
+        hook: Arc<dyn CodeBlockHook>,
+        language: String,
+        source: String,
+        colors: Colors,
+        alignment: Alignment,
+        image_registry: ImageRegistry,
+        image_properties: ImageProperties,
+    ) -> Self {
+        Self {
+            hook,
+            language,
+            source,
+            colors,
+            alignment,
+            image_registry,
+            image_properties,
+            last_render: Default::default(),
+        }
+    }
+
+    fn render(&self, context: &HookContext) -> Vec<RenderOperation> {
+        let alignment = self.alignment.clone();
+        let output = match self.hook.render(&self.source, context) {
+            Ok(output) => output,
+            Err(e) => return self.render_error(&e.to_string(), alignment),
+        };
+        match output {
+            HookOutput::Lines(lines) => {
+                let mut operations = Vec::new();
+                for line in lines {
+                    let line: Vec<Text> = line.into_iter().map(Text::from).collect();
+                    operations.extend([
+                        RenderOperation::RenderText { line: line.into(), alignment: alignment.clone() },
+                        RenderOperation::RenderLineBreak,
+                    ]);
+                }
+                operations
+            }
+            HookOutput::Image(image) => match self.image_registry.register_image(image) {
+                Ok(image) => vec![
+                    RenderOperation::RenderImage(image, self.image_properties.clone()),
+                    RenderOperation::SetColors(self.colors.clone()),
+                ],
+                Err(e) => self.render_error(&e.to_string(), alignment),
+            },
+        }
+    }
+
+    fn render_error(&self, error: &str, alignment: Alignment) -> Vec<RenderOperation> {
+        let style = TextStyle::default().colors(Colors { foreground: Some(Color::new(255, 0, 0)), background: None });
+        let text = Text::new(format!("failed to render {} block: {error}", self.language), style);
+        vec![RenderOperation::RenderText { line: vec![text].into(), alignment }, RenderOperation::RenderLineBreak]
+    }
+}
+
+impl fmt::Debug for HookedCodeBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HookedCodeBlock").field("language", &self.language).field("source", &self.source).finish()
+    }
+}
+
+impl AsRenderOperations for HookedCodeBlock {
+    fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
+        let context = HookContext {
+            columns: dimensions.columns,
+            rows: dimensions.rows,
+            foreground: self.colors.foreground.and_then(|color| color.as_rgb()),
+            background: self.colors.background.and_then(|color| color.as_rgb()),
+        };
+        // Hooks may be expensive so only invoke them again if the context changed.
+        let mut last_render = self.last_render.borrow_mut();
+        if let Some((last_context, operations)) = last_render.as_ref() {
+            if last_context == &context {
+                return operations.clone();
+            }
+        }
+        let operations = self.render(&context);
+        *last_render = Some((context, operations.clone()));
+        operations
+    }
+
+    fn diffable_content(&self) -> Option<&str> {
+        Some(&self.source)
+    }
+
+    fn cache_usage(&self) -> Option<CacheUsage> {
+        let last_render = self.last_render.borrow();
+        last_render.as_ref().map(|(_, operations)| CacheUsage::of_operations(operations))
+    }
+
+    fn evict_cache(&self) {
+        self.last_render.take();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::theme::Margin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingHook(AtomicUsize);
+
+    impl CodeBlockHook for CountingHook {
+        fn render(&self, source: &str, context: &HookContext) -> Result<HookOutput, HookError> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            let line = vec![StyledText::new(format!("{source} {}", context.columns)).bold()];
+            Ok(HookOutput::Lines(vec![line]))
+        }
+    }
+
+    fn make_block(hook: Arc<dyn CodeBlockHook>) -> HookedCodeBlock {
+        HookedCodeBlock::new(
+            hook,
+            "toy".into(),
+            "hi".into(),
+            Default::default(),
+            Alignment::Center { minimum_margin: Margin::Fixed(2), minimum_size: 0 },
+            Default::default(),
+            ImageProperties { z_index: 0, size: Default::default(), restore_cursor: false, background_color: None },
+        )
+    }
+
+    fn rendered_text(operations: &[RenderOperation]) -> Vec<String> {
+        operations
+            .iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderText { line, .. } => {
+                    Some(line.iter_texts().map(|text| text.text().content.clone()).collect())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rendering_is_cached() {
+        let hook = Arc::new(CountingHook::default());
+        let block = make_block(hook.clone());
+        let dimensions = WindowSize { rows: 10, columns: 20, width: 0, height: 0 };
+        let operations = block.as_render_operations(&dimensions);
+        assert_eq!(rendered_text(&operations), &["hi 20"]);
+        block.as_render_operations(&dimensions);
+        assert_eq!(hook.0.load(Ordering::Relaxed), 1);
+
+        let dimensions = WindowSize { rows: 10, columns: 30, width: 0, height: 0 };
+        let operations = block.as_render_operations(&dimensions);
+        assert_eq!(rendered_text(&operations), &["hi 30"]);
+        assert_eq!(hook.0.load(Ordering::Relaxed), 2);
+
+        assert!(block.cache_usage().is_some());
+        block.evict_cache();
+        assert_eq!(block.cache_usage(), None);
+        block.as_render_operations(&dimensions);
+        assert_eq!(hook.0.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn hook_error() {
+        let hook = |_: &str, _: &HookContext| -> Result<HookOutput, HookError> { Err("oops".into()) };
+        let block = make_block(Arc::new(hook));
+        let operations = block.as_render_operations(&WindowSize { rows: 10, columns: 20, width: 0, height: 0 });
+        assert_eq!(rendered_text(&operations), &["failed to render toy block: oops"]);
+    }
+
+    #[test]
+    fn theme_alignment() {
+        let block = make_block(Arc::new(CountingHook::default()));
+        let operations = block.as_render_operations(&WindowSize { rows: 10, columns: 20, width: 0, height: 0 });
+        let expected = Alignment::Center { minimum_margin: Margin::Fixed(2), minimum_size: 0 };
+        let alignments: Vec<_> = operations
+            .iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderText { alignment, .. } => Some(alignment),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(alignments, &[&expected]);
+    }
+}
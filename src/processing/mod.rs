@@ -1,7 +1,23 @@
+pub(crate) mod bigtext;
 pub(crate) mod builder;
 pub(crate) mod code;
+pub(crate) mod countdown;
+pub(crate) mod deferred;
+pub(crate) mod details;
+pub(crate) mod directives;
+pub(crate) mod end_screen;
 pub(crate) mod execution;
+pub(crate) mod focus;
 pub(crate) mod footer;
+pub(crate) mod footnotes;
+pub(crate) mod glossary;
+pub(crate) mod hooks;
 pub(crate) mod modals;
 pub(crate) mod padding;
+pub(crate) mod redaction;
 pub(crate) mod separator;
+pub(crate) mod shortcodes;
+pub(crate) mod split;
+pub(crate) mod structure;
+pub(crate) mod table;
+pub(crate) mod transform;
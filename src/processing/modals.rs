@@ -3,23 +3,33 @@ use crate::{
     input::user::KeyBinding,
     markdown::{
         elements::{Text, TextBlock},
-        text::WeightedTextBlock,
+        text::{truncate_styled, WeightedTextBlock},
     },
     media::image::Image,
     presentation::{
         AsRenderOperations, ImageProperties, ImageSize, MarginProperties, PresentationState, RenderOperation,
+        SlideNumbering,
     },
-    processing::padding::NumberPadder,
+    processing::{glossary::GlossaryEntry, padding::NumberPadder},
     render::properties::WindowSize,
     style::{Colors, TextStyle},
     theme::Margin,
     PresentationTheme,
 };
-use std::{iter, rc::Rc};
+use std::{ops::Range, rc::Rc};
 use unicode_width::UnicodeWidthStr;
 
 static MODAL_Z_INDEX: i32 = -1;
 
+// The borders and the heading take up this many rows.
+static CHROME_ROWS: usize = 4;
+
+// The borders and the padding around each row take up this many columns.
+static CHROME_COLUMNS: usize = 6;
+
+// Ensure we have a minimum width so modals don't look too narrow.
+static MIN_CONTENT_WIDTH: usize = 12;
+
 #[derive(Default)]
 pub(crate) struct IndexBuilder {
     titles: Vec<TextBlock>,
@@ -35,74 +45,30 @@ impl IndexBuilder {
         self.background = Some(background);
     }
 
-    pub(crate) fn build(self, theme: &PresentationTheme, state: PresentationState) -> Vec<RenderOperation> {
-        let mut builder = ModalBuilder::new("Slides");
-        let padder = NumberPadder::new(self.titles.len());
+    pub(crate) fn build(
+        self,
+        theme: &PresentationTheme,
+        state: PresentationState,
+        numbering: &SlideNumbering,
+    ) -> Vec<RenderOperation> {
+        let padder = NumberPadder::new(numbering.total().max(1));
+        let mut rows = Vec::new();
         for (index, mut title) in self.titles.into_iter().enumerate() {
-            let index = padder.pad_right(index + 1);
-            title.0.insert(0, format!("{index}: ").into());
-            builder.content.push(title);
+            // Unnumbered slides are still listed so that every row maps to a slide.
+            let number = match numbering.display_number(index) {
+                Some(number) => format!("{}: ", padder.pad_right(number)),
+                None => " ".repeat(padder.width() + 2),
+            };
+            title.0.insert(0, number.into());
+            rows.push(title);
         }
-        let base_color = theme.modals.colors.merge(&theme.default_style.colors);
-        let selection_style = TextStyle::default().colors(theme.modals.selection_colors.clone()).bold();
-        let ModalContent { prefix, content, suffix, content_width } = builder.build(base_color);
-        let drawer = IndexDrawer {
-            prefix,
-            rows: content,
-            suffix,
-            state,
-            content_width,
-            selection_style,
-            background: self.background,
-        };
+        let drawer = ModalDrawer::new("Slides", rows, ModalColors::new(theme))
+            .with_selection(state)
+            .with_background(self.background);
         vec![RenderOperation::RenderDynamic(Rc::new(drawer))]
     }
 }
 
-#[derive(Debug)]
-struct IndexDrawer {
-    prefix: Vec<RenderOperation>,
-    rows: Vec<ContentRow>,
-    suffix: Vec<RenderOperation>,
-    content_width: u16,
-    state: PresentationState,
-    selection_style: TextStyle,
-    background: Option<Image>,
-}
-
-impl AsRenderOperations for IndexDrawer {
-    fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
-        let current_slide_index = self.state.current_slide_index();
-        let max_rows = (dimensions.rows as f64 * 0.8) as u16;
-        let (skip, take) = match self.rows.len() as u16 > max_rows {
-            true => {
-                let start = (current_slide_index as u16).saturating_sub(max_rows / 2);
-                let start = start.min(self.rows.len() as u16 - max_rows);
-                (start as usize, max_rows as usize)
-            }
-            false => (0, self.rows.len()),
-        };
-        let visible_rows = self.rows.iter().enumerate().skip(skip).take(take);
-        let mut operations = vec![CenterModalContent::new(self.content_width, take, self.background.clone()).into()];
-        operations.extend(self.prefix.iter().cloned());
-        for (index, row) in visible_rows {
-            let mut row = row.clone();
-            if index == current_slide_index {
-                row = row.with_style(self.selection_style.clone());
-            }
-            let operation = RenderOperation::RenderText { line: row.build(), alignment: Default::default() };
-            operations.extend([operation, RenderOperation::RenderLineBreak]);
-        }
-        operations.extend(self.suffix.iter().cloned());
-        operations
-    }
-
-    fn diffable_content(&self) -> Option<&str> {
-        // The index is just a view over the underlying data so it won't change in isolation.
-        None
-    }
-}
-
 #[derive(Default)]
 pub(crate) struct KeyBindingsModalBuilder {
     background: Option<Image>,
@@ -114,8 +80,7 @@ impl KeyBindingsModalBuilder {
     }
 
     pub(crate) fn build(self, theme: &PresentationTheme, config: &KeyBindingsConfig) -> Vec<RenderOperation> {
-        let mut builder = ModalBuilder::new("Key bindings");
-        builder.content.extend([
+        let mut rows = vec![
             Self::build_line("Next", &config.next),
             Self::build_line("Previous", &config.previous),
             Self::build_line("First slide", &config.first_slide),
@@ -125,15 +90,18 @@ impl KeyBindingsModalBuilder {
             Self::build_line("Reload", &config.reload),
             Self::build_line("Toggle slide index", &config.toggle_slide_index),
             Self::build_line("Close modal", &config.close_modal),
-            Self::build_line("Exit", &config.exit),
-        ]);
-        let lines = builder.content.len();
-        let colors = theme.default_style.colors.clone().merge(&theme.modals.colors);
-        let content = builder.build(colors);
-        let content_width = content.content_width;
-        let mut operations = content.into_operations();
-        operations.insert(0, CenterModalContent::new(content_width, lines, self.background).into());
-        operations
+            Self::build_line("Toggle blank screen", &config.toggle_blank),
+            Self::build_line("Cycle preview dimensions", &config.cycle_preview_dimensions),
+            Self::build_line("Toggle glossary", &config.toggle_glossary),
+            Self::build_line("Focus code", &config.focus_code),
+            Self::build_line("Toggle details", &config.toggle_details),
+        ];
+        if !config.toggle_redactions.is_empty() {
+            rows.push(Self::build_line("Toggle redactions", &config.toggle_redactions));
+        }
+        rows.extend([Self::build_line("Command line", &config.command_line), Self::build_line("Exit", &config.exit)]);
+        let drawer = ModalDrawer::new("Key bindings", rows, ModalColors::new(theme)).with_background(self.background);
+        vec![RenderOperation::RenderDynamic(Rc::new(drawer))]
     }
 
     fn build_line(label: &str, bindings: &[KeyBinding]) -> TextBlock {
@@ -148,181 +116,303 @@ impl KeyBindingsModalBuilder {
     }
 }
 
-struct ModalBuilder {
-    heading: String,
-    content: Vec<TextBlock>,
+/// Builds the modal that shows the glossary terms used in the current slide.
+#[derive(Default)]
+pub(crate) struct GlossaryModalBuilder {
+    slides: Vec<Vec<GlossaryEntry>>,
+    background: Option<Image>,
 }
 
-impl ModalBuilder {
-    fn new<S: Into<String>>(heading: S) -> Self {
-        Self { heading: heading.into(), content: Vec::new() }
+impl GlossaryModalBuilder {
+    pub(crate) fn add_slide(&mut self, entries: Vec<GlossaryEntry>) {
+        self.slides.push(entries);
     }
 
-    fn build(self, colors: Colors) -> ModalContent {
-        let longest_line = self.content.iter().map(TextBlock::width).max().unwrap_or(0) as u16;
-        let longest_line = longest_line.max(self.heading.len() as u16);
-        // Ensure we have a minimum width so it doesn't look too narrow.
-        let longest_line = longest_line.max(12);
-        // The final text looks like "|  <content>  |"
-        let content_width = longest_line + 6;
-        let mut prefix = vec![RenderOperation::SetColors(colors)];
-
-        let heading = Self::center_line(self.heading, longest_line as usize);
-        prefix.extend(Border::Top.render_line(content_width));
-        prefix.extend([
-            RenderOperation::RenderText {
-                line: Self::build_line(vec![Text::from(heading)], content_width).build(),
-                alignment: Default::default(),
-            },
-            RenderOperation::RenderLineBreak,
-        ]);
-        prefix.extend(Border::Separator.render_line(content_width));
-        let mut content = Vec::new();
-        for title in self.content {
-            content.push(Self::build_line(title.0, content_width));
+    pub(crate) fn set_background(&mut self, background: Image) {
+        self.background = Some(background);
+    }
+
+    pub(crate) fn build(self, theme: &PresentationTheme, state: PresentationState) -> Vec<RenderOperation> {
+        let colors = ModalColors::new(theme);
+        let mut slides = Vec::new();
+        for entries in self.slides {
+            let mut rows = Vec::new();
+            for entry in entries {
+                let line =
+                    vec![Text::new(entry.term, TextStyle::default().bold()), ": ".into(), entry.expansion.into()];
+                rows.push(TextBlock(line));
+            }
+            if rows.is_empty() {
+                rows.push(Text::new("no terms in this slide", TextStyle::default().italics()).into());
+            }
+            let drawer = ModalDrawer::new("Terms", rows, colors.clone()).with_background(self.background.clone());
+            slides.push(drawer);
         }
-        let suffix = Border::Bottom.render_line(content_width).into_iter().collect();
-        ModalContent { prefix, content, suffix, content_width }
+        vec![RenderOperation::RenderDynamic(Rc::new(GlossaryDrawer { slides, state }))]
     }
+}
 
-    fn center_line(text: String, longest_line: usize) -> String {
-        let missing = longest_line.saturating_sub(text.len());
-        let padding = missing / 2;
-        let mut output = " ".repeat(padding);
-        output.push_str(&text);
-        output.extend(iter::repeat(' ').take(padding));
-        output
+#[derive(Debug)]
+struct GlossaryDrawer {
+    slides: Vec<ModalDrawer>,
+    state: PresentationState,
+}
+
+impl AsRenderOperations for GlossaryDrawer {
+    fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
+        match self.slides.get(self.state.current_slide_index()) {
+            Some(drawer) => drawer.as_render_operations(dimensions),
+            None => Vec::new(),
+        }
     }
 
-    fn build_line(text_chunks: Vec<Text>, content_width: u16) -> ContentRow {
-        let (opening, closing) = Border::Regular.edges();
-        let prefix = Text::from(format!("{opening}  "));
-        let content = text_chunks;
-        let total_width = content.iter().map(|c| c.content.width()).sum::<usize>() + prefix.content.width();
-        let missing = content_width as usize - 1 - total_width;
-
-        let mut suffix = " ".repeat(missing);
-        suffix.push(closing);
-        ContentRow { prefix, content, suffix: suffix.into() }
+    fn diffable_content(&self) -> Option<&str> {
+        // Like the index, this is just a view over the underlying data.
+        None
     }
 }
 
-struct ModalContent {
-    prefix: Vec<RenderOperation>,
-    content: Vec<ContentRow>,
-    suffix: Vec<RenderOperation>,
-    content_width: u16,
+/// The colors a modal is drawn with.
+#[derive(Clone, Debug)]
+struct ModalColors {
+    base: Colors,
+    border: Colors,
+    selection: TextStyle,
 }
 
-impl ModalContent {
-    fn into_operations(self) -> Vec<RenderOperation> {
-        let mut operations = self.prefix;
-        operations.extend(self.content.into_iter().flat_map(|c| {
-            [
-                RenderOperation::RenderText { line: c.build(), alignment: Default::default() },
-                RenderOperation::RenderLineBreak,
-            ]
-        }));
-        operations.extend(self.suffix);
-        operations
+impl ModalColors {
+    fn new(theme: &PresentationTheme) -> Self {
+        let base = theme.modals.colors.merge(&theme.default_style.colors);
+        let border = theme.modals.border_colors.merge(&base);
+        let selection_colors = theme.modals.selection_colors.clone();
+        // Without colors of its own the selected row is drawn with its colors swapped so it still stands out.
+        let selection = match selection_colors == Colors::default() {
+            true => TextStyle::default().bold().inverse(),
+            false => TextStyle::default().colors(selection_colors).bold(),
+        };
+        Self { base, border, selection }
     }
 }
 
-#[derive(Clone, Debug)]
-struct ContentRow {
-    prefix: Text,
-    content: Vec<Text>,
-    suffix: Text,
+/// A modal, laid out every time it's rendered so it always fits in the terminal.
+///
+/// Rows that are too wide are truncated and rows that don't fit vertically are cut off. When a
+/// row is selected, the visible rows scroll so the selected one is always shown.
+#[derive(Debug)]
+struct ModalDrawer {
+    heading: String,
+    rows: Vec<TextBlock>,
+    colors: ModalColors,
+    selection: Option<PresentationState>,
+    background: Option<Image>,
 }
 
-impl ContentRow {
-    fn with_style(mut self, style: TextStyle) -> ContentRow {
-        for chunk in &mut self.content {
-            chunk.style.merge(&style);
-        }
+impl ModalDrawer {
+    fn new<S: Into<String>>(heading: S, rows: Vec<TextBlock>, colors: ModalColors) -> Self {
+        Self { heading: heading.into(), rows, colors, selection: None, background: None }
+    }
+
+    /// Select the row at the current slide's index.
+    fn with_selection(mut self, state: PresentationState) -> Self {
+        self.selection = Some(state);
         self
     }
 
-    fn build(self) -> WeightedTextBlock {
-        let mut chunks = self.content;
-        chunks.insert(0, self.prefix);
-        chunks.push(self.suffix);
-        WeightedTextBlock::from(chunks)
+    fn with_background(mut self, background: Option<Image>) -> Self {
+        self.background = background;
+        self
     }
-}
 
-enum Border {
-    Regular,
-    Top,
-    Separator,
-    Bottom,
-}
+    fn selected_row(&self) -> Option<usize> {
+        self.selection.as_ref().map(PresentationState::current_slide_index)
+    }
 
-impl Border {
-    fn render_line(&self, content_length: u16) -> [RenderOperation; 2] {
-        let (opening, closing) = self.edges();
+    fn visible_rows(&self, max_rows: usize) -> Range<usize> {
+        if self.rows.len() <= max_rows {
+            return 0..self.rows.len();
+        }
+        let start = match self.selected_row() {
+            Some(selected) => selected.saturating_sub(max_rows / 2).min(self.rows.len() - max_rows),
+            None => 0,
+        };
+        start..start + max_rows
+    }
+
+    fn border_line(&self, border: Border, width: usize) -> [RenderOperation; 2] {
+        let (opening, closing) = border.edges();
         let mut line = String::from(opening);
-        line.push_str(&"─".repeat(content_length.saturating_sub(2) as usize));
+        line.push_str(&"─".repeat(width.saturating_sub(2)));
         line.push(closing);
-        let horizontal_border = WeightedTextBlock::from(vec![Text::from(line)]);
+        let line = vec![Text::new(line, TextStyle::default().colors(self.colors.border.clone()))];
         [
-            RenderOperation::RenderText { line: horizontal_border.clone(), alignment: Default::default() },
+            RenderOperation::RenderText { line: WeightedTextBlock::from(line), alignment: Default::default() },
             RenderOperation::RenderLineBreak,
         ]
     }
 
-    fn edges(&self) -> (char, char) {
-        match self {
-            Self::Regular => ('│', '│'),
-            Self::Top => ('┌', '┐'),
-            Self::Separator => ('├', '┤'),
-            Self::Bottom => ('└', '┘'),
-        }
+    // Builds a line that looks like "│  <content>  │".
+    fn content_line(&self, content: TextBlock, content_width: usize) -> [RenderOperation; 2] {
+        let (opening, closing) = Border::Regular.edges();
+        let border_style = TextStyle::default().colors(self.colors.border.clone());
+        let padding = content_width.saturating_sub(content.width()) + 2;
+        let mut line = vec![Text::new(opening.to_string(), border_style.clone()), Text::from("  ")];
+        line.extend(content.0);
+        line.extend([Text::from(" ".repeat(padding)), Text::new(closing.to_string(), border_style)]);
+        [
+            RenderOperation::RenderText { line: WeightedTextBlock::from(line), alignment: Default::default() },
+            RenderOperation::RenderLineBreak,
+        ]
     }
 }
 
-#[derive(Debug)]
-struct CenterModalContent {
-    content_width: u16,
-    content_height: usize,
-    background: Option<Image>,
-}
+impl AsRenderOperations for ModalDrawer {
+    fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
+        let max_width = (dimensions.columns as usize).saturating_sub(CHROME_COLUMNS);
+        let max_rows = (dimensions.rows as f64 * 0.8) as usize;
+        let max_rows = max_rows.min((dimensions.rows as usize).saturating_sub(CHROME_ROWS)).max(1);
 
-impl CenterModalContent {
-    fn new(content_width: u16, content_height: usize, background: Option<Image>) -> Self {
-        Self { content_width, content_height, background }
-    }
-}
+        let longest_line = self.rows.iter().map(TextBlock::width).max().unwrap_or(0).max(self.heading.width());
+        let content_width = longest_line.max(MIN_CONTENT_WIDTH).min(max_width);
+        let width = content_width + CHROME_COLUMNS;
+        let visible_rows = self.visible_rows(max_rows);
+        let height = visible_rows.len() + CHROME_ROWS;
 
-impl AsRenderOperations for CenterModalContent {
-    fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation> {
-        let margin = dimensions.columns.saturating_sub(self.content_width) / 2;
+        let margin = dimensions.columns.saturating_sub(width as u16) / 2;
         let properties = MarginProperties { horizontal_margin: Margin::Fixed(margin), bottom_slide_margin: 0 };
-        // However many we see + 3 for the title and 1 at the bottom.
-        let content_height = (self.content_height + 4) as u16;
-        let target_row = dimensions.rows.saturating_sub(content_height) / 2;
-
+        let target_row = dimensions.rows.saturating_sub(height as u16) / 2;
         let mut operations =
             vec![RenderOperation::ApplyMargin(properties), RenderOperation::JumpToRow { index: target_row }];
         if let Some(image) = &self.background {
             let properties = ImageProperties {
                 z_index: MODAL_Z_INDEX,
-                size: ImageSize::Specific(self.content_width, content_height),
+                size: ImageSize::Specific(width as u16, height as u16),
                 restore_cursor: true,
                 background_color: None,
             };
             operations.push(RenderOperation::RenderImage(image.clone(), properties));
         }
+        operations.push(RenderOperation::SetColors(self.colors.base.clone()));
+
+        let heading = truncate_styled(&TextBlock::from(self.heading.as_str()), content_width);
+        let mut centered_heading = TextBlock::from(" ".repeat(content_width.saturating_sub(heading.width()) / 2));
+        centered_heading.0.extend(heading.0);
+        operations.extend(self.border_line(Border::Top, width));
+        operations.extend(self.content_line(centered_heading, content_width));
+        operations.extend(self.border_line(Border::Separator, width));
+        for index in visible_rows {
+            let mut row = truncate_styled(&self.rows[index], content_width);
+            if self.selected_row() == Some(index) {
+                for chunk in &mut row.0 {
+                    chunk.style.merge(&self.colors.selection);
+                }
+            }
+            operations.extend(self.content_line(row, content_width));
+        }
+        operations.extend(self.border_line(Border::Bottom, width));
         operations
     }
 
     fn diffable_content(&self) -> Option<&str> {
+        // Modals are just a view over the underlying data so they won't change in isolation.
         None
     }
 }
 
-impl From<CenterModalContent> for RenderOperation {
-    fn from(op: CenterModalContent) -> Self {
-        Self::RenderDynamic(Rc::new(op))
+enum Border {
+    Regular,
+    Top,
+    Separator,
+    Bottom,
+}
+
+impl Border {
+    fn edges(&self) -> (char, char) {
+        match self {
+            Self::Regular => ('│', '│'),
+            Self::Top => ('┌', '┐'),
+            Self::Separator => ('├', '┤'),
+            Self::Bottom => ('└', '┘'),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    fn drawer(rows: usize) -> ModalDrawer {
+        let rows = (0..rows).map(|index| TextBlock::from(format!("row {index}"))).collect();
+        let colors =
+            ModalColors { base: Colors::default(), border: Colors::default(), selection: TextStyle::default() };
+        ModalDrawer::new("Title", rows, colors)
+    }
+
+    fn render(drawer: &ModalDrawer, columns: u16, rows: u16) -> Vec<String> {
+        let dimensions = WindowSize { rows, columns, width: 0, height: 0 };
+        drawer
+            .as_render_operations(&dimensions)
+            .into_iter()
+            .filter_map(|operation| match operation {
+                RenderOperation::RenderText { line, .. } => {
+                    Some(line.iter_texts().map(|text| text.text().content.as_str()).collect())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layout() {
+        let lines = render(&drawer(2), 80, 20);
+        let expected = &[
+            "┌────────────────┐",
+            "│     Title      │",
+            "├────────────────┤",
+            "│  row 0         │",
+            "│  row 1         │",
+            "└────────────────┘",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn clamp_width() {
+        let drawer = ModalDrawer::new("Title", vec![TextBlock::from("a long line that doesn't fit")], drawer(0).colors);
+        let lines = render(&drawer, 20, 20);
+        let expected = &[
+            "┌──────────────────┐",
+            "│      Title       │",
+            "├──────────────────┤",
+            "│  a long line t…  │",
+            "└──────────────────┘",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn clamp_height() {
+        // 80% of 10 rows is 8 but only 6 fit once the borders and the heading are accounted for.
+        let lines = render(&drawer(20), 80, 10);
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[3], "│  row 0         │");
+        assert_eq!(lines[8], "│  row 5         │");
+    }
+
+    #[rstest]
+    #[case::first(0, 0)]
+    #[case::middle(10, 7)]
+    #[case::last(19, 14)]
+    fn scroll_to_selection(#[case] selected: usize, #[case] first_row: usize) {
+        let state = PresentationState::default();
+        state.set_current_slide_index(selected);
+        let drawer = drawer(20).with_selection(state);
+        assert_eq!(drawer.visible_rows(6), first_row..first_row + 6);
+    }
+
+    #[test]
+    fn no_scrolling_without_selection() {
+        assert_eq!(drawer(20).visible_rows(6), 0..6);
+        assert_eq!(drawer(3).visible_rows(6), 0..3);
     }
 }
@@ -0,0 +1,304 @@
+use super::transform::{TextTransform, TransformStage};
+use crate::{markdown::elements::Text, presentation::RedactionConfig};
+use std::{borrow::Cow, ops::Range};
+use syntect::parsing::{Regex, Region};
+use unicode_width::UnicodeWidthStr;
+
+// The delimiter around spoilers, like `||this||`.
+const SPOILER_DELIMITER: &str = "||";
+
+// The character redacted text is replaced with.
+const BLOCK: char = '█';
+
+/// Hides sensitive text in a presentation, like tokens or customer names.
+///
+/// Redacted text is replaced with as many blocks as columns it takes up so the layout of the text
+/// around it doesn't change. When redactions are revealed, spoiler delimiters are still removed
+/// but the text in them is left as is.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Redactions {
+    spoilers: bool,
+    patterns: Vec<Regex>,
+    reveal: bool,
+}
+
+impl Redactions {
+    pub(crate) fn new(config: &RedactionConfig, reveal: bool) -> Result<Self, InvalidRedactionPattern> {
+        let mut patterns = Vec::new();
+        for pattern in &config.patterns {
+            if let Some(e) = Regex::try_compile(pattern) {
+                return Err(InvalidRedactionPattern { pattern: pattern.clone(), error: e.to_string() });
+            }
+            patterns.push(Regex::new(pattern.clone()));
+        }
+        Ok(Self { spoilers: config.spoilers, patterns, reveal })
+    }
+
+    /// Get a copy of these redactions that are never revealed.
+    pub(crate) fn concealed(&self) -> Self {
+        Self { reveal: false, ..self.clone() }
+    }
+
+    /// Whether this doesn't redact anything.
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.spoilers && self.patterns.is_empty()
+    }
+
+    /// Redact a line of highlighted code.
+    ///
+    /// This runs after highlighting so the code is highlighted just like it would be otherwise,
+    /// and every redacted piece of code is displayed using a single style so the boundaries
+    /// between the tokens in it aren't visible. Spoilers aren't a thing in code.
+    pub(crate) fn redact_tokens<'a, S: Clone>(&self, tokens: Vec<(S, &'a str)>) -> Vec<(S, Cow<'a, str>)> {
+        let segments: Vec<_> = tokens.iter().map(|(_, tokens)| Segment { text: tokens, spoilers: false }).collect();
+        match self.redact(&segments, self.reveal) {
+            Some(pieces) => {
+                pieces.into_iter().map(|piece| (tokens[piece.segment].0.clone(), piece.text.into())).collect()
+            }
+            None => tokens.into_iter().map(|(style, tokens)| (style, tokens.into())).collect(),
+        }
+    }
+
+    /// Redact a piece of plain text.
+    pub(crate) fn redact_text(&self, text: &str) -> String {
+        self.redact_plain(text, self.reveal)
+    }
+
+    /// Redact a piece of text that's shown outside of the presentation itself, like a slide title.
+    ///
+    /// This ignores whether redactions are revealed as that only applies to what's on screen.
+    pub(crate) fn conceal(&self, text: &str) -> String {
+        self.redact_plain(text, false)
+    }
+
+    fn redact_plain(&self, text: &str, reveal: bool) -> String {
+        match self.redact(&[Segment { text, spoilers: true }], reveal) {
+            Some(pieces) => pieces.into_iter().map(|piece| piece.text).collect(),
+            None => text.into(),
+        }
+    }
+
+    // Returns `None` if nothing in the segments is redacted.
+    fn redact(&self, segments: &[Segment], reveal: bool) -> Option<Vec<Piece>> {
+        let text: String = segments.iter().map(|segment| segment.text).collect();
+        let mut owners = Vec::with_capacity(text.len());
+        for (index, segment) in segments.iter().enumerate() {
+            owners.extend(std::iter::repeat(index).take(segment.text.len()));
+        }
+
+        let mut masks = Vec::new();
+        let mut removals = Vec::new();
+        if self.spoilers {
+            // Delimiters within code don't count but anything between them, code included, is redacted.
+            let delimiters: Vec<_> = text
+                .match_indices(SPOILER_DELIMITER)
+                .map(|(index, _)| index)
+                .filter(|index| segments[owners[*index]].spoilers && segments[owners[index + 1]].spoilers)
+                .collect();
+            for pair in delimiters.chunks_exact(2) {
+                let (start, end) = (pair[0], pair[1]);
+                if end == start + SPOILER_DELIMITER.len() {
+                    continue;
+                }
+                removals.push(start..start + SPOILER_DELIMITER.len());
+                removals.push(end..end + SPOILER_DELIMITER.len());
+                masks.push(start + SPOILER_DELIMITER.len()..end);
+            }
+        }
+        for pattern in &self.patterns {
+            masks.extend(Self::find_matches(pattern, &text));
+        }
+        if reveal {
+            masks.clear();
+        }
+        if masks.is_empty() && removals.is_empty() {
+            return None;
+        }
+
+        // Every byte is either kept, removed, or part of the mask with the given index.
+        let mut states = vec![ByteState::Kept; text.len()];
+        masks.sort_by_key(|mask| mask.start);
+        let mut mask_index = 0;
+        let mut last_end = 0;
+        for mask in masks {
+            // Overlapping masks are merged into a single one.
+            if mask.start >= last_end {
+                mask_index += 1;
+            }
+            last_end = last_end.max(mask.end);
+            states[mask.clone()].fill(ByteState::Masked(mask_index));
+        }
+        for removal in removals {
+            states[removal].fill(ByteState::Removed);
+        }
+
+        let mut pieces: Vec<Piece> = Vec::new();
+        for (index, c) in text.char_indices() {
+            let state = states[index];
+            let segment = owners[index];
+            match (state, pieces.last_mut()) {
+                (ByteState::Removed, _) => continue,
+                (ByteState::Kept, Some(last)) if last.state == state && last.segment == segment => last.text.push(c),
+                // Masks keep the style of the segment they start in.
+                (ByteState::Masked(_), Some(last)) if last.state == state => last.text.push(c),
+                _ => pieces.push(Piece { segment, state, text: c.to_string() }),
+            };
+        }
+        for piece in &mut pieces {
+            if matches!(piece.state, ByteState::Masked(_)) {
+                piece.text = BLOCK.to_string().repeat(piece.text.width());
+            }
+        }
+        pieces.retain(|piece| !piece.text.is_empty());
+        Some(pieces)
+    }
+
+    fn find_matches(pattern: &Regex, text: &str) -> Vec<Range<usize>> {
+        let mut matches = Vec::new();
+        let mut region = Region::new();
+        let mut start = 0;
+        while start < text.len() && pattern.search(text, start, text.len(), Some(&mut region)) {
+            let Some((match_start, match_end)) = region.pos(0) else {
+                break;
+            };
+            if match_end > match_start {
+                matches.push(match_start..match_end);
+                start = match_end;
+            } else {
+                // Empty matches don't redact anything so skip past them.
+                start = match_start + text[match_start..].chars().next().map(char::len_utf8).unwrap_or(1);
+            }
+        }
+        matches
+    }
+}
+
+impl TextTransform for Redactions {
+    fn stage(&self) -> TransformStage {
+        TransformStage::Substitute
+    }
+
+    fn transforms_code(&self) -> bool {
+        true
+    }
+
+    fn transform(&self, chunks: Vec<Text>) -> Vec<Text> {
+        let segments: Vec<_> =
+            chunks.iter().map(|chunk| Segment { text: &chunk.content, spoilers: !chunk.style.is_code() }).collect();
+        match self.redact(&segments, self.reveal) {
+            Some(pieces) => {
+                pieces.into_iter().map(|piece| Text { content: piece.text, ..chunks[piece.segment].clone() }).collect()
+            }
+            None => chunks,
+        }
+    }
+}
+
+/// A redaction pattern that isn't a valid regular expression.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid redaction pattern '{pattern}': {error}")]
+pub(crate) struct InvalidRedactionPattern {
+    pattern: String,
+    error: String,
+}
+
+struct Segment<'a> {
+    text: &'a str,
+    spoilers: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ByteState {
+    Kept,
+    Removed,
+    Masked(usize),
+}
+
+struct Piece {
+    segment: usize,
+    state: ByteState,
+    text: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::style::TextStyle;
+    use rstest::rstest;
+
+    fn redactions(spoilers: bool, patterns: &[&str]) -> Redactions {
+        let patterns = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        let config = RedactionConfig { spoilers, patterns };
+        Redactions::new(&config, false).expect("invalid config")
+    }
+
+    fn contents(chunks: &[Text]) -> Vec<&str> {
+        chunks.iter().map(|chunk| chunk.content.as_str()).collect()
+    }
+
+    #[rstest]
+    #[case::spoiler("my ||secret|| token", "my ██████ token")]
+    #[case::many_spoilers("||a|| and ||bc||", "█ and ██")]
+    #[case::pattern("key sk-abc123 here", "key █████████ here")]
+    #[case::many_matches("sk-a sk-b", "████ ████")]
+    #[case::overlapping("||sk-a|| b", "████ b")]
+    #[case::unclosed("a || b", "a || b")]
+    #[case::empty_spoiler("a |||| b", "a |||| b")]
+    #[case::wide("||日本|| x", "████ x")]
+    #[case::combining("||e\u{301}|| x", "█ x")]
+    #[case::emoji("||🚀|| x", "██ x")]
+    fn redact_text(#[case] input: &str, #[case] expected: &str) {
+        let output = redactions(true, &["sk-[a-z0-9]+"]).transform(vec![Text::from(input)]);
+        assert_eq!(contents(&output).concat(), expected);
+    }
+
+    #[test]
+    fn spoilers_across_styles() {
+        let chunks = vec![
+            Text::from("a ||b "),
+            Text::new("c", TextStyle::default().bold()),
+            Text::new("d", TextStyle::default().code()),
+            Text::from("|| e"),
+        ];
+        let output = redactions(true, &[]).transform(chunks);
+        // The whole spoiler uses the style it starts with.
+        assert_eq!(output, &[Text::from("a "), Text::from("████"), Text::from(" e")]);
+    }
+
+    #[test]
+    fn spoiler_delimiters_in_code() {
+        let chunks = vec![Text::from("a "), Text::new("x || y", TextStyle::default().code()), Text::from(" ||b||")];
+        let output = redactions(true, &[]).transform(chunks);
+        assert_eq!(contents(&output), &["a ", "x || y", " ", "█"]);
+    }
+
+    #[test]
+    fn spoilers_disabled() {
+        let output = redactions(false, &["x"]).transform(vec![Text::from("||a|| x")]);
+        assert_eq!(contents(&output).concat(), "||a|| █");
+    }
+
+    #[test]
+    fn reveal() {
+        let config = RedactionConfig { spoilers: true, patterns: vec!["sk-[a-z]+".into()] };
+        let redactions = Redactions::new(&config, true).expect("invalid config");
+        let output = redactions.transform(vec![Text::from("||a|| sk-b")]);
+        assert_eq!(contents(&output), &["a sk-b"]);
+        assert_eq!(redactions.redact_text("||a|| sk-b"), "a sk-b");
+        assert_eq!(redactions.conceal("||a|| sk-b"), "█ ████");
+    }
+
+    #[test]
+    fn code_tokens() {
+        let tokens = vec![(1, "let key = \"sk-"), (2, "abc"), (3, "\";")];
+        let output = redactions(true, &["sk-[a-z]+"]).redact_tokens(tokens);
+        let output: Vec<_> = output.iter().map(|(style, text)| (*style, text.as_ref())).collect();
+        assert_eq!(output, &[(1, "let key = \""), (1, "██████"), (3, "\";")]);
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let config = RedactionConfig { spoilers: false, patterns: vec!["(".into()] };
+        Redactions::new(&config, false).expect_err("invalid pattern accepted");
+    }
+}
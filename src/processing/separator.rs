@@ -3,6 +3,7 @@ use crate::{
     render::properties::WindowSize,
 };
 use std::rc::Rc;
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Clone, Debug, Default)]
 pub(crate) struct RenderSeparator {
@@ -27,10 +28,11 @@ impl AsRenderOperations for RenderSeparator {
         let separator = match self.heading.is_empty() {
             true => character.repeat(dimensions.columns as usize),
             false => {
-                let dashes_len = (dimensions.columns as usize).saturating_sub(self.heading.len()) / 2;
-                let dashes = character.repeat(dashes_len);
+                let dashes_len = (dimensions.columns as usize).saturating_sub(self.heading.width());
+                let before = character.repeat(dashes_len / 2);
+                let after = character.repeat(dashes_len - dashes_len / 2);
                 let heading = &self.heading;
-                format!("{dashes}{heading}{dashes}")
+                format!("{before}{heading}{after}")
             }
         };
         vec![RenderOperation::RenderText { line: separator.into(), alignment: Default::default() }]
@@ -40,3 +42,19 @@ impl AsRenderOperations for RenderSeparator {
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::ascii("hi")]
+    #[case::wide("日本語 🚀")]
+    fn heading_centered(#[case] heading: &str) {
+        let dimensions = WindowSize { rows: 10, columns: 20, width: 0, height: 0 };
+        let operations = RenderSeparator::new(heading).as_render_operations(&dimensions);
+        let [RenderOperation::RenderText { line, .. }] = operations.as_slice() else { panic!("unexpected operations") };
+        assert_eq!(line.width(), 20);
+    }
+}
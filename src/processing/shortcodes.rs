@@ -0,0 +1,193 @@
+use super::transform::{TextTransform, TransformStage};
+use crate::markdown::elements::Text;
+
+// The emoji shortcodes that are supported, sorted by name so they can be binary searched.
+const SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("alarm_clock", "⏰"),
+    ("arrow_down", "⬇️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("bangbang", "‼️"),
+    ("bell", "🔔"),
+    ("blush", "😊"),
+    ("bomb", "💣"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("brain", "🧠"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("calendar", "📆"),
+    ("chart_with_downwards_trend", "📉"),
+    ("chart_with_upwards_trend", "📈"),
+    ("check", "✔️"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("cloud", "☁️"),
+    ("coffee", "☕"),
+    ("computer", "💻"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cry", "😢"),
+    ("dart", "🎯"),
+    ("desktop_computer", "🖥️"),
+    ("exclamation", "❗"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("gear", "⚙️"),
+    ("globe_with_meridians", "🌐"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("heart", "❤️"),
+    ("heavy_check_mark", "✔️"),
+    ("heavy_minus_sign", "➖"),
+    ("heavy_plus_sign", "➕"),
+    ("hourglass", "⌛"),
+    ("information_source", "ℹ️"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("laughing", "😆"),
+    ("lock", "🔒"),
+    ("mag", "🔍"),
+    ("memo", "📝"),
+    ("no_entry", "⛔"),
+    ("ok_hand", "👌"),
+    ("package", "📦"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "☝️"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("raised_hands", "🙌"),
+    ("recycle", "♻️"),
+    ("rocket", "🚀"),
+    ("rotating_light", "🚨"),
+    ("scream", "😱"),
+    ("shield", "🛡️"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("speech_balloon", "💬"),
+    ("star", "⭐"),
+    ("stop_sign", "🛑"),
+    ("sunglasses", "😎"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("trophy", "🏆"),
+    ("unlock", "🔓"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+];
+
+/// Replace every known emoji shortcode in a text, like `:rocket:`, with the emoji it stands for.
+///
+/// Unknown shortcodes and code are left as they are.
+fn expand_shortcodes(text: &Text) -> Text {
+    if text.style.is_code() || !text.content.contains(':') {
+        return text.clone();
+    }
+    let mut content = String::new();
+    let mut rest = text.content.as_str();
+    while let Some(start) = rest.find(':') {
+        content.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match find_emoji(&rest[1..]) {
+            Some((emoji, length)) => {
+                content.push_str(emoji);
+                // Skip both colons along with the name.
+                rest = &rest[length + 2..];
+            }
+            None => {
+                // This colon may still be the start of a shortcode so only skip it.
+                content.push(':');
+                rest = &rest[1..];
+            }
+        };
+    }
+    content.push_str(rest);
+    Text { content, ..text.clone() }
+}
+
+/// A text transform that replaces emoji shortcodes with the emoji they stand for.
+#[derive(Debug)]
+pub(crate) struct ShortcodeEmoji;
+
+impl TextTransform for ShortcodeEmoji {
+    fn stage(&self) -> TransformStage {
+        TransformStage::Substitute
+    }
+
+    fn transform(&self, chunks: Vec<Text>) -> Vec<Text> {
+        chunks.iter().map(expand_shortcodes).collect()
+    }
+}
+
+// Find the emoji for the shortcode name at the start of `input`, which must be followed by a `:`.
+fn find_emoji(input: &str) -> Option<(&'static str, usize)> {
+    let length = input.find(|c: char| !is_shortcode_char(c))?;
+    if length == 0 || !input[length..].starts_with(':') {
+        return None;
+    }
+    let name = &input[..length];
+    let index = SHORTCODES.binary_search_by_key(&name, |(name, _)| name).ok()?;
+    Some((SHORTCODES[index].1, length))
+}
+
+fn is_shortcode_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '+' | '-')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::style::TextStyle;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::single(":rocket:", "🚀")]
+    #[case::surrounded("launch :rocket: now", "launch 🚀 now")]
+    #[case::adjacent(":warning::x:", "⚠️❌")]
+    #[case::symbols(":+1: and :-1:", "👍 and 👎")]
+    #[case::unknown(":not_an_emoji:", ":not_an_emoji:")]
+    #[case::unknown_before_known("a:b:tada:", "a:b🎉")]
+    #[case::unterminated("time: 10:30", "time: 10:30")]
+    #[case::empty("::", "::")]
+    #[case::spaces(": smile :", ": smile :")]
+    fn expand(#[case] input: &str, #[case] expected: &str) {
+        let text = expand_shortcodes(&Text::from(input));
+        assert_eq!(text.content, expected);
+    }
+
+    #[test]
+    fn keeps_style() {
+        let bold = TextStyle::default().bold();
+        let text = expand_shortcodes(&Text::new("hi :wave:", bold.clone()));
+        assert_eq!(text, Text::new("hi 👋", bold));
+    }
+
+    #[test]
+    fn code_untouched() {
+        let code = Text::new(":rocket:", TextStyle::default().code());
+        assert_eq!(expand_shortcodes(&code), code);
+    }
+
+    #[test]
+    fn sorted() {
+        let names: Vec<_> = SHORTCODES.iter().map(|(name, _)| *name).collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+    }
+}
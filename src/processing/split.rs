@@ -0,0 +1,388 @@
+//! Splitting slides that don't fit in the presentation's slide budget.
+//!
+//! Slides are measured by building the presentation once as is. Any slide that goes over the
+//! budget is then split in between its top level elements, or in between the items at the top
+//! level of a list, and the presentation is built again out of the rewritten elements. Code
+//! blocks, tables, and anything else that's a single element are never split.
+
+use crate::{
+    custom::{PreviewDimensions, SlideBudget},
+    markdown::elements::{ListItem, ListItemType, MarkdownElement, SourcePosition, Text},
+    presentation::{RenderOperation, Slide, SlideReference},
+    render::{draw::RenderError, validate::OverflowValidator},
+};
+use std::fmt;
+
+/// The suffix added to the heading of every slide a slide is split into except for the first one.
+const CONTINUATION_SUFFIX: &str = " (cont.)";
+
+/// A position in between two elements where a slide can be split.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SplitPosition {
+    /// Right before an element.
+    Element(usize),
+
+    /// Right before an item at the top level of a list.
+    ListItem {
+        element: usize,
+        item: usize,
+
+        /// The number this item is displayed with if the list is ordered.
+        number: usize,
+    },
+}
+
+impl SplitPosition {
+    fn element(&self) -> usize {
+        match self {
+            Self::Element(element) | Self::ListItem { element, .. } => *element,
+        }
+    }
+}
+
+/// A place where a slide can be split, found while building it.
+#[derive(Clone, Debug)]
+pub(crate) struct SplitPoint {
+    pub(crate) position: SplitPosition,
+
+    /// The number of operations in the slide before this point.
+    pub(crate) operations: usize,
+
+    /// The step the content right before this point is shown in.
+    pub(crate) step: usize,
+
+    /// Whether lists are incremental at this point, if the slide sets it.
+    pub(crate) incremental_lists: Option<bool>,
+}
+
+/// The places a slide can be split at.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SlideMeasurement {
+    /// The index of the heading the slide starts with, if any.
+    pub(crate) heading: Option<usize>,
+
+    /// The number of operations up to and including the slide's heading.
+    ///
+    /// These are repeated at the top of every slide this one is split into.
+    pub(crate) header_operations: usize,
+
+    pub(crate) points: Vec<SplitPoint>,
+}
+
+/// A place where the presentation's elements need to be split.
+#[derive(Clone, Debug)]
+pub(crate) struct Split {
+    position: SplitPosition,
+    heading: Option<usize>,
+    incremental_lists: Option<bool>,
+}
+
+/// The result of planning how slides are split.
+#[derive(Debug, Default)]
+pub(crate) struct SplitPlan {
+    /// The places the presentation's elements need to be split at.
+    pub(crate) splits: Vec<Split>,
+
+    /// The problems found, along with the index of the slide they refer to once the slides before it are split.
+    pub(crate) warnings: Vec<(usize, BudgetIssue)>,
+}
+
+/// Decides which slides go over a budget and where they're split.
+pub(crate) struct SlideSplitter {
+    budget: SlideBudget,
+}
+
+impl SlideSplitter {
+    pub(crate) fn new(budget: SlideBudget) -> Self {
+        Self { budget }
+    }
+
+    /// Find the places slides need to be split at to fit in the budget, along with the problems found.
+    ///
+    /// Splits are only returned if the budget allows splitting slides.
+    pub(crate) fn plan(&self, slides: &[Slide], measurements: &[SlideMeasurement]) -> Result<SplitPlan, RenderError> {
+        let mut splits = Vec::new();
+        let mut warnings = Vec::new();
+        let mut extra_slides = 0;
+        for (index, (slide, measurement)) in slides.iter().zip(measurements).enumerate() {
+            let index = index + extra_slides;
+            let parts = SlideParts::new(slide, measurement.header_operations);
+            let Some(overrun) = self.overrun(&parts, None, None)? else {
+                continue;
+            };
+            if !self.budget.split {
+                warnings.push((index, BudgetIssue::Overrun(overrun)));
+                continue;
+            }
+            match self.split_points(&parts, &measurement.points)? {
+                Some(points) => {
+                    warnings.push((index, BudgetIssue::Split { slides: points.len() + 1 }));
+                    extra_slides += points.len();
+                    splits.extend(points.into_iter().map(|point| Split {
+                        position: point.position,
+                        heading: measurement.heading,
+                        incremental_lists: point.incremental_lists,
+                    }));
+                }
+                None => warnings.push((index, BudgetIssue::Unsplittable(overrun))),
+            };
+        }
+        Ok(SplitPlan { splits, warnings })
+    }
+
+    // Greedily fits as much as possible into every part, returning `None` if some part can't be
+    // made to fit.
+    fn split_points<'a>(
+        &self,
+        parts: &SlideParts,
+        points: &'a [SplitPoint],
+    ) -> Result<Option<Vec<&'a SplitPoint>>, RenderError> {
+        let mut output = Vec::new();
+        let mut start: Option<&SplitPoint> = None;
+        let mut next = 0;
+        while self.overrun(parts, start, None)?.is_some() {
+            let start_operations = start.map(|point| point.operations).unwrap_or(parts.header);
+            let mut best = None;
+            for (index, point) in points.iter().enumerate().skip(next) {
+                // Splitting here would leave nothing in this part.
+                if point.operations <= start_operations {
+                    continue;
+                }
+                if self.overrun(parts, start, Some(point))?.is_some() {
+                    break;
+                }
+                best = Some(index);
+            }
+            let Some(index) = best else {
+                return Ok(None);
+            };
+            output.push(&points[index]);
+            start = Some(&points[index]);
+            next = index + 1;
+        }
+        Ok(Some(output))
+    }
+
+    // Checks whether the part of a slide in between two points goes over the budget. A missing
+    // start or end stands for the start or end of the slide.
+    fn overrun(
+        &self,
+        parts: &SlideParts,
+        start: Option<&SplitPoint>,
+        end: Option<&SplitPoint>,
+    ) -> Result<Option<Overrun>, RenderError> {
+        let first_step = start.map(|point| point.step).unwrap_or_default();
+        let last_step = end.map(|point| point.step).unwrap_or(parts.last_step);
+        let steps = last_step.saturating_sub(first_step) + 1;
+        if let Some(max_steps) = self.budget.max_steps {
+            if steps > max_steps {
+                return Ok(Some(Overrun::TooManySteps { steps, max_steps }));
+            }
+        }
+        let Some(size) = self.budget.size else {
+            return Ok(None);
+        };
+        let end = end.map(|point| point.operations).unwrap_or(parts.operations.len()).min(parts.operations.len());
+        // Every part after the first one starts with the slide's header.
+        let (header, body_start) = match start {
+            Some(point) => (parts.header, point.operations.min(end)),
+            None => (0, 0),
+        };
+        let operations =
+            parts.operations[..header].iter().chain(&parts.operations[body_start..end]).chain(&parts.footer).copied();
+        let dimensions = (size.columns, size.rows).into();
+        match OverflowValidator::fits_vertically(operations, dimensions)? {
+            true => Ok(None),
+            false => Ok(Some(Overrun::TooTall(size))),
+        }
+    }
+}
+
+// The operations in a slide, laid out so any part of it can be measured.
+struct SlideParts<'a> {
+    operations: Vec<&'a RenderOperation>,
+    footer: Vec<&'a RenderOperation>,
+    header: usize,
+    last_step: usize,
+}
+
+impl<'a> SlideParts<'a> {
+    fn new(slide: &'a Slide, header: usize) -> Self {
+        let operations: Vec<_> = slide.iter_chunks().flat_map(|chunk| chunk.iter_operations()).collect();
+        let footer = slide.iter_all_operations().skip(operations.len()).collect();
+        let header = header.min(operations.len());
+        Self { operations, footer, header, last_step: slide.steps().total() - 1 }
+    }
+}
+
+/// Rewrite a presentation's elements so its slides are split at the given places.
+///
+/// Every slide that's added starts with the heading of the slide it was split from, if it had one.
+pub(crate) fn apply_splits(
+    elements: Vec<MarkdownElement>,
+    splits: &[Split],
+    command_prefix: &str,
+) -> Vec<MarkdownElement> {
+    let mut output = Vec::new();
+    let mut splits = splits.iter().peekable();
+    for (index, element) in elements.iter().enumerate() {
+        let mut element_splits = Vec::new();
+        while let Some(split) = splits.next_if(|split| split.position.element() == index) {
+            element_splits.push(split);
+        }
+        let MarkdownElement::List(items) = element else {
+            for split in element_splits {
+                output.extend(continuation(split, &elements, command_prefix));
+            }
+            output.push(element.clone());
+            continue;
+        };
+        let mut remaining = items.clone();
+        let mut consumed = 0;
+        for split in element_splits {
+            let (item, number) = match split.position {
+                SplitPosition::ListItem { item, number, .. } => (item, Some(number)),
+                SplitPosition::Element(_) => (0, None),
+            };
+            let tail = remaining.split_off(item.saturating_sub(consumed).min(remaining.len()));
+            if !remaining.is_empty() {
+                output.push(MarkdownElement::List(remaining));
+            }
+            output.extend(continuation(split, &elements, command_prefix));
+            remaining = tail;
+            consumed = item;
+            if let Some(number) = number {
+                renumber(&mut remaining, number);
+            }
+        }
+        output.push(MarkdownElement::List(remaining));
+    }
+    output
+}
+
+// The elements that end a slide and start the one that continues it.
+fn continuation(split: &Split, elements: &[MarkdownElement], command_prefix: &str) -> Vec<MarkdownElement> {
+    let comment = |command: String| MarkdownElement::Comment {
+        comment: format!("{command_prefix}{command}"),
+        source_position: SourcePosition::default(),
+    };
+    let mut output = vec![comment("end_slide".into())];
+    if let Some(incremental_lists) = split.incremental_lists {
+        output.push(comment(format!("incremental_lists: {incremental_lists}")));
+    }
+    if let Some(mut heading) = split.heading.and_then(|index| elements.get(index)).cloned() {
+        if let MarkdownElement::SetexHeading { text, .. } | MarkdownElement::Heading { text, .. } = &mut heading {
+            text.0.push(Text::from(CONTINUATION_SUFFIX));
+        }
+        output.push(heading);
+    }
+    output
+}
+
+// Makes a list that's split off another one keep numbering its items where the original left off.
+fn renumber(items: &mut [ListItem], number: usize) {
+    for item in items.iter_mut().filter(|item| item.depth == 0) {
+        item.item_type = match item.item_type {
+            ListItemType::Unordered => ListItemType::Unordered,
+            ListItemType::OrderedParens { .. } => ListItemType::OrderedParens { start: number },
+            ListItemType::OrderedPeriod { .. } => ListItemType::OrderedPeriod { start: number },
+        };
+    }
+}
+
+/// A problem with a slide's size found when checking it against the slide budget.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BudgetWarning {
+    pub(crate) slide: SlideReference,
+    pub(crate) issue: BudgetIssue,
+}
+
+impl fmt::Display for BudgetWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { slide, issue } = self;
+        match issue {
+            BudgetIssue::Split { slides } => {
+                write!(f, "{slide} was split into {slides} slides to fit the slide budget")
+            }
+            BudgetIssue::Overrun(overrun) => write!(f, "{slide} {overrun}"),
+            BudgetIssue::Unsplittable(overrun) => write!(f, "{slide} {overrun} and can't be split"),
+        }
+    }
+}
+
+/// The way a slide doesn't fit in the slide budget.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BudgetIssue {
+    /// The slide was split into several ones to fit in the budget.
+    Split { slides: usize },
+
+    /// The slide goes over the budget.
+    Overrun(Overrun),
+
+    /// The slide goes over the budget and there's nowhere to split it at so it fits.
+    Unsplittable(Overrun),
+}
+
+/// The way a slide goes over the slide budget.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Overrun {
+    /// The slide doesn't fit vertically.
+    TooTall(PreviewDimensions),
+
+    /// The slide has too many steps.
+    TooManySteps { steps: usize, max_steps: usize },
+}
+
+impl fmt::Display for Overrun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooTall(size) => write!(f, "doesn't fit in {size}"),
+            Self::TooManySteps { steps, max_steps } => {
+                write!(f, "has {steps} steps, more than the maximum of {max_steps}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::markdown::elements::TextBlock;
+
+    fn list_item(depth: u8, text: &str) -> ListItem {
+        ListItem { depth, contents: TextBlock::from(text), item_type: ListItemType::OrderedPeriod { start: 1 } }
+    }
+
+    #[test]
+    fn split_ordered_list() {
+        let heading = MarkdownElement::SetexHeading { text: "Title".into(), source_position: Default::default() };
+        let items = vec![list_item(0, "a"), list_item(1, "a.1"), list_item(0, "b"), list_item(0, "c")];
+        let elements = vec![heading, MarkdownElement::List(items)];
+        let split = Split {
+            position: SplitPosition::ListItem { element: 1, item: 2, number: 2 },
+            heading: Some(0),
+            incremental_lists: Some(true),
+        };
+        let output: Vec<_> = apply_splits(elements, &[split], "")
+            .into_iter()
+            .map(|element| match element {
+                MarkdownElement::SetexHeading { text, .. } => text.0.into_iter().map(|text| text.content).collect(),
+                MarkdownElement::Comment { comment, .. } => comment,
+                MarkdownElement::List(items) => {
+                    let starts: Vec<_> = items.iter().map(|item| item.item_type.start().to_string()).collect();
+                    format!("list starting at {}", starts.join(", "))
+                }
+                other => panic!("unexpected element: {other:?}"),
+            })
+            .collect();
+        let expected = &[
+            "Title",
+            "list starting at 1, 1",
+            "end_slide",
+            "incremental_lists: true",
+            "Title (cont.)",
+            "list starting at 2, 2",
+        ];
+        assert_eq!(output, expected);
+    }
+}
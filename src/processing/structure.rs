@@ -0,0 +1,428 @@
+//! Checks on how the headings in a presentation are structured.
+//!
+//! Presentations converted from other formats often jump from `#` to `####` or put several top
+//! level headings in the same slide. None of this prevents a presentation from being built, but it
+//! makes themed heading styles look random so it's reported when the presentation is loaded.
+//!
+//! Images without alt text are reported as well, as screen readers have no way of describing them,
+//! along with slides that share the same title, which makes them hard to tell apart in the slide
+//! index.
+
+use crate::{
+    custom::StructureChecks,
+    markdown::elements::{MarkdownElement, TextBlock},
+    presentation::{SlideNumbering, SlideReference},
+    processing::builder::PresentationBuilderOptions,
+};
+use std::fmt::{self, Display};
+
+/// The parts of an element that matter when checking a presentation's structure.
+#[derive(Clone, Debug)]
+pub(crate) enum StructureElement {
+    Title { text: String, line: usize },
+    Heading { level: u8, text: String, line: usize },
+    Image { alt_text: String, line: usize },
+    Content,
+    Ignored,
+}
+
+impl StructureElement {
+    pub(crate) fn new(element: &MarkdownElement, options: &PresentationBuilderOptions) -> Self {
+        match element {
+            MarkdownElement::FrontMatter(_) | MarkdownElement::Comment { .. } => Self::Ignored,
+            MarkdownElement::ThematicBreak { .. } if options.end_slide_shorthand => Self::Ignored,
+            MarkdownElement::SetexHeading { text, source_position } => {
+                Self::Title { text: plain_text(text), line: source_position.start.line }
+            }
+            MarkdownElement::Heading { level, text, source_position } => {
+                Self::Heading { level: *level, text: plain_text(text), line: source_position.start.line }
+            }
+            MarkdownElement::Image { alt_text, source_position, .. } => {
+                Self::Image { alt_text: alt_text.clone(), line: source_position.start.line }
+            }
+            _ => Self::Content,
+        }
+    }
+}
+
+/// Keeps track of the headings seen so far to find problems in their structure.
+///
+/// Elements are expected to be fed in the order they show up in the presentation, along with the
+/// index of the slide they ended up in.
+#[derive(Debug)]
+pub(crate) struct StructureValidator {
+    /// The problems found so far along with the index of the slide and the line they're in.
+    warnings: Vec<(usize, usize, StructureIssue)>,
+    slide: usize,
+    previous_level: Option<u8>,
+    slide_has_content: bool,
+    slide_has_top_heading: bool,
+    check_headings: bool,
+    check_titles: bool,
+
+    /// The title of the current slide along with the line it's in.
+    title: Option<(String, usize)>,
+
+    /// The title of the current slide if it doesn't have one, which is its first heading.
+    first_heading: Option<(String, usize)>,
+
+    /// The title of every slide seen so far along with the slide's index.
+    titles: Vec<(usize, String, usize)>,
+}
+
+impl Default for StructureValidator {
+    fn default() -> Self {
+        Self {
+            warnings: Vec::new(),
+            slide: 0,
+            previous_level: None,
+            slide_has_content: false,
+            slide_has_top_heading: false,
+            check_headings: true,
+            check_titles: true,
+            title: None,
+            first_heading: None,
+            titles: Vec::new(),
+        }
+    }
+}
+
+impl StructureValidator {
+    pub(crate) fn new(options: &PresentationBuilderOptions) -> Self {
+        // Missing alt text is an accessibility problem rather than a stylistic one so it's always checked.
+        let check_headings = options.structure == StructureChecks::Strict;
+        Self { check_headings, check_titles: options.warn_on_duplicate_titles, ..Default::default() }
+    }
+
+    /// Check an element that ended up in the slide at the given index.
+    pub(crate) fn push(&mut self, slide: usize, element: StructureElement) {
+        if slide != self.slide {
+            self.end_slide();
+            self.slide = slide;
+        }
+        match element {
+            StructureElement::Title { text, line } => self.slide_title(text, line),
+            StructureElement::Heading { level, text, line } => self.heading(level, text, line),
+            StructureElement::Image { alt_text, line } => self.image(&alt_text, line),
+            StructureElement::Content => self.content(),
+            StructureElement::Ignored => (),
+        };
+    }
+
+    fn heading(&mut self, level: u8, text: String, line: usize) {
+        if self.first_heading.is_none() {
+            self.first_heading = Some((text, line));
+        }
+        if let Some(previous) = self.previous_level.filter(|_| self.check_headings) {
+            if level > previous + 1 {
+                self.warn(line, StructureIssue::SkippedLevel { from: previous, to: level });
+            }
+        }
+        if level == 1 {
+            if self.check_headings && self.slide_has_top_heading {
+                self.warn(line, StructureIssue::MultipleTopHeadings);
+            }
+            self.slide_has_top_heading = true;
+        }
+        self.previous_level = Some(level);
+    }
+
+    fn slide_title(&mut self, text: String, line: usize) {
+        if self.title.is_none() {
+            self.title = Some((text, line));
+        }
+        // Slide titles are styled to be at the top of the slide.
+        if self.check_headings && self.slide_has_content {
+            self.warn(line, StructureIssue::TitleAfterContent);
+        }
+    }
+
+    fn image(&mut self, alt_text: &str, line: usize) {
+        if alt_text.trim().is_empty() {
+            self.warn(line, StructureIssue::MissingAltText);
+        }
+        self.content();
+    }
+
+    fn content(&mut self) {
+        self.slide_has_content = true;
+    }
+
+    fn end_slide(&mut self) {
+        // Slides without a title are named after their first heading, just like when building them.
+        if let Some((title, line)) = self.title.take().or_else(|| self.first_heading.take()) {
+            self.titles.push((self.slide, title, line));
+        }
+        self.first_heading = None;
+        self.slide_has_content = false;
+        self.slide_has_top_heading = false;
+    }
+
+    fn warn(&mut self, line: usize, issue: StructureIssue) {
+        self.warnings.push((self.slide, line, issue));
+    }
+
+    /// Get the problems found in the presentation, referring to slides using the given numbering.
+    pub(crate) fn finish(mut self, numbering: &SlideNumbering) -> Vec<StructureWarning> {
+        self.end_slide();
+        if self.check_titles {
+            self.find_duplicate_titles(numbering);
+        }
+        self.warnings
+            .into_iter()
+            .map(|(index, line, issue)| StructureWarning { slide: numbering.reference(index), line, issue })
+            .collect()
+    }
+
+    // Report every title used by more than one slide at the first slide that uses it.
+    fn find_duplicate_titles(&mut self, numbering: &SlideNumbering) {
+        let mut seen: Vec<(&str, usize, Vec<usize>)> = Vec::new();
+        for (index, title, line) in &self.titles {
+            match seen.iter_mut().find(|(other, ..)| other == title) {
+                Some((_, _, slides)) => slides.push(*index),
+                None => seen.push((title, *line, vec![*index])),
+            };
+        }
+        let mut duplicates = Vec::new();
+        for (title, line, slides) in seen {
+            if slides.len() > 1 {
+                let references = slides.iter().map(|index| numbering.reference(*index)).collect();
+                let issue = StructureIssue::DuplicateTitle { title: title.into(), slides: references };
+                duplicates.push((slides[0], line, issue));
+            }
+        }
+        self.warnings.extend(duplicates);
+    }
+}
+
+fn plain_text(text: &TextBlock) -> String {
+    text.0.iter().map(|text| text.content.as_str()).collect()
+}
+
+/// A problem in the structure of a presentation's headings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructureWarning {
+    slide: SlideReference,
+    line: usize,
+    issue: StructureIssue,
+}
+
+impl Display for StructureWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, line {}: {}", self.slide, self.line, self.issue)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum StructureIssue {
+    /// A heading is more than one level deeper than the one before it.
+    SkippedLevel { from: u8, to: u8 },
+
+    /// A slide has more than one level 1 heading.
+    MultipleTopHeadings,
+
+    /// A slide title shows up after some other content in the same slide.
+    TitleAfterContent,
+
+    /// An image has no alt text.
+    MissingAltText,
+
+    /// More than one slide has the same title.
+    DuplicateTitle { title: String, slides: Vec<SlideReference> },
+}
+
+impl Display for StructureIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SkippedLevel { from, to } => write!(f, "heading jumps from level {from} to level {to}"),
+            Self::MultipleTopHeadings => write!(f, "slide has more than one level 1 heading"),
+            Self::TitleAfterContent => write!(f, "slide title comes after other content in the slide"),
+            Self::MissingAltText => {
+                write!(f, "image has no alt text, which screen readers need to describe it (WCAG 2.1 criterion 1.1.1)")
+            }
+            Self::DuplicateTitle { title, slides } => {
+                let slides = slides.iter().map(|slide| slide.to_string()).collect::<Vec<_>>().join(", ");
+                write!(f, "title '{title}' is used by {slides}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        custom::KeyBindingsConfig,
+        processing::builder::{PresentationBuilder, PresentationBuilderOptions, Themes},
+        theme::PresentationTheme,
+        typst::TypstRender,
+        MarkdownParser, Resources,
+    };
+    use rstest::rstest;
+
+    fn warnings(contents: &str) -> Vec<StructureWarning> {
+        let options = PresentationBuilderOptions { warn_on_duplicate_titles: true, ..Default::default() };
+        build_warnings(contents, options)
+    }
+
+    fn build_warnings(contents: &str, options: PresentationBuilderOptions) -> Vec<StructureWarning> {
+        let arena = comrak::Arena::new();
+        let elements = MarkdownParser::new(&arena).parse(contents).expect("parse failed");
+        let theme = PresentationTheme::default();
+        let mut resources = Resources::new("examples", Default::default());
+        let mut typst = TypstRender::default();
+        let themes = Themes::default();
+        let bindings = KeyBindingsConfig::default();
+        let builder = PresentationBuilder::new(
+            &theme,
+            &mut resources,
+            &mut typst,
+            &themes,
+            Default::default(),
+            bindings,
+            options,
+        );
+        builder.build(elements).expect("build failed").structure_warnings().to_vec()
+    }
+
+    fn warning(slide: usize, line: usize, issue: StructureIssue) -> StructureWarning {
+        StructureWarning { slide: SlideReference::Numbered(slide), line, issue }
+    }
+
+    #[rstest]
+    #[case::sequential("# a\n\n## b\n\n### c\n\n## d\n\n<!-- end_slide -->\n\n# e\n")]
+    #[case::first_heading("### a\n\n#### b\n")]
+    #[case::going_back_up("# a\n\n## b\n\n### c\n\n<!-- end_slide -->\n\n# d\n\n## e\n")]
+    fn well_structured(#[case] contents: &str) {
+        assert_eq!(warnings(contents), &[]);
+    }
+
+    #[test]
+    fn skipped_levels() {
+        let contents = "# a\n\n#### b\n\n<!-- end_slide -->\n\n## c\n\n#### d\n";
+        let expected = &[
+            warning(1, 3, StructureIssue::SkippedLevel { from: 1, to: 4 }),
+            warning(2, 9, StructureIssue::SkippedLevel { from: 2, to: 4 }),
+        ];
+        assert_eq!(warnings(contents), expected);
+    }
+
+    #[rstest]
+    #[case::by_two(1, 3)]
+    #[case::by_five(1, 6)]
+    #[case::deep(3, 5)]
+    fn skipped_level(#[case] from: u8, #[case] to: u8) {
+        let contents = format!("{} a\n\n{} b\n", "#".repeat(from as usize), "#".repeat(to as usize));
+        assert_eq!(warnings(&contents), &[warning(1, 3, StructureIssue::SkippedLevel { from, to })]);
+    }
+
+    #[test]
+    fn multiple_top_headings() {
+        let contents = "# a\n\ntext\n\n# b\n\n<!-- end_slide -->\n\n# c\n";
+        assert_eq!(warnings(contents), &[warning(1, 5, StructureIssue::MultipleTopHeadings)]);
+    }
+
+    #[test]
+    fn title_after_content() {
+        let contents = "Title\n===\n\ntext\n\nOther title\n===\n\n<!-- end_slide -->\n\nLast\n===\n";
+        assert_eq!(warnings(contents), &[warning(1, 6, StructureIssue::TitleAfterContent)]);
+    }
+
+    #[test]
+    fn implicit_slide_ends() {
+        let contents = "---\noptions:\n  implicit_slide_ends: true\n---\n\nTitle\n===\n\ntext\n\nOther title\n===\n";
+        assert_eq!(warnings(contents), &[]);
+    }
+
+    #[test]
+    fn end_slide_shorthand() {
+        let contents = "---\noptions:\n  end_slide_shorthand: true\n---\n\n# a\n\n---\n\n# b\n";
+        assert_eq!(warnings(contents), &[]);
+    }
+
+    #[test]
+    fn relaxed() {
+        let contents = "---\noptions:\n  structure: relaxed\n---\n\n# a\n\n#### b\n\n# c\n";
+        assert_eq!(warnings(contents), &[]);
+    }
+
+    #[test]
+    fn missing_alt_text() {
+        let contents = "# a\n\n![](doge.png)\n\n![a picture](doge.png)\n\n<!-- end_slide -->\n\n![ ](doge.png)\n";
+        let expected = &[warning(1, 3, StructureIssue::MissingAltText), warning(2, 9, StructureIssue::MissingAltText)];
+        assert_eq!(warnings(contents), expected);
+    }
+
+    #[test]
+    fn missing_alt_text_relaxed() {
+        let contents = "---\noptions:\n  structure: relaxed\n---\n# a\n\n#### b\n\n![](doge.png)\n";
+        assert_eq!(warnings(contents), &[warning(1, 9, StructureIssue::MissingAltText)]);
+    }
+
+    #[test]
+    fn duplicate_titles() {
+        let contents = "Intro\n===\n\n<!-- end_slide -->\n\n# Intro\n\n<!-- end_slide -->\n\n## Other\n\n\
+            <!-- end_slide -->\n\nIntro\n===\n";
+        let slides = [1, 2, 4].map(SlideReference::Numbered).to_vec();
+        let issue = StructureIssue::DuplicateTitle { title: "Intro".into(), slides };
+        assert_eq!(warnings(contents), &[warning(1, 1, issue)]);
+    }
+
+    #[test]
+    fn duplicate_titles_disabled_by_default() {
+        let contents = "# A\n\n<!-- end_slide -->\n\n# A\n";
+        assert_eq!(build_warnings(contents, Default::default()), &[]);
+    }
+
+    #[test]
+    fn intro_slide() {
+        let contents = "---
+title: hi
+---
+
+# a
+
+#### b
+";
+        assert_eq!(warnings(contents), &[warning(2, 6, StructureIssue::SkippedLevel { from: 1, to: 4 })]);
+    }
+
+    #[test]
+    fn unnumbered_slide() {
+        let contents = "# a
+
+<!-- end_slide -->
+
+<!-- unnumbered -->
+
+# b
+
+#### c
+";
+        let slide = SlideReference::Unnumbered { after: Some(1) };
+        let expected = StructureWarning { slide, line: 9, issue: StructureIssue::SkippedLevel { from: 1, to: 4 } };
+        assert_eq!(warnings(contents), &[expected]);
+    }
+
+    #[rstest]
+    #[case::different_titles("A\n===\n\n<!-- end_slide -->\n\nB\n===\n")]
+    #[case::untitled("text\n\n<!-- end_slide -->\n\ntext\n")]
+    #[case::title_wins_over_heading("A\n===\n\n# B\n\n<!-- end_slide -->\n\n# B\n\n<!-- end_slide -->\n\nC\n===\n")]
+    #[case::disabled("---\noptions:\n  warn_on_duplicate_titles: false\n---\n\n# A\n\n<!-- end_slide -->\n\n# A\n")]
+    fn no_duplicate_titles(#[case] contents: &str) {
+        assert_eq!(warnings(contents), &[]);
+    }
+
+    #[test]
+    fn display() {
+        let warning = warning(2, 7, StructureIssue::SkippedLevel { from: 1, to: 3 });
+        assert_eq!(warning.to_string(), "slide 2, line 7: heading jumps from level 1 to level 3");
+    }
+
+    #[test]
+    fn display_duplicate_title() {
+        let slides = vec![SlideReference::Numbered(1), SlideReference::Unnumbered { after: Some(2) }];
+        let issue = StructureIssue::DuplicateTitle { title: "Intro".into(), slides };
+        assert_eq!(issue.to_string(), "title 'Intro' is used by slide 1, unnumbered slide after slide 2");
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    markdown::{elements::TextBlock, text::WeightedTextBlock},
+    presentation::{AsRenderOperations, ChunkMutator, RenderOperation, StepKind},
+    render::properties::WindowSize,
+    style::TextStyle,
+    theme::Alignment,
+};
+use std::{cell::RefCell, rc::Rc};
+
+/// The state of a table whose rows are revealed one at a time.
+#[derive(Debug)]
+pub(crate) struct RevealedRowsContext {
+    current: usize,
+    total: usize,
+}
+
+impl RevealedRowsContext {
+    pub(crate) fn new(total: usize) -> Self {
+        Self { current: 0, total }
+    }
+}
+
+/// A table row that is displayed dimmed until it's revealed.
+#[derive(Debug)]
+pub(crate) struct DimmedTableRow {
+    index: usize,
+    content: String,
+    revealed: WeightedTextBlock,
+    dimmed: WeightedTextBlock,
+    alignment: Alignment,
+    context: Rc<RefCell<RevealedRowsContext>>,
+}
+
+impl DimmedTableRow {
+    pub(crate) fn new(
+        index: usize,
+        row: TextBlock,
+        alignment: Alignment,
+        context: Rc<RefCell<RevealedRowsContext>>,
+    ) -> Self {
+        let content = row.0.iter().map(|text| text.content.as_str()).collect();
+        let mut dimmed = row.clone();
+        dimmed.apply_style(&TextStyle::default().dim());
+        Self { index, content, revealed: row.into(), dimmed: dimmed.into(), alignment, context }
+    }
+}
+
+impl AsRenderOperations for DimmedTableRow {
+    fn as_render_operations(&self, _: &WindowSize) -> Vec<RenderOperation> {
+        let line = match self.index <= self.context.borrow().current {
+            true => self.revealed.clone(),
+            false => self.dimmed.clone(),
+        };
+        vec![RenderOperation::RenderText { line, alignment: self.alignment.clone() }]
+    }
+
+    fn diffable_content(&self) -> Option<&str> {
+        Some(&self.content)
+    }
+}
+
+/// Reveals the rows in a table one at a time.
+#[derive(Debug)]
+pub(crate) struct RevealRowsMutator {
+    context: Rc<RefCell<RevealedRowsContext>>,
+}
+
+impl RevealRowsMutator {
+    pub(crate) fn new(context: Rc<RefCell<RevealedRowsContext>>) -> Self {
+        Self { context }
+    }
+}
+
+impl ChunkMutator for RevealRowsMutator {
+    fn mutate_next(&self) -> bool {
+        let mut context = self.context.borrow_mut();
+        if context.current + 1 >= context.total {
+            false
+        } else {
+            context.current += 1;
+            true
+        }
+    }
+
+    fn reset_mutations(&self) {
+        self.context.borrow_mut().current = 0;
+    }
+
+    fn apply_all_mutations(&self) {
+        let mut context = self.context.borrow_mut();
+        context.current = context.total.saturating_sub(1);
+    }
+
+    fn mutations(&self) -> (usize, usize) {
+        let context = self.context.borrow();
+        (context.current, context.total)
+    }
+
+    fn step_kind(&self) -> StepKind {
+        StepKind::TableRow
+    }
+}
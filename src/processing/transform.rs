@@ -0,0 +1,233 @@
+use crate::{
+    markdown::elements::{MarkdownElement, ParagraphElement, Text, TextBlock},
+    style::TextStyle,
+};
+use std::{fmt, mem, rc::Rc};
+
+/// A transformation applied to the text in a presentation after it's parsed.
+pub(crate) trait TextTransform: fmt::Debug {
+    /// The stage this transform runs in, which defines its position in a pipeline.
+    fn stage(&self) -> TransformStage;
+
+    /// Whether code chunks are passed to this transform.
+    ///
+    /// When they're not, code splits the text around it into separate runs so nothing matched by
+    /// the transform can start or end inside code.
+    fn transforms_code(&self) -> bool {
+        false
+    }
+
+    /// Transform a run of contiguous chunks of text.
+    ///
+    /// Chunks can be split or merged freely as long as their styles are kept.
+    fn transform(&self, chunks: Vec<Text>) -> Vec<Text>;
+}
+
+/// The stages text transforms run in, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum TransformStage {
+    /// Transforms that change the text's contents, like replacing emoji shortcodes.
+    Substitute,
+
+    /// Transforms that annotate the text without changing it, like tagging glossary terms.
+    ///
+    /// These run last so they see the text as it's going to be displayed.
+    Tag,
+}
+
+/// A transform that adds a style to all the text it's applied to, including code.
+#[derive(Debug)]
+pub(crate) struct ApplyStyle(pub(crate) TextStyle);
+
+impl TextTransform for ApplyStyle {
+    fn stage(&self) -> TransformStage {
+        TransformStage::Tag
+    }
+
+    fn transforms_code(&self) -> bool {
+        true
+    }
+
+    fn transform(&self, mut chunks: Vec<Text>) -> Vec<Text> {
+        for chunk in &mut chunks {
+            chunk.style.merge(&self.0);
+        }
+        chunks
+    }
+}
+
+/// An ordered list of text transforms that are applied to every element.
+///
+/// Transforms run by stage regardless of the order they're added in, and those in the same stage
+/// run in the order they were added.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TextPipeline {
+    transforms: Vec<Rc<dyn TextTransform>>,
+}
+
+impl TextPipeline {
+    /// Add a transform to this pipeline.
+    pub(crate) fn push(&mut self, transform: Rc<dyn TextTransform>) {
+        let stage = transform.stage();
+        let index = self.transforms.partition_point(|existing| existing.stage() <= stage);
+        self.transforms.insert(index, transform);
+    }
+
+    /// Apply every transform to the text in an element.
+    ///
+    /// The text in block quotes and in the summary of details blocks is plain text in the element
+    /// so it's left alone here, and it needs to be transformed via [TextPipeline::apply_text].
+    pub(crate) fn apply(&self, element: &mut MarkdownElement) {
+        if self.transforms.is_empty() {
+            return;
+        }
+        match element {
+            MarkdownElement::SetexHeading { text, .. } | MarkdownElement::Heading { text, .. } => self.apply_text(text),
+            MarkdownElement::Paragraph(elements) => {
+                for element in elements {
+                    if let ParagraphElement::Text(text) = element {
+                        self.apply_text(text);
+                    }
+                }
+            }
+            MarkdownElement::List(items) => {
+                for item in items {
+                    self.apply_text(&mut item.contents);
+                }
+            }
+            MarkdownElement::DescriptionList(items) => {
+                for item in items {
+                    self.apply_text(&mut item.term);
+                    for definition in &mut item.definitions {
+                        self.apply_text(definition);
+                    }
+                }
+            }
+            MarkdownElement::Table(table) => {
+                for cell in table.header.0.iter_mut().chain(table.rows.iter_mut().flat_map(|row| row.0.iter_mut())) {
+                    self.apply_text(cell);
+                }
+            }
+            _ => (),
+        };
+    }
+
+    /// Apply every transform to a piece of text that isn't part of an element, like a presentation's title.
+    pub(crate) fn apply_text(&self, text: &mut TextBlock) {
+        for transform in &self.transforms {
+            let chunks = mem::take(&mut text.0);
+            text.0 = match transform.transforms_code() {
+                true => transform.transform(chunks),
+                false => Self::transform_runs(transform.as_ref(), chunks),
+            };
+        }
+    }
+
+    // Transforms every run of chunks between code chunks independently, leaving code alone.
+    fn transform_runs(transform: &dyn TextTransform, chunks: Vec<Text>) -> Vec<Text> {
+        let mut output = Vec::new();
+        let mut run = Vec::new();
+        for chunk in chunks {
+            if chunk.style.is_code() {
+                output.extend(transform.transform(mem::take(&mut run)));
+                output.push(chunk);
+            } else {
+                run.push(chunk);
+            }
+        }
+        if !run.is_empty() {
+            output.extend(transform.transform(run));
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::processing::{glossary::Glossary, shortcodes::ShortcodeEmoji};
+    use rstest::rstest;
+
+    #[derive(Debug)]
+    struct Uppercase {
+        code: bool,
+    }
+
+    impl TextTransform for Uppercase {
+        fn stage(&self) -> TransformStage {
+            TransformStage::Substitute
+        }
+
+        fn transforms_code(&self) -> bool {
+            self.code
+        }
+
+        fn transform(&self, chunks: Vec<Text>) -> Vec<Text> {
+            chunks.into_iter().map(|chunk| Text { content: chunk.content.to_uppercase(), ..chunk }).collect()
+        }
+    }
+
+    fn apply(pipeline: &TextPipeline, text: TextBlock) -> Vec<(String, Option<String>)> {
+        let mut element = MarkdownElement::Paragraph(vec![ParagraphElement::Text(text)]);
+        pipeline.apply(&mut element);
+        let MarkdownElement::Paragraph(mut elements) = element else { panic!("not a paragraph") };
+        let ParagraphElement::Text(text) = elements.remove(0) else { panic!("not text") };
+        text.0.into_iter().map(|chunk| (chunk.content, chunk.expansion)).collect()
+    }
+
+    fn glossary() -> Rc<Glossary> {
+        let terms = [("🚀".to_string(), "launch".to_string()), ("go".into(), "the go language".into())];
+        Rc::new(Glossary::new(terms.into(), true))
+    }
+
+    #[rstest]
+    #[case::nothing(false, false, &[(":rocket: go", None)])]
+    #[case::emoji(true, false, &[("🚀 go", None)])]
+    #[case::glossary(false, true, &[(":rocket: ", None), ("go", Some("the go language"))])]
+    #[case::both(true, true, &[("🚀", Some("launch")), (" ", None), ("go", Some("the go language"))])]
+    fn enabled_transforms(
+        #[case] emoji: bool,
+        #[case] glossary_enabled: bool,
+        #[case] expected: &[(&str, Option<&str>)],
+    ) {
+        let transforms: Vec<Rc<dyn TextTransform>> = [
+            glossary_enabled.then(|| glossary() as Rc<dyn TextTransform>),
+            emoji.then(|| Rc::new(ShortcodeEmoji) as Rc<dyn TextTransform>),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let expected: Vec<_> =
+            expected.iter().map(|(content, expansion)| (content.to_string(), expansion.map(String::from))).collect();
+
+        // The order transforms are added in doesn't matter.
+        for transforms in [transforms.clone(), transforms.into_iter().rev().collect()] {
+            let mut pipeline = TextPipeline::default();
+            for transform in transforms {
+                pipeline.push(transform);
+            }
+            assert_eq!(apply(&pipeline, TextBlock::from(":rocket: go")), expected);
+        }
+    }
+
+    #[rstest]
+    #[case::skips_code(false, "A b C")]
+    #[case::includes_code(true, "A B C")]
+    fn code_chunks(#[case] code: bool, #[case] expected: &str) {
+        let mut pipeline = TextPipeline::default();
+        pipeline.push(Rc::new(Uppercase { code }));
+        let text = TextBlock(vec![Text::from("a "), Text::new("b", TextStyle::default().code()), Text::from(" c")]);
+        let output: String = apply(&pipeline, text).into_iter().map(|(content, _)| content).collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn runs_split_by_code() {
+        let glossary = Rc::new(Glossary::new([("a b".to_string(), "x".to_string())].into(), true));
+        let mut pipeline = TextPipeline::default();
+        pipeline.push(glossary);
+        let text = TextBlock(vec![Text::from("a "), Text::new("b", TextStyle::default().code())]);
+        let output = apply(&pipeline, text);
+        assert!(output.iter().all(|(_, expansion)| expansion.is_none()), "{output:?}");
+    }
+}
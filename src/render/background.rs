@@ -0,0 +1,101 @@
+use super::capabilities::{query_terminal, TerminalQueryError};
+use std::time::Duration;
+
+// The query for the terminal's background color (OSC 11).
+const BACKGROUND_QUERY: &[u8] = b"\x1b]11;?\x1b\\";
+
+/// The kind of background a terminal has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerminalBackground {
+    Light,
+    Dark,
+}
+
+impl TerminalBackground {
+    /// Query the terminal to find out whether its background is light or dark.
+    ///
+    /// This waits at most `timeout` for the terminal to reply.
+    pub fn detect(timeout: Duration) -> Result<Self, DetectBackgroundError> {
+        let response = query_background(timeout)?;
+        Self::from_response(&response).ok_or(DetectBackgroundError::Unsupported)
+    }
+
+    /// Parse the response to an OSC 11 query, which looks like `ESC ] 11 ; rgb:RRRR/GGGG/BBBB ST`.
+    fn from_response(response: &[u8]) -> Option<Self> {
+        let response = String::from_utf8_lossy(response);
+        let (_, color) = response.split_once("]11;rgb:")?;
+        let color = color.split(['\x07', '\x1b']).next()?;
+        let mut components = color.split('/').map(Self::parse_component);
+        let (red, green, blue) = (components.next()??, components.next()??, components.next()??);
+        if components.next().is_some() {
+            return None;
+        }
+        // Relative luminance as defined by ITU-R BT.709.
+        let luminance = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+        if luminance > 0.5 { Some(Self::Light) } else { Some(Self::Dark) }
+    }
+
+    // Components are 1 to 4 hex digits each and are scaled based on how many digits they have.
+    fn parse_component(component: &str) -> Option<f64> {
+        if component.is_empty() || component.len() > 4 {
+            return None;
+        }
+        let value = u16::from_str_radix(component, 16).ok()?;
+        let max = (1u32 << (component.len() * 4)) - 1;
+        Some(value as f64 / max as f64)
+    }
+}
+
+fn query_background(timeout: Duration) -> Result<Vec<u8>, DetectBackgroundError> {
+    let response = query_terminal(&[BACKGROUND_QUERY], timeout)?;
+    Ok(response)
+}
+
+/// An error when detecting the terminal's background.
+#[derive(Debug, thiserror::Error)]
+pub enum DetectBackgroundError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("terminal did not reply in time")]
+    Timeout,
+
+    #[error("terminal does not support background color queries")]
+    Unsupported,
+}
+
+impl From<TerminalQueryError> for DetectBackgroundError {
+    fn from(error: TerminalQueryError) -> Self {
+        match error {
+            TerminalQueryError::Io(e) => Self::Io(e),
+            TerminalQueryError::Timeout => Self::Timeout,
+            TerminalQueryError::Unsupported => Self::Unsupported,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::black_st(b"\x1b]11;rgb:0000/0000/0000\x1b\\", TerminalBackground::Dark)]
+    #[case::white_bel(b"\x1b]11;rgb:ffff/ffff/ffff\x07", TerminalBackground::Light)]
+    #[case::short_components(b"\x1b]11;rgb:fd/f6/e3\x1b\\", TerminalBackground::Light)]
+    #[case::dark_grey(b"\x1b]11;rgb:2828/2c2c/3434\x1b\\", TerminalBackground::Dark)]
+    #[case::followed_by_attributes(b"\x1b]11;rgb:eeee/eeee/eeee\x1b\\\x1b[?62;4c", TerminalBackground::Light)]
+    fn parse_response(#[case] response: &[u8], #[case] expected: TerminalBackground) {
+        assert_eq!(TerminalBackground::from_response(response), Some(expected));
+    }
+
+    #[rstest]
+    #[case::only_attributes(b"\x1b[?62;4c")]
+    #[case::missing_component(b"\x1b]11;rgb:ffff/ffff\x1b\\")]
+    #[case::too_many_components(b"\x1b]11;rgb:ff/ff/ff/ff\x1b\\")]
+    #[case::invalid_hex(b"\x1b]11;rgb:zzzz/ffff/ffff\x1b\\")]
+    #[case::too_long(b"\x1b]11;rgb:fffff/ffff/ffff\x1b\\")]
+    fn invalid_response(#[case] response: &[u8]) {
+        assert_eq!(TerminalBackground::from_response(response), None);
+    }
+}
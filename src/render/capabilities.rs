@@ -0,0 +1,256 @@
+//! Queries for terminal capabilities that can't be inferred from the terminal's size alone.
+
+use super::properties::WindowSize;
+use serde::Serialize;
+use std::time::Duration;
+
+// Asks for the size of a cell in pixels (CSI 16 t). Replies look like `ESC [ 6 ; height ; width t`.
+const CELL_SIZE_QUERY: &[u8] = b"\x1b[16t";
+
+// Asks for the size of the text area in pixels (CSI 14 t). Replies look like `ESC [ 4 ; height ; width t`.
+const TEXT_AREA_SIZE_QUERY: &[u8] = b"\x1b[14t";
+
+// The primary device attributes query. Every terminal replies to this one so we use it to know
+// when to stop waiting for a reply to any other query sent before it.
+#[cfg(unix)]
+const DEVICE_ATTRIBUTES_QUERY: &[u8] = b"\x1b[c";
+
+/// The size of a terminal cell, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct CellSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl CellSize {
+    /// Construct a cell size, as long as neither of its dimensions is zero.
+    pub fn new(width: u16, height: u16) -> Option<Self> {
+        (width > 0 && height > 0).then_some(Self { width, height })
+    }
+
+    /// Assume cells are twice as tall as they are wide, which is what most fonts look like.
+    pub fn from_font_size(font_size: u8) -> Self {
+        let width = font_size.max(1) as u16;
+        Self { width, height: width * 2 }
+    }
+
+    /// Query the terminal for its cell size.
+    ///
+    /// This waits at most `timeout` for the terminal to reply.
+    pub fn detect(timeout: Duration) -> Result<Self, TerminalQueryError> {
+        let response = query_terminal(&[CELL_SIZE_QUERY, TEXT_AREA_SIZE_QUERY], timeout)?;
+        let size = crossterm::terminal::size()?;
+        Self::from_response(&response, size.into()).ok_or(TerminalQueryError::Unsupported)
+    }
+
+    /// Parse the replies to the cell size and text area size queries.
+    ///
+    /// The cell size is used if the terminal replied to that query, otherwise it's derived from
+    /// the text area size.
+    fn from_response(response: &[u8], window: WindowSize) -> Option<Self> {
+        let response = String::from_utf8_lossy(response);
+        let mut text_area = None;
+        for reply in response.split('\x1b').filter_map(|reply| reply.strip_prefix('[')?.strip_suffix('t')) {
+            let mut values = reply.split(';').map(|value| value.parse::<u16>().ok());
+            let (kind, height, width) = (values.next()??, values.next()??, values.next()??);
+            match kind {
+                6 => return Self::new(width, height),
+                4 => text_area = Some((width, height)),
+                _ => continue,
+            };
+        }
+        let (width, height) = text_area?;
+        Self::new(width.checked_div(window.columns)?, height.checked_div(window.rows)?)
+    }
+}
+
+/// How the pixel size of the terminal's cells is figured out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellSizePolicy {
+    /// Always use this cell size, regardless of what the terminal reports.
+    Fixed(CellSize),
+
+    /// Use the pixel size the terminal reports along with its window size, falling back to this
+    /// cell size if it doesn't.
+    Detect { fallback: CellSize },
+}
+
+impl CellSizePolicy {
+    /// Figure out how the cell size should be computed.
+    ///
+    /// A configured cell size always wins. Otherwise, if the terminal doesn't report its size in
+    /// pixels, it's queried for its cell size and, if that fails as well, the cell size is derived
+    /// from the configured font size.
+    pub fn resolve<F>(configured: Option<CellSize>, font_size: u8, reports_pixels: bool, query: F) -> Self
+    where
+        F: FnOnce() -> Option<CellSize>,
+    {
+        if let Some(size) = configured {
+            return Self::Fixed(size);
+        }
+        let from_font_size = CellSize::from_font_size(font_size);
+        if reports_pixels {
+            return Self::Detect { fallback: from_font_size };
+        }
+        Self::Detect { fallback: query().unwrap_or(from_font_size) }
+    }
+
+    /// Resolve the policy by looking at the terminal.
+    ///
+    /// This sends queries to the terminal so it must be called before anything else reads from it.
+    pub fn detect(configured: Option<CellSize>, font_size: u8, timeout: Duration) -> Self {
+        let reports_pixels = crossterm::terminal::window_size().map(|size| size.width > 0).unwrap_or(false);
+        Self::resolve(configured, font_size, reports_pixels, || CellSize::detect(timeout).ok())
+    }
+
+    /// Get the policy to use when the terminal can't be looked at.
+    pub fn from_font_size(font_size: u8) -> Self {
+        Self::Detect { fallback: CellSize::from_font_size(font_size) }
+    }
+}
+
+/// Check whether the terminal supports 24 bit colors.
+///
+/// Terminals can't be reliably queried for this so, like most tools do, we rely on `COLORTERM`.
+pub(crate) fn supports_truecolor() -> bool {
+    is_truecolor(std::env::var("COLORTERM").ok().as_deref())
+}
+
+fn is_truecolor(colorterm: Option<&str>) -> bool {
+    matches!(colorterm, Some("truecolor" | "24bit"))
+}
+
+/// Send some queries to the terminal and read back everything it replies up until it replies to a
+/// device attributes query.
+#[cfg(unix)]
+pub(crate) fn query_terminal(queries: &[&[u8]], timeout: Duration) -> Result<Vec<u8>, TerminalQueryError> {
+    use std::{fs::OpenOptions, io::Write};
+
+    // Stdin/stdout may be redirected so talk to the terminal directly.
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+    crossterm::terminal::enable_raw_mode()?;
+    let result = queries
+        .iter()
+        .chain(&[DEVICE_ATTRIBUTES_QUERY])
+        .try_for_each(|query| tty.write_all(query))
+        .and_then(|_| tty.flush())
+        .map_err(TerminalQueryError::from)
+        .and_then(|_| read_response(&mut tty, timeout));
+    crossterm::terminal::disable_raw_mode()?;
+    result
+}
+
+#[cfg(unix)]
+fn read_response(tty: &mut std::fs::File, timeout: Duration) -> Result<Vec<u8>, TerminalQueryError> {
+    use std::{io::Read, os::fd::AsRawFd, time::Instant};
+
+    let deadline = Instant::now() + timeout;
+    let mut response = Vec::new();
+    // Keep reading until the device attributes reply, which looks like `ESC [ ? ... c`, shows up.
+    while !ends_with_device_attributes(&response) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(TerminalQueryError::Timeout);
+        }
+        let mut poll_fd = libc::pollfd { fd: tty.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        let timeout = remaining.as_millis().try_into().unwrap_or(libc::c_int::MAX);
+        // SAFETY: the pointer points to a single, valid pollfd.
+        let result = unsafe { libc::poll(&mut poll_fd, 1, timeout) };
+        match result {
+            0 => return Err(TerminalQueryError::Timeout),
+            n if n < 0 => return Err(std::io::Error::last_os_error().into()),
+            _ => (),
+        };
+        let mut buffer = [0; 64];
+        let bytes_read = tty.read(&mut buffer)?;
+        if bytes_read == 0 {
+            return Err(TerminalQueryError::Unsupported);
+        }
+        response.extend(&buffer[..bytes_read]);
+    }
+    Ok(response)
+}
+
+#[cfg(unix)]
+fn ends_with_device_attributes(response: &[u8]) -> bool {
+    let Some(position) = response.windows(3).rposition(|window| window == b"\x1b[?") else {
+        return false;
+    };
+    response[position..].ends_with(b"c")
+}
+
+#[cfg(not(unix))]
+pub(crate) fn query_terminal(_queries: &[&[u8]], _timeout: Duration) -> Result<Vec<u8>, TerminalQueryError> {
+    Err(TerminalQueryError::Unsupported)
+}
+
+/// An error when querying the terminal.
+#[derive(Debug, thiserror::Error)]
+pub enum TerminalQueryError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("terminal did not reply in time")]
+    Timeout,
+
+    #[error("terminal does not support this query")]
+    Unsupported,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    const WINDOW: WindowSize = WindowSize { rows: 10, columns: 20, width: 0, height: 0 };
+
+    #[rstest]
+    #[case::cell_size(b"\x1b[6;20;10t\x1b[?62;4c", Some((10, 20)))]
+    #[case::text_area(b"\x1b[4;300;200t\x1b[?62;4c", Some((10, 30)))]
+    #[case::both(b"\x1b[4;300;200t\x1b[6;18;9t\x1b[?62;4c", Some((9, 18)))]
+    #[case::only_attributes(b"\x1b[?62;4c", None)]
+    #[case::zero_cell_size(b"\x1b[6;0;0t\x1b[?62;4c", None)]
+    #[case::too_small_text_area(b"\x1b[4;5;5t", None)]
+    #[case::garbage(b"\x1b[6;potato;10t", None)]
+    fn parse_response(#[case] response: &[u8], #[case] expected: Option<(u16, u16)>) {
+        let expected = expected.and_then(|(width, height)| CellSize::new(width, height));
+        assert_eq!(CellSize::from_response(response, WINDOW), expected);
+    }
+
+    fn size(width: u16, height: u16) -> CellSize {
+        CellSize { width, height }
+    }
+
+    #[rstest]
+    #[case::configured(Some(size(5, 11)), false, Some(size(7, 15)), CellSizePolicy::Fixed(size(5, 11)))]
+    #[case::reports_pixels(None, true, Some(size(7, 15)), CellSizePolicy::Detect { fallback: size(8, 16) })]
+    #[case::queried(None, false, Some(size(7, 15)), CellSizePolicy::Detect { fallback: size(7, 15) })]
+    #[case::font_size(None, false, None, CellSizePolicy::Detect { fallback: size(8, 16) })]
+    fn fallback_chain(
+        #[case] configured: Option<CellSize>,
+        #[case] reports_pixels: bool,
+        #[case] queried: Option<CellSize>,
+        #[case] expected: CellSizePolicy,
+    ) {
+        let policy = CellSizePolicy::resolve(configured, 8, reports_pixels, || queried);
+        assert_eq!(policy, expected);
+    }
+
+    #[rstest]
+    #[case::truecolor(Some("truecolor"), true)]
+    #[case::bits(Some("24bit"), true)]
+    #[case::other(Some("yes"), false)]
+    #[case::unset(None, false)]
+    fn truecolor(#[case] colorterm: Option<&str>, #[case] expected: bool) {
+        assert_eq!(is_truecolor(colorterm), expected);
+    }
+
+    #[cfg(unix)]
+    #[rstest]
+    #[case::complete(b"\x1b[6;20;10t\x1b[?62;4c", true)]
+    #[case::partial(b"\x1b[6;20;10t\x1b[?62;4", false)]
+    #[case::nothing(b"", false)]
+    fn device_attributes_terminator(#[case] response: &[u8], #[case] expected: bool) {
+        assert_eq!(ends_with_device_attributes(response), expected);
+    }
+}
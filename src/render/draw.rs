@@ -1,16 +1,32 @@
 use super::{
-    engine::RenderEngine,
-    terminal::{Terminal, TerminalWrite},
+    engine::{RenderEngine, RenderEngineOptions},
+    layout::Positioning,
+    profile::FrameProfile,
+    region::{PaintedRegions, Region, SlideRegion},
+    terminal::{is_disconnection, Terminal, TerminalWrite},
+    text::TextDrawer,
 };
 use crate::{
-    markdown::{elements::Text, text::WeightedTextBlock},
+    custom::{PreviewDimensions, SafeAreaMargin},
+    markdown::{
+        elements::{Text, TextBlock},
+        text::WeightedTextBlock,
+    },
     media::printer::{ImagePrinter, PrintImageError},
     presentation::{Presentation, RenderOperation},
-    render::properties::WindowSize,
+    processing::focus::CodeFocus,
+    render::{
+        capabilities::{supports_truecolor, CellSizePolicy},
+        properties::{CursorPosition, RenderDimensions, WindowSize},
+    },
     style::{Color, Colors, TextStyle},
     theme::{Alignment, Margin},
 };
-use std::{io, rc::Rc};
+use std::{io, mem, rc::Rc};
+use unicode_width::UnicodeWidthStr;
+
+// What's drawn in the top right corner after a slow frame.
+const SLOW_FRAME_INDICATOR: &str = "!";
 
 /// The result of a render operation.
 pub(crate) type RenderResult = Result<(), RenderError>;
@@ -18,7 +34,13 @@ pub(crate) type RenderResult = Result<(), RenderError>;
 /// Allows drawing elements in the terminal.
 pub(crate) struct TerminalDrawer<W: TerminalWrite> {
     terminal: Terminal<W>,
-    font_size_fallback: u8,
+    cell_size: CellSizePolicy,
+    dimensions: RenderDimensions,
+    safe_area: SafeAreaMargin,
+    truecolor: bool,
+    profile: Option<FrameProfile>,
+    painted: PaintedRegions,
+    regions: Vec<SlideRegion>,
 }
 
 impl<W> TerminalDrawer<W>
@@ -26,23 +48,67 @@ where
     W: TerminalWrite,
 {
     /// Construct a drawer over a [std::io::Write].
-    pub(crate) fn new(handle: W, image_printer: Rc<ImagePrinter>, font_size_fallback: u8) -> io::Result<Self> {
+    pub(crate) fn new(
+        handle: W,
+        image_printer: Rc<ImagePrinter>,
+        cell_size: CellSizePolicy,
+        dimensions: RenderDimensions,
+        safe_area: SafeAreaMargin,
+    ) -> io::Result<Self> {
         let terminal = Terminal::new(handle, image_printer)?;
-        Ok(Self { terminal, font_size_fallback })
+        Ok(Self {
+            terminal,
+            cell_size,
+            dimensions,
+            safe_area,
+            truecolor: supports_truecolor(),
+            profile: None,
+            painted: Default::default(),
+            regions: Vec::new(),
+        })
+    }
+
+    /// Start recording how long the next frame takes to render.
+    pub(crate) fn start_profile(&mut self) {
+        self.profile = Some(FrameProfile::default());
+    }
+
+    /// Take the profile recorded since [TerminalDrawer::start_profile] was called.
+    pub(crate) fn take_profile(&mut self) -> Option<FrameProfile> {
+        self.profile.take()
     }
 
     /// Render a slide.
     pub(crate) fn render_slide(&mut self, presentation: &Presentation) -> RenderResult {
-        let dimensions = WindowSize::current(self.font_size_fallback)?;
+        let dimensions = self.window_size()?;
         let slide = presentation.current_slide();
         let engine = self.create_engine(dimensions);
         engine.render(slide.iter_operations())?;
         Ok(())
     }
 
+    /// Render a slide while code in it is running.
+    ///
+    /// Everything but code blocks and their output is dimmed so it's clear what's going on.
+    pub(crate) fn render_executing_slide(&mut self, presentation: &Presentation) -> RenderResult {
+        let dimensions = self.window_size()?;
+        let options = RenderEngineOptions {
+            safe_area: self.safe_area,
+            dim_text: true,
+            truecolor: self.truecolor,
+            ..Default::default()
+        };
+        self.painted.invalidate();
+        let engine = RenderEngine::new(&mut self.terminal, dimensions, options)
+            .profile(self.profile.as_mut())
+            .regions(&mut self.regions);
+        engine.render(presentation.current_slide().iter_operations())?;
+        Ok(())
+    }
+
     /// Render an error.
     pub(crate) fn render_error(&mut self, message: &str) -> RenderResult {
-        let dimensions = WindowSize::current(self.font_size_fallback)?;
+        let dimensions = self.window_size()?;
         let heading = vec![Text::new("Error loading presentation", TextStyle::default().bold()), Text::from(": ")];
 
         let alignment = Alignment::Center { minimum_size: 0, minimum_margin: Margin::Percent(8) };
@@ -52,7 +118,7 @@ where
                 background: Some(Color::new(0, 0, 0)),
             }),
             RenderOperation::ClearScreen,
-            RenderOperation::JumpToVerticalCenter,
+            RenderOperation::JumpToVerticalCenter { operations: None },
             RenderOperation::RenderText { line: WeightedTextBlock::from(heading), alignment: alignment.clone() },
             RenderOperation::RenderLineBreak,
             RenderOperation::RenderLineBreak,
@@ -67,23 +133,268 @@ where
         Ok(())
     }
 
+    /// Render a blank screen.
+    pub(crate) fn render_blank(&mut self) -> RenderResult {
+        let black = Some(Color::new(0, 0, 0));
+        let operations =
+            [RenderOperation::SetColors(Colors { foreground: black, background: black }), RenderOperation::ClearScreen];
+        self.render_operations(&operations)
+    }
+
+    /// Render a slide as if the terminal had the given dimensions.
+    ///
+    /// The slide is laid out using the given dimensions and drawn centered in the terminal, inside
+    /// a frame that shows its size.
+    pub(crate) fn render_preview(&mut self, presentation: &Presentation, preview: PreviewDimensions) -> RenderResult {
+        let dimensions = self.window_size()?;
+        let Some(frame) = PreviewFrame::new(&dimensions, preview) else {
+            let text = vec![Text::from(format!("terminal is too small to preview the slide at {preview}"))];
+            let alignment = Alignment::Center { minimum_size: 0, minimum_margin: Margin::Percent(8) };
+            let operations = [
+                RenderOperation::SetColors(Default::default()),
+                RenderOperation::ClearScreen,
+                RenderOperation::JumpToVerticalCenter { operations: None },
+                RenderOperation::RenderText { line: WeightedTextBlock::from(text), alignment },
+            ];
+            return self.render_operations(&operations);
+        };
+        let options = RenderEngineOptions {
+            safe_area: self.safe_area,
+            origin: frame.origin.clone(),
+            truecolor: self.truecolor,
+            ..Default::default()
+        };
+        self.painted.invalidate();
+        let engine = RenderEngine::new(&mut self.terminal, frame.dimensions.clone(), options)
+            .profile(self.profile.as_mut())
+            .regions(&mut self.regions);
+        engine.render(presentation.current_slide().iter_operations())?;
+
+        for (position, line) in frame.border() {
+            self.terminal.move_to(position.column, position.row)?;
+            self.terminal.print_line(&line)?;
+        }
+        self.terminal.flush()?;
+        Ok(())
+    }
+
     pub(crate) fn render_slide_index(&mut self, presentation: &Presentation) -> RenderResult {
-        let dimensions = WindowSize::current(self.font_size_fallback)?;
+        let dimensions = self.window_size()?;
         let engine = self.create_engine(dimensions);
         engine.render(presentation.iter_slide_index_operations())?;
         Ok(())
     }
 
     pub(crate) fn render_key_bindings(&mut self, presentation: &Presentation) -> RenderResult {
-        let dimensions = WindowSize::current(self.font_size_fallback)?;
+        let dimensions = self.window_size()?;
         let engine = self.create_engine(dimensions);
         engine.render(presentation.iter_bindings_operations())?;
         Ok(())
     }
 
-    fn create_engine(&mut self, dimensions: WindowSize) -> RenderEngine<W> {
-        let options = Default::default();
+    pub(crate) fn render_glossary(&mut self, presentation: &Presentation) -> RenderResult {
+        let dimensions = self.window_size()?;
+        let engine = self.create_engine(dimensions);
+        engine.render(presentation.iter_glossary_operations())?;
+        Ok(())
+    }
+
+    /// Render one of the current slide's code blocks on its own.
+    pub(crate) fn render_code_focus(&mut self, presentation: &Presentation, focus: &CodeFocus) -> RenderResult {
+        let dimensions = self.window_size()?;
+        let Some(code) = presentation.current_slide().focusable_code().get(focus.block).copied() else {
+            return self.render_slide(presentation);
+        };
+        let operations = code.render_operations(focus.scroll, &dimensions);
+        let engine = self.create_engine(dimensions);
+        engine.render(operations.iter())?;
+        Ok(())
+    }
+
+    /// Render a list of operations.
+    pub(crate) fn render_operations(&mut self, operations: &[RenderOperation]) -> RenderResult {
+        let dimensions = self.window_size()?;
+        let engine = self.create_engine(dimensions);
+        engine.render(operations.iter())?;
+        Ok(())
+    }
+
+    /// Draw a line of text on the bottom row, on top of whatever was drawn there.
+    ///
+    /// The text is truncated so it never takes up more than that row. Unless it's given a
+    /// background color, the row is drawn in inverse video so it looks like a bar that stands out
+    /// from the slide behind it.
+    pub(crate) fn render_status_line(&mut self, line: Vec<Text>, colors: &Colors) -> RenderResult {
+        let dimensions = self.window_size()?;
+        let region = Region {
+            name: "status",
+            column: self.safe_area.columns,
+            row: dimensions.rows.saturating_sub(1 + self.safe_area.rows),
+            width: dimensions.columns.saturating_sub(self.safe_area.columns * 2),
+            height: 1,
+        };
+        let mut line = TextBlock(line);
+        if colors.background.is_none() {
+            line.apply_style(&TextStyle::default().inverse());
+        }
+        self.paint_region(&region, vec![line], colors)
+    }
+
+    /// Draw a marker in the top right corner to signal the last frame was slow to render.
+    pub(crate) fn render_slow_frame_indicator(&mut self) -> RenderResult {
+        let dimensions = self.window_size()?;
+        let width = SLOW_FRAME_INDICATOR.width() as u16;
+        let region = Region {
+            name: "slow-frame",
+            column: dimensions.columns.saturating_sub(width + self.safe_area.columns),
+            row: self.safe_area.rows,
+            width,
+            height: 1,
+        };
+        let colors = Colors { foreground: Some(Color::new(255, 165, 0)), background: None };
+        let text = Text::new(SLOW_FRAME_INDICATOR, TextStyle::default().bold().colors(colors));
+        self.paint_region(&region, vec![TextBlock(vec![text])], &Default::default())
+    }
+
+    /// Render how much memory cached content is using in the bottom right corner of the screen.
+    pub(crate) fn render_cache_usage(&mut self, usage: &str) -> RenderResult {
+        let dimensions = self.window_size()?;
+        let width = usage.width() as u16;
+        let region = Region {
+            name: "cache-usage",
+            column: dimensions.columns.saturating_sub(width + self.safe_area.columns),
+            row: dimensions.rows.saturating_sub(1 + self.safe_area.rows),
+            width,
+            height: 1,
+        };
+        let text = Text::new(usage, TextStyle::default().dim());
+        self.paint_region(&region, vec![TextBlock(vec![text])], &Default::default())
+    }
+
+    /// Ring the terminal's bell.
+    pub(crate) fn ring_bell(&mut self) -> RenderResult {
+        self.terminal.ring_bell()?;
+        Ok(())
+    }
+
+    /// Paint lines in a region, on top of whatever was drawn there.
+    ///
+    /// The lines are fit into the region so nothing outside of it is touched, and only the rows
+    /// that changed since the last time the region was painted are drawn again.
+    pub(crate) fn paint_region(&mut self, region: &Region, lines: Vec<TextBlock>, colors: &Colors) -> RenderResult {
+        let lines = region.fit(lines);
+        let changed = self.painted.update(region, &lines);
+        if changed.is_empty() {
+            return Ok(());
+        }
+        let positioning = Positioning { max_line_length: region.width, start_column: region.column };
+        for index in changed {
+            let line = WeightedTextBlock::from(lines[index].clone());
+            let drawer = TextDrawer::positioned(&line, positioning.clone(), colors)?;
+            self.terminal.move_to(region.column, region.row + index as u16)?;
+            drawer.draw(&mut self.terminal)?;
+        }
+        self.terminal.flush()?;
+        Ok(())
+    }
+
+    /// Paint the regions in the last slide rendered again, like the one a countdown is in when it ticks.
+    ///
+    /// Only the rows that changed are drawn. Returns `false` without painting anything if a region
+    /// no longer fits in the rows it was laid out with, in which case the slide needs to be
+    /// rendered again.
+    pub(crate) fn repaint_regions(&mut self) -> Result<bool, RenderError> {
+        let regions = mem::take(&mut self.regions);
+        let updates: Vec<_> = regions.iter().map(|slide| slide.painter.region_lines(slide.region.width)).collect();
+        if regions.iter().zip(&updates).any(|(slide, lines)| lines.len() != slide.rows) {
+            self.regions = regions;
+            return Ok(false);
+        }
+        let result = regions.iter().zip(updates).try_for_each(|(slide, lines)| {
+            self.painted.painted_elsewhere(&slide.region, &slide.lines);
+            self.paint_region(&slide.region, lines, &slide.colors)
+        });
+        self.regions = regions;
+        result.map(|_| true)
+    }
+
+    /// Set the margin to leave empty around the terminal.
+    pub(crate) fn set_safe_area(&mut self, safe_area: SafeAreaMargin) {
+        self.safe_area = safe_area;
+    }
+
+    fn window_size(&self) -> io::Result<WindowSize> {
+        self.dimensions.window_size(self.cell_size)
+    }
+
+    fn create_engine(&mut self, dimensions: WindowSize) -> RenderEngine<'_, W> {
+        // Anything rendered by the engine can draw over regions so they need to be painted again.
+        self.painted.invalidate();
+        let options =
+            RenderEngineOptions { safe_area: self.safe_area, truecolor: self.truecolor, ..Default::default() };
         RenderEngine::new(&mut self.terminal, dimensions, options)
+            .profile(self.profile.as_mut())
+            .regions(&mut self.regions)
+    }
+}
+
+/// The frame a slide preview is drawn in.
+struct PreviewFrame {
+    /// The position of the top left corner of the area inside the frame.
+    origin: CursorPosition,
+
+    /// The dimensions of the area inside the frame.
+    dimensions: WindowSize,
+}
+
+impl PreviewFrame {
+    /// Center a frame for the given preview in a terminal, if it fits in it.
+    fn new(terminal: &WindowSize, preview: PreviewDimensions) -> Option<Self> {
+        let PreviewDimensions { columns, rows } = preview;
+        // The frame takes one row/column on every side.
+        if columns == 0
+            || rows == 0
+            || columns.saturating_add(2) > terminal.columns
+            || rows.saturating_add(2) > terminal.rows
+        {
+            return None;
+        }
+        let origin = CursorPosition { column: (terminal.columns - columns) / 2, row: (terminal.rows - rows) / 2 };
+        // Keep the same font size so images are scaled the same way they would be at this size.
+        let dimensions = WindowSize {
+            columns,
+            rows,
+            width: (terminal.pixels_per_column() * columns as f64) as u16,
+            height: (terminal.pixels_per_row() * rows as f64) as u16,
+        };
+        Some(Self { origin, dimensions })
+    }
+
+    /// The lines that make up the border around the preview, along with the position they start at.
+    fn border(&self) -> Vec<(CursorPosition, String)> {
+        let WindowSize { columns, rows, .. } = self.dimensions;
+        let CursorPosition { column, row } = self.origin;
+        let left = column - 1;
+        let right = column + columns;
+
+        let top = format!("┌{}┐", "─".repeat(columns as usize));
+        let mut lines = vec![(CursorPosition { column: left, row: row - 1 }, top)];
+        for row in row..row + rows {
+            lines.push((CursorPosition { column: left, row }, "│".into()));
+            lines.push((CursorPosition { column: right, row }, "│".into()));
+        }
+        // The bottom edge works as a footer that shows the size being previewed.
+        let label = format!(" preview: {columns}x{rows} ");
+        let label_width = label.width();
+        let bottom = match (columns as usize).checked_sub(label_width) {
+            Some(remaining) => {
+                let before = remaining / 2;
+                format!("└{}{label}{}┘", "─".repeat(before), "─".repeat(remaining - before))
+            }
+            None => format!("└{}┘", "─".repeat(columns as usize)),
+        };
+        lines.push((CursorPosition { column: left, row: row + rows }, bottom));
+        lines
     }
 }
 
@@ -117,3 +428,249 @@ pub enum RenderError {
     #[error(transparent)]
     Other(Box<dyn std::error::Error>),
 }
+
+impl RenderError {
+    /// Checks whether this error was caused by the terminal being gone.
+    pub(crate) fn is_disconnection(&self) -> bool {
+        match self {
+            Self::Io(e) | Self::PrintImage(PrintImageError::Io(e)) => is_disconnection(e),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        custom::{CountdownReturn, CountdownStart},
+        presentation::{SlideBuilder, SlideChunk},
+        processing::{
+            builder::{PresentationBuilder, Themes},
+            countdown::CountdownOperation,
+        },
+        render::terminal::TestWriter,
+        MarkdownParser, PresentationTheme, Resources, TypstRender,
+    };
+    use comrak::Arena;
+    use rstest::rstest;
+    use std::{cell::Cell, rc::Rc, time::Duration};
+
+    // A terminal that's gone, which fails every write with a broken pipe.
+    struct BrokenPipe {
+        writes: Rc<Cell<usize>>,
+    }
+
+    impl io::Write for BrokenPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            self.writes.set(self.writes.get() + 1);
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    impl TerminalWrite for BrokenPipe {
+        fn init(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn deinit(&mut self) {}
+    }
+
+    fn capture_drawer() -> (TerminalDrawer<TestWriter>, TestWriter) {
+        let capture = TestWriter::default();
+        let dimensions = RenderDimensions::Fixed { columns: 40, rows: 10 };
+        let drawer = TerminalDrawer::new(
+            capture.clone(),
+            Default::default(),
+            CellSizePolicy::from_font_size(16),
+            dimensions,
+            Default::default(),
+        )
+        .expect("creating drawer");
+        capture.take();
+        (drawer, capture)
+    }
+
+    // Draw the first slide in a presentation.
+    fn render_markdown(contents: &str) -> String {
+        let arena = Arena::new();
+        let elements = MarkdownParser::new(&arena).parse(contents).expect("parse failed");
+        let theme = PresentationTheme::default();
+        let mut resources = Resources::new("/tmp", Default::default());
+        let mut typst = TypstRender::default();
+        let themes = Themes::default();
+        let presentation = PresentationBuilder::new(
+            &theme,
+            &mut resources,
+            &mut typst,
+            &themes,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .build(elements)
+        .expect("build failed");
+        let (mut drawer, capture) = capture_drawer();
+        drawer.render_slide(&presentation).expect("render failed");
+        capture.take()
+    }
+
+    fn lines(lines: &[&str]) -> Vec<TextBlock> {
+        lines.iter().map(|line| TextBlock::from(*line)).collect()
+    }
+
+    #[test]
+    fn region_repaints_changed_rows() {
+        let (mut drawer, capture) = capture_drawer();
+        let region = Region { name: "timer", column: 30, row: 2, width: 8, height: 2 };
+        drawer.paint_region(&region, lines(&["elapsed", "00:05"]), &Default::default()).expect("paint failed");
+        let output = capture.take();
+        assert!(output.contains("elapsed") && output.contains("00:05"), "{output:?}");
+
+        // Nothing changed so nothing is written.
+        drawer.paint_region(&region, lines(&["elapsed", "00:05"]), &Default::default()).expect("paint failed");
+        assert_eq!(capture.take(), "");
+
+        // A tick only touches the row the time is in.
+        drawer.paint_region(&region, lines(&["elapsed", "00:06"]), &Default::default()).expect("paint failed");
+        let output = capture.take();
+        assert!(output.contains("\x1b[4;31H"), "{output:?}");
+        assert!(output.contains("00:06"), "{output:?}");
+        assert!(!output.contains("elapsed"), "{output:?}");
+
+        // Rendering the slide draws over the region so it's painted in full again.
+        drawer.render_operations(&[RenderOperation::ClearScreen]).expect("render failed");
+        capture.take();
+        drawer.paint_region(&region, lines(&["elapsed", "00:06"]), &Default::default()).expect("paint failed");
+        let output = capture.take();
+        assert!(output.contains("elapsed") && output.contains("00:06"), "{output:?}");
+    }
+
+    #[test]
+    fn region_is_clipped() {
+        let (mut drawer, capture) = capture_drawer();
+        let region = Region { name: "toast", column: 0, row: 0, width: 6, height: 1 };
+        drawer.paint_region(&region, lines(&["copied to clipboard", "second"]), &Default::default()).expect("paint");
+        let output = capture.take();
+        assert!(output.contains("copie…"), "{output:?}");
+        assert!(!output.contains("clipboard") && !output.contains("second"), "{output:?}");
+    }
+
+    #[test]
+    fn countdown_tick_repaints_its_rows() {
+        let (mut drawer, capture) = capture_drawer();
+        let alignment = Alignment::Center { minimum_size: 0, minimum_margin: Margin::Fixed(0) };
+        let countdown = Rc::new(CountdownOperation::new(
+            "90s".into(),
+            Duration::from_secs(90),
+            Default::default(),
+            Default::default(),
+            alignment,
+            CountdownStart::Shown,
+            CountdownReturn::Resume,
+            false,
+        ));
+        let operations = vec![
+            RenderOperation::JumpToRow { index: 2 },
+            RenderOperation::RenderOnDemand(countdown.clone()),
+            RenderOperation::RenderLineBreak,
+            RenderOperation::RenderText { line: vec![Text::from("below")].into(), alignment: Default::default() },
+        ];
+        let slide = SlideBuilder::default().chunks(vec![SlideChunk::new(operations, vec![])]).build();
+        let presentation = Presentation::from(vec![slide]);
+        drawer.render_slide(&presentation).expect("render failed");
+        let output = capture.take();
+        assert!(output.contains("below"), "{output:?}");
+
+        // Nothing changed so nothing is written.
+        assert!(drawer.repaint_regions().expect("repaint failed"));
+        assert_eq!(capture.take(), "");
+
+        // Going from 01:30 to 01:29 only touches the rows the countdown is in.
+        countdown.advance(Duration::from_secs(1));
+        assert!(drawer.repaint_regions().expect("repaint failed"));
+        let output = capture.take();
+        assert!(!output.contains("below"), "{output:?}");
+        let rows: Vec<u16> = output
+            .split("\x1b[")
+            .skip(1)
+            .filter_map(|sequence| sequence.split_once('H'))
+            .filter_map(|(position, _)| position.split_once(';'))
+            .map(|(row, _)| row.parse().expect("invalid row"))
+            .collect();
+        assert!(!rows.is_empty(), "{output:?}");
+        // Rows are 1 based and the countdown's 5 rows start at the third one.
+        assert!(rows.iter().all(|row| (3..8).contains(row)), "{rows:?}");
+    }
+
+    // Control characters would move the cursor, so text containing them has to be drawn exactly like the text they're
+    // stripped from, including everything drawn after it.
+    #[rstest]
+    #[case::tab("hello\tthere", "hello there")]
+    #[case::carriage_return("hello\rthere", "hello there")]
+    #[case::escape("hello\x1b[2Kthere", "hello[2Kthere")]
+    fn control_characters(#[case] text: &str, #[case] sanitized: &str) {
+        let output = render_markdown(&format!("{text}\n\n# after\n\nlast"));
+        assert!(output.contains(sanitized), "{output:?}");
+        assert_eq!(output, render_markdown(&format!("{sanitized}\n\n# after\n\nlast")));
+    }
+
+    #[test]
+    fn broken_pipe() {
+        let text = RenderOperation::RenderText { line: vec![Text::from("hi")].into(), alignment: Default::default() };
+        let slide = SlideBuilder::default().chunks(vec![SlideChunk::new(vec![text], vec![])]).build();
+        let presentation = Presentation::from(vec![slide]);
+        let writes = Rc::new(Cell::new(0));
+        let dimensions = RenderDimensions::Fixed { columns: 40, rows: 10 };
+        let mut drawer = TerminalDrawer::new(
+            BrokenPipe { writes: writes.clone() },
+            Default::default(),
+            CellSizePolicy::from_font_size(16),
+            dimensions,
+            Default::default(),
+        )
+        .expect("creating drawer");
+
+        // The error is reported as a disconnection, which makes the presenter exit without
+        // touching the terminal again.
+        let error = drawer.render_slide(&presentation).expect_err("render succeeded");
+        assert!(error.is_disconnection(), "not a disconnection: {error}");
+        let error = drawer.render_slide(&presentation).expect_err("render succeeded");
+        assert!(error.is_disconnection());
+        drop(drawer);
+        assert_eq!(writes.get(), 1);
+    }
+
+    #[test]
+    fn preview_frame() {
+        let terminal = WindowSize { rows: 10, columns: 30, width: 300, height: 200 };
+        let preview = PreviewDimensions { columns: 22, rows: 5 };
+        let frame = PreviewFrame::new(&terminal, preview).expect("no frame");
+        assert_eq!(frame.origin.column, 4);
+        assert_eq!(frame.origin.row, 2);
+        assert_eq!(frame.dimensions, WindowSize { rows: 5, columns: 22, width: 220, height: 100 });
+
+        let border = frame.border();
+        let (position, top) = &border[0];
+        assert_eq!((position.column, position.row), (3, 1));
+        assert_eq!(top.chars().count(), 24);
+        let (position, bottom) = border.last().unwrap();
+        assert_eq!((position.column, position.row), (3, 7));
+        assert_eq!(bottom, "└─── preview: 22x5 ────┘");
+        let sides: Vec<_> = border[1..border.len() - 1].iter().map(|(position, _)| position.column).collect();
+        assert_eq!(sides, [3, 26].repeat(5));
+    }
+
+    #[test]
+    fn preview_does_not_fit() {
+        let terminal = WindowSize { rows: 10, columns: 30, width: 300, height: 200 };
+        assert!(PreviewFrame::new(&terminal, PreviewDimensions { columns: 29, rows: 5 }).is_none());
+        assert!(PreviewFrame::new(&terminal, PreviewDimensions { columns: 20, rows: 9 }).is_none());
+        assert!(PreviewFrame::new(&terminal, PreviewDimensions { columns: u16::MAX, rows: 5 }).is_none());
+        assert!(PreviewFrame::new(&terminal, PreviewDimensions { columns: 20, rows: u16::MAX }).is_none());
+    }
+}
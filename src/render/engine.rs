@@ -1,30 +1,51 @@
 use super::{
     draw::{RenderError, RenderResult},
     layout::Layout,
+    profile::FrameProfile,
     properties::CursorPosition,
+    region::{PaintRegion, Region, SlideRegion},
     terminal::{Terminal, TerminalWrite},
     text::TextDrawer,
 };
 use crate::{
+    custom::SafeAreaMargin,
     markdown::text::WeightedTextBlock,
     media::{
         image::Image,
-        printer::{PrintOptions, ResourceProperties},
+        printer::{ImagePrinter, PrintOptions, ResourceProperties},
         scale::scale_image,
     },
     presentation::{
         AsRenderOperations, ImageProperties, ImageSize, MarginProperties, PreformattedLine, RenderOnDemand,
         RenderOperation,
     },
-    render::{layout::Positioning, properties::WindowSize},
-    style::Colors,
+    render::{
+        gradient::{BackgroundGradient, HALF_BLOCK},
+        layout::Positioning,
+        properties::WindowSize,
+    },
+    style::{Color, Colors},
     theme::Alignment,
 };
-use std::mem;
+use std::{io, mem, rc::Rc, time::Instant};
 
 #[derive(Debug, Default)]
 pub(crate) struct RenderEngineOptions {
     pub(crate) validate_overflows: bool,
+
+    /// Ignore horizontal overflows when validating, as nothing can be done about them when measuring slides.
+    pub(crate) vertical_overflows_only: bool,
+
+    pub(crate) safe_area: SafeAreaMargin,
+
+    /// The position in the terminal where the top left corner of the window being rendered is.
+    pub(crate) origin: CursorPosition,
+
+    /// Dim all text except for code blocks and their output, e.g. while code is running.
+    pub(crate) dim_text: bool,
+
+    /// Whether the terminal supports 24 bit colors.
+    pub(crate) truecolor: bool,
 }
 
 pub(crate) struct RenderEngine<'a, W>
@@ -35,8 +56,13 @@ where
     window_rects: Vec<WindowRect>,
     colors: Colors,
     max_modified_row: u16,
+    last_row: u16,
     layout: LayoutState,
+    dim_text: bool,
+    gradient: Option<ActiveGradient>,
     options: RenderEngineOptions,
+    profile: Option<&'a mut FrameProfile>,
+    regions: Option<&'a mut Vec<SlideRegion>>,
 }
 
 impl<'a, W> RenderEngine<'a, W>
@@ -49,46 +75,98 @@ where
         options: RenderEngineOptions,
     ) -> Self {
         let max_modified_row = terminal.cursor_row;
-        let current_rect = WindowRect { dimensions: window_dimensions, start_column: 0 };
+        // The safe area is applied before anything else so everything is laid out within it.
+        let SafeAreaMargin { rows, columns } = options.safe_area;
+        let CursorPosition { column, row } = options.origin;
+        let last_row = (row + window_dimensions.rows).saturating_sub(1);
+        let current_rect = WindowRect { dimensions: window_dimensions, start_column: column, start_row: row }
+            .apply_margin(columns)
+            .apply_vertical_margin(rows);
         let window_rects = vec![current_rect.clone()];
         Self {
             terminal,
             window_rects,
             colors: Default::default(),
             max_modified_row,
+            last_row,
             layout: Default::default(),
+            dim_text: options.dim_text,
+            gradient: None,
             options,
+            profile: None,
+            regions: None,
         }
     }
 
+    /// Record how long rendering takes in the given profile.
+    pub(crate) fn profile(mut self, profile: Option<&'a mut FrameProfile>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Keep track of the regions laid out for components that are painted in a region of their own.
+    pub(crate) fn regions(mut self, regions: &'a mut Vec<SlideRegion>) -> Self {
+        regions.clear();
+        self.regions = Some(regions);
+        self
+    }
+
     pub(crate) fn render<'b>(mut self, operations: impl Iterator<Item = &'b RenderOperation>) -> RenderResult {
+        let operations: Vec<_> = operations.collect();
+        let start = self.profile.is_some().then(Instant::now);
         self.terminal.begin_update()?;
-        for operation in operations {
-            self.render_one(operation)?;
-        }
+        self.render_all(&operations)?;
+        let laid_out = start.map(|_| Instant::now());
         self.terminal.end_update()?;
         self.terminal.flush()?;
-        if self.options.validate_overflows && self.max_modified_row > self.window_rects[0].dimensions.rows {
+        if let (Some(profile), Some(start), Some(laid_out)) = (&mut self.profile, start, laid_out) {
+            profile.layout += laid_out - start;
+            profile.paint += laid_out.elapsed();
+        }
+        let screen = &self.window_rects[0];
+        if self.options.validate_overflows && self.max_modified_row > screen.start_row + screen.dimensions.rows {
             return Err(RenderError::VerticalOverflow);
         }
         Ok(())
     }
 
-    fn render_one(&mut self, operation: &RenderOperation) -> RenderResult {
+    fn render_all(&mut self, operations: &[&RenderOperation]) -> RenderResult {
+        for (index, operation) in operations.iter().enumerate() {
+            let start = self.profile.is_some().then(Instant::now);
+            self.render_one(operation, &operations[index + 1..])?;
+            if let (Some(profile), Some(start)) = (&mut self.profile, start) {
+                profile.record(index, operation, start.elapsed());
+            }
+        }
+        Ok(())
+    }
+
+    fn render_one(&mut self, operation: &RenderOperation, following: &[&RenderOperation]) -> RenderResult {
         match operation {
             RenderOperation::ClearScreen => self.clear_screen(),
             RenderOperation::ApplyMargin(properties) => self.apply_margin(properties),
             RenderOperation::PopMargin => self.pop_margin(),
             RenderOperation::SetColors(colors) => self.set_colors(colors),
-            RenderOperation::JumpToVerticalCenter => self.jump_to_vertical_center(),
+            RenderOperation::RenderBackgroundGradient(gradient) => self.render_background_gradient(gradient),
+            RenderOperation::JumpToVerticalCenter { operations } => {
+                let end = operations.unwrap_or(following.len()).min(following.len());
+                self.jump_to_vertical_center(&following[..end])
+            }
             RenderOperation::JumpToRow { index } => self.jump_to_row(*index),
             RenderOperation::JumpToBottomRow { index } => self.jump_to_bottom(*index),
             RenderOperation::RenderText { line, alignment } => self.render_text(line, alignment),
             RenderOperation::RenderLineBreak => self.render_line_break(),
             RenderOperation::RenderImage(image, properties) => self.render_image(image, properties),
             RenderOperation::RenderPreformattedLine(operation) => self.render_preformatted_line(operation),
-            RenderOperation::RenderDynamic(generator) => self.render_dynamic(generator.as_ref()),
-            RenderOperation::RenderOnDemand(generator) => self.render_on_demand(generator.as_ref()),
+            RenderOperation::RenderDynamic(generator) => match generator.clone().region_painter() {
+                Some(painter) => self.render_region(painter, self.dim_text),
+                None => self.render_dynamic(generator.as_ref()),
+            },
+            RenderOperation::RenderOnDemand(generator) => match generator.clone().region_painter() {
+                // Just like in any other on demand operation, this shouldn't be dimmed.
+                Some(painter) => self.render_region(painter, false),
+                None => self.render_on_demand(generator.as_ref()),
+            },
             RenderOperation::InitColumnLayout { columns } => self.init_column_layout(columns),
             RenderOperation::EnterColumn { column } => self.enter_column(*column),
             RenderOperation::ExitLayout => self.exit_layout(),
@@ -107,9 +185,32 @@ where
     }
 
     fn clear_screen(&mut self) -> RenderResult {
+        let start_row = self.window_rects[0].start_row;
         self.terminal.clear_screen()?;
-        self.terminal.move_to(0, 0)?;
-        self.max_modified_row = 0;
+        self.terminal.move_to(0, start_row)?;
+        self.max_modified_row = start_row;
+        self.gradient = None;
+        Ok(())
+    }
+
+    fn render_background_gradient(&mut self, gradient: &BackgroundGradient) -> RenderResult {
+        let screen = self.window_rects[0].clone();
+        let rows = screen.dimensions.rows;
+        let columns = screen.dimensions.columns as usize;
+        // Without truecolor support the gradient can't be displayed so we use a solid background.
+        let line = if self.options.truecolor { HALF_BLOCK.repeat(columns) } else { " ".repeat(columns) };
+        for row in 0..rows {
+            let colors = match self.options.truecolor {
+                true => gradient.row_colors(row, rows),
+                false => Colors { foreground: None, background: Some(gradient.top()) },
+            };
+            self.terminal.move_to(screen.start_column, screen.start_row + row)?;
+            self.terminal.set_colors(colors)?;
+            self.terminal.print_line(&line)?;
+        }
+        self.gradient = Some(ActiveGradient { gradient: gradient.clone(), background: self.colors.background });
+        self.apply_colors()?;
+        self.terminal.move_to(0, screen.start_row)?;
         Ok(())
     }
 
@@ -140,31 +241,130 @@ where
         Ok(())
     }
 
-    fn jump_to_vertical_center(&mut self) -> RenderResult {
-        let center_row = self.current_dimensions().rows / 2;
-        self.terminal.move_to_row(center_row)?;
+    fn jump_to_vertical_center(&mut self, following: &[&RenderOperation]) -> RenderResult {
+        // Whatever comes next is centered as a whole, so we need to know how tall it is first.
+        let height = self.measure_height(following)?;
+        let rect = self.current_rect();
+        let start_row = rect.start_row + rect.dimensions.rows.saturating_sub(height) / 2;
+        self.terminal.move_to_row(start_row)?;
         Ok(())
     }
 
+    /// Measure the number of rows the given operations take up when rendered from the top of the window.
+    ///
+    /// Only the operations up until the next one that moves the cursor to a specific row are taken
+    /// into account, and line breaks at the end are ignored as they don't render anything.
+    fn measure_height(&self, operations: &[&RenderOperation]) -> Result<u16, RenderError> {
+        let end = operations
+            .iter()
+            .position(|operation| {
+                matches!(
+                    operation,
+                    RenderOperation::ClearScreen
+                        | RenderOperation::JumpToVerticalCenter { .. }
+                        | RenderOperation::JumpToRow { .. }
+                        | RenderOperation::JumpToBottomRow { .. }
+                )
+            })
+            .unwrap_or(operations.len());
+        let mut operations = &operations[..end];
+        while let [rest @ .., RenderOperation::RenderLineBreak] = operations {
+            operations = rest;
+        }
+        if operations.is_empty() {
+            return Ok(0);
+        }
+
+        let start_row = self.current_rect().start_row;
+        let mut terminal = Terminal::new(MeasureWriter, Rc::new(ImagePrinter::Null))?;
+        terminal.move_to_row(start_row)?;
+        let mut engine = RenderEngine {
+            terminal: &mut terminal,
+            window_rects: self.window_rects.clone(),
+            colors: self.colors.clone(),
+            max_modified_row: start_row,
+            // Nothing is actually drawn so there's no need to stop at the bottom of the window.
+            last_row: u16::MAX,
+            layout: self.layout.clone(),
+            dim_text: false,
+            gradient: None,
+            options: Default::default(),
+            profile: None,
+            regions: None,
+        };
+        engine.render_all(operations)?;
+        Ok(engine.max_modified_row - start_row + 1)
+    }
+
     fn jump_to_row(&mut self, index: u16) -> RenderResult {
-        self.terminal.move_to_row(index)?;
+        self.terminal.move_to_row(self.current_rect().start_row + index)?;
         Ok(())
     }
 
     fn jump_to_bottom(&mut self, index: u16) -> RenderResult {
-        let target_row = self.current_dimensions().rows.saturating_sub(index).saturating_sub(1);
+        let rect = self.current_rect();
+        let target_row = rect.start_row + rect.dimensions.rows.saturating_sub(index).saturating_sub(1);
         self.terminal.move_to_row(target_row)?;
         Ok(())
     }
 
     fn render_text(&mut self, text: &WeightedTextBlock, alignment: &Alignment) -> RenderResult {
         let layout = self.build_layout(alignment.clone());
-        let text_drawer = TextDrawer::new(&layout, text, self.current_dimensions(), &self.colors)?;
-        text_drawer.draw(self.terminal)
+        let colors = self.text_colors();
+        let mut text_drawer = TextDrawer::new(&layout, text, self.current_dimensions(), &colors)?;
+        if self.dim_text {
+            text_drawer = text_drawer.dim();
+        }
+        if colors == self.colors {
+            return text_drawer.draw(self.terminal);
+        }
+        self.terminal.set_colors(colors.clone())?;
+        text_drawer.draw(self.terminal)?;
+        self.apply_colors()
+    }
+
+    /// Get the colors to draw text with.
+    ///
+    /// Text that uses the background color that was set when the background gradient was painted
+    /// takes the color of the gradient in the row it's drawn at instead, so it blends with it.
+    fn text_colors(&self) -> Colors {
+        let Some(ActiveGradient { gradient, background }) = &self.gradient else {
+            return self.colors.clone();
+        };
+        if &self.colors.background != background {
+            return self.colors.clone();
+        }
+        let screen = &self.window_rects[0];
+        let background = match self.options.truecolor {
+            true => {
+                let row = self.terminal.cursor_row.saturating_sub(screen.start_row);
+                gradient.row_background(row, screen.dimensions.rows)
+            }
+            false => gradient.top(),
+        };
+        Colors { background: Some(background), ..self.colors.clone() }
     }
 
     fn render_line_break(&mut self) -> RenderResult {
-        self.terminal.move_to_next_line(1)?;
+        self.move_down_safe(1)
+    }
+
+    /// Move the cursor down to the start of a line without going past the bottom of the window.
+    ///
+    /// The terminal stops the cursor at its last row so moving past it would make the row we keep
+    /// track of drift away from the one the cursor is actually at. Any rows we couldn't move past
+    /// are still taken into account when looking for overflows.
+    fn move_down_safe(&mut self, amount: u16) -> RenderResult {
+        let cursor_row = self.terminal.cursor_row;
+        let target_row = cursor_row.saturating_add(amount);
+        let skipped_rows = target_row.saturating_sub(self.last_row);
+        if skipped_rows > 0 {
+            self.max_modified_row = self.max_modified_row.max(self.last_row).saturating_add(skipped_rows);
+        }
+        match target_row.min(self.last_row).saturating_sub(cursor_row) {
+            0 => self.terminal.move_to_column(0)?,
+            amount => self.terminal.move_to_next_line(amount)?,
+        };
         Ok(())
     }
 
@@ -205,7 +405,10 @@ where
 
         let dimensions = self.current_dimensions();
         let Positioning { max_line_length, start_column } = layout.compute(dimensions, *block_length);
-        if self.options.validate_overflows && unformatted_length > &max_line_length {
+        if self.options.validate_overflows
+            && !self.options.vertical_overflows_only
+            && unformatted_length > &max_line_length
+        {
             return Err(RenderError::HorizontalOverflow);
         }
 
@@ -231,16 +434,54 @@ where
 
     fn render_dynamic(&mut self, generator: &dyn AsRenderOperations) -> RenderResult {
         let operations = generator.as_render_operations(self.current_dimensions());
-        for operation in operations {
-            self.render_one(&operation)?;
-        }
-        Ok(())
+        let operations: Vec<_> = operations.iter().collect();
+        self.render_all(&operations)
     }
 
     fn render_on_demand(&mut self, generator: &dyn RenderOnDemand) -> RenderResult {
         let operations = generator.as_render_operations(self.current_dimensions());
-        for operation in operations {
-            self.render_one(&operation)?;
+        // This is the output of running code, which is precisely what shouldn't be dimmed.
+        let operations: Vec<_> = operations.iter().collect();
+        let dim_text = mem::replace(&mut self.dim_text, false);
+        let result = self.render_all(&operations);
+        self.dim_text = dim_text;
+        result
+    }
+
+    fn render_region(&mut self, painter: Rc<dyn PaintRegion>, dim: bool) -> RenderResult {
+        let rect = self.current_rect();
+        let (column, width) = (rect.start_column, rect.dimensions.columns);
+        let row = self.terminal.cursor_row;
+        let lines = painter.region_lines(width);
+        if lines.is_empty() {
+            return Ok(());
+        }
+        // Anything that doesn't fit in the window is cut off but still counts as an overflow.
+        let height = lines.len() as u16;
+        let region = Region {
+            name: painter.region_name(),
+            column,
+            row,
+            width,
+            height: height.min(self.last_row.saturating_add(1).saturating_sub(row)).max(1),
+        };
+        let lines = region.fit(lines);
+        let colors = self.text_colors();
+        let positioning = Positioning { max_line_length: width, start_column: column };
+        self.terminal.set_colors(colors.clone())?;
+        for (index, line) in lines.iter().enumerate() {
+            let line = WeightedTextBlock::from(line.clone());
+            let mut drawer = TextDrawer::positioned(&line, positioning.clone(), &colors)?;
+            if dim {
+                drawer = drawer.dim();
+            }
+            self.terminal.move_to(column, row + index as u16)?;
+            drawer.draw(self.terminal)?;
+        }
+        self.apply_colors()?;
+        self.max_modified_row = self.max_modified_row.max(row.saturating_add(height - 1));
+        if let Some(regions) = &mut self.regions {
+            regions.push(SlideRegion { region, colors, painter, lines, rows: height as usize });
         }
         Ok(())
     }
@@ -270,14 +511,11 @@ where
                 (columns, start_row)
             }
         };
-        let total_column_units: u16 = columns.iter().sum();
-        let column_units_before: u16 = columns.iter().take(column_index).sum();
         let current_rect = self.current_rect();
-        let unit_width = current_rect.dimensions.columns as f64 / total_column_units as f64;
-        let start_column = current_rect.start_column + (unit_width * column_units_before as f64) as u16;
-        let new_column_count = (total_column_units - columns[column_index]) * unit_width as u16;
-        let new_size = current_rect.dimensions.shrink_columns(new_column_count);
-        let mut dimensions = WindowRect { dimensions: new_size, start_column };
+        let (offset, width) = column_bounds(&columns, column_index, current_rect.dimensions.columns);
+        let start_column = current_rect.start_column + offset;
+        let new_size = current_rect.dimensions.shrink_columns(current_rect.dimensions.columns - width);
+        let mut dimensions = WindowRect { dimensions: new_size, start_column, start_row: current_rect.start_row };
         // Shrink every column's right edge except for last
         if column_index < columns.len() - 1 {
             dimensions = dimensions.shrink_right(4);
@@ -310,7 +548,39 @@ where
     }
 }
 
-#[derive(Default)]
+// A writer that discards everything, used to measure how operations are laid out.
+struct MeasureWriter;
+
+impl io::Write for MeasureWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl TerminalWrite for MeasureWriter {
+    fn init(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn deinit(&mut self) {}
+}
+
+/// Get the offset and width of a column in a layout, splitting the total width based on the weight of
+/// every column.
+fn column_bounds(weights: &[u16], index: usize, total_width: u16) -> (u16, u16) {
+    let total_weight: u32 = weights.iter().copied().map(u32::from).sum();
+    let weight_before: u32 = weights.iter().take(index).copied().map(u32::from).sum();
+    let edge = |weight: u32| (weight * u32::from(total_width) / total_weight.max(1)) as u16;
+    let start = edge(weight_before);
+    let end = edge(weight_before + u32::from(weights[index]));
+    (start, end - start)
+}
+
+#[derive(Clone, Default)]
 enum LayoutState {
     #[default]
     Default,
@@ -324,32 +594,345 @@ enum LayoutState {
     },
 }
 
+// A background gradient that was painted, along with the background color that was set back then.
+struct ActiveGradient {
+    gradient: BackgroundGradient,
+    background: Option<Color>,
+}
+
 #[derive(Clone, Debug)]
 struct WindowRect {
     dimensions: WindowSize,
     start_column: u16,
+    start_row: u16,
 }
 
 impl WindowRect {
     fn apply_margin(&self, margin: u16) -> Self {
+        // A margin can't push the window past its own right edge.
+        let margin = margin.min(self.dimensions.columns / 2);
         let dimensions = self.dimensions.shrink_columns(margin.saturating_mul(2));
-        let start_column = self.start_column + margin;
-        Self { dimensions, start_column }
+        let start_column = self.start_column.saturating_add(margin);
+        Self { dimensions, start_column, start_row: self.start_row }
+    }
+
+    fn apply_vertical_margin(&self, margin: u16) -> Self {
+        let margin = margin.min(self.dimensions.rows / 2);
+        let dimensions = self.dimensions.shrink_rows(margin.saturating_mul(2));
+        let start_row = self.start_row.saturating_add(margin);
+        Self { dimensions, start_column: self.start_column, start_row }
     }
 
     fn shrink_left(&self, size: u16) -> Self {
         let dimensions = self.dimensions.shrink_columns(size);
         let start_column = self.start_column.saturating_add(size);
-        Self { dimensions, start_column }
+        Self { dimensions, start_column, start_row: self.start_row }
     }
 
     fn shrink_right(&self, size: u16) -> Self {
         let dimensions = self.dimensions.shrink_columns(size);
-        Self { dimensions, start_column: self.start_column }
+        Self { dimensions, start_column: self.start_column, start_row: self.start_row }
     }
 
     fn shrink_rows(&self, rows: u16) -> Self {
         let dimensions = self.dimensions.shrink_rows(rows);
-        Self { dimensions, start_column: self.start_column }
+        Self { dimensions, start_column: self.start_column, start_row: self.start_row }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        markdown::elements::Text, media::printer::ImagePrinter, presentation::RenderOnDemandState,
+        render::terminal::TestWriter, theme::Margin,
+    };
+    use rstest::rstest;
+    use std::{rc::Rc, thread, time::Duration};
+
+    const DIM: &str = "\x1b[2m";
+
+    #[derive(Debug)]
+    struct CodeOutput;
+
+    impl AsRenderOperations for CodeOutput {
+        fn as_render_operations(&self, _: &WindowSize) -> Vec<RenderOperation> {
+            vec![render_text("output")]
+        }
+
+        fn diffable_content(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    impl RenderOnDemand for CodeOutput {
+        fn start_render(&self) -> bool {
+            false
+        }
+
+        fn poll_state(&self) -> RenderOnDemandState {
+            RenderOnDemandState::Rendered
+        }
+    }
+
+    fn render_text(text: &str) -> RenderOperation {
+        let alignment = Alignment::Left { margin: Margin::Fixed(0) };
+        RenderOperation::RenderText { line: vec![Text::from(text)].into(), alignment }
+    }
+
+    fn render(operation: RenderOperation, dim_text: bool) -> String {
+        let output = TestWriter::default();
+        let mut terminal = Terminal::new(output.clone(), Rc::new(ImagePrinter::Null)).expect("creating terminal");
+        let dimensions = WindowSize { rows: 10, columns: 40, width: 0, height: 0 };
+        let options = RenderEngineOptions { dim_text, ..Default::default() };
+        RenderEngine::new(&mut terminal, dimensions, options).render([operation].iter()).expect("render failed");
+        output.contents()
+    }
+
+    #[test]
+    fn dim_text() {
+        assert!(render(render_text("hi"), true).contains(&format!("{DIM}hi")));
+        assert!(!render(render_text("hi"), false).contains(DIM));
+    }
+
+    #[test]
+    fn code_output_not_dimmed() {
+        let output = render(RenderOperation::RenderOnDemand(Rc::new(CodeOutput)), true);
+        assert!(output.contains("output"));
+        assert!(!output.contains(DIM), "{output:?}");
+    }
+
+    fn render_in_safe_area(safe_area: SafeAreaMargin, origin: CursorPosition) -> Result<String, RenderError> {
+        let output = TestWriter::default();
+        let mut terminal = Terminal::new(output.clone(), Rc::new(ImagePrinter::Null)).expect("creating terminal");
+        let dimensions = WindowSize { rows: 10, columns: 40, width: 0, height: 0 };
+        let alignment = Alignment::Right { margin: Margin::Fixed(0) };
+        let operations = [
+            RenderOperation::ClearScreen,
+            RenderOperation::RenderText { line: vec![Text::from("hi")].into(), alignment },
+        ];
+        let options = RenderEngineOptions { safe_area, origin, ..Default::default() };
+        RenderEngine::new(&mut terminal, dimensions, options).render(operations.iter())?;
+        Ok(output.contents())
+    }
+
+    #[rstest]
+    #[case::none(SafeAreaMargin { rows: 0, columns: 0 }, "\x1b[1;1H", "\x1b[39Ghi")]
+    // The text is moved down and right, and it's right aligned within the narrower window.
+    #[case::margin(SafeAreaMargin { rows: 2, columns: 3 }, "\x1b[3;1H", "\x1b[36Ghi")]
+    fn safe_area_margin(#[case] margin: SafeAreaMargin, #[case] start: &str, #[case] text: &str) {
+        let output = render_in_safe_area(margin, CursorPosition::default()).expect("render failed");
+        assert!(output.contains(&format!("{start}{text}")), "{output:?}");
+    }
+
+    #[test]
+    fn safe_area_margin_overflow() {
+        let margin = SafeAreaMargin { rows: u16::MAX, columns: u16::MAX };
+        // This leaves no room for anything rather than overflowing.
+        let result = render_in_safe_area(margin, CursorPosition { row: 5, column: 5 });
+        assert!(matches!(result, Err(RenderError::TerminalTooSmall)), "{result:?}");
+    }
+
+    // An element that takes a while to lay out.
+    #[derive(Debug)]
+    struct SlowElement(Duration);
+
+    impl AsRenderOperations for SlowElement {
+        fn as_render_operations(&self, _: &WindowSize) -> Vec<RenderOperation> {
+            thread::sleep(self.0);
+            Vec::new()
+        }
+
+        fn diffable_content(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    #[test]
+    fn profile() {
+        let mut terminal =
+            Terminal::new(TestWriter::default(), Rc::new(ImagePrinter::Null)).expect("creating terminal");
+        let dimensions = WindowSize { rows: 10, columns: 40, width: 0, height: 0 };
+        let delay = Duration::from_millis(20);
+        let operations = [
+            render_text("hi"),
+            RenderOperation::RenderDynamic(Rc::new(SlowElement(delay))),
+            RenderOperation::RenderLineBreak,
+        ];
+        let mut profile = FrameProfile::default();
+        RenderEngine::new(&mut terminal, dimensions, Default::default())
+            .profile(Some(&mut profile))
+            .render(operations.iter())
+            .expect("render failed");
+
+        assert_eq!(profile.operations.len(), operations.len());
+        let slowest = profile.slowest(1)[0];
+        assert_eq!((slowest.index, slowest.operation), (1, "dynamic"));
+        assert!(slowest.elapsed >= delay);
+        assert!(profile.layout >= delay);
+    }
+
+    #[rstest]
+    #[case::within_window(3, 3)]
+    #[case::last_row(9, 9)]
+    #[case::past_window(15, 9)]
+    fn line_breaks_stop_at_bottom(#[case] line_breaks: usize, #[case] expected_row: u16) {
+        let mut terminal =
+            Terminal::new(TestWriter::default(), Rc::new(ImagePrinter::Null)).expect("creating terminal");
+        let dimensions = WindowSize { rows: 10, columns: 40, width: 0, height: 0 };
+        let operations = vec![RenderOperation::RenderLineBreak; line_breaks];
+        RenderEngine::new(&mut terminal, dimensions, Default::default())
+            .render(operations.iter())
+            .expect("render failed");
+        assert_eq!(terminal.cursor_row, expected_row);
+    }
+
+    #[test]
+    fn clamped_line_breaks_overflow() {
+        let mut terminal =
+            Terminal::new(TestWriter::default(), Rc::new(ImagePrinter::Null)).expect("creating terminal");
+        let dimensions = WindowSize { rows: 10, columns: 40, width: 0, height: 0 };
+        let options = RenderEngineOptions { validate_overflows: true, ..Default::default() };
+        let operations = vec![RenderOperation::RenderLineBreak; 15];
+        let result = RenderEngine::new(&mut terminal, dimensions, options).render(operations.iter());
+        assert!(matches!(result, Err(RenderError::VerticalOverflow)));
+    }
+
+    fn render_at(operations: &[RenderOperation], dimensions: WindowSize) -> Terminal<TestWriter> {
+        let mut terminal =
+            Terminal::new(TestWriter::default(), Rc::new(ImagePrinter::Null)).expect("creating terminal");
+        RenderEngine::new(&mut terminal, dimensions, Default::default())
+            .render(operations.iter())
+            .expect("render failed");
+        terminal
+    }
+
+    #[rstest]
+    #[case::single_line(1, 11)]
+    #[case::many_lines(5, 9)]
+    #[case::taller_than_window(30, 0)]
+    fn vertical_centering(#[case] lines: usize, #[case] expected_start_row: u16) {
+        let mut operations = vec![RenderOperation::JumpToVerticalCenter { operations: None }, render_text("hi")];
+        for _ in 1..lines {
+            operations.extend([RenderOperation::RenderLineBreak, render_text("hi")]);
+        }
+        let dimensions = WindowSize { rows: 24, columns: 80, width: 0, height: 0 };
+        let terminal = render_at(&operations, dimensions.clone());
+        let expected_last_row = (expected_start_row + lines as u16 - 1).min(23);
+        assert_eq!(terminal.cursor_row, expected_last_row);
+
+        // Trailing line breaks are never drawn and the footer is positioned on its own, so neither
+        // of them should change where the contents start.
+        operations.extend([RenderOperation::RenderLineBreak, RenderOperation::JumpToBottomRow { index: 0 }]);
+        operations.push(render_text("footer"));
+        let terminal = render_at(&operations, dimensions);
+        assert_eq!(terminal.cursor_row, 23);
+    }
+
+    #[derive(Debug)]
+    struct Footer;
+
+    impl AsRenderOperations for Footer {
+        fn as_render_operations(&self, _: &WindowSize) -> Vec<RenderOperation> {
+            vec![RenderOperation::JumpToBottomRow { index: 0 }, render_text("footer")]
+        }
+
+        fn diffable_content(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    #[rstest]
+    #[case::bounded(Some(1), 11)]
+    #[case::unbounded(None, 0)]
+    fn vertical_centering_with_footer(#[case] measured: Option<usize>, #[case] expected_row: u16) {
+        let operations = [
+            RenderOperation::JumpToVerticalCenter { operations: measured },
+            render_text("hi"),
+            RenderOperation::RenderDynamic(Rc::new(Footer)),
+        ];
+        let output = TestWriter::default();
+        let mut terminal = Terminal::new(output.clone(), Rc::new(ImagePrinter::Null)).expect("creating terminal");
+        let dimensions = WindowSize { rows: 24, columns: 80, width: 0, height: 0 };
+        RenderEngine::new(&mut terminal, dimensions, Default::default())
+            .render(operations.iter())
+            .expect("render failed");
+        let output = output.contents();
+        let expected = format!("\x1b[{}d\x1b[1Ghi", expected_row + 1);
+        assert!(output.contains(&expected), "{output:?}");
+    }
+
+    #[test]
+    fn vertical_centering_columns() {
+        let operations = [
+            RenderOperation::JumpToVerticalCenter { operations: None },
+            RenderOperation::InitColumnLayout { columns: vec![1, 1] },
+            RenderOperation::EnterColumn { column: 0 },
+            render_text("a"),
+            RenderOperation::RenderLineBreak,
+            render_text("b"),
+            RenderOperation::RenderLineBreak,
+            render_text("c"),
+            RenderOperation::EnterColumn { column: 1 },
+            render_text("d"),
+            RenderOperation::ExitLayout,
+        ];
+        let dimensions = WindowSize { rows: 24, columns: 80, width: 0, height: 0 };
+        let terminal = render_at(&operations, dimensions);
+        // The tallest column takes up 3 rows, so it goes from row 10 to 12.
+        assert_eq!(terminal.cursor_row, 12);
+    }
+
+    fn render_gradient(truecolor: bool) -> String {
+        let gradient = BackgroundGradient::new(Color::new(0, 0, 0), Color::new(90, 90, 90)).expect("not rgb");
+        let operations = [
+            RenderOperation::SetColors(Colors { foreground: None, background: Some(Color::new(1, 1, 1)) }),
+            RenderOperation::ClearScreen,
+            RenderOperation::RenderBackgroundGradient(gradient),
+            RenderOperation::JumpToRow { index: 5 },
+            render_text("hi"),
+        ];
+        let output = TestWriter::default();
+        let mut terminal = Terminal::new(output.clone(), Rc::new(ImagePrinter::Null)).expect("creating terminal");
+        let dimensions = WindowSize { rows: 10, columns: 40, width: 0, height: 0 };
+        let options = RenderEngineOptions { truecolor, ..Default::default() };
+        RenderEngine::new(&mut terminal, dimensions, options).render(operations.iter()).expect("render failed");
+        output.contents()
+    }
+
+    // Get the last background color set before some text was printed.
+    fn background_before(output: &str, text: &str) -> String {
+        let output = &output[..output.find(text).expect("text not found")];
+        let start = output.rfind("48;2;").expect("no background set");
+        output[start..].split('m').next().unwrap().to_string()
+    }
+
+    #[test]
+    fn background_gradient() {
+        let output = render_gradient(true);
+        assert_eq!(output.matches(&HALF_BLOCK.repeat(40)).count(), 10);
+        assert!(output.contains("48;2;0;0;0"), "{output:?}");
+        assert!(output.contains("38;2;90;90;90"), "{output:?}");
+
+        // Text blends with the row it's drawn at, which is a bit past the middle of the gradient.
+        assert_eq!(background_before(&output, "hi"), "48;2;50;50;50");
+    }
+
+    #[test]
+    fn background_gradient_fallback() {
+        let output = render_gradient(false);
+        assert!(!output.contains(HALF_BLOCK), "{output:?}");
+        assert!(!output.contains("38;2;90;90;90"), "{output:?}");
+        assert_eq!(background_before(&output, "hi"), "48;2;0;0;0");
+    }
+
+    #[rstest]
+    #[case::equal(&[1, 1], 100, &[(0, 50), (50, 50)])]
+    #[case::weighted(&[1, 1, 2], 100, &[(0, 25), (25, 25), (50, 50)])]
+    #[case::uneven(&[1, 1, 1], 100, &[(0, 33), (33, 33), (66, 34)])]
+    #[case::single(&[3], 80, &[(0, 80)])]
+    fn column_widths(#[case] weights: &[u16], #[case] width: u16, #[case] expected: &[(u16, u16)]) {
+        let bounds: Vec<_> = (0..weights.len()).map(|index| column_bounds(weights, index, width)).collect();
+        assert_eq!(bounds, expected);
     }
 }
@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+/// Limits how often the screen is redrawn.
+///
+/// Frames that would be drawn too soon after the previous one are skipped, but the last one that
+/// was skipped is kept pending so the screen always ends up showing the latest state.
+#[derive(Debug)]
+pub(crate) struct FrameLimiter {
+    interval: Duration,
+    last_frame: Option<Instant>,
+    pending: bool,
+}
+
+impl FrameLimiter {
+    /// Construct a limiter that allows up to `fps` frames per second.
+    pub(crate) fn new(fps: u16) -> Self {
+        let interval = Duration::from_secs(1) / fps.max(1) as u32;
+        Self { interval, last_frame: None, pending: false }
+    }
+
+    /// Try to start a frame at the given time.
+    ///
+    /// If it's too soon after the last one, this returns false and the frame is marked as pending.
+    pub(crate) fn try_frame(&mut self, now: Instant) -> bool {
+        let ready =
+            self.last_frame.map_or(true, |last_frame| now.saturating_duration_since(last_frame) >= self.interval);
+        if ready {
+            self.last_frame = Some(now);
+        }
+        self.pending = !ready;
+        ready
+    }
+
+    /// How long until a pending frame can be drawn, if there's one.
+    pub(crate) fn pending_delay(&self, now: Instant) -> Option<Duration> {
+        if !self.pending {
+            return None;
+        }
+        let next_frame = self.last_frame? + self.interval;
+        Some(next_frame.saturating_duration_since(now))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn skips_frames() {
+        let mut limiter = FrameLimiter::new(10);
+        let start = Instant::now();
+        assert!(limiter.try_frame(start));
+        assert_eq!(limiter.pending_delay(start), None);
+
+        let now = start + Duration::from_millis(40);
+        assert!(!limiter.try_frame(now));
+        assert_eq!(limiter.pending_delay(now), Some(Duration::from_millis(60)));
+        assert_eq!(limiter.pending_delay(start + Duration::from_millis(150)), Some(Duration::ZERO));
+
+        let now = start + Duration::from_millis(100);
+        assert!(limiter.try_frame(now));
+        assert_eq!(limiter.pending_delay(now), None);
+    }
+
+    #[test]
+    fn zero_fps() {
+        let mut limiter = FrameLimiter::new(0);
+        let start = Instant::now();
+        assert!(limiter.try_frame(start));
+        assert!(limiter.try_frame(start + Duration::from_secs(1)));
+    }
+}
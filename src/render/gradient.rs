@@ -0,0 +1,92 @@
+use crate::style::{Color, Colors};
+
+// The character used to paint the gradient, which lets every row display two colors.
+pub(crate) const HALF_BLOCK: &str = "▄";
+
+/// A vertical gradient that fills the background of the screen.
+///
+/// Every row is split in two halves using a half block character: the upper half takes the
+/// character's background color and the lower half its foreground one. This doubles the
+/// gradient's resolution compared to using a single color per row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BackgroundGradient {
+    top: (u8, u8, u8),
+    bottom: (u8, u8, u8),
+}
+
+impl BackgroundGradient {
+    /// Construct a gradient between two colors.
+    ///
+    /// Only RGB colors can be interpolated so this returns `None` for any other colors.
+    pub(crate) fn new(top: Color, bottom: Color) -> Option<Self> {
+        Some(Self { top: top.as_rgb()?, bottom: bottom.as_rgb()? })
+    }
+
+    /// Get the color at the top of the gradient.
+    pub(crate) fn top(&self) -> Color {
+        let (r, g, b) = self.top;
+        Color::new(r, g, b)
+    }
+
+    /// Get the colors used to paint the given row in a window with the given number of rows.
+    pub(crate) fn row_colors(&self, row: u16, rows: u16) -> Colors {
+        let half_rows = rows as f64 * 2.0 - 1.0;
+        let upper = self.color_at(row as f64 * 2.0 / half_rows);
+        let lower = self.color_at((row as f64 * 2.0 + 1.0) / half_rows);
+        Colors { foreground: Some(lower), background: Some(upper) }
+    }
+
+    /// Get the color to be used as the background of anything drawn on top of the given row.
+    pub(crate) fn row_background(&self, row: u16, rows: u16) -> Color {
+        let half_rows = rows as f64 * 2.0 - 1.0;
+        self.color_at((row as f64 * 2.0 + 0.5) / half_rows)
+    }
+
+    // Get the color at some point in the gradient, where 0 is the top and 1 the bottom.
+    fn color_at(&self, position: f64) -> Color {
+        let position = if position.is_finite() { position.clamp(0.0, 1.0) } else { 0.0 };
+        let interpolate = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * position).round() as u8;
+        let (top, bottom) = (self.top, self.bottom);
+        Color::new(interpolate(top.0, bottom.0), interpolate(top.1, bottom.1), interpolate(top.2, bottom.2))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gradient() -> BackgroundGradient {
+        BackgroundGradient::new(Color::new(0, 0, 0), Color::new(30, 60, 90)).expect("not rgb")
+    }
+
+    #[test]
+    fn edges() {
+        let gradient = gradient();
+        let first = gradient.row_colors(0, 4);
+        let last = gradient.row_colors(3, 4);
+        assert_eq!(first.background, Some(Color::new(0, 0, 0)));
+        assert_eq!(last.foreground, Some(Color::new(30, 60, 90)));
+    }
+
+    #[test]
+    fn interpolation() {
+        // 2 rows are 4 halves, so each one is a third of the way further.
+        let colors = gradient().row_colors(0, 2);
+        assert_eq!(colors.foreground, Some(Color::new(10, 20, 30)));
+        let colors = gradient().row_colors(1, 2);
+        assert_eq!(colors.background, Some(Color::new(20, 40, 60)));
+    }
+
+    #[test]
+    fn single_row() {
+        let colors = gradient().row_colors(0, 1);
+        assert_eq!(colors.background, Some(Color::new(0, 0, 0)));
+        assert_eq!(colors.foreground, Some(Color::new(30, 60, 90)));
+    }
+
+    #[test]
+    fn non_rgb() {
+        let red = "red".parse().expect("invalid color");
+        assert!(BackgroundGradient::new(red, Color::new(0, 0, 0)).is_none());
+    }
+}
@@ -1,8 +1,5 @@
-use crate::{markdown::elements::CodeLanguage, theme::CodeBlockStyle};
-use crossterm::{
-    style::{SetBackgroundColor, SetForegroundColor},
-    QueueableCommand,
-};
+use crate::{markdown::elements::CodeLanguage, render::terminal::SetColors, theme::CodeBlockStyle};
+use crossterm::{style::Colors, QueueableCommand};
 use flate2::read::ZlibDecoder;
 use once_cell::sync::Lazy;
 use serde::Deserialize;
@@ -97,7 +94,7 @@ pub struct CodeHighlighter {
 
 impl CodeHighlighter {
     /// Create a highlighter for a specific language.
-    pub(crate) fn language_highlighter(&self, language: &CodeLanguage) -> LanguageHighlighter {
+    pub(crate) fn language_highlighter(&self, language: &CodeLanguage) -> LanguageHighlighter<'_> {
         let extension = Self::language_extension(language);
         let syntax = SYNTAX_SET.find_syntax_by_extension(extension).unwrap();
         let highlighter = HighlightLines::new(syntax, &self.theme);
@@ -137,6 +134,7 @@ impl CodeHighlighter {
             Lua => "lua",
             Makefile => "make",
             Markdown => "md",
+            Mermaid => "txt",
             Nix => "nix",
             OCaml => "ml",
             Perl => "pl",
@@ -207,12 +205,7 @@ impl<'a> StyledTokens<'a> {
         // Note: these unwraps shouldn't happen as this is an in-memory writer so there's no
         // fallible IO here.
         let mut cursor = io::BufWriter::new(Vec::new());
-        if let Some(color) = background {
-            cursor.queue(SetBackgroundColor(color)).unwrap();
-        }
-        if let Some(color) = foreground {
-            cursor.queue(SetForegroundColor(color)).unwrap();
-        }
+        cursor.queue(SetColors(Colors { foreground, background })).unwrap();
         // syntect likes its input to contain \n but we don't want them as we pad text with extra
         // " " at the end so we get rid of them here.
         for chunk in self.tokens.split('\n') {
@@ -224,11 +217,6 @@ impl<'a> StyledTokens<'a> {
     }
 }
 
-/// A theme could not be found.
-#[derive(Debug, thiserror::Error)]
-#[error("theme not found")]
-pub struct ThemeNotFound;
-
 // This code has been adapted from bat's: https://github.com/sharkdp/bat
 fn to_ansi_color(color: syntect::highlighting::Color) -> Option<crossterm::style::Color> {
     use crossterm::style::Color;
@@ -1,8 +1,14 @@
+pub(crate) mod background;
+pub(crate) mod capabilities;
 pub(crate) mod draw;
 pub(crate) mod engine;
+pub(crate) mod frames;
+pub(crate) mod gradient;
 pub(crate) mod highlighting;
 pub(crate) mod layout;
+pub(crate) mod profile;
 pub(crate) mod properties;
+pub(crate) mod region;
 pub(crate) mod terminal;
 pub(crate) mod text;
 pub(crate) mod validate;
@@ -0,0 +1,71 @@
+use crate::presentation::RenderOperation;
+use std::{cmp::Reverse, time::Duration};
+
+/// How long rendering a frame took, broken down by phase and by operation.
+///
+/// This is only collected when asked for so rendering doesn't pay for it otherwise.
+#[derive(Debug, Default)]
+pub(crate) struct FrameProfile {
+    /// The time spent laying out operations and turning them into terminal commands.
+    pub(crate) layout: Duration,
+
+    /// The time spent writing those commands to the terminal.
+    pub(crate) paint: Duration,
+
+    /// The time every operation took to lay out, in the order they were rendered in.
+    pub(crate) operations: Vec<OperationTiming>,
+}
+
+impl FrameProfile {
+    /// The total time spent rendering the frame.
+    pub(crate) fn total(&self) -> Duration {
+        self.layout + self.paint
+    }
+
+    /// Record how long an operation took to lay out.
+    pub(crate) fn record(&mut self, index: usize, operation: &RenderOperation, elapsed: Duration) {
+        self.operations.push(OperationTiming { index, operation: operation_name(operation), elapsed });
+    }
+
+    /// Get the operations that took the longest, slowest first.
+    pub(crate) fn slowest(&self, count: usize) -> Vec<&OperationTiming> {
+        let mut operations: Vec<_> = self.operations.iter().collect();
+        operations.sort_by_key(|timing| Reverse(timing.elapsed));
+        operations.truncate(count);
+        operations
+    }
+}
+
+/// The time an operation took to lay out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct OperationTiming {
+    /// The index of the operation within the ones rendered along with it.
+    pub(crate) index: usize,
+
+    /// The kind of operation.
+    pub(crate) operation: &'static str,
+
+    pub(crate) elapsed: Duration,
+}
+
+fn operation_name(operation: &RenderOperation) -> &'static str {
+    match operation {
+        RenderOperation::ClearScreen => "clear_screen",
+        RenderOperation::SetColors(_) => "set_colors",
+        RenderOperation::RenderBackgroundGradient(_) => "background_gradient",
+        RenderOperation::JumpToVerticalCenter { .. } => "jump_to_vertical_center",
+        RenderOperation::JumpToRow { .. } => "jump_to_row",
+        RenderOperation::JumpToBottomRow { .. } => "jump_to_bottom_row",
+        RenderOperation::RenderText { .. } => "text",
+        RenderOperation::RenderLineBreak => "line_break",
+        RenderOperation::RenderImage(..) => "image",
+        RenderOperation::RenderPreformattedLine(_) => "preformatted_line",
+        RenderOperation::RenderDynamic(_) => "dynamic",
+        RenderOperation::RenderOnDemand(_) => "on_demand",
+        RenderOperation::InitColumnLayout { .. } => "column_layout",
+        RenderOperation::EnterColumn { .. } => "enter_column",
+        RenderOperation::ExitLayout => "exit_layout",
+        RenderOperation::ApplyMargin(_) => "apply_margin",
+        RenderOperation::PopMargin => "pop_margin",
+    }
+}
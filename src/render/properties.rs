@@ -1,11 +1,21 @@
+use super::capabilities::CellSizePolicy;
 use crossterm::terminal;
-use std::io::{self, ErrorKind};
+use serde::Serialize;
+use std::{
+    fmt,
+    io::{self, ErrorKind},
+    str::FromStr,
+};
+
+// The smallest and largest number of columns and rows presentations can be laid out in.
+const MINIMUM_DIMENSIONS: (u16, u16) = (20, 5);
+const MAXIMUM_DIMENSIONS: (u16, u16) = (1000, 500);
 
 /// The size of the terminal window.
 ///
 /// This is the same as [crossterm::terminal::window_size] except with some added functionality,
 /// like implementing `Clone`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct WindowSize {
     pub(crate) rows: u16,
     pub(crate) columns: u16,
@@ -15,7 +25,10 @@ pub(crate) struct WindowSize {
 
 impl WindowSize {
     /// Get the current window size.
-    pub(crate) fn current(font_size_fallback: u8) -> io::Result<Self> {
+    ///
+    /// The size in pixels comes from the terminal unless the cell size policy says otherwise or the
+    /// terminal doesn't report it.
+    pub(crate) fn current(cell_size: CellSizePolicy) -> io::Result<Self> {
         let mut size: Self = match terminal::window_size() {
             Ok(size) => size.into(),
             Err(e) if e.kind() == ErrorKind::Unsupported => {
@@ -25,16 +38,27 @@ impl WindowSize {
             }
             Err(e) => return Err(e),
         };
-        let font_size_fallback = font_size_fallback as u16;
-        if size.width == 0 {
-            size.width = size.columns * font_size_fallback;
-        }
-        if size.height == 0 {
-            size.height = size.rows * font_size_fallback * 2;
-        }
+        size.apply_cell_size(cell_size);
         Ok(size)
     }
 
+    fn apply_cell_size(&mut self, policy: CellSizePolicy) {
+        match policy {
+            CellSizePolicy::Fixed(cell_size) => {
+                self.width = self.columns.saturating_mul(cell_size.width);
+                self.height = self.rows.saturating_mul(cell_size.height);
+            }
+            CellSizePolicy::Detect { fallback } => {
+                if self.width == 0 {
+                    self.width = self.columns.saturating_mul(fallback.width);
+                }
+                if self.height == 0 {
+                    self.height = self.rows.saturating_mul(fallback.height);
+                }
+            }
+        };
+    }
+
     /// Shrink a window by the given number of rows.
     ///
     /// This preserves the relationship between rows and pixels.
@@ -86,6 +110,82 @@ impl From<(u16, u16)> for WindowSize {
     }
 }
 
+/// The dimensions presentations are laid out in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum RenderDimensions {
+    /// Use the terminal's size.
+    #[default]
+    Terminal,
+
+    /// Use a fixed number of columns and rows, regardless of the terminal's size.
+    Fixed { columns: u16, rows: u16 },
+}
+
+impl RenderDimensions {
+    /// The dimensions used when there's no terminal to get them from.
+    pub(crate) const FALLBACK: Self = Self::Fixed { columns: 80, rows: 24 };
+
+    /// Get the window size to lay out presentations in.
+    ///
+    /// For fixed dimensions, the size in pixels is derived from the cell size policy so that
+    /// images are scaled using the configured cell aspect.
+    pub(crate) fn window_size(&self, cell_size: CellSizePolicy) -> io::Result<WindowSize> {
+        match self {
+            Self::Terminal => WindowSize::current(cell_size),
+            Self::Fixed { columns, rows } => {
+                let mut size = WindowSize::from((*columns, *rows));
+                size.apply_cell_size(cell_size);
+                Ok(size)
+            }
+        }
+    }
+
+    /// Check whether these dimensions come from the terminal.
+    pub(crate) fn is_terminal(&self) -> bool {
+        matches!(self, Self::Terminal)
+    }
+}
+
+impl FromStr for RenderDimensions {
+    type Err = InvalidRenderDimensions;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (columns, rows) = s.split_once(['x', 'X']).ok_or(InvalidRenderDimensions::Format)?;
+        let columns: u16 = columns.trim().parse().map_err(|_| InvalidRenderDimensions::Format)?;
+        let rows: u16 = rows.trim().parse().map_err(|_| InvalidRenderDimensions::Format)?;
+        if columns < MINIMUM_DIMENSIONS.0 || rows < MINIMUM_DIMENSIONS.1 {
+            return Err(InvalidRenderDimensions::TooSmall);
+        }
+        if columns > MAXIMUM_DIMENSIONS.0 || rows > MAXIMUM_DIMENSIONS.1 {
+            return Err(InvalidRenderDimensions::TooLarge);
+        }
+        Ok(Self::Fixed { columns, rows })
+    }
+}
+
+impl fmt::Display for RenderDimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Terminal => write!(f, "terminal"),
+            Self::Fixed { columns, rows } => write!(f, "{columns}x{rows}"),
+        }
+    }
+}
+
+/// An error when parsing render dimensions.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum InvalidRenderDimensions {
+    #[error("dimensions must be specified as <columns>x<rows>, e.g. 100x30")]
+    Format,
+
+    #[error("dimensions can't be smaller than {}x{}", MINIMUM_DIMENSIONS.0, MINIMUM_DIMENSIONS.1)]
+    TooSmall,
+
+    #[error("dimensions can't be larger than {}x{}", MAXIMUM_DIMENSIONS.0, MAXIMUM_DIMENSIONS.1)]
+    TooLarge,
+}
+
 /// The cursor's position.
 #[derive(Debug, Clone, Default)]
 pub(crate) struct CursorPosition {
@@ -96,6 +196,8 @@ pub(crate) struct CursorPosition {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::render::capabilities::CellSize;
+    use rstest::rstest;
 
     #[test]
     fn shrink() {
@@ -111,4 +213,53 @@ mod test {
         assert_eq!(new_dimensions.columns, 7);
         assert_eq!(new_dimensions.width, 140);
     }
+
+    #[test]
+    fn cell_size() {
+        let reported = WindowSize { rows: 10, columns: 10, width: 200, height: 100 };
+        let unreported = WindowSize { rows: 10, columns: 10, width: 0, height: 0 };
+        let cell_size = CellSize { width: 9, height: 20 };
+
+        let mut size = reported.clone();
+        size.apply_cell_size(CellSizePolicy::Detect { fallback: cell_size });
+        assert_eq!(size, reported);
+
+        let mut size = unreported.clone();
+        size.apply_cell_size(CellSizePolicy::Detect { fallback: cell_size });
+        assert_eq!((size.width, size.height), (90, 200));
+
+        // A fixed cell size overrides whatever the terminal reports.
+        let mut size = reported.clone();
+        size.apply_cell_size(CellSizePolicy::Fixed(cell_size));
+        assert_eq!((size.width, size.height), (90, 200));
+    }
+
+    #[rstest]
+    #[case::lowercase("100x30", 100, 30)]
+    #[case::uppercase("80X24", 80, 24)]
+    #[case::minimum("20x5", 20, 5)]
+    fn parse_dimensions(#[case] input: &str, #[case] columns: u16, #[case] rows: u16) {
+        let dimensions: RenderDimensions = input.parse().expect("parse failed");
+        assert_eq!(dimensions, RenderDimensions::Fixed { columns, rows });
+        assert_eq!(dimensions.to_string(), format!("{columns}x{rows}"));
+    }
+
+    #[rstest]
+    #[case::no_separator("100", InvalidRenderDimensions::Format)]
+    #[case::not_a_number("ax30", InvalidRenderDimensions::Format)]
+    #[case::negative("-100x30", InvalidRenderDimensions::Format)]
+    #[case::too_narrow("19x30", InvalidRenderDimensions::TooSmall)]
+    #[case::too_short("100x4", InvalidRenderDimensions::TooSmall)]
+    #[case::too_wide("1001x30", InvalidRenderDimensions::TooLarge)]
+    fn invalid_dimensions(#[case] input: &str, #[case] expected: InvalidRenderDimensions) {
+        assert_eq!(input.parse::<RenderDimensions>(), Err(expected));
+    }
+
+    #[test]
+    fn fixed_window_size() {
+        let dimensions = RenderDimensions::Fixed { columns: 100, rows: 30 };
+        let cell_size = CellSize { width: 9, height: 20 };
+        let size = dimensions.window_size(CellSizePolicy::Detect { fallback: cell_size }).expect("no size");
+        assert_eq!(size, WindowSize { columns: 100, rows: 30, width: 900, height: 600 });
+    }
 }
@@ -0,0 +1,155 @@
+use crate::{
+    markdown::{
+        elements::{Text, TextBlock},
+        text::truncate_styled,
+    },
+    style::Colors,
+};
+use std::{collections::HashMap, fmt::Debug, rc::Rc};
+
+/// A rectangle in the terminal owned by a single UI component, like the status line.
+///
+/// Regions are painted independently of the slide behind them and of each other, so a component
+/// that changes often can repaint itself without the rest of the screen being redrawn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Region {
+    /// The name of the component that owns this region.
+    pub(crate) name: &'static str,
+
+    /// The column the region starts at.
+    pub(crate) column: u16,
+
+    /// The row the region starts at.
+    pub(crate) row: u16,
+
+    /// The number of columns in the region.
+    pub(crate) width: u16,
+
+    /// The number of rows in the region.
+    pub(crate) height: u16,
+}
+
+impl Region {
+    /// Fit lines into this region.
+    ///
+    /// Lines that don't fit are dropped, long ones are truncated and the rest are padded, so
+    /// whatever is painted covers the entire region and never goes outside of it.
+    pub(crate) fn fit(&self, mut lines: Vec<TextBlock>) -> Vec<TextBlock> {
+        let width = self.width as usize;
+        lines.resize_with(self.height as usize, || TextBlock(Vec::new()));
+        lines
+            .into_iter()
+            .map(|line| {
+                let mut line = truncate_styled(&line, width);
+                let padding = width.saturating_sub(line.width());
+                if padding > 0 {
+                    line.0.push(Text::from(" ".repeat(padding)));
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+/// A slide component that's painted in a region of its own, like the footer or a countdown.
+///
+/// These are laid out along with the rest of the slide, but they can be painted again on their
+/// own afterwards so a countdown ticking only touches the cells it's in.
+pub(crate) trait PaintRegion: Debug {
+    /// The name of the region this component is painted in.
+    fn region_name(&self) -> &'static str;
+
+    /// Get the lines to paint in a region with the given number of columns, one per row.
+    fn region_lines(&self, width: u16) -> Vec<TextBlock>;
+}
+
+/// A region laid out while rendering a slide, along with the component that owns it.
+#[derive(Debug)]
+pub(crate) struct SlideRegion {
+    pub(crate) region: Region,
+    pub(crate) colors: Colors,
+    pub(crate) painter: Rc<dyn PaintRegion>,
+
+    /// The lines painted in the region.
+    pub(crate) lines: Vec<TextBlock>,
+
+    /// The number of rows the component wanted, which can be more than the region has if it
+    /// didn't fit in the screen.
+    pub(crate) rows: usize,
+}
+
+/// The contents last painted in every region.
+///
+/// This is used to only repaint the rows in a region that changed since it was last painted.
+#[derive(Default)]
+pub(crate) struct PaintedRegions {
+    regions: HashMap<&'static str, (Region, Vec<TextBlock>)>,
+}
+
+impl PaintedRegions {
+    /// Record the lines painted in a region and get the indexes of the rows that changed.
+    ///
+    /// Every row changed if the region moved or was resized since it was last painted.
+    pub(crate) fn update(&mut self, region: &Region, lines: &[TextBlock]) -> Vec<usize> {
+        let changed = match self.regions.get(region.name) {
+            Some((previous, previous_lines)) if previous == region => {
+                (0..lines.len()).filter(|index| previous_lines.get(*index) != Some(&lines[*index])).collect()
+            }
+            _ => (0..lines.len()).collect(),
+        };
+        self.regions.insert(region.name, (region.clone(), lines.to_vec()));
+        changed
+    }
+
+    /// Record the lines painted in a region by something else, unless this region was painted since.
+    pub(crate) fn painted_elsewhere(&mut self, region: &Region, lines: &[TextBlock]) {
+        self.regions.entry(region.name).or_insert_with(|| (region.clone(), lines.to_vec()));
+    }
+
+    /// Forget what was painted, which must be done whenever something else draws over regions.
+    pub(crate) fn invalidate(&mut self) {
+        self.regions.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    fn region(width: u16, height: u16) -> Region {
+        Region { name: "test", column: 0, row: 0, width, height }
+    }
+
+    fn contents(lines: &[TextBlock]) -> Vec<String> {
+        lines.iter().map(|line| line.0.iter().map(|chunk| chunk.content.as_str()).collect()).collect()
+    }
+
+    #[rstest]
+    #[case::padded(&["hi"], &["hi   ", "     "])]
+    #[case::truncated(&["hello world"], &["hell…", "     "])]
+    #[case::extra_lines(&["a", "b", "c"], &["a    ", "b    "])]
+    fn fit(#[case] lines: &[&str], #[case] expected: &[&str]) {
+        let lines = lines.iter().map(|line| TextBlock::from(*line)).collect();
+        let lines = region(5, 2).fit(lines);
+        assert_eq!(contents(&lines), expected);
+    }
+
+    #[test]
+    fn changed_rows() {
+        let mut painted = PaintedRegions::default();
+        let region = region(5, 2);
+        let lines = region.fit(vec!["a".into(), "b".into()]);
+        assert_eq!(painted.update(&region, &lines), &[0, 1]);
+        assert_eq!(painted.update(&region, &lines), &[] as &[usize]);
+
+        let lines = region.fit(vec!["a".into(), "c".into()]);
+        assert_eq!(painted.update(&region, &lines), &[1]);
+
+        let moved = Region { row: 1, ..region.clone() };
+        assert_eq!(painted.update(&moved, &lines), &[0, 1]);
+
+        painted.invalidate();
+        assert_eq!(painted.update(&moved, &lines), &[0, 1]);
+    }
+}
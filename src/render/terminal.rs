@@ -7,33 +7,44 @@ use crate::{
 };
 use crossterm::{
     cursor,
-    style::{self, StyledContent},
+    event::DisableFocusChange,
+    style::{self, Colored},
     terminal::{self},
-    QueueableCommand,
+    Command, QueueableCommand,
 };
 use std::{
+    fmt,
     io::{self, Write},
+    panic,
     rc::Rc,
+    thread,
+    time::Duration,
 };
 
+// How many times a write that would block is retried before giving up.
+const WOULD_BLOCK_RETRIES: u32 = 5;
+
 /// A wrapper over the terminal write handle.
 pub(crate) struct Terminal<W>
 where
     W: TerminalWrite,
 {
-    writer: W,
+    writer: GuardedWriter<W>,
     image_printer: Rc<ImagePrinter>,
     pub(crate) cursor_row: u16,
 }
 
 impl<W: TerminalWrite> Terminal<W> {
-    pub(crate) fn new(mut writer: W, image_printer: Rc<ImagePrinter>) -> io::Result<Self> {
+    pub(crate) fn new(writer: W, image_printer: Rc<ImagePrinter>) -> io::Result<Self> {
+        let mut writer = GuardedWriter { inner: writer, disconnected: false };
         writer.init()?;
         Ok(Self { writer, image_printer, cursor_row: 0 })
     }
 
     pub(crate) fn begin_update(&mut self) -> io::Result<()> {
         self.writer.queue(terminal::BeginSynchronizedUpdate)?;
+        // Start every frame from a clean state so nothing left over by the last one leaks into it.
+        self.writer.queue(style::SetAttribute(style::Attribute::Reset))?;
         Ok(())
     }
 
@@ -76,8 +87,24 @@ impl<W: TerminalWrite> Terminal<W> {
         Ok(())
     }
 
-    pub(crate) fn print_styled_line(&mut self, content: StyledContent<String>) -> io::Result<()> {
-        self.writer.queue(style::PrintStyledContent(content))?;
+    pub(crate) fn ring_bell(&mut self) -> io::Result<()> {
+        self.writer.write_all(b"\x07")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Change the colors without resetting any attributes.
+    ///
+    /// Any color that isn't set is changed to the terminal's default one.
+    pub(crate) fn change_colors(&mut self, colors: Colors) -> io::Result<()> {
+        let foreground = colors.foreground.map(Into::into).unwrap_or(style::Color::Reset);
+        let background = colors.background.map(Into::into).unwrap_or(style::Color::Reset);
+        self.writer.queue(SetColors(style::Colors { foreground: Some(foreground), background: Some(background) }))?;
+        Ok(())
+    }
+
+    pub(crate) fn set_attribute(&mut self, attribute: style::Attribute) -> io::Result<()> {
+        self.writer.queue(style::SetAttribute(attribute))?;
         Ok(())
     }
 
@@ -89,7 +116,7 @@ impl<W: TerminalWrite> Terminal<W> {
 
     pub(crate) fn set_colors(&mut self, colors: Colors) -> io::Result<()> {
         self.writer.queue(style::ResetColor)?;
-        self.writer.queue(style::SetColors(colors.into()))?;
+        self.writer.queue(SetColors(colors.into()))?;
         Ok(())
     }
 
@@ -121,6 +148,95 @@ where
     }
 }
 
+/// Checks whether an error means the terminal is gone, e.g. because the pty was closed.
+pub(crate) fn is_disconnection(error: &io::Error) -> bool {
+    #[cfg(unix)]
+    if error.raw_os_error() == Some(libc::EIO) {
+        return true;
+    }
+    matches!(error.kind(), io::ErrorKind::BrokenPipe)
+}
+
+/// A writer that stops touching the terminal as soon as it finds out it's gone.
+///
+/// After a write fails because the terminal was disconnected, every write after it fails right
+/// away so the rest of a partially written escape sequence never makes it out. Writes that fail
+/// because they would block are retried a few times, waiting a bit longer after each attempt,
+/// before giving up.
+struct GuardedWriter<W> {
+    inner: W,
+    disconnected: bool,
+}
+
+impl<W: io::Write> GuardedWriter<W> {
+    fn guard<T>(&mut self, mut operation: impl FnMut(&mut W) -> io::Result<T>) -> io::Result<T> {
+        if self.disconnected {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        let mut result = operation(&mut self.inner);
+        let mut delay = Duration::from_millis(1);
+        for _ in 0..WOULD_BLOCK_RETRIES {
+            if !matches!(&result, Err(e) if e.kind() == io::ErrorKind::WouldBlock) {
+                break;
+            }
+            // Give whoever's reading on the other end some time to catch up.
+            thread::sleep(delay);
+            delay *= 2;
+            result = operation(&mut self.inner);
+        }
+        if let Err(e) = &result {
+            self.disconnected = is_disconnection(e);
+        }
+        result
+    }
+}
+
+impl<W: io::Write> io::Write for GuardedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.guard(|writer| writer.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.guard(|writer| writer.flush())
+    }
+}
+
+impl<W: TerminalWrite> TerminalWrite for GuardedWriter<W> {
+    fn init(&mut self) -> io::Result<()> {
+        self.guard(|writer| writer.init())
+    }
+
+    fn deinit(&mut self) {
+        if self.disconnected {
+            self.inner.abandon();
+        } else {
+            self.inner.deinit();
+        }
+    }
+}
+
+/// Sets the foreground and background colors.
+///
+/// Unlike [style::SetColors], this sets both colors using a single escape sequence so there's
+/// never a point in time where only one of them has been changed.
+pub(crate) struct SetColors(pub(crate) style::Colors);
+
+impl Command for SetColors {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        match (self.0.foreground, self.0.background) {
+            (Some(foreground), Some(background)) => {
+                write!(f, "\x1b[{};{}m", Colored::ForegroundColor(foreground), Colored::BackgroundColor(background))
+            }
+            _ => style::SetColors(self.0).write_ansi(f),
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        style::SetColors(self.0).execute_winapi()
+    }
+}
+
 fn should_hide_cursor() -> bool {
     // WezTerm on Windows fails to display images if we've hidden the cursor so we **always** hide it
     // unless we're on WezTerm on Windows.
@@ -135,9 +251,42 @@ fn is_windows_based_os() -> bool {
     is_windows || is_wsl
 }
 
+/// Install a panic hook that restores the terminal before the panic message is displayed.
+///
+/// Destructors don't run when built with `panic = "abort"` so without this a panic leaves the
+/// terminal in raw mode and within the alternate screen, which also hides the panic message.
+///
+/// This must be called from the main thread, as only panics in it restore the terminal.
+pub fn install_panic_hook() {
+    install_restoring_panic_hook(|| {
+        if terminal::is_raw_mode_enabled().unwrap_or_default() {
+            io::stdout().deinit();
+        }
+    });
+}
+
+// Panics in other threads, like the ones images are loaded in, are handled when those threads are joined and the
+// presentation keeps going, so they must not tear down the terminal.
+fn install_restoring_panic_hook<F>(restore: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    let main_thread = thread::current().id();
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().id() == main_thread {
+            restore();
+        }
+        default_hook(info);
+    }));
+}
+
 pub trait TerminalWrite: io::Write {
     fn init(&mut self) -> io::Result<()>;
     fn deinit(&mut self);
+
+    /// Restore whatever state can be restored without writing anything, as the terminal is gone.
+    fn abandon(&mut self) {}
 }
 
 impl TerminalWrite for io::Stdout {
@@ -151,6 +300,7 @@ impl TerminalWrite for io::Stdout {
     }
 
     fn deinit(&mut self) {
+        let _ = self.queue(DisableFocusChange);
         let _ = self.queue(terminal::LeaveAlternateScreen);
         if should_hide_cursor() {
             let _ = self.queue(cursor::Show);
@@ -158,4 +308,214 @@ impl TerminalWrite for io::Stdout {
         let _ = self.flush();
         let _ = terminal::disable_raw_mode();
     }
+
+    fn abandon(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// A terminal writer for tests that keeps everything written to it.
+///
+/// Every clone shares the same output, so a clone can be handed over to a terminal and the
+/// original used to look at what was written.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct TestWriter {
+    output: Rc<std::cell::RefCell<Vec<u8>>>,
+    flushed: Rc<std::cell::RefCell<Vec<String>>>,
+}
+
+#[cfg(test)]
+impl TestWriter {
+    /// Get everything written so far.
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8(self.output.borrow().clone()).expect("invalid utf8")
+    }
+
+    /// Take everything written so far, leaving the output empty.
+    pub(crate) fn take(&self) -> String {
+        String::from_utf8(self.output.take()).expect("invalid utf8")
+    }
+
+    /// Get what had been written by the time of every flush.
+    pub(crate) fn flushed(&self) -> Vec<String> {
+        self.flushed.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+impl io::Write for TestWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let contents = self.contents();
+        self.flushed.borrow_mut().push(contents);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl TerminalWrite for TestWriter {
+    fn init(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn deinit(&mut self) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use std::{
+        cell::Cell,
+        rc::Rc,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+    use style::Color;
+
+    #[rstest]
+    #[case::both(Some(Color::Red), Some(Color::Rgb { r: 1, g: 2, b: 3 }), "\x1b[38;5;9;48;2;1;2;3m")]
+    #[case::foreground(Some(Color::Red), None, "\x1b[38;5;9m")]
+    #[case::background(None, Some(Color::Red), "\x1b[48;5;9m")]
+    #[case::neither(None, None, "")]
+    fn set_colors(#[case] foreground: Option<Color>, #[case] background: Option<Color>, #[case] expected: &str) {
+        let mut output = String::new();
+        SetColors(style::Colors { foreground, background }).write_ansi(&mut output).expect("write failed");
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn panic_hook_ignores_other_threads() {
+        let restores = Arc::new(AtomicUsize::new(0));
+        install_restoring_panic_hook({
+            let restores = restores.clone();
+            move || {
+                restores.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let worker = thread::spawn(|| panic!("worker panicked"));
+        assert!(worker.join().is_err());
+        assert_eq!(restores.load(Ordering::SeqCst), 0);
+
+        assert!(panic::catch_unwind(|| panic!("main thread panicked")).is_err());
+        assert_eq!(restores.load(Ordering::SeqCst), 1);
+    }
+
+    // Fails a number of writes as if they would block before letting them through.
+    struct Blocking {
+        failures: usize,
+        output: Vec<u8>,
+    }
+
+    impl io::Write for Blocking {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    #[case::once(1, true)]
+    #[case::until_last_retry(WOULD_BLOCK_RETRIES as usize, true)]
+    #[case::past_last_retry(WOULD_BLOCK_RETRIES as usize + 1, false)]
+    fn would_block(#[case] failures: usize, #[case] succeeds: bool) {
+        let mut writer = GuardedWriter { inner: Blocking { failures, output: Vec::new() }, disconnected: false };
+        let result = writer.write(b"hi");
+        assert_eq!(result.is_ok(), succeeds, "{result:?}");
+        // It's not a disconnection so writing keeps working afterwards.
+        assert!(!writer.disconnected);
+        writer.write_all(b"!").expect("write failed");
+        let expected: &[u8] = if succeeds { b"hi!" } else { b"!" };
+        assert_eq!(writer.inner.output, expected);
+    }
+
+    // Writes into a pipe whose read end is closed, keeping track of what's written after it fails.
+    #[cfg(unix)]
+    struct ClosedPipe {
+        pipe: std::fs::File,
+        failed: Rc<Cell<bool>>,
+        writes_after_failure: Rc<Cell<usize>>,
+        deinitialized: Rc<Cell<bool>>,
+    }
+
+    #[cfg(unix)]
+    impl io::Write for ClosedPipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.failed.get() {
+                self.writes_after_failure.set(self.writes_after_failure.get() + 1);
+            }
+            let result = self.pipe.write(buf);
+            if result.is_err() {
+                self.failed.set(true);
+            }
+            result
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.pipe.flush()
+        }
+    }
+
+    #[cfg(unix)]
+    impl TerminalWrite for ClosedPipe {
+        fn init(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn deinit(&mut self) {
+            self.deinitialized.set(true);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn closed_pipe() {
+        use std::os::fd::FromRawFd;
+
+        let mut fds = [0; 2];
+        // SAFETY: the array has room for both ends of the pipe.
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0, "creating pipe");
+        // SAFETY: the read end is closed right away and the write end is owned by the file from now on.
+        let pipe = unsafe {
+            libc::close(fds[0]);
+            std::fs::File::from_raw_fd(fds[1])
+        };
+        let failed = Rc::new(Cell::new(false));
+        let writes_after_failure = Rc::new(Cell::new(0));
+        let deinitialized = Rc::new(Cell::new(false));
+        let writer = ClosedPipe {
+            pipe,
+            failed: failed.clone(),
+            writes_after_failure: writes_after_failure.clone(),
+            deinitialized: deinitialized.clone(),
+        };
+        let mut terminal = Terminal::new(writer, Rc::new(ImagePrinter::Null)).expect("creating terminal");
+
+        let error = terminal.move_to(3, 4).expect_err("write succeeded");
+        assert!(is_disconnection(&error), "not a disconnection: {error}");
+        assert!(failed.get());
+
+        let error = terminal.print_line("hello").expect_err("write succeeded");
+        assert!(is_disconnection(&error));
+        terminal.set_colors(Colors::default()).expect_err("write succeeded");
+        terminal.flush().expect_err("flush succeeded");
+        drop(terminal);
+
+        assert_eq!(writes_after_failure.get(), 0);
+        assert!(!deinitialized.get(), "terminal was written to after it was gone");
+    }
 }
@@ -8,6 +8,7 @@ use crate::{
     },
     style::{Colors, TextStyle},
 };
+use crossterm::style::Attribute;
 
 const MINIMUM_LINE_LENGTH: u16 = 10;
 
@@ -18,6 +19,7 @@ pub(crate) struct TextDrawer<'a> {
     line: &'a WeightedTextBlock,
     positioning: Positioning,
     default_colors: &'a Colors,
+    dim: bool,
 }
 
 impl<'a> TextDrawer<'a> {
@@ -26,17 +28,32 @@ impl<'a> TextDrawer<'a> {
         line: &'a WeightedTextBlock,
         dimensions: &WindowSize,
         default_colors: &'a Colors,
+    ) -> Result<Self, RenderError> {
+        let positioning = layout.compute(dimensions, line.width() as u16);
+        Self::positioned(line, positioning, default_colors)
+    }
+
+    /// Construct a drawer that lays out text using the given positioning.
+    pub(crate) fn positioned(
+        line: &'a WeightedTextBlock,
+        positioning: Positioning,
+        default_colors: &'a Colors,
     ) -> Result<Self, RenderError> {
         let text_length = line.width() as u16;
-        let positioning = layout.compute(dimensions, text_length);
         // If our line doesn't fit and it's just too small then abort
         if text_length > positioning.max_line_length && positioning.max_line_length <= MINIMUM_LINE_LENGTH {
             Err(RenderError::TerminalTooSmall)
         } else {
-            Ok(Self { line, positioning, default_colors })
+            Ok(Self { line, positioning, default_colors, dim: false })
         }
     }
 
+    /// Draw the text dimmed.
+    pub(crate) fn dim(mut self) -> Self {
+        self.dim = true;
+        self
+    }
+
     /// Draw text on the given handle.
     ///
     /// This performs word splitting and word wrapping.
@@ -45,24 +62,248 @@ impl<'a> TextDrawer<'a> {
         W: TerminalWrite,
     {
         let Positioning { max_line_length, start_column } = self.positioning;
-
+        let mut tracker = StyleTracker::new(self.default_colors);
         for (line_index, line) in self.line.split(max_line_length as usize).enumerate() {
             terminal.move_to_column(start_column)?;
             if line_index > 0 {
                 terminal.move_down(1)?;
             }
+            // Adjacent chunks that look the same are printed at once.
+            let mut pending: Option<(String, TextStyle)> = None;
             for chunk in line {
-                let (text, style) = chunk.into_parts();
-                let text = style.apply(text);
-                terminal.print_styled_line(text)?;
-
-                // Crossterm resets colors if any attributes are set so let's just re-apply colors
-                // if the format has anything on it at all.
-                if style != TextStyle::default() {
-                    terminal.set_colors(self.default_colors.clone())?;
+                let (text, mut style) = chunk.into_parts();
+                if self.dim {
+                    style = style.dim();
                 }
+                match &mut pending {
+                    Some((pending_text, pending_style)) if tracker.looks_same(pending_style, &style) => {
+                        pending_text.push_str(text);
+                    }
+                    _ => {
+                        if let Some((text, style)) = pending.replace((text.to_string(), style)) {
+                            tracker.print(terminal, &text, &style)?;
+                        }
+                    }
+                };
+            }
+            if let Some((text, style)) = pending {
+                tracker.print(terminal, &text, &style)?;
+            }
+        }
+        tracker.reset(terminal)?;
+        Ok(())
+    }
+}
+
+/// Keeps track of the style applied in the terminal so only changes to it are emitted.
+///
+/// Text is expected to start being drawn using the default colors and no attributes, and the
+/// terminal is always left that way once it's done.
+struct StyleTracker<'a> {
+    default_colors: &'a Colors,
+    current: EffectiveStyle,
+}
+
+impl<'a> StyleTracker<'a> {
+    fn new(default_colors: &'a Colors) -> Self {
+        Self { default_colors, current: EffectiveStyle { attributes: Vec::new(), colors: default_colors.clone() } }
+    }
+
+    fn print<W: TerminalWrite>(&mut self, terminal: &mut Terminal<W>, text: &str, style: &TextStyle) -> RenderResult {
+        let target = self.effective_style(style);
+        // Attributes can't be reliably turned off one by one so everything is reset instead.
+        if self.current.attributes.iter().any(|attribute| !target.attributes.contains(attribute)) {
+            terminal.set_attribute(Attribute::Reset)?;
+            self.current = EffectiveStyle::default();
+        }
+        for attribute in &target.attributes {
+            if !self.current.attributes.contains(attribute) {
+                terminal.set_attribute(*attribute)?;
             }
         }
+        if target.colors != self.current.colors {
+            terminal.change_colors(target.colors.clone())?;
+        }
+        self.current = target;
+        terminal.print_line(text)?;
+        Ok(())
+    }
+
+    fn reset<W: TerminalWrite>(self, terminal: &mut Terminal<W>) -> RenderResult {
+        if !self.current.attributes.is_empty() {
+            terminal.set_attribute(Attribute::Reset)?;
+            terminal.set_colors(self.default_colors.clone())?;
+        } else if &self.current.colors != self.default_colors {
+            terminal.set_colors(self.default_colors.clone())?;
+        }
         Ok(())
     }
+
+    fn looks_same(&self, lhs: &TextStyle, rhs: &TextStyle) -> bool {
+        self.effective_style(lhs) == self.effective_style(rhs)
+    }
+
+    // The style text is displayed with, which takes any colors it doesn't set from the default ones.
+    fn effective_style(&self, style: &TextStyle) -> EffectiveStyle {
+        EffectiveStyle { attributes: style.attributes(), colors: style.colors.merge(self.default_colors) }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct EffectiveStyle {
+    attributes: Vec<Attribute>,
+    colors: Colors,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{markdown::elements::Text, media::printer::ImagePrinter, render::terminal::TestWriter, style::Color};
+    use crossterm::style::Colored;
+    use std::{collections::BTreeSet, rc::Rc};
+
+    #[test]
+    fn positioned() {
+        let mut terminal =
+            Terminal::new(TestWriter::default(), Rc::new(ImagePrinter::Null)).expect("creating terminal");
+        terminal.move_to(20, 3).expect("move failed");
+        let line = WeightedTextBlock::from(vec![Text::from("the quick brown fox jumps over the lazy dog")]);
+        let positioning = Positioning { max_line_length: 20, start_column: 20 };
+        let colors = Colors::default();
+        let drawer = TextDrawer::positioned(&line, positioning, &colors).expect("too small");
+        drawer.draw(&mut terminal).expect("draw failed");
+        assert_eq!(terminal.cursor_row, 5);
+    }
+
+    #[test]
+    fn positioned_too_small() {
+        let line = WeightedTextBlock::from(vec![Text::from("the quick brown fox jumps over the lazy dog")]);
+        let positioning = Positioning { max_line_length: 5, start_column: 75 };
+        let colors = Colors::default();
+        let result = TextDrawer::positioned(&line, positioning, &colors);
+        assert!(matches!(result, Err(RenderError::TerminalTooSmall)));
+    }
+
+    // The style of a cell in the terminal's grid.
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Cell {
+        attributes: BTreeSet<u16>,
+        foreground: Option<String>,
+        background: Option<String>,
+    }
+
+    // Draw some text, followed by a plain "$", and return the style every character ends up with.
+    fn draw(texts: Vec<Text>, colors: &Colors) -> (Vec<(char, Cell)>, usize) {
+        let output = TestWriter::default();
+        let mut terminal = Terminal::new(output.clone(), Rc::new(ImagePrinter::Null)).expect("creating terminal");
+        terminal.set_colors(colors.clone()).expect("setting colors failed");
+        let start = output.contents().len();
+
+        let line = WeightedTextBlock::from(texts);
+        let positioning = Positioning { max_line_length: 80, start_column: 0 };
+        let drawer = TextDrawer::positioned(&line, positioning, colors).expect("too small");
+        drawer.draw(&mut terminal).expect("draw failed");
+        let length = output.contents().len() - start;
+        terminal.print_line("$").expect("print failed");
+        terminal.flush().expect("flush failed");
+        let output = output.contents();
+        (paint(&output), length)
+    }
+
+    // Interpret the SGR sequences in some output to find out the style every character is printed with.
+    fn paint(output: &str) -> Vec<(char, Cell)> {
+        let mut cells = Vec::new();
+        let mut cell = Cell::default();
+        let mut rest = output;
+        while let Some(c) = rest.chars().next() {
+            if let Some(sequence) = rest.strip_prefix("\x1b[") {
+                let end = sequence.find(|c: char| !c.is_ascii_digit() && c != ';' && c != '?').expect("unterminated");
+                if sequence[end..].starts_with('m') {
+                    apply_sgr(&mut cell, &sequence[..end]);
+                }
+                rest = &sequence[end + 1..];
+                continue;
+            }
+            cells.push((c, cell.clone()));
+            rest = &rest[c.len_utf8()..];
+        }
+        cells
+    }
+
+    fn apply_sgr(cell: &mut Cell, parameters: &str) {
+        let mut parameters = parameters.split(';').map(|parameter| parameter.parse::<u16>().unwrap_or(0));
+        while let Some(parameter) = parameters.next() {
+            match parameter {
+                0 => *cell = Cell::default(),
+                1..=9 => {
+                    cell.attributes.insert(parameter);
+                }
+                22 => cell.attributes.retain(|attribute| ![1, 2].contains(attribute)),
+                23 | 24 | 29 => {
+                    cell.attributes.remove(&(parameter - 20));
+                }
+                38 | 48 => {
+                    let color = match parameters.next() {
+                        Some(2) => parameters.by_ref().take(3).map(|value| value.to_string()).collect::<Vec<_>>(),
+                        _ => continue,
+                    };
+                    let color = Some(format!("{parameter};2;{}", color.join(";")));
+                    if parameter == 38 { cell.foreground = color } else { cell.background = color }
+                }
+                39 => cell.foreground = None,
+                49 => cell.background = None,
+                _ => (),
+            }
+        }
+    }
+
+    fn expected_cell(style: &TextStyle, default_colors: &Colors) -> Cell {
+        let attributes = style.attributes().into_iter().map(|attribute| attribute.sgr().parse().unwrap()).collect();
+        let colors = style.colors.merge(default_colors);
+        let foreground = colors.foreground.map(|color| Colored::ForegroundColor(color.into()).to_string());
+        let background = colors.background.map(|color| Colored::BackgroundColor(color.into()).to_string());
+        Cell { attributes, foreground, background }
+    }
+
+    #[test]
+    fn interleaved_styles() {
+        let colors = Colors { foreground: Some(Color::new(200, 200, 200)), background: Some(Color::new(10, 10, 10)) };
+        let red = Colors { foreground: Some(Color::new(255, 0, 0)), background: None };
+        let blue = Colors { foreground: None, background: Some(Color::new(0, 0, 255)) };
+        let styles = [
+            TextStyle::default().bold().colors(red),
+            TextStyle::default(),
+            TextStyle::default().bold().italics(),
+            TextStyle::default().italics().colors(blue),
+            TextStyle::default().dim(),
+            TextStyle::default().strikethrough().underlined(),
+            TextStyle::default().strikethrough().underlined().code(),
+            TextStyle::default().bold(),
+        ];
+        let texts = styles.iter().zip('a'..).map(|(style, c)| Text::new(c.to_string(), style.clone())).collect();
+        let (cells, _) = draw(texts, &colors);
+
+        let mut expected: Vec<_> =
+            styles.iter().zip('a'..).map(|(style, c)| (c, expected_cell(style, &colors))).collect();
+        // Whatever comes afterwards isn't affected by the text's style.
+        expected.push(('$', expected_cell(&TextStyle::default(), &colors)));
+        assert_eq!(cells, expected);
+    }
+
+    #[test]
+    fn repeated_styles() {
+        let texts = vec![
+            Text::new("a", TextStyle::default().bold()),
+            Text::new("b", TextStyle::default().bold()),
+            Text::new("c", TextStyle::default().bold().code()),
+        ];
+        let (cells, length) = draw(texts, &Colors::default());
+        let bold = expected_cell(&TextStyle::default().bold(), &Colors::default());
+        let expected = vec![('a', bold.clone()), ('b', bold.clone()), ('c', bold), ('$', Cell::default())];
+        assert_eq!(cells, expected);
+
+        // Moving to the column, setting bold once, the text, and resetting. Resetting and setting
+        // bold again around every chunk used to take 43 bytes.
+        assert_eq!(length, 19);
+    }
 }
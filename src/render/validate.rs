@@ -1,6 +1,7 @@
 use super::{properties::WindowSize, terminal::TerminalWrite};
 use crate::{
-    presentation::Presentation,
+    custom::SafeAreaMargin,
+    presentation::{Presentation, RenderOperation, SlideReference},
     render::{
         draw::RenderError,
         engine::{RenderEngine, RenderEngineOptions},
@@ -13,22 +14,42 @@ use std::{io, rc::Rc};
 pub(crate) struct OverflowValidator;
 
 impl OverflowValidator {
-    pub(crate) fn validate(presentation: &Presentation, dimensions: WindowSize) -> Result<(), OverflowError> {
+    pub(crate) fn validate(
+        presentation: &Presentation,
+        dimensions: WindowSize,
+        safe_area: SafeAreaMargin,
+    ) -> Result<(), OverflowError> {
         let printer = Rc::new(ImagePrinter::Null);
         for (index, slide) in presentation.iter_slides().enumerate() {
-            let index = index + 1;
+            let slide_reference = || presentation.numbering().reference(index);
             let mut terminal = Terminal::new(io::Empty::default(), printer.clone()).map_err(RenderError::from)?;
-            let options = RenderEngineOptions { validate_overflows: true };
+            let options = RenderEngineOptions { validate_overflows: true, safe_area, ..Default::default() };
             let engine = RenderEngine::new(&mut terminal, dimensions.clone(), options);
             match engine.render(slide.iter_operations()) {
                 Ok(()) => (),
-                Err(RenderError::HorizontalOverflow) => return Err(OverflowError::Horizontal(index)),
-                Err(RenderError::VerticalOverflow) => return Err(OverflowError::Vertical(index)),
+                Err(RenderError::HorizontalOverflow) => return Err(OverflowError::Horizontal(slide_reference())),
+                Err(RenderError::VerticalOverflow) => return Err(OverflowError::Vertical(slide_reference())),
                 Err(e) => return Err(OverflowError::Render(e)),
             };
         }
         Ok(())
     }
+
+    /// Check whether a set of operations fits vertically in a window of the given size.
+    pub(crate) fn fits_vertically<'a>(
+        operations: impl Iterator<Item = &'a RenderOperation>,
+        dimensions: WindowSize,
+    ) -> Result<bool, RenderError> {
+        let mut terminal = Terminal::new(io::Empty::default(), Rc::new(ImagePrinter::Null))?;
+        let options =
+            RenderEngineOptions { validate_overflows: true, vertical_overflows_only: true, ..Default::default() };
+        let engine = RenderEngine::new(&mut terminal, dimensions, options);
+        match engine.render(operations) {
+            Ok(()) => Ok(true),
+            Err(RenderError::VerticalOverflow) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 impl TerminalWrite for io::Empty {
@@ -41,12 +62,31 @@ impl TerminalWrite for io::Empty {
 
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum OverflowError {
-    #[error("presentation overflows horizontally on slide {0}")]
-    Horizontal(usize),
+    #[error("presentation overflows horizontally on {0}")]
+    Horizontal(SlideReference),
 
-    #[error("presentation overflows vertically on slide {0}")]
-    Vertical(usize),
+    #[error("presentation overflows vertically on {0}")]
+    Vertical(SlideReference),
 
     #[error(transparent)]
     Render(#[from] RenderError),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::presentation::{SlideBuilder, SlideChunk};
+
+    #[test]
+    fn overflows_use_display_numbers() {
+        let slide = |numbered, lines| {
+            let operations = vec![RenderOperation::RenderLineBreak; lines];
+            SlideBuilder::default().chunks(vec![SlideChunk::new(operations, vec![])]).numbered(numbered).build()
+        };
+        let presentation = Presentation::from(vec![slide(true, 1), slide(false, 1), slide(false, 100)]);
+        let dimensions = WindowSize { rows: 10, columns: 80, width: 800, height: 200 };
+        let error =
+            OverflowValidator::validate(&presentation, dimensions, Default::default()).expect_err("no overflow");
+        assert_eq!(error.to_string(), "presentation overflows vertically on unnumbered slide after slide 1");
+    }
+}
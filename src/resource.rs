@@ -1,20 +1,27 @@
 use crate::{
-    media::{image::Image, printer::RegisterImageError, register::ImageRegistry},
+    media::{
+        image::{Image, ImageSource},
+        printer::{ImageResource, RegisterImage, RegisterImageError},
+        register::ImageRegistry,
+    },
     theme::{LoadThemeError, PresentationTheme},
 };
 use std::{
     collections::HashMap,
-    io,
+    fs, io,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
 };
 
 /// Manages resources pulled from the filesystem such as images.
 ///
 /// All resources are cached so once a specific resource is loaded, looking it up with the same
-/// path will involve an in-memory lookup.
+/// path will involve an in-memory lookup. Images are only cached while something is still using
+/// them so memory is released once they're evicted from the presentation.
 pub struct Resources {
     base_path: PathBuf,
-    images: HashMap<PathBuf, Image>,
+    sandbox: Option<PathBuf>,
+    images: Arc<Mutex<HashMap<PathBuf, Weak<ImageResource>>>>,
     themes: HashMap<PathBuf, PresentationTheme>,
     image_registry: ImageRegistry,
 }
@@ -24,19 +31,102 @@ impl Resources {
     ///
     /// Any relative paths will be assumed to be relative to the given base.
     pub fn new<P: Into<PathBuf>>(base_path: P, image_registry: ImageRegistry) -> Self {
-        Self { base_path: base_path.into(), images: Default::default(), themes: Default::default(), image_registry }
+        Self {
+            base_path: base_path.into(),
+            sandbox: None,
+            images: Default::default(),
+            themes: Default::default(),
+            image_registry,
+        }
+    }
+
+    /// Only allow resources within the base path's directory tree to be loaded.
+    pub fn sandboxed(mut self) -> io::Result<Self> {
+        // The base path is empty when the presentation is in the current directory.
+        let root = if self.base_path.as_os_str().is_empty() { Path::new(".") } else { &self.base_path };
+        self.sandbox = Some(root.canonicalize()?);
+        Ok(self)
+    }
+
+    /// Construct a resource manager over the same base path and sandbox as this one that doesn't
+    /// share any of its loaded resources.
+    pub(crate) fn detached(&self) -> Self {
+        Self {
+            base_path: self.base_path.clone(),
+            sandbox: self.sandbox.clone(),
+            images: Default::default(),
+            themes: Default::default(),
+            image_registry: self.image_registry.clone(),
+        }
+    }
+
+    /// Whether this resource manager only allows loading resources within its base path.
+    pub(crate) fn is_sandboxed(&self) -> bool {
+        self.sandbox.is_some()
+    }
+
+    /// Resolve a path so it can be used to load a resource.
+    ///
+    /// When sandboxed, the path is canonicalized so that any `..` components and symlinks can't
+    /// be used to reference files outside of the base path, which means it needs to exist.
+    /// Otherwise the path is returned as-is.
+    pub(crate) fn resolve<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf, ResolveError> {
+        let path = path.as_ref();
+        let Some(root) = &self.sandbox else {
+            return Ok(path.into());
+        };
+        match self.base_path.join(path).canonicalize() {
+            Ok(canonical) if canonical.starts_with(root) => Ok(canonical),
+            Ok(_) => Err(ResolveError::OutsideSandbox),
+            Err(e) => Err(ResolveError::Io(e)),
+        }
     }
 
     /// Get the image at the given path.
     pub(crate) fn image<P: AsRef<Path>>(&mut self, path: P) -> Result<Image, LoadImageError> {
+        self.image_loader(path)?()
+    }
+
+    /// Get the image at the given path if it's already loaded.
+    pub(crate) fn cached_image<P: AsRef<Path>>(&self, path: P) -> Option<Image> {
+        let path = self.base_path.join(path);
+        Self::find_cached_image(&self.images.lock().unwrap(), path)
+    }
+
+    /// Get a function that loads the image at the given path.
+    ///
+    /// The function can be run in a background thread and it populates the cache once the image is
+    /// loaded. This fails right away if the image can't be accessed so that missing images are
+    /// reported without having to wait until they're loaded.
+    pub(crate) fn image_loader<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<impl Fn() -> Result<Image, LoadImageError> + Send + Sync + 'static, LoadImageError> {
         let path = self.base_path.join(path);
-        if let Some(image) = self.images.get(&path) {
-            return Ok(image.clone());
+        if let Err(e) = fs::metadata(&path) {
+            return Err(LoadImageError::Io(path, e));
         }
+        let images = self.images.clone();
+        let registrar = self.image_registry.registrar();
+        let loader = move || {
+            if let Some(image) = Self::find_cached_image(&images.lock().unwrap(), path.clone()) {
+                return Ok(image);
+            }
+            // Don't hold the lock while loading as this can take a while.
+            let resource = registrar.register_resource(&path)?;
+            let image = Image::new(resource, ImageSource::Filesystem(path.clone()));
+            let mut images = images.lock().unwrap();
+            // Drop anything that's no longer used while we're at it.
+            images.retain(|_, resource| resource.strong_count() > 0);
+            images.insert(path.clone(), Arc::downgrade(&image.resource));
+            Ok(image)
+        };
+        Ok(loader)
+    }
 
-        let image = self.image_registry.register_resource(path.clone())?;
-        self.images.insert(path, image.clone());
-        Ok(image)
+    fn find_cached_image(images: &HashMap<PathBuf, Weak<ImageResource>>, path: PathBuf) -> Option<Image> {
+        let resource = images.get(&path)?.upgrade()?;
+        Some(Image { resource, source: ImageSource::Filesystem(path) })
     }
 
     /// Get the theme at the given path.
@@ -53,11 +143,21 @@ impl Resources {
 
     /// Clears all resources.
     pub(crate) fn clear(&mut self) {
-        self.images.clear();
+        self.images.lock().unwrap().clear();
         self.themes.clear();
     }
 }
 
+/// An error resolving a path.
+#[derive(thiserror::Error, Debug)]
+pub enum ResolveError {
+    #[error("path is outside of the presentation's directory")]
+    OutsideSandbox,
+
+    #[error(transparent)]
+    Io(io::Error),
+}
+
 /// An error loading an image.
 #[derive(thiserror::Error, Debug)]
 pub enum LoadImageError {
@@ -67,3 +167,62 @@ pub enum LoadImageError {
     #[error(transparent)]
     RegisterImage(#[from] RegisterImageError),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use std::fs;
+    use tempfile::{tempdir, TempDir};
+
+    // Creates a presentation directory within another one that contains a file that's outside of it.
+    fn make_tree() -> (TempDir, PathBuf) {
+        let root = tempdir().expect("failed to create directory");
+        let base = root.path().join("deck");
+        fs::create_dir_all(base.join("images")).expect("failed to create directory");
+        fs::write(base.join("images").join("a.png"), "").expect("failed to write");
+        fs::write(root.path().join("secret.txt"), "").expect("failed to write");
+        (root, base)
+    }
+
+    #[rstest]
+    #[case::inside("images/a.png", true)]
+    #[case::dot_dot_inside("images/../images/a.png", true)]
+    #[case::missing("missing.png", false)]
+    #[case::parent("../secret.txt", false)]
+    #[case::nested_parent("images/../../secret.txt", false)]
+    // Backslashes are only separators on Windows, anywhere else these are files named like this that don't exist.
+    #[case::windows_separators("images\\a.png", cfg!(windows))]
+    #[case::windows_parent("..\\secret.txt", false)]
+    fn sandboxed_paths(#[case] path: &str, #[case] allowed: bool) {
+        let (_root, base) = make_tree();
+        let resources = Resources::new(&base, Default::default()).sandboxed().expect("failed to sandbox");
+        assert_eq!(resources.resolve(path).is_ok(), allowed);
+    }
+
+    #[test]
+    fn sandboxed_absolute_path() {
+        let (root, base) = make_tree();
+        let resources = Resources::new(&base, Default::default()).sandboxed().expect("failed to sandbox");
+        assert!(resources.resolve(root.path().join("secret.txt")).is_err());
+        assert!(resources.resolve(base.join("images").join("a.png")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sandboxed_symlinks() {
+        let (root, base) = make_tree();
+        std::os::unix::fs::symlink(root.path().join("secret.txt"), base.join("outside")).expect("failed to link");
+        std::os::unix::fs::symlink(base.join("images"), base.join("inside")).expect("failed to link");
+        let resources = Resources::new(&base, Default::default()).sandboxed().expect("failed to sandbox");
+        assert!(resources.resolve("outside").is_err());
+        assert!(resources.resolve("inside/a.png").is_ok());
+    }
+
+    #[test]
+    fn unsandboxed_paths() {
+        let (_root, base) = make_tree();
+        let resources = Resources::new(&base, Default::default());
+        assert_eq!(resources.resolve("../secret.txt").expect("resolve failed"), Path::new("../secret.txt"));
+    }
+}
@@ -0,0 +1,704 @@
+//! Export presentations as [reveal.js](https://revealjs.com) presentations.
+
+use crate::{
+    markdown::{
+        elements::{
+            Code, CodeLanguage, DescriptionItem, DetailsTag, ListItem, ListItemType, MarkdownElement, ParagraphElement,
+            Table, TextBlock,
+        },
+        parse::ParseError,
+    },
+    presentation::PresentationMetadata,
+    processing::{
+        builder::{CommentCommand, PresentationBuilderOptions},
+        glossary::Glossary,
+        redaction::Redactions,
+        shortcodes::ShortcodeEmoji,
+        transform::TextPipeline,
+    },
+    resource::{ResolveError, Resources},
+    MarkdownParser,
+};
+use std::{
+    fmt::Write,
+    fs, io,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+};
+
+include!(concat!(env!("OUT_DIR"), "/reveal_js.rs"));
+
+// The directory reveal.js is written to, relative to the presentation's `index.html`.
+const REVEAL_JS_DIRECTORY: &str = "reveal.js";
+
+/// Get the contents of one of the bundled reveal.js files, given its path relative to `index.html`.
+pub(crate) fn reveal_js_asset(path: &str) -> Option<&'static [u8]> {
+    let path = path.strip_prefix(REVEAL_JS_DIRECTORY)?.strip_prefix('/')?;
+    REVEAL_JS_ASSETS.iter().find(|(name, _)| *name == path).map(|(_, contents)| *contents)
+}
+
+/// Allows exporting presentations into a reveal.js presentation.
+pub struct RevealExporter<'a> {
+    parser: MarkdownParser<'a>,
+    resources: Resources,
+    options: PresentationBuilderOptions,
+}
+
+impl<'a> RevealExporter<'a> {
+    /// Construct a new exporter.
+    ///
+    /// Images are resolved via the given resources, so they're only allowed within the presentation's directory when
+    /// these are sandboxed.
+    pub fn new(parser: MarkdownParser<'a>, resources: Resources, options: PresentationBuilderOptions) -> Self {
+        Self { parser, resources, options }
+    }
+
+    /// Export the given presentation into the given output directory.
+    ///
+    /// This creates an `index.html` file in the output directory and copies every image referenced
+    /// via a relative path next to it. reveal.js is bundled and written next to it as well, so the
+    /// presentation can be opened without an internet connection.
+    pub fn export(&self, presentation_path: &Path, output_directory: &Path) -> Result<(), RevealExportError> {
+        let RevealPresentation { html, images } = self.generate(presentation_path)?;
+        fs::create_dir_all(output_directory).map_err(RevealExportError::Io)?;
+        for ExportedImage { path, source } in images {
+            let target = output_directory.join(&path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(RevealExportError::Io)?;
+            }
+            fs::copy(source, target).map_err(|e| RevealExportError::CopyImage(path, e))?;
+        }
+        for (name, contents) in REVEAL_JS_ASSETS {
+            let target = output_directory.join(REVEAL_JS_DIRECTORY).join(name);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(RevealExportError::Io)?;
+            }
+            fs::write(target, contents).map_err(RevealExportError::Io)?;
+        }
+        fs::write(output_directory.join("index.html"), html).map_err(RevealExportError::Io)?;
+        Ok(())
+    }
+
+    /// Generate the HTML for the given presentation without writing it anywhere.
+    pub(crate) fn generate(&self, presentation_path: &Path) -> Result<RevealPresentation, RevealExportError> {
+        let contents = fs::read_to_string(presentation_path).map_err(RevealExportError::ReadPresentation)?;
+        let elements = self.parser.parse(&contents)?;
+        let (html, paths) = HtmlGenerator::new(self.options.clone()).generate(&elements)?;
+        let base_path = presentation_path.parent().unwrap_or(Path::new("/"));
+        let mut images = Vec::new();
+        for path in paths {
+            let source = self.resources.resolve(&path).map_err(|e| RevealExportError::ResolveImage(path.clone(), e))?;
+            images.push(ExportedImage { path, source: base_path.join(source) });
+        }
+        Ok(RevealPresentation { html, images })
+    }
+}
+
+/// A presentation exported into reveal.js.
+pub(crate) struct RevealPresentation {
+    /// The presentation's `index.html` file.
+    pub(crate) html: String,
+
+    /// The images referenced by the presentation.
+    pub(crate) images: Vec<ExportedImage>,
+}
+
+/// An image referenced by an exported presentation.
+pub(crate) struct ExportedImage {
+    /// The path the presentation references the image by, relative to the presentation's directory.
+    pub(crate) path: PathBuf,
+
+    /// The path to the image's file.
+    pub(crate) source: PathBuf,
+}
+
+/// Generates the HTML for a reveal.js presentation.
+struct HtmlGenerator {
+    options: PresentationBuilderOptions,
+    slides: Vec<String>,
+    current: String,
+    open_fragments: usize,
+    title: Option<String>,
+    images: Vec<PathBuf>,
+    redactions: Rc<Redactions>,
+    text_pipeline: TextPipeline,
+}
+
+impl HtmlGenerator {
+    fn new(options: PresentationBuilderOptions) -> Self {
+        let redactions = Rc::new(Redactions::default());
+        let text_pipeline = Self::build_text_pipeline(&options, Glossary::default(), &redactions);
+        Self {
+            options,
+            slides: Vec::new(),
+            current: String::new(),
+            open_fragments: 0,
+            title: None,
+            images: Vec::new(),
+            redactions,
+            text_pipeline,
+        }
+    }
+
+    fn build_text_pipeline(
+        options: &PresentationBuilderOptions,
+        glossary: Glossary,
+        redactions: &Rc<Redactions>,
+    ) -> TextPipeline {
+        let mut pipeline = TextPipeline::default();
+        if options.shortcode_emoji {
+            pipeline.push(Rc::new(ShortcodeEmoji));
+        }
+        if !redactions.is_empty() {
+            pipeline.push(redactions.clone());
+        }
+        pipeline.push(Rc::new(glossary));
+        pipeline
+    }
+
+    fn generate(mut self, elements: &[MarkdownElement]) -> Result<(String, Vec<PathBuf>), RevealExportError> {
+        for element in elements {
+            self.process_element(element)?;
+        }
+        if !self.current.is_empty() {
+            self.terminate_slide();
+        }
+        let title = escape(self.title.as_deref().unwrap_or("presenterm"));
+        let mut output = String::new();
+        let _ = write!(
+            output,
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<link rel="stylesheet" href="{REVEAL_JS_DIRECTORY}/reveal.css">
+<link rel="stylesheet" href="{REVEAL_JS_DIRECTORY}/theme/black.css">
+</head>
+<body>
+<div class="reveal">
+<div class="slides">
+"#
+        );
+        for slide in self.slides {
+            let _ = writeln!(output, "<section>\n{slide}</section>");
+        }
+        let _ = write!(
+            output,
+            r#"</div>
+</div>
+<script src="{REVEAL_JS_DIRECTORY}/reveal.js"></script>
+<script>Reveal.initialize({{ hash: true }});</script>
+</body>
+</html>
+"#
+        );
+        Ok((output, self.images))
+    }
+
+    fn process_element(&mut self, element: &MarkdownElement) -> Result<(), RevealExportError> {
+        let mut element = element.clone();
+        self.text_pipeline.apply(&mut element);
+        match &element {
+            MarkdownElement::FrontMatter(contents) => self.process_front_matter(contents)?,
+            MarkdownElement::SetexHeading { text, .. } => {
+                if self.options.implicit_slide_ends && !self.current.is_empty() {
+                    self.terminate_slide();
+                }
+                self.push_heading(1, text);
+            }
+            MarkdownElement::Heading { level, text, .. } => self.push_heading(*level, text),
+            MarkdownElement::Paragraph(elements) => self.push_paragraph(elements),
+            MarkdownElement::Image { path, .. } => self.push_image(path)?,
+            MarkdownElement::List(items) => self.push_list(items),
+            MarkdownElement::DescriptionList(items) => self.push_description_list(items),
+            MarkdownElement::Code(code) => self.push_code(code),
+            MarkdownElement::Table(table) => self.push_table(table),
+            MarkdownElement::ThematicBreak { .. } => {
+                if self.options.end_slide_shorthand {
+                    self.terminate_slide();
+                } else {
+                    self.current.push_str("<hr>\n");
+                }
+            }
+            MarkdownElement::Comment { comment, .. } => self.process_comment(comment),
+            MarkdownElement::BlockQuote(lines) => {
+                self.current.push_str("<blockquote>\n");
+                let mut depth = 0;
+                for line in lines {
+                    for _ in depth..line.depth {
+                        self.current.push_str("<blockquote>\n");
+                    }
+                    for _ in line.depth..depth {
+                        self.current.push_str("</blockquote>\n");
+                    }
+                    depth = line.depth;
+                    let mut text = TextBlock::from(line.text.as_str());
+                    self.text_pipeline.apply_text(&mut text);
+                    let _ = writeln!(self.current, "{}<br>", text_to_html(&text));
+                }
+                for _ in 0..=depth {
+                    self.current.push_str("</blockquote>\n");
+                }
+            }
+            MarkdownElement::Details(DetailsTag::Start { summary, open }) => {
+                let open = if *open { " open" } else { "" };
+                let summary = self.redactions.conceal(summary);
+                let _ = writeln!(self.current, "<details{open}><summary>{}</summary>", escape(&summary));
+            }
+            MarkdownElement::Details(DetailsTag::End) => self.current.push_str("</details>\n"),
+            // Column layouts are terminal specific.
+            MarkdownElement::FencedDiv(_) => (),
+        };
+        Ok(())
+    }
+
+    fn process_front_matter(&mut self, contents: &str) -> Result<(), RevealExportError> {
+        let metadata: PresentationMetadata =
+            serde_yaml::from_str(contents).map_err(|e| RevealExportError::InvalidMetadata(e.to_string()))?;
+        if let Some(options) = metadata.options {
+            self.options.merge(options);
+        }
+        // Exported presentations are shared with others so redactions are never revealed in them.
+        let redactions =
+            Redactions::new(&metadata.redact, false).map_err(|e| RevealExportError::InvalidMetadata(e.to_string()))?;
+        self.redactions = Rc::new(redactions);
+        let glossary = Glossary::new(metadata.glossary, self.options.glossary_case_sensitive);
+        self.text_pipeline = Self::build_text_pipeline(&self.options, glossary, &self.redactions);
+        if let Some(title) = &metadata.title {
+            let title = self.redactions.conceal(title);
+            let _ = writeln!(self.current, "<h1>{}</h1>", escape(&title));
+            self.title = Some(title);
+        }
+        if let Some(sub_title) = &metadata.sub_title {
+            let _ = writeln!(self.current, "<h3>{}</h3>", escape(&self.redactions.conceal(sub_title)));
+        }
+        for author in metadata.author.iter().chain(metadata.authors.iter()) {
+            let _ = writeln!(self.current, "<p>{}</p>", escape(&self.redactions.conceal(author)));
+        }
+        if !self.current.is_empty() {
+            self.terminate_slide();
+        }
+        Ok(())
+    }
+
+    fn process_comment(&mut self, comment: &str) {
+        let comment = comment.trim();
+        let prefix = &self.options.command_prefix;
+        if comment.contains('\n') || !comment.starts_with(prefix.as_str()) {
+            return;
+        }
+        match comment.trim_start_matches(prefix.as_str()).parse::<CommentCommand>() {
+            Ok(CommentCommand::EndSlide) => self.terminate_slide(),
+            Ok(CommentCommand::Pause) => {
+                self.current.push_str(r#"<div class="fragment">"#);
+                self.current.push('\n');
+                self.open_fragments += 1;
+            }
+            // Anything else is either terminal specific or malformed, which is caught when
+            // presenting.
+            _ => (),
+        }
+    }
+
+    fn push_heading(&mut self, level: u8, text: &TextBlock) {
+        let _ = writeln!(self.current, "<h{level}>{}</h{level}>", text_to_html(text));
+    }
+
+    fn push_paragraph(&mut self, elements: &[ParagraphElement]) {
+        self.current.push_str("<p>");
+        for element in elements {
+            match element {
+                ParagraphElement::Text(text) => self.current.push_str(&text_to_html(text)),
+                ParagraphElement::LineBreak => self.current.push_str("<br>"),
+            };
+        }
+        self.current.push_str("</p>\n");
+    }
+
+    fn push_image(&mut self, path: &Path) -> Result<(), RevealExportError> {
+        let path = match path.is_relative() {
+            // Relative images are copied next to the presentation so they can't point outside of it.
+            true => {
+                let path = normalize(path).ok_or_else(|| RevealExportError::ImageOutsideDirectory(path.into()))?;
+                self.images.push(path.clone());
+                path
+            }
+            false => path.into(),
+        };
+        let _ = writeln!(self.current, r#"<img src="{}">"#, escape(&path.display().to_string()));
+        Ok(())
+    }
+
+    fn push_description_list(&mut self, items: &[DescriptionItem]) {
+        self.current.push_str("<dl>\n");
+        for item in items {
+            let _ = writeln!(self.current, "<dt>{}</dt>", text_to_html(&item.term));
+            for definition in &item.definitions {
+                let _ = writeln!(self.current, "<dd>{}</dd>", text_to_html(definition));
+            }
+        }
+        self.current.push_str("</dl>\n");
+    }
+
+    fn push_list(&mut self, items: &[ListItem]) {
+        // Lists are flattened so we need to keep track of the tags we opened at every depth. Every
+        // open list also has an open item, which is where any nested lists go.
+        let mut open_tags: Vec<&'static str> = Vec::new();
+        for item in items {
+            let depth = item.depth as usize + 1;
+            let (tag, attributes) = match item.item_type {
+                ListItemType::Unordered => ("ul", String::new()),
+                ListItemType::OrderedParens { start: 1 } | ListItemType::OrderedPeriod { start: 1 } => {
+                    ("ol", String::new())
+                }
+                ListItemType::OrderedParens { start } | ListItemType::OrderedPeriod { start } => {
+                    ("ol", format!(" start=\"{start}\""))
+                }
+            };
+            while open_tags.len() > depth || (open_tags.len() == depth && open_tags.last() != Some(&tag)) {
+                let _ = writeln!(self.current, "</li>\n</{}>", open_tags.pop().unwrap());
+            }
+            if open_tags.len() == depth {
+                self.current.push_str("</li>\n");
+            }
+            while open_tags.len() < depth {
+                if !open_tags.is_empty() {
+                    self.current.push('\n');
+                }
+                let _ = writeln!(self.current, "<{tag}{attributes}>");
+                open_tags.push(tag);
+                // Skipped levels still need an item to nest the next list in.
+                if open_tags.len() < depth {
+                    self.current.push_str("<li>");
+                }
+            }
+            let _ = write!(self.current, "<li>{}", text_to_html(&item.contents));
+        }
+        while let Some(tag) = open_tags.pop() {
+            let _ = writeln!(self.current, "</li>\n</{tag}>");
+        }
+    }
+
+    fn push_code(&mut self, code: &Code) {
+        let mut contents = String::new();
+        // Code is redacted line by line, just like it is when presenting.
+        for line in code.contents.split_inclusive('\n') {
+            let (line, line_ending) = line.split_at(line.trim_end_matches(['\r', '\n']).len());
+            let tokens = self.redactions.redact_tokens(vec![((), line)]);
+            contents.extend(tokens.into_iter().map(|(_, tokens)| tokens));
+            contents.push_str(line_ending);
+        }
+        let contents = escape(&contents);
+        match language_class(&code.language) {
+            Some(language) => {
+                let _ = writeln!(self.current, r#"<pre><code class="language-{language}">{contents}</code></pre>"#);
+            }
+            None => {
+                let _ = writeln!(self.current, "<pre><code>{contents}</code></pre>");
+            }
+        };
+    }
+
+    fn push_table(&mut self, table: &Table) {
+        self.current.push_str("<table>\n<thead>\n<tr>");
+        for cell in &table.header.0 {
+            let _ = write!(self.current, "<th>{}</th>", text_to_html(cell));
+        }
+        self.current.push_str("</tr>\n</thead>\n<tbody>\n");
+        for row in &table.rows {
+            self.current.push_str("<tr>");
+            for cell in &row.0 {
+                let _ = write!(self.current, "<td>{}</td>", text_to_html(cell));
+            }
+            self.current.push_str("</tr>\n");
+        }
+        self.current.push_str("</tbody>\n</table>\n");
+    }
+
+    fn terminate_slide(&mut self) {
+        for _ in 0..self.open_fragments {
+            self.current.push_str("</div>\n");
+        }
+        self.open_fragments = 0;
+        self.slides.push(std::mem::take(&mut self.current));
+    }
+}
+
+fn text_to_html(text: &TextBlock) -> String {
+    let mut output = String::new();
+    for (index, chunk) in text.0.iter().enumerate() {
+        // A term can be split into chunks with different styles, in which case they all go in the same abbreviation.
+        let expansion_at = |index: Option<usize>| index.and_then(|index| text.0.get(index)?.expansion.as_ref());
+        let expansion = chunk.expansion.as_ref();
+        let opens_expansion = expansion.is_some() && expansion_at(index.checked_sub(1)) != expansion;
+        let closes_expansion = expansion.is_some() && expansion_at(Some(index + 1)) != expansion;
+        let style = &chunk.style;
+        let mut tags = Vec::new();
+        if style.is_code() {
+            tags.push("code");
+        }
+        if style.is_bold() {
+            tags.push("strong");
+        }
+        if style.is_italics() {
+            tags.push("em");
+        }
+        if style.is_strikethrough() {
+            tags.push("del");
+        }
+        if style.is_underlined() {
+            tags.push("u");
+        }
+        if style.is_inverse() {
+            tags.push("mark");
+        }
+        if let Some(expansion) = expansion.filter(|_| opens_expansion) {
+            let _ = write!(output, r#"<abbr title="{}">"#, escape(expansion));
+        }
+        for tag in &tags {
+            let _ = write!(output, "<{tag}>");
+        }
+        output.push_str(&escape(&chunk.content));
+        for tag in tags.iter().rev() {
+            let _ = write!(output, "</{tag}>");
+        }
+        if closes_expansion {
+            output.push_str("</abbr>");
+        }
+    }
+    output
+}
+
+fn language_class(language: &CodeLanguage) -> Option<String> {
+    match language {
+        CodeLanguage::Unknown => None,
+        CodeLanguage::Shell(interpreter) => Some(interpreter.clone()),
+        other => Some(format!("{other:?}").to_lowercase()),
+    }
+}
+
+// Resolve the `.` and `..` components in a relative path, failing if it points outside of its base directory.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut output = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(component) => output.push(component),
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !output.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        };
+    }
+    Some(output)
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            c => output.push(c),
+        };
+    }
+    output
+}
+
+/// An error when exporting a presentation into reveal.js.
+#[derive(thiserror::Error, Debug)]
+pub enum RevealExportError {
+    #[error("failed to read presentation: {0}")]
+    ReadPresentation(io::Error),
+
+    #[error("failed to parse presentation: {0}")]
+    ParsePresentation(#[from] ParseError),
+
+    #[error("invalid presentation metadata: {0}")]
+    InvalidMetadata(String),
+
+    #[error("image {0} is outside of the presentation's directory")]
+    ImageOutsideDirectory(PathBuf),
+
+    #[error("image {0}: {1}")]
+    ResolveImage(PathBuf, ResolveError),
+
+    #[error("copying image {0}: {1}")]
+    CopyImage(PathBuf, io::Error),
+
+    #[error("io: {0}")]
+    Io(io::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use comrak::Arena;
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    fn generate(input: &str) -> String {
+        let arena = Arena::new();
+        let parser = MarkdownParser::new(&arena);
+        let elements = parser.parse(input).expect("parsing failed");
+        HtmlGenerator::new(Default::default()).generate(&elements).expect("generation failed").0
+    }
+
+    fn count_sections(html: &str) -> usize {
+        html.matches("<section>").count()
+    }
+
+    #[test]
+    fn slides_become_sections() {
+        let html = generate(
+            r"
+# hi
+
+<!-- end_slide -->
+
+bye
+",
+        );
+        assert_eq!(count_sections(&html), 2);
+        assert!(html.contains("<h1>hi</h1>"));
+        assert!(html.contains("<p>bye</p>"));
+    }
+
+    #[test]
+    fn front_matter_intro_slide() {
+        let html = generate(
+            r"---
+title: my <title>
+author: bob
+---
+
+hi
+",
+        );
+        assert_eq!(count_sections(&html), 2);
+        assert!(html.contains("<title>my &lt;title&gt;</title>"));
+        assert!(html.contains("<h1>my &lt;title&gt;</h1>"));
+        assert!(html.contains("<p>bob</p>"));
+    }
+
+    #[test]
+    fn inline_formatting() {
+        let html = generate("**bold** _italics_ `code`");
+        assert!(html.contains("<p><strong>bold</strong> <em>italics</em> <code>code</code></p>"), "{html}");
+    }
+
+    #[test]
+    fn code_block() {
+        let html = generate("```rust\nlet q = a < b;\n```");
+        assert!(html.contains(r#"<pre><code class="language-rust">let q = a &lt; b;"#), "{html}");
+    }
+
+    #[test]
+    fn nested_lists() {
+        let html = generate("* one\n    1. two\n* three");
+        let expected = "<ul>\n<li>one\n<ol>\n<li>two</li>\n</ol>\n</li>\n<li>three</li>\n</ul>\n";
+        assert!(html.contains(expected), "{html}");
+    }
+
+    #[test]
+    fn list_type_changes() {
+        let html = generate("* one\n\n1. two");
+        let expected = "<ul>\n<li>one</li>\n</ul>\n<ol>\n<li>two</li>\n</ol>\n";
+        assert!(html.contains(expected), "{html}");
+    }
+
+    #[rstest]
+    #[case::plain("image.png", Some("image.png"))]
+    #[case::nested("./images/../image.png", Some("image.png"))]
+    #[case::parent("../image.png", None)]
+    #[case::escaping("images/../../image.png", None)]
+    fn image_paths(#[case] path: &str, #[case] expected: Option<&str>) {
+        let arena = Arena::new();
+        let elements = MarkdownParser::new(&arena).parse(&format!("![]({path})")).expect("parsing failed");
+        let result = HtmlGenerator::new(Default::default()).generate(&elements);
+        match expected {
+            Some(expected) => {
+                let (html, images) = result.expect("generation failed");
+                assert_eq!(images, &[PathBuf::from(expected)]);
+                assert!(html.contains(&format!(r#"<img src="{expected}">"#)), "{html}");
+            }
+            None => assert!(matches!(result, Err(RevealExportError::ImageOutsideDirectory(_))), "{:?}", result.err()),
+        }
+    }
+
+    #[test]
+    fn ordered_list_start() {
+        let html = generate("3. one\n4. two");
+        let expected = "<ol start=\"3\">\n<li>one</li>\n<li>two</li>\n</ol>\n";
+        assert!(html.contains(expected), "{html}");
+    }
+
+    #[rstest]
+    #[case::paragraph(
+        "the API and `API`",
+        r#"<p>the <abbr title="application programming interface">API</abbr> and <code>API</code></p>"#
+    )]
+    #[case::block_quote("> an API", r#"an <abbr title="application programming interface">API</abbr><br>"#)]
+    #[case::split("an A**PI**", r#"an <abbr title="application programming interface">A<strong>PI</strong></abbr>"#)]
+    fn glossary_terms(#[case] input: &str, #[case] expected: &str) {
+        let html = generate(&format!("---\nglossary:\n  API: application programming interface\n---\n\n{input}"));
+        assert!(html.contains(expected), "{html}");
+    }
+
+    #[test]
+    fn redactions() {
+        let input = "---
+title: about ||acme||
+redact:
+  spoilers: true
+  patterns: ['sk-[a-z]+']
+---
+
+hi ||acme|| and `a || b`
+
+```bash
+export KEY=sk-abc
+```";
+        let html = generate(input);
+        assert!(html.contains("<h1>about ████</h1>"), "{html}");
+        assert!(html.contains("<p>hi ████ and <code>a || b</code></p>"), "{html}");
+        assert!(html.contains("export KEY=██████\n"), "{html}");
+        assert!(!html.contains("acme") && !html.contains("sk-abc"), "{html}");
+    }
+
+    #[test]
+    fn bundled_reveal_js() {
+        let directory = tempdir().expect("creating tempdir");
+        let path = directory.path().join("presentation.md");
+        fs::write(&path, "# hi").expect("write failed");
+        let output = directory.path().join("output");
+        let arena = Arena::new();
+        let resources = Resources::new(directory.path(), Default::default());
+        RevealExporter::new(MarkdownParser::new(&arena), resources, Default::default())
+            .export(&path, &output)
+            .expect("export failed");
+
+        let html = fs::read_to_string(output.join("index.html")).expect("read failed");
+        assert!(html.contains(r#"<script src="reveal.js/reveal.js"></script>"#), "{html}");
+        assert!(!html.contains("http"), "{html}");
+        for (name, contents) in REVEAL_JS_ASSETS {
+            let path = format!("{REVEAL_JS_DIRECTORY}/{name}");
+            assert_eq!(fs::read(output.join(&path)).expect("read failed"), *contents);
+            assert_eq!(reveal_js_asset(&path), Some(*contents));
+        }
+        assert_eq!(reveal_js_asset("reveal.js/missing.js"), None);
+        assert_eq!(reveal_js_asset("index.html"), None);
+    }
+
+    #[test]
+    fn pauses_become_fragments() {
+        let html = generate("hi\n\n<!-- pause -->\n\nbye");
+        assert!(html.contains("<div class=\"fragment\">\n<p>bye</p>\n</div>"), "{html}");
+    }
+}
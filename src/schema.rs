@@ -0,0 +1,182 @@
+//! JSON schemas for the files presenterm reads, meant to be used by editors and other tooling.
+//!
+//! These are derived from the types the files are deserialized into so they can't get out of date,
+//! and every field's documentation ends up as its description.
+
+use crate::{custom::Config, presentation::PresentationMetadata, theme::PresentationTheme};
+use clap::ValueEnum;
+use schemars::{schema::RootSchema, schema_for};
+
+/// The kind of file a schema describes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SchemaKind {
+    /// A theme file.
+    Theme,
+
+    /// The configuration file.
+    Config,
+
+    /// A presentation's front matter.
+    #[clap(name = "frontmatter")]
+    FrontMatter,
+}
+
+/// Generate the JSON schema for a kind of file.
+pub fn generate_schema(kind: SchemaKind) -> RootSchema {
+    match kind {
+        SchemaKind::Theme => schema_for!(PresentationTheme),
+        SchemaKind::Config => schema_for!(Config),
+        SchemaKind::FrontMatter => schema_for!(PresentationMetadata),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
+    use serde_json::Value;
+    use std::fs;
+
+    // A minimal validator that covers the parts of JSON schema that schemars generates for our types.
+    fn validate(root: &RootSchema, schema: &Schema, value: &Value, path: &str) -> Result<(), String> {
+        let schema = match schema {
+            Schema::Bool(true) => return Ok(()),
+            Schema::Bool(false) => return Err(format!("{path}: nothing is allowed here")),
+            Schema::Object(schema) => schema,
+        };
+        if let Some(reference) = &schema.reference {
+            let name = reference.trim_start_matches("#/definitions/");
+            let definition = root.definitions.get(name).ok_or_else(|| format!("{path}: missing {name}"))?;
+            return validate(root, definition, value, path);
+        }
+        check_type(schema, value, path)?;
+        if let Some(values) = &schema.enum_values {
+            if !values.contains(value) {
+                return Err(format!("{path}: {value} is not one of {values:?}"));
+            }
+        }
+        if let Some(subschemas) = &schema.subschemas {
+            for subschema in subschemas.all_of.iter().flatten() {
+                validate(root, subschema, value, path)?;
+            }
+            let alternatives = subschemas.any_of.iter().chain(&subschemas.one_of).flatten().collect::<Vec<_>>();
+            if !alternatives.is_empty() && !alternatives.iter().any(|s| validate(root, s, value, path).is_ok()) {
+                return Err(format!("{path}: {value} doesn't match any alternative"));
+            }
+        }
+        if let (Some(object), Value::Object(map)) = (&schema.object, value) {
+            for required in &object.required {
+                if !map.contains_key(required) {
+                    return Err(format!("{path}: missing required field {required}"));
+                }
+            }
+            for (key, value) in map {
+                let path = format!("{path}.{key}");
+                if let Some(schema) = object.properties.get(key).or(object.additional_properties.as_deref()) {
+                    validate(root, schema, value, &path)?;
+                }
+            }
+        }
+        if let (Some(array), Value::Array(values)) = (&schema.array, value) {
+            if let Some(SingleOrVec::Single(items)) = &array.items {
+                for (index, value) in values.iter().enumerate() {
+                    validate(root, items, value, &format!("{path}[{index}]"))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_type(schema: &SchemaObject, value: &Value, path: &str) -> Result<(), String> {
+        let types = match &schema.instance_type {
+            Some(SingleOrVec::Single(instance_type)) => vec![**instance_type],
+            Some(SingleOrVec::Vec(types)) => types.clone(),
+            None => return Ok(()),
+        };
+        let matches = types.iter().any(|instance_type| match (instance_type, value) {
+            (InstanceType::Null, Value::Null)
+            | (InstanceType::Boolean, Value::Bool(_))
+            | (InstanceType::Object, Value::Object(_))
+            | (InstanceType::Array, Value::Array(_))
+            | (InstanceType::String, Value::String(_))
+            | (InstanceType::Number, Value::Number(_)) => true,
+            (InstanceType::Integer, Value::Number(number)) => number.is_i64() || number.is_u64(),
+            _ => false,
+        });
+        if matches { Ok(()) } else { Err(format!("{path}: {value} is not a {types:?}")) }
+    }
+
+    fn assert_valid(kind: SchemaKind, contents: &str) {
+        let root = generate_schema(kind);
+        let value: Value = serde_yaml::from_str(contents).expect("invalid yaml");
+        let schema = Schema::Object(root.schema.clone());
+        validate(&root, &schema, &value, "").expect("doesn't match schema");
+    }
+
+    fn front_matter(contents: &str) -> Option<&str> {
+        let contents = contents.strip_prefix("---\n")?;
+        contents.split_once("\n---\n").map(|(front_matter, _)| front_matter)
+    }
+
+    #[test]
+    fn builtin_themes() {
+        for entry in fs::read_dir("themes").expect("no themes") {
+            let path = entry.expect("invalid entry").path();
+            let contents = fs::read_to_string(&path).expect("failed to read theme");
+            serde_yaml::from_str::<PresentationTheme>(&contents).expect("invalid theme");
+            assert_valid(SchemaKind::Theme, &contents);
+        }
+    }
+
+    #[test]
+    fn example_front_matter() {
+        for entry in fs::read_dir("examples").expect("no examples") {
+            let path = entry.expect("invalid entry").path();
+            let contents = fs::read_to_string(&path).unwrap_or_default();
+            if let Some(front_matter) = front_matter(&contents) {
+                serde_yaml::from_str::<PresentationMetadata>(front_matter).expect("invalid front matter");
+                assert_valid(SchemaKind::FrontMatter, front_matter);
+            }
+        }
+    }
+
+    #[test]
+    fn sample_config() {
+        let contents = fs::read_to_string("config.sample.yaml").expect("no sample config");
+        serde_yaml::from_str::<Config>(&contents).expect("invalid config");
+        assert_valid(SchemaKind::Config, &contents);
+    }
+
+    #[rstest]
+    #[case::theme(SchemaKind::Theme, "default: { colors: { foreground: 42 } }")]
+    #[case::unknown_theme_field(SchemaKind::Theme, "potato: 42")]
+    #[case::bad_margin(SchemaKind::Theme, "default: { margin: { fixed: large } }")]
+    #[case::front_matter(SchemaKind::FrontMatter, "authors: bob")]
+    #[case::config(SchemaKind::Config, "defaults: { max_columns: many }")]
+    fn invalid_files(#[case] kind: SchemaKind, #[case] contents: &str) {
+        let root = generate_schema(kind);
+        let value: Value = serde_yaml::from_str(contents).expect("invalid yaml");
+        let schema = Schema::Object(root.schema.clone());
+        validate(&root, &schema, &value, "").expect_err("matched schema");
+    }
+
+    #[rstest]
+    #[case::theme(SchemaKind::Theme, "PresentationTheme")]
+    #[case::config(SchemaKind::Config, "Config")]
+    #[case::front_matter(SchemaKind::FrontMatter, "PresentationMetadata")]
+    fn descriptions(#[case] kind: SchemaKind, #[case] title: &str) {
+        let root = generate_schema(kind);
+        let metadata = root.schema.metadata.as_ref().expect("no metadata");
+        assert_eq!(metadata.title.as_deref(), Some(title));
+        for (name, definition) in &root.definitions {
+            let Schema::Object(definition) = definition else { continue };
+            let object = definition.object.as_deref().into_iter();
+            for (field, schema) in object.flat_map(|object| &object.properties) {
+                let Schema::Object(schema) = schema else { continue };
+                let description = schema.metadata.as_ref().and_then(|m| m.description.as_ref());
+                assert!(description.is_some(), "{name}.{field} has no description");
+            }
+        }
+    }
+}
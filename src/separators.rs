@@ -0,0 +1,214 @@
+//! Analysis and normalization of the thematic breaks in a presentation.
+//!
+//! Thematic breaks can be written as `---`, `***` or `___` and, when the `end_slide_shorthand`
+//! option is enabled, all of them end the current slide. Otherwise they're all rendered as a
+//! horizontal rule, which is easy to miss in presentations written by more than one person.
+
+use crate::{
+    markdown::{elements::MarkdownElement, parse::ParseError},
+    presentation::PresentationMetadata,
+    processing::builder::PresentationBuilderOptions,
+    MarkdownParser,
+};
+use std::fmt::{self, Display};
+
+/// The separator every thematic break is converted into when normalizing.
+const SEPARATOR: &str = "---";
+
+/// Analyzes and normalizes the thematic breaks in a presentation.
+pub struct SeparatorAnalyzer<'a> {
+    parser: MarkdownParser<'a>,
+    options: PresentationBuilderOptions,
+}
+
+impl<'a> SeparatorAnalyzer<'a> {
+    /// Construct a new separator analyzer.
+    pub fn new(parser: MarkdownParser<'a>, options: PresentationBuilderOptions) -> Self {
+        Self { parser, options }
+    }
+
+    /// Find the thematic breaks that won't end a slide in a presentation that mixes separator styles.
+    pub fn warnings(&self, contents: &str) -> Result<Vec<SeparatorWarning>, ParseError> {
+        let elements = self.parser.parse(contents)?;
+        Ok(self.find_warnings(contents, &elements))
+    }
+
+    /// Same as [SeparatorAnalyzer::warnings] but using the elements the presentation was already parsed into.
+    pub(crate) fn find_warnings(&self, contents: &str, elements: &[MarkdownElement]) -> Vec<SeparatorWarning> {
+        if self.end_slide_shorthand(elements) {
+            return Vec::new();
+        }
+        let breaks = ThematicBreak::find_all(contents, elements);
+        let mixed = breaks.iter().any(|b| b.marker != breaks[0].marker);
+        if !mixed {
+            return Vec::new();
+        }
+        breaks.into_iter().map(|b| SeparatorWarning { line: b.line, marker: b.marker }).collect()
+    }
+
+    /// Rewrite every thematic break in a presentation as `---`.
+    ///
+    /// Everything other than the thematic breaks themselves is left untouched, other than an empty
+    /// line being added before any break that would otherwise turn the line above it into a heading.
+    pub fn normalize(&self, contents: &str) -> Result<String, ParseError> {
+        let elements = self.parser.parse(contents)?;
+        let breaks = ThematicBreak::find_all(contents, &elements);
+        let mut output = String::with_capacity(contents.len());
+        let mut previous_blank = true;
+        for (index, line) in contents.split_inclusive('\n').enumerate() {
+            let (text, ending) = split_line_ending(line);
+            let is_break = breaks.iter().any(|b| b.line == index + 1);
+            let content = text.trim();
+            if is_break && content != SEPARATOR {
+                if !previous_blank {
+                    output.push_str(if ending.is_empty() { "\n" } else { ending });
+                }
+                let indentation = &text[..text.len() - text.trim_start().len()];
+                output.push_str(indentation);
+                output.push_str(SEPARATOR);
+                output.push_str(ending);
+            } else {
+                output.push_str(line);
+            }
+            previous_blank = content.is_empty();
+        }
+        Ok(output)
+    }
+
+    fn end_slide_shorthand(&self, elements: &[MarkdownElement]) -> bool {
+        // Front matter options take precedence over the configuration, just like when building
+        // the presentation.
+        let options = match elements.first() {
+            Some(MarkdownElement::FrontMatter(contents)) => {
+                serde_yaml::from_str::<PresentationMetadata>(contents).ok().and_then(|metadata| metadata.options)
+            }
+            _ => None,
+        };
+        options.and_then(|options| options.end_slide_shorthand).unwrap_or(self.options.end_slide_shorthand)
+    }
+}
+
+/// A thematic break that is rendered as a horizontal rule rather than ending the slide.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeparatorWarning {
+    line: usize,
+    marker: String,
+}
+
+impl Display for SeparatorWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: thematic break '{}' is rendered as a horizontal rule and doesn't end the slide",
+            self.line, self.marker
+        )
+    }
+}
+
+struct ThematicBreak {
+    line: usize,
+    marker: String,
+}
+
+impl ThematicBreak {
+    fn find_all(contents: &str, elements: &[MarkdownElement]) -> Vec<Self> {
+        let lines: Vec<_> = contents.lines().collect();
+        let offset = Self::front_matter_padding(&lines, elements);
+        elements
+            .iter()
+            .filter_map(|element| match element {
+                MarkdownElement::ThematicBreak { source_position } => Some(source_position.start.line + offset),
+                _ => None,
+            })
+            .filter_map(|line| {
+                let text = lines.get(line.checked_sub(1)?)?;
+                let marker = text.trim().to_string();
+                Some(Self { line, marker })
+            })
+            .collect()
+    }
+
+    // The parser's line numbers don't account for the empty line right after the front matter.
+    fn front_matter_padding(lines: &[&str], elements: &[MarkdownElement]) -> usize {
+        if !matches!(elements.first(), Some(MarkdownElement::FrontMatter(_))) {
+            return 0;
+        }
+        let mut after_front_matter = lines.iter().skip(1).skip_while(|line| line.trim_end() != "---").skip(1);
+        match after_front_matter.next() {
+            Some(line) if line.trim().is_empty() => 1,
+            _ => 0,
+        }
+    }
+}
+
+fn split_line_ending(line: &str) -> (&str, &str) {
+    if let Some(text) = line.strip_suffix("\r\n") {
+        (text, "\r\n")
+    } else if let Some(text) = line.strip_suffix('\n') {
+        (text, "\n")
+    } else {
+        (line, "")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use comrak::Arena;
+    use rstest::rstest;
+
+    fn normalize(contents: &str) -> String {
+        let arena = Arena::new();
+        let analyzer = SeparatorAnalyzer::new(MarkdownParser::new(&arena), Default::default());
+        analyzer.normalize(contents).expect("normalize failed")
+    }
+
+    fn warning_lines(contents: &str, end_slide_shorthand: bool) -> Vec<usize> {
+        let arena = Arena::new();
+        let options = PresentationBuilderOptions { end_slide_shorthand, ..Default::default() };
+        let analyzer = SeparatorAnalyzer::new(MarkdownParser::new(&arena), options);
+        analyzer.warnings(contents).expect("analysis failed").into_iter().map(|warning| warning.line).collect()
+    }
+
+    #[rstest]
+    #[case::untouched("hi\n\n---\n\nbye\n", "hi\n\n---\n\nbye\n")]
+    #[case::stars("hi\n\n***\n\nbye\n", "hi\n\n---\n\nbye\n")]
+    #[case::underscores("hi\n\n___\n\nbye", "hi\n\n---\n\nbye")]
+    #[case::spaced("hi\n\n* * * *\n", "hi\n\n---\n")]
+    #[case::indented("hi\n\n  ***\n", "hi\n\n  ---\n")]
+    #[case::crlf("hi\r\n\r\n***\r\nbye\r\n", "hi\r\n\r\n---\r\nbye\r\n")]
+    #[case::after_paragraph("hi\n***\nbye\n", "hi\n\n---\nbye\n")]
+    #[case::setext_heading("hi\n===\n\n***\n", "hi\n===\n\n---\n")]
+    #[case::code_block("```\n***\n```\n", "```\n***\n```\n")]
+    #[case::no_trailing_newline("hi\n\n***", "hi\n\n---")]
+    fn normalize_separators(#[case] input: &str, #[case] expected: &str) {
+        let output = normalize(input);
+        assert_eq!(output, expected);
+        // Normalizing twice must not change anything.
+        assert_eq!(normalize(&output), output);
+    }
+
+    #[test]
+    fn normalize_with_front_matter() {
+        let input = "---\ntitle: hi\n---\n\nhello\n\n***\n\nbye\n";
+        let output = normalize(input);
+        assert_eq!(output, "---\ntitle: hi\n---\n\nhello\n\n---\n\nbye\n");
+        assert_eq!(normalize(&output), output);
+    }
+
+    #[rstest]
+    #[case::mixed("a\n\n---\n\nb\n\n***\n", false, &[3, 7])]
+    #[case::mixed_with_shorthand("a\n\n---\n\nb\n\n***\n", true, &[])]
+    #[case::consistent("a\n\n***\n\nb\n\n***\n", false, &[])]
+    #[case::front_matter_shorthand(
+        "---\noptions:\n  end_slide_shorthand: true\n---\n\na\n\n---\n\nb\n\n***\n",
+        false,
+        &[]
+    )]
+    #[case::front_matter_offset("---\ntitle: hi\n---\n\na\n\n---\n\nb\n\n***\n", false, &[7, 11])]
+    #[case::front_matter_no_padding("---\ntitle: hi\n---\na\n\n---\n\nb\n\n***\n", false, &[6, 10])]
+    #[case::front_matter_padding("---\ntitle: hi\n---\n\n\na\n\n---\n\nb\n\n***\n", false, &[8, 12])]
+    fn warnings(#[case] input: &str, #[case] end_slide_shorthand: bool, #[case] expected: &[usize]) {
+        assert_eq!(warning_lines(input, end_slide_shorthand), expected);
+    }
+}
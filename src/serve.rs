@@ -0,0 +1,517 @@
+//! Serve presentations exported into reveal.js over HTTP.
+//!
+//! This is meant for previewing a presentation in a browser while it's being written: the
+//! presentation is exported again every time its file changes, and pages being displayed are
+//! reloaded by long polling an endpoint that only replies once that happens.
+
+use crate::{
+    input::fs::PresentationFileWatcher,
+    reveal::{escape, reveal_js_asset, ExportedImage, RevealExporter, RevealPresentation},
+};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read, Write},
+    mem,
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+// The path pages poll to find out when the presentation changes.
+const RELOAD_PATH: &str = "/__presenterm/reload";
+
+// How long a reload request is held before replying to it even if nothing changed.
+const RELOAD_TIMEOUT: Duration = Duration::from_secs(25);
+
+// How long a client has to send its request before its connection is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+// How long writing a response can stall, like when a browser stops reading it, before the connection is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// A server that serves a presentation exported into reveal.js.
+///
+/// Everything happens in a single thread: requests are handled as they come and the
+/// presentation's file is checked for changes in between them. Connections are read from without
+/// blocking so a client that's slow to send its request doesn't hold up everyone else.
+pub struct PreviewServer<'a> {
+    exporter: RevealExporter<'a>,
+    path: PathBuf,
+    listener: TcpListener,
+    watcher: PresentationFileWatcher,
+    page: Page,
+    version: u64,
+    reading: Vec<PendingRequest>,
+    waiting: Vec<WaitingReload>,
+}
+
+impl<'a> PreviewServer<'a> {
+    /// Bind a server for the presentation in the given path to an address.
+    pub fn bind<A: ToSocketAddrs>(exporter: RevealExporter<'a>, path: &Path, address: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let watcher = PresentationFileWatcher::new(path);
+        let page = Page::generate(&exporter, path, 0);
+        Ok(Self {
+            exporter,
+            path: path.into(),
+            listener,
+            watcher,
+            page,
+            version: 0,
+            reading: Vec::new(),
+            waiting: Vec::new(),
+        })
+    }
+
+    /// The address this server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serve requests forever.
+    pub fn run(mut self) -> io::Result<()> {
+        loop {
+            self.poll()?;
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Handle every pending request and check whether the presentation changed.
+    pub(crate) fn poll(&mut self) -> io::Result<()> {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    // Accepted connections don't inherit the listener's non blocking mode.
+                    if stream.set_nonblocking(true).is_ok() {
+                        let deadline = Instant::now() + READ_TIMEOUT;
+                        self.reading.push(PendingRequest { stream, request: Vec::new(), deadline });
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            };
+        }
+        for mut pending in mem::take(&mut self.reading) {
+            match Self::read_request(&mut pending.stream, &mut pending.request) {
+                Ok(true) => self.handle(pending.stream, &pending.request),
+                Ok(false) if Instant::now() < pending.deadline => self.reading.push(pending),
+                // Clients that take too long or send garbage are simply disconnected.
+                _ => (),
+            };
+        }
+        if self.watcher.has_modifications()? {
+            self.version += 1;
+            self.page = Page::generate(&self.exporter, &self.path, self.version);
+        }
+        let now = Instant::now();
+        let version = self.version;
+        let (ready, waiting) = mem::take(&mut self.waiting)
+            .into_iter()
+            .partition(|reload| reload.version != version || now > reload.deadline);
+        self.waiting = waiting;
+        for reload in ready {
+            Self::respond(reload.stream, Response::ok("text/plain", version.to_string().into_bytes()));
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, stream: TcpStream, request: &[u8]) {
+        let request = String::from_utf8_lossy(request);
+        let mut parts = request.split(' ');
+        let path = match (parts.next(), parts.next()) {
+            (Some("GET"), Some(path)) => path.to_string(),
+            _ => return Self::respond(stream, Response::error(405, "Method Not Allowed")),
+        };
+        let (path, query) = path.split_once('?').unwrap_or((&path, ""));
+        let response = match path {
+            "/" | "/index.html" => self.page.response(),
+            RELOAD_PATH => {
+                let version = query.split('&').find_map(|pair| pair.strip_prefix("version="));
+                if version.and_then(|version| version.parse().ok()) == Some(self.version) {
+                    let deadline = Instant::now() + RELOAD_TIMEOUT;
+                    self.waiting.push(WaitingReload { stream, version: self.version, deadline });
+                    return;
+                }
+                Response::ok("text/plain", self.version.to_string().into_bytes())
+            }
+            path => match reveal_js_asset(path.trim_start_matches('/')) {
+                Some(contents) => Response::ok(content_type(Path::new(path)), contents.to_vec()),
+                None => self.image(path),
+            },
+        };
+        Self::respond(stream, response);
+    }
+
+    // Only the images the presentation references can be served so nothing else in the
+    // presentation's directory is exposed.
+    fn image(&self, path: &str) -> Response {
+        let path = PathBuf::from(percent_decode(path.trim_start_matches('/')));
+        let Some(source) = self.page.images.get(&path) else {
+            return Response::error(404, "Not Found");
+        };
+        match fs::read(source) {
+            Ok(contents) => Response::ok(content_type(&path), contents),
+            Err(_) => Response::error(404, "Not Found"),
+        }
+    }
+
+    // Reads whatever the client sent so far into `request`, returning whether its headers are complete.
+    fn read_request(stream: &mut TcpStream, request: &mut Vec<u8>) -> io::Result<bool> {
+        let mut buffer = [0; 1024];
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            let bytes_read = match stream.read(&mut buffer) {
+                Ok(bytes_read) => bytes_read,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if bytes_read == 0 || request.len() > MAX_REQUEST_SIZE {
+                return Err(io::ErrorKind::InvalidData.into());
+            }
+            request.extend_from_slice(&buffer[..bytes_read]);
+        }
+        Ok(true)
+    }
+
+    fn respond(mut stream: TcpStream, response: Response) {
+        let Response { status, content_type, body } = response;
+        let header = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-store\r\n\
+             Connection: close\r\n\r\n",
+            body.len()
+        );
+        // The client going away isn't our problem. Responses are written in one go so a client that stops reading
+        // could otherwise hold up the server forever.
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+        let _ = stream.write_all(header.as_bytes()).and_then(|_| stream.write_all(&body));
+    }
+}
+
+// The page for the presentation's current version.
+struct Page {
+    html: String,
+    images: HashMap<PathBuf, PathBuf>,
+    failed: bool,
+}
+
+impl Page {
+    // Errors are displayed in the page itself so the server keeps going until they're fixed.
+    fn generate(exporter: &RevealExporter, path: &Path, version: u64) -> Self {
+        let (html, images, failed) = match exporter.generate(path) {
+            Ok(RevealPresentation { html, images }) => {
+                let images = images.into_iter().map(|ExportedImage { path, source }| (path, source)).collect();
+                (html, images, false)
+            }
+            Err(e) => {
+                let error = escape(&e.to_string());
+                let html = format!(
+                    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>presenterm</title>\n</head>\n\
+                     <body>\n<h1>Failed to load presentation</h1>\n<pre>{error}</pre>\n</body>\n</html>\n"
+                );
+                (html, HashMap::new(), true)
+            }
+        };
+        let script = format!(
+            r#"<script>
+(function poll() {{
+  fetch("{RELOAD_PATH}?version={version}")
+    .then((response) => response.text())
+    .then((version) => version == "{version}" ? poll() : location.reload())
+    .catch(() => setTimeout(poll, 1000));
+}})();
+</script>
+"#
+        );
+        let mut html = html;
+        let position = html.rfind("</body>").unwrap_or(html.len());
+        html.insert_str(position, &script);
+        Self { html, images, failed }
+    }
+
+    fn response(&self) -> Response {
+        let status = if self.failed { "500 Internal Server Error" } else { "200 OK" };
+        let body = self.html.clone().into_bytes();
+        Response { status: status.into(), content_type: "text/html; charset=utf-8", body }
+    }
+}
+
+struct PendingRequest {
+    stream: TcpStream,
+    request: Vec<u8>,
+    deadline: Instant,
+}
+
+struct WaitingReload {
+    stream: TcpStream,
+    version: u64,
+    deadline: Instant,
+}
+
+struct Response {
+    status: String,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self { status: "200 OK".into(), content_type, body }
+    }
+
+    fn error(code: u16, reason: &str) -> Self {
+        Self { status: format!("{code} {reason}"), content_type: "text/plain", body: reason.as_bytes().to_vec() }
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    match extension.to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        _ => "application/octet-stream",
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let mut output = Vec::new();
+    let mut bytes = text.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte != b'%' {
+            output.push(byte);
+            continue;
+        }
+        let hex = [bytes.next(), bytes.next()];
+        let decoded = hex
+            .iter()
+            .flatten()
+            .map(|byte| (*byte as char).to_digit(16))
+            .collect::<Option<Vec<_>>>()
+            .filter(|digits| digits.len() == 2);
+        match decoded {
+            Some(digits) => output.push((digits[0] * 16 + digits[1]) as u8),
+            None => {
+                output.push(byte);
+                output.extend(hex.into_iter().flatten());
+            }
+        };
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{resource::Resources, MarkdownParser};
+    use comrak::Arena;
+    use filetime::FileTime;
+    use rstest::rstest;
+    use std::{sync::mpsc, time::SystemTime};
+    use tempfile::{tempdir, TempDir};
+
+    // A client that makes requests from another thread while the server is polled in this one.
+    fn run_client<T, F>(server: &mut PreviewServer, client: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce(SocketAddr) -> T + Send + 'static,
+    {
+        let address = server.local_addr().expect("no address");
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || sender.send(client(address)));
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            server.poll().expect("poll failed");
+            if let Ok(output) = receiver.try_recv() {
+                return output;
+            }
+            assert!(Instant::now() < deadline, "client timed out");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn get(address: SocketAddr, path: &str) -> (String, Vec<u8>) {
+        let mut stream = TcpStream::connect(address).expect("connect failed");
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").expect("write failed");
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).expect("read failed");
+        let split = response.windows(4).position(|window| window == b"\r\n\r\n").expect("no body");
+        let header = String::from_utf8_lossy(&response[..split]);
+        let status = header.lines().next().and_then(|line| line.split_once(' ')).map(|(_, status)| status);
+        let status = status.unwrap_or_default().to_string();
+        (status, response[split + 4..].to_vec())
+    }
+
+    fn get_text(address: SocketAddr, path: &str) -> (String, String) {
+        let (status, body) = get(address, path);
+        (status, String::from_utf8(body).expect("not utf8"))
+    }
+
+    // Writing twice within the file system's timestamp resolution wouldn't look like a change
+    // otherwise.
+    fn write_presentation(path: &Path, contents: &str, age: u64) {
+        fs::write(path, contents).expect("write failed");
+        let modified = SystemTime::now() - Duration::from_secs(age);
+        filetime::set_file_mtime(path, FileTime::from_system_time(modified)).expect("touch failed");
+    }
+
+    fn resources(path: &Path) -> Resources {
+        Resources::new(path.parent().expect("no parent"), Default::default())
+    }
+
+    fn setup(contents: &str) -> (TempDir, PathBuf) {
+        let directory = tempdir().expect("creating tempdir");
+        let path = directory.path().join("presentation.md");
+        write_presentation(&path, contents, 100);
+        (directory, path)
+    }
+
+    #[test]
+    fn regenerates_on_change() {
+        let (_directory, path) = setup("# hi");
+        let arena = Arena::new();
+        let exporter = RevealExporter::new(MarkdownParser::new(&arena), resources(&path), Default::default());
+        let mut server = PreviewServer::bind(exporter, &path, "127.0.0.1:0").expect("bind failed");
+
+        let client_path = path.clone();
+        let responses = run_client(&mut server, move |address| {
+            let first = get_text(address, "/");
+            write_presentation(&client_path, "# bye", 50);
+            // This one only comes back once the server notices the change.
+            let version = get_text(address, &format!("{RELOAD_PATH}?version=0"));
+            let second = get_text(address, "/");
+            write_presentation(&client_path, ":::: columns\n", 0);
+            get_text(address, &format!("{RELOAD_PATH}?version=1"));
+            let broken = get_text(address, "/");
+            (first, version, second, broken)
+        });
+        let (first, version, second, broken) = responses;
+        assert_eq!(first.0, "200 OK");
+        assert!(first.1.contains("<h1>hi</h1>"), "{}", first.1);
+        assert!(first.1.contains("version=0"));
+        assert_eq!(version, ("200 OK".into(), "1".into()));
+        assert!(second.1.contains("<h1>bye</h1>"), "{}", second.1);
+        assert!(second.1.contains("version=1"));
+
+        // Errors are displayed rather than stopping the server.
+        assert_eq!(broken.0, "500 Internal Server Error");
+        assert!(broken.1.contains("parse error at 1:1"), "{}", broken.1);
+        assert!(broken.1.contains("version=2"));
+    }
+
+    #[rstest]
+    #[case::referenced("/image.png", "200 OK", b"png")]
+    #[case::escaped("/my%20image.png", "200 OK", b"spaced")]
+    #[case::unreferenced("/secret.png", "404 Not Found", b"Not Found")]
+    #[case::presentation("/presentation.md", "404 Not Found", b"Not Found")]
+    #[case::outside("/../image.png", "404 Not Found", b"Not Found")]
+    fn images(#[case] request: &'static str, #[case] status: &str, #[case] body: &[u8]) {
+        let (directory, path) = setup("![](image.png)\n\n![](<my image.png>)");
+        fs::write(directory.path().join("image.png"), "png").expect("write failed");
+        fs::write(directory.path().join("my image.png"), "spaced").expect("write failed");
+        fs::write(directory.path().join("secret.png"), "secret").expect("write failed");
+        let arena = Arena::new();
+        let exporter = RevealExporter::new(MarkdownParser::new(&arena), resources(&path), Default::default());
+        let mut server = PreviewServer::bind(exporter, &path, "127.0.0.1:0").expect("bind failed");
+
+        let response = run_client(&mut server, move |address| get(address, request));
+        assert_eq!(response, (status.to_string(), body.to_vec()));
+    }
+
+    #[test]
+    fn bundled_reveal_js() {
+        let (_directory, path) = setup("# hi");
+        let arena = Arena::new();
+        let exporter = RevealExporter::new(MarkdownParser::new(&arena), resources(&path), Default::default());
+        let mut server = PreviewServer::bind(exporter, &path, "127.0.0.1:0").expect("bind failed");
+
+        let (status, body) = run_client(&mut server, |address| get(address, "/reveal.js/reveal.js"));
+        match reveal_js_asset("reveal.js/reveal.js") {
+            Some(contents) => assert_eq!((status.as_str(), body.as_slice()), ("200 OK", contents)),
+            None => assert_eq!(status, "404 Not Found"),
+        };
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sandboxed_images() {
+        let (directory, path) = setup("![](image.png)");
+        let outside = tempdir().expect("creating tempdir");
+        fs::write(outside.path().join("secret.png"), "secret").expect("write failed");
+        std::os::unix::fs::symlink(outside.path().join("secret.png"), directory.path().join("image.png"))
+            .expect("failed to link");
+        let arena = Arena::new();
+        let resources = resources(&path).sandboxed().expect("failed to sandbox");
+        let exporter = RevealExporter::new(MarkdownParser::new(&arena), resources, Default::default());
+        let mut server = PreviewServer::bind(exporter, &path, "127.0.0.1:0").expect("bind failed");
+
+        let (index, image) = run_client(&mut server, |address| (get_text(address, "/"), get(address, "/image.png")));
+        assert_eq!(index.0, "500 Internal Server Error");
+        assert!(index.1.contains("outside of the presentation&#39;s directory"), "{}", index.1);
+        assert_eq!(image.0, "404 Not Found");
+    }
+
+    #[test]
+    fn idle_connections() {
+        let (_directory, path) = setup("# hi");
+        let arena = Arena::new();
+        let exporter = RevealExporter::new(MarkdownParser::new(&arena), resources(&path), Default::default());
+        let mut server = PreviewServer::bind(exporter, &path, "127.0.0.1:0").expect("bind failed");
+
+        // A connection that never sends anything, like a browser preconnecting, doesn't hold up other requests.
+        let (status, elapsed) = run_client(&mut server, |address| {
+            let _idle = TcpStream::connect(address).expect("connect failed");
+            let start = Instant::now();
+            let (status, _) = get(address, "/");
+            (status, start.elapsed())
+        });
+        assert_eq!(status, "200 OK");
+        assert!(elapsed < READ_TIMEOUT / 2, "request took {elapsed:?}");
+    }
+
+    #[test]
+    fn stalled_client() {
+        let (directory, path) = setup("![](image.png)");
+        // This has to be larger than what the socket buffers can hold so writing it stalls.
+        fs::write(directory.path().join("image.png"), vec![0; 32 * 1024 * 1024]).expect("write failed");
+        let arena = Arena::new();
+        let exporter = RevealExporter::new(MarkdownParser::new(&arena), resources(&path), Default::default());
+        let mut server = PreviewServer::bind(exporter, &path, "127.0.0.1:0").expect("bind failed");
+
+        // A client that requests the image but never reads the response only holds up the server for a while.
+        let status = run_client(&mut server, |address| {
+            let mut stalled = TcpStream::connect(address).expect("connect failed");
+            write!(stalled, "GET /image.png HTTP/1.1\r\nHost: localhost\r\n\r\n").expect("write failed");
+            let (status, _) = get(address, "/");
+            status
+        });
+        assert_eq!(status, "200 OK");
+    }
+
+    #[test]
+    fn unsupported_method() {
+        let (_directory, path) = setup("# hi");
+        let arena = Arena::new();
+        let exporter = RevealExporter::new(MarkdownParser::new(&arena), resources(&path), Default::default());
+        let mut server = PreviewServer::bind(exporter, &path, "127.0.0.1:0").expect("bind failed");
+
+        let response = run_client(&mut server, |address| {
+            let mut stream = TcpStream::connect(address).expect("connect failed");
+            write!(stream, "POST / HTTP/1.1\r\n\r\n").expect("write failed");
+            let mut response = String::new();
+            stream.read_to_string(&mut response).expect("read failed");
+            response
+        });
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed"), "{response}");
+    }
+}
@@ -0,0 +1,288 @@
+//! Persistence of the position within a presentation so it can be resumed later.
+//!
+//! The current slide and step are written into a small JSON file per presentation, so relaunching
+//! after a crash or after quitting by accident can take you back to where you were.
+
+use crate::presentation::Presentation;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+// The minimum amount of time between two writes of the session state.
+const WRITE_DEBOUNCE: Duration = Duration::from_secs(1);
+
+// How old a session can be for it to still be worth suggesting to resume it.
+const RECENT_SESSION: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// The position within a presentation when it was last viewed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionState {
+    /// The zero based index of the slide.
+    pub slide: usize,
+
+    /// The zero based index of the step within the slide.
+    pub step: usize,
+
+    /// The title of the slide, if it has one.
+    pub title: Option<String>,
+
+    /// The number of slides in the presentation.
+    pub slide_count: usize,
+
+    /// The number of seconds since the epoch when this state was saved.
+    pub saved_at: u64,
+}
+
+impl SessionState {
+    /// Capture the current position within a presentation.
+    pub(crate) fn capture(presentation: &Presentation) -> Option<Self> {
+        if presentation.slide_count() == 0 {
+            return None;
+        }
+        let saved_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        Some(Self {
+            slide: presentation.current_slide_index(),
+            step: presentation.current_step(),
+            title: presentation.current_slide().title().map(String::from),
+            slide_count: presentation.slide_count(),
+            saved_at,
+        })
+    }
+
+    /// Whether this state was saved recently enough to suggest resuming it.
+    pub fn is_recent(&self, now: SystemTime) -> bool {
+        let saved_at = UNIX_EPOCH + Duration::from_secs(self.saved_at);
+        now.duration_since(saved_at).map(|age| age <= RECENT_SESSION).unwrap_or(true)
+    }
+
+    /// Find the slide and step this state points to in a presentation.
+    ///
+    /// If the slide was titled, it's looked up by its title in case slides were added or removed
+    /// before it. Otherwise the slide index is used as long as the number of slides didn't change.
+    /// `None` is returned if the presentation changed too much for the position to be found.
+    pub(crate) fn locate(&self, presentation: &Presentation) -> Option<(usize, usize)> {
+        let slides: Vec<_> = presentation.iter_slides().collect();
+        let slide = match &self.title {
+            Some(title) if slides.get(self.slide).and_then(|slide| slide.title()) == Some(title) => self.slide,
+            Some(title) => slides.iter().position(|slide| slide.title() == Some(title))?,
+            None if slides.len() == self.slide_count && self.slide < slides.len() => self.slide,
+            None => return None,
+        };
+        let step = if self.step < slides[slide].steps().total() { self.step } else { 0 };
+        Some((slide, step))
+    }
+}
+
+/// Stores the session state of every presentation in a directory.
+#[derive(Clone, Debug)]
+pub struct SessionStore {
+    directory: PathBuf,
+}
+
+impl SessionStore {
+    /// Construct a store that keeps session states in the given directory.
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    /// Load the session state for the presentation in the given path.
+    ///
+    /// Any errors, including the state file being corrupted, are treated as there being no state.
+    pub fn load(&self, presentation_path: &Path) -> Option<SessionState> {
+        let contents = fs::read(self.state_path(presentation_path)).ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    /// Save the session state for the presentation in the given path.
+    pub(crate) fn save(&self, presentation_path: &Path, state: &SessionState) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.state_path(presentation_path);
+        // Write into a temporary file first so a crash never leaves a half written state behind.
+        let temporary_path = path.with_extension("json.tmp");
+        fs::write(&temporary_path, serde_json::to_vec(state)?)?;
+        fs::rename(temporary_path, path)
+    }
+
+    fn state_path(&self, presentation_path: &Path) -> PathBuf {
+        let path = presentation_path.canonicalize().unwrap_or_else(|_| presentation_path.into());
+        let key = fnv1a(path.as_os_str().as_encoded_bytes());
+        self.directory.join(format!("{key:016x}.json"))
+    }
+}
+
+// A tiny hash function whose output, unlike the standard library's hasher, is stable across
+// releases so state files can be found again after upgrading.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Keeps a presentation's session state up to date as it's navigated.
+///
+/// Writes are debounced so quickly going through slides doesn't write a file on every key press.
+pub(crate) struct SessionRecorder {
+    store: SessionStore,
+    presentation_path: PathBuf,
+    pending: Option<SessionState>,
+    position: Option<(usize, usize)>,
+    last_write: Option<Instant>,
+}
+
+impl SessionRecorder {
+    pub(crate) fn new(store: SessionStore, presentation_path: PathBuf) -> Self {
+        Self { store, presentation_path, pending: None, position: None, last_write: None }
+    }
+
+    /// Record the presentation's current position, if it changed.
+    pub(crate) fn moved(&mut self, presentation: &Presentation) {
+        let Some(state) = SessionState::capture(presentation) else {
+            return;
+        };
+        let position = (state.slide, state.step);
+        if self.position != Some(position) {
+            self.position = Some(position);
+            self.pending = Some(state);
+        }
+    }
+
+    /// Write the pending state if enough time went by since the last write.
+    pub(crate) fn tick(&mut self, now: Instant) {
+        let due = self.last_write.map(|last_write| now.duration_since(last_write) >= WRITE_DEBOUNCE).unwrap_or(true);
+        if due && self.pending.is_some() {
+            self.write(now);
+        }
+    }
+
+    /// Write the pending state right away.
+    pub(crate) fn flush(&mut self) {
+        self.write(Instant::now());
+    }
+
+    fn write(&mut self, now: Instant) {
+        if let Some(state) = self.pending.take() {
+            // There's nowhere to display errors while presenting and this is a best effort anyway.
+            let _ = self.store.save(&self.presentation_path, &state);
+            self.last_write = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::presentation::{RenderOperation, SlideBuilder, SlideChunk};
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    fn build_presentation(titles: &[Option<&str>]) -> Presentation {
+        let chunk = || SlideChunk::new(vec![RenderOperation::ClearScreen], vec![]);
+        let slides: Vec<_> = titles
+            .iter()
+            .map(|title| {
+                let builder = SlideBuilder::default().chunks(vec![chunk(), chunk()]);
+                match title {
+                    Some(title) => builder.title(title.to_string()),
+                    None => builder,
+                }
+                .build()
+            })
+            .collect();
+        Presentation::from(slides)
+    }
+
+    fn state(slide: usize, step: usize, title: Option<&str>, slide_count: usize) -> SessionState {
+        SessionState { slide, step, title: title.map(String::from), slide_count, saved_at: 0 }
+    }
+
+    #[rstest]
+    #[case::same_slide(state(1, 1, Some("b"), 3), &[Some("a"), Some("b"), Some("c")], Some((1, 1)))]
+    #[case::slide_inserted_before(state(1, 1, Some("b"), 3), &[Some("a"), Some("new"), Some("b")], Some((2, 1)))]
+    #[case::slide_removed(state(1, 0, Some("b"), 3), &[Some("a"), Some("c")], None)]
+    #[case::untitled(state(1, 1, None, 2), &[None, None], Some((1, 1)))]
+    #[case::untitled_count_changed(state(1, 1, None, 2), &[None, None, None], None)]
+    #[case::step_out_of_range(state(0, 5, Some("a"), 1), &[Some("a")], Some((0, 0)))]
+    #[case::empty(state(0, 0, None, 1), &[], None)]
+    fn locate(#[case] state: SessionState, #[case] titles: &[Option<&str>], #[case] expected: Option<(usize, usize)>) {
+        let presentation = build_presentation(titles);
+        assert_eq!(state.locate(&presentation), expected);
+    }
+
+    #[test]
+    fn capture() {
+        let mut presentation = build_presentation(&[Some("a"), Some("b")]);
+        presentation.jump_next();
+        presentation.jump_next();
+        let captured = SessionState::capture(&presentation).expect("no state");
+        assert_eq!(captured, SessionState { saved_at: captured.saved_at, ..state(1, 0, Some("b"), 2) });
+        assert!(SessionState::capture(&build_presentation(&[])).is_none());
+    }
+
+    #[test]
+    fn serialization() {
+        let state = state(3, 1, Some("intro"), 10);
+        let serialized = serde_json::to_string(&state).expect("serialization failed");
+        assert_eq!(serialized, r#"{"slide":3,"step":1,"title":"intro","slide_count":10,"saved_at":0}"#);
+        assert_eq!(serde_json::from_str::<SessionState>(&serialized).expect("deserialization failed"), state);
+    }
+
+    #[test]
+    fn keyed_by_canonical_path() {
+        let directory = tempdir().expect("creating tempdir");
+        let presentation_path = directory.path().join("slides.md");
+        fs::write(&presentation_path, "").expect("writing presentation");
+        let store = SessionStore::new(directory.path().join("state"));
+
+        let state = state(1, 0, None, 2);
+        store.save(&presentation_path, &state).expect("saving failed");
+        let relative_path = directory.path().join(".").join("slides.md");
+        assert_eq!(store.load(&relative_path), Some(state));
+        assert_eq!(store.load(&directory.path().join("other.md")), None);
+    }
+
+    #[test]
+    fn corrupted_state() {
+        let directory = tempdir().expect("creating tempdir");
+        let store = SessionStore::new(directory.path().into());
+        let presentation_path = directory.path().join("slides.md");
+        fs::write(store.state_path(&presentation_path), "potato").expect("writing state");
+        assert_eq!(store.load(&presentation_path), None);
+    }
+
+    #[rstest]
+    #[case::recent(Duration::from_secs(60), true)]
+    #[case::old(Duration::from_secs(60 * 60 * 48), false)]
+    fn recent(#[case] age: Duration, #[case] expected: bool) {
+        let state = state(0, 0, None, 1);
+        assert_eq!(state.is_recent(UNIX_EPOCH + age), expected);
+    }
+
+    #[test]
+    fn debounced_writes() {
+        let directory = tempdir().expect("creating tempdir");
+        let store = SessionStore::new(directory.path().into());
+        let presentation_path = directory.path().join("slides.md");
+        let mut recorder = SessionRecorder::new(store.clone(), presentation_path.clone());
+        let mut presentation = build_presentation(&[Some("a"), Some("b")]);
+        let start = Instant::now();
+
+        recorder.moved(&presentation);
+        recorder.tick(start);
+        assert_eq!(store.load(&presentation_path).map(|state| state.step), Some(0));
+
+        // This one is too close to the last write so it's held back.
+        presentation.jump_next();
+        recorder.moved(&presentation);
+        recorder.tick(start + Duration::from_millis(100));
+        assert_eq!(store.load(&presentation_path).map(|state| state.step), Some(0));
+
+        recorder.tick(start + WRITE_DEBOUNCE);
+        assert_eq!(store.load(&presentation_path).map(|state| state.step), Some(1));
+
+        presentation.jump_next();
+        recorder.moved(&presentation);
+        recorder.flush();
+        assert_eq!(store.load(&presentation_path).map(|state| state.slide), Some(1));
+    }
+}
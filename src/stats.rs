@@ -0,0 +1,219 @@
+//! Statistics about a presentation, like how long it takes to go through each of its slides.
+
+use crate::{
+    custom::KeyBindingsConfig,
+    markdown::parse::ParseError,
+    presentation::{Presentation, TodoNote},
+    processing::builder::{BuildError, PresentationBuilder, PresentationBuilderOptions, Themes},
+    typst::TypstRender,
+    MarkdownParser, PresentationTheme, Resources,
+};
+use std::{
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+    time::Duration,
+};
+
+/// The number of characters a speaker is assumed to get through every second.
+const CHARS_PER_SECOND: usize = 250;
+
+/// Collects statistics about presentations.
+pub struct StatsCollector<'a> {
+    parser: MarkdownParser<'a>,
+    default_theme: &'a PresentationTheme,
+    resources: Resources,
+    typst: TypstRender,
+    themes: Themes,
+    options: PresentationBuilderOptions,
+}
+
+impl<'a> StatsCollector<'a> {
+    /// Construct a new stats collector.
+    pub fn new(
+        parser: MarkdownParser<'a>,
+        default_theme: &'a PresentationTheme,
+        resources: Resources,
+        typst: TypstRender,
+        themes: Themes,
+        options: PresentationBuilderOptions,
+    ) -> Self {
+        Self { parser, default_theme, resources, typst, themes, options }
+    }
+
+    /// Collect the statistics for the given presentation.
+    pub fn collect(&mut self, presentation_path: &Path) -> Result<PresentationStats, StatsError> {
+        let content = fs::read_to_string(presentation_path).map_err(StatsError::Read)?;
+        let elements = self.parser.parse(&content)?;
+        let presentation = PresentationBuilder::new(
+            self.default_theme,
+            &mut self.resources,
+            &mut self.typst,
+            &self.themes,
+            Default::default(),
+            KeyBindingsConfig::default(),
+            self.options.clone(),
+        )
+        .build(elements)?;
+        Ok(PresentationStats::from(&presentation))
+    }
+}
+
+/// The statistics for a presentation.
+#[derive(Clone, Debug)]
+pub struct PresentationStats {
+    slides: Vec<SlideStats>,
+    todos: Vec<TodoNote>,
+}
+
+impl PresentationStats {
+    /// Get the `TODO`/`FIXME` notes left in the presentation.
+    pub fn todos(&self) -> &[TodoNote] {
+        &self.todos
+    }
+}
+
+impl From<&Presentation> for PresentationStats {
+    fn from(presentation: &Presentation) -> Self {
+        let slides = presentation
+            .iter_slides()
+            .enumerate()
+            .map(|(index, slide)| SlideStats {
+                number: presentation.numbering().display_number(index),
+                title: slide.title().map(ToString::to_string),
+                char_count: slide.char_count(),
+                code_char_count: slide.code_char_count(),
+            })
+            .collect();
+        Self { slides, todos: presentation.todos().to_vec() }
+    }
+}
+
+impl Display for PresentationStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>5}  {:>7}  {:>7}  {:>8}  title", "slide", "chars", "code", "time")?;
+        for slide in &self.slides {
+            let number = slide.number.map(|number| number.to_string()).unwrap_or_else(|| "-".into());
+            let title = slide.title.as_deref().unwrap_or("<no title>");
+            let time = format_duration(slide.reading_time());
+            writeln!(f, "{number:>5}  {:>7}  {:>7}  {time:>8}  {title}", slide.char_count, slide.code_char_count)?;
+        }
+        let char_count: usize = self.slides.iter().map(|slide| slide.char_count).sum();
+        let code_char_count: usize = self.slides.iter().map(|slide| slide.code_char_count).sum();
+        let time = format_duration(self.slides.iter().map(SlideStats::reading_time).sum());
+        writeln!(f, "{:>5}  {char_count:>7}  {code_char_count:>7}  {time:>8}", "total")?;
+        if !self.todos.is_empty() {
+            writeln!(f, "\n{} TODO/FIXME notes:", self.todos.len())?;
+            for todo in &self.todos {
+                writeln!(f, "  {todo}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The statistics for a single slide.
+#[derive(Clone, Debug)]
+struct SlideStats {
+    number: Option<usize>,
+    title: Option<String>,
+    char_count: usize,
+    code_char_count: usize,
+}
+
+impl SlideStats {
+    /// The estimated time it takes to go through this slide.
+    ///
+    /// Code is left out of this as it's usually not read out loud.
+    fn reading_time(&self) -> Duration {
+        Duration::from_secs_f64(self.char_count as f64 / CHARS_PER_SECOND as f64)
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64().ceil() as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// An error when collecting a presentation's statistics.
+#[derive(thiserror::Error, Debug)]
+pub enum StatsError {
+    #[error("failed to read presentation: {0}")]
+    Read(io::Error),
+
+    #[error("failed to parse presentation: {0}")]
+    Parse(#[from] ParseError),
+
+    #[error("failed to build presentation: {0}")]
+    Build(#[from] BuildError),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::presentation::SlideReference;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::empty(0, "0:00")]
+    #[case::partial_second(10, "0:01")]
+    #[case::exact(500, "0:02")]
+    #[case::minutes(250 * 75, "1:15")]
+    fn reading_time(#[case] char_count: usize, #[case] expected: &str) {
+        let slide = SlideStats { number: Some(1), title: None, char_count, code_char_count: 1000 };
+        assert_eq!(format_duration(slide.reading_time()), expected);
+    }
+
+    #[test]
+    fn display() {
+        let stats = PresentationStats {
+            slides: vec![
+                SlideStats { number: Some(1), title: Some("intro".into()), char_count: 500, code_char_count: 0 },
+                SlideStats { number: None, title: Some("backup".into()), char_count: 0, code_char_count: 0 },
+                SlideStats { number: Some(2), title: None, char_count: 250, code_char_count: 30 },
+            ],
+            todos: vec![TodoNote { slide: SlideReference::Numbered(2), line: 7, text: "TODO: add numbers".into() }],
+        };
+        let expected = "\
+slide    chars     code      time  title
+    1      500        0      0:02  intro
+    -        0        0      0:00  backup
+    2      250       30      0:01  <no title>
+total      750       30      0:03
+
+1 TODO/FIXME notes:
+  slide 2, line 7: TODO: add numbers
+";
+        assert_eq!(stats.to_string(), expected);
+    }
+
+    #[rstest]
+    #[case::none("hi\n\n<!-- pause -->\n", 0)]
+    #[case::todo_and_fixme("<!-- TODO: numbers -->\n\nhi\n\n<!-- FIXME -->\n", 2)]
+    #[case::multi_line("<!-- TODO: numbers\nand more -->\n", 0)]
+    #[case::front_matter_prefix(
+        "---\noptions:\n  command_prefix: \"cmd:\"\n---\n\n<!-- cmd:TODO -->\n<!-- TODO -->\n",
+        1
+    )]
+    fn todo_count(#[case] contents: &str, #[case] expected: usize) {
+        let arena = comrak::Arena::new();
+        let elements = MarkdownParser::new(&arena).parse(contents).expect("parse failed");
+        let theme = PresentationTheme::default();
+        let mut resources = Resources::new("/tmp", Default::default());
+        let mut typst = TypstRender::default();
+        let themes = Themes::default();
+        let bindings = KeyBindingsConfig::default();
+        let options = Default::default();
+        let builder = PresentationBuilder::new(
+            &theme,
+            &mut resources,
+            &mut typst,
+            &themes,
+            Default::default(),
+            bindings,
+            options,
+        );
+        let presentation = builder.build(elements).expect("build failed");
+        assert_eq!(presentation.todos().len(), expected);
+    }
+}
@@ -1,5 +1,10 @@
-use crossterm::style::Stylize;
+use crossterm::style::Attribute;
 use hex::{FromHex, FromHexError};
+use schemars::{
+    r#gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, Schema, SchemaObject, StringValidation},
+    JsonSchema,
+};
 use serde::{Deserialize, Serialize};
 use serde_with::{DeserializeFromStr, SerializeDisplay};
 use std::{
@@ -8,13 +13,18 @@ use std::{
 };
 
 /// The style of a piece of text.
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct TextStyle {
     flags: u8,
     pub(crate) colors: Colors,
 }
 
 impl TextStyle {
+    /// A style without any formatting or colors.
+    ///
+    /// This is the same as [TextStyle::default] but can be used in `const` and `static` contexts.
+    pub(crate) const PLAIN: Self = Self { flags: 0, colors: Colors { background: None, foreground: None } };
+
     /// Add bold to this style.
     pub(crate) fn bold(self) -> Self {
         self.add_flag(TextFormatFlags::Bold)
@@ -40,6 +50,16 @@ impl TextStyle {
         self.add_flag(TextFormatFlags::Underlined)
     }
 
+    /// Add dim to this style.
+    pub(crate) fn dim(self) -> Self {
+        self.add_flag(TextFormatFlags::Dim)
+    }
+
+    /// Swap the foreground and background colors in this style.
+    pub(crate) fn inverse(self) -> Self {
+        self.add_flag(TextFormatFlags::Inverse)
+    }
+
     /// Indicate this is a link.
     pub(crate) fn link(self) -> Self {
         self.italics().underlined()
@@ -76,6 +96,31 @@ impl TextStyle {
         self.has_flag(TextFormatFlags::Underlined)
     }
 
+    /// Check whether this text style is dimmed.
+    pub(crate) fn is_dim(&self) -> bool {
+        self.has_flag(TextFormatFlags::Dim)
+    }
+
+    /// Check whether this text style swaps its foreground and background colors.
+    pub(crate) fn is_inverse(&self) -> bool {
+        self.has_flag(TextFormatFlags::Inverse)
+    }
+
+    /// Get the terminal attributes needed to display this style.
+    ///
+    /// Whether this is a piece of code only affects the colors it's given, so it doesn't need any.
+    pub(crate) fn attributes(&self) -> Vec<Attribute> {
+        let attributes = [
+            (self.is_bold(), Attribute::Bold),
+            (self.is_italics(), Attribute::Italic),
+            (self.is_strikethrough(), Attribute::CrossedOut),
+            (self.is_underlined(), Attribute::Underlined),
+            (self.is_dim(), Attribute::Dim),
+            (self.is_inverse(), Attribute::Reverse),
+        ];
+        attributes.into_iter().filter_map(|(enabled, attribute)| enabled.then_some(attribute)).collect()
+    }
+
     /// Merge this style with another one.
     pub(crate) fn merge(&mut self, other: &TextStyle) {
         self.flags |= other.flags;
@@ -83,31 +128,6 @@ impl TextStyle {
         self.colors.foreground = self.colors.foreground.or(other.colors.foreground);
     }
 
-    /// Apply this style to a piece of text.
-    pub(crate) fn apply<T: Into<String>>(&self, text: T) -> <String as Stylize>::Styled {
-        let text: String = text.into();
-        let mut styled = text.stylize();
-        if self.is_bold() {
-            styled = styled.bold();
-        }
-        if self.is_italics() {
-            styled = styled.italic();
-        }
-        if self.is_strikethrough() {
-            styled = styled.crossed_out();
-        }
-        if self.is_underlined() {
-            styled = styled.underlined();
-        }
-        if let Some(color) = self.colors.background {
-            styled = styled.on(color.into());
-        }
-        if let Some(color) = self.colors.foreground {
-            styled = styled.with(color.into());
-        }
-        styled
-    }
-
     fn add_flag(mut self, flag: TextFormatFlags) -> Self {
         self.flags |= flag as u8;
         self
@@ -118,6 +138,13 @@ impl TextStyle {
     }
 }
 
+impl Default for TextStyle {
+    #[inline]
+    fn default() -> Self {
+        Self::PLAIN
+    }
+}
+
 #[derive(Debug)]
 enum TextFormatFlags {
     Bold = 1,
@@ -125,6 +152,8 @@ enum TextFormatFlags {
     Code = 4,
     Strikethrough = 8,
     Underlined = 16,
+    Dim = 32,
+    Inverse = 64,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, SerializeDisplay, DeserializeFromStr)]
@@ -141,6 +170,11 @@ impl Color {
             _ => None,
         }
     }
+
+    /// Check whether this color means "use the terminal's default color".
+    pub(crate) fn is_inherit(&self) -> bool {
+        self.0 == crossterm::style::Color::Reset
+    }
 }
 
 impl FromStr for Color {
@@ -164,6 +198,8 @@ impl FromStr for Color {
             "dark_magenta" => Self(C::DarkMagenta),
             "cyan" => Self(C::Cyan),
             "dark_cyan" => Self(C::DarkCyan),
+            // Use whatever color the terminal uses by default.
+            "inherit" => Self(C::Reset),
             // Fallback to hex-encoded rgb
             _ => {
                 let values = <[u8; 3]>::from_hex(input)?;
@@ -194,11 +230,33 @@ impl Display for Color {
             C::DarkMagenta => write!(f, "dark_magenta"),
             C::Cyan => write!(f, "cyan"),
             C::DarkCyan => write!(f, "dark_cyan"),
+            C::Reset => write!(f, "inherit"),
             _ => panic!("unsupported color"),
         }
     }
 }
 
+impl JsonSchema for Color {
+    fn schema_name() -> String {
+        "Color".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let pattern = "^(black|white|grey|red|dark_red|green|dark_green|blue|dark_blue|yellow|dark_yellow|magenta|\
+                       dark_magenta|cyan|dark_cyan|inherit|[0-9a-fA-F]{6})$";
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            metadata: Some(Box::new(Metadata {
+                description: Some("A color name, `inherit`, or a hex encoded RGB color like `ff0000`.".into()),
+                ..Default::default()
+            })),
+            string: Some(Box::new(StringValidation { pattern: Some(pattern.into()), ..Default::default() })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 impl From<Color> for crossterm::style::Color {
     fn from(value: Color) -> Self {
         value.0
@@ -206,7 +264,7 @@ impl From<Color> for crossterm::style::Color {
 }
 
 /// Text colors.
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize, JsonSchema)]
 pub(crate) struct Colors {
     /// The background color.
     pub(crate) background: Option<Color>,
@@ -239,9 +297,31 @@ pub(crate) struct ParseColorError(#[from] FromHexError);
 mod test {
     use super::*;
 
+    #[test]
+    fn plain_style() {
+        const STYLE: TextStyle = TextStyle::PLAIN;
+        assert_eq!(STYLE, TextStyle::default());
+        assert!(!STYLE.is_bold() && !STYLE.is_italics() && !STYLE.is_code());
+    }
+
+    #[test]
+    fn inverse_attribute() {
+        let style = TextStyle::default().bold().inverse();
+        assert!(style.is_inverse());
+        assert_eq!(style.attributes(), &[Attribute::Bold, Attribute::Reverse]);
+    }
+
     #[test]
     fn color_serde() {
         let color: Color = "beef42".parse().unwrap();
         assert_eq!(color.to_string(), "beef42");
     }
+
+    #[test]
+    fn inherit_color() {
+        let color: Color = "inherit".parse().unwrap();
+        assert_eq!(color.to_string(), "inherit");
+        assert_eq!(color.as_rgb(), None);
+        assert_eq!(crossterm::style::Color::from(color), crossterm::style::Color::Reset);
+    }
 }
@@ -1,9 +1,20 @@
 use crate::style::{Color, Colors};
-use serde::{Deserialize, Serialize};
+use schemars::{
+    r#gen::SchemaGenerator,
+    schema::{InstanceType, ObjectValidation, Schema, SchemaObject},
+    JsonSchema,
+};
+use serde::{de, Deserialize, Deserializer, Serialize};
 use std::{collections::BTreeMap, fs, io, path::Path};
 
 include!(concat!(env!("OUT_DIR"), "/themes.rs"));
 
+static BUILTIN_LAYOUTS: Lazy<BTreeMap<String, SlideLayout>> = Lazy::new(|| {
+    let contents = include_bytes!("../layouts/builtin.yaml");
+    // This is going to be caught by the test down here.
+    serde_yaml::from_slice(contents).expect("corrupted built-in layouts")
+});
+
 #[derive(Default)]
 pub struct PresentationThemeSet {
     custom_themes: BTreeMap<String, PresentationTheme>,
@@ -38,7 +49,7 @@ impl PresentationThemeSet {
                 if THEMES.contains_key(theme_name) {
                     return Err(LoadThemeError::Duplicate(theme_name.into()));
                 }
-                let theme = PresentationTheme::from_path(&entry.path())?;
+                let theme = PresentationTheme::from_path(entry.path())?;
                 self.custom_themes.insert(theme_name.into(), theme);
             }
         }
@@ -54,7 +65,7 @@ impl PresentationThemeSet {
 }
 
 /// A presentation theme.
-#[derive(Default, Clone, Debug, Deserialize, Serialize)]
+#[derive(Default, Clone, Debug, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct PresentationTheme {
     /// The style for a slide's title.
@@ -75,17 +86,26 @@ pub struct PresentationTheme {
 
     /// The style for a table.
     #[serde(default)]
-    pub(crate) table: Option<Alignment>,
+    pub(crate) table: TableStyle,
 
     /// The style for a block quote.
     #[serde(default)]
     pub(crate) block_quote: BlockQuoteStyle,
 
+    /// The style for lists.
+    #[serde(default)]
+    pub(crate) list: ListStyle,
+
     /// The default style.
     #[serde(rename = "default", default)]
     pub(crate) default_style: DefaultStyle,
 
-    //// The style of all headings.
+    /// A vertical gradient, from the first color at the top to the second one at the bottom, to
+    /// be used as the background of every slide.
+    #[serde(default)]
+    pub(crate) background_gradient: Option<(Color, Color)>,
+
+    /// The style of all headings.
     #[serde(default)]
     pub(crate) headings: HeadingStyles,
 
@@ -101,9 +121,17 @@ pub struct PresentationTheme {
     #[serde(default)]
     pub(crate) typst: TypstStyle,
 
-    /// The style for typst auto-rendered code blocks.
+    /// The style for modals.
     #[serde(default)]
     pub(crate) modals: ModalStyle,
+
+    /// The style for countdown timers.
+    #[serde(default)]
+    pub(crate) countdown: CountdownStyle,
+
+    /// The named slide layouts, selectable via the `layout` command.
+    #[serde(default)]
+    pub(crate) layouts: BTreeMap<String, SlideLayout>,
 }
 
 impl PresentationTheme {
@@ -134,18 +162,34 @@ impl PresentationTheme {
             PresentationTitle => &self.intro_slide.title.alignment,
             PresentationSubTitle => &self.intro_slide.subtitle.alignment,
             PresentationAuthor => &self.intro_slide.author.alignment,
-            Table => &self.table,
+            Table => &self.table.alignment,
             BlockQuote => &self.block_quote.alignment,
+            // Countdowns are centered unless told otherwise.
+            Countdown => {
+                let center = Alignment::Center { minimum_margin: Margin::Fixed(0), minimum_size: 0 };
+                return self.countdown.alignment.clone().unwrap_or(center);
+            }
         };
         alignment.clone().unwrap_or_default()
     }
+
+    /// Get a slide layout by name.
+    ///
+    /// Layouts defined in the theme take precedence over the built-in ones.
+    pub(crate) fn layout(&self, name: &str) -> Option<SlideLayout> {
+        if let Some(layout) = self.layouts.get(name) {
+            return Some(layout.clone());
+        }
+        BUILTIN_LAYOUTS.get(name).cloned()
+    }
 }
 
 /// The style of a slide title.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct SlideTitleStyle {
     /// The alignment.
     #[serde(flatten, default)]
+    #[schemars(with = "OptionalAlignment")]
     pub(crate) alignment: Option<Alignment>,
 
     /// Whether to use a separator line.
@@ -178,7 +222,7 @@ pub(crate) struct SlideTitleStyle {
 }
 
 /// The style for all headings.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct HeadingStyles {
     /// H1 style.
     #[serde(default)]
@@ -206,10 +250,11 @@ pub(crate) struct HeadingStyles {
 }
 
 /// The style for a heading.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct HeadingStyle {
     /// The alignment.
     #[serde(flatten, default)]
+    #[schemars(with = "OptionalAlignment")]
     pub(crate) alignment: Option<Alignment>,
 
     /// The prefix to be added to this heading.
@@ -223,11 +268,27 @@ pub(crate) struct HeadingStyle {
     pub(crate) colors: Colors,
 }
 
+/// The style of a table.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub(crate) struct TableStyle {
+    /// The alignment.
+    #[serde(flatten, default, deserialize_with = "deserialize_optional_alignment")]
+    #[schemars(with = "OptionalAlignment")]
+    pub(crate) alignment: Option<Alignment>,
+
+    /// The colors used in every other row, starting from the second one after the header.
+    ///
+    /// This makes it easier to follow rows in tables that have many of them.
+    #[serde(default)]
+    pub(crate) alternate_row_colors: Colors,
+}
+
 /// The style of a block quote.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct BlockQuoteStyle {
     /// The alignment.
     #[serde(flatten, default)]
+    #[schemars(with = "OptionalAlignment")]
     pub(crate) alignment: Option<Alignment>,
 
     /// The prefix to be added to this block quote.
@@ -242,7 +303,7 @@ pub(crate) struct BlockQuoteStyle {
 }
 
 /// The colors of a block quote.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct BlockQuoteColors {
     /// The foreground/background colors.
     #[serde(flatten)]
@@ -252,12 +313,125 @@ pub(crate) struct BlockQuoteColors {
     pub(crate) prefix: Option<Color>,
 }
 
+/// The style of a list.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ListStyle {
+    /// The markers to use for ordered list items, one for every nesting level.
+    ///
+    /// Levels nested deeper than the number of markers start over from the first one. When this
+    /// is empty, items use the marker they're written with.
+    #[serde(default)]
+    pub(crate) ordered_markers: Vec<OrderedListMarker>,
+}
+
+impl ListStyle {
+    /// Get the marker to use for ordered list items at the given depth, if one was configured.
+    pub(crate) fn ordered_marker(&self, depth: u8) -> Option<OrderedListMarker> {
+        if self.ordered_markers.is_empty() {
+            return None;
+        }
+        Some(self.ordered_markers[depth as usize % self.ordered_markers.len()])
+    }
+}
+
+/// The marker for an item in an ordered list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub(crate) enum OrderedListMarker {
+    /// A number followed by a period, like `1.`.
+    #[serde(rename = "1.")]
+    NumberPeriod,
+
+    /// A number followed by a parenthesis, like `1)`.
+    #[serde(rename = "1)")]
+    NumberParens,
+
+    /// A lowercase letter followed by a period, like `a.`.
+    #[serde(rename = "a.")]
+    LowerAlpha,
+
+    /// An uppercase letter followed by a period, like `A.`.
+    #[serde(rename = "A.")]
+    UpperAlpha,
+
+    /// A lowercase roman numeral followed by a period, like `i.`.
+    #[serde(rename = "i.")]
+    LowerRoman,
+
+    /// An uppercase roman numeral followed by a period, like `I.`.
+    #[serde(rename = "I.")]
+    UpperRoman,
+}
+
+impl OrderedListMarker {
+    /// Format the marker for the item with the given number.
+    ///
+    /// Numbers that can't be written using letters or roman numerals, like 0, are written as is.
+    pub(crate) fn format(&self, number: usize) -> String {
+        let (value, delimiter) = match self {
+            Self::NumberPeriod => (number.to_string(), '.'),
+            Self::NumberParens => (number.to_string(), ')'),
+            Self::LowerAlpha => (Self::alpha(number).unwrap_or_else(|| number.to_string()), '.'),
+            Self::UpperAlpha => (Self::alpha(number).unwrap_or_else(|| number.to_string()).to_uppercase(), '.'),
+            Self::LowerRoman => (Self::roman(number).unwrap_or_else(|| number.to_string()).to_lowercase(), '.'),
+            Self::UpperRoman => (Self::roman(number).unwrap_or_else(|| number.to_string()), '.'),
+        };
+        format!("{value}{delimiter}")
+    }
+
+    // Letters go from `a` to `z` and then continue with `aa`, `ab`, and so on.
+    fn alpha(number: usize) -> Option<String> {
+        if number == 0 {
+            return None;
+        }
+        let mut letters = Vec::new();
+        let mut remaining = number;
+        while remaining > 0 {
+            remaining -= 1;
+            letters.push(b'a' + (remaining % 26) as u8);
+            remaining /= 26;
+        }
+        letters.reverse();
+        String::from_utf8(letters).ok()
+    }
+
+    fn roman(number: usize) -> Option<String> {
+        const NUMERALS: &[(usize, &str)] = &[
+            (1000, "M"),
+            (900, "CM"),
+            (500, "D"),
+            (400, "CD"),
+            (100, "C"),
+            (90, "XC"),
+            (50, "L"),
+            (40, "XL"),
+            (10, "X"),
+            (9, "IX"),
+            (5, "V"),
+            (4, "IV"),
+            (1, "I"),
+        ];
+        if number == 0 || number >= 4000 {
+            return None;
+        }
+        let mut output = String::new();
+        let mut remaining = number;
+        for (value, numeral) in NUMERALS {
+            while remaining >= *value {
+                output.push_str(numeral);
+                remaining -= value;
+            }
+        }
+        Some(output)
+    }
+}
+
 /// The style for the presentation introduction slide.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct IntroSlideStyle {
     /// The style of the title line.
     #[serde(default)]
-    pub(crate) title: BasicStyle,
+    pub(crate) title: IntroSlideTitleStyle,
 
     /// The style of the subtitle line.
     #[serde(default)]
@@ -272,11 +446,29 @@ pub(crate) struct IntroSlideStyle {
     pub(crate) footer: Option<bool>,
 }
 
+/// The style of the intro slide's title.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub(crate) struct IntroSlideTitleStyle {
+    /// The alignment.
+    #[serde(flatten, default)]
+    #[schemars(with = "OptionalAlignment")]
+    pub(crate) alignment: Option<Alignment>,
+
+    /// The colors to be used.
+    #[serde(default)]
+    pub(crate) colors: Colors,
+
+    /// Whether to render the title using big text made out of block characters.
+    #[serde(default)]
+    pub(crate) big_text: bool,
+}
+
 /// A simple style.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct DefaultStyle {
     /// The margin on the left/right of the screen.
     #[serde(default, with = "serde_yaml::with::singleton_map")]
+    #[schemars(with = "Option<Margin>")]
     pub(crate) margin: Option<Margin>,
 
     /// The colors to be used.
@@ -285,10 +477,11 @@ pub(crate) struct DefaultStyle {
 }
 
 /// A simple style.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct BasicStyle {
     /// The alignment.
     #[serde(flatten, default)]
+    #[schemars(with = "OptionalAlignment")]
     pub(crate) alignment: Option<Alignment>,
 
     /// The colors to be used.
@@ -299,7 +492,7 @@ pub(crate) struct BasicStyle {
 /// Text alignment.
 ///
 /// This allows anchoring presentation elements to the left, center, or right of the screen.
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
 #[serde(tag = "alignment", rename_all = "snake_case")]
 pub(crate) enum Alignment {
     /// Left alignment.
@@ -334,11 +527,54 @@ impl Default for Alignment {
     }
 }
 
+/// The schema for an optional alignment that's flattened into a style.
+///
+/// schemars keeps the `alignment` tag required when flattening an `Option<Alignment>`, which would
+/// reject every style that doesn't set one, so this adds an alternative for when it's missing.
+struct OptionalAlignment;
+
+impl JsonSchema for OptionalAlignment {
+    fn schema_name() -> String {
+        "OptionalAlignment".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let mut schema = Alignment::json_schema(generator).into_object();
+        let missing = SchemaObject {
+            instance_type: Some(InstanceType::Object.into()),
+            object: Some(Box::new(ObjectValidation {
+                properties: [("alignment".into(), Schema::Bool(false))].into(),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        schema.subschemas().one_of.get_or_insert_with(Vec::new).push(missing.into());
+        schema.into()
+    }
+}
+
+/// Deserializes an optional alignment that's flattened into a style.
+///
+/// serde turns any error when deserializing a flattened `Option` into `None`, which would silently
+/// ignore an invalid alignment, so this only treats the alignment as missing if there's no
+/// `alignment` key at all.
+fn deserialize_optional_alignment<'de, D>(deserializer: D) -> Result<Option<Alignment>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let fields = serde_json::Map::deserialize(deserializer)?;
+    if !fields.contains_key("alignment") {
+        return Ok(None);
+    }
+    Alignment::deserialize(serde_json::Value::Object(fields)).map(Some).map_err(de::Error::custom)
+}
+
 /// The style for the author line in the presentation intro slide.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct AuthorStyle {
     /// The alignment.
     #[serde(flatten, default)]
+    #[schemars(with = "OptionalAlignment")]
     pub(crate) alignment: Option<Alignment>,
 
     /// The colors to be used.
@@ -351,19 +587,19 @@ pub(crate) struct AuthorStyle {
 }
 
 /// The style of the footer that's shown in every slide.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
 #[serde(tag = "style", rename_all = "snake_case")]
 pub(crate) enum FooterStyle {
     /// Use a template to generate the footer.
     Template {
         /// The template for the text to be put on the left.
-        left: Option<String>,
+        left: Option<FooterRegion>,
 
         /// The template for the text to be put on the center.
-        center: Option<String>,
+        center: Option<FooterRegion>,
 
         /// The template for the text to be put on the right.
-        right: Option<String>,
+        right: Option<FooterRegion>,
 
         /// The colors to be used.
         #[serde(default)]
@@ -387,7 +623,7 @@ pub(crate) enum FooterStyle {
 impl Default for FooterStyle {
     fn default() -> Self {
         Self::Template {
-            left: Some("{current_slide} / {total_slides}".to_string()),
+            left: Some(FooterRegion::Template("{current_slide} / {total_slides}".to_string())),
             center: None,
             right: None,
             colors: Colors::default(),
@@ -395,11 +631,47 @@ impl Default for FooterStyle {
     }
 }
 
+/// The contents of one of the regions in a template footer.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum FooterRegion {
+    /// A template that uses the footer's colors.
+    Template(String),
+
+    /// A template with its own colors.
+    Styled {
+        /// The template for the text.
+        template: String,
+
+        /// The colors to be used, falling back to the footer's colors.
+        #[serde(default)]
+        colors: Colors,
+    },
+}
+
+impl FooterRegion {
+    /// Get the template for this region.
+    pub(crate) fn template(&self) -> &str {
+        match self {
+            Self::Template(template) | Self::Styled { template, .. } => template,
+        }
+    }
+
+    /// Get the colors for this region, given the footer's colors.
+    pub(crate) fn colors(&self, footer_colors: &Colors) -> Colors {
+        match self {
+            Self::Template(_) => footer_colors.clone(),
+            Self::Styled { colors, .. } => colors.merge(footer_colors),
+        }
+    }
+}
+
 /// The style for a piece of code.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct CodeBlockStyle {
     /// The alignment.
     #[serde(flatten)]
+    #[schemars(with = "OptionalAlignment")]
     pub(crate) alignment: Option<Alignment>,
 
     /// The padding.
@@ -412,10 +684,28 @@ pub(crate) struct CodeBlockStyle {
 
     /// Whether to use the theme's background color.
     pub(crate) background: Option<bool>,
+
+    /// The border to draw around code blocks.
+    #[serde(default)]
+    pub(crate) border: Option<CornerStyle>,
+}
+
+/// The corners a border is drawn with.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CornerStyle {
+    /// Square corners, like `┌─┐`.
+    Sharp,
+
+    /// Rounded corners, like `╭─╮`.
+    Rounded,
+
+    /// Thick lines with square corners, like `┏━┓`.
+    Heavy,
 }
 
 /// The style for the output of a code execution block.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct ExecutionOutputBlockStyle {
     /// The colors to be used.
     #[serde(default)]
@@ -423,7 +713,7 @@ pub(crate) struct ExecutionOutputBlockStyle {
 }
 
 /// The style for inline code.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct InlineCodeStyle {
     /// The colors to be used.
     #[serde(default)]
@@ -431,7 +721,7 @@ pub(crate) struct InlineCodeStyle {
 }
 
 /// Vertical/horizontal padding.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct PaddingRect {
     /// The number of columns to use as horizontal padding.
     #[serde(default)]
@@ -443,7 +733,7 @@ pub(crate) struct PaddingRect {
 }
 
 /// A margin.
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum Margin {
     /// A fixed number of characters.
@@ -472,7 +762,7 @@ impl Default for Margin {
 }
 
 /// An element type.
-#[derive(Clone, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[derive(Clone, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum ElementType {
     SlideTitle,
@@ -490,10 +780,11 @@ pub(crate) enum ElementType {
     PresentationAuthor,
     Table,
     BlockQuote,
+    Countdown,
 }
 
 /// Where to position the author's name in the intro slide.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum AuthorPositioning {
     /// Right below the title.
@@ -505,7 +796,7 @@ pub(crate) enum AuthorPositioning {
 }
 
 /// Where to position the author's name in the intro slide.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct TypstStyle {
     /// The horizontal margin on the generated images.
     pub(crate) horizontal_margin: Option<u16>,
@@ -518,8 +809,35 @@ pub(crate) struct TypstStyle {
     pub(crate) colors: Colors,
 }
 
+/// The style for countdown timers.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub(crate) struct CountdownStyle {
+    /// The alignment.
+    #[serde(flatten, default)]
+    #[schemars(with = "OptionalAlignment")]
+    pub(crate) alignment: Option<Alignment>,
+
+    /// The colors to be used.
+    #[serde(default)]
+    pub(crate) colors: Colors,
+
+    /// The colors to be used during the last 30 seconds.
+    #[serde(default)]
+    pub(crate) final_colors: Option<Colors>,
+}
+
+impl CountdownStyle {
+    /// Get the colors to use during the last 30 seconds, which default to a red foreground.
+    pub(crate) fn final_colors(&self) -> Colors {
+        match &self.final_colors {
+            Some(colors) => colors.clone(),
+            None => Colors { foreground: "red".parse().ok(), background: self.colors.background },
+        }
+    }
+}
+
 /// Modals style.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct ModalStyle {
     /// The default colors to use for everything in the modal.
     #[serde(default)]
@@ -528,6 +846,56 @@ pub(crate) struct ModalStyle {
     /// The colors to use for selected lines.
     #[serde(default)]
     pub(crate) selection_colors: Colors,
+
+    /// The colors to use for the modal's borders, which default to the modal's colors.
+    #[serde(default)]
+    pub(crate) border_colors: Colors,
+
+    /// The colors to use for the command line prompt.
+    #[serde(default)]
+    pub(crate) prompt_colors: Colors,
+
+    /// The colors to use for errors displayed below the command line prompt.
+    #[serde(default)]
+    pub(crate) error_colors: Option<Colors>,
+}
+
+impl ModalStyle {
+    /// Get the colors to use for errors, which default to a red foreground.
+    pub(crate) fn error_colors(&self) -> Colors {
+        match &self.error_colors {
+            Some(colors) => colors.clone(),
+            None => Colors { foreground: "red".parse().ok(), background: None },
+        }
+    }
+}
+
+/// A named slide layout.
+///
+/// Layouts split the area below a slide's title into regions, each of which behaves like a column
+/// in a column layout.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub(crate) struct SlideLayout {
+    /// The regions in this layout, from left to right.
+    pub(crate) regions: Vec<LayoutRegion>,
+}
+
+/// A region within a slide layout.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub(crate) struct LayoutRegion {
+    /// The name of this region, used in `region` commands.
+    pub(crate) name: String,
+
+    /// The width of this region, relative to the others.
+    pub(crate) width: u8,
+
+    /// Whether this region must contain at least one element.
+    #[serde(default = "default_region_required")]
+    pub(crate) required: bool,
+}
+
+fn default_region_required() -> bool {
+    true
 }
 
 /// An error loading a presentation theme.
@@ -546,6 +914,7 @@ pub enum LoadThemeError {
 #[cfg(test)]
 mod test {
     use super::*;
+    use rstest::rstest;
     use tempfile::tempdir;
 
     #[test]
@@ -561,6 +930,15 @@ mod test {
         }
     }
 
+    #[test]
+    fn validate_builtin_layouts() {
+        let layouts = &*BUILTIN_LAYOUTS;
+        assert!(!layouts.is_empty());
+        for (name, layout) in layouts {
+            assert!(!layout.regions.is_empty(), "layout '{name}' has no regions");
+        }
+    }
+
     #[test]
     fn load_custom() {
         let directory = tempdir().expect("creating tempdir");
@@ -572,10 +950,74 @@ mod test {
         assert!(themes.load_by_name("potato").is_some());
     }
 
+    #[test]
+    fn theme_layout_overrides_builtin() {
+        let theme: PresentationTheme = serde_yaml::from_str(
+            "
+layouts:
+  image_right:
+    regions:
+      - name: text
+        width: 2
+      - name: image
+        width: 1
+        required: false
+",
+        )
+        .expect("invalid theme");
+        let layout = theme.layout("image_right").expect("no layout");
+        let regions: Vec<_> = layout.regions.iter().map(|r| (r.name.as_str(), r.width, r.required)).collect();
+        assert_eq!(regions, &[("text", 2, true), ("image", 1, false)]);
+        assert!(theme.layout("two_column_code").is_some());
+        assert!(theme.layout("potato").is_none());
+    }
+
     #[test]
     fn register_from_missing_directory() {
         let mut themes = PresentationThemeSet::default();
         let result = themes.register_from_directory("/tmp/presenterm/8ee2027983915ec78acc45027d874316");
         result.expect("loading failed");
     }
+
+    #[rstest]
+    #[case::number(OrderedListMarker::NumberPeriod, 12, "12.")]
+    #[case::parens(OrderedListMarker::NumberParens, 3, "3)")]
+    #[case::letter(OrderedListMarker::LowerAlpha, 1, "a.")]
+    #[case::last_letter(OrderedListMarker::LowerAlpha, 26, "z.")]
+    #[case::past_last_letter(OrderedListMarker::UpperAlpha, 27, "AA.")]
+    #[case::many_letters(OrderedListMarker::LowerAlpha, 703, "aaa.")]
+    #[case::zero_letter(OrderedListMarker::LowerAlpha, 0, "0.")]
+    #[case::roman(OrderedListMarker::LowerRoman, 4, "iv.")]
+    #[case::long_roman(OrderedListMarker::UpperRoman, 1994, "MCMXCIV.")]
+    #[case::zero_roman(OrderedListMarker::UpperRoman, 0, "0.")]
+    #[case::huge_roman(OrderedListMarker::UpperRoman, 4000, "4000.")]
+    fn ordered_list_markers(#[case] marker: OrderedListMarker, #[case] number: usize, #[case] expected: &str) {
+        assert_eq!(marker.format(number), expected);
+    }
+
+    #[rstest]
+    #[case::missing("alternate_row_colors:\n  background: ff0000", None)]
+    #[case::center("alignment: center", Some(Alignment::Center { minimum_margin: Margin::Fixed(0), minimum_size: 0 }))]
+    #[case::margin("alignment: right\nmargin:\n  fixed: 2", Some(Alignment::Right { margin: Margin::Fixed(2) }))]
+    fn table_alignment(#[case] input: &str, #[case] expected: Option<Alignment>) {
+        let style: TableStyle = serde_yaml::from_str(input).expect("invalid style");
+        assert_eq!(style.alignment, expected);
+    }
+
+    #[rstest]
+    #[case::unknown_alignment("alignment: middle")]
+    #[case::invalid_margin("alignment: left\nmargin: wide")]
+    fn invalid_table_alignment(#[case] input: &str) {
+        let result = serde_yaml::from_str::<TableStyle>(input);
+        assert!(result.is_err(), "parsed invalid style: {result:?}");
+    }
+
+    #[test]
+    fn ordered_marker_depths() {
+        let style: ListStyle = serde_yaml::from_str("ordered_markers: ['1.', 'a.']").expect("invalid style");
+        assert_eq!(style.ordered_marker(0), Some(OrderedListMarker::NumberPeriod));
+        assert_eq!(style.ordered_marker(1), Some(OrderedListMarker::LowerAlpha));
+        assert_eq!(style.ordered_marker(2), Some(OrderedListMarker::NumberPeriod));
+        assert_eq!(ListStyle::default().ordered_marker(0), None);
+    }
 }
@@ -1,7 +1,11 @@
 use itertools::Itertools;
+use once_cell::sync::Lazy;
 use std::{
+    collections::HashMap,
+    env,
     io::{self, Write},
     process::{Command, Output, Stdio},
+    sync::{Mutex, PoisonError},
 };
 
 const MAX_ERROR_LINES: usize = 10;
@@ -17,9 +21,39 @@ impl ThirdPartyTools {
         Tool::new("typst", args)
     }
 
+    pub(crate) fn mmdc(args: &[&str]) -> Tool {
+        Tool::new("mmdc", args)
+    }
+
+    pub(crate) fn graph_easy(args: &[&str]) -> Tool {
+        Tool::new("graph-easy", args)
+    }
+
     pub(crate) fn presenterm_export(args: &[&str]) -> Tool {
         Tool::new("presenterm-export", args).inherit_stdout()
     }
+
+    /// Get the version a tool reports, which is only looked up once per tool.
+    ///
+    /// This is empty if the version can't be found, e.g. because the tool isn't installed.
+    pub(crate) fn version(command_name: &'static str) -> String {
+        static VERSIONS: Lazy<Mutex<HashMap<&'static str, String>>> = Lazy::new(Default::default);
+        let mut versions = VERSIONS.lock().unwrap_or_else(PoisonError::into_inner);
+        let version = versions.entry(command_name).or_insert_with(|| {
+            let output = Tool::new(command_name, &["--version"]).run_and_capture_stdout().unwrap_or_default();
+            String::from_utf8_lossy(&output).trim().to_string()
+        });
+        version.clone()
+    }
+
+    /// Check whether a tool can be found in any of the directories in `$PATH`.
+    pub(crate) fn is_installed(command_name: &str) -> bool {
+        let Some(paths) = env::var_os("PATH") else {
+            return false;
+        };
+        let file_name = format!("{command_name}{}", env::consts::EXE_SUFFIX);
+        env::split_paths(&paths).any(|directory| directory.join(&file_name).is_file())
+    }
 }
 
 pub(crate) struct Tool {
@@ -1,5 +1,9 @@
 use crate::{
-    media::{image::Image, printer::RegisterImageError},
+    artifacts::{ArtifactKey, ArtifactStore},
+    media::{
+        image::{Image, ImageSource},
+        printer::{ImageRegistrar, RegisterImage, RegisterImageError},
+    },
     style::Color,
     theme::TypstStyle,
     tools::{ExecutionError, ThirdPartyTools},
@@ -12,10 +16,12 @@ const DEFAULT_PPI: u32 = 300;
 const DEFAULT_HORIZONTAL_MARGIN: u16 = 5;
 const DEFAULT_VERTICAL_MARGIN: u16 = 7;
 
+#[derive(Clone)]
 pub struct TypstRender {
     ppi: String,
-    image_registry: ImageRegistry,
+    registrar: ImageRegistrar,
     root_dir: String,
+    artifacts: Option<ArtifactStore>,
 }
 
 impl TypstRender {
@@ -25,29 +31,68 @@ impl TypstRender {
             path if path.is_empty() => ".".into(),
             path => path,
         };
-        Self { ppi: ppi.to_string(), image_registry, root_dir }
+        Self { ppi: ppi.to_string(), registrar: image_registry.registrar(), root_dir, artifacts: None }
+    }
+
+    /// Store the images generated in an artifact store so they're only generated once.
+    pub fn artifacts(mut self, artifacts: ArtifactStore) -> Self {
+        self.artifacts = Some(artifacts);
+        self
     }
 
     pub(crate) fn render_typst(&self, input: &str, style: &TypstStyle) -> Result<Image, TypstRenderError> {
-        let workdir = tempdir_in(&self.root_dir)?;
         let mut typst_input = Self::generate_page_header(style)?;
         typst_input.push_str(input);
 
-        let input_path = workdir.path().join("input.typst");
-        fs::write(&input_path, &typst_input)?;
-        self.render_to_image(workdir.path(), &input_path)
+        let png_contents = self.generate("typst", &["typst"], &typst_input, || self.compile(&typst_input))?;
+        self.register(&png_contents)
     }
 
     pub(crate) fn render_latex(&self, input: &str, style: &TypstStyle) -> Result<Image, TypstRenderError> {
-        let output = ThirdPartyTools::pandoc(&["--from", "latex", "--to", "typst"])
-            .stdin(input.as_bytes().into())
-            .run_and_capture_stdout()?;
+        let header = Self::generate_page_header(style)?;
+        let create = || {
+            let output = ThirdPartyTools::pandoc(&["--from", "latex", "--to", "typst"])
+                .stdin(input.as_bytes().into())
+                .run_and_capture_stdout()?;
+            let typst_input = format!("{header}{}", String::from_utf8_lossy(&output));
+            self.compile(&typst_input)
+        };
+        // Both tools take part in generating the image so a new version of either invalidates it.
+        let png_contents = self.generate("latex", &["pandoc", "typst"], &format!("{header}{input}"), create)?;
+        self.register(&png_contents)
+    }
 
-        let input = String::from_utf8_lossy(&output);
-        self.render_typst(&input, style)
+    // Files the input references aren't part of the key, so changes to them aren't picked up until
+    // the input itself changes. The versions of the tools used are only looked up when there's a
+    // store as that means running each of them.
+    fn generate<F>(
+        &self,
+        tool: &str,
+        tools_used: &[&'static str],
+        source: &str,
+        create: F,
+    ) -> Result<Vec<u8>, TypstRenderError>
+    where
+        F: FnOnce() -> Result<Vec<u8>, TypstRenderError>,
+    {
+        let Some(artifacts) = &self.artifacts else {
+            return create();
+        };
+        let versions: Vec<_> = tools_used.iter().map(|tool| ThirdPartyTools::version(tool)).collect();
+        let version = versions.join("\n");
+        let target = format!("{} {}", self.root_dir, self.ppi);
+        let key = ArtifactKey { tool, version: &version, source: source.as_bytes(), target: &target };
+        artifacts.get_or_create(&key, create)
     }
 
-    fn render_to_image(&self, base_path: &Path, path: &Path) -> Result<Image, TypstRenderError> {
+    fn compile(&self, input: &str) -> Result<Vec<u8>, TypstRenderError> {
+        let workdir = tempdir_in(&self.root_dir)?;
+        let input_path = workdir.path().join("input.typst");
+        fs::write(&input_path, input)?;
+        self.render_to_png(workdir.path(), &input_path)
+    }
+
+    fn render_to_png(&self, base_path: &Path, path: &Path) -> Result<Vec<u8>, TypstRenderError> {
         let output_path = base_path.join("output.png");
         ThirdPartyTools::typst(&[
             "compile",
@@ -62,21 +107,25 @@ impl TypstRender {
         ])
         .run()?;
 
-        let png_contents = fs::read(&output_path)?;
-        let image = image::load_from_memory(&png_contents)?;
-        let image = self.image_registry.register_image(image)?;
-        Ok(image)
+        Ok(fs::read(&output_path)?)
+    }
+
+    fn register(&self, png_contents: &[u8]) -> Result<Image, TypstRenderError> {
+        let image = image::load_from_memory(png_contents)?;
+        let resource = self.registrar.register_image(image)?;
+        Ok(Image::new(resource, ImageSource::Generated))
     }
 
     fn generate_page_header(style: &TypstStyle) -> Result<String, TypstRenderError> {
         let x_margin = style.horizontal_margin.unwrap_or(DEFAULT_HORIZONTAL_MARGIN);
         let y_margin = style.vertical_margin.unwrap_or(DEFAULT_VERTICAL_MARGIN);
-        let background =
-            style.colors.background.as_ref().map(Self::as_typst_color).unwrap_or_else(|| Ok(String::from("none")))?;
+        // There's no terminal to inherit colors from so those are treated as absent.
+        let background = style.colors.background.as_ref().filter(|color| !color.is_inherit());
+        let background = background.map(Self::as_typst_color).unwrap_or_else(|| Ok(String::from("none")))?;
         let mut header = format!(
             "#set page(width: auto, height: auto, margin: (x: {x_margin}pt, y: {y_margin}pt), fill: {background})\n"
         );
-        if let Some(color) = &style.colors.foreground {
+        if let Some(color) = style.colors.foreground.as_ref().filter(|color| !color.is_inherit()) {
             let color = Self::as_typst_color(color)?;
             header.push_str(&format!("#set text(fill: {color})\n"));
         }